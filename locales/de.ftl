@@ -0,0 +1,52 @@
+form-feedback-label = Ihr Feedback
+form-feedback-placeholder = Teilen Sie uns Ihre Gedanken mit...
+form-max-length = Bis zu { $max } Zeichen.
+form-optional = (optional)
+form-email-label = Ihre E-Mail-Adresse (optional)
+form-merge-note = Wenn Sie über diesen Link erneut absenden, ersetzt das Ihre vorherige Antwort.
+form-receipt-note = Wir senden Ihnen eine Kopie Ihrer Antwort mit einem Link zum Bearbeiten.
+form-honeypot-label = Dieses Feld leer lassen
+form-submit = Feedback absenden
+form-board-note = Das Feedback hier ist öffentlich.
+form-board-link = Sehen Sie, was andere geschrieben haben, und stimmen Sie ab
+form-report-link = Dieses Formular melden
+form-invalid-email = Ungültige E-Mail-Adresse
+form-feedback-required = Bitte schreiben Sie Ihr Feedback, bevor Sie absenden.
+form-feedback-too-short = Ihr Feedback muss mindestens { $min } Zeichen lang sein.
+form-feedback-too-long = Ihr Feedback darf höchstens { $max } Zeichen lang sein.
+
+success-title = Vielen Dank!
+success-message = Ihr Feedback wurde erfolgreich übermittelt.
+
+closed-message = Hier wird kein Feedback mehr angenommen. Danke für Ihr Interesse!
+full-message = Diese Umfrage ist voll: Sie hat alle benötigten Antworten. Danke für Ihr Interesse!
+closed-board-link = Sehen Sie, was andere geschrieben haben
+
+scheduled-message = Dieses Formular ist noch nicht geöffnet. Bitte schauen Sie wieder vorbei, sobald es offen ist.
+scheduled-opens = Öffnet:
+
+too-long-title = Nachricht zu lang
+too-long-message = Ihre Nachricht ist zu lang (maximal { $max } Zeichen). Bitte kürzen Sie sie und versuchen Sie es erneut.
+too-long-back = Zurück zum Formular
+nps-question = Wie wahrscheinlich ist es, dass Sie uns einem Freund oder Kollegen empfehlen?
+nps-low = 0 = überhaupt nicht wahrscheinlich
+nps-high = 10 = äußerst wahrscheinlich
+nps-comment-label = Was ist der wichtigste Grund für Ihre Bewertung?
+nps-score-required = Bitte wählen Sie einen Wert von 0 bis 10.
+reaction-question = Wie war Ihre Erfahrung?
+reaction-love = Begeistert
+reaction-happy = Gut
+reaction-neutral = Okay
+reaction-unhappy = Nicht gut
+reaction-comment-label = Möchten Sie noch etwas hinzufügen?
+reaction-required = Bitte tippen Sie auf eines der Gesichter.
+
+access-prompt = Dieses Formular steht nur eingeladenen Personen offen. Geben Sie den Zugangscode ein, den Sie erhalten haben.
+access-code-label = Zugangscode
+access-submit = Weiter
+access-wrong-code = Dieser Code ist nicht richtig. Bitte prüfen Sie ihn und versuchen Sie es erneut.
+access-locked = Zu viele falsche Codes. Versuchen Sie es in { $minutes } Minuten erneut.
+
+invite-required = Dieses Formular ist nur auf Einladung zugänglich. Öffnen Sie es über den persönlichen Link, den Sie erhalten haben.
+invite-used = Mit diesem Einladungslink wurde bereits geantwortet. Danke für Ihre Teilnahme!
+invite-unknown = Dieser Einladungslink ist ungültig. Prüfen Sie, ob Sie ihn vollständig kopiert haben, oder fragen Sie nach einem neuen.
@@ -0,0 +1,54 @@
+# Public feedback pages. Keep every message in all the other catalogs too.
+
+form-feedback-label = Your Feedback
+form-feedback-placeholder = Share your thoughts...
+form-max-length = Up to { $max } characters.
+form-optional = (optional)
+form-email-label = Your Email (optional)
+form-merge-note = Submitting again from this link replaces your previous response.
+form-receipt-note = We'll email you a copy of your answer with a link to edit it.
+form-honeypot-label = Leave this field empty
+form-submit = Submit Feedback
+form-board-note = Feedback here is public.
+form-board-link = See what others said and upvote it
+form-report-link = Report this form
+form-invalid-email = Invalid email address
+form-feedback-required = Please write your feedback before submitting.
+form-feedback-too-short = Your feedback must be at least { $min } characters.
+form-feedback-too-long = Your feedback must be at most { $max } characters.
+
+success-title = Thank you!
+success-message = Your feedback has been submitted successfully.
+
+closed-message = This prompt is no longer accepting feedback. Thanks for your interest!
+full-message = This survey is full: it has all the responses it needs. Thanks for your interest!
+closed-board-link = See what others said
+
+scheduled-message = This form isn't open yet. Please come back once it opens.
+scheduled-opens = Opens:
+
+too-long-title = Message too long
+too-long-message = Your message is too long (max { $max } characters). Please shorten it and try again.
+too-long-back = Back to the form
+nps-question = How likely are you to recommend us to a friend or colleague?
+nps-low = 0 = not at all likely
+nps-high = 10 = extremely likely
+nps-comment-label = What's the main reason for your score?
+nps-score-required = Please choose a score from 0 to 10.
+reaction-question = How was your experience?
+reaction-love = Loved it
+reaction-happy = Good
+reaction-neutral = Okay
+reaction-unhappy = Not good
+reaction-comment-label = Anything you'd like to add?
+reaction-required = Please tap one of the faces.
+
+access-prompt = This form is only open to invited respondents. Enter the access code you were given.
+access-code-label = Access code
+access-submit = Continue
+access-wrong-code = That code isn't right. Check it and try again.
+access-locked = Too many wrong codes. Try again in { $minutes } minutes.
+
+invite-required = This form is by invitation only. Open it from the personal link you were sent.
+invite-used = This invite link has already been used to respond. Thanks for taking part!
+invite-unknown = This invite link isn't valid. Check that you copied all of it, or ask for a new one.
@@ -0,0 +1,52 @@
+form-feedback-label = Tus comentarios
+form-feedback-placeholder = Cuéntanos qué piensas...
+form-max-length = Hasta { $max } caracteres.
+form-optional = (opcional)
+form-email-label = Tu correo electrónico (opcional)
+form-merge-note = Si vuelves a enviar desde este enlace, se reemplazará tu respuesta anterior.
+form-receipt-note = Te enviaremos por correo una copia de tu respuesta con un enlace para editarla.
+form-honeypot-label = Deja este campo vacío
+form-submit = Enviar comentarios
+form-board-note = Los comentarios aquí son públicos.
+form-board-link = Mira lo que opinan otros y vota
+form-report-link = Denunciar este formulario
+form-invalid-email = Dirección de correo no válida
+form-feedback-required = Escribe tus comentarios antes de enviarlos.
+form-feedback-too-short = Tus comentarios deben tener al menos { $min } caracteres.
+form-feedback-too-long = Tus comentarios deben tener como máximo { $max } caracteres.
+
+success-title = ¡Gracias!
+success-message = Tus comentarios se han enviado correctamente.
+
+closed-message = Ya no se aceptan comentarios aquí. ¡Gracias por tu interés!
+full-message = Esta encuesta está completa: ya tiene todas las respuestas que necesita. ¡Gracias por tu interés!
+closed-board-link = Mira lo que opinan otros
+
+scheduled-message = Este formulario aún no está abierto. Vuelve cuando se abra.
+scheduled-opens = Se abre:
+
+too-long-title = Mensaje demasiado largo
+too-long-message = Tu mensaje es demasiado largo (máximo { $max } caracteres). Acórtalo e inténtalo de nuevo.
+too-long-back = Volver al formulario
+nps-question = ¿Qué probabilidad hay de que nos recomiendes a un amigo o colega?
+nps-low = 0 = nada probable
+nps-high = 10 = muy probable
+nps-comment-label = ¿Cuál es el motivo principal de tu puntuación?
+nps-score-required = Elige una puntuación del 0 al 10.
+reaction-question = ¿Qué tal tu experiencia?
+reaction-love = Me encantó
+reaction-happy = Bien
+reaction-neutral = Regular
+reaction-unhappy = Mal
+reaction-comment-label = ¿Quieres añadir algo?
+reaction-required = Toca una de las caras.
+
+access-prompt = Este formulario solo está abierto a personas invitadas. Introduce el código de acceso que recibiste.
+access-code-label = Código de acceso
+access-submit = Continuar
+access-wrong-code = Ese código no es correcto. Revísalo e inténtalo de nuevo.
+access-locked = Demasiados códigos incorrectos. Inténtalo de nuevo dentro de { $minutes } minutos.
+
+invite-required = Este formulario es solo por invitación. Ábrelo desde el enlace personal que recibiste.
+invite-used = Este enlace de invitación ya se ha usado para responder. ¡Gracias por participar!
+invite-unknown = Este enlace de invitación no es válido. Comprueba que lo copiaste completo o pide uno nuevo.
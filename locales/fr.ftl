@@ -0,0 +1,52 @@
+form-feedback-label = Votre avis
+form-feedback-placeholder = Faites-nous part de vos idées...
+form-max-length = Jusqu’à { $max } caractères.
+form-optional = (facultatif)
+form-email-label = Votre adresse e-mail (facultatif)
+form-merge-note = Un nouvel envoi depuis ce lien remplace votre réponse précédente.
+form-receipt-note = Nous vous enverrons une copie de votre réponse avec un lien pour la modifier.
+form-honeypot-label = Laissez ce champ vide
+form-submit = Envoyer mon avis
+form-board-note = Les avis sont publics ici.
+form-board-link = Voir ce que les autres ont dit et voter
+form-report-link = Signaler ce formulaire
+form-invalid-email = Adresse e-mail invalide
+form-feedback-required = Veuillez écrire votre avis avant d'envoyer.
+form-feedback-too-short = Votre avis doit comporter au moins { $min } caractères.
+form-feedback-too-long = Votre avis doit comporter au plus { $max } caractères.
+
+success-title = Merci !
+success-message = Votre avis a bien été envoyé.
+
+closed-message = Ce formulaire n’accepte plus de réponses. Merci de votre intérêt !
+full-message = Ce sondage est complet : il a reçu toutes les réponses nécessaires. Merci de votre intérêt !
+closed-board-link = Voir ce que les autres ont dit
+
+scheduled-message = Ce formulaire n’est pas encore ouvert. Revenez dès son ouverture.
+scheduled-opens = Ouverture :
+
+too-long-title = Message trop long
+too-long-message = Votre message est trop long ({ $max } caractères maximum). Raccourcissez-le puis réessayez.
+too-long-back = Retour au formulaire
+nps-question = Quelle est la probabilité que vous nous recommandiez à un ami ou un collègue ?
+nps-low = 0 = pas du tout probable
+nps-high = 10 = très probable
+nps-comment-label = Quelle est la raison principale de votre note ?
+nps-score-required = Veuillez choisir une note de 0 à 10.
+reaction-question = Comment s'est passée votre expérience ?
+reaction-love = J'ai adoré
+reaction-happy = Bien
+reaction-neutral = Correct
+reaction-unhappy = Pas bien
+reaction-comment-label = Souhaitez-vous ajouter quelque chose ?
+reaction-required = Veuillez toucher l'un des visages.
+
+access-prompt = Ce formulaire est réservé aux personnes invitées. Saisissez le code d'accès qui vous a été communiqué.
+access-code-label = Code d'accès
+access-submit = Continuer
+access-wrong-code = Ce code n'est pas correct. Vérifiez-le et réessayez.
+access-locked = Trop de codes incorrects. Réessayez dans { $minutes } minutes.
+
+invite-required = Ce formulaire est accessible sur invitation uniquement. Ouvrez-le depuis le lien personnel que vous avez reçu.
+invite-used = Ce lien d'invitation a déjà servi à répondre. Merci de votre participation !
+invite-unknown = Ce lien d'invitation n'est pas valide. Vérifiez que vous l'avez copié en entier, ou demandez-en un nouveau.
@@ -0,0 +1,107 @@
+//! Access codes for prompts only invited people should answer. The form asks
+//! for the code (or takes it from `?code=` in the link), posts it back with
+//! the submission, and wrong guesses are throttled per prompt and client so
+//! a short code can't be found by trying them all.
+
+use moka::sync::Cache;
+use std::time::Duration;
+
+use crate::auth;
+
+pub const MIN_LEN: usize = 4;
+pub const MAX_LEN: usize = 64;
+/// Wrong codes a client may enter for one prompt before being locked out
+pub const MAX_FAILURES: u32 = 5;
+/// How long a lockout lasts, counted from the latest wrong code
+pub const LOCKOUT: Duration = Duration::from_secs(15 * 60);
+
+/// A code as an admin typed it, trimmed, if it's usable: 4 to 64 letters,
+/// digits, `-` or `_`
+pub fn normalize(code: &str) -> Option<String> {
+    let code = code.trim();
+    let valid = (MIN_LEN..=MAX_LEN).contains(&code.chars().count())
+        && code
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then(|| code.to_string())
+}
+
+/// Whether `given` opens a prompt whose code is `expected`. Case is ignored,
+/// since codes are often read out or copied from print.
+pub fn matches(expected: &str, given: &str) -> bool {
+    // Comparing digests keeps the time taken unrelated to how much matched
+    auth::token_digest(&expected.to_ascii_lowercase())
+        == auth::token_digest(&given.trim().to_ascii_lowercase())
+}
+
+/// Wrong-code counts by prompt and client, forgotten `LOCKOUT` after the
+/// latest failure
+pub struct Throttle {
+    failures: Cache<String, u32>,
+}
+
+impl Default for Throttle {
+    fn default() -> Self {
+        Throttle {
+            failures: Cache::builder()
+                .max_capacity(100_000)
+                .time_to_live(LOCKOUT)
+                .build(),
+        }
+    }
+}
+
+impl Throttle {
+    pub fn is_locked(&self, prompt_id: &str, client: &str) -> bool {
+        self.failures
+            .get(&key(prompt_id, client))
+            .is_some_and(|n| n >= MAX_FAILURES)
+    }
+
+    pub fn record_failure(&self, prompt_id: &str, client: &str) {
+        self.failures
+            .entry(key(prompt_id, client))
+            .and_upsert_with(|entry| entry.map_or(1, |e| e.into_value() + 1));
+    }
+
+    /// Forget a client's failures once it gets the code right
+    pub fn reset(&self, prompt_id: &str, client: &str) {
+        self.failures.invalidate(&key(prompt_id, client));
+    }
+}
+
+fn key(prompt_id: &str, client: &str) -> String {
+    format!("{}|{}", prompt_id, client)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_and_match() {
+        assert_eq!(normalize(" Spring-24 ").as_deref(), Some("Spring-24"));
+        assert_eq!(normalize("abc"), None);
+        assert_eq!(normalize("two words"), None);
+        assert!(matches("Spring-24", " spring-24"));
+        assert!(!matches("Spring-24", "Spring-25"));
+        assert!(!matches("Spring-24", ""));
+    }
+
+    #[test]
+    fn test_throttle_locks_after_repeated_failures() {
+        let throttle = Throttle::default();
+        for _ in 0..MAX_FAILURES - 1 {
+            throttle.record_failure("p1", "10.0.0.1");
+        }
+        assert!(!throttle.is_locked("p1", "10.0.0.1"));
+        throttle.record_failure("p1", "10.0.0.1");
+        assert!(throttle.is_locked("p1", "10.0.0.1"));
+        // Other clients and other prompts are unaffected
+        assert!(!throttle.is_locked("p1", "10.0.0.2"));
+        assert!(!throttle.is_locked("p2", "10.0.0.1"));
+
+        throttle.reset("p1", "10.0.0.1");
+        assert!(!throttle.is_locked("p1", "10.0.0.1"));
+    }
+}
@@ -0,0 +1,846 @@
+//! JSON archives of every prompt and response, for moving data between instances,
+//! and a flattened JSON Lines rendering of the same data for ML tooling.
+
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::{SqliteConnection, SqlitePool};
+use std::collections::{HashMap, HashSet};
+
+use crate::db::{self, Answer, Cycle, Feedback, Field, Prompt, PromptField};
+
+/// Bumped whenever the archive layout changes incompatibly
+pub const ARCHIVE_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Archive {
+    pub version: u32,
+    /// Taken before reading any rows; pass it as the next export's `since`
+    pub exported_at: String,
+    /// Set on incremental archives, which hold only feedback created or edited
+    /// after this point plus the prompts and cycles it needs. Deletions and tag
+    /// changes aren't captured.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub since: Option<String>,
+    pub prompts: Vec<ArchivedPrompt>,
+    #[serde(default)]
+    pub cycles: Vec<Cycle>,
+    /// The whole question bank, even in incremental archives
+    #[serde(default)]
+    pub fields: Vec<Field>,
+    #[serde(default)]
+    pub prompt_fields: Vec<PromptField>,
+    /// Each entry carries its tag names and answers; tags are matched by name
+    /// on import
+    pub feedback: Vec<Feedback>,
+}
+
+/// A prompt with the secrets `Prompt` leaves out when serialized, so an
+/// imported prompt keeps its webhook signing key and access code
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArchivedPrompt {
+    #[serde(flatten)]
+    pub prompt: Prompt,
+    #[serde(default)]
+    pub webhook_secret: Option<String>,
+    #[serde(default)]
+    pub access_code: Option<String>,
+}
+
+impl From<Prompt> for ArchivedPrompt {
+    fn from(prompt: Prompt) -> Self {
+        ArchivedPrompt {
+            webhook_secret: prompt.webhook_secret.clone(),
+            access_code: prompt.access_code.clone(),
+            prompt,
+        }
+    }
+}
+
+/// What to do when an archived row's ID already exists in this database
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ConflictMode {
+    /// Refuse the whole import
+    #[default]
+    Abort,
+    /// Keep the existing row and import everything else
+    Skip,
+    /// Replace the existing row with the archived one
+    Overwrite,
+}
+
+impl ConflictMode {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "abort" => Some(ConflictMode::Abort),
+            "skip" => Some(ConflictMode::Skip),
+            "overwrite" => Some(ConflictMode::Overwrite),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct ImportSummary {
+    pub prompts: usize,
+    pub cycles: usize,
+    pub fields: usize,
+    pub feedback: usize,
+    pub skipped: usize,
+}
+
+#[derive(Debug)]
+pub enum ImportError {
+    UnsupportedVersion(u32),
+    /// IDs that already exist, reported when importing in abort mode
+    Conflicts(Vec<String>),
+    /// IDs that belong to another workspace, which no mode may touch
+    ForeignIds(Vec<String>),
+    /// The archive references a prompt it doesn't contain
+    UnknownPrompt(String),
+    /// The archive references a question bank field it doesn't contain
+    UnknownField(String),
+    Database(sqlx::Error),
+}
+
+impl From<sqlx::Error> for ImportError {
+    fn from(e: sqlx::Error) -> Self {
+        ImportError::Database(e)
+    }
+}
+
+/// Archive every prompt in a workspace along with its cycles and feedback.
+/// With `since` (normalized by `db::parse_since`), only feedback created or
+/// edited after it is included, with the prompts and cycles it belongs to and
+/// any prompts created after it.
+pub async fn export(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    since: Option<&str>,
+) -> Result<Archive, sqlx::Error> {
+    let exported_at = chrono::Utc::now().to_rfc3339();
+
+    let mut prompts = db::get_all_prompts(pool, workspace_id).await?;
+    prompts.reverse();
+
+    let filter = db::FeedbackFilter {
+        since: since.map(str::to_string),
+        ..Default::default()
+    };
+    let mut feedback = Vec::new();
+    for prompt in &prompts {
+        let mut entries = db::list_feedback(pool, &prompt.id, &filter).await?;
+        entries.reverse();
+        feedback.extend(entries);
+    }
+
+    if let Some(since) = since {
+        let changed: HashSet<&str> = feedback.iter().map(|f| f.prompt_id.as_str()).collect();
+        prompts.retain(|p| changed.contains(p.id.as_str()) || p.created_at.as_str() > since);
+    }
+    let included: HashSet<&str> = prompts.iter().map(|p| p.id.as_str()).collect();
+
+    let cycles: Vec<Cycle> = sqlx::query_as(
+        r#"
+        SELECT c.id, c.prompt_id, c.number, c.starts_at, c.ends_at
+        FROM cycles c
+        JOIN prompts p ON p.id = c.prompt_id
+        WHERE p.workspace_id = ?
+        ORDER BY c.prompt_id, c.number
+        "#,
+    )
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await?;
+    let cycles = cycles
+        .into_iter()
+        .filter(|c| included.contains(c.prompt_id.as_str()))
+        .collect();
+
+    let fields = sqlx::query_as(
+        "SELECT id, workspace_id, label, help_text, kind, required, min_length, max_length, pattern, created_at, updated_at FROM fields WHERE workspace_id = ? ORDER BY created_at",
+    )
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await?;
+    let prompt_fields: Vec<PromptField> = sqlx::query_as(
+        r#"
+        SELECT pf.prompt_id, pf.field_id, pf.position
+        FROM prompt_fields pf
+        JOIN prompts p ON p.id = pf.prompt_id
+        WHERE p.workspace_id = ?
+        ORDER BY pf.prompt_id, pf.position
+        "#,
+    )
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await?;
+    let prompt_fields = prompt_fields
+        .into_iter()
+        .filter(|pf| included.contains(pf.prompt_id.as_str()))
+        .collect();
+
+    Ok(Archive {
+        version: ARCHIVE_VERSION,
+        exported_at,
+        since: since.map(str::to_string),
+        prompts: prompts.into_iter().map(ArchivedPrompt::from).collect(),
+        cycles,
+        fields,
+        prompt_fields,
+        feedback,
+    })
+}
+
+/// One feedback entry with the context a labeling or ML pipeline needs, so
+/// each line of a JSONL export stands alone
+#[derive(Debug, Serialize)]
+pub struct JsonlRecord<'a> {
+    pub id: &'a str,
+    pub workspace: &'a str,
+    pub prompt_id: &'a str,
+    pub prompt_title: &'a str,
+    pub prompt_description: &'a str,
+    /// Set for recurring prompts
+    pub cycle_number: Option<i64>,
+    pub content: &'a str,
+    pub respondent_email: Option<&'a str>,
+    pub created_at: &'a str,
+    pub updated_at: Option<&'a str>,
+    pub sentiment_score: Option<f64>,
+    pub sentiment_label: Option<&'a str>,
+    pub tags: &'a [String],
+    pub answers: &'a [Answer],
+}
+
+impl Archive {
+    /// Render the archive's feedback as JSON Lines, one record per line in
+    /// the archive's order (prompts oldest first, then entries oldest first)
+    pub fn to_jsonl(&self, workspace: &str) -> String {
+        let prompts: HashMap<&str, &Prompt> = self
+            .prompts
+            .iter()
+            .map(|p| (p.prompt.id.as_str(), &p.prompt))
+            .collect();
+        let cycles: HashMap<&str, i64> = self
+            .cycles
+            .iter()
+            .map(|c| (c.id.as_str(), c.number))
+            .collect();
+
+        let mut out = String::new();
+        for fb in &self.feedback {
+            let Some(prompt) = prompts.get(fb.prompt_id.as_str()) else {
+                continue;
+            };
+            let record = JsonlRecord {
+                id: &fb.id,
+                workspace,
+                prompt_id: &prompt.id,
+                prompt_title: &prompt.title,
+                prompt_description: &prompt.description,
+                cycle_number: fb
+                    .cycle_id
+                    .as_deref()
+                    .and_then(|id| cycles.get(id).copied()),
+                content: &fb.content,
+                respondent_email: fb.respondent_email.as_deref(),
+                created_at: &fb.created_at,
+                updated_at: fb.updated_at.as_deref(),
+                sentiment_score: fb.sentiment_score,
+                sentiment_label: fb.sentiment_label.as_deref(),
+                tags: &fb.tags,
+                answers: &fb.answers,
+            };
+            // Serializing these plain fields can't fail, and JSON escapes newlines
+            out.push_str(&serde_json::to_string(&record).expect("record serializes"));
+            out.push('\n');
+        }
+        out
+    }
+}
+
+/// Existing rows among `ids`, paired with the workspace that owns them.
+/// `query` must select `(id, workspace_id)` filtered by the JSON array bound to it.
+async fn existing_ids(
+    conn: &mut SqliteConnection,
+    query: &str,
+    ids: Vec<&str>,
+) -> Result<Vec<(String, String)>, sqlx::Error> {
+    sqlx::query_as(query)
+        .bind(serde_json::to_string(&ids).unwrap_or_default())
+        .fetch_all(conn)
+        .await
+}
+
+const PROMPT_UPDATES: &[&str] = &[
+    "title",
+    "description",
+    "created_at",
+    "webhook_url",
+    "webhook_secret",
+    "merge_responses",
+    "recurrence",
+    "content_min_length",
+    "content_max_length",
+    "content_pattern",
+    "closed_at",
+    "archived_at",
+    "public_board",
+    "send_receipts",
+    "language",
+    "slug",
+    "thank_you_message",
+    "redirect_url",
+    "access_code",
+    "publish_at",
+    "kind",
+    "retention_days",
+    "max_responses",
+];
+
+const FIELD_UPDATES: &[&str] = &[
+    "label",
+    "help_text",
+    "kind",
+    "required",
+    "min_length",
+    "max_length",
+    "pattern",
+    "created_at",
+    "updated_at",
+];
+
+const FEEDBACK_UPDATES: &[&str] = &[
+    "prompt_id",
+    "content",
+    "content_hash",
+    "created_at",
+    "respondent_email",
+    "updated_at",
+    "cycle_id",
+    "sentiment_score",
+    "sentiment_label",
+    "status",
+    "nps_score",
+    "reaction",
+];
+
+/// Upsert clause for an import; updating in place rather than `INSERT OR
+/// REPLACE` keeps rows that reference the existing one valid
+fn on_conflict(overwrite: bool, columns: &[&str]) -> String {
+    if !overwrite {
+        return "ON CONFLICT (id) DO NOTHING".to_string();
+    }
+    let sets: Vec<String> = columns
+        .iter()
+        .map(|c| format!("{c} = excluded.{c}"))
+        .collect();
+    format!("ON CONFLICT (id) DO UPDATE SET {}", sets.join(", "))
+}
+
+/// Restore an archive into a workspace in a single transaction. Prompts are
+/// imported into `workspace_id` whatever workspace they were exported from.
+pub async fn import(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    archive: &Archive,
+    mode: ConflictMode,
+) -> Result<ImportSummary, ImportError> {
+    if archive.version != ARCHIVE_VERSION {
+        return Err(ImportError::UnsupportedVersion(archive.version));
+    }
+
+    let mut tx = pool.begin().await?;
+
+    let mut existing = existing_ids(
+        &mut tx,
+        "SELECT id, workspace_id FROM prompts WHERE id IN (SELECT value FROM json_each(?))",
+        archive
+            .prompts
+            .iter()
+            .map(|p| p.prompt.id.as_str())
+            .collect(),
+    )
+    .await?;
+    existing.extend(
+        existing_ids(
+            &mut tx,
+            r#"
+            SELECT f.id, p.workspace_id FROM feedback f JOIN prompts p ON p.id = f.prompt_id
+            WHERE f.id IN (SELECT value FROM json_each(?))
+            "#,
+            archive.feedback.iter().map(|f| f.id.as_str()).collect(),
+        )
+        .await?,
+    );
+    existing.extend(
+        existing_ids(
+            &mut tx,
+            "SELECT id, workspace_id FROM fields WHERE id IN (SELECT value FROM json_each(?))",
+            archive.fields.iter().map(|f| f.id.as_str()).collect(),
+        )
+        .await?,
+    );
+    let (conflicts, foreign): (Vec<_>, Vec<_>) = existing
+        .into_iter()
+        .partition(|(_, owner)| owner == workspace_id);
+    if !foreign.is_empty() {
+        return Err(ImportError::ForeignIds(
+            foreign.into_iter().map(|(id, _)| id).collect(),
+        ));
+    }
+    if mode == ConflictMode::Abort && !conflicts.is_empty() {
+        return Err(ImportError::Conflicts(
+            conflicts.into_iter().map(|(id, _)| id).collect(),
+        ));
+    }
+
+    // Cycles and feedback may only hang off prompts in this workspace
+    let archived: HashSet<&str> = archive
+        .prompts
+        .iter()
+        .map(|p| p.prompt.id.as_str())
+        .collect();
+    let referenced: HashSet<&str> = archive
+        .cycles
+        .iter()
+        .map(|c| c.prompt_id.as_str())
+        .chain(archive.feedback.iter().map(|f| f.prompt_id.as_str()))
+        .chain(archive.prompt_fields.iter().map(|pf| pf.prompt_id.as_str()))
+        .filter(|id| !archived.contains(id))
+        .collect();
+    for prompt_id in referenced {
+        let owned: Option<(String,)> =
+            sqlx::query_as("SELECT id FROM prompts WHERE id = ? AND workspace_id = ?")
+                .bind(prompt_id)
+                .bind(workspace_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if owned.is_none() {
+            return Err(ImportError::UnknownPrompt(prompt_id.to_string()));
+        }
+    }
+
+    // Likewise answers and form layouts may only use this workspace's fields
+    let archived: HashSet<&str> = archive.fields.iter().map(|f| f.id.as_str()).collect();
+    let referenced: HashSet<&str> = archive
+        .prompt_fields
+        .iter()
+        .map(|pf| pf.field_id.as_str())
+        .chain(
+            archive
+                .feedback
+                .iter()
+                .flat_map(|f| f.answers.iter().map(|a| a.field_id.as_str())),
+        )
+        .filter(|id| !archived.contains(id))
+        .collect();
+    for field_id in referenced {
+        let owned: Option<(String,)> =
+            sqlx::query_as("SELECT id FROM fields WHERE id = ? AND workspace_id = ?")
+                .bind(field_id)
+                .bind(workspace_id)
+                .fetch_optional(&mut *tx)
+                .await?;
+        if owned.is_none() {
+            return Err(ImportError::UnknownField(field_id.to_string()));
+        }
+    }
+
+    let overwrite = mode == ConflictMode::Overwrite;
+    let mut summary = ImportSummary::default();
+
+    for archived in &archive.prompts {
+        let prompt = &archived.prompt;
+        let result = sqlx::query(&format!(
+            r#"
+            INSERT INTO prompts (id, workspace_id, title, description, created_at, webhook_url, webhook_secret, merge_responses, recurrence, content_min_length, content_max_length, content_pattern, closed_at, archived_at, public_board, send_receipts, language, slug, thank_you_message, redirect_url, access_code, publish_at, kind, retention_days, max_responses)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            {}
+            "#,
+            on_conflict(overwrite, PROMPT_UPDATES)
+        ))
+        .bind(&prompt.id)
+        .bind(workspace_id)
+        .bind(&prompt.title)
+        .bind(&prompt.description)
+        .bind(&prompt.created_at)
+        .bind(&prompt.webhook_url)
+        .bind(&archived.webhook_secret)
+        .bind(prompt.merge_responses)
+        .bind(&prompt.recurrence)
+        .bind(prompt.content_min_length)
+        .bind(prompt.content_max_length)
+        .bind(&prompt.content_pattern)
+        .bind(&prompt.closed_at)
+        .bind(&prompt.archived_at)
+        .bind(prompt.public_board)
+        .bind(prompt.send_receipts)
+        .bind(&prompt.language)
+        .bind(&prompt.slug)
+        .bind(&prompt.thank_you_message)
+        .bind(&prompt.redirect_url)
+        .bind(&archived.access_code)
+        .bind(&prompt.publish_at)
+        .bind(prompt.kind().as_str())
+        .bind(
+            prompt
+                .retention_days
+                .filter(|d| crate::retention::is_valid_days(*d)),
+        )
+        .bind(prompt.max_responses.filter(|max| *max >= 1))
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() > 0 {
+            summary.prompts += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+
+    for field in &archive.fields {
+        let result = sqlx::query(&format!(
+            r#"
+            INSERT INTO fields (id, workspace_id, label, help_text, kind, required, min_length, max_length, pattern, created_at, updated_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            {}
+            "#,
+            on_conflict(overwrite, FIELD_UPDATES)
+        ))
+        .bind(&field.id)
+        .bind(workspace_id)
+        .bind(&field.label)
+        .bind(&field.help_text)
+        .bind(&field.kind)
+        .bind(field.required)
+        .bind(field.min_length)
+        .bind(field.max_length)
+        .bind(&field.pattern)
+        .bind(&field.created_at)
+        .bind(&field.updated_at)
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() > 0 {
+            summary.fields += 1;
+        } else {
+            summary.skipped += 1;
+        }
+    }
+
+    for pf in &archive.prompt_fields {
+        sqlx::query(
+            "INSERT OR IGNORE INTO prompt_fields (prompt_id, field_id, position) VALUES (?, ?, ?)",
+        )
+        .bind(&pf.prompt_id)
+        .bind(&pf.field_id)
+        .bind(pf.position)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    // Cycles are unique per (prompt, number), so an existing cycle may have a
+    // different ID; remap feedback onto whichever row ends up in the table
+    let mut cycle_ids = HashMap::new();
+    for cycle in &archive.cycles {
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO cycles (id, prompt_id, number, starts_at, ends_at) VALUES (?, ?, ?, ?, ?)",
+        )
+        .bind(&cycle.id)
+        .bind(&cycle.prompt_id)
+        .bind(cycle.number)
+        .bind(&cycle.starts_at)
+        .bind(&cycle.ends_at)
+        .execute(&mut *tx)
+        .await?;
+        summary.cycles += result.rows_affected() as usize;
+
+        let (id,): (String,) =
+            sqlx::query_as("SELECT id FROM cycles WHERE prompt_id = ? AND number = ?")
+                .bind(&cycle.prompt_id)
+                .bind(cycle.number)
+                .fetch_one(&mut *tx)
+                .await?;
+        cycle_ids.insert(cycle.id.clone(), id);
+    }
+
+    for fb in &archive.feedback {
+        let cycle_id = fb
+            .cycle_id
+            .as_ref()
+            .map(|id| cycle_ids.get(id).unwrap_or(id).clone());
+
+        if overwrite {
+            // The archived tags and answers replace whatever the existing row had
+            sqlx::query("DELETE FROM feedback_tags WHERE feedback_id = ?")
+                .bind(&fb.id)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query("DELETE FROM answers WHERE feedback_id = ?")
+                .bind(&fb.id)
+                .execute(&mut *tx)
+                .await?;
+        }
+
+        let result = sqlx::query(&format!(
+            r#"
+            INSERT INTO feedback (id, prompt_id, content, content_hash, created_at, respondent_email, updated_at, cycle_id, sentiment_score, sentiment_label, status, nps_score, reaction)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            {}
+            "#,
+            on_conflict(overwrite, FEEDBACK_UPDATES)
+        ))
+        .bind(&fb.id)
+        .bind(&fb.prompt_id)
+        .bind(&fb.content)
+        .bind(db::content_hash(&fb.content))
+        .bind(&fb.created_at)
+        .bind(&fb.respondent_email)
+        .bind(&fb.updated_at)
+        .bind(&cycle_id)
+        .bind(fb.sentiment_score)
+        .bind(&fb.sentiment_label)
+        .bind(db::FeedbackStatus::parse(&fb.status).unwrap_or_default().as_str())
+        .bind(fb.nps_score.filter(|s| crate::nps::is_valid(*s)))
+        .bind(
+            fb.reaction
+                .as_deref()
+                .and_then(crate::reactions::Reaction::parse)
+                .map(|r| r.key()),
+        )
+        .execute(&mut *tx)
+        .await?;
+        if result.rows_affected() == 0 {
+            summary.skipped += 1;
+            continue;
+        }
+        summary.feedback += 1;
+
+        for name in fb.tags.iter().filter_map(|t| db::normalize_tag(t)) {
+            sqlx::query("INSERT OR IGNORE INTO tags (id, name) VALUES (?, ?)")
+                .bind(uuid::Uuid::new_v4().to_string())
+                .bind(&name)
+                .execute(&mut *tx)
+                .await?;
+            sqlx::query(
+                "INSERT OR IGNORE INTO feedback_tags (feedback_id, tag_id) SELECT ?, id FROM tags WHERE name = ?",
+            )
+            .bind(&fb.id)
+            .bind(&name)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for answer in &fb.answers {
+            sqlx::query(
+                "INSERT OR IGNORE INTO answers (feedback_id, field_id, value, verified) VALUES (?, ?, ?, ?)",
+            )
+            .bind(&fb.id)
+            .bind(&answer.field_id)
+            .bind(&answer.value)
+            .bind(answer.verified)
+            .execute(&mut *tx)
+            .await?;
+        }
+    }
+
+    // Imported entries count against the response cap too
+    sqlx::query(
+        "UPDATE prompts SET accepted_responses = MAX(accepted_responses, (SELECT COUNT(*) FROM feedback WHERE feedback.prompt_id = prompts.id AND spam_reason IS NULL))",
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_test_db() -> SqlitePool {
+        db::init_db("sqlite::memory:").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_round_trip_into_empty_database() {
+        let source = setup_test_db().await;
+        let prompt_options = db::PromptOptions {
+            webhook_url: Some("https://hooks.example.com/feedback".to_string()),
+            access_code: Some("spring-24".to_string()),
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &source,
+            db::DEFAULT_WORKSPACE_ID,
+            "Title",
+            "Desc",
+            &prompt_options,
+        )
+        .await
+        .unwrap();
+        assert!(prompt.webhook_secret.is_some());
+        let spec = db::FieldSpec {
+            label: "Team".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: false,
+            rules: Default::default(),
+        };
+        let field = db::create_field(&source, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&source, &prompt.id, &field.id)
+            .await
+            .unwrap();
+        let options = db::FeedbackOptions {
+            answers: vec![(field.id.clone(), "Platform".to_string())],
+            ..Default::default()
+        };
+        let feedback =
+            db::create_feedback_with_options(&source, &prompt.id, "Great work", &options)
+                .await
+                .unwrap();
+        db::add_tag(&source, &feedback.id, "praise").await.unwrap();
+
+        let archive = export(&source, db::DEFAULT_WORKSPACE_ID, None)
+            .await
+            .unwrap();
+        let json = serde_json::to_string(&archive).unwrap();
+        let archive: Archive = serde_json::from_str(&json).unwrap();
+
+        // Land in a differently named workspace on the target instance
+        let target = setup_test_db().await;
+        let workspace = db::create_workspace(&target, "imported", "Imported")
+            .await
+            .unwrap()
+            .unwrap();
+        let summary = import(&target, &workspace.id, &archive, ConflictMode::Abort)
+            .await
+            .unwrap();
+        assert_eq!(summary.prompts, 1);
+        assert_eq!(summary.fields, 1);
+        assert_eq!(summary.feedback, 1);
+        // The secrets the API never shows still travel in the archive
+        let imported = db::get_workspace_prompt(&target, &workspace.id, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(imported.webhook_secret, prompt.webhook_secret);
+        assert_eq!(imported.access_code.as_deref(), Some("spring-24"));
+        assert_eq!(
+            db::get_prompt_fields(&target, &prompt.id).await.unwrap()[0].id,
+            field.id
+        );
+
+        let restored = db::get_feedback_for_prompt(&target, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].id, feedback.id);
+        assert_eq!(restored[0].content, "Great work");
+        assert_eq!(restored[0].tags, vec!["praise".to_string()]);
+        assert_eq!(restored[0].answers[0].value, "Platform");
+
+        // Imported content is searchable through the FTS triggers
+        let hits = db::search_feedback(&target, &workspace.id, "great")
+            .await
+            .unwrap();
+        assert_eq!(hits.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_jsonl_has_one_self_contained_record_per_entry() {
+        let pool = setup_test_db().await;
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+        let first = db::create_feedback(&pool, &prompt.id, "Line one\nline two")
+            .await
+            .unwrap();
+        db::add_tag(&pool, &first.id, "praise").await.unwrap();
+        db::create_feedback(&pool, &prompt.id, "Second")
+            .await
+            .unwrap();
+
+        let archive = export(&pool, db::DEFAULT_WORKSPACE_ID, None).await.unwrap();
+        let jsonl = archive.to_jsonl("default");
+        let lines: Vec<serde_json::Value> = jsonl
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["content"], "Line one\nline two");
+        assert_eq!(lines[0]["prompt_title"], "Title");
+        assert_eq!(lines[0]["workspace"], "default");
+        assert_eq!(lines[0]["tags"], serde_json::json!(["praise"]));
+        assert_eq!(lines[1]["cycle_number"], serde_json::Value::Null);
+    }
+
+    #[tokio::test]
+    async fn test_conflicting_ids() {
+        let pool = setup_test_db().await;
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+        db::create_feedback(&pool, &prompt.id, "Original")
+            .await
+            .unwrap();
+
+        let ws = db::DEFAULT_WORKSPACE_ID;
+        let mut archive = export(&pool, ws, None).await.unwrap();
+        archive.prompts[0].prompt.title = "Renamed".to_string();
+
+        match import(&pool, ws, &archive, ConflictMode::Abort).await {
+            Err(ImportError::Conflicts(ids)) => assert_eq!(ids.len(), 2),
+            other => panic!("expected conflicts, got {:?}", other),
+        }
+
+        let summary = import(&pool, ws, &archive, ConflictMode::Skip)
+            .await
+            .unwrap();
+        assert_eq!(summary.skipped, 2);
+        let kept = db::get_prompt_by_id(&pool, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(kept.title, "Title");
+
+        import(&pool, ws, &archive, ConflictMode::Overwrite)
+            .await
+            .unwrap();
+        let replaced = db::get_prompt_by_id(&pool, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(replaced.title, "Renamed");
+    }
+
+    #[tokio::test]
+    async fn test_cannot_touch_another_workspace() {
+        let pool = setup_test_db().await;
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+        let archive = export(&pool, db::DEFAULT_WORKSPACE_ID, None).await.unwrap();
+        let other = db::create_workspace(&pool, "other", "Other")
+            .await
+            .unwrap()
+            .unwrap();
+
+        match import(&pool, &other.id, &archive, ConflictMode::Overwrite).await {
+            Err(ImportError::ForeignIds(ids)) => assert_eq!(ids, vec![prompt.id.clone()]),
+            other => panic!("expected foreign IDs, got {:?}", other),
+        }
+
+        // Feedback can't be attached to a prompt outside the archive and workspace
+        let smuggled = Archive {
+            prompts: Vec::new(),
+            cycles: Vec::new(),
+            feedback: vec![Feedback {
+                id: "smuggled".to_string(),
+                prompt_id: prompt.id.clone(),
+                content: "x".to_string(),
+                created_at: chrono::Utc::now().to_rfc3339(),
+                ..Default::default()
+            }],
+            ..archive
+        };
+        match import(&pool, &other.id, &smuggled, ConflictMode::Abort).await {
+            Err(ImportError::UnknownPrompt(id)) => assert_eq!(id, prompt.id),
+            other => panic!("expected unknown prompt, got {:?}", other),
+        }
+    }
+}
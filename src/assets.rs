@@ -0,0 +1,112 @@
+//! Stylesheets and scripts under `static/`, compiled into the binary and
+//! served from `/static/`. Templates link to `url(name)`, which puts a hash
+//! of the contents into the file name (`app.1a2b3c4d.css`), so browsers can
+//! cache a response forever and a changed file gets a new URL. The plain
+//! name is served as well, but has to be revalidated.
+
+use sha2::{Digest, Sha256};
+use std::sync::OnceLock;
+
+/// How long fingerprinted responses may be cached
+pub const IMMUTABLE_CACHE_CONTROL: &str = "public, max-age=31536000, immutable";
+/// For the plain names, whose contents change between releases
+pub const REVALIDATE_CACHE_CONTROL: &str = "public, no-cache";
+
+const SOURCES: &[(&str, &[u8])] = &[
+    ("app.css", include_bytes!("../static/app.css")),
+    ("embed.css", include_bytes!("../static/embed.css")),
+];
+
+pub struct Asset {
+    pub name: &'static str,
+    /// `name` with the content hash before the extension
+    pub fingerprinted: String,
+    pub content_type: &'static str,
+    /// Quoted, for `ETag`
+    pub etag: String,
+    pub body: &'static [u8],
+}
+
+impl Asset {
+    fn load(name: &'static str, body: &'static [u8]) -> Self {
+        let hash = hex::encode(&Sha256::digest(body)[..4]);
+        let fingerprinted = match name.rsplit_once('.') {
+            Some((stem, extension)) => format!("{}.{}.{}", stem, hash, extension),
+            None => format!("{}.{}", name, hash),
+        };
+        Asset {
+            name,
+            fingerprinted,
+            content_type: content_type(name),
+            etag: format!("\"{}\"", hash),
+            body,
+        }
+    }
+}
+
+fn assets() -> &'static [Asset] {
+    static ASSETS: OnceLock<Vec<Asset>> = OnceLock::new();
+    ASSETS.get_or_init(|| {
+        SOURCES
+            .iter()
+            .map(|(name, body)| Asset::load(name, body))
+            .collect()
+    })
+}
+
+/// The cache-busting URL for an asset, e.g. `/static/app.1a2b3c4d.css`
+pub fn url(name: &str) -> String {
+    match assets().iter().find(|asset| asset.name == name) {
+        Some(asset) => format!("/static/{}", asset.fingerprinted),
+        None => panic!("static/{} is not a compiled-in asset", name),
+    }
+}
+
+/// Find a requested file; the flag says whether it was asked for by its
+/// fingerprinted name
+pub fn lookup(file: &str) -> Option<(&'static Asset, bool)> {
+    assets().iter().find_map(|asset| {
+        if asset.fingerprinted == file {
+            Some((asset, true))
+        } else if asset.name == file {
+            Some((asset, false))
+        } else {
+            None
+        }
+    })
+}
+
+fn content_type(name: &str) -> &'static str {
+    match name.rsplit_once('.').map(|(_, extension)| extension) {
+        Some("css") => "text/css; charset=utf-8",
+        Some("js") => "text/javascript; charset=utf-8",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        Some("ico") => "image/x-icon",
+        Some("woff2") => "font/woff2",
+        _ => "application/octet-stream",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fingerprinted_urls() {
+        let url = url("app.css");
+        let file = url.strip_prefix("/static/").unwrap();
+        assert!(file.starts_with("app.") && file.ends_with(".css"));
+        assert_eq!(file.len(), "app.".len() + 8 + ".css".len());
+
+        let (asset, fingerprinted) = lookup(file).unwrap();
+        assert!(fingerprinted);
+        assert_eq!(asset.content_type, "text/css; charset=utf-8");
+        let (plain, fingerprinted) = lookup("app.css").unwrap();
+        assert!(!fingerprinted);
+        assert_eq!(plain.etag, asset.etag);
+
+        assert!(lookup("app.00000000.css").is_none());
+        assert!(lookup("../Cargo.toml").is_none());
+    }
+}
@@ -0,0 +1,356 @@
+//! Admin accounts: password hashing, session cookies, and the role extractors
+//! that guard admin handlers.
+//!
+//! Until the first account exists every admin page sends the browser to the
+//! setup wizard, which creates it. Installs with `open_admin` set instead stay
+//! open, with every request acting as an owner.
+
+use argon2::{
+    password_hash::{
+        rand_core::{OsRng, RngCore},
+        PasswordHash, PasswordHasher, PasswordVerifier, SaltString,
+    },
+    Argon2,
+};
+use axum::{
+    async_trait,
+    extract::FromRequestParts,
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{Html, IntoResponse, Redirect, Response},
+};
+use chrono::{Duration, Utc};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+
+use crate::db::{self, Role};
+use crate::error::ApiError;
+use crate::AppState;
+
+pub const SESSION_COOKIE: &str = "feedback_session";
+const SESSION_TTL_DAYS: i64 = 30;
+/// Identifies a browser on public boards so it can upvote each entry once
+pub const VOTER_COOKIE: &str = "feedback_voter";
+const VOTER_TTL_DAYS: i64 = 365;
+pub const MIN_PASSWORD_LEN: usize = 8;
+/// The first-run wizard, where admin pages lead before any account exists
+pub const SETUP_PATH: &str = "/setup";
+
+pub fn hash_password(password: &str) -> String {
+    let salt = SaltString::generate(&mut OsRng);
+    Argon2::default()
+        .hash_password(password.as_bytes(), &salt)
+        .expect("argon2 accepts any password with a generated salt")
+        .to_string()
+}
+
+pub fn verify_password(password: &str, hash: &str) -> bool {
+    PasswordHash::new(hash)
+        .map(|parsed| {
+            Argon2::default()
+                .verify_password(password.as_bytes(), &parsed)
+                .is_ok()
+        })
+        .unwrap_or(false)
+}
+
+/// Only this digest is stored, so a leaked database can't be used to sign in
+pub fn token_digest(token: &str) -> String {
+    hex::encode(Sha256::digest(token.as_bytes()))
+}
+
+/// 256 random bits, hex encoded
+pub fn random_token() -> String {
+    let mut bytes = [0u8; 32];
+    OsRng.fill_bytes(&mut bytes);
+    hex::encode(bytes)
+}
+
+/// Start a session for `user`, returning the `Set-Cookie` value
+pub async fn start_session(
+    pool: &sqlx::SqlitePool,
+    user: &db::User,
+) -> Result<String, sqlx::Error> {
+    let token = random_token();
+
+    let ttl = Duration::days(SESSION_TTL_DAYS);
+    db::create_session(pool, &token_digest(&token), &user.id, Utc::now() + ttl).await?;
+    Ok(format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+        SESSION_COOKIE,
+        token,
+        ttl.num_seconds()
+    ))
+}
+
+pub fn clear_session_cookie() -> String {
+    format!(
+        "{}=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0",
+        SESSION_COOKIE
+    )
+}
+
+/// The browser's voter token, and the `Set-Cookie` value to send when it
+/// didn't have one yet
+pub fn voter_token(headers: &HeaderMap) -> (String, Option<String>) {
+    if let Some(token) = cookie(headers, VOTER_COOKIE).filter(|t| !t.is_empty()) {
+        return (token.to_string(), None);
+    }
+    let token = random_token();
+    let set_cookie = format!(
+        "{}={}; Path=/feedback; HttpOnly; SameSite=Lax; Max-Age={}",
+        VOTER_COOKIE,
+        token,
+        Duration::days(VOTER_TTL_DAYS).num_seconds()
+    );
+    (token, Some(set_cookie))
+}
+
+pub fn session_token(headers: &HeaderMap) -> Option<&str> {
+    cookie(headers, SESSION_COOKIE)
+}
+
+pub fn cookie<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    headers
+        .get_all(header::COOKIE)
+        .iter()
+        .filter_map(|v| v.to_str().ok())
+        .flat_map(|v| v.split(';'))
+        .filter_map(|pair| pair.trim().split_once('='))
+        .find(|(key, _)| *key == name)
+        .map(|(_, value)| value)
+}
+
+/// Who is making an admin request
+#[derive(Debug, Clone)]
+pub struct Admin {
+    /// None for requests made with an API token, and while an open admin has
+    /// no accounts yet
+    pub user: Option<db::User>,
+    /// The user's or API token's role; an open admin can do anything
+    pub role: Role,
+}
+
+/// Prefix on minted API tokens so they're recognisable in config and logs
+const API_TOKEN_PREFIX: &str = "fbk_";
+
+pub fn new_api_token() -> String {
+    format!("{}{}", API_TOKEN_PREFIX, random_token())
+}
+
+fn bearer_token(headers: &HeaderMap) -> Option<Option<&str>> {
+    let value = headers.get(header::AUTHORIZATION)?;
+    Some(
+        value
+            .to_str()
+            .ok()
+            .and_then(|v| v.strip_prefix("Bearer "))
+            .map(str::trim),
+    )
+}
+
+/// The workspace slug an `/api` route acts on, or None for instance-wide routes
+fn api_workspace_slug(path: &str) -> Option<&str> {
+    let mut segments = path.trim_start_matches('/').split('/');
+    match (segments.next(), segments.next()) {
+        (Some("api"), Some("v1")) => match segments.next() {
+            Some("workspaces") => segments.next(),
+            _ => None,
+        },
+        (Some("api"), slug) => slug,
+        _ => None,
+    }
+}
+
+async fn authorize(parts: &Parts, state: &AppState, required: Role) -> Result<Admin, Response> {
+    let is_api = parts.uri.path().starts_with("/api/");
+    let forbidden = || {
+        if is_api {
+            ApiError::forbidden().into_response()
+        } else {
+            (
+                StatusCode::FORBIDDEN,
+                Html("Your role does not allow this action".to_string()),
+            )
+                .into_response()
+        }
+    };
+
+    // API tokens only open the JSON API, and a bad one is refused even while
+    // the admin is open so misconfigured clients find out
+    if is_api {
+        if let Some(token) = bearer_token(&parts.headers) {
+            let api_token = match token {
+                Some(token) => db::use_api_token(&state.pool, &token_digest(token), Utc::now())
+                    .await
+                    .map_err(internal)?,
+                None => None,
+            };
+            let Some(api_token) = api_token else {
+                return Err(ApiError::unauthorized().into_response());
+            };
+            if api_token.role() < required {
+                return Err(forbidden());
+            }
+            if let Some(scope) = &api_token.workspace_id {
+                let workspace = match api_workspace_slug(parts.uri.path()) {
+                    Some(slug) => db::get_workspace_by_slug(&state.pool, slug)
+                        .await
+                        .map_err(internal)?,
+                    None => None,
+                };
+                if workspace.map(|w| w.id).as_ref() != Some(scope) {
+                    return Err(forbidden());
+                }
+            }
+            return Ok(Admin {
+                user: None,
+                role: api_token.role(),
+            });
+        }
+    }
+
+    if db::count_users(&state.pool).await.map_err(internal)? == 0 {
+        if state.open_admin {
+            return Ok(Admin {
+                user: None,
+                role: Role::Owner,
+            });
+        }
+        return Err(if is_api {
+            ApiError::unauthorized().into_response()
+        } else {
+            Redirect::to(SETUP_PATH).into_response()
+        });
+    }
+
+    let user = match session_token(&parts.headers) {
+        Some(token) => db::get_session_user(&state.pool, &token_digest(token), Utc::now())
+            .await
+            .map_err(internal)?,
+        None => None,
+    };
+    let Some(user) = user else {
+        return Err(if is_api {
+            ApiError::unauthorized().into_response()
+        } else {
+            let next = parts
+                .uri
+                .path_and_query()
+                .map(|pq| pq.as_str())
+                .unwrap_or("/admin");
+            Redirect::to(&format!("/login?next={}", encode_component(next))).into_response()
+        });
+    };
+
+    let role = user.role();
+    if role < required {
+        return Err(forbidden());
+    }
+    Ok(Admin {
+        user: Some(user),
+        role,
+    })
+}
+
+fn internal(e: sqlx::Error) -> Response {
+    ApiError::from(e).into_response()
+}
+
+fn encode_component(value: &str) -> String {
+    value
+        .bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+/// Only follow same-site paths after signing in
+pub fn safe_next(next: &str) -> &str {
+    if next.starts_with('/') && !next.starts_with("//") && !next.starts_with("/\\") {
+        next
+    } else {
+        "/admin"
+    }
+}
+
+macro_rules! role_extractor {
+    ($(#[$doc:meta])* $name:ident, $role:expr) => {
+        $(#[$doc])*
+        // Not every handler needs to know who is asking
+        pub struct $name(#[allow(dead_code)] pub Admin);
+
+        #[async_trait]
+        impl FromRequestParts<Arc<AppState>> for $name {
+            type Rejection = Response;
+
+            async fn from_request_parts(
+                parts: &mut Parts,
+                state: &Arc<AppState>,
+            ) -> Result<Self, Self::Rejection> {
+                authorize(parts, state, $role).await.map($name)
+            }
+        }
+    };
+}
+
+role_extractor!(
+    /// Any signed-in admin
+    RequireViewer,
+    Role::Viewer
+);
+role_extractor!(
+    /// Editors and owners
+    RequireEditor,
+    Role::Editor
+);
+role_extractor!(
+    /// Owners only
+    RequireOwner,
+    Role::Owner
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_api_workspace_slug() {
+        assert_eq!(
+            api_workspace_slug("/api/v1/workspaces/team/prompts/p1/close"),
+            Some("team")
+        );
+        assert_eq!(api_workspace_slug("/api/team/feedback/p1"), Some("team"));
+        assert_eq!(api_workspace_slug("/api/v1/prompts/p1/feedback"), None);
+        assert_eq!(api_workspace_slug("/admin/team"), None);
+    }
+
+    #[test]
+    fn test_password_round_trip() {
+        let hash = hash_password("correct horse");
+        assert!(verify_password("correct horse", &hash));
+        assert!(!verify_password("wrong horse", &hash));
+        assert!(!verify_password("correct horse", "not a hash"));
+    }
+
+    #[test]
+    fn test_session_token_from_cookie_header() {
+        let mut headers = HeaderMap::new();
+        headers.insert(
+            header::COOKIE,
+            "theme=dark; feedback_session=abc123".parse().unwrap(),
+        );
+        assert_eq!(session_token(&headers), Some("abc123"));
+        assert_eq!(session_token(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_safe_next() {
+        assert_eq!(safe_next("/admin/default"), "/admin/default");
+        assert_eq!(safe_next("//evil.example"), "/admin");
+        assert_eq!(safe_next("https://evil.example"), "/admin");
+    }
+}
@@ -0,0 +1,119 @@
+//! Consistent copies of the live database, taken with `VACUUM INTO` so
+//! writers are never blocked for longer than the copy itself.
+
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use std::path::{Path, PathBuf};
+
+/// Snapshot files are named `feedback-<timestamp>.db` so they sort by age
+const SNAPSHOT_PREFIX: &str = "feedback-";
+const SNAPSHOT_SUFFIX: &str = ".db";
+
+/// Write a consistent copy of the database to `path`, which must not exist
+pub async fn snapshot_to(pool: &SqlitePool, path: &Path) -> Result<(), sqlx::Error> {
+    sqlx::query("VACUUM INTO ?")
+        .bind(path.to_string_lossy().as_ref())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Write a timestamped snapshot into `dir`, then delete all but the newest `keep`
+pub async fn write_snapshot(
+    pool: &SqlitePool,
+    dir: &Path,
+    keep: usize,
+) -> Result<PathBuf, Box<dyn std::error::Error + Send + Sync>> {
+    tokio::fs::create_dir_all(dir).await?;
+
+    let path = dir.join(format!(
+        "{}{}{}",
+        SNAPSHOT_PREFIX,
+        Utc::now().format("%Y%m%dT%H%M%SZ"),
+        SNAPSHOT_SUFFIX
+    ));
+    snapshot_to(pool, &path).await?;
+
+    prune_snapshots(dir, keep).await?;
+    Ok(path)
+}
+
+async fn prune_snapshots(dir: &Path, keep: usize) -> std::io::Result<()> {
+    let mut snapshots = Vec::new();
+    let mut entries = tokio::fs::read_dir(dir).await?;
+    while let Some(entry) = entries.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(SNAPSHOT_PREFIX) && name.ends_with(SNAPSHOT_SUFFIX) {
+            snapshots.push(entry.path());
+        }
+    }
+
+    snapshots.sort();
+    let excess = snapshots.len().saturating_sub(keep);
+    for path in &snapshots[..excess] {
+        tokio::fs::remove_file(path).await?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[tokio::test]
+    async fn test_snapshot_is_a_readable_copy() {
+        // VACUUM INTO can't copy out of an in-memory database
+        let dir = std::env::temp_dir().join(format!("feedback-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let pool = db::init_db(&format!(
+            "sqlite:{}?mode=rwc",
+            dir.join("live.db").display()
+        ))
+        .await
+        .unwrap();
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+
+        let path = write_snapshot(&pool, &dir.join("snapshots"), 7)
+            .await
+            .unwrap();
+
+        let copy = SqlitePool::connect(&format!("sqlite:{}", path.display()))
+            .await
+            .unwrap();
+        let restored = db::get_prompt_by_id(&copy, &prompt.id).await.unwrap();
+        assert!(restored.is_some());
+
+        copy.close().await;
+        pool.close().await;
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_prune_keeps_newest() {
+        let dir = std::env::temp_dir().join(format!("feedback-backup-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for stamp in ["20240101", "20240102", "20240103"] {
+            std::fs::write(dir.join(format!("feedback-{}.db", stamp)), b"").unwrap();
+        }
+        std::fs::write(dir.join("unrelated.txt"), b"").unwrap();
+
+        prune_snapshots(&dir, 2).await.unwrap();
+
+        let mut names: Vec<String> = std::fs::read_dir(&dir)
+            .unwrap()
+            .map(|e| e.unwrap().file_name().to_string_lossy().to_string())
+            .collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "feedback-20240102.db",
+                "feedback-20240103.db",
+                "unrelated.txt"
+            ]
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,481 @@
+//! Declarative setup applied at startup, so a fresh container comes up with
+//! its accounts, workspaces, prompts and API tokens in place.
+//!
+//! The file is read from `BOOTSTRAP_FILE`, or `bootstrap.yaml` in the working
+//! directory if present. Applying it is idempotent: anything that already
+//! exists (users by email, workspaces by slug, prompts by title within their
+//! workspace, tokens by secret) is left as it is, except that user roles are
+//! brought in line with the file. Secrets can be given inline or, better,
+//! named by environment variable:
+//!
+//! ```yaml
+//! users:
+//!   - email: admin@example.com
+//!     password_env: ADMIN_PASSWORD
+//!     role: owner
+//! workspaces:
+//!   - slug: support
+//!     name: Support
+//! prompts:
+//!   - workspace: support
+//!     title: How did we do?
+//!     description: Tell us about your last support conversation.
+//!     recurrence: monthly
+//! tokens:
+//!   - name: CI export job
+//!     role: viewer
+//!     token_env: CI_API_TOKEN
+//!   - name: Release pipeline
+//!     role: editor
+//!     workspace: support
+//!     token_env: RELEASE_API_TOKEN
+//! ```
+
+use serde::Deserialize;
+use sqlx::sqlite::SqlitePool;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::{auth, db, webhook};
+
+const DEFAULT_PATH: &str = "bootstrap.yaml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Bootstrap {
+    #[serde(default)]
+    pub users: Vec<UserSpec>,
+    #[serde(default)]
+    pub workspaces: Vec<WorkspaceSpec>,
+    #[serde(default)]
+    pub prompts: Vec<PromptSpec>,
+    #[serde(default)]
+    pub tokens: Vec<TokenSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct UserSpec {
+    pub email: String,
+    pub password: Option<String>,
+    /// Environment variable holding the password
+    pub password_env: Option<String>,
+    #[serde(default = "default_role")]
+    pub role: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspaceSpec {
+    pub slug: String,
+    pub name: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PromptSpec {
+    /// Workspace slug
+    #[serde(default = "default_workspace")]
+    pub workspace: String,
+    pub title: String,
+    #[serde(default)]
+    pub description: String,
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub merge_responses: bool,
+    pub recurrence: Option<String>,
+    #[serde(default)]
+    pub public_board: bool,
+    #[serde(default)]
+    pub send_receipts: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TokenSpec {
+    pub name: String,
+    #[serde(default = "default_role")]
+    pub role: String,
+    /// The full secret, e.g. `fbk_...`
+    pub token: Option<String>,
+    /// Environment variable holding the secret
+    pub token_env: Option<String>,
+    /// Slug of the only workspace the token may act on
+    pub workspace: Option<String>,
+}
+
+fn default_role() -> String {
+    "viewer".to_string()
+}
+
+fn default_workspace() -> String {
+    "default".to_string()
+}
+
+/// What applying the file changed
+#[derive(Debug, Default, PartialEq)]
+pub struct Summary {
+    pub users: usize,
+    pub roles_updated: usize,
+    pub workspaces: usize,
+    pub prompts: usize,
+    pub tokens: usize,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Io(PathBuf, std::io::Error),
+    Parse(serde_yaml::Error),
+    /// A setting the file can't be applied with, e.g. a missing password
+    Invalid(String),
+    Database(sqlx::Error),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(path, e) => write!(f, "could not read {}: {}", path.display(), e),
+            Error::Parse(e) => write!(f, "invalid bootstrap file: {}", e),
+            Error::Invalid(message) => write!(f, "{}", message),
+            Error::Database(e) => write!(f, "database error: {}", e),
+        }
+    }
+}
+
+impl From<sqlx::Error> for Error {
+    fn from(e: sqlx::Error) -> Self {
+        Error::Database(e)
+    }
+}
+
+/// The file to apply: `BOOTSTRAP_FILE` if set, which must then exist, else
+/// `bootstrap.yaml` if there is one
+pub fn configured_path() -> Option<PathBuf> {
+    match std::env::var("BOOTSTRAP_FILE") {
+        Ok(path) if !path.is_empty() => Some(path.into()),
+        _ => Some(PathBuf::from(DEFAULT_PATH)).filter(|p| p.exists()),
+    }
+}
+
+pub fn load(path: &Path) -> Result<Bootstrap, Error> {
+    let text = std::fs::read_to_string(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+    parse(&text)
+}
+
+pub fn parse(text: &str) -> Result<Bootstrap, Error> {
+    // An empty file is a valid, empty bootstrap
+    if text.trim().is_empty() {
+        return Ok(Bootstrap::default());
+    }
+    serde_yaml::from_str(text).map_err(Error::Parse)
+}
+
+/// An inline secret, or the value of the named environment variable
+fn secret(
+    inline: &Option<String>,
+    env: &Option<String>,
+    what: &str,
+) -> Result<Option<String>, Error> {
+    if let Some(value) = inline {
+        return Ok(Some(value.clone()));
+    }
+    match env {
+        Some(name) => std::env::var(name).map(Some).map_err(|_| {
+            Error::Invalid(format!(
+                "{}: environment variable {} is not set",
+                what, name
+            ))
+        }),
+        None => Ok(None),
+    }
+}
+
+fn role(value: &str, what: &str) -> Result<db::Role, Error> {
+    db::Role::parse(value).ok_or_else(|| {
+        Error::Invalid(format!(
+            "{}: role must be owner, editor or viewer, not {:?}",
+            what, value
+        ))
+    })
+}
+
+/// Apply the file. Everything is checked before anything is written, so a
+/// mistake in the file leaves the database untouched.
+pub async fn apply(pool: &SqlitePool, bootstrap: &Bootstrap) -> Result<Summary, Error> {
+    struct User {
+        email: String,
+        password: Option<String>,
+        role: db::Role,
+    }
+    let mut users = Vec::new();
+    for spec in &bootstrap.users {
+        let what = format!("user {}", spec.email);
+        let email = spec.email.trim().to_lowercase();
+        if !email.contains('@') {
+            return Err(Error::Invalid(format!("{}: not an email address", what)));
+        }
+        let password = secret(&spec.password, &spec.password_env, &what)?;
+        if password
+            .as_ref()
+            .is_some_and(|p| p.len() < auth::MIN_PASSWORD_LEN)
+        {
+            return Err(Error::Invalid(format!(
+                "{}: password must be at least {} characters",
+                what,
+                auth::MIN_PASSWORD_LEN
+            )));
+        }
+        if password.is_none() && db::get_user_by_email(pool, &email).await?.is_none() {
+            return Err(Error::Invalid(format!(
+                "{}: set password or password_env to create the account",
+                what
+            )));
+        }
+        users.push(User {
+            email,
+            password,
+            role: role(&spec.role, &what)?,
+        });
+    }
+
+    for spec in &bootstrap.workspaces {
+        if db::normalize_workspace_slug(&spec.slug).as_deref() != Some(spec.slug.as_str()) {
+            return Err(Error::Invalid(format!(
+                "workspace {}: not a valid slug (lowercase words joined by hyphens)",
+                spec.slug
+            )));
+        }
+    }
+
+    for spec in &bootstrap.prompts {
+        let what = format!("prompt {:?}", spec.title);
+        if spec.title.trim().is_empty() {
+            return Err(Error::Invalid(
+                "prompt: title must not be empty".to_string(),
+            ));
+        }
+        if spec
+            .webhook_url
+            .as_deref()
+            .is_some_and(|u| !webhook::is_valid_url(u))
+        {
+            return Err(Error::Invalid(format!(
+                "{}: webhook_url must be a public http or https URL",
+                what
+            )));
+        }
+        if spec
+            .recurrence
+            .as_deref()
+            .is_some_and(|r| db::Recurrence::parse(r).is_none())
+        {
+            return Err(Error::Invalid(format!(
+                "{}: recurrence must be weekly or monthly",
+                what
+            )));
+        }
+        let declared = bootstrap
+            .workspaces
+            .iter()
+            .any(|w| w.slug == spec.workspace);
+        if !declared
+            && db::get_workspace_by_slug(pool, &spec.workspace)
+                .await?
+                .is_none()
+        {
+            return Err(Error::Invalid(format!(
+                "{}: unknown workspace {}",
+                what, spec.workspace
+            )));
+        }
+    }
+
+    let mut tokens = Vec::new();
+    for spec in &bootstrap.tokens {
+        let what = format!("token {:?}", spec.name);
+        let Some(token) = secret(&spec.token, &spec.token_env, &what)? else {
+            return Err(Error::Invalid(format!("{}: set token or token_env", what)));
+        };
+        if token.len() < 32 {
+            return Err(Error::Invalid(format!(
+                "{}: token must be at least 32 characters",
+                what
+            )));
+        }
+        if let Some(slug) = &spec.workspace {
+            let declared = bootstrap.workspaces.iter().any(|w| &w.slug == slug);
+            if !declared && db::get_workspace_by_slug(pool, slug).await?.is_none() {
+                return Err(Error::Invalid(format!(
+                    "{}: unknown workspace {}",
+                    what, slug
+                )));
+            }
+        }
+        tokens.push((spec, token, role(&spec.role, &what)?));
+    }
+
+    let mut summary = Summary::default();
+
+    for user in users {
+        match db::get_user_by_email(pool, &user.email).await? {
+            Some(existing) => {
+                if existing.role() != user.role {
+                    db::set_user_role(pool, &existing.id, user.role).await?;
+                    summary.roles_updated += 1;
+                }
+            }
+            None => {
+                let Some(password) = &user.password else {
+                    continue;
+                };
+                let hash = auth::hash_password(password);
+                if db::create_user(pool, &user.email, &hash, user.role)
+                    .await?
+                    .is_some()
+                {
+                    summary.users += 1;
+                }
+            }
+        }
+    }
+
+    for spec in &bootstrap.workspaces {
+        if db::create_workspace(pool, &spec.slug, &spec.name)
+            .await?
+            .is_some()
+        {
+            summary.workspaces += 1;
+        }
+    }
+
+    for spec in &bootstrap.prompts {
+        let Some(workspace) = db::get_workspace_by_slug(pool, &spec.workspace).await? else {
+            continue;
+        };
+        let title = spec.title.trim();
+        let existing = db::get_all_prompts(pool, &workspace.id).await?;
+        if existing.iter().any(|p| p.title == title) {
+            continue;
+        }
+        let options = db::PromptOptions {
+            webhook_url: spec.webhook_url.clone(),
+            merge_responses: spec.merge_responses,
+            public_board: spec.public_board,
+            send_receipts: spec.send_receipts,
+            recurrence: spec.recurrence.as_deref().and_then(db::Recurrence::parse),
+            ..Default::default()
+        };
+        db::create_prompt_with_options(pool, &workspace.id, title, &spec.description, &options)
+            .await?;
+        summary.prompts += 1;
+    }
+
+    for (spec, token, role) in tokens {
+        let digest = auth::token_digest(&token);
+        if !db::api_token_exists(pool, &digest).await? {
+            let workspace = match &spec.workspace {
+                Some(slug) => db::get_workspace_by_slug(pool, slug).await?,
+                None => None,
+            };
+            let workspace_id = workspace.as_ref().map(|w| w.id.as_str());
+            db::create_api_token(pool, &spec.name, &digest, role, workspace_id).await?;
+            summary.tokens += 1;
+        }
+    }
+
+    Ok(summary)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_apply_is_idempotent() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let bootstrap = parse(
+            r#"
+users:
+  - email: Admin@Example.com
+    password: correct horse battery
+    role: owner
+workspaces:
+  - slug: support
+    name: Support
+prompts:
+  - workspace: support
+    title: How did we do?
+    recurrence: monthly
+  - title: Roadmap ideas
+tokens:
+  - name: CI
+    role: editor
+    token: fbk_0123456789abcdef0123456789abcdef
+"#,
+        )
+        .unwrap();
+
+        let summary = apply(&pool, &bootstrap).await.unwrap();
+        assert_eq!(
+            summary,
+            Summary {
+                users: 1,
+                roles_updated: 0,
+                workspaces: 1,
+                prompts: 2,
+                tokens: 1,
+            }
+        );
+        assert_eq!(apply(&pool, &bootstrap).await.unwrap(), Summary::default());
+
+        let user = db::get_user_by_email(&pool, "admin@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(auth::verify_password(
+            "correct horse battery",
+            &user.password_hash
+        ));
+        let support = db::get_workspace_by_slug(&pool, "support")
+            .await
+            .unwrap()
+            .unwrap();
+        let prompts = db::get_all_prompts(&pool, &support.id).await.unwrap();
+        assert_eq!(prompts[0].recurrence.as_deref(), Some("monthly"));
+        assert_eq!(
+            db::get_api_tokens(&pool).await.unwrap()[0].role(),
+            db::Role::Editor
+        );
+
+        // Roles follow the file; passwords are only used to create accounts
+        let demoted = parse("users:\n  - email: admin@example.com\n    role: editor\n").unwrap();
+        assert_eq!(apply(&pool, &demoted).await.unwrap().roles_updated, 1);
+    }
+
+    #[tokio::test]
+    async fn test_invalid_file_changes_nothing() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let bootstrap = parse(
+            r#"
+workspaces:
+  - slug: support
+    name: Support
+prompts:
+  - workspace: missing
+    title: Orphan
+"#,
+        )
+        .unwrap();
+        match apply(&pool, &bootstrap).await {
+            Err(Error::Invalid(message)) => assert!(message.contains("unknown workspace")),
+            other => panic!("expected invalid file, got {:?}", other),
+        }
+        assert!(db::get_workspace_by_slug(&pool, "support")
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(matches!(parse("users: 3"), Err(Error::Parse(_))));
+        assert!(matches!(parse("admins: []"), Err(Error::Parse(_))));
+    }
+}
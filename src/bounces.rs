@@ -0,0 +1,221 @@
+//! Delivery health for outgoing email. Each time the mail server refuses a
+//! message, the recipient's address is held off for a while, twice as long
+//! for every further bounce in a row; after `DISABLE_AFTER` of them it gets
+//! no more email until an owner re-enables it. A delivery that goes through
+//! clears the address's record. Failing to reach the server at all says
+//! nothing about the address, so it doesn't count.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use sqlx::{sqlite::SqlitePool, FromRow};
+
+use crate::email;
+
+/// Bounces in a row before an address is disabled
+pub const DISABLE_AFTER: i64 = 5;
+
+/// Hold-off after the first bounce; each further one doubles it
+const FIRST_BACKOFF_MINUTES: i64 = 15;
+
+const COLUMNS: &str = "address, bounces, last_error, last_bounced_at, retry_after, disabled_at";
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Bounce {
+    pub address: String,
+    /// Bounces in a row since the last successful delivery
+    pub bounces: i64,
+    pub last_error: String,
+    pub last_bounced_at: String,
+    /// Nothing is sent before this; unset once the address is disabled
+    pub retry_after: Option<String>,
+    pub disabled_at: Option<String>,
+}
+
+impl Bounce {
+    pub fn is_disabled(&self) -> bool {
+        self.disabled_at.is_some()
+    }
+
+    fn allows(&self, now: DateTime<Utc>) -> bool {
+        let retry_after = self
+            .retry_after
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok());
+        !self.is_disabled() && retry_after.is_none_or(|t| t <= now)
+    }
+}
+
+/// How long to hold off an address after `bounces` in a row
+pub fn backoff(bounces: i64) -> Duration {
+    Duration::minutes(FIRST_BACKOFF_MINUTES << (bounces - 1).clamp(0, 16))
+}
+
+/// Addresses are compared the way users and respondents are stored
+fn normalize(address: &str) -> String {
+    address.trim().to_lowercase()
+}
+
+pub async fn get(pool: &SqlitePool, address: &str) -> Result<Option<Bounce>, sqlx::Error> {
+    sqlx::query_as::<_, Bounce>(&format!(
+        "SELECT {} FROM email_bounces WHERE address = ?",
+        COLUMNS
+    ))
+    .bind(normalize(address))
+    .fetch_optional(pool)
+    .await
+}
+
+/// Every address with recent bounces, disabled ones first
+pub async fn list(pool: &SqlitePool) -> Result<Vec<Bounce>, sqlx::Error> {
+    sqlx::query_as::<_, Bounce>(&format!(
+        "SELECT {} FROM email_bounces ORDER BY disabled_at IS NULL, last_bounced_at DESC",
+        COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Count a bounce against `address`, backing it off or disabling it
+pub async fn record(
+    pool: &SqlitePool,
+    address: &str,
+    error: &str,
+    now: DateTime<Utc>,
+) -> Result<Bounce, sqlx::Error> {
+    let previous = get(pool, address).await?.map_or(0, |b| b.bounces);
+    let bounces = previous + 1;
+    let disabled = bounces >= DISABLE_AFTER;
+    let bounce = Bounce {
+        address: normalize(address),
+        bounces,
+        last_error: error.to_string(),
+        last_bounced_at: now.to_rfc3339(),
+        retry_after: (!disabled).then(|| (now + backoff(bounces)).to_rfc3339()),
+        disabled_at: disabled.then(|| now.to_rfc3339()),
+    };
+    sqlx::query(&format!(
+        "INSERT OR REPLACE INTO email_bounces ({}) VALUES (?, ?, ?, ?, ?, ?)",
+        COLUMNS
+    ))
+    .bind(&bounce.address)
+    .bind(bounce.bounces)
+    .bind(&bounce.last_error)
+    .bind(&bounce.last_bounced_at)
+    .bind(&bounce.retry_after)
+    .bind(&bounce.disabled_at)
+    .execute(pool)
+    .await?;
+    Ok(bounce)
+}
+
+/// Forget an address's bounces, re-enabling it. Returns false if it had none.
+pub async fn clear(pool: &SqlitePool, address: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM email_bounces WHERE address = ?")
+        .bind(normalize(address))
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Send through `mailer` unless `to` is held off or disabled, keeping its
+/// delivery record up to date
+pub async fn send(
+    pool: &SqlitePool,
+    mailer: &email::Mailer,
+    to: &str,
+    subject: &str,
+    body: &str,
+) -> Result<(), email::Error> {
+    send_at(pool, mailer, to, subject, body, Utc::now()).await
+}
+
+async fn send_at(
+    pool: &SqlitePool,
+    mailer: &email::Mailer,
+    to: &str,
+    subject: &str,
+    body: &str,
+    now: DateTime<Utc>,
+) -> Result<(), email::Error> {
+    // Without the record there's nothing to hold back on, so try anyway
+    let bounce = get(pool, to).await.ok().flatten();
+    if bounce.as_ref().is_some_and(|b| !b.allows(now)) {
+        return Err(email::Error::Suppressed(to.to_string()));
+    }
+
+    let result = mailer.send(to, subject, body).await;
+    let saved = match &result {
+        Ok(()) if bounce.is_some() => clear(pool, to).await.map(drop),
+        Err(e) if e.is_bounce() => record(pool, to, &e.to_string(), now).await.map(drop),
+        _ => Ok(()),
+    };
+    if let Err(e) = saved {
+        eprintln!("Saving delivery health for {} failed: {}", to, e);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_backoff_doubles() {
+        assert_eq!(backoff(1), Duration::minutes(15));
+        assert_eq!(backoff(2), Duration::minutes(30));
+        assert_eq!(backoff(4), Duration::hours(2));
+    }
+
+    #[tokio::test]
+    async fn test_bouncing_address_backs_off_then_disables() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let rejecting = email::Mailer::stub_rejecting("feedback@example.com").unwrap();
+        let mut now: DateTime<Utc> = "2026-10-16T08:00:00Z".parse().unwrap();
+
+        let result = send_at(&pool, &rejecting, "Ann@Example.com", "Hi", "Body", now).await;
+        assert!(result.unwrap_err().is_bounce());
+        let bounce = get(&pool, "ann@example.com").await.unwrap().unwrap();
+        assert_eq!(bounce.bounces, 1);
+        assert!(!bounce.is_disabled());
+
+        // Held off: nothing reaches the server until the backoff has passed
+        let result = send_at(&pool, &rejecting, "ann@example.com", "Hi", "Body", now).await;
+        assert!(matches!(result, Err(email::Error::Suppressed(_))));
+        assert_eq!(rejecting.sent().await.len(), 1);
+
+        for _ in 1..DISABLE_AFTER {
+            now += backoff(DISABLE_AFTER);
+            let _ = send_at(&pool, &rejecting, "ann@example.com", "Hi", "Body", now).await;
+        }
+        let bounce = get(&pool, "ann@example.com").await.unwrap().unwrap();
+        assert_eq!(bounce.bounces, DISABLE_AFTER);
+        assert!(bounce.is_disabled());
+        assert!(bounce.retry_after.is_none());
+
+        // Disabled addresses stay off however long it's been
+        let later = now + Duration::days(30);
+        let result = send_at(&pool, &rejecting, "ann@example.com", "Hi", "Body", later).await;
+        assert!(matches!(result, Err(email::Error::Suppressed(_))));
+        assert_eq!(list(&pool).await.unwrap().len(), 1);
+
+        // Re-enabled, a delivery that goes through leaves no record
+        assert!(clear(&pool, "ann@example.com").await.unwrap());
+        let working = email::Mailer::stub("feedback@example.com").unwrap();
+        record(&pool, "ann@example.com", "450 mailbox busy", later)
+            .await
+            .unwrap();
+        let after_backoff = later + backoff(1);
+        send_at(
+            &pool,
+            &working,
+            "ann@example.com",
+            "Hi",
+            "Body",
+            after_backoff,
+        )
+        .await
+        .unwrap();
+        assert!(get(&pool, "ann@example.com").await.unwrap().is_none());
+    }
+}
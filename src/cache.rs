@@ -0,0 +1,111 @@
+//! Short-lived copies of prompt records for the public pages. A prompt shared
+//! with hundreds of respondents is looked up on every form view and
+//! submission; the cache answers those from memory. Handlers that change a
+//! prompt evict it, and the TTL bounds how stale anything else can get.
+
+use moka::sync::Cache;
+use sqlx::sqlite::SqlitePool;
+use std::time::Duration;
+
+use crate::db;
+
+pub const DEFAULT_TTL: Duration = Duration::from_secs(30);
+pub const DEFAULT_CAPACITY: u64 = 10_000;
+
+pub struct PromptCache {
+    /// `None` when caching is turned off
+    prompts: Option<Cache<String, db::Prompt>>,
+}
+
+impl Default for PromptCache {
+    fn default() -> Self {
+        PromptCache::new(DEFAULT_TTL, DEFAULT_CAPACITY)
+    }
+}
+
+impl PromptCache {
+    /// A zero TTL or capacity turns caching off
+    pub fn new(ttl: Duration, capacity: u64) -> Self {
+        let enabled = !ttl.is_zero() && capacity > 0;
+        PromptCache {
+            prompts: enabled.then(|| {
+                Cache::builder()
+                    .max_capacity(capacity)
+                    .time_to_live(ttl)
+                    .build()
+            }),
+        }
+    }
+
+    /// A prompt by ID, from memory when possible. Misses aren't cached, so a
+    /// prompt created a moment ago is found straight away.
+    pub async fn get(
+        &self,
+        pool: &SqlitePool,
+        id: &str,
+    ) -> Result<Option<db::Prompt>, sqlx::Error> {
+        let Some(prompts) = &self.prompts else {
+            return db::get_prompt_by_id(pool, id).await;
+        };
+        if let Some(prompt) = prompts.get(id) {
+            return Ok(Some(prompt));
+        }
+        let prompt = db::get_prompt_by_id(pool, id).await?;
+        if let Some(prompt) = &prompt {
+            prompts.insert(prompt.id.clone(), prompt.clone());
+        }
+        Ok(prompt)
+    }
+
+    /// Drop a prompt after it's edited or deleted
+    pub fn invalidate(&self, id: &str) {
+        if let Some(prompts) = &self.prompts {
+            prompts.invalidate(id);
+        }
+    }
+
+    /// Drop everything, after changes that touch many prompts at once
+    pub fn clear(&self) {
+        if let Some(prompts) = &self.prompts {
+            prompts.invalidate_all();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hit_and_invalidate() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt(&pool, "Cached", "").await.unwrap();
+        let cache = PromptCache::default();
+        assert!(cache.get(&pool, &prompt.id).await.unwrap().is_some());
+
+        db::set_prompt_status(&pool, &prompt.id, db::PromptStatus::Closed)
+            .await
+            .unwrap();
+        let stale = cache.get(&pool, &prompt.id).await.unwrap().unwrap();
+        assert!(stale.closed_at.is_none());
+
+        cache.invalidate(&prompt.id);
+        let fresh = cache.get(&pool, &prompt.id).await.unwrap().unwrap();
+        assert!(fresh.closed_at.is_some());
+
+        assert!(cache.get(&pool, "missing").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_disabled() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt(&pool, "Uncached", "").await.unwrap();
+        let cache = PromptCache::new(Duration::ZERO, DEFAULT_CAPACITY);
+        cache.get(&pool, &prompt.id).await.unwrap();
+        db::set_prompt_status(&pool, &prompt.id, db::PromptStatus::Closed)
+            .await
+            .unwrap();
+        let prompt = cache.get(&pool, &prompt.id).await.unwrap().unwrap();
+        assert!(prompt.closed_at.is_some());
+    }
+}
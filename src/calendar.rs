@@ -0,0 +1,167 @@
+//! A year of submissions as a GitHub-style heatmap: one column per week,
+//! Sunday at the top, darker squares for busier days. Rendered as a plain
+//! SVG string, since it's only rectangles, dates and numbers.
+
+use crate::db::DailyCount;
+use chrono::{Datelike, Duration, NaiveDate};
+use serde::Serialize;
+use std::fmt::Write;
+
+/// Weeks shown, counting the current, partial one
+pub const WEEKS: i64 = 53;
+
+/// Fill colours from no responses to the busiest days
+const LEVELS: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+const CELL: i64 = 10;
+const STEP: i64 = CELL + 2;
+/// Room for the weekday labels on the left and month labels on top
+const LEFT: i64 = 28;
+const TOP: i64 = 15;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Day {
+    pub date: NaiveDate,
+    pub count: i64,
+}
+
+/// The first day shown for a calendar ending on `end`: the Sunday that
+/// starts the earliest week
+pub fn start(end: NaiveDate) -> NaiveDate {
+    let sunday = end - Duration::days(end.weekday().num_days_from_sunday() as i64);
+    sunday - Duration::weeks(WEEKS - 1)
+}
+
+/// Every day from `start(end)` through `end`, with days missing from
+/// `counts` as zero
+pub fn days(end: NaiveDate, counts: &[DailyCount]) -> Vec<Day> {
+    start(end)
+        .iter_days()
+        .take_while(|date| *date <= end)
+        .map(|date| {
+            let key = date.format("%Y-%m-%d").to_string();
+            let count = counts.iter().find(|c| c.day == key).map_or(0, |c| c.count);
+            Day { date, count }
+        })
+        .collect()
+}
+
+/// Which of `LEVELS` a day gets, relative to the busiest day shown
+fn level(count: i64, max: i64) -> usize {
+    if count <= 0 || max <= 0 {
+        return 0;
+    }
+    ((count * 4 + max - 1) / max).clamp(1, 4) as usize
+}
+
+/// The heatmap for `days`, which must start on a Sunday (as `days` does)
+pub fn render_svg(days: &[Day]) -> String {
+    let max = days.iter().map(|d| d.count).max().unwrap_or(0);
+    let weeks = (days.len() as i64 + 6) / 7;
+    let width = LEFT + weeks * STEP;
+    let height = TOP + 7 * STEP;
+
+    let mut svg = String::new();
+    let _ = write!(
+        svg,
+        r##"<svg xmlns="http://www.w3.org/2000/svg" width="{width}" height="{height}" viewBox="0 0 {width} {height}" font-family="sans-serif" font-size="9" fill="#767676">"##
+    );
+    for (row, label) in [(1, "Mon"), (3, "Wed"), (5, "Fri")] {
+        let _ = write!(
+            svg,
+            r#"<text x="0" y="{}">{}</text>"#,
+            TOP + row * STEP + CELL - 1,
+            label
+        );
+    }
+
+    let mut last_month = None;
+    for (week, chunk) in days.chunks(7).enumerate() {
+        let x = LEFT + week as i64 * STEP;
+        let month = chunk[0].date.month();
+        if last_month.is_some_and(|last| last != month) {
+            let _ = write!(
+                svg,
+                r#"<text x="{}" y="{}">{}</text>"#,
+                x,
+                TOP - 5,
+                chunk[0].date.format("%b")
+            );
+        }
+        last_month = Some(month);
+
+        for day in chunk {
+            let y = TOP + day.date.weekday().num_days_from_sunday() as i64 * STEP;
+            let noun = if day.count == 1 {
+                "response"
+            } else {
+                "responses"
+            };
+            let _ = write!(
+                svg,
+                r#"<rect x="{}" y="{}" width="{CELL}" height="{CELL}" rx="2" fill="{}"><title>{} {} on {}</title></rect>"#,
+                x,
+                y,
+                LEVELS[level(day.count, max)],
+                day.count,
+                noun,
+                day.date.format("%Y-%m-%d")
+            );
+        }
+    }
+    svg.push_str("</svg>");
+    svg
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_days_cover_whole_weeks_up_to_today() {
+        // A Friday
+        let end = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let counts = vec![DailyCount {
+            day: "2026-10-14".to_string(),
+            count: 3,
+        }];
+        let days = days(end, &counts);
+
+        assert_eq!(days[0].date.weekday(), chrono::Weekday::Sun);
+        assert_eq!(days.last().unwrap().date, end);
+        assert_eq!(days.len() as i64, (WEEKS - 1) * 7 + 6);
+        let busy = days.iter().find(|d| d.count > 0).unwrap();
+        assert_eq!(busy.date, NaiveDate::from_ymd_opt(2026, 10, 14).unwrap());
+    }
+
+    #[test]
+    fn test_render_svg() {
+        let end = NaiveDate::from_ymd_opt(2026, 10, 16).unwrap();
+        let counts = vec![
+            DailyCount {
+                day: "2026-10-14".to_string(),
+                count: 4,
+            },
+            DailyCount {
+                day: "2026-10-15".to_string(),
+                count: 1,
+            },
+        ];
+        let days = days(end, &counts);
+        let svg = render_svg(&days);
+
+        assert!(svg.starts_with("<svg"));
+        assert_eq!(svg.matches("<rect").count(), days.len());
+        assert!(svg.contains(r##"fill="#216e39"><title>4 responses on 2026-10-14</title>"##));
+        assert!(svg.contains(r##"fill="#9be9a8"><title>1 response on 2026-10-15</title>"##));
+        assert!(svg.contains(">Oct</text>"));
+    }
+
+    #[test]
+    fn test_level() {
+        assert_eq!(level(0, 10), 0);
+        assert_eq!(level(1, 10), 1);
+        assert_eq!(level(5, 10), 2);
+        assert_eq!(level(8, 10), 4);
+        assert_eq!(level(10, 10), 4);
+    }
+}
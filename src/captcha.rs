@@ -0,0 +1,133 @@
+//! Optional CAPTCHA on the public feedback form, for prompts shared where
+//! bots find them. Enabled by setting `CAPTCHA_PROVIDER` (`hcaptcha` or
+//! `turnstile`), `CAPTCHA_SITE_KEY` and `CAPTCHA_SECRET`.
+//!
+//! Both providers share the same shape: a script renders the widget, which
+//! adds a token field to the form, and the server checks that token against
+//! the provider's siteverify endpoint.
+
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Provider {
+    HCaptcha,
+    Turnstile,
+}
+
+impl Provider {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_lowercase().as_str() {
+            "hcaptcha" => Some(Provider::HCaptcha),
+            "turnstile" => Some(Provider::Turnstile),
+            _ => None,
+        }
+    }
+
+    pub fn script_url(&self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "https://js.hcaptcha.com/1/api.js",
+            Provider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/api.js",
+        }
+    }
+
+    /// Class of the element the script turns into a widget
+    pub fn widget_class(&self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "h-captcha",
+            Provider::Turnstile => "cf-turnstile",
+        }
+    }
+
+    /// Form field the widget fills with its token
+    pub fn response_field(&self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "h-captcha-response",
+            Provider::Turnstile => "cf-turnstile-response",
+        }
+    }
+
+    /// JavaScript global used to reset the widget after an htmx submission
+    pub fn js_global(&self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "hcaptcha",
+            Provider::Turnstile => "turnstile",
+        }
+    }
+
+    fn verify_url(&self) -> &'static str {
+        match self {
+            Provider::HCaptcha => "https://api.hcaptcha.com/siteverify",
+            Provider::Turnstile => "https://challenges.cloudflare.com/turnstile/v0/siteverify",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub provider: Provider,
+    pub site_key: String,
+    pub secret: String,
+    /// Defaults to the provider's siteverify endpoint
+    pub verify_url: Option<String>,
+}
+
+impl Config {
+    pub fn from_env() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        Some(Config {
+            provider: Provider::parse(&var("CAPTCHA_PROVIDER")?)?,
+            site_key: var("CAPTCHA_SITE_KEY")?,
+            secret: var("CAPTCHA_SECRET")?,
+            verify_url: var("CAPTCHA_VERIFY_URL"),
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct VerifyResponse {
+    success: bool,
+}
+
+/// Ask the provider whether `token` is a solved challenge for our site.
+/// An empty token is rejected without a request.
+pub async fn verify(
+    http: &reqwest::Client,
+    config: &Config,
+    token: &str,
+) -> Result<bool, reqwest::Error> {
+    let token = token.trim();
+    if token.is_empty() {
+        return Ok(false);
+    }
+
+    let url = config
+        .verify_url
+        .as_deref()
+        .unwrap_or(config.provider.verify_url());
+    let mut params = vec![("secret", config.secret.as_str()), ("response", token)];
+    // hCaptcha also checks the token was issued for our site key
+    if config.provider == Provider::HCaptcha {
+        params.push(("sitekey", config.site_key.as_str()));
+    }
+    let response: VerifyResponse = http
+        .post(url)
+        .form(&params)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(response.success)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_provider_parse() {
+        assert_eq!(Provider::parse("hCaptcha"), Some(Provider::HCaptcha));
+        assert_eq!(Provider::parse(" turnstile "), Some(Provider::Turnstile));
+        assert_eq!(Provider::parse("recaptcha"), None);
+    }
+}
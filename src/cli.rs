@@ -0,0 +1,464 @@
+//! The binary's subcommands. `serve` (the default) runs the web app; the
+//! rest are one-off operational tasks against the same database, so nobody
+//! has to edit the SQLite file by hand.
+
+use std::path::PathBuf;
+
+use crate::{archive, auth, db, doctor, export, validation};
+
+pub const USAGE: &str = "Usage: feedback-app [COMMAND]
+
+Commands:
+  serve                                     Run the web app (the default)
+  migrate                                   Create or upgrade the database schema, then exit
+  doctor [--repair]                         Check the database for problems
+  export --format json|jsonl|csv [--workspace SLUG] [--since DATE] [--output PATH]
+                                            Write a workspace export to PATH or stdout
+  prompt create --title TITLE [--description TEXT] [--workspace SLUG]
+                                            Create an open prompt and print its ID
+  admin create-user --email EMAIL [--role owner|editor|viewer]
+                                            Create an account; the password comes from
+                                            ADMIN_PASSWORD or the first line of stdin
+
+DATABASE_URL picks the database, as for `serve`.";
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Command {
+    Serve,
+    Migrate,
+    Doctor {
+        repair: bool,
+    },
+    Export {
+        format: ExportFormat,
+        workspace: String,
+        since: Option<String>,
+        output: Option<PathBuf>,
+    },
+    CreatePrompt {
+        workspace: String,
+        title: String,
+        description: String,
+    },
+    CreateUser {
+        email: String,
+        role: db::Role,
+    },
+    Help,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ExportFormat {
+    Json,
+    Jsonl,
+    Csv,
+}
+
+/// Read the command line (without the program name)
+pub fn parse(args: &[String]) -> Result<Command, String> {
+    let (command, rest) = match args.split_first() {
+        None => return Ok(Command::Serve),
+        Some((command, rest)) => (command.as_str(), rest),
+    };
+    match command {
+        "serve" => {
+            Options::parse(rest, &[])?;
+            Ok(Command::Serve)
+        }
+        "migrate" => {
+            Options::parse(rest, &[])?;
+            Ok(Command::Migrate)
+        }
+        "doctor" => {
+            let options = Options::parse(rest, &["--repair"])?;
+            Ok(Command::Doctor {
+                repair: options.flag("--repair"),
+            })
+        }
+        "export" => {
+            let mut options = Options::parse(rest, &[])?;
+            let format = match options.take("--format").as_deref() {
+                Some("json") => ExportFormat::Json,
+                Some("jsonl") => ExportFormat::Jsonl,
+                Some("csv") => ExportFormat::Csv,
+                Some(other) => return Err(format!("Unknown export format: {}", other)),
+                None => return Err("export needs --format json, jsonl or csv".to_string()),
+            };
+            let since = match options.take("--since") {
+                Some(value) => Some(db::parse_since(&value).ok_or_else(|| {
+                    "--since must be an RFC 3339 timestamp or YYYY-MM-DD".to_string()
+                })?),
+                None => None,
+            };
+            let command = Command::Export {
+                format,
+                workspace: options.take_workspace(),
+                since,
+                output: options.take("--output").map(PathBuf::from),
+            };
+            options.finish()?;
+            Ok(command)
+        }
+        "prompt" => match rest.split_first() {
+            Some((sub, rest)) if sub == "create" => {
+                let mut options = Options::parse(rest, &[])?;
+                let title = options
+                    .take("--title")
+                    .ok_or_else(|| "prompt create needs --title".to_string())?;
+                let command = Command::CreatePrompt {
+                    title,
+                    description: options.take("--description").unwrap_or_default(),
+                    workspace: options.take_workspace(),
+                };
+                options.finish()?;
+                Ok(command)
+            }
+            _ => Err("Usage: feedback-app prompt create --title TITLE".to_string()),
+        },
+        "admin" => match rest.split_first() {
+            Some((sub, rest)) if sub == "create-user" => {
+                let mut options = Options::parse(rest, &[])?;
+                let email = options
+                    .take("--email")
+                    .ok_or_else(|| "admin create-user needs --email".to_string())?
+                    .trim()
+                    .to_lowercase();
+                let role = match options.take("--role") {
+                    Some(role) => {
+                        db::Role::parse(&role).ok_or_else(|| format!("Unknown role: {}", role))?
+                    }
+                    None => db::Role::Owner,
+                };
+                options.finish()?;
+                Ok(Command::CreateUser { email, role })
+            }
+            _ => Err("Usage: feedback-app admin create-user --email EMAIL".to_string()),
+        },
+        "help" | "--help" | "-h" => Ok(Command::Help),
+        other => Err(format!("Unknown command: {}", other)),
+    }
+}
+
+/// `--name value` / `--name=value` options and bare `--flag`s
+struct Options {
+    values: Vec<(String, String)>,
+    flags: Vec<String>,
+}
+
+impl Options {
+    fn parse(args: &[String], flags: &[&str]) -> Result<Self, String> {
+        let mut options = Options {
+            values: Vec::new(),
+            flags: Vec::new(),
+        };
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            if flags.contains(&arg.as_str()) {
+                options.flags.push(arg.clone());
+                continue;
+            }
+            if !arg.starts_with("--") {
+                return Err(format!("Unexpected argument: {}", arg));
+            }
+            let (name, value) = match arg.split_once('=') {
+                Some((name, value)) => (name.to_string(), value.to_string()),
+                None => {
+                    let value = args
+                        .next()
+                        .ok_or_else(|| format!("{} needs a value", arg))?;
+                    (arg.clone(), value.clone())
+                }
+            };
+            options.values.push((name, value));
+        }
+        Ok(options)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.iter().any(|f| f == name)
+    }
+
+    fn take(&mut self, name: &str) -> Option<String> {
+        let index = self.values.iter().position(|(n, _)| n == name)?;
+        Some(self.values.remove(index).1)
+    }
+
+    fn take_workspace(&mut self) -> String {
+        self.take("--workspace")
+            .unwrap_or_else(|| db::DEFAULT_WORKSPACE_ID.to_string())
+    }
+
+    /// Anything not taken by now wasn't a known option
+    fn finish(self) -> Result<(), String> {
+        match self.values.first() {
+            Some((name, _)) => Err(format!("Unknown option: {}", name)),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Run everything but `serve`; returns the process exit code
+pub async fn run(command: Command, database_url: &str) -> i32 {
+    match command {
+        Command::Serve => unreachable!("serve is run by main"),
+        Command::Help => {
+            println!("{}", USAGE);
+            0
+        }
+        Command::Doctor { repair } => run_doctor(database_url, repair).await,
+        command => {
+            let pool = match db::init_db_with(database_url, &db::PoolConfig::default()).await {
+                Ok(pool) => pool,
+                Err(e) => {
+                    eprintln!("Failed to open database: {}", e);
+                    return 2;
+                }
+            };
+            let result = run_with_pool(command, &pool).await;
+            pool.close().await;
+            match result {
+                Ok(()) => 0,
+                Err(message) => {
+                    eprintln!("{}", message);
+                    1
+                }
+            }
+        }
+    }
+}
+
+async fn run_with_pool(command: Command, pool: &sqlx::SqlitePool) -> Result<(), String> {
+    match command {
+        // Opening the pool already brought the schema up to date
+        Command::Migrate => println!("Database schema is up to date"),
+        Command::Export {
+            format,
+            workspace,
+            since,
+            output,
+        } => {
+            let workspace = find_workspace(pool, &workspace).await?;
+            let archive = archive::export(pool, &workspace.id, since.as_deref())
+                .await
+                .map_err(database_error)?;
+            let body = match format {
+                ExportFormat::Json => serde_json::to_string_pretty(&archive)
+                    .map_err(|e| format!("Failed to encode export: {}", e))?,
+                ExportFormat::Jsonl => archive.to_jsonl(&workspace.slug),
+                ExportFormat::Csv => export::sheet(
+                    &archive,
+                    &export::default_columns(&archive.fields),
+                    export::DEFAULT_DATE_FORMAT,
+                )
+                .to_csv(),
+            };
+            match output {
+                Some(path) => {
+                    std::fs::write(&path, body)
+                        .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+                    eprintln!(
+                        "Exported {} entries to {}",
+                        archive.feedback.len(),
+                        path.display()
+                    );
+                }
+                None => print!("{}", body),
+            }
+        }
+        Command::CreatePrompt {
+            workspace,
+            title,
+            description,
+        } => {
+            let workspace = find_workspace(pool, &workspace).await?;
+            let title = validation::title(&title).map_err(|p| format!("Title {}", p.message()))?;
+            let description = validation::description(&description, false)
+                .map_err(|p| format!("Description {}", p.message()))?;
+            let prompt = db::create_prompt_with_options(
+                pool,
+                &workspace.id,
+                title,
+                description,
+                &db::PromptOptions::default(),
+            )
+            .await
+            .map_err(database_error)?;
+            println!("{}", prompt.id);
+            eprintln!("Collecting feedback at /feedback/{}", prompt.id);
+        }
+        Command::CreateUser { email, role } => {
+            if !crate::is_plausible_email(&email) {
+                return Err(format!("Invalid email address: {}", email));
+            }
+            let password = read_password()?;
+            if password.chars().count() < auth::MIN_PASSWORD_LEN {
+                return Err(format!(
+                    "Passwords must be at least {} characters",
+                    auth::MIN_PASSWORD_LEN
+                ));
+            }
+            let created = db::create_user(pool, &email, &auth::hash_password(&password), role)
+                .await
+                .map_err(database_error)?;
+            match created {
+                Some(user) => println!("Created {} ({})", user.email, role.as_str()),
+                None => return Err(format!("An account for {} already exists", email)),
+            }
+        }
+        Command::Serve | Command::Help | Command::Doctor { .. } => unreachable!(),
+    }
+    Ok(())
+}
+
+async fn find_workspace(pool: &sqlx::SqlitePool, slug: &str) -> Result<db::Workspace, String> {
+    db::get_workspace_by_slug(pool, slug)
+        .await
+        .map_err(database_error)?
+        .ok_or_else(|| format!("No workspace named {}", slug))
+}
+
+fn database_error(e: sqlx::Error) -> String {
+    format!("Database error: {}", e)
+}
+
+/// `ADMIN_PASSWORD`, as for the bootstrap file, or else the first line of
+/// stdin, so the password never has to appear in the command line
+fn read_password() -> Result<String, String> {
+    if let Ok(password) = std::env::var("ADMIN_PASSWORD") {
+        return Ok(password);
+    }
+    let mut line = String::new();
+    std::io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| format!("Failed to read password: {}", e))?;
+    Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// `feedback-app doctor [--repair]`; returns the process exit code
+async fn run_doctor(database_url: &str, repair: bool) -> i32 {
+    // Connect without migrating so the database is inspected as it is on disk
+    let pool = match sqlx::SqlitePool::connect(database_url).await {
+        Ok(pool) => pool,
+        Err(e) => {
+            eprintln!("Failed to open database: {}", e);
+            return 2;
+        }
+    };
+
+    let report = match doctor::run(&pool, repair).await {
+        Ok(report) => report,
+        Err(e) => {
+            eprintln!("Doctor failed: {}", e);
+            return 2;
+        }
+    };
+
+    for issue in &report.issues {
+        let status = if issue.repaired { "repaired" } else { "found" };
+        println!("[{}] {}: {}", status, issue.check, issue.detail);
+    }
+
+    let outstanding = report.outstanding();
+    if report.issues.is_empty() {
+        println!("No issues found");
+    } else if outstanding > 0 && !repair {
+        println!(
+            "{} issue(s) found; run `feedback-app doctor --repair` to fix what can be fixed",
+            outstanding
+        );
+    } else {
+        println!(
+            "{} issue(s) repaired, {} outstanding",
+            report.issues.len() - outstanding,
+            outstanding
+        );
+    }
+
+    if outstanding > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(line: &str) -> Vec<String> {
+        line.split_whitespace().map(String::from).collect()
+    }
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(parse(&[]), Ok(Command::Serve));
+        assert_eq!(
+            parse(&args("doctor --repair")),
+            Ok(Command::Doctor { repair: true })
+        );
+        assert_eq!(
+            parse(&args(
+                "export --format=csv --workspace team --output out.csv"
+            )),
+            Ok(Command::Export {
+                format: ExportFormat::Csv,
+                workspace: "team".to_string(),
+                since: None,
+                output: Some(PathBuf::from("out.csv")),
+            })
+        );
+        assert_eq!(
+            parse(&args(
+                "admin create-user --email Ann@Example.com --role viewer"
+            )),
+            Ok(Command::CreateUser {
+                email: "ann@example.com".to_string(),
+                role: db::Role::Viewer,
+            })
+        );
+        assert!(parse(&args("export")).is_err());
+        assert!(parse(&args("export --format xml")).is_err());
+        assert!(parse(&args("export --format json --since soon")).is_err());
+        assert!(parse(&args("prompt create --title")).is_err());
+        assert!(parse(&args("prompt create --title Hi --colour red")).is_err());
+        assert!(parse(&args("admin create-user --email a@b.c --role boss")).is_err());
+        assert!(parse(&args("launch")).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_create_prompt_and_export() {
+        let path = std::env::temp_dir().join(format!("feedback-cli-{}.db", uuid::Uuid::new_v4()));
+        let url = format!("sqlite:{}?mode=rwc", path.display());
+        let output = path.with_extension("csv");
+
+        let create = parse(&args("prompt create --title Roadmap")).unwrap();
+        assert_eq!(run(create, &url).await, 0);
+        let pool = db::init_db(&url).await.unwrap();
+        let workspace = db::get_workspace_by_slug(&pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .unwrap();
+        let prompts = db::get_all_prompts(&pool, &workspace.id).await.unwrap();
+        assert_eq!(prompts.len(), 1);
+        db::create_feedback(&pool, &prompts[0].id, "Dark mode, please")
+            .await
+            .unwrap();
+        pool.close().await;
+
+        let command = Command::Export {
+            format: ExportFormat::Csv,
+            workspace: db::DEFAULT_WORKSPACE_ID.to_string(),
+            since: None,
+            output: Some(output.clone()),
+        };
+        assert_eq!(run(command, &url).await, 0);
+        let csv = std::fs::read_to_string(&output).unwrap();
+        assert!(csv.contains("Dark mode, please"));
+
+        let missing = parse(&args("prompt create --title X --workspace nope")).unwrap();
+        assert_eq!(run(missing, &url).await, 1);
+
+        let _ = std::fs::remove_file(&output);
+        let _ = std::fs::remove_file(&path);
+    }
+}
@@ -0,0 +1,709 @@
+//! Typed client for the `/api/v1` JSON API.
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), feedback_app::client::Error> {
+//! use feedback_app::client::{Client, NewFeedback, NewPrompt};
+//!
+//! let client = Client::new("https://feedback.example.com");
+//! let prompt = client
+//!     .create_prompt("default", &NewPrompt::new("Release 1.4", "How did the upgrade go?"))
+//!     .await?;
+//! client
+//!     .submit_feedback(&prompt.id, &NewFeedback::new("Smooth, thanks!"))
+//!     .await?;
+//! let feedback = client.list_feedback("default", &prompt.id).await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Prompt {
+    pub id: String,
+    pub workspace_id: String,
+    pub title: String,
+    pub description: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub merge_responses: bool,
+    #[serde(default)]
+    pub recurrence: Option<String>,
+    #[serde(default)]
+    pub content_min_length: Option<i64>,
+    #[serde(default)]
+    pub content_max_length: Option<i64>,
+    #[serde(default)]
+    pub content_pattern: Option<String>,
+    /// Set while the prompt refuses new feedback
+    #[serde(default)]
+    pub closed_at: Option<String>,
+    /// Set once archived; archived prompts are closed too
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    /// When set, respondents can see and upvote each other's feedback
+    #[serde(default)]
+    pub public_board: bool,
+    /// When set, respondents who leave an email get a copy of their submission
+    #[serde(default)]
+    pub send_receipts: bool,
+    /// Set while taken down pending abuse review
+    #[serde(default)]
+    pub suspended_at: Option<String>,
+    /// Language the public form is always shown in; `None` follows the
+    /// respondent's browser
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Short name for the `/f/:slug` form URL
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Markdown shown after a submission instead of the standard thanks
+    #[serde(default)]
+    pub thank_you_message: Option<String>,
+    /// Where respondents are sent after submitting
+    #[serde(default)]
+    pub redirect_url: Option<String>,
+    /// UTC time before which the form isn't open yet
+    #[serde(default)]
+    pub publish_at: Option<String>,
+    /// `text` for written feedback, `nps` for a 0-10 score or `reactions`
+    /// for a one-tap emoji, the latter two with an optional comment
+    #[serde(default)]
+    pub kind: String,
+    /// Days its feedback is kept before the purge deletes it, if set
+    #[serde(default)]
+    pub retention_days: Option<i64>,
+    /// The form closes as full once this many responses are in
+    #[serde(default)]
+    pub max_responses: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Feedback {
+    pub id: String,
+    pub prompt_id: String,
+    pub content: String,
+    pub created_at: String,
+    #[serde(default)]
+    pub respondent_email: Option<String>,
+    #[serde(default)]
+    pub updated_at: Option<String>,
+    #[serde(default)]
+    pub cycle_id: Option<String>,
+    #[serde(default)]
+    pub sentiment_score: Option<f64>,
+    #[serde(default)]
+    pub sentiment_label: Option<String>,
+    /// Triage state: `new`, `reviewed`, `actioned` or `dismissed`
+    #[serde(default)]
+    pub status: String,
+    /// 0-10 answer on an NPS prompt
+    #[serde(default)]
+    pub nps_score: Option<i64>,
+    /// `love`, `happy`, `neutral` or `unhappy` on a reactions prompt
+    #[serde(default)]
+    pub reaction: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub answers: Vec<Answer>,
+}
+
+/// An answer to one of the prompt's question bank fields
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Answer {
+    pub field_id: String,
+    pub label: String,
+    pub value: String,
+    /// Came from a signed prefill link rather than the respondent
+    #[serde(default)]
+    pub verified: bool,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewPrompt {
+    pub title: String,
+    pub description: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook_url: Option<String>,
+    #[serde(default)]
+    pub merge_responses: bool,
+    /// `weekly` or `monthly`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<String>,
+    /// Limits on feedback length, in characters
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_min_length: Option<i64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_max_length: Option<i64>,
+    /// A pattern the whole feedback text must match
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_pattern: Option<String>,
+    #[serde(default)]
+    pub public_board: bool,
+    #[serde(default)]
+    pub send_receipts: bool,
+    /// Language code (`en`, `de`, `es`, `fr`) to show the public form in
+    /// instead of negotiating from the respondent's browser
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub language: Option<String>,
+    /// Unique short name for a `/f/:slug` form URL: letters, numbers and `-`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub slug: Option<String>,
+    /// Markdown shown to respondents after they submit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub thank_you_message: Option<String>,
+    /// Absolute http(s) URL to send respondents to after they submit
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub redirect_url: Option<String>,
+    /// Code respondents must enter first: 4 to 64 letters, numbers, `-` or `_`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_code: Option<String>,
+    /// RFC 3339 time the form opens at; it refuses feedback until then
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub publish_at: Option<String>,
+    /// `text` (the default), `nps` or `reactions`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub kind: Option<String>,
+    /// Days to keep feedback, 1 to 3650; the site-wide period applies if shorter
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_days: Option<i64>,
+    /// Stop taking responses once this many are in; at least 1
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_responses: Option<i64>,
+}
+
+impl NewPrompt {
+    pub fn new(title: impl Into<String>, description: impl Into<String>) -> Self {
+        NewPrompt {
+            title: title.into(),
+            description: description.into(),
+            ..Default::default()
+        }
+    }
+}
+
+/// Overrides for a duplicated prompt; unset fields are copied from the original
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct DuplicatePrompt {
+    /// Defaults to the original's title with " (copy)" appended
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct NewFeedback {
+    pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub email: Option<String>,
+    /// Answers to the prompt's question bank fields, keyed by field ID
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub answers: HashMap<String, String>,
+    /// Token from a signed prefill link; its answers override `answers`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prefill: Option<String>,
+    /// The prompt's access code, when it has one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access_code: Option<String>,
+    /// A single-use invite token, on prompts that only open through invites
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub invite: Option<String>,
+    /// 0-10, required on NPS prompts (where `content` may be empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<i64>,
+    /// Required on reactions prompts (where `content` may be empty)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reaction: Option<String>,
+    /// A solved CAPTCHA's response token, on instances that require one
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub captcha_token: Option<String>,
+}
+
+impl NewFeedback {
+    pub fn new(content: impl Into<String>) -> Self {
+        NewFeedback {
+            content: content.into(),
+            email: None,
+            answers: HashMap::new(),
+            prefill: None,
+            access_code: None,
+            invite: None,
+            score: None,
+            reaction: None,
+            captcha_token: None,
+        }
+    }
+
+    pub fn with_email(mut self, email: impl Into<String>) -> Self {
+        self.email = Some(email.into());
+        self
+    }
+
+    pub fn with_score(mut self, score: i64) -> Self {
+        self.score = Some(score);
+        self
+    }
+
+    pub fn with_reaction(mut self, reaction: impl Into<String>) -> Self {
+        self.reaction = Some(reaction.into());
+        self
+    }
+
+    pub fn with_answer(mut self, field_id: impl Into<String>, value: impl Into<String>) -> Self {
+        self.answers.insert(field_id.into(), value.into());
+        self
+    }
+
+    pub fn with_prefill(mut self, token: impl Into<String>) -> Self {
+        self.prefill = Some(token.into());
+        self
+    }
+
+    pub fn with_access_code(mut self, code: impl Into<String>) -> Self {
+        self.access_code = Some(code.into());
+        self
+    }
+
+    pub fn with_invite(mut self, token: impl Into<String>) -> Self {
+        self.invite = Some(token.into());
+        self
+    }
+
+    pub fn with_captcha_token(mut self, token: impl Into<String>) -> Self {
+        self.captcha_token = Some(token.into());
+        self
+    }
+}
+
+/// Responses received on one UTC day
+#[derive(Debug, Clone, Deserialize)]
+pub struct CalendarDay {
+    /// `YYYY-MM-DD`
+    pub date: String,
+    pub count: i64,
+}
+
+/// A prompt's title, description and field labels in another language
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct PromptTranslation {
+    /// `de`, `es`, `fr` or `en`; set from the URL when saving
+    #[serde(default, skip_serializing)]
+    pub language: String,
+    pub title: String,
+    /// Empty to keep the original description
+    #[serde(default)]
+    pub description: String,
+    /// Labels keyed by field ID; fields left out keep their original label
+    #[serde(default)]
+    pub field_labels: HashMap<String, String>,
+    #[serde(default, skip_serializing)]
+    pub updated_at: String,
+}
+
+/// A signed link to the feedback form with some answers locked
+#[derive(Debug, Clone, Deserialize)]
+pub struct PrefillLink {
+    /// Pass as `NewFeedback::with_prefill` when submitting through the API
+    pub token: String,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, Deserialize, PartialEq)]
+pub struct FieldError {
+    pub field: String,
+    pub message: String,
+}
+
+/// The server's JSON error envelope
+#[derive(Debug, Clone, Deserialize)]
+pub struct ApiError {
+    pub code: String,
+    pub message: String,
+    #[serde(default)]
+    pub fields: Vec<FieldError>,
+    #[serde(default)]
+    pub request_id: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum Error {
+    /// The request never got a usable response
+    Http(reqwest::Error),
+    /// The server rejected the request
+    Api { status: u16, error: ApiError },
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "request failed: {}", e),
+            Error::Api { status, error } => {
+                write!(f, "{} ({}): {}", error.code, status, error.message)
+            }
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+#[derive(Deserialize)]
+struct ErrorEnvelope {
+    error: ApiError,
+}
+
+#[derive(Debug, Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    base_url: String,
+    token: Option<String>,
+}
+
+impl Client {
+    /// `base_url` is the server root, e.g. `https://feedback.example.com`
+    pub fn new(base_url: impl Into<String>) -> Self {
+        Self::with_http_client(base_url, reqwest::Client::new())
+    }
+
+    /// Reuse an existing HTTP client, e.g. one with custom timeouts
+    pub fn with_http_client(base_url: impl Into<String>, http: reqwest::Client) -> Self {
+        Client {
+            http,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+            token: None,
+        }
+    }
+
+    /// Authenticate with an API token minted under Admin > API Tokens
+    pub fn with_token(mut self, token: impl Into<String>) -> Self {
+        self.token = Some(token.into());
+        self
+    }
+
+    pub async fn create_prompt(
+        &self,
+        workspace: &str,
+        prompt: &NewPrompt,
+    ) -> Result<Prompt, Error> {
+        let url = format!("{}/api/v1/workspaces/{}/prompts", self.base_url, workspace);
+        self.send(self.http.post(url).json(prompt)).await
+    }
+
+    /// Start accepting feedback again (also unarchives)
+    pub async fn open_prompt(&self, workspace: &str, prompt_id: &str) -> Result<Prompt, Error> {
+        self.prompt_action(workspace, prompt_id, "open").await
+    }
+
+    /// Stop accepting feedback; the prompt and its responses stay visible
+    pub async fn close_prompt(&self, workspace: &str, prompt_id: &str) -> Result<Prompt, Error> {
+        self.prompt_action(workspace, prompt_id, "close").await
+    }
+
+    /// Close the prompt and put it away
+    pub async fn archive_prompt(&self, workspace: &str, prompt_id: &str) -> Result<Prompt, Error> {
+        self.prompt_action(workspace, prompt_id, "archive").await
+    }
+
+    /// Create an open copy of a prompt with its settings, question bank
+    /// fields and notification rules, but none of its feedback
+    pub async fn duplicate_prompt(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+        overrides: &DuplicatePrompt,
+    ) -> Result<Prompt, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/duplicate",
+            self.base_url, workspace, prompt_id
+        );
+        self.send(self.http.post(url).json(overrides)).await
+    }
+
+    /// Responses per day for the past year, oldest first, including quiet days
+    pub async fn prompt_calendar(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+    ) -> Result<Vec<CalendarDay>, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/calendar",
+            self.base_url, workspace, prompt_id
+        );
+        self.send(self.http.get(url)).await
+    }
+
+    /// Sign a per-respondent form link whose answers for the given field
+    /// IDs are shown read-only and stored as verified
+    pub async fn create_prefill_link(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+        values: &HashMap<String, String>,
+    ) -> Result<PrefillLink, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/prefill-links",
+            self.base_url, workspace, prompt_id
+        );
+        let body = serde_json::json!({ "values": values });
+        self.send(self.http.post(url).json(&body)).await
+    }
+
+    /// The prompt's translations, by language code
+    pub async fn prompt_translations(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+    ) -> Result<Vec<PromptTranslation>, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/translations",
+            self.base_url, workspace, prompt_id
+        );
+        self.send(self.http.get(url)).await
+    }
+
+    /// Add the prompt's translation into `language`, or replace it
+    pub async fn set_prompt_translation(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+        language: &str,
+        translation: &PromptTranslation,
+    ) -> Result<PromptTranslation, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/translations/{}",
+            self.base_url, workspace, prompt_id, language
+        );
+        self.send(self.http.put(url).json(translation)).await
+    }
+
+    async fn prompt_action(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+        action: &str,
+    ) -> Result<Prompt, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/{}",
+            self.base_url, workspace, prompt_id, action
+        );
+        self.send(self.http.post(url)).await
+    }
+
+    pub async fn submit_feedback(
+        &self,
+        prompt_id: &str,
+        feedback: &NewFeedback,
+    ) -> Result<Feedback, Error> {
+        let url = format!("{}/api/v1/prompts/{}/feedback", self.base_url, prompt_id);
+        self.send(self.http.post(url).json(feedback)).await
+    }
+
+    /// All feedback for a prompt, newest first
+    pub async fn list_feedback(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+    ) -> Result<Vec<Feedback>, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/feedback",
+            self.base_url, workspace, prompt_id
+        );
+        self.send(self.http.get(url)).await
+    }
+
+    /// Feedback created or edited after `since` (RFC 3339 or `YYYY-MM-DD`),
+    /// for incremental syncs
+    pub async fn list_feedback_since(
+        &self,
+        workspace: &str,
+        prompt_id: &str,
+        since: &str,
+    ) -> Result<Vec<Feedback>, Error> {
+        let url = format!(
+            "{}/api/v1/workspaces/{}/prompts/{}/feedback",
+            self.base_url, workspace, prompt_id
+        );
+        self.send(self.http.get(url).query(&[("since", since)]))
+            .await
+    }
+
+    async fn send<T: DeserializeOwned>(
+        &self,
+        mut request: reqwest::RequestBuilder,
+    ) -> Result<T, Error> {
+        if let Some(token) = &self.token {
+            request = request.bearer_auth(token);
+        }
+        let response = request.send().await?;
+        let status = response.status();
+        if status.is_success() {
+            return Ok(response.json().await?);
+        }
+
+        // Fall back to a generic error if the body isn't an envelope, e.g. from a proxy
+        let body = response.text().await?;
+        let error = serde_json::from_str::<ErrorEnvelope>(&body)
+            .map(|envelope| envelope.error)
+            .unwrap_or_else(|_| ApiError {
+                code: "http_error".to_string(),
+                message: body,
+                fields: Vec::new(),
+                request_id: None,
+            });
+        Err(Error::Api {
+            status: status.as_u16(),
+            error,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        extract::Path,
+        http::StatusCode,
+        routing::{get, post},
+        Json, Router,
+    };
+
+    async fn spawn_server(app: Router) -> String {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        format!("http://{}/", addr)
+    }
+
+    fn feedback(prompt_id: &str, content: &str) -> Feedback {
+        Feedback {
+            id: "fb-1".to_string(),
+            prompt_id: prompt_id.to_string(),
+            content: content.to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            respondent_email: None,
+            updated_at: None,
+            cycle_id: None,
+            sentiment_score: None,
+            sentiment_label: None,
+            status: "new".to_string(),
+            nps_score: None,
+            reaction: None,
+            tags: Vec::new(),
+            answers: Vec::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_requests_hit_versioned_routes() {
+        let app = Router::new()
+            .route(
+                "/api/v1/workspaces/:workspace/prompts",
+                post(
+                    |Path(workspace): Path<String>, Json(new): Json<NewPrompt>| async move {
+                        (
+                            StatusCode::CREATED,
+                            Json(Prompt {
+                                id: "p-1".to_string(),
+                                workspace_id: workspace,
+                                title: new.title,
+                                description: new.description,
+                                created_at: "2024-01-01T00:00:00+00:00".to_string(),
+                                webhook_url: None,
+                                merge_responses: new.merge_responses,
+                                recurrence: None,
+                                content_min_length: new.content_min_length,
+                                content_max_length: new.content_max_length,
+                                content_pattern: new.content_pattern,
+                                closed_at: None,
+                                archived_at: None,
+                                public_board: new.public_board,
+                                send_receipts: new.send_receipts,
+                                suspended_at: None,
+                                language: new.language,
+                                slug: new.slug,
+                                thank_you_message: new.thank_you_message,
+                                redirect_url: new.redirect_url,
+                                publish_at: new.publish_at,
+                                kind: new.kind.unwrap_or_else(|| "text".to_string()),
+                                retention_days: new.retention_days,
+                                max_responses: new.max_responses,
+                            }),
+                        )
+                    },
+                ),
+            )
+            .route(
+                "/api/v1/prompts/:id/feedback",
+                post(
+                    |Path(id): Path<String>, Json(new): Json<NewFeedback>| async move {
+                        Json(feedback(&id, &new.content))
+                    },
+                ),
+            )
+            .route(
+                "/api/v1/workspaces/:workspace/prompts/:id/feedback",
+                get(|Path((_, id)): Path<(String, String)>| async move {
+                    Json(vec![feedback(&id, "listed")])
+                }),
+            );
+        let client = Client::new(spawn_server(app).await);
+
+        let prompt = client
+            .create_prompt("team", &NewPrompt::new("Title", "Desc"))
+            .await
+            .unwrap();
+        assert_eq!(prompt.workspace_id, "team");
+
+        let submitted = client
+            .submit_feedback(&prompt.id, &NewFeedback::new("Nice"))
+            .await
+            .unwrap();
+        assert_eq!(submitted.prompt_id, "p-1");
+        assert_eq!(submitted.content, "Nice");
+
+        let listed = client.list_feedback("team", "p-1").await.unwrap();
+        assert_eq!(listed[0].content, "listed");
+    }
+
+    #[tokio::test]
+    async fn test_error_envelope_is_decoded() {
+        let app = Router::new().route(
+            "/api/v1/prompts/:id/feedback",
+            post(|| async {
+                (
+                    StatusCode::UNPROCESSABLE_ENTITY,
+                    Json(serde_json::json!({
+                        "error": {
+                            "code": "validation_failed",
+                            "message": "One or more fields are invalid",
+                            "fields": [{"field": "content", "message": "must not be empty"}],
+                            "request_id": "req-1"
+                        }
+                    })),
+                )
+            }),
+        );
+        let client = Client::new(spawn_server(app).await);
+
+        match client.submit_feedback("p-1", &NewFeedback::new("")).await {
+            Err(Error::Api { status, error }) => {
+                assert_eq!(status, 422);
+                assert_eq!(error.code, "validation_failed");
+                assert_eq!(error.fields[0].field, "content");
+                assert_eq!(error.request_id.as_deref(), Some("req-1"));
+            }
+            other => panic!("expected API error, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,118 @@
+//! Cross-origin access to the public JSON submit endpoint, so a single-page
+//! app or an in-product "send feedback" button can post straight from the
+//! browser. Off unless `CORS_ALLOWED_ORIGINS` lists the sites allowed to:
+//! a comma-separated list of origins such as `https://app.example.com`, or
+//! `*` for any site. No cookies are involved, so credentials are never
+//! allowed.
+
+use axum::http::{header, HeaderValue, Method};
+use std::time::Duration;
+use tower_http::cors::{AllowOrigin, CorsLayer};
+
+/// How long browsers may cache a preflight answer
+const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Config {
+    AnyOrigin,
+    /// Normalized `scheme://host[:port]` origins
+    Origins(Vec<String>),
+}
+
+impl Config {
+    pub fn from_env() -> Option<Self> {
+        let spec = std::env::var("CORS_ALLOWED_ORIGINS").ok()?;
+        match Config::parse(&spec) {
+            Ok(config) => config,
+            Err(e) => {
+                eprintln!("CORS disabled: CORS_ALLOWED_ORIGINS {}", e);
+                None
+            }
+        }
+    }
+
+    /// None for an empty list
+    pub fn parse(spec: &str) -> Result<Option<Self>, String> {
+        let entries: Vec<&str> = spec
+            .split(',')
+            .map(str::trim)
+            .filter(|e| !e.is_empty())
+            .collect();
+        if entries.is_empty() {
+            return Ok(None);
+        }
+        if entries.contains(&"*") {
+            return Ok(Some(Config::AnyOrigin));
+        }
+        let origins = entries
+            .into_iter()
+            .map(|entry| {
+                normalize_origin(entry).ok_or_else(|| format!("has a bad origin: {}", entry))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(Some(Config::Origins(origins)))
+    }
+
+    pub fn layer(&self) -> CorsLayer {
+        let allow_origin = match self {
+            Config::AnyOrigin => AllowOrigin::any(),
+            Config::Origins(origins) => AllowOrigin::list(
+                origins
+                    .iter()
+                    .filter_map(|origin| HeaderValue::from_str(origin).ok()),
+            ),
+        };
+        CorsLayer::new()
+            .allow_origin(allow_origin)
+            .allow_methods([Method::POST])
+            .allow_headers([header::CONTENT_TYPE])
+            .max_age(MAX_AGE)
+    }
+}
+
+/// An origin the way browsers send it: lowercase scheme and host, the port
+/// only when it isn't the default, and no path
+fn normalize_origin(entry: &str) -> Option<String> {
+    let url = reqwest::Url::parse(entry).ok()?;
+    let path_is_root = url.path() == "/" || url.path().is_empty();
+    if !matches!(url.scheme(), "http" | "https")
+        || !path_is_root
+        || url.query().is_some()
+        || url.fragment().is_some()
+        || !url.username().is_empty()
+    {
+        return None;
+    }
+    let host = url.host_str()?;
+    Some(match url.port() {
+        Some(port) => format!("{}://{}:{}", url.scheme(), host, port),
+        None => format!("{}://{}", url.scheme(), host),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(Config::parse(" , "), Ok(None));
+        assert_eq!(
+            Config::parse("https://a.example.com, *"),
+            Ok(Some(Config::AnyOrigin))
+        );
+        assert_eq!(
+            Config::parse(
+                "https://App.Example.com/, http://localhost:5173,https://b.example.com:443"
+            ),
+            Ok(Some(Config::Origins(vec![
+                "https://app.example.com".to_string(),
+                "http://localhost:5173".to_string(),
+                "https://b.example.com".to_string(),
+            ])))
+        );
+        assert!(Config::parse("https://a.example.com/app").is_err());
+        assert!(Config::parse("a.example.com").is_err());
+        assert!(Config::parse("ftp://a.example.com").is_err());
+    }
+}
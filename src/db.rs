@@ -1,25 +1,1073 @@
-use chrono::Utc;
+use chrono::{DateTime, Duration, Months, Utc};
 use serde::{Deserialize, Serialize};
-use sqlx::{sqlite::SqlitePool, FromRow};
+use sqlx::{
+    sqlite::{
+        Sqlite, SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePool,
+        SqlitePoolOptions, SqliteSynchronous,
+    },
+    FromRow, QueryBuilder,
+};
 
-#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+use crate::keywords;
+use crate::notify;
+use crate::reactions::Reaction;
+use crate::rules::FieldRules;
+use crate::sentiment;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+/// Workspace that data from before multi-workspace support belongs to
+pub const DEFAULT_WORKSPACE_ID: &str = "default";
+
+/// A team's isolated set of prompts and feedback
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
+pub struct Workspace {
+    pub id: String,
+    /// URL-safe name used in admin routes, e.g. `/admin/design-team`
+    pub slug: String,
+    pub name: String,
+    pub created_at: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
 pub struct Prompt {
     pub id: String,
+    pub workspace_id: String,
     pub title: String,
     pub description: String,
     pub created_at: String,
+    pub webhook_url: Option<String>,
+    /// Never serialized, so it can't leak through an API response; archives
+    /// carry it separately
+    #[serde(skip_serializing)]
+    pub webhook_secret: Option<String>,
+    /// When set, a known respondent's new submission replaces their previous one
+    pub merge_responses: bool,
+    /// `weekly` or `monthly` for pulse surveys that reopen as a new cycle each period
+    pub recurrence: Option<String>,
+    pub content_min_length: Option<i64>,
+    pub content_max_length: Option<i64>,
+    pub content_pattern: Option<String>,
+    /// Set while the prompt refuses new feedback
+    #[serde(default)]
+    pub closed_at: Option<String>,
+    /// Set when the prompt is put away; archived prompts are closed too
+    #[serde(default)]
+    pub archived_at: Option<String>,
+    /// When set, respondents can see and upvote each other's feedback
+    #[serde(default)]
+    pub public_board: bool,
+    /// When set, respondents who leave an email get a copy of their submission
+    #[serde(default)]
+    pub send_receipts: bool,
+    /// Set while taken down pending abuse review; only owners can lift it
+    #[serde(default)]
+    pub suspended_at: Option<String>,
+    /// Language code the public form is always shown in; `None` follows the
+    /// respondent's browser
+    #[serde(default)]
+    pub language: Option<String>,
+    /// Unique short name for the `/f/:slug` form URL
+    #[serde(default)]
+    pub slug: Option<String>,
+    /// Markdown shown after a submission instead of the standard thanks
+    #[serde(default)]
+    pub thank_you_message: Option<String>,
+    /// Where respondents are sent after submitting; takes precedence over
+    /// `thank_you_message`
+    #[serde(default)]
+    pub redirect_url: Option<String>,
+    /// Code respondents must enter before the form takes their feedback;
+    /// never serialized, like `webhook_secret`
+    #[serde(default, skip_serializing)]
+    pub access_code: Option<String>,
+    /// UTC time before which the form isn't open yet
+    #[serde(default)]
+    pub publish_at: Option<String>,
+    /// One of `PromptKind`: what the form asks for
+    #[serde(default = "text_kind")]
+    pub kind: String,
+    /// Feedback older than this many days is purged; see `retention`
+    #[serde(default)]
+    pub retention_days: Option<i64>,
+    /// The form closes as full once this many responses are in
+    #[serde(default)]
+    pub max_responses: Option<i64>,
+    /// When set, the form only opens through one of the prompt's invites
+    #[serde(default)]
+    pub invite_only: bool,
+}
+
+impl Prompt {
+    pub fn recurrence(&self) -> Option<Recurrence> {
+        self.recurrence.as_deref().and_then(Recurrence::parse)
+    }
+
+    pub fn kind(&self) -> PromptKind {
+        PromptKind::parse(&self.kind).unwrap_or_default()
+    }
+
+    /// Asks for a 0-10 score, with the text as an optional comment
+    pub fn is_nps(&self) -> bool {
+        self.kind() == PromptKind::Nps
+    }
+
+    /// Asks for a one-tap emoji reaction, with the text as an optional comment
+    pub fn is_reactions(&self) -> bool {
+        self.kind() == PromptKind::Reactions
+    }
+
+    pub fn status(&self) -> PromptStatus {
+        if self.archived_at.is_some() {
+            PromptStatus::Archived
+        } else if self.closed_at.is_some() {
+            PromptStatus::Closed
+        } else {
+            PromptStatus::Open
+        }
+    }
+
+    /// Accepting feedback: neither closed nor suspended
+    pub fn is_open(&self) -> bool {
+        self.status() == PromptStatus::Open && !self.is_suspended() && !self.is_scheduled()
+    }
+
+    /// When the form opens, if that's still to come
+    pub fn scheduled_until(&self) -> Option<DateTime<Utc>> {
+        let publish_at = DateTime::parse_from_rfc3339(self.publish_at.as_deref()?).ok()?;
+        Some(publish_at.with_timezone(&Utc)).filter(|t| *t > Utc::now())
+    }
+
+    pub fn is_scheduled(&self) -> bool {
+        self.scheduled_until().is_some()
+    }
+
+    /// `publish_at` for a `datetime-local` input, in UTC
+    pub fn publish_at_input(&self) -> String {
+        self.publish_at
+            .as_deref()
+            .and_then(|t| DateTime::parse_from_rfc3339(t).ok())
+            .map(|t| t.with_timezone(&Utc).format("%Y-%m-%dT%H:%M").to_string())
+            .unwrap_or_default()
+    }
+
+    pub fn is_suspended(&self) -> bool {
+        self.suspended_at.is_some()
+    }
+
+    /// Constraints on the feedback text
+    pub fn content_rules(&self) -> FieldRules {
+        FieldRules {
+            min_length: self.content_min_length,
+            max_length: self.content_max_length,
+            pattern: self.content_pattern.clone(),
+        }
+    }
+}
+
+/// What a prompt's form asks respondents for
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum PromptKind {
+    /// Free-text feedback
+    #[default]
+    Text,
+    /// Net Promoter Score: "how likely are you to recommend us", 0-10
+    Nps,
+    /// One tap on an emoji face, for kiosks; see `reactions::Reaction`
+    Reactions,
+}
+
+impl PromptKind {
+    pub const ALL: [PromptKind; 3] = [PromptKind::Text, PromptKind::Nps, PromptKind::Reactions];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "text" => Some(PromptKind::Text),
+            "nps" => Some(PromptKind::Nps),
+            "reactions" => Some(PromptKind::Reactions),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptKind::Text => "text",
+            PromptKind::Nps => "nps",
+            PromptKind::Reactions => "reactions",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PromptKind::Text => "Written feedback",
+            PromptKind::Nps => "Net Promoter Score (0-10 with a comment)",
+            PromptKind::Reactions => "Emoji reactions (one tap, with an optional comment)",
+        }
+    }
+
+    /// The feedback text is a comment on a structured answer, and may be empty
+    pub fn comment_optional(&self) -> bool {
+        *self != PromptKind::Text
+    }
+}
+
+fn text_kind() -> String {
+    PromptKind::Text.as_str().to_string()
+}
+
+/// Where a prompt is in its lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PromptStatus {
+    /// Accepting feedback
+    Open,
+    /// Kept in the list but refusing feedback
+    Closed,
+    /// Closed and put away
+    Archived,
+}
+
+impl PromptStatus {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "open" => Some(PromptStatus::Open),
+            "closed" => Some(PromptStatus::Closed),
+            "archived" => Some(PromptStatus::Archived),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PromptStatus::Open => "open",
+            PromptStatus::Closed => "closed",
+            PromptStatus::Archived => "archived",
+        }
+    }
+}
+
+/// Where a feedback entry is in the team's triage
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum FeedbackStatus {
+    /// Not looked at yet
+    #[default]
+    New,
+    Reviewed,
+    /// Something was done about it
+    Actioned,
+    /// Read and set aside
+    Dismissed,
+}
+
+impl FeedbackStatus {
+    pub const ALL: [FeedbackStatus; 4] = [
+        FeedbackStatus::New,
+        FeedbackStatus::Reviewed,
+        FeedbackStatus::Actioned,
+        FeedbackStatus::Dismissed,
+    ];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "new" => Some(FeedbackStatus::New),
+            "reviewed" => Some(FeedbackStatus::Reviewed),
+            "actioned" => Some(FeedbackStatus::Actioned),
+            "dismissed" => Some(FeedbackStatus::Dismissed),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FeedbackStatus::New => "new",
+            FeedbackStatus::Reviewed => "reviewed",
+            FeedbackStatus::Actioned => "actioned",
+            FeedbackStatus::Dismissed => "dismissed",
+        }
+    }
+}
+
+fn new_status() -> String {
+    FeedbackStatus::New.as_str().to_string()
+}
+
+/// Optional settings supplied when creating a prompt
+#[derive(Debug, Clone, Default)]
+pub struct PromptOptions {
+    pub webhook_url: Option<String>,
+    pub merge_responses: bool,
+    pub recurrence: Option<Recurrence>,
+    pub content_rules: FieldRules,
+    pub public_board: bool,
+    pub send_receipts: bool,
+    pub language: Option<String>,
+    /// Must be normalized and not taken
+    pub slug: Option<String>,
+    pub thank_you_message: Option<String>,
+    /// Must be an absolute http(s) URL
+    pub redirect_url: Option<String>,
+    /// Must be normalized with `access::normalize`
+    pub access_code: Option<String>,
+    /// Normalized with `parse_publish_at`
+    pub publish_at: Option<String>,
+    pub kind: PromptKind,
+    /// Checked with `retention::parse_days`
+    pub retention_days: Option<i64>,
+    /// At least 1
+    pub max_responses: Option<i64>,
+}
+
+const PROMPT_COLUMNS: &str =
+    "id, workspace_id, title, description, created_at, webhook_url, webhook_secret, merge_responses, recurrence, content_min_length, content_max_length, content_pattern, closed_at, archived_at, public_board, send_receipts, suspended_at, language, slug, thank_you_message, redirect_url, access_code, publish_at, kind, retention_days, max_responses, invite_only";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Recurrence {
+    Weekly,
+    Monthly,
+}
+
+impl Recurrence {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "weekly" => Some(Recurrence::Weekly),
+            "monthly" => Some(Recurrence::Monthly),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Recurrence::Weekly => "weekly",
+            Recurrence::Monthly => "monthly",
+        }
+    }
+
+    /// Start of the period `n` periods after the one that starts at
+    /// `anchor`. Counting from the first period keeps monthly cycles on its
+    /// day: month ends clamp, e.g. Jan 31 -> Feb 29, then back to Mar 31.
+    pub fn nth(&self, anchor: DateTime<Utc>, n: u32) -> DateTime<Utc> {
+        match self {
+            Recurrence::Weekly => anchor + Duration::weeks(i64::from(n)),
+            Recurrence::Monthly => anchor.checked_add_months(Months::new(n)).unwrap_or(anchor),
+        }
+    }
+}
+
+/// What an admin account may do; each role includes everything below it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Read prompts and feedback
+    Viewer,
+    /// Also create prompts, tag feedback and import archives
+    Editor,
+    /// Also delete prompts, manage workspaces and users, and download backups
+    Owner,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Owner, Role::Editor, Role::Viewer];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "owner" => Some(Role::Owner),
+            "editor" => Some(Role::Editor),
+            "viewer" => Some(Role::Viewer),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Owner => "owner",
+            Role::Editor => "editor",
+            Role::Viewer => "viewer",
+        }
+    }
+}
+
+/// An admin account
+#[derive(Debug, Clone, Default, Serialize, FromRow)]
+pub struct User {
+    pub id: String,
+    pub email: String,
+    #[serde(skip)]
+    pub password_hash: String,
+    pub role: String,
+    pub created_at: String,
+    /// `sub` claim of the OIDC identity linked to this account
+    pub oidc_subject: Option<String>,
+    /// Emailed the scheduled digest of new feedback
+    pub digest_subscribed: bool,
+}
+
+impl User {
+    /// Unrecognised roles get the least access
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role).unwrap_or(Role::Viewer)
+    }
+}
+
+/// A bearer token for the JSON API; only a digest of the secret is stored
+#[derive(Debug, Clone, Default, Serialize, FromRow)]
+pub struct ApiToken {
+    pub id: String,
+    /// What it's for, e.g. "CI export job"
+    pub name: String,
+    pub role: String,
+    pub created_at: String,
+    pub last_used_at: Option<String>,
+    pub revoked_at: Option<String>,
+    /// When set, the token only works on this workspace's routes
+    pub workspace_id: Option<String>,
 }
 
+impl ApiToken {
+    pub fn role(&self) -> Role {
+        Role::parse(&self.role).unwrap_or(Role::Viewer)
+    }
+}
+
+const API_TOKEN_COLUMNS: &str =
+    "id, name, role, created_at, last_used_at, revoked_at, workspace_id";
+
+const USER_COLUMNS: &str =
+    "id, email, password_hash, role, created_at, oidc_subject, digest_subscribed";
+
+/// One period of a recurring prompt; feedback submitted during it belongs to it
 #[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Cycle {
+    pub id: String,
+    pub prompt_id: String,
+    pub number: i64,
+    pub starts_at: String,
+    pub ends_at: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct CycleSummary {
+    pub id: String,
+    pub number: i64,
+    pub starts_at: String,
+    pub ends_at: String,
+    pub response_count: i64,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, FromRow)]
 pub struct Feedback {
     pub id: String,
     pub prompt_id: String,
     pub content: String,
     pub created_at: String,
+    pub respondent_email: Option<String>,
+    pub updated_at: Option<String>,
+    pub cycle_id: Option<String>,
+    pub sentiment_score: Option<f64>,
+    pub sentiment_label: Option<String>,
+    /// Triage state, one of `FeedbackStatus`
+    #[serde(default = "new_status")]
+    pub status: String,
+    /// 0-10 answer on an NPS prompt
+    #[serde(default)]
+    pub nps_score: Option<i64>,
+    /// `reactions::Reaction` key on a reactions prompt
+    #[serde(default)]
+    pub reaction: Option<String>,
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Answers to the prompt's question bank fields, in the prompt's order
+    #[sqlx(skip)]
+    #[serde(default)]
+    pub answers: Vec<Answer>,
+    /// Identical entries on other prompts, filled in by `attach_duplicates`
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub duplicates: Vec<Duplicate>,
+    /// Admin replies, oldest first
+    #[sqlx(skip)]
+    #[serde(skip)]
+    pub replies: Vec<Reply>,
+}
+
+/// An admin's response to a feedback entry
+#[derive(Debug, Clone, FromRow)]
+pub struct Reply {
+    pub id: String,
+    pub feedback_id: String,
+    /// Email of the admin who wrote it; None when written without an account
+    pub author: Option<String>,
+    pub content: String,
+    pub created_at: String,
+    /// Set once the reply has been mailed to the respondent
+    pub emailed_at: Option<String>,
+}
+
+/// Another entry with the same normalized content, posted to a different prompt
+#[derive(Debug, Clone)]
+pub struct Duplicate {
+    pub feedback_id: String,
+    pub prompt_id: String,
+    pub prompt_title: String,
+    pub workspace_slug: String,
+}
+
+/// A respondent's answer to a question bank field
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Answer {
+    pub field_id: String,
+    /// The field's current label, so edits show up on old answers too
+    pub label: String,
+    pub value: String,
+    /// Came from a signed prefill link rather than the respondent
+    #[serde(default)]
+    pub verified: bool,
+}
+
+/// Optional details supplied with a feedback submission
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackOptions {
+    pub respondent_email: Option<String>,
+    /// `respondent_email` came from a signed prefill link rather than the
+    /// form, so it may replace the respondent's earlier answer
+    pub email_verified: bool,
+    pub cycle_id: Option<String>,
+    /// Quarantine the entry as spam, for this reason
+    pub spam_reason: Option<String>,
+    /// `(field_id, value)` for each answered question bank field
+    pub answers: Vec<(String, String)>,
+    /// Fields among `answers` whose values came from a signed prefill link
+    pub verified_fields: Vec<String>,
+    /// 0-10, on NPS prompts
+    pub nps_score: Option<i64>,
+    /// On reactions prompts
+    pub reaction: Option<Reaction>,
+}
+
+/// How a question bank field is rendered on the feedback form
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FieldKind {
+    /// Single-line input
+    Short,
+    /// Multi-line textarea
+    Long,
+}
+
+impl FieldKind {
+    pub const ALL: [FieldKind; 2] = [FieldKind::Short, FieldKind::Long];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "short" => Some(FieldKind::Short),
+            "long" => Some(FieldKind::Long),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            FieldKind::Short => "short",
+            FieldKind::Long => "long",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            FieldKind::Short => "Short answer",
+            FieldKind::Long => "Long answer",
+        }
+    }
+}
+
+/// A reusable question in a workspace's question bank. Prompts reference
+/// fields rather than copying them, so an edit shows up everywhere.
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct Field {
+    pub id: String,
+    pub workspace_id: String,
+    pub label: String,
+    pub help_text: Option<String>,
+    /// `short` or `long`
+    pub kind: String,
+    pub required: bool,
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    pub pattern: Option<String>,
+    pub created_at: String,
+    pub updated_at: Option<String>,
+}
+
+impl Field {
+    pub fn kind(&self) -> FieldKind {
+        FieldKind::parse(&self.kind).unwrap_or(FieldKind::Short)
+    }
+
+    pub fn rules(&self) -> FieldRules {
+        FieldRules {
+            min_length: self.min_length,
+            max_length: self.max_length,
+            pattern: self.pattern.clone(),
+        }
+    }
+}
+
+/// A field's place on a prompt's form
+#[derive(Debug, Clone, Serialize, Deserialize, FromRow)]
+pub struct PromptField {
+    pub prompt_id: String,
+    pub field_id: String,
+    pub position: i64,
+}
+
+/// A prompt's title, description and field labels in another language
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PromptTranslation {
+    /// One of `i18n::LANGUAGES`
+    pub language: String,
+    pub title: String,
+    /// Empty to keep the original description
+    #[serde(default)]
+    pub description: String,
+    /// Labels keyed by field ID; fields left out keep their original label
+    #[serde(default)]
+    pub field_labels: BTreeMap<String, String>,
+    #[serde(default)]
+    pub updated_at: String,
+}
+
+impl PromptTranslation {
+    /// Swap the translated text into a prompt and its fields
+    pub fn apply(&self, prompt: &mut Prompt, fields: &mut [Field]) {
+        prompt.title = self.title.clone();
+        if !self.description.is_empty() {
+            prompt.description = self.description.clone();
+        }
+        for field in fields {
+            if let Some(label) = self.field_labels.get(&field.id) {
+                field.label = label.clone();
+            }
+        }
+    }
+}
+
+/// `prompt_translations` row; field labels are stored as a JSON object
+#[derive(FromRow)]
+struct PromptTranslationRow {
+    language: String,
+    title: String,
+    description: String,
+    field_labels: String,
+    updated_at: String,
+}
+
+impl From<PromptTranslationRow> for PromptTranslation {
+    fn from(row: PromptTranslationRow) -> Self {
+        PromptTranslation {
+            language: row.language,
+            title: row.title,
+            description: row.description,
+            field_labels: serde_json::from_str(&row.field_labels).unwrap_or_default(),
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+/// A saved shape for spreadsheet exports; see `export`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportTemplate {
+    pub id: String,
+    pub workspace_id: String,
+    pub name: String,
+    /// In spreadsheet order
+    pub columns: Vec<ExportColumn>,
+    /// strftime pattern for timestamp columns
+    pub date_format: String,
+    pub created_at: String,
+    pub updated_at: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExportColumn {
+    /// `export::Source` key, e.g. `created_at` or `answer:<field_id>`
+    pub source: String,
+    /// Empty for the source's default header
+    #[serde(default)]
+    pub header: String,
+}
+
+/// `export_templates` row; columns are stored as a JSON array
+#[derive(FromRow)]
+struct ExportTemplateRow {
+    id: String,
+    workspace_id: String,
+    name: String,
+    columns: String,
+    date_format: String,
+    created_at: String,
+    updated_at: String,
+}
+
+impl From<ExportTemplateRow> for ExportTemplate {
+    fn from(row: ExportTemplateRow) -> Self {
+        ExportTemplate {
+            id: row.id,
+            workspace_id: row.workspace_id,
+            name: row.name,
+            columns: serde_json::from_str(&row.columns).unwrap_or_default(),
+            date_format: row.date_format,
+            created_at: row.created_at,
+            updated_at: row.updated_at,
+        }
+    }
+}
+
+const EXPORT_TEMPLATE_COLUMNS: &str =
+    "id, workspace_id, name, columns, date_format, created_at, updated_at";
+
+/// One of a prompt's notification routes; see `notify`
+#[derive(Debug, Clone, FromRow)]
+pub struct NotificationRule {
+    pub id: String,
+    pub prompt_id: String,
+    /// Rules are checked in ascending order
+    pub position: i64,
+    /// `notify::Condition::kind`
+    pub condition: String,
+    pub field_id: Option<String>,
+    pub operand: Option<String>,
+    /// `immediate` or `digest`
+    pub delivery: String,
+    /// Chat incoming webhook the rule posts to
+    pub target_url: String,
+    pub created_at: String,
+    pub last_digest_at: Option<String>,
+}
+
+impl NotificationRule {
+    /// None if the stored parts no longer make a condition
+    pub fn condition(&self) -> Option<notify::Condition> {
+        notify::Condition::from_parts(
+            &self.condition,
+            self.field_id.as_deref(),
+            self.operand.as_deref(),
+        )
+        .ok()
+    }
+
+    pub fn delivery(&self) -> notify::Delivery {
+        notify::Delivery::parse(&self.delivery).unwrap_or(notify::Delivery::Immediate)
+    }
+}
+
+const NOTIFICATION_RULE_COLUMNS: &str = "id, prompt_id, position, condition, field_id, operand, delivery, target_url, created_at, last_digest_at";
+
+/// Settings for creating or editing a question bank field
+#[derive(Debug, Clone)]
+pub struct FieldSpec {
+    pub label: String,
+    pub help_text: Option<String>,
+    pub kind: FieldKind,
+    pub required: bool,
+    pub rules: FieldRules,
+}
+
+/// A field with how widely it's used
+#[derive(Debug, Clone, FromRow)]
+pub struct FieldUsage {
+    #[sqlx(flatten)]
+    pub field: Field,
+    pub prompt_count: i64,
+    pub answer_count: i64,
+}
+
+const FIELD_COLUMNS: &str = "id, workspace_id, label, help_text, kind, required, min_length, max_length, pattern, created_at, updated_at";
+
+const FEEDBACK_COLUMNS: &str = "id, prompt_id, content, created_at, respondent_email, updated_at, cycle_id, sentiment_score, sentiment_label, status, nps_score, reaction";
+
+/// Narrows the feedback listed for a prompt; unset fields don't filter
+#[derive(Debug, Clone, Default)]
+pub struct FeedbackFilter {
+    pub cycle_id: Option<String>,
+    pub tag: Option<String>,
+    pub sentiment: Option<sentiment::Label>,
+    pub status: Option<FeedbackStatus>,
+    /// Only entries created or edited strictly after this RFC 3339 UTC
+    /// timestamp, as produced by `parse_since`
+    pub since: Option<String>,
+    /// Only entries created at or after this RFC 3339 UTC timestamp
+    pub created_from: Option<String>,
+    /// Only entries created strictly before this RFC 3339 UTC timestamp, as
+    /// produced by `parse_until`
+    pub created_before: Option<String>,
+    /// Only entries whose text contains this, compared after `keywords::fold`
+    /// so top-term links find the entries they were counted from
+    pub contains: Option<String>,
+    pub rating: Option<RatingRange>,
+    pub sort: SortOrder,
+    /// Only the first this many entries in `sort` order
+    pub limit: Option<i64>,
+}
+
+/// Entries whose numeric answer to a question bank field is within bounds;
+/// entries that didn't answer it are left out
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RatingRange {
+    pub field_id: String,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+/// Order of a feedback list by when entries were submitted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SortOrder {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+impl SortOrder {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "newest" => Some(SortOrder::Newest),
+            "oldest" => Some(SortOrder::Oldest),
+            _ => None,
+        }
+    }
+}
+
+/// Normalize a `?since=` value (RFC 3339, or a bare `YYYY-MM-DD` meaning
+/// midnight UTC) to the UTC RFC 3339 form stored timestamps use, so the two
+/// compare correctly as strings
+pub fn parse_since(value: &str) -> Option<String> {
+    let value = value.trim();
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()
+                .and_then(|d| d.and_hms_opt(0, 0, 0))
+                .map(|t| t.and_utc())
+        })?;
+    Some(parsed.to_rfc3339())
+}
+
+/// Normalize a publish time: RFC 3339, or a `datetime-local` input value
+/// (`YYYY-MM-DDTHH:MM`, seconds optional) taken as UTC
+pub fn parse_publish_at(value: &str) -> Option<String> {
+    let value = value.trim();
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|t| t.with_timezone(&Utc))
+        .ok()
+        .or_else(|| {
+            ["%Y-%m-%dT%H:%M", "%Y-%m-%dT%H:%M:%S"]
+                .iter()
+                .find_map(|format| chrono::NaiveDateTime::parse_from_str(value, format).ok())
+                .map(|t| t.and_utc())
+        })?;
+    Some(parsed.to_rfc3339())
+}
+
+/// Normalize the end of a date range like `parse_since`, except that a bare
+/// `YYYY-MM-DD` means the end of that day, so the range includes it
+pub fn parse_until(value: &str) -> Option<String> {
+    let value = value.trim();
+    match chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        Ok(date) => Some(
+            date.succ_opt()?
+                .and_hms_opt(0, 0, 0)?
+                .and_utc()
+                .to_rfc3339(),
+        ),
+        Err(_) => parse_since(value),
+    }
+}
+
+/// How many of a prompt's entries are in each triage state
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct StatusCounts {
+    pub new: i64,
+    pub reviewed: i64,
+    pub actioned: i64,
+    pub dismissed: i64,
+}
+
+impl StatusCounts {
+    pub fn get(&self, status: &FeedbackStatus) -> i64 {
+        match status {
+            FeedbackStatus::New => self.new,
+            FeedbackStatus::Reviewed => self.reviewed,
+            FeedbackStatus::Actioned => self.actioned,
+            FeedbackStatus::Dismissed => self.dismissed,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct SentimentBreakdown {
+    pub positive: i64,
+    pub neutral: i64,
+    pub negative: i64,
+}
+
+/// Headline numbers for a prompt's public results widget
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct ResultsSummary {
+    pub responses: i64,
+    /// Responses in the last 7 days
+    pub recent: i64,
+}
+
+/// Responses received on one UTC day (`YYYY-MM-DD`)
+#[derive(Debug, Clone, FromRow)]
+pub struct DailyCount {
+    pub day: String,
+    pub count: i64,
+}
+
+/// Responses received during one hour of the day (0-23, UTC), across all days
+#[derive(Debug, Clone, FromRow)]
+pub struct HourlyCount {
+    pub hour: i64,
+    pub count: i64,
+}
+
+/// Mean of a question bank field whose answers are all numbers, e.g. a 1-5 rating
+#[derive(Debug, Clone)]
+pub struct RatingAverage {
+    pub field_id: String,
+    pub label: String,
+    pub average: f64,
+    pub responses: i64,
+}
+
+/// Why a respondent reported a form, with its label on the report page
+pub const ABUSE_REASONS: &[(&str, &str)] = &[
+    ("phishing", "Phishing or asks for passwords"),
+    ("spam", "Spam or scam"),
+    ("offensive", "Offensive or harmful content"),
+    ("other", "Something else"),
+];
+
+#[derive(Debug, Clone, FromRow)]
+pub struct AbuseReport {
+    pub id: String,
+    pub prompt_id: String,
+    /// One of `ABUSE_REASONS`
+    pub reason: String,
+    pub details: Option<String>,
+    pub reporter_email: Option<String>,
+    pub created_at: String,
+}
+
+impl AbuseReport {
+    pub fn reason_label(&self) -> &'static str {
+        ABUSE_REASONS
+            .iter()
+            .find(|(value, _)| *value == self.reason)
+            .map_or("Something else", |(_, label)| label)
+    }
+}
+
+/// A prompt awaiting abuse review: it has open reports, is suspended, or both
+#[derive(Debug, Clone)]
+pub struct AbuseCase {
+    pub prompt: Prompt,
+    pub workspace_slug: String,
+    /// Open reports, oldest first
+    pub reports: Vec<AbuseReport>,
+}
+
+/// An entry as shown on a prompt's public board: no respondent details
+#[derive(Debug, Clone, FromRow)]
+pub struct BoardEntry {
+    pub id: String,
+    pub content: String,
+    pub created_at: String,
+    pub votes: i64,
+    /// Whether the browser viewing the board has upvoted this entry
+    pub voted: bool,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct RespondentSummary {
+    pub email: String,
+    pub response_count: i64,
+    pub prompt_count: i64,
+    pub first_seen: String,
+    pub last_seen: String,
+}
+
+/// A respondent's feedback entry alongside the prompt it answered
+#[derive(Debug, Clone, FromRow)]
+pub struct RespondentEntry {
+    #[sqlx(flatten)]
+    pub feedback: Feedback,
+    pub prompt_title: String,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct TagCount {
+    pub name: String,
+    pub count: i64,
+}
+
+/// Response bytes held by a prompt and by its whole workspace
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StorageUsage {
+    pub prompt_bytes: i64,
+    pub workspace_bytes: i64,
+}
+
+/// How `init_db_with` opens the database
+#[derive(Debug, Clone)]
+pub struct PoolConfig {
+    pub max_connections: u32,
+    /// How long a write waits for another connection's lock before failing
+    /// with `database is locked`
+    pub busy_timeout: std::time::Duration,
+    /// Write-ahead logging, so reads and a write can proceed at once
+    pub wal: bool,
 }
 
+impl Default for PoolConfig {
+    fn default() -> Self {
+        PoolConfig {
+            max_connections: 10,
+            busy_timeout: std::time::Duration::from_secs(10),
+            wal: true,
+        }
+    }
+}
+
+/// Shorthand for opening a database with the default pool settings
+#[cfg(test)]
 pub async fn init_db(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
-    let pool = SqlitePool::connect(database_url).await?;
+    init_db_with(database_url, &PoolConfig::default()).await
+}
+
+pub async fn init_db_with(
+    database_url: &str,
+    config: &PoolConfig,
+) -> Result<SqlitePool, sqlx::Error> {
+    let mut options = SqliteConnectOptions::from_str(database_url)?
+        .foreign_keys(true)
+        .busy_timeout(config.busy_timeout);
+    if config.wal {
+        // In WAL mode a commit only needs the log synced, not the database
+        options = options
+            .journal_mode(SqliteJournalMode::Wal)
+            .synchronous(SqliteSynchronous::Normal);
+    }
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.max_connections)
+        .connect_with(options)
+        .await?;
+
+    // A replica or full disk can still serve reads with the schema it has
+    if !crate::readonly::probe(&pool).await? {
+        eprintln!("Database is read-only; skipping schema setup");
+        return Ok(pool);
+    }
 
     sqlx::query(
         r#"
@@ -48,241 +1096,5240 @@ pub async fn init_db(database_url: &str) -> Result<SqlitePool, sqlx::Error> {
     .execute(&pool)
     .await?;
 
-    Ok(pool)
-}
-
-pub async fn create_prompt(
-    pool: &SqlitePool,
-    title: &str,
+    add_column_if_missing(&pool, "prompts", "webhook_url", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "webhook_secret", "TEXT").await?;
+    add_column_if_missing(
+        &pool,
+        "prompts",
+        "merge_responses",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    add_column_if_missing(&pool, "feedback", "respondent_email", "TEXT").await?;
+    add_column_if_missing(&pool, "feedback", "updated_at", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "recurrence", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "content_min_length", "INTEGER").await?;
+    add_column_if_missing(&pool, "prompts", "content_max_length", "INTEGER").await?;
+    add_column_if_missing(&pool, "prompts", "content_pattern", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "embed_secret", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "feed_secret", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "prefill_secret", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "closed_at", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "archived_at", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "suspended_at", "TEXT").await?;
+    add_column_if_missing(
+        &pool,
+        "prompts",
+        "public_board",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    add_column_if_missing(
+        &pool,
+        "prompts",
+        "send_receipts",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+    add_column_if_missing(&pool, "prompts", "language", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "slug", "TEXT").await?;
+    // What respondents see after submitting
+    add_column_if_missing(&pool, "prompts", "thank_you_message", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "redirect_url", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "access_code", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "publish_at", "TEXT").await?;
+    add_column_if_missing(&pool, "prompts", "kind", "TEXT NOT NULL DEFAULT 'text'").await?;
+    add_column_if_missing(&pool, "prompts", "retention_days", "INTEGER").await?;
+    add_column_if_missing(&pool, "prompts", "max_responses", "INTEGER").await?;
+    add_column_if_missing(&pool, "feedback", "cycle_id", "TEXT").await?;
+    add_column_if_missing(&pool, "feedback", "sentiment_score", "REAL").await?;
+    add_column_if_missing(&pool, "feedback", "sentiment_label", "TEXT").await?;
+    add_column_if_missing(&pool, "feedback", "spam_reason", "TEXT").await?;
+    add_column_if_missing(&pool, "feedback", "content_hash", "TEXT").await?;
+    add_column_if_missing(&pool, "feedback", "edit_token", "TEXT").await?;
+    add_column_if_missing(&pool, "feedback", "status", "TEXT NOT NULL DEFAULT 'new'").await?;
+    add_column_if_missing(&pool, "feedback", "nps_score", "INTEGER").await?;
+    add_column_if_missing(&pool, "feedback", "reaction", "TEXT").await?;
+    if !column_exists(&pool, "prompts", "accepted_responses").await? {
+        add_column_if_missing(
+            &pool,
+            "prompts",
+            "accepted_responses",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        // Start from the entries taken before there was a counter
+        sqlx::query(
+            "UPDATE prompts SET accepted_responses = (SELECT COUNT(*) FROM feedback WHERE feedback.prompt_id = prompts.id AND spam_reason IS NULL)",
+        )
+        .execute(&pool)
+        .await?;
+    }
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS workspaces (
+            id TEXT PRIMARY KEY,
+            slug TEXT NOT NULL UNIQUE,
+            name TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+    sqlx::query(
+        "INSERT OR IGNORE INTO workspaces (id, slug, name, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(DEFAULT_WORKSPACE_ID)
+    .bind(DEFAULT_WORKSPACE_ID)
+    .bind("Default")
+    .bind(Utc::now().to_rfc3339())
+    .execute(&pool)
+    .await?;
+    add_column_if_missing(
+        &pool,
+        "prompts",
+        "workspace_id",
+        "TEXT NOT NULL DEFAULT 'default' REFERENCES workspaces(id)",
+    )
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS cycles (
+            id TEXT PRIMARY KEY,
+            prompt_id TEXT NOT NULL,
+            number INTEGER NOT NULL,
+            starts_at TEXT NOT NULL,
+            ends_at TEXT NOT NULL,
+            UNIQUE (prompt_id, number),
+            FOREIGN KEY (prompt_id) REFERENCES prompts(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS tags (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL UNIQUE
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS feedback_tags (
+            feedback_id TEXT NOT NULL,
+            tag_id TEXT NOT NULL,
+            PRIMARY KEY (feedback_id, tag_id),
+            FOREIGN KEY (feedback_id) REFERENCES feedback(id),
+            FOREIGN KEY (tag_id) REFERENCES tags(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS users (
+            id TEXT PRIMARY KEY,
+            email TEXT NOT NULL UNIQUE,
+            password_hash TEXT NOT NULL,
+            role TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    add_column_if_missing(&pool, "users", "oidc_subject", "TEXT").await?;
+    add_column_if_missing(
+        &pool,
+        "users",
+        "digest_subscribed",
+        "INTEGER NOT NULL DEFAULT 0",
+    )
+    .await?;
+
+    // Sessions are keyed by a digest of the cookie token, never the token itself
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS sessions (
+            id TEXT PRIMARY KEY,
+            user_id TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            expires_at TEXT NOT NULL,
+            FOREIGN KEY (user_id) REFERENCES users(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS api_tokens (
+            id TEXT PRIMARY KEY,
+            name TEXT NOT NULL,
+            token_digest TEXT NOT NULL UNIQUE,
+            role TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            last_used_at TEXT,
+            revoked_at TEXT
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+    // Workspace a token is limited to, if any
+    add_column_if_missing(&pool, "api_tokens", "workspace_id", "TEXT").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS email_digests (
+            id TEXT PRIMARY KEY,
+            sent_at TEXT NOT NULL,
+            responses INTEGER NOT NULL,
+            recipients INTEGER NOT NULL
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS email_bounces (
+            address TEXT PRIMARY KEY,
+            bounces INTEGER NOT NULL,
+            last_error TEXT NOT NULL,
+            last_bounced_at TEXT NOT NULL,
+            retry_after TEXT,
+            disabled_at TEXT
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // Site name, base URL and branding; a single row
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS settings (
+            id INTEGER PRIMARY KEY CHECK (id = 1),
+            site_name TEXT NOT NULL,
+            base_url TEXT,
+            organization_name TEXT,
+            logo_url TEXT,
+            accent_color TEXT,
+            footer_text TEXT,
+            updated_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+    add_column_if_missing(&pool, "settings", "retention_days", "INTEGER").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS maintenance_runs (
+            id TEXT PRIMARY KEY,
+            started_at TEXT NOT NULL,
+            finished_at TEXT NOT NULL,
+            size_before INTEGER NOT NULL,
+            size_after INTEGER NOT NULL,
+            vacuum TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS retention_runs (
+            id TEXT PRIMARY KEY,
+            ran_at TEXT NOT NULL,
+            deleted INTEGER NOT NULL,
+            purged TEXT NOT NULL DEFAULT '[]'
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS fields (
+            id TEXT PRIMARY KEY,
+            workspace_id TEXT NOT NULL,
+            label TEXT NOT NULL,
+            help_text TEXT,
+            kind TEXT NOT NULL,
+            required INTEGER NOT NULL DEFAULT 0,
+            min_length INTEGER,
+            max_length INTEGER,
+            pattern TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT,
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prompt_fields (
+            prompt_id TEXT NOT NULL,
+            field_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            PRIMARY KEY (prompt_id, field_id),
+            FOREIGN KEY (prompt_id) REFERENCES prompts(id),
+            FOREIGN KEY (field_id) REFERENCES fields(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS answers (
+            feedback_id TEXT NOT NULL,
+            field_id TEXT NOT NULL,
+            value TEXT NOT NULL,
+            PRIMARY KEY (feedback_id, field_id),
+            FOREIGN KEY (feedback_id) REFERENCES feedback(id),
+            FOREIGN KEY (field_id) REFERENCES fields(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+    // Set for answers locked by a signed prefill link
+    add_column_if_missing(&pool, "answers", "verified", "INTEGER NOT NULL DEFAULT 0").await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS prompt_translations (
+            prompt_id TEXT NOT NULL,
+            language TEXT NOT NULL,
+            title TEXT NOT NULL,
+            description TEXT NOT NULL DEFAULT '',
+            field_labels TEXT NOT NULL DEFAULT '{}',
+            updated_at TEXT NOT NULL,
+            PRIMARY KEY (prompt_id, language),
+            FOREIGN KEY (prompt_id) REFERENCES prompts(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS export_templates (
+            id TEXT PRIMARY KEY,
+            workspace_id TEXT NOT NULL,
+            name TEXT NOT NULL,
+            columns TEXT NOT NULL,
+            date_format TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS notification_rules (
+            id TEXT PRIMARY KEY,
+            prompt_id TEXT NOT NULL,
+            position INTEGER NOT NULL,
+            condition TEXT NOT NULL,
+            field_id TEXT,
+            operand TEXT,
+            delivery TEXT NOT NULL,
+            target_url TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            last_digest_at TEXT,
+            FOREIGN KEY (prompt_id) REFERENCES prompts(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // Entries waiting for their rule's next digest
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS digest_entries (
+            rule_id TEXT NOT NULL,
+            feedback_id TEXT NOT NULL,
+            PRIMARY KEY (rule_id, feedback_id),
+            FOREIGN KEY (rule_id) REFERENCES notification_rules(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS replies (
+            id TEXT PRIMARY KEY,
+            feedback_id TEXT NOT NULL,
+            author TEXT,
+            content TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            emailed_at TEXT,
+            FOREIGN KEY (feedback_id) REFERENCES feedback(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // Reports from the public "report this form" page; `reporter` is a
+    // digest of the browser's cookie, so each browser counts once per prompt
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS abuse_reports (
+            id TEXT PRIMARY KEY,
+            prompt_id TEXT NOT NULL,
+            reason TEXT NOT NULL,
+            details TEXT,
+            reporter_email TEXT,
+            reporter TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            resolved_at TEXT,
+            resolution TEXT,
+            UNIQUE (prompt_id, reporter),
+            FOREIGN KEY (prompt_id) REFERENCES prompts(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // One row per browser per upvoted entry; `voter` is a digest of the
+    // browser's voter cookie, so a cookie can't be replayed from the database
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS votes (
+            feedback_id TEXT NOT NULL,
+            voter TEXT NOT NULL,
+            created_at TEXT NOT NULL,
+            PRIMARY KEY (feedback_id, voter),
+            FOREIGN KEY (feedback_id) REFERENCES feedback(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // Form submission tokens already used, so a double-clicked or refreshed
+    // POST isn't stored twice
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS submission_tokens (
+            token TEXT PRIMARY KEY,
+            prompt_id TEXT NOT NULL,
+            created_at TEXT NOT NULL
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    // Single-use invite links; a prompt with any of these only takes
+    // submissions through one
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS invites (
+            id TEXT PRIMARY KEY,
+            prompt_id TEXT NOT NULL,
+            token TEXT NOT NULL UNIQUE,
+            created_at TEXT NOT NULL,
+            redeemed_at TEXT,
+            FOREIGN KEY (prompt_id) REFERENCES prompts(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+    if !column_exists(&pool, "prompts", "invite_only").await? {
+        add_column_if_missing(
+            &pool,
+            "prompts",
+            "invite_only",
+            "INTEGER NOT NULL DEFAULT 0",
+        )
+        .await?;
+        // Invite-only used to mean having any invites
+        sqlx::query(
+            "UPDATE prompts SET invite_only = 1 WHERE id IN (SELECT prompt_id FROM invites)",
+        )
+        .execute(&pool)
+        .await?;
+    }
+
+    // Audit trail of respondent data exports and deletions; `respondent` is
+    // a digest of the address, so the log doesn't keep what was erased
+    sqlx::query(
+        r#"
+        CREATE TABLE IF NOT EXISTS privacy_requests (
+            id TEXT PRIMARY KEY,
+            workspace_id TEXT NOT NULL,
+            action TEXT NOT NULL,
+            respondent TEXT NOT NULL,
+            respondent_hint TEXT NOT NULL,
+            entries INTEGER NOT NULL,
+            performed_by TEXT,
+            created_at TEXT NOT NULL,
+            FOREIGN KEY (workspace_id) REFERENCES workspaces(id)
+        )
+        "#,
+    )
+    .execute(&pool)
+    .await?;
+
+    for (_, sql) in INDEXES {
+        sqlx::query(sql).execute(&pool).await?;
+    }
+
+    init_search_index(&pool).await?;
+    init_storage_accounting(&pool).await?;
+    score_unscored_feedback(&pool).await?;
+    hash_unhashed_feedback(&pool).await?;
+
+    Ok(pool)
+}
+
+/// Secondary indexes the app expects, by name, with the statement that creates each
+pub const INDEXES: &[(&str, &str)] = &[
+    (
+        "idx_prompts_workspace",
+        "CREATE INDEX IF NOT EXISTS idx_prompts_workspace ON prompts (workspace_id, created_at)",
+    ),
+    (
+        "idx_feedback_prompt",
+        "CREATE INDEX IF NOT EXISTS idx_feedback_prompt ON feedback (prompt_id, created_at)",
+    ),
+    (
+        "idx_feedback_respondent",
+        "CREATE INDEX IF NOT EXISTS idx_feedback_respondent ON feedback (respondent_email)",
+    ),
+    (
+        "idx_feedback_cycle",
+        "CREATE INDEX IF NOT EXISTS idx_feedback_cycle ON feedback (cycle_id)",
+    ),
+    (
+        "idx_feedback_tags_tag",
+        "CREATE INDEX IF NOT EXISTS idx_feedback_tags_tag ON feedback_tags (tag_id)",
+    ),
+    (
+        "idx_prompts_slug",
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_prompts_slug ON prompts (slug)",
+    ),
+    (
+        "idx_users_oidc_subject",
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_users_oidc_subject ON users (oidc_subject)",
+    ),
+    (
+        "idx_sessions_user",
+        "CREATE INDEX IF NOT EXISTS idx_sessions_user ON sessions (user_id)",
+    ),
+    (
+        "idx_fields_workspace",
+        "CREATE INDEX IF NOT EXISTS idx_fields_workspace ON fields (workspace_id, label)",
+    ),
+    (
+        "idx_prompt_fields_field",
+        "CREATE INDEX IF NOT EXISTS idx_prompt_fields_field ON prompt_fields (field_id)",
+    ),
+    (
+        "idx_feedback_content_hash",
+        "CREATE INDEX IF NOT EXISTS idx_feedback_content_hash ON feedback (content_hash)",
+    ),
+    (
+        "idx_feedback_edit_token",
+        "CREATE UNIQUE INDEX IF NOT EXISTS idx_feedback_edit_token ON feedback (edit_token)",
+    ),
+    (
+        "idx_answers_field",
+        "CREATE INDEX IF NOT EXISTS idx_answers_field ON answers (field_id)",
+    ),
+    (
+        "idx_notification_rules_prompt",
+        "CREATE INDEX IF NOT EXISTS idx_notification_rules_prompt ON notification_rules (prompt_id, position)",
+    ),
+    (
+        "idx_invites_prompt",
+        "CREATE INDEX IF NOT EXISTS idx_invites_prompt ON invites (prompt_id)",
+    ),
+    (
+        "idx_submission_tokens_created",
+        "CREATE INDEX IF NOT EXISTS idx_submission_tokens_created ON submission_tokens (created_at)",
+    ),
+];
+
+/// Score feedback written before sentiment analysis existed
+async fn score_unscored_feedback(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let unscored: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, content FROM feedback WHERE sentiment_label IS NULL")
+            .fetch_all(pool)
+            .await?;
+
+    for (id, content) in unscored {
+        let sentiment = sentiment::analyze(&content);
+        sqlx::query("UPDATE feedback SET sentiment_score = ?, sentiment_label = ? WHERE id = ?")
+            .bind(sentiment.score)
+            .bind(sentiment.label.as_str())
+            .bind(&id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// Fill in `content_hash` for entries stored before it existed
+async fn hash_unhashed_feedback(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let unhashed: Vec<(String, String)> =
+        sqlx::query_as("SELECT id, content FROM feedback WHERE content_hash IS NULL")
+            .fetch_all(pool)
+            .await?;
+
+    for (id, content) in unhashed {
+        sqlx::query("UPDATE feedback SET content_hash = ? WHERE id = ?")
+            .bind(content_hash(&content))
+            .bind(&id)
+            .execute(pool)
+            .await?;
+    }
+
+    Ok(())
+}
+
+/// SHA-256 of the content with case and whitespace normalized, so the same
+/// report pasted into several prompts hashes the same
+pub fn content_hash(content: &str) -> String {
+    let normalized = content
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase();
+    hex::encode(Sha256::digest(normalized.as_bytes()))
+}
+
+/// Create the FTS5 index over feedback content and the triggers that keep it in sync
+async fn init_search_index(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let exists: Option<(String,)> = sqlx::query_as(
+        "SELECT name FROM sqlite_master WHERE type = 'table' AND name = 'feedback_fts'",
+    )
+    .fetch_optional(pool)
+    .await?;
+    if exists.is_some() {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+
+    // Keyed by feedback id rather than rowid, which VACUUM may renumber
+    sqlx::query("CREATE VIRTUAL TABLE feedback_fts USING fts5(feedback_id UNINDEXED, content)")
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER feedback_fts_insert AFTER INSERT ON feedback BEGIN
+            INSERT INTO feedback_fts (feedback_id, content) VALUES (new.id, new.content);
+        END
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER feedback_fts_update AFTER UPDATE OF content ON feedback BEGIN
+            UPDATE feedback_fts SET content = new.content WHERE feedback_id = old.id;
+        END
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query(
+        r#"
+        CREATE TRIGGER feedback_fts_delete AFTER DELETE ON feedback BEGIN
+            DELETE FROM feedback_fts WHERE feedback_id = old.id;
+        END
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    // Index feedback written before search existed
+    sqlx::query("INSERT INTO feedback_fts (feedback_id, content) SELECT id, content FROM feedback")
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await
+}
+
+/// Add `prompts.response_bytes` and the triggers that keep it current as
+/// feedback and answers are written, edited and deleted
+async fn init_storage_accounting(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    if column_exists(pool, "prompts", "response_bytes").await? {
+        return Ok(());
+    }
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("ALTER TABLE prompts ADD COLUMN response_bytes INTEGER NOT NULL DEFAULT 0")
+        .execute(&mut *tx)
+        .await?;
+
+    let triggers = [
+        r#"
+        CREATE TRIGGER storage_feedback_insert AFTER INSERT ON feedback BEGIN
+            UPDATE prompts SET response_bytes = response_bytes + length(CAST(new.content AS BLOB))
+            WHERE id = new.prompt_id;
+        END
+        "#,
+        r#"
+        CREATE TRIGGER storage_feedback_update AFTER UPDATE OF content, prompt_id ON feedback BEGIN
+            UPDATE prompts SET response_bytes = response_bytes - length(CAST(old.content AS BLOB))
+            WHERE id = old.prompt_id;
+            UPDATE prompts SET response_bytes = response_bytes + length(CAST(new.content AS BLOB))
+            WHERE id = new.prompt_id;
+        END
+        "#,
+        r#"
+        CREATE TRIGGER storage_feedback_delete AFTER DELETE ON feedback BEGIN
+            UPDATE prompts SET response_bytes = response_bytes - length(CAST(old.content AS BLOB))
+            WHERE id = old.prompt_id;
+        END
+        "#,
+        r#"
+        CREATE TRIGGER storage_answer_insert AFTER INSERT ON answers BEGIN
+            UPDATE prompts SET response_bytes = response_bytes + length(CAST(new.value AS BLOB))
+            WHERE id = (SELECT prompt_id FROM feedback WHERE id = new.feedback_id);
+        END
+        "#,
+        r#"
+        CREATE TRIGGER storage_answer_update AFTER UPDATE OF value ON answers BEGIN
+            UPDATE prompts SET response_bytes = response_bytes
+                - length(CAST(old.value AS BLOB)) + length(CAST(new.value AS BLOB))
+            WHERE id = (SELECT prompt_id FROM feedback WHERE id = new.feedback_id);
+        END
+        "#,
+        r#"
+        CREATE TRIGGER storage_answer_delete AFTER DELETE ON answers BEGIN
+            UPDATE prompts SET response_bytes = response_bytes - length(CAST(old.value AS BLOB))
+            WHERE id = (SELECT prompt_id FROM feedback WHERE id = old.feedback_id);
+        END
+        "#,
+    ];
+    for sql in triggers {
+        sqlx::query(sql).execute(&mut *tx).await?;
+    }
+
+    // Count feedback written before accounting existed
+    sqlx::query(
+        r#"
+        UPDATE prompts SET response_bytes =
+            (SELECT COALESCE(SUM(length(CAST(content AS BLOB))), 0) FROM feedback WHERE prompt_id = prompts.id)
+            + (SELECT COALESCE(SUM(length(CAST(a.value AS BLOB))), 0)
+               FROM answers a JOIN feedback f ON f.id = a.feedback_id WHERE f.prompt_id = prompts.id)
+        "#,
+    )
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+/// Add a column to an existing table, for databases created before the column existed
+pub async fn column_exists(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+) -> Result<bool, sqlx::Error> {
+    let exists: Option<(String,)> =
+        sqlx::query_as("SELECT name FROM pragma_table_info(?) WHERE name = ?")
+            .bind(table)
+            .bind(column)
+            .fetch_optional(pool)
+            .await?;
+
+    Ok(exists.is_some())
+}
+
+async fn add_column_if_missing(
+    pool: &SqlitePool,
+    table: &str,
+    column: &str,
+    definition: &str,
+) -> Result<(), sqlx::Error> {
+    if !column_exists(pool, table, column).await? {
+        sqlx::query(&format!(
+            "ALTER TABLE {} ADD COLUMN {} {}",
+            table, column, definition
+        ))
+        .execute(pool)
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Slugs that would shadow instance-wide admin routes or the versioned JSON API
+const RESERVED_WORKSPACE_SLUGS: &[&str] = &[
+    "workspaces",
+    "backup",
+    "maintenance",
+    "users",
+    "tokens",
+    "v1",
+];
+
+/// Canonical form of a workspace slug: lowercase words joined by hyphens.
+/// Returns None for slugs that are empty, too long, reserved, or contain other characters.
+pub fn normalize_workspace_slug(slug: &str) -> Option<String> {
+    normalize_slug(slug).filter(|s| !RESERVED_WORKSPACE_SLUGS.contains(&s.as_str()))
+}
+
+/// Canonical form of a prompt's short URL slug, by the same rules as
+/// workspace slugs; prompts have their own `/f/` namespace, so none are reserved
+pub fn normalize_prompt_slug(slug: &str) -> Option<String> {
+    normalize_slug(slug)
+}
+
+fn normalize_slug(slug: &str) -> Option<String> {
+    let normalized = slug
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    let valid = !normalized.is_empty()
+        && normalized.len() <= 40
+        && normalized
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-');
+    valid.then_some(normalized)
+}
+
+/// Create a workspace; `slug` must be normalized. Returns None if the slug is taken.
+pub async fn create_workspace(
+    pool: &SqlitePool,
+    slug: &str,
+    name: &str,
+) -> Result<Option<Workspace>, sqlx::Error> {
+    let workspace = Workspace {
+        id: uuid::Uuid::new_v4().to_string(),
+        slug: slug.to_string(),
+        name: name.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+    };
+
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO workspaces (id, slug, name, created_at) VALUES (?, ?, ?, ?)",
+    )
+    .bind(&workspace.id)
+    .bind(&workspace.slug)
+    .bind(&workspace.name)
+    .bind(&workspace.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok((result.rows_affected() > 0).then_some(workspace))
+}
+
+pub async fn get_workspaces(pool: &SqlitePool) -> Result<Vec<Workspace>, sqlx::Error> {
+    sqlx::query_as::<_, Workspace>(
+        "SELECT id, slug, name, created_at FROM workspaces ORDER BY name COLLATE NOCASE",
+    )
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_workspace_by_slug(
+    pool: &SqlitePool,
+    slug: &str,
+) -> Result<Option<Workspace>, sqlx::Error> {
+    sqlx::query_as::<_, Workspace>(
+        "SELECT id, slug, name, created_at FROM workspaces WHERE slug = ?",
+    )
+    .bind(slug)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Shorthand for creating a prompt with default options
+#[cfg(test)]
+pub async fn create_prompt(
+    pool: &SqlitePool,
+    title: &str,
+    description: &str,
+) -> Result<Prompt, sqlx::Error> {
+    create_prompt_with_options(
+        pool,
+        DEFAULT_WORKSPACE_ID,
+        title,
+        description,
+        &PromptOptions::default(),
+    )
+    .await
+}
+
+pub async fn create_prompt_with_options(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    title: &str,
+    description: &str,
+    options: &PromptOptions,
+) -> Result<Prompt, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    insert_prompt(&mut conn, workspace_id, title, description, options).await
+}
+
+async fn insert_prompt(
+    conn: &mut SqliteConnection,
+    workspace_id: &str,
+    title: &str,
+    description: &str,
+    options: &PromptOptions,
+) -> Result<Prompt, sqlx::Error> {
+    let id = uuid::Uuid::new_v4().to_string();
+    let created_at = Utc::now().to_rfc3339();
+    // Each webhook gets its own secret so receivers can verify receipts independently
+    let webhook_secret = options
+        .webhook_url
+        .as_ref()
+        .map(|_| uuid::Uuid::new_v4().simple().to_string());
+
+    let recurrence = options.recurrence.map(|r| r.as_str().to_string());
+
+    sqlx::query(
+        "INSERT INTO prompts (id, workspace_id, title, description, created_at, webhook_url, webhook_secret, merge_responses, recurrence, content_min_length, content_max_length, content_pattern, public_board, send_receipts, language, slug, thank_you_message, redirect_url, access_code, publish_at, kind, retention_days, max_responses) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&id)
+    .bind(workspace_id)
+    .bind(title)
+    .bind(description)
+    .bind(&created_at)
+    .bind(&options.webhook_url)
+    .bind(&webhook_secret)
+    .bind(options.merge_responses)
+    .bind(&recurrence)
+    .bind(options.content_rules.min_length)
+    .bind(options.content_rules.max_length)
+    .bind(&options.content_rules.pattern)
+    .bind(options.public_board)
+    .bind(options.send_receipts)
+    .bind(&options.language)
+    .bind(&options.slug)
+    .bind(&options.thank_you_message)
+    .bind(&options.redirect_url)
+    .bind(&options.access_code)
+    .bind(&options.publish_at)
+    .bind(options.kind.as_str())
+    .bind(options.retention_days)
+    .bind(options.max_responses)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(Prompt {
+        id,
+        workspace_id: workspace_id.to_string(),
+        title: title.to_string(),
+        description: description.to_string(),
+        created_at,
+        webhook_url: options.webhook_url.clone(),
+        webhook_secret,
+        merge_responses: options.merge_responses,
+        recurrence,
+        content_min_length: options.content_rules.min_length,
+        content_max_length: options.content_rules.max_length,
+        content_pattern: options.content_rules.pattern.clone(),
+        closed_at: None,
+        archived_at: None,
+        public_board: options.public_board,
+        send_receipts: options.send_receipts,
+        suspended_at: None,
+        language: options.language.clone(),
+        slug: options.slug.clone(),
+        thank_you_message: options.thank_you_message.clone(),
+        redirect_url: options.redirect_url.clone(),
+        access_code: options.access_code.clone(),
+        publish_at: options.publish_at.clone(),
+        kind: options.kind.as_str().to_string(),
+        retention_days: options.retention_days,
+        max_responses: options.max_responses,
+        invite_only: false,
+    })
+}
+
+/// Copy a prompt's settings, question bank fields, notification rules and
+/// translations into a new, open prompt in the same workspace. Feedback,
+/// cycles and the results widget aren't copied; a webhook gets a fresh secret.
+pub async fn clone_prompt(
+    pool: &SqlitePool,
+    source: &Prompt,
+    title: &str,
     description: &str,
 ) -> Result<Prompt, sqlx::Error> {
+    let options = PromptOptions {
+        webhook_url: source.webhook_url.clone(),
+        merge_responses: source.merge_responses,
+        recurrence: source.recurrence(),
+        content_rules: source.content_rules(),
+        public_board: source.public_board,
+        send_receipts: source.send_receipts,
+        language: source.language.clone(),
+        // Slugs are unique, so the copy starts without one
+        slug: None,
+        thank_you_message: source.thank_you_message.clone(),
+        redirect_url: source.redirect_url.clone(),
+        access_code: source.access_code.clone(),
+        publish_at: source.publish_at.clone(),
+        kind: source.kind(),
+        retention_days: source.retention_days,
+        max_responses: source.max_responses,
+    };
+
+    let mut tx = pool.begin().await?;
+    let prompt = insert_prompt(&mut tx, &source.workspace_id, title, description, &options).await?;
+
+    sqlx::query(
+        "INSERT INTO prompt_fields (prompt_id, field_id, position) SELECT ?, field_id, position FROM prompt_fields WHERE prompt_id = ?",
+    )
+    .bind(&prompt.id)
+    .bind(&source.id)
+    .execute(&mut *tx)
+    .await?;
+
+    let rules = sqlx::query_as::<_, NotificationRule>(&format!(
+        "SELECT {} FROM notification_rules WHERE prompt_id = ?",
+        NOTIFICATION_RULE_COLUMNS
+    ))
+    .bind(&source.id)
+    .fetch_all(&mut *tx)
+    .await?;
+    let created_at = Utc::now().to_rfc3339();
+    for rule in rules {
+        sqlx::query(&format!(
+            "INSERT INTO notification_rules ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, NULL)",
+            NOTIFICATION_RULE_COLUMNS
+        ))
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(&prompt.id)
+        .bind(rule.position)
+        .bind(&rule.condition)
+        .bind(&rule.field_id)
+        .bind(&rule.operand)
+        .bind(&rule.delivery)
+        .bind(&rule.target_url)
+        .bind(&created_at)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    sqlx::query(
+        "INSERT INTO prompt_translations (prompt_id, language, title, description, field_labels, updated_at) SELECT ?, language, title, description, field_labels, ? FROM prompt_translations WHERE prompt_id = ?",
+    )
+    .bind(&prompt.id)
+    .bind(&created_at)
+    .bind(&source.id)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await?;
+    Ok(prompt)
+}
+
+/// Open, close or archive a prompt. An earlier close time is kept when
+/// archiving; closing an archived prompt unarchives it; opening clears both.
+pub async fn set_prompt_status(
+    pool: &SqlitePool,
+    id: &str,
+    status: PromptStatus,
+) -> Result<(), sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let query = match status {
+        PromptStatus::Open => {
+            sqlx::query("UPDATE prompts SET closed_at = NULL, archived_at = NULL WHERE id = ?")
+        }
+        PromptStatus::Closed => sqlx::query(
+            "UPDATE prompts SET closed_at = COALESCE(closed_at, ?), archived_at = NULL WHERE id = ?",
+        )
+        .bind(&now),
+        PromptStatus::Archived => sqlx::query(
+            "UPDATE prompts SET closed_at = COALESCE(closed_at, ?1), archived_at = COALESCE(archived_at, ?1) WHERE id = ?2",
+        )
+        .bind(&now),
+    };
+    query.bind(id).execute(pool).await?;
+    Ok(())
+}
+
+pub async fn set_send_receipts(
+    pool: &SqlitePool,
+    id: &str,
+    enabled: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE prompts SET send_receipts = ? WHERE id = ?")
+        .bind(enabled)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// What respondents see after submitting: a custom message, a redirect, or
+/// (with neither) the standard thanks
+pub async fn set_prompt_thank_you(
+    pool: &SqlitePool,
+    id: &str,
+    message: Option<&str>,
+    redirect_url: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE prompts SET thank_you_message = ?, redirect_url = ? WHERE id = ?")
+        .bind(message)
+        .bind(redirect_url)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+const ABUSE_REPORT_COLUMNS: &str = "id, prompt_id, reason, details, reporter_email, created_at";
+
+/// File a report against a prompt. Returns the number of open reports it now
+/// has, or None if this browser had already reported it.
+pub async fn create_abuse_report(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    reason: &str,
+    details: Option<&str>,
+    reporter_email: Option<&str>,
+    reporter: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO abuse_reports (id, prompt_id, reason, details, reporter_email, reporter, created_at) VALUES (?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(prompt_id)
+    .bind(reason)
+    .bind(details)
+    .bind(reporter_email)
+    .bind(reporter)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(None);
+    }
+
+    let (open,): (i64,) = sqlx::query_as(
+        "SELECT COUNT(*) FROM abuse_reports WHERE prompt_id = ? AND resolved_at IS NULL",
+    )
+    .bind(prompt_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(Some(open))
+}
+
+/// Every prompt with open reports or a suspension, most reported first
+pub async fn get_abuse_queue(pool: &SqlitePool) -> Result<Vec<AbuseCase>, sqlx::Error> {
+    #[derive(FromRow)]
+    struct Row {
+        #[sqlx(flatten)]
+        prompt: Prompt,
+        workspace_slug: String,
+    }
+
+    let rows = sqlx::query_as::<_, Row>(&format!(
+        r#"
+        SELECT {}, w.slug AS workspace_slug
+        FROM prompts p
+        JOIN workspaces w ON w.id = p.workspace_id
+        WHERE p.suspended_at IS NOT NULL
+           OR p.id IN (SELECT prompt_id FROM abuse_reports WHERE resolved_at IS NULL)
+        "#,
+        PROMPT_COLUMNS
+            .split(", ")
+            .map(|c| format!("p.{}", c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+    .fetch_all(pool)
+    .await?;
+    let reports = sqlx::query_as::<_, AbuseReport>(&format!(
+        "SELECT {} FROM abuse_reports WHERE resolved_at IS NULL ORDER BY created_at",
+        ABUSE_REPORT_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await?;
+
+    let mut cases: Vec<AbuseCase> = rows
+        .into_iter()
+        .map(|row| AbuseCase {
+            reports: reports
+                .iter()
+                .filter(|r| r.prompt_id == row.prompt.id)
+                .cloned()
+                .collect(),
+            prompt: row.prompt,
+            workspace_slug: row.workspace_slug,
+        })
+        .collect();
+    cases.sort_by_key(|case| std::cmp::Reverse(case.reports.len()));
+    Ok(cases)
+}
+
+/// Take a prompt down; its reports stay open until an owner reviews them
+pub async fn suspend_prompt(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE prompts SET suspended_at = COALESCE(suspended_at, ?) WHERE id = ?")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn reinstate_prompt(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE prompts SET suspended_at = NULL WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Close a prompt's open reports as `suspended` (upheld) or `dismissed`
+pub async fn resolve_abuse_reports(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    resolution: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "UPDATE abuse_reports SET resolved_at = ?, resolution = ? WHERE prompt_id = ? AND resolved_at IS NULL",
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(resolution)
+    .bind(prompt_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+pub async fn get_all_prompts(
+    pool: &SqlitePool,
+    workspace_id: &str,
+) -> Result<Vec<Prompt>, sqlx::Error> {
+    sqlx::query_as::<_, Prompt>(&format!(
+        "SELECT {} FROM prompts WHERE workspace_id = ? ORDER BY created_at DESC",
+        PROMPT_COLUMNS
+    ))
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Look up a prompt from an admin view, only if it belongs to the workspace
+pub async fn get_workspace_prompt(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<Option<Prompt>, sqlx::Error> {
+    sqlx::query_as::<_, Prompt>(&format!(
+        "SELECT {} FROM prompts WHERE id = ? AND workspace_id = ?",
+        PROMPT_COLUMNS
+    ))
+    .bind(id)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// How much a prompt and its workspace are storing, as kept by the
+/// accounting triggers
+pub async fn get_storage_usage(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<StorageUsage, sqlx::Error> {
+    let usage: Option<(i64, i64)> = sqlx::query_as(
+        r#"
+        SELECT p.response_bytes,
+            (SELECT COALESCE(SUM(response_bytes), 0) FROM prompts WHERE workspace_id = p.workspace_id)
+        FROM prompts p WHERE p.id = ?
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(usage
+        .map(|(prompt_bytes, workspace_bytes)| StorageUsage {
+            prompt_bytes,
+            workspace_bytes,
+        })
+        .unwrap_or_default())
+}
+
+/// Response bytes stored across all of a workspace's prompts
+pub async fn get_workspace_storage_bytes(
+    pool: &SqlitePool,
+    workspace_id: &str,
+) -> Result<i64, sqlx::Error> {
+    let (bytes,): (i64,) = sqlx::query_as(
+        "SELECT COALESCE(SUM(response_bytes), 0) FROM prompts WHERE workspace_id = ?",
+    )
+    .bind(workspace_id)
+    .fetch_one(pool)
+    .await?;
+    Ok(bytes)
+}
+
+/// Look up a prompt by its unguessable ID alone, as the public form does
+pub async fn get_prompt_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Prompt>, sqlx::Error> {
+    sqlx::query_as::<_, Prompt>(&format!(
+        "SELECT {} FROM prompts WHERE id = ?",
+        PROMPT_COLUMNS
+    ))
+    .bind(id)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_prompt_by_slug(
+    pool: &SqlitePool,
+    slug: &str,
+) -> Result<Option<Prompt>, sqlx::Error> {
+    sqlx::query_as::<_, Prompt>(&format!(
+        "SELECT {} FROM prompts WHERE slug = ?",
+        PROMPT_COLUMNS
+    ))
+    .bind(slug)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Set or clear a prompt's slug, which must be normalized. Returns false if
+/// another prompt has it.
+pub async fn set_prompt_slug(
+    pool: &SqlitePool,
+    id: &str,
+    slug: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE OR IGNORE prompts SET slug = ? WHERE id = ?")
+        .bind(slug)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Set or clear the code respondents must enter; `code` must be normalized
+pub async fn set_access_code(
+    pool: &SqlitePool,
+    id: &str,
+    code: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE prompts SET access_code = ? WHERE id = ?")
+        .bind(code)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Set or clear when the form opens; `publish_at` must be normalized with
+/// `parse_publish_at`
+pub async fn set_publish_at(
+    pool: &SqlitePool,
+    id: &str,
+    publish_at: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE prompts SET publish_at = ? WHERE id = ?")
+        .bind(publish_at)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Set or clear the prompt's own retention period
+pub async fn set_retention_days(
+    pool: &SqlitePool,
+    id: &str,
+    days: Option<i64>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE prompts SET retention_days = ? WHERE id = ?")
+        .bind(days)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Refusal of an entry for a prompt that already has its `max_responses`
+#[derive(Debug)]
+pub struct PromptFull;
+
+impl std::fmt::Display for PromptFull {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(sqlx::error::DatabaseError::message(self))
+    }
+}
+
+impl std::error::Error for PromptFull {}
+
+impl sqlx::error::DatabaseError for PromptFull {
+    fn message(&self) -> &str {
+        "prompt has reached its response limit"
+    }
+
+    fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+        self
+    }
+
+    fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+        self
+    }
+
+    fn kind(&self) -> sqlx::error::ErrorKind {
+        sqlx::error::ErrorKind::CheckViolation
+    }
+}
+
+/// Whether an insert failed because the prompt is full
+pub fn is_prompt_full(e: &sqlx::Error) -> bool {
+    e.as_database_error()
+        .is_some_and(|e| e.try_downcast_ref::<PromptFull>().is_some())
+}
+
+/// Responses the prompt has taken, counted against its `max_responses`:
+/// every new entry but spam. Deleting entries doesn't give places back.
+pub async fn accepted_responses(pool: &SqlitePool, prompt_id: &str) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT accepted_responses FROM prompts WHERE id = ?")
+        .bind(prompt_id)
+        .fetch_optional(pool)
+        .await
+        .map(Option::unwrap_or_default)
+}
+
+/// Shorthand for recording anonymous feedback
+#[cfg(test)]
+pub async fn create_feedback(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    content: &str,
+) -> Result<Feedback, sqlx::Error> {
+    create_feedback_with_options(pool, prompt_id, content, &FeedbackOptions::default()).await
+}
+
+pub async fn create_feedback_with_options(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    content: &str,
+    options: &FeedbackOptions,
+) -> Result<Feedback, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let feedback = insert_feedback(&mut tx, prompt_id, content, options).await?;
+    tx.commit().await?;
+    Ok(feedback)
+}
+
+async fn insert_feedback(
+    conn: &mut SqliteConnection,
+    prompt_id: &str,
+    content: &str,
+    options: &FeedbackOptions,
+) -> Result<Feedback, sqlx::Error> {
     let id = uuid::Uuid::new_v4().to_string();
     let created_at = Utc::now().to_rfc3339();
+    let sentiment = sentiment::analyze(content);
+
+    // Take a place before inserting: the counter update holds the write lock
+    // until the caller commits, so concurrent submissions queue behind it and
+    // can't both take the last place. The insert is refused if that place was
+    // past the cap, and the caller's transaction rolls the counter back.
+    // Spam doesn't take a place.
+    if options.spam_reason.is_none() {
+        sqlx::query("UPDATE prompts SET accepted_responses = accepted_responses + 1 WHERE id = ?")
+            .bind(prompt_id)
+            .execute(&mut *conn)
+            .await?;
+    }
+    let result = sqlx::query(
+        r#"
+        INSERT INTO feedback (id, prompt_id, content, content_hash, created_at, respondent_email, cycle_id, sentiment_score, sentiment_label, spam_reason, nps_score, reaction)
+        SELECT ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?
+        WHERE NOT EXISTS (
+            SELECT 1 FROM prompts
+            WHERE id = ?
+              AND max_responses < accepted_responses
+        )
+        "#,
+    )
+    .bind(&id)
+    .bind(prompt_id)
+    .bind(content)
+    .bind(content_hash(content))
+    .bind(&created_at)
+    .bind(&options.respondent_email)
+    .bind(&options.cycle_id)
+    .bind(sentiment.score)
+    .bind(sentiment.label.as_str())
+    .bind(&options.spam_reason)
+    .bind(options.nps_score)
+    .bind(options.reaction.map(|r| r.key()))
+    .bind(prompt_id)
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Err(sqlx::Error::Database(Box::new(PromptFull)));
+    }
+    let answers = save_answers(conn, prompt_id, &id, options).await?;
+
+    Ok(Feedback {
+        id,
+        prompt_id: prompt_id.to_string(),
+        content: content.to_string(),
+        created_at,
+        respondent_email: options.respondent_email.clone(),
+        updated_at: None,
+        cycle_id: options.cycle_id.clone(),
+        sentiment_score: Some(sentiment.score),
+        sentiment_label: Some(sentiment.label.as_str().to_string()),
+        status: new_status(),
+        nps_score: options.nps_score,
+        reaction: options.reaction.map(|r| r.key().to_string()),
+        tags: Vec::new(),
+        answers,
+        duplicates: Vec::new(),
+        replies: Vec::new(),
+    })
+}
+
+/// Replace an entry's answers, returning them as they'll be listed
+async fn save_answers(
+    conn: &mut SqliteConnection,
+    prompt_id: &str,
+    feedback_id: &str,
+    options: &FeedbackOptions,
+) -> Result<Vec<Answer>, sqlx::Error> {
+    sqlx::query("DELETE FROM answers WHERE feedback_id = ?")
+        .bind(feedback_id)
+        .execute(&mut *conn)
+        .await?;
+    if options.answers.is_empty() {
+        return Ok(Vec::new());
+    }
+    for (field_id, value) in &options.answers {
+        sqlx::query(
+            "INSERT INTO answers (feedback_id, field_id, value, verified) VALUES (?, ?, ?, ?)",
+        )
+        .bind(feedback_id)
+        .bind(field_id)
+        .bind(value)
+        .bind(options.verified_fields.contains(field_id))
+        .execute(&mut *conn)
+        .await?;
+    }
+
+    sqlx::query_as::<_, Answer>(
+        r#"
+        SELECT a.field_id, fl.label, a.value, a.verified
+        FROM answers a
+        JOIN fields fl ON fl.id = a.field_id
+        LEFT JOIN prompt_fields pf ON pf.field_id = a.field_id AND pf.prompt_id = ?
+        WHERE a.feedback_id = ?
+        ORDER BY pf.position IS NULL, pf.position, fl.label
+        "#,
+    )
+    .bind(prompt_id)
+    .bind(feedback_id)
+    .fetch_all(&mut *conn)
+    .await
+}
+
+/// Record feedback from a known respondent, replacing their most recent answer to
+/// the same prompt (and cycle, for recurring prompts) instead of appending a new entry.
+/// An email typed into the form proves nothing about who sent it, so only a
+/// verified one (`options.email_verified`) replaces anything; otherwise the
+/// entry is appended.
+pub async fn replace_respondent_feedback(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    content: &str,
+    options: &FeedbackOptions,
+) -> Result<Feedback, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    let existing = match &options.respondent_email {
+        Some(email) if options.email_verified => {
+            latest_respondent_feedback(&mut tx, prompt_id, email, options.cycle_id.as_deref())
+                .await?
+        }
+        _ => None,
+    };
+
+    let feedback = match existing {
+        Some(mut feedback) => {
+            update_content(&mut tx, &mut feedback, content).await?;
+            if options.nps_score.is_some() {
+                sqlx::query("UPDATE feedback SET nps_score = ? WHERE id = ?")
+                    .bind(options.nps_score)
+                    .bind(&feedback.id)
+                    .execute(&mut *tx)
+                    .await?;
+                feedback.nps_score = options.nps_score;
+            }
+            if let Some(reaction) = options.reaction {
+                sqlx::query("UPDATE feedback SET reaction = ? WHERE id = ?")
+                    .bind(reaction.key())
+                    .bind(&feedback.id)
+                    .execute(&mut *tx)
+                    .await?;
+                feedback.reaction = Some(reaction.key().to_string());
+            }
+            feedback.answers = save_answers(&mut tx, prompt_id, &feedback.id, options).await?;
+            feedback
+        }
+        None => insert_feedback(&mut tx, prompt_id, content, options).await?,
+    };
+
+    tx.commit().await?;
+    Ok(feedback)
+}
+
+/// Whether `email` already has an entry in the prompt's cycle that
+/// [`replace_respondent_feedback`] would replace
+pub async fn has_respondent_feedback(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    email: &str,
+    cycle_id: Option<&str>,
+) -> Result<bool, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    latest_respondent_feedback(&mut conn, prompt_id, email, cycle_id)
+        .await
+        .map(|feedback| feedback.is_some())
+}
+
+async fn latest_respondent_feedback(
+    conn: &mut SqliteConnection,
+    prompt_id: &str,
+    email: &str,
+    cycle_id: Option<&str>,
+) -> Result<Option<Feedback>, sqlx::Error> {
+    sqlx::query_as::<_, Feedback>(&format!(
+        "SELECT {} FROM feedback WHERE prompt_id = ? AND respondent_email = ? AND cycle_id IS ? AND spam_reason IS NULL ORDER BY created_at DESC LIMIT 1",
+        FEEDBACK_COLUMNS
+    ))
+    .bind(prompt_id)
+    .bind(email)
+    .bind(cycle_id)
+    .fetch_optional(&mut *conn)
+    .await
+}
+
+/// Swap in new text for an entry, re-scoring and re-hashing it; changed
+/// text goes back to triage as new
+async fn update_content(
+    conn: &mut SqliteConnection,
+    feedback: &mut Feedback,
+    content: &str,
+) -> Result<(), sqlx::Error> {
+    let updated_at = Utc::now().to_rfc3339();
+    let sentiment = sentiment::analyze(content);
+    sqlx::query(
+        "UPDATE feedback SET content = ?, content_hash = ?, updated_at = ?, sentiment_score = ?, sentiment_label = ?, status = 'new' WHERE id = ?",
+    )
+    .bind(content)
+    .bind(content_hash(content))
+    .bind(&updated_at)
+    .bind(sentiment.score)
+    .bind(sentiment.label.as_str())
+    .bind(&feedback.id)
+    .execute(&mut *conn)
+    .await?;
+
+    feedback.content = content.to_string();
+    feedback.updated_at = Some(updated_at);
+    feedback.sentiment_score = Some(sentiment.score);
+    feedback.sentiment_label = Some(sentiment.label.as_str().to_string());
+    feedback.status = new_status();
+    Ok(())
+}
+
+/// Let the holder of `digest`'s token edit an entry, replacing any earlier token
+pub async fn set_edit_token(
+    pool: &SqlitePool,
+    feedback_id: &str,
+    digest: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE feedback SET edit_token = ? WHERE id = ?")
+        .bind(digest)
+        .bind(feedback_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The prompt's entry whose edit token has this digest; quarantined entries
+/// can't be edited
+pub async fn get_feedback_by_edit_token(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    digest: &str,
+) -> Result<Option<Feedback>, sqlx::Error> {
+    sqlx::query_as::<_, Feedback>(&format!(
+        "SELECT {} FROM feedback WHERE prompt_id = ? AND edit_token = ? AND spam_reason IS NULL",
+        FEEDBACK_COLUMNS
+    ))
+    .bind(prompt_id)
+    .bind(digest)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Replace an entry's text, as its respondent edited it
+pub async fn edit_feedback(
+    pool: &SqlitePool,
+    feedback: &mut Feedback,
+    content: &str,
+) -> Result<(), sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    update_content(&mut conn, feedback, content).await
+}
+
+pub async fn get_feedback_for_prompt(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<Feedback>, sqlx::Error> {
+    list_feedback(pool, prompt_id, &FeedbackFilter::default()).await
+}
+
+/// Feedback for a prompt matching `filter`, in its sort order, with tags attached
+pub async fn list_feedback(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    filter: &FeedbackFilter,
+) -> Result<Vec<Feedback>, sqlx::Error> {
+    let mut query = QueryBuilder::<Sqlite>::new(format!(
+        "SELECT {} FROM feedback WHERE spam_reason IS NULL AND prompt_id = ",
+        FEEDBACK_COLUMNS
+    ));
+    query.push_bind(prompt_id);
+
+    if let Some(cycle_id) = &filter.cycle_id {
+        query.push(" AND cycle_id = ").push_bind(cycle_id);
+    }
+    if let Some(tag) = &filter.tag {
+        query
+            .push(" AND id IN (SELECT ft.feedback_id FROM feedback_tags ft JOIN tags t ON t.id = ft.tag_id WHERE t.name = ")
+            .push_bind(tag)
+            .push(")");
+    }
+    if let Some(label) = filter.sentiment {
+        query
+            .push(" AND sentiment_label = ")
+            .push_bind(label.as_str());
+    }
+    if let Some(status) = filter.status {
+        query.push(" AND status = ").push_bind(status.as_str());
+    }
+    if let Some(since) = &filter.since {
+        query
+            .push(" AND COALESCE(updated_at, created_at) > ")
+            .push_bind(since);
+    }
+    if let Some(from) = &filter.created_from {
+        query.push(" AND created_at >= ").push_bind(from);
+    }
+    if let Some(before) = &filter.created_before {
+        query.push(" AND created_at < ").push_bind(before);
+    }
+    if let Some(rating) = &filter.rating {
+        query
+            .push(" AND id IN (SELECT feedback_id FROM answers WHERE field_id = ")
+            .push_bind(&rating.field_id)
+            .push(" AND trim(value) != ''");
+        if let Some(min) = rating.min {
+            query.push(" AND CAST(value AS REAL) >= ").push_bind(min);
+        }
+        if let Some(max) = rating.max {
+            query.push(" AND CAST(value AS REAL) <= ").push_bind(max);
+        }
+        query.push(")");
+    }
+    query.push(match filter.sort {
+        SortOrder::Newest => " ORDER BY created_at DESC",
+        SortOrder::Oldest => " ORDER BY created_at ASC",
+    });
+    // SQLite's lower() only folds ASCII, so text is matched here instead
+    let contains = filter
+        .contains
+        .as_deref()
+        .filter(|t| !t.is_empty())
+        .map(keywords::fold);
+    if let (Some(limit), None) = (filter.limit, &contains) {
+        query.push(" LIMIT ").push_bind(limit);
+    }
+
+    let mut feedback = query.build_query_as::<Feedback>().fetch_all(pool).await?;
+    if let Some(text) = &contains {
+        feedback.retain(|fb| keywords::fold(&fb.content).contains(text.as_str()));
+        if let Some(limit) = filter.limit {
+            feedback.truncate(limit.max(0) as usize);
+        }
+    }
+    attach_tags(pool, &mut feedback).await?;
+    attach_answers(pool, &mut feedback).await?;
+    attach_replies(pool, &mut feedback).await?;
+    Ok(feedback)
+}
+
+/// `FEEDBACK_COLUMNS` qualified with the `f.` alias, for queries that join prompts
+fn qualified_feedback_columns() -> String {
+    FEEDBACK_COLUMNS
+        .split(", ")
+        .map(|c| format!("f.{}", c))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+pub async fn get_feedback_by_id(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<Option<Feedback>, sqlx::Error> {
+    let feedback = sqlx::query_as::<_, Feedback>(&format!(
+        "SELECT {} FROM feedback f JOIN prompts p ON p.id = f.prompt_id WHERE f.id = ? AND p.workspace_id = ?",
+        qualified_feedback_columns()
+    ))
+    .bind(id)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await?;
+
+    match feedback {
+        Some(feedback) => {
+            let mut list = vec![feedback];
+            attach_tags(pool, &mut list).await?;
+            attach_answers(pool, &mut list).await?;
+            attach_replies(pool, &mut list).await?;
+            Ok(list.pop())
+        }
+        None => Ok(None),
+    }
+}
+
+/// Fill in `duplicates` for each entry: non-spam entries on other prompts, in
+/// any workspace, whose normalized content is identical
+pub async fn attach_duplicates(
+    pool: &SqlitePool,
+    feedback: &mut [Feedback],
+) -> Result<(), sqlx::Error> {
+    if feedback.is_empty() {
+        return Ok(());
+    }
+
+    let mut query = QueryBuilder::<Sqlite>::new(
+        r#"
+        SELECT src.id AS source_id, f.id AS feedback_id, f.prompt_id, p.title AS prompt_title, w.slug AS workspace_slug
+        FROM feedback src
+        JOIN feedback f ON f.content_hash = src.content_hash AND f.prompt_id != src.prompt_id
+        JOIN prompts p ON p.id = f.prompt_id
+        JOIN workspaces w ON w.id = p.workspace_id
+        WHERE f.spam_reason IS NULL AND src.content != '' AND src.id IN (
+        "#,
+    );
+    let mut ids = query.separated(", ");
+    for fb in feedback.iter() {
+        ids.push_bind(&fb.id);
+    }
+    query.push(") ORDER BY f.created_at");
+
+    let rows: Vec<(String, String, String, String, String)> =
+        query.build_query_as().fetch_all(pool).await?;
+    for (source_id, feedback_id, prompt_id, prompt_title, workspace_slug) in rows {
+        if let Some(fb) = feedback.iter_mut().find(|fb| fb.id == source_id) {
+            fb.duplicates.push(Duplicate {
+                feedback_id,
+                prompt_id,
+                prompt_title,
+                workspace_slug,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Fill in `tags` for each entry with a single query
+async fn attach_tags(pool: &SqlitePool, feedback: &mut [Feedback]) -> Result<(), sqlx::Error> {
+    if feedback.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<&str> = feedback.iter().map(|fb| fb.id.as_str()).collect();
+    let rows: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT ft.feedback_id, t.name
+        FROM feedback_tags ft
+        JOIN tags t ON t.id = ft.tag_id
+        WHERE ft.feedback_id IN (SELECT value FROM json_each(?))
+        ORDER BY t.name
+        "#,
+    )
+    .bind(serde_json::to_string(&ids).unwrap_or_default())
+    .fetch_all(pool)
+    .await?;
+
+    for (feedback_id, name) in rows {
+        if let Some(fb) = feedback.iter_mut().find(|fb| fb.id == feedback_id) {
+            fb.tags.push(name);
+        }
+    }
+    Ok(())
+}
+
+/// Fill in `answers` for each entry with a single query
+async fn attach_answers(pool: &SqlitePool, feedback: &mut [Feedback]) -> Result<(), sqlx::Error> {
+    if feedback.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<&str> = feedback.iter().map(|fb| fb.id.as_str()).collect();
+    let rows: Vec<(String, String, String, String, bool)> = sqlx::query_as(
+        r#"
+        SELECT a.feedback_id, a.field_id, fl.label, a.value, a.verified
+        FROM answers a
+        JOIN feedback f ON f.id = a.feedback_id
+        JOIN fields fl ON fl.id = a.field_id
+        LEFT JOIN prompt_fields pf ON pf.field_id = a.field_id AND pf.prompt_id = f.prompt_id
+        WHERE a.feedback_id IN (SELECT value FROM json_each(?))
+        ORDER BY pf.position IS NULL, pf.position, fl.label
+        "#,
+    )
+    .bind(serde_json::to_string(&ids).unwrap_or_default())
+    .fetch_all(pool)
+    .await?;
+
+    for (feedback_id, field_id, label, value, verified) in rows {
+        if let Some(fb) = feedback.iter_mut().find(|fb| fb.id == feedback_id) {
+            fb.answers.push(Answer {
+                field_id,
+                label,
+                value,
+                verified,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// Fill in `replies` for each entry with a single query
+async fn attach_replies(pool: &SqlitePool, feedback: &mut [Feedback]) -> Result<(), sqlx::Error> {
+    if feedback.is_empty() {
+        return Ok(());
+    }
+
+    let ids: Vec<&str> = feedback.iter().map(|fb| fb.id.as_str()).collect();
+    let rows = sqlx::query_as::<_, Reply>(&format!(
+        "SELECT {} FROM replies WHERE feedback_id IN (SELECT value FROM json_each(?)) ORDER BY created_at",
+        REPLY_COLUMNS
+    ))
+    .bind(serde_json::to_string(&ids).unwrap_or_default())
+    .fetch_all(pool)
+    .await?;
+
+    for reply in rows {
+        if let Some(fb) = feedback.iter_mut().find(|fb| fb.id == reply.feedback_id) {
+            fb.replies.push(reply);
+        }
+    }
+    Ok(())
+}
+
+const REPLY_COLUMNS: &str = "id, feedback_id, author, content, created_at, emailed_at";
+
+pub async fn create_reply(
+    pool: &SqlitePool,
+    feedback_id: &str,
+    author: Option<&str>,
+    content: &str,
+) -> Result<Reply, sqlx::Error> {
+    let reply = Reply {
+        id: uuid::Uuid::new_v4().to_string(),
+        feedback_id: feedback_id.to_string(),
+        author: author.map(str::to_string),
+        content: content.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        emailed_at: None,
+    };
+    sqlx::query(&format!(
+        "INSERT INTO replies ({}) VALUES (?, ?, ?, ?, ?, NULL)",
+        REPLY_COLUMNS
+    ))
+    .bind(&reply.id)
+    .bind(&reply.feedback_id)
+    .bind(&reply.author)
+    .bind(&reply.content)
+    .bind(&reply.created_at)
+    .execute(pool)
+    .await?;
+    Ok(reply)
+}
+
+pub async fn mark_reply_emailed(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE replies SET emailed_at = ? WHERE id = ?")
+        .bind(Utc::now().to_rfc3339())
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Everyone who left an email in the workspace, most recently active first
+pub async fn get_respondents(
+    pool: &SqlitePool,
+    workspace_id: &str,
+) -> Result<Vec<RespondentSummary>, sqlx::Error> {
+    sqlx::query_as::<_, RespondentSummary>(
+        r#"
+        SELECT f.respondent_email AS email,
+               COUNT(*) AS response_count,
+               COUNT(DISTINCT f.prompt_id) AS prompt_count,
+               MIN(f.created_at) AS first_seen,
+               MAX(f.created_at) AS last_seen
+        FROM feedback f
+        JOIN prompts p ON p.id = f.prompt_id
+        WHERE f.respondent_email IS NOT NULL AND f.spam_reason IS NULL AND p.workspace_id = ?
+        GROUP BY f.respondent_email
+        ORDER BY last_seen DESC
+        "#,
+    )
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// All feedback a respondent has left across prompts, newest first
+pub async fn get_feedback_by_respondent(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    email: &str,
+) -> Result<Vec<RespondentEntry>, sqlx::Error> {
+    let entries = sqlx::query_as::<_, RespondentEntry>(&format!(
+        "SELECT {}, p.title AS prompt_title FROM feedback f JOIN prompts p ON p.id = f.prompt_id WHERE f.respondent_email = ? AND f.spam_reason IS NULL AND p.workspace_id = ? ORDER BY f.created_at DESC",
+        qualified_feedback_columns()
+    ))
+    .bind(email)
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await?;
+
+    let (mut feedback, titles): (Vec<Feedback>, Vec<String>) = entries
+        .into_iter()
+        .map(|e| (e.feedback, e.prompt_title))
+        .unzip();
+    attach_tags(pool, &mut feedback).await?;
+    attach_answers(pool, &mut feedback).await?;
+
+    Ok(feedback
+        .into_iter()
+        .zip(titles)
+        .map(|(feedback, prompt_title)| RespondentEntry {
+            feedback,
+            prompt_title,
+        })
+        .collect())
+}
+
+/// Everything a workspace holds from a respondent, spam included, oldest
+/// first, for a privacy export
+pub async fn get_respondent_data(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    email: &str,
+) -> Result<Vec<RespondentEntry>, sqlx::Error> {
+    let entries = sqlx::query_as::<_, RespondentEntry>(&format!(
+        "SELECT {}, p.title AS prompt_title FROM feedback f JOIN prompts p ON p.id = f.prompt_id WHERE f.respondent_email = ? AND p.workspace_id = ? ORDER BY f.created_at",
+        qualified_feedback_columns()
+    ))
+    .bind(email)
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await?;
+
+    let (mut feedback, titles): (Vec<Feedback>, Vec<String>) = entries
+        .into_iter()
+        .map(|e| (e.feedback, e.prompt_title))
+        .unzip();
+    attach_tags(pool, &mut feedback).await?;
+    attach_answers(pool, &mut feedback).await?;
+    attach_replies(pool, &mut feedback).await?;
+
+    Ok(feedback
+        .into_iter()
+        .zip(titles)
+        .map(|(feedback, prompt_title)| RespondentEntry {
+            feedback,
+            prompt_title,
+        })
+        .collect())
+}
+
+/// Tables holding rows that belong to one feedback entry, cleared before the
+/// entry itself is deleted
+const FEEDBACK_CHILD_TABLES: [&str; 5] = [
+    "feedback_tags",
+    "answers",
+    "votes",
+    "replies",
+    "digest_entries",
+];
+
+/// Those of `ids` that are entries on the workspace's prompts, spam included
+async fn workspace_feedback_ids(
+    conn: &mut SqliteConnection,
+    workspace_id: &str,
+    ids: &[String],
+) -> Result<Vec<String>, sqlx::Error> {
+    if ids.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut query = QueryBuilder::<Sqlite>::new(
+        "SELECT f.id FROM feedback f JOIN prompts p ON p.id = f.prompt_id WHERE p.workspace_id = ",
+    );
+    query.push_bind(workspace_id).push(" AND f.id IN (");
+    let mut separated = query.separated(", ");
+    for id in ids {
+        separated.push_bind(id);
+    }
+    query.push(")");
+    query.build_query_scalar().fetch_all(conn).await
+}
+
+/// Delete several of a workspace's entries along with their answers, tags,
+/// votes and replies, all in one transaction. IDs that aren't the workspace's
+/// are skipped; returns how many entries were deleted.
+pub async fn delete_feedback_bulk(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    ids: &[String],
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let ids = workspace_feedback_ids(&mut tx, workspace_id, ids).await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    let delete = |table: &str| {
+        let mut query = QueryBuilder::<Sqlite>::new(format!(
+            "DELETE FROM {} WHERE {} IN (",
+            table,
+            if table == "feedback" {
+                "id"
+            } else {
+                "feedback_id"
+            }
+        ));
+        let mut separated = query.separated(", ");
+        for id in &ids {
+            separated.push_bind(id.clone());
+        }
+        query.push(")");
+        query
+    };
+    for table in FEEDBACK_CHILD_TABLES {
+        delete(table).build().execute(&mut *tx).await?;
+    }
+    let result = delete("feedback").build().execute(&mut *tx).await?;
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// Attach a tag (creating it if new) to several of a workspace's entries in
+/// one transaction; `name` must be normalized. IDs that aren't the
+/// workspace's are skipped; returns how many entries were tagged.
+pub async fn add_tag_bulk(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    ids: &[String],
+    name: &str,
+) -> Result<u64, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let ids = workspace_feedback_ids(&mut tx, workspace_id, ids).await?;
+    if ids.is_empty() {
+        return Ok(0);
+    }
+    sqlx::query("INSERT OR IGNORE INTO tags (id, name) VALUES (?, ?)")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+    for id in &ids {
+        sqlx::query(
+            "INSERT OR IGNORE INTO feedback_tags (feedback_id, tag_id) SELECT ?, id FROM tags WHERE name = ?",
+        )
+        .bind(id)
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+    }
+    tx.commit().await?;
+    Ok(ids.len() as u64)
+}
+
+/// Delete every entry a respondent left in a workspace, spam included, along
+/// with its answers, tags, votes and replies, and drop their address from
+/// abuse reports they filed. Returns how many entries were deleted.
+pub async fn delete_feedback_by_respondent(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    email: &str,
+) -> Result<u64, sqlx::Error> {
+    const ENTRIES: &str = "SELECT f.id FROM feedback f JOIN prompts p ON p.id = f.prompt_id WHERE f.respondent_email = ? AND p.workspace_id = ?";
+
+    let mut tx = pool.begin().await?;
+    for table in FEEDBACK_CHILD_TABLES {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE feedback_id IN ({})",
+            table, ENTRIES
+        ))
+        .bind(email)
+        .bind(workspace_id)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let result = sqlx::query(&format!("DELETE FROM feedback WHERE id IN ({})", ENTRIES))
+        .bind(email)
+        .bind(workspace_id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query(
+        "UPDATE abuse_reports SET reporter_email = NULL WHERE reporter_email = ? AND prompt_id IN (SELECT id FROM prompts WHERE workspace_id = ?)",
+    )
+    .bind(email)
+    .bind(workspace_id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// Permanently delete a prompt's entries created before `cutoff`, spam
+/// included, with their answers, tags, votes and replies. Returns how many
+/// entries were deleted.
+pub async fn delete_feedback_before(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    cutoff: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    const ENTRIES: &str = "SELECT id FROM feedback WHERE prompt_id = ? AND created_at < ?";
+
+    let cutoff = cutoff.to_rfc3339();
+    let mut tx = pool.begin().await?;
+    for table in FEEDBACK_CHILD_TABLES {
+        sqlx::query(&format!(
+            "DELETE FROM {} WHERE feedback_id IN ({})",
+            table, ENTRIES
+        ))
+        .bind(prompt_id)
+        .bind(&cutoff)
+        .execute(&mut *tx)
+        .await?;
+    }
+    let result = sqlx::query(&format!("DELETE FROM feedback WHERE id IN ({})", ENTRIES))
+        .bind(prompt_id)
+        .bind(&cutoff)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected())
+}
+
+/// A quarantined submission alongside the prompt it answered
+#[derive(Debug, Clone, FromRow)]
+pub struct SpamEntry {
+    #[sqlx(flatten)]
+    pub feedback: Feedback,
+    pub prompt_title: String,
+    pub spam_reason: String,
+}
+
+/// Submissions quarantined as spam across the workspace, newest first
+pub async fn get_spam(
+    pool: &SqlitePool,
+    workspace_id: &str,
+) -> Result<Vec<SpamEntry>, sqlx::Error> {
+    sqlx::query_as::<_, SpamEntry>(&format!(
+        "SELECT {}, p.title AS prompt_title, f.spam_reason FROM feedback f JOIN prompts p ON p.id = f.prompt_id WHERE f.spam_reason IS NOT NULL AND p.workspace_id = ? ORDER BY f.created_at DESC",
+        qualified_feedback_columns()
+    ))
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Move a quarantined entry back into its prompt's feedback; returns false if
+/// the workspace has no such spam entry
+pub async fn release_spam(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let result = sqlx::query(
+        "UPDATE feedback SET spam_reason = NULL WHERE id = ? AND spam_reason IS NOT NULL AND prompt_id IN (SELECT id FROM prompts WHERE workspace_id = ?)",
+    )
+    .bind(id)
+    .bind(workspace_id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+    // A released entry takes a place like any other, even past the cap
+    sqlx::query(
+        "UPDATE prompts SET accepted_responses = accepted_responses + 1 WHERE id = (SELECT prompt_id FROM feedback WHERE id = ?)",
+    )
+    .bind(id)
+    .execute(&mut *tx)
+    .await?;
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Permanently delete a quarantined entry; returns false if the workspace has
+/// no such spam entry
+pub async fn delete_spam(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query("DELETE FROM feedback_tags WHERE feedback_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM answers WHERE feedback_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM votes WHERE feedback_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    sqlx::query("DELETE FROM replies WHERE feedback_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query(
+        "DELETE FROM feedback WHERE id = ? AND spam_reason IS NOT NULL AND prompt_id IN (SELECT id FROM prompts WHERE workspace_id = ?)",
+    )
+    .bind(id)
+    .bind(workspace_id)
+    .execute(&mut *tx)
+    .await?;
+    if result.rows_affected() == 0 {
+        // Leave everything hanging off a real entry alone
+        tx.rollback().await?;
+        return Ok(false);
+    }
+    tx.commit().await?;
+    Ok(true)
+}
+
+/// Canonical form of a tag name: lowercase words joined by hyphens.
+/// Returns None for names that are empty, too long, or contain other characters.
+pub fn normalize_tag(name: &str) -> Option<String> {
+    let normalized = name
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join("-")
+        .to_lowercase();
+    let valid = !normalized.is_empty()
+        && normalized.len() <= 32
+        && normalized
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    valid.then_some(normalized)
+}
+
+/// Attach a tag (creating it if new) to a feedback entry; `name` must be normalized
+pub async fn add_tag(pool: &SqlitePool, feedback_id: &str, name: &str) -> Result<(), sqlx::Error> {
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("INSERT OR IGNORE INTO tags (id, name) VALUES (?, ?)")
+        .bind(uuid::Uuid::new_v4().to_string())
+        .bind(name)
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        "INSERT OR IGNORE INTO feedback_tags (feedback_id, tag_id) SELECT ?, id FROM tags WHERE name = ?",
+    )
+    .bind(feedback_id)
+    .bind(name)
+    .execute(&mut *tx)
+    .await?;
+
+    tx.commit().await
+}
+
+pub async fn remove_tag(
+    pool: &SqlitePool,
+    feedback_id: &str,
+    name: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "DELETE FROM feedback_tags WHERE feedback_id = ? AND tag_id IN (SELECT id FROM tags WHERE name = ?)",
+    )
+    .bind(feedback_id)
+    .bind(name)
+    .execute(pool)
+    .await?;
+
+    Ok(())
+}
+
+pub async fn get_sentiment_breakdown(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<SentimentBreakdown, sqlx::Error> {
+    sqlx::query_as::<_, SentimentBreakdown>(
+        r#"
+        SELECT COALESCE(SUM(sentiment_label = 'positive'), 0) AS positive,
+               COALESCE(SUM(sentiment_label = 'neutral'), 0) AS neutral,
+               COALESCE(SUM(sentiment_label = 'negative'), 0) AS negative
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_one(pool)
+    .await
+}
+
+pub async fn get_status_counts(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<StatusCounts, sqlx::Error> {
+    sqlx::query_as::<_, StatusCounts>(
+        r#"
+        SELECT COALESCE(SUM(status = 'new'), 0) AS new,
+               COALESCE(SUM(status = 'reviewed'), 0) AS reviewed,
+               COALESCE(SUM(status = 'actioned'), 0) AS actioned,
+               COALESCE(SUM(status = 'dismissed'), 0) AS dismissed
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Move an entry through triage
+pub async fn set_feedback_status(
+    pool: &SqlitePool,
+    id: &str,
+    status: FeedbackStatus,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE feedback SET status = ? WHERE id = ?")
+        .bind(status.as_str())
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn get_results_summary(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<ResultsSummary, sqlx::Error> {
+    let week_ago = (Utc::now() - Duration::days(7)).to_rfc3339();
+    sqlx::query_as::<_, ResultsSummary>(
+        r#"
+        SELECT COUNT(*) AS responses,
+               COALESCE(SUM(created_at > ?), 0) AS recent
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL
+        "#,
+    )
+    .bind(&week_ago)
+    .bind(prompt_id)
+    .fetch_one(pool)
+    .await
+}
+
+/// Responses per day since `since` (RFC 3339), oldest first; days without
+/// any are left out
+pub async fn get_daily_counts(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    since: &str,
+) -> Result<Vec<DailyCount>, sqlx::Error> {
+    sqlx::query_as::<_, DailyCount>(
+        r#"
+        SELECT substr(created_at, 1, 10) AS day, COUNT(*) AS count
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL AND created_at >= ?
+        GROUP BY day
+        ORDER BY day
+        "#,
+    )
+    .bind(prompt_id)
+    .bind(since)
+    .fetch_all(pool)
+    .await
+}
+
+/// `(created_at, score)` for every NPS answer on a prompt, oldest first
+pub async fn get_nps_scores(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT created_at, nps_score
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL AND nps_score IS NOT NULL
+        ORDER BY created_at
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// `(reaction key, count)` across a prompt's feedback; unused reactions are
+/// left out
+pub async fn get_reaction_counts(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<(String, i64)>, sqlx::Error> {
+    sqlx::query_as(
+        r#"
+        SELECT reaction, COUNT(*)
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL AND reaction IS NOT NULL
+        GROUP BY reaction
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// All-time responses by hour of day; hours without any are left out
+pub async fn get_hourly_counts(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<HourlyCount>, sqlx::Error> {
+    sqlx::query_as::<_, HourlyCount>(
+        r#"
+        SELECT CAST(substr(created_at, 12, 2) AS INTEGER) AS hour, COUNT(*) AS count
+        FROM feedback
+        WHERE prompt_id = ? AND spam_reason IS NULL
+        GROUP BY hour
+        ORDER BY hour
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Averages for the prompt's fields that only ever got numeric answers, in
+/// form order. Fields with any free text are skipped as not ratings.
+pub async fn get_rating_averages(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<RatingAverage>, sqlx::Error> {
+    let rows: Vec<(String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT a.field_id, fl.label, a.value
+        FROM answers a
+        JOIN feedback f ON f.id = a.feedback_id
+        JOIN fields fl ON fl.id = a.field_id
+        JOIN prompt_fields pf ON pf.field_id = a.field_id AND pf.prompt_id = f.prompt_id
+        WHERE f.prompt_id = ? AND f.spam_reason IS NULL AND trim(a.value) != ''
+        ORDER BY pf.position
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await?;
+
+    let mut averages: Vec<(String, RatingAverage, bool)> = Vec::new();
+    for (field_id, label, value) in rows {
+        let index = match averages.iter().position(|(id, _, _)| *id == field_id) {
+            Some(index) => index,
+            None => {
+                let empty = RatingAverage {
+                    field_id: field_id.clone(),
+                    label,
+                    average: 0.0,
+                    responses: 0,
+                };
+                averages.push((field_id, empty, true));
+                averages.len() - 1
+            }
+        };
+        let (_, rating, numeric) = &mut averages[index];
+        match value.trim().parse::<f64>() {
+            Ok(n) if n.is_finite() => {
+                rating.average += n;
+                rating.responses += 1;
+            }
+            _ => *numeric = false,
+        }
+    }
+    Ok(averages
+        .into_iter()
+        .filter(|(_, _, numeric)| *numeric)
+        .map(|(_, mut rating, _)| {
+            rating.average /= rating.responses as f64;
+            rating
+        })
+        .collect())
+}
+
+/// A public board's entries, most upvoted first. Quarantined entries stay off
+/// the board. `voter` marks the entries that browser has already upvoted.
+pub async fn get_board(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    voter: Option<&str>,
+) -> Result<Vec<BoardEntry>, sqlx::Error> {
+    sqlx::query_as::<_, BoardEntry>(
+        r#"
+        SELECT f.id, f.content, f.created_at,
+               COUNT(v.voter) AS votes,
+               COALESCE(MAX(v.voter = ?), 0) AS voted
+        FROM feedback f
+        LEFT JOIN votes v ON v.feedback_id = f.id
+        WHERE f.prompt_id = ? AND f.spam_reason IS NULL
+        GROUP BY f.id
+        ORDER BY votes DESC, f.created_at DESC
+        "#,
+    )
+    .bind(voter)
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Upvote an entry on a prompt's board, once per voter. Returns the entry's
+/// vote count, or None if the prompt has no such visible entry.
+pub async fn add_vote(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    feedback_id: &str,
+    voter: &str,
+) -> Result<Option<i64>, sqlx::Error> {
+    let visible: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM feedback WHERE id = ? AND prompt_id = ? AND spam_reason IS NULL",
+    )
+    .bind(feedback_id)
+    .bind(prompt_id)
+    .fetch_optional(pool)
+    .await?;
+    if visible.is_none() {
+        return Ok(None);
+    }
+
+    sqlx::query("INSERT OR IGNORE INTO votes (feedback_id, voter, created_at) VALUES (?, ?, ?)")
+        .bind(feedback_id)
+        .bind(voter)
+        .bind(Utc::now().to_rfc3339())
+        .execute(pool)
+        .await?;
+    let (votes,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM votes WHERE feedback_id = ?")
+        .bind(feedback_id)
+        .fetch_one(pool)
+        .await?;
+    Ok(Some(votes))
+}
+
+/// Key for signing a prompt's results widget link; `None` while embedding is off
+pub async fn get_embed_secret(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT embed_secret FROM prompts WHERE id = ?")
+            .bind(prompt_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.and_then(|(secret,)| secret))
+}
+
+/// Turn embedding on with a fresh key (invalidating any earlier link), or off
+pub async fn set_embed_secret(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    enabled: bool,
+) -> Result<Option<String>, sqlx::Error> {
+    let secret = enabled.then(|| uuid::Uuid::new_v4().simple().to_string());
+    sqlx::query("UPDATE prompts SET embed_secret = ? WHERE id = ?")
+        .bind(&secret)
+        .bind(prompt_id)
+        .execute(pool)
+        .await?;
+    Ok(secret)
+}
+
+/// Key for signing a prompt's Atom feed link; `None` while the feed is off
+pub async fn get_feed_secret(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT feed_secret FROM prompts WHERE id = ?")
+            .bind(prompt_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.and_then(|(secret,)| secret))
+}
+
+/// Turn the feed on with a fresh key (invalidating any earlier link), or off
+pub async fn set_feed_secret(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    enabled: bool,
+) -> Result<Option<String>, sqlx::Error> {
+    let secret = enabled.then(|| uuid::Uuid::new_v4().simple().to_string());
+    sqlx::query("UPDATE prompts SET feed_secret = ? WHERE id = ?")
+        .bind(&secret)
+        .bind(prompt_id)
+        .execute(pool)
+        .await?;
+    Ok(secret)
+}
+
+/// Key for signing a prompt's prefill links and form render stamps, created
+/// the first time one is needed
+pub async fn get_or_create_prefill_secret(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<String, sqlx::Error> {
+    let secret = uuid::Uuid::new_v4().simple().to_string();
+    sqlx::query("UPDATE prompts SET prefill_secret = ? WHERE id = ? AND prefill_secret IS NULL")
+        .bind(&secret)
+        .bind(prompt_id)
+        .execute(pool)
+        .await?;
+    get_prefill_secret(pool, prompt_id)
+        .await
+        .map(|secret| secret.unwrap_or_default())
+}
+
+/// Key a prompt's prefill links are signed with; `None` until one has been made
+pub async fn get_prefill_secret(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Option<String>, sqlx::Error> {
+    let row: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT prefill_secret FROM prompts WHERE id = ?")
+            .bind(prompt_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(row.and_then(|(secret,)| secret))
+}
+
+/// The text of a prompt's feedback, spam excluded, for word counts
+pub async fn get_feedback_contents(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<String>, sqlx::Error> {
+    sqlx::query_scalar("SELECT content FROM feedback WHERE prompt_id = ? AND spam_reason IS NULL")
+        .bind(prompt_id)
+        .fetch_all(pool)
+        .await
+}
+
+/// Tags used on a prompt's feedback with how many entries carry each
+pub async fn get_tag_counts_for_prompt(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<TagCount>, sqlx::Error> {
+    sqlx::query_as::<_, TagCount>(
+        r#"
+        SELECT t.name, COUNT(*) AS count
+        FROM feedback_tags ft
+        JOIN tags t ON t.id = ft.tag_id
+        JOIN feedback f ON f.id = ft.feedback_id
+        WHERE f.prompt_id = ? AND f.spam_reason IS NULL
+        GROUP BY t.name
+        ORDER BY t.name
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Marks the start and end of a matched term inside `SearchHit::snippet`
+pub const SNIPPET_MATCH_START: char = '\u{2}';
+pub const SNIPPET_MATCH_END: char = '\u{3}';
+
+#[derive(Debug, Clone, FromRow)]
+pub struct SearchHit {
+    pub feedback_id: String,
+    pub prompt_id: String,
+    pub prompt_title: String,
+    pub snippet: String,
+    pub created_at: String,
+}
+
+/// Turn free text into an FTS5 query that matches entries containing every word.
+/// Each word is quoted so user input can't be parsed as FTS5 syntax.
+fn fts_query(query: &str) -> String {
+    query
+        .split_whitespace()
+        .map(|word| format!("\"{}\"", word.replace('"', "\"\"")))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+pub async fn search_feedback(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    query: &str,
+) -> Result<Vec<SearchHit>, sqlx::Error> {
+    let fts_query = fts_query(query);
+    if fts_query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    sqlx::query_as::<_, SearchHit>(
+        r#"
+        SELECT f.id AS feedback_id, f.prompt_id, p.title AS prompt_title,
+               snippet(feedback_fts, 1, char(2), char(3), '…', 16) AS snippet,
+               f.created_at
+        FROM feedback_fts
+        JOIN feedback f ON f.id = feedback_fts.feedback_id
+        JOIN prompts p ON p.id = f.prompt_id
+        WHERE feedback_fts MATCH ? AND p.workspace_id = ? AND f.spam_reason IS NULL
+        ORDER BY rank
+        LIMIT 200
+        "#,
+    )
+    .bind(fts_query)
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Open prompts that repeat; closed ones don't start new cycles
+pub async fn get_recurring_prompts(pool: &SqlitePool) -> Result<Vec<Prompt>, sqlx::Error> {
+    sqlx::query_as::<_, Prompt>(&format!(
+        "SELECT {} FROM prompts WHERE recurrence IS NOT NULL AND closed_at IS NULL",
+        PROMPT_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+async fn get_cycle_by_number(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    number: i64,
+) -> Result<Option<Cycle>, sqlx::Error> {
+    sqlx::query_as::<_, Cycle>(
+        "SELECT id, prompt_id, number, starts_at, ends_at FROM cycles WHERE prompt_id = ? AND number = ?",
+    )
+    .bind(prompt_id)
+    .bind(number)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Insert a cycle unless another writer already created that number, then return it
+async fn ensure_cycle(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    number: i64,
+    starts_at: DateTime<Utc>,
+    ends_at: DateTime<Utc>,
+) -> Result<Cycle, sqlx::Error> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO cycles (id, prompt_id, number, starts_at, ends_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(prompt_id)
+    .bind(number)
+    .bind(starts_at.to_rfc3339())
+    .bind(ends_at.to_rfc3339())
+    .execute(pool)
+    .await?;
+
+    get_cycle_by_number(pool, prompt_id, number)
+        .await?
+        .ok_or(sqlx::Error::RowNotFound)
+}
+
+/// Return the cycle covering `now` for a recurring prompt, opening any cycles
+/// whose periods have started since the last one. Non-recurring prompts have none.
+pub async fn current_cycle(
+    pool: &SqlitePool,
+    prompt: &Prompt,
+    now: DateTime<Utc>,
+) -> Result<Option<Cycle>, sqlx::Error> {
+    let Some(recurrence) = prompt.recurrence() else {
+        return Ok(None);
+    };
+
+    let first = sqlx::query_as::<_, Cycle>(
+        "SELECT id, prompt_id, number, starts_at, ends_at FROM cycles WHERE prompt_id = ? ORDER BY number ASC LIMIT 1",
+    )
+    .bind(&prompt.id)
+    .fetch_optional(pool)
+    .await?;
+    let latest = sqlx::query_as::<_, Cycle>(
+        "SELECT id, prompt_id, number, starts_at, ends_at FROM cycles WHERE prompt_id = ? ORDER BY number DESC LIMIT 1",
+    )
+    .bind(&prompt.id)
+    .fetch_optional(pool)
+    .await?;
+    let parse = |at: &str| {
+        DateTime::parse_from_rfc3339(at)
+            .map(|at| at.with_timezone(&Utc))
+            .map_err(|e| sqlx::Error::Decode(Box::new(e)))
+    };
+
+    // Periods are counted from the first cycle, so month-end clamping in one
+    // cycle doesn't carry into the next
+    let (anchor, first_number) = match &first {
+        Some(first) => (parse(&first.starts_at)?, first.number),
+        None => (now, 1),
+    };
+    let ends_at_for = |number: i64| {
+        let periods = u32::try_from(number - first_number + 1).unwrap_or(0);
+        recurrence.nth(anchor, periods)
+    };
+
+    let mut cycle = match latest {
+        Some(cycle) => cycle,
+        None => ensure_cycle(pool, &prompt.id, 1, now, ends_at_for(1)).await?,
+    };
+
+    loop {
+        let ends_at = parse(&cycle.ends_at)?;
+        if ends_at > now {
+            return Ok(Some(cycle));
+        }
+        let number = cycle.number + 1;
+        cycle = ensure_cycle(pool, &prompt.id, number, ends_at, ends_at_for(number)).await?;
+    }
+}
+
+/// Cycles of a prompt with their response counts, newest first
+pub async fn get_cycle_summaries(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<CycleSummary>, sqlx::Error> {
+    sqlx::query_as::<_, CycleSummary>(
+        r#"
+        SELECT c.id, c.number, c.starts_at, c.ends_at, COUNT(f.id) AS response_count
+        FROM cycles c
+        LEFT JOIN feedback f ON f.cycle_id = c.id AND f.spam_reason IS NULL
+        WHERE c.prompt_id = ?
+        GROUP BY c.id
+        ORDER BY c.number DESC
+        "#,
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete a prompt and everything hanging off it; returns false if the
+/// workspace has no such prompt
+pub async fn delete_prompt(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<bool, sqlx::Error> {
+    if get_workspace_prompt(pool, workspace_id, id)
+        .await?
+        .is_none()
+    {
+        return Ok(false);
+    }
+
+    sqlx::query(
+        "DELETE FROM feedback_tags WHERE feedback_id IN (SELECT id FROM feedback WHERE prompt_id = ?)",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "DELETE FROM answers WHERE feedback_id IN (SELECT id FROM feedback WHERE prompt_id = ?)",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "DELETE FROM votes WHERE feedback_id IN (SELECT id FROM feedback WHERE prompt_id = ?)",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    sqlx::query(
+        "DELETE FROM replies WHERE feedback_id IN (SELECT id FROM feedback WHERE prompt_id = ?)",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    sqlx::query("DELETE FROM prompt_fields WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM abuse_reports WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    sqlx::query(
+        "DELETE FROM digest_entries WHERE rule_id IN (SELECT id FROM notification_rules WHERE prompt_id = ?)",
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    sqlx::query("DELETE FROM notification_rules WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM prompt_translations WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    sqlx::query("DELETE FROM invites WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    // Delete all feedback for this prompt first (foreign key constraint)
+    sqlx::query("DELETE FROM feedback WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    sqlx::query("DELETE FROM cycles WHERE prompt_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    // Delete the prompt
+    sqlx::query("DELETE FROM prompts WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+
+    Ok(true)
+}
+
+pub async fn count_users(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users")
+        .fetch_one(pool)
+        .await
+}
+
+pub async fn count_owners(pool: &SqlitePool) -> Result<i64, sqlx::Error> {
+    sqlx::query_scalar("SELECT COUNT(*) FROM users WHERE role = ?")
+        .bind(Role::Owner.as_str())
+        .fetch_one(pool)
+        .await
+}
+
+/// Create an admin account; `email` must be lowercase. Returns None if the email is taken.
+pub async fn create_user(
+    pool: &SqlitePool,
+    email: &str,
+    password_hash: &str,
+    role: Role,
+) -> Result<Option<User>, sqlx::Error> {
+    let user = User {
+        id: uuid::Uuid::new_v4().to_string(),
+        email: email.to_string(),
+        password_hash: password_hash.to_string(),
+        role: role.as_str().to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        oidc_subject: None,
+        digest_subscribed: false,
+    };
+
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO users (id, email, password_hash, role, created_at) VALUES (?, ?, ?, ?, ?)",
+    )
+    .bind(&user.id)
+    .bind(&user.email)
+    .bind(&user.password_hash)
+    .bind(&user.role)
+    .bind(&user.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok((result.rows_affected() > 0).then_some(user))
+}
+
+/// Create the first admin account as an owner; `email` must be lowercase.
+/// Returns None if any account already exists.
+pub async fn create_first_owner(
+    pool: &SqlitePool,
+    email: &str,
+    password_hash: &str,
+) -> Result<Option<User>, sqlx::Error> {
+    let user = User {
+        id: uuid::Uuid::new_v4().to_string(),
+        email: email.to_string(),
+        password_hash: password_hash.to_string(),
+        role: Role::Owner.as_str().to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        oidc_subject: None,
+        digest_subscribed: false,
+    };
+
+    // One statement, so two first-run wizards can't both succeed
+    let result = sqlx::query(
+        "INSERT INTO users (id, email, password_hash, role, created_at)
+         SELECT ?, ?, ?, ?, ? WHERE NOT EXISTS (SELECT 1 FROM users)",
+    )
+    .bind(&user.id)
+    .bind(&user.email)
+    .bind(&user.password_hash)
+    .bind(&user.role)
+    .bind(&user.created_at)
+    .execute(pool)
+    .await?;
+
+    Ok((result.rows_affected() > 0).then_some(user))
+}
+
+pub async fn get_users(pool: &SqlitePool) -> Result<Vec<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {} FROM users ORDER BY email",
+        USER_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+pub async fn get_user_by_id(pool: &SqlitePool, id: &str) -> Result<Option<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(&format!("SELECT {} FROM users WHERE id = ?", USER_COLUMNS))
+        .bind(id)
+        .fetch_optional(pool)
+        .await
+}
+
+pub async fn get_user_by_email(
+    pool: &SqlitePool,
+    email: &str,
+) -> Result<Option<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {} FROM users WHERE email = ?",
+        USER_COLUMNS
+    ))
+    .bind(email)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn get_user_by_oidc_subject(
+    pool: &SqlitePool,
+    subject: &str,
+) -> Result<Option<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {} FROM users WHERE oidc_subject = ?",
+        USER_COLUMNS
+    ))
+    .bind(subject)
+    .fetch_optional(pool)
+    .await
+}
+
+/// Link an OIDC identity to an account that doesn't have one yet
+pub async fn link_oidc_subject(
+    pool: &SqlitePool,
+    user_id: &str,
+    subject: &str,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE users SET oidc_subject = ? WHERE id = ? AND oidc_subject IS NULL")
+            .bind(subject)
+            .bind(user_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn set_user_role(pool: &SqlitePool, id: &str, role: Role) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET role = ? WHERE id = ?")
+        .bind(role.as_str())
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Turn the scheduled feedback digest on or off for an account
+pub async fn set_digest_subscribed(
+    pool: &SqlitePool,
+    id: &str,
+    subscribed: bool,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("UPDATE users SET digest_subscribed = ? WHERE id = ?")
+        .bind(subscribed)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Accounts that get the scheduled feedback digest
+pub async fn get_digest_subscribers(pool: &SqlitePool) -> Result<Vec<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(&format!(
+        "SELECT {} FROM users WHERE digest_subscribed = 1 ORDER BY email",
+        USER_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete an account and sign it out everywhere
+pub async fn delete_user(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query("DELETE FROM sessions WHERE user_id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    let result = sqlx::query("DELETE FROM users WHERE id = ?")
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn create_session(
+    pool: &SqlitePool,
+    token_digest: &str,
+    user_id: &str,
+    expires_at: DateTime<Utc>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT INTO sessions (id, user_id, created_at, expires_at) VALUES (?, ?, ?, ?)")
+        .bind(token_digest)
+        .bind(user_id)
+        .bind(Utc::now().to_rfc3339())
+        .bind(expires_at.to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// The user signed in by a session, if it exists and hasn't expired
+pub async fn get_session_user(
+    pool: &SqlitePool,
+    token_digest: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<User>, sqlx::Error> {
+    sqlx::query_as::<_, User>(
+        r#"
+        SELECT u.id, u.email, u.password_hash, u.role, u.created_at, u.oidc_subject,
+               u.digest_subscribed
+        FROM sessions s
+        JOIN users u ON u.id = s.user_id
+        WHERE s.id = ? AND s.expires_at > ?
+        "#,
+    )
+    .bind(token_digest)
+    .bind(now.to_rfc3339())
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn delete_session(pool: &SqlitePool, token_digest: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM sessions WHERE id = ?")
+        .bind(token_digest)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+pub async fn create_api_token(
+    pool: &SqlitePool,
+    name: &str,
+    token_digest: &str,
+    role: Role,
+    workspace_id: Option<&str>,
+) -> Result<ApiToken, sqlx::Error> {
+    let token = ApiToken {
+        id: uuid::Uuid::new_v4().to_string(),
+        name: name.to_string(),
+        role: role.as_str().to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        last_used_at: None,
+        revoked_at: None,
+        workspace_id: workspace_id.map(str::to_string),
+    };
+
+    sqlx::query(
+        "INSERT INTO api_tokens (id, name, token_digest, role, created_at, workspace_id) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&token.id)
+    .bind(&token.name)
+    .bind(token_digest)
+    .bind(&token.role)
+    .bind(&token.created_at)
+    .bind(&token.workspace_id)
+    .execute(pool)
+    .await?;
+
+    Ok(token)
+}
+
+/// Every token, revoked ones included, newest first
+pub async fn get_api_tokens(pool: &SqlitePool) -> Result<Vec<ApiToken>, sqlx::Error> {
+    sqlx::query_as::<_, ApiToken>(&format!(
+        "SELECT {} FROM api_tokens ORDER BY created_at DESC",
+        API_TOKEN_COLUMNS
+    ))
+    .fetch_all(pool)
+    .await
+}
+
+/// Whether a token with this digest was ever minted, revoked or not
+pub async fn api_token_exists(pool: &SqlitePool, token_digest: &str) -> Result<bool, sqlx::Error> {
+    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM api_tokens WHERE token_digest = ?)")
+        .bind(token_digest)
+        .fetch_one(pool)
+        .await
+}
+
+/// Look up an unrevoked token by digest, recording that it was used
+pub async fn use_api_token(
+    pool: &SqlitePool,
+    token_digest: &str,
+    now: DateTime<Utc>,
+) -> Result<Option<ApiToken>, sqlx::Error> {
+    sqlx::query_as::<_, ApiToken>(&format!(
+        "UPDATE api_tokens SET last_used_at = ? WHERE token_digest = ? AND revoked_at IS NULL RETURNING {}",
+        API_TOKEN_COLUMNS
+    ))
+    .bind(now.to_rfc3339())
+    .bind(token_digest)
+    .fetch_optional(pool)
+    .await
+}
+
+pub async fn revoke_api_token(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("UPDATE api_tokens SET revoked_at = ? WHERE id = ? AND revoked_at IS NULL")
+            .bind(Utc::now().to_rfc3339())
+            .bind(id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn create_field(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    spec: &FieldSpec,
+) -> Result<Field, sqlx::Error> {
+    let field = Field {
+        id: uuid::Uuid::new_v4().to_string(),
+        workspace_id: workspace_id.to_string(),
+        label: spec.label.clone(),
+        help_text: spec.help_text.clone(),
+        kind: spec.kind.as_str().to_string(),
+        required: spec.required,
+        min_length: spec.rules.min_length,
+        max_length: spec.rules.max_length,
+        pattern: spec.rules.pattern.clone(),
+        created_at: Utc::now().to_rfc3339(),
+        updated_at: None,
+    };
+    sqlx::query(&format!(
+        "INSERT INTO fields ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        FIELD_COLUMNS
+    ))
+    .bind(&field.id)
+    .bind(&field.workspace_id)
+    .bind(&field.label)
+    .bind(&field.help_text)
+    .bind(&field.kind)
+    .bind(field.required)
+    .bind(field.min_length)
+    .bind(field.max_length)
+    .bind(&field.pattern)
+    .bind(&field.created_at)
+    .bind(&field.updated_at)
+    .execute(pool)
+    .await?;
+    Ok(field)
+}
+
+/// Edit a field in place; every prompt using it picks up the change.
+/// Returns false if the workspace has no such field.
+pub async fn update_field(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+    spec: &FieldSpec,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE fields SET label = ?, help_text = ?, kind = ?, required = ?, min_length = ?, max_length = ?, pattern = ?, updated_at = ? WHERE id = ? AND workspace_id = ?",
+    )
+    .bind(&spec.label)
+    .bind(&spec.help_text)
+    .bind(spec.kind.as_str())
+    .bind(spec.required)
+    .bind(spec.rules.min_length)
+    .bind(spec.rules.max_length)
+    .bind(&spec.rules.pattern)
+    .bind(Utc::now().to_rfc3339())
+    .bind(id)
+    .bind(workspace_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn get_field(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<Option<Field>, sqlx::Error> {
+    sqlx::query_as::<_, Field>(&format!(
+        "SELECT {} FROM fields WHERE id = ? AND workspace_id = ?",
+        FIELD_COLUMNS
+    ))
+    .bind(id)
+    .bind(workspace_id)
+    .fetch_optional(pool)
+    .await
+}
+
+/// The workspace's question bank with how many prompts use each field and
+/// how many answers it has collected, alphabetically
+pub async fn get_field_usage(
+    pool: &SqlitePool,
+    workspace_id: &str,
+) -> Result<Vec<FieldUsage>, sqlx::Error> {
+    sqlx::query_as::<_, FieldUsage>(&format!(
+        r#"
+        SELECT {},
+               (SELECT COUNT(*) FROM prompt_fields pf WHERE pf.field_id = fields.id) AS prompt_count,
+               (SELECT COUNT(*) FROM answers a WHERE a.field_id = fields.id) AS answer_count
+        FROM fields
+        WHERE workspace_id = ?
+        ORDER BY label COLLATE NOCASE
+        "#,
+        FIELD_COLUMNS
+    ))
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Delete a field and its answers. Fields still on a prompt are kept;
+/// returns false in that case.
+pub async fn delete_field(pool: &SqlitePool, id: &str) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    let (in_use,): (bool,) =
+        sqlx::query_as("SELECT EXISTS (SELECT 1 FROM prompt_fields WHERE field_id = ?)")
+            .bind(id)
+            .fetch_one(&mut *tx)
+            .await?;
+    if in_use {
+        return Ok(false);
+    }
+    sqlx::query("DELETE FROM answers WHERE field_id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    let result = sqlx::query("DELETE FROM fields WHERE id = ?")
+        .bind(id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A prompt's translations, by language code
+pub async fn get_prompt_translations(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<PromptTranslation>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, PromptTranslationRow>(
+        "SELECT language, title, description, field_labels, updated_at FROM prompt_translations WHERE prompt_id = ? ORDER BY language",
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(PromptTranslation::from).collect())
+}
+
+/// Add a translation, or replace the prompt's existing one in that language
+pub async fn set_prompt_translation(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    translation: &PromptTranslation,
+) -> Result<PromptTranslation, sqlx::Error> {
+    let saved = PromptTranslation {
+        updated_at: Utc::now().to_rfc3339(),
+        ..translation.clone()
+    };
+    sqlx::query(
+        r#"
+        INSERT INTO prompt_translations (prompt_id, language, title, description, field_labels, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?)
+        ON CONFLICT (prompt_id, language) DO UPDATE SET
+            title = excluded.title,
+            description = excluded.description,
+            field_labels = excluded.field_labels,
+            updated_at = excluded.updated_at
+        "#,
+    )
+    .bind(prompt_id)
+    .bind(&saved.language)
+    .bind(&saved.title)
+    .bind(&saved.description)
+    .bind(serde_json::to_string(&saved.field_labels).unwrap_or_default())
+    .bind(&saved.updated_at)
+    .execute(pool)
+    .await?;
+    Ok(saved)
+}
+
+pub async fn delete_prompt_translation(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    language: &str,
+) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM prompt_translations WHERE prompt_id = ? AND language = ?")
+            .bind(prompt_id)
+            .bind(language)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A workspace's export templates, by name
+pub async fn get_export_templates(
+    pool: &SqlitePool,
+    workspace_id: &str,
+) -> Result<Vec<ExportTemplate>, sqlx::Error> {
+    let rows = sqlx::query_as::<_, ExportTemplateRow>(&format!(
+        "SELECT {} FROM export_templates WHERE workspace_id = ? ORDER BY name COLLATE NOCASE",
+        EXPORT_TEMPLATE_COLUMNS
+    ))
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.into_iter().map(ExportTemplate::from).collect())
+}
+
+pub async fn get_export_template(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<Option<ExportTemplate>, sqlx::Error> {
+    let row = sqlx::query_as::<_, ExportTemplateRow>(&format!(
+        "SELECT {} FROM export_templates WHERE workspace_id = ? AND id = ?",
+        EXPORT_TEMPLATE_COLUMNS
+    ))
+    .bind(workspace_id)
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+    Ok(row.map(ExportTemplate::from))
+}
+
+pub async fn create_export_template(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    name: &str,
+    columns: &[ExportColumn],
+    date_format: &str,
+) -> Result<ExportTemplate, sqlx::Error> {
+    let now = Utc::now().to_rfc3339();
+    let template = ExportTemplate {
+        id: uuid::Uuid::new_v4().to_string(),
+        workspace_id: workspace_id.to_string(),
+        name: name.to_string(),
+        columns: columns.to_vec(),
+        date_format: date_format.to_string(),
+        created_at: now.clone(),
+        updated_at: now,
+    };
+    sqlx::query(&format!(
+        "INSERT INTO export_templates ({}) VALUES (?, ?, ?, ?, ?, ?, ?)",
+        EXPORT_TEMPLATE_COLUMNS
+    ))
+    .bind(&template.id)
+    .bind(&template.workspace_id)
+    .bind(&template.name)
+    .bind(serde_json::to_string(&template.columns).unwrap_or_default())
+    .bind(&template.date_format)
+    .bind(&template.created_at)
+    .bind(&template.updated_at)
+    .execute(pool)
+    .await?;
+    Ok(template)
+}
+
+/// Save a template's name, columns and date format. Returns false if it no
+/// longer exists.
+pub async fn update_export_template(
+    pool: &SqlitePool,
+    template: &ExportTemplate,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE export_templates SET name = ?, columns = ?, date_format = ?, updated_at = ? WHERE workspace_id = ? AND id = ?",
+    )
+    .bind(&template.name)
+    .bind(serde_json::to_string(&template.columns).unwrap_or_default())
+    .bind(&template.date_format)
+    .bind(Utc::now().to_rfc3339())
+    .bind(&template.workspace_id)
+    .bind(&template.id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn delete_export_template(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    id: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM export_templates WHERE workspace_id = ? AND id = ?")
+        .bind(workspace_id)
+        .bind(id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// A prompt's fields in the order they appear on its form
+pub async fn get_prompt_fields(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<Field>, sqlx::Error> {
+    sqlx::query_as::<_, Field>(&format!(
+        "SELECT {} FROM fields JOIN prompt_fields pf ON pf.field_id = fields.id WHERE pf.prompt_id = ? ORDER BY pf.position",
+        FIELD_COLUMNS
+            .split(", ")
+            .map(|c| format!("fields.{}", c))
+            .collect::<Vec<_>>()
+            .join(", ")
+    ))
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Add a field to the end of a prompt's form; adding it twice is a no-op
+pub async fn add_prompt_field(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    field_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT OR IGNORE INTO prompt_fields (prompt_id, field_id, position) SELECT ?, ?, COALESCE(MAX(position), 0) + 1 FROM prompt_fields WHERE prompt_id = ?",
+    )
+    .bind(prompt_id)
+    .bind(field_id)
+    .bind(prompt_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Take a field off a prompt's form; answers already given are kept
+pub async fn remove_prompt_field(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    field_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM prompt_fields WHERE prompt_id = ? AND field_id = ?")
+        .bind(prompt_id)
+        .bind(field_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// A prompt's notification rules in the order they're checked
+pub async fn get_notification_rules(
+    pool: &SqlitePool,
+    prompt_id: &str,
+) -> Result<Vec<NotificationRule>, sqlx::Error> {
+    sqlx::query_as::<_, NotificationRule>(&format!(
+        "SELECT {} FROM notification_rules WHERE prompt_id = ? ORDER BY position",
+        NOTIFICATION_RULE_COLUMNS
+    ))
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Add a rule after the prompt's existing ones
+pub async fn create_notification_rule(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    condition: &notify::Condition,
+    delivery: notify::Delivery,
+    target_url: &str,
+) -> Result<NotificationRule, sqlx::Error> {
+    let (position,): (i64,) = sqlx::query_as(
+        "SELECT COALESCE(MAX(position), 0) + 1 FROM notification_rules WHERE prompt_id = ?",
+    )
+    .bind(prompt_id)
+    .fetch_one(pool)
+    .await?;
+    let rule = NotificationRule {
+        id: uuid::Uuid::new_v4().to_string(),
+        prompt_id: prompt_id.to_string(),
+        position,
+        condition: condition.kind().to_string(),
+        field_id: condition.field_id().map(str::to_string),
+        operand: condition.operand(),
+        delivery: delivery.as_str().to_string(),
+        target_url: target_url.to_string(),
+        created_at: Utc::now().to_rfc3339(),
+        last_digest_at: None,
+    };
+    sqlx::query(&format!(
+        "INSERT INTO notification_rules ({}) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)",
+        NOTIFICATION_RULE_COLUMNS
+    ))
+    .bind(&rule.id)
+    .bind(&rule.prompt_id)
+    .bind(rule.position)
+    .bind(&rule.condition)
+    .bind(&rule.field_id)
+    .bind(&rule.operand)
+    .bind(&rule.delivery)
+    .bind(&rule.target_url)
+    .bind(&rule.created_at)
+    .bind(&rule.last_digest_at)
+    .execute(pool)
+    .await?;
+    Ok(rule)
+}
+
+/// Delete a rule along with any entries waiting for its digest
+pub async fn delete_notification_rule(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    id: &str,
+) -> Result<bool, sqlx::Error> {
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        "DELETE FROM digest_entries WHERE rule_id IN (SELECT id FROM notification_rules WHERE id = ? AND prompt_id = ?)",
+    )
+    .bind(id)
+    .bind(prompt_id)
+    .execute(&mut *tx)
+    .await?;
+    let result = sqlx::query("DELETE FROM notification_rules WHERE id = ? AND prompt_id = ?")
+        .bind(id)
+        .bind(prompt_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(result.rows_affected() > 0)
+}
+
+pub async fn queue_digest_entry(
+    pool: &SqlitePool,
+    rule_id: &str,
+    feedback_id: &str,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("INSERT OR IGNORE INTO digest_entries (rule_id, feedback_id) VALUES (?, ?)")
+        .bind(rule_id)
+        .bind(feedback_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Digest rules with queued entries that were last sent (or, if never,
+/// created) at or before `before`
+pub async fn get_due_digest_rules(
+    pool: &SqlitePool,
+    before: &str,
+) -> Result<Vec<NotificationRule>, sqlx::Error> {
+    sqlx::query_as::<_, NotificationRule>(&format!(
+        r#"
+        SELECT {} FROM notification_rules
+        WHERE delivery = 'digest'
+          AND COALESCE(last_digest_at, created_at) <= ?
+          AND id IN (SELECT rule_id FROM digest_entries)
+        "#,
+        NOTIFICATION_RULE_COLUMNS
+    ))
+    .bind(before)
+    .fetch_all(pool)
+    .await
+}
+
+/// The entries queued for a digest, oldest first. Entries deleted or
+/// quarantined since they were queued are left out.
+pub async fn get_digest_entries(
+    pool: &SqlitePool,
+    rule_id: &str,
+) -> Result<Vec<Feedback>, sqlx::Error> {
+    sqlx::query_as::<_, Feedback>(&format!(
+        r#"
+        SELECT {} FROM feedback
+        WHERE id IN (SELECT feedback_id FROM digest_entries WHERE rule_id = ?)
+          AND spam_reason IS NULL
+        ORDER BY created_at
+        "#,
+        FEEDBACK_COLUMNS
+    ))
+    .bind(rule_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Unqueue the entries a digest included (plus any that have since gone
+/// away) and record when it went out. Entries queued meanwhile stay queued.
+pub async fn finish_digest(
+    pool: &SqlitePool,
+    rule_id: &str,
+    sent: &[Feedback],
+    sent_at: &str,
+) -> Result<(), sqlx::Error> {
+    let ids: Vec<&str> = sent.iter().map(|fb| fb.id.as_str()).collect();
+    let mut tx = pool.begin().await?;
+    sqlx::query(
+        r#"
+        DELETE FROM digest_entries
+        WHERE rule_id = ?
+          AND (feedback_id IN (SELECT value FROM json_each(?))
+               OR feedback_id NOT IN (SELECT id FROM feedback WHERE spam_reason IS NULL))
+        "#,
+    )
+    .bind(rule_id)
+    .bind(serde_json::to_string(&ids).unwrap_or_default())
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("UPDATE notification_rules SET last_digest_at = ? WHERE id = ?")
+        .bind(sent_at)
+        .bind(rule_id)
+        .execute(&mut *tx)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
 
-    sqlx::query("INSERT INTO prompts (id, title, description, created_at) VALUES (?, ?, ?, ?)")
-        .bind(&id)
-        .bind(title)
-        .bind(description)
-        .bind(&created_at)
+/// Mark a form's submission token as used; false means it already was, so
+/// the POST is a duplicate
+pub async fn claim_submission_token(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    token: &str,
+) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "INSERT OR IGNORE INTO submission_tokens (token, prompt_id, created_at) VALUES (?, ?, ?)",
+    )
+    .bind(token)
+    .bind(prompt_id)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Give a token back when its submission couldn't be stored, so a retry works
+pub async fn release_submission_token(pool: &SqlitePool, token: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("DELETE FROM submission_tokens WHERE token = ?")
+        .bind(token)
         .execute(pool)
         .await?;
+    Ok(())
+}
 
-    Ok(Prompt {
-        id,
-        title: title.to_string(),
-        description: description.to_string(),
-        created_at,
-    })
+/// Forget tokens used before `before`; duplicates arrive within seconds
+pub async fn prune_submission_tokens(
+    pool: &SqlitePool,
+    before: DateTime<Utc>,
+) -> Result<u64, sqlx::Error> {
+    let result = sqlx::query("DELETE FROM submission_tokens WHERE created_at < ?")
+        .bind(before.to_rfc3339())
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected())
 }
 
-pub async fn get_all_prompts(pool: &SqlitePool) -> Result<Vec<Prompt>, sqlx::Error> {
-    sqlx::query_as::<_, Prompt>(
-        "SELECT id, title, description, created_at FROM prompts ORDER BY created_at DESC",
-    )
-    .fetch_all(pool)
-    .await
-}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_test_db() -> SqlitePool {
+        init_db("sqlite::memory:").await.unwrap()
+    }
+
+    fn respondent(email: &str) -> FeedbackOptions {
+        FeedbackOptions {
+            respondent_email: Some(email.to_string()),
+            email_verified: true,
+            ..Default::default()
+        }
+    }
+
+    /// A database file in a fresh temporary directory; WAL needs a real file
+    async fn file_db(config: &PoolConfig) -> (SqlitePool, std::path::PathBuf) {
+        let dir = std::env::temp_dir().join(format!("pool-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let url = format!("sqlite:{}?mode=rwc", dir.join("feedback.db").display());
+        (init_db_with(&url, config).await.unwrap(), dir)
+    }
+
+    #[tokio::test]
+    async fn test_wal_lets_writes_through_open_reads() {
+        // An open read transaction holds the rollback journal's shared lock,
+        // so a writer that won't wait is refused
+        let rollback = PoolConfig {
+            wal: false,
+            busy_timeout: std::time::Duration::ZERO,
+            ..PoolConfig::default()
+        };
+        let (pool, dir) = file_db(&rollback).await;
+        let prompt = create_prompt(&pool, "Locks", "Desc").await.unwrap();
+        let mut reader = pool.begin().await.unwrap();
+        sqlx::query("SELECT COUNT(*) FROM feedback")
+            .fetch_one(&mut *reader)
+            .await
+            .unwrap();
+        let e = create_feedback(&pool, &prompt.id, "Blocked")
+            .await
+            .unwrap_err();
+        assert!(e.to_string().contains("database is locked"));
+        reader.rollback().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let (pool, dir) = file_db(&PoolConfig::default()).await;
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(mode, "wal");
+        let prompt = create_prompt(&pool, "Locks", "Desc").await.unwrap();
+        let mut reader = pool.begin().await.unwrap();
+        sqlx::query("SELECT COUNT(*) FROM feedback")
+            .fetch_one(&mut *reader)
+            .await
+            .unwrap();
+        create_feedback(&pool, &prompt.id, "Through").await.unwrap();
+        reader.rollback().await.unwrap();
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_concurrent_submissions_under_load() {
+        const TASKS: usize = 20;
+        const SUBMISSIONS: usize = 20;
+        let (pool, dir) = file_db(&PoolConfig::default()).await;
+        let prompt = create_prompt(&pool, "Load", "Desc").await.unwrap();
+
+        let mut handles = Vec::new();
+        for task in 0..TASKS {
+            let pool = pool.clone();
+            let prompt_id = prompt.id.clone();
+            handles.push(tokio::spawn(async move {
+                for n in 0..SUBMISSIONS {
+                    let content = format!("Submission {} from task {}", n, task);
+                    create_feedback(&pool, &prompt_id, &content).await?;
+                    // Readers keep running alongside the writes
+                    get_feedback_for_prompt(&pool, &prompt_id).await?;
+                }
+                Ok::<_, sqlx::Error>(())
+            }));
+        }
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+        let stored = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        assert_eq!(stored.len(), TASKS * SUBMISSIONS);
+        pool.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[tokio::test]
+    async fn test_create_prompt() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Test Title", "Test Description")
+            .await
+            .unwrap();
+
+        assert_eq!(prompt.title, "Test Title");
+        assert_eq!(prompt.description, "Test Description");
+        assert!(!prompt.id.is_empty());
+        assert!(!prompt.created_at.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_create_prompt_with_webhook() {
+        let pool = setup_test_db().await;
+
+        let options = PromptOptions {
+            webhook_url: Some("https://example.com/hook".to_string()),
+            ..Default::default()
+        };
+        let prompt =
+            create_prompt_with_options(&pool, DEFAULT_WORKSPACE_ID, "Hooked", "Desc", &options)
+                .await
+                .unwrap();
+        let plain = create_prompt(&pool, "Plain", "Desc").await.unwrap();
+
+        let found = get_prompt_by_id(&pool, &prompt.id).await.unwrap().unwrap();
+        assert_eq!(
+            found.webhook_url.as_deref(),
+            Some("https://example.com/hook")
+        );
+        assert_eq!(found.webhook_secret, prompt.webhook_secret);
+        assert_eq!(found.webhook_secret.unwrap().len(), 32);
+
+        assert!(plain.webhook_url.is_none());
+        assert!(plain.webhook_secret.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_get_all_prompts_empty() {
+        let pool = setup_test_db().await;
+
+        let prompts = get_all_prompts(&pool, DEFAULT_WORKSPACE_ID).await.unwrap();
+
+        assert!(prompts.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_all_prompts() {
+        let pool = setup_test_db().await;
+
+        create_prompt(&pool, "First", "First desc").await.unwrap();
+        create_prompt(&pool, "Second", "Second desc").await.unwrap();
+
+        let prompts = get_all_prompts(&pool, DEFAULT_WORKSPACE_ID).await.unwrap();
+
+        assert_eq!(prompts.len(), 2);
+        // Should be ordered by created_at DESC (most recent first)
+        assert_eq!(prompts[0].title, "Second");
+        assert_eq!(prompts[1].title, "First");
+    }
+
+    #[tokio::test]
+    async fn test_storage_usage_tracks_writes() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Sizes", "Desc").await.unwrap();
+        let other = create_prompt(&pool, "Other", "Desc").await.unwrap();
+        let spec = FieldSpec {
+            label: "Team".to_string(),
+            help_text: None,
+            kind: FieldKind::Short,
+            required: false,
+            rules: Default::default(),
+        };
+        let field = create_field(&pool, DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        let options = FeedbackOptions {
+            answers: vec![(field.id.clone(), "ops".to_string())],
+            ..Default::default()
+        };
+        // Multi-byte text is counted in bytes, not characters
+        let mut fb = create_feedback_with_options(&pool, &prompt.id, "héllo", &options)
+            .await
+            .unwrap();
+        create_feedback(&pool, &other.id, "1234").await.unwrap();
+
+        let usage = get_storage_usage(&pool, &prompt.id).await.unwrap();
+        assert_eq!(usage.prompt_bytes, 9);
+        assert_eq!(usage.workspace_bytes, 13);
+
+        edit_feedback(&pool, &mut fb, "hi").await.unwrap();
+        let usage = get_storage_usage(&pool, &prompt.id).await.unwrap();
+        assert_eq!(usage.prompt_bytes, 5);
+
+        delete_prompt(&pool, DEFAULT_WORKSPACE_ID, &other.id)
+            .await
+            .unwrap();
+        assert_eq!(
+            get_workspace_storage_bytes(&pool, DEFAULT_WORKSPACE_ID)
+                .await
+                .unwrap(),
+            5
+        );
+        assert_eq!(
+            get_storage_usage(&pool, "missing").await.unwrap(),
+            StorageUsage::default()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_get_prompt_by_id() {
+        let pool = setup_test_db().await;
+
+        let created = create_prompt(&pool, "Test", "Description").await.unwrap();
+
+        let found = get_prompt_by_id(&pool, &created.id).await.unwrap();
+
+        assert!(found.is_some());
+        let found = found.unwrap();
+        assert_eq!(found.id, created.id);
+        assert_eq!(found.title, "Test");
+        assert_eq!(found.description, "Description");
+    }
+
+    #[tokio::test]
+    async fn test_get_prompt_by_id_not_found() {
+        let pool = setup_test_db().await;
+
+        let found = get_prompt_by_id(&pool, "nonexistent-id").await.unwrap();
+
+        assert!(found.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_create_feedback() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+
+        let feedback = create_feedback(&pool, &prompt.id, "Great feedback!")
+            .await
+            .unwrap();
+
+        assert_eq!(feedback.prompt_id, prompt.id);
+        assert_eq!(feedback.content, "Great feedback!");
+        assert!(!feedback.id.is_empty());
+        assert!(!feedback.created_at.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_feedback_for_prompt_empty() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+
+        let feedback_list = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+
+        assert!(feedback_list.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_get_feedback_for_prompt() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+
+        create_feedback(&pool, &prompt.id, "First feedback")
+            .await
+            .unwrap();
+        create_feedback(&pool, &prompt.id, "Second feedback")
+            .await
+            .unwrap();
+
+        let feedback_list = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+
+        assert_eq!(feedback_list.len(), 2);
+        // Should be ordered by created_at DESC
+        assert_eq!(feedback_list[0].content, "Second feedback");
+        assert_eq!(feedback_list[1].content, "First feedback");
+    }
+
+    #[tokio::test]
+    async fn test_feedback_isolation_between_prompts() {
+        let pool = setup_test_db().await;
+
+        let prompt1 = create_prompt(&pool, "Prompt 1", "Desc 1").await.unwrap();
+        let prompt2 = create_prompt(&pool, "Prompt 2", "Desc 2").await.unwrap();
+
+        create_feedback(&pool, &prompt1.id, "Feedback for prompt 1")
+            .await
+            .unwrap();
+        create_feedback(&pool, &prompt2.id, "Feedback for prompt 2")
+            .await
+            .unwrap();
+
+        let feedback1 = get_feedback_for_prompt(&pool, &prompt1.id).await.unwrap();
+        let feedback2 = get_feedback_for_prompt(&pool, &prompt2.id).await.unwrap();
+
+        assert_eq!(feedback1.len(), 1);
+        assert_eq!(feedback1[0].content, "Feedback for prompt 1");
+
+        assert_eq!(feedback2.len(), 1);
+        assert_eq!(feedback2[0].content, "Feedback for prompt 2");
+    }
+
+    #[tokio::test]
+    async fn test_create_feedback_with_respondent() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+        create_feedback_with_options(&pool, &prompt.id, "Hi", &respondent("ann@example.com"))
+            .await
+            .unwrap();
+
+        let feedback_list = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        assert_eq!(
+            feedback_list[0].respondent_email.as_deref(),
+            Some("ann@example.com")
+        );
+        assert!(feedback_list[0].updated_at.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_replace_respondent_feedback() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Pulse", "How are you?").await.unwrap();
+
+        let first =
+            replace_respondent_feedback(&pool, &prompt.id, "Tired", &respondent("ann@example.com"))
+                .await
+                .unwrap();
+        create_feedback(&pool, &prompt.id, "Anonymous")
+            .await
+            .unwrap();
+        replace_respondent_feedback(&pool, &prompt.id, "Great", &respondent("bob@example.com"))
+            .await
+            .unwrap();
+        let second = replace_respondent_feedback(
+            &pool,
+            &prompt.id,
+            "Rested",
+            &respondent("ann@example.com"),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(second.id, first.id);
+        assert!(second.updated_at.is_some());
+
+        let feedback_list = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        assert_eq!(feedback_list.len(), 3);
+        let ann: Vec<_> = feedback_list
+            .iter()
+            .filter(|fb| fb.respondent_email.as_deref() == Some("ann@example.com"))
+            .collect();
+        assert_eq!(ann.len(), 1);
+        assert_eq!(ann[0].content, "Rested");
+        assert_eq!(ann[0].created_at, first.created_at);
+
+        // Anyone can type Ann's address; that adds an entry beside hers
+        let typed = FeedbackOptions {
+            email_verified: false,
+            ..respondent("ann@example.com")
+        };
+        let impostor = replace_respondent_feedback(&pool, &prompt.id, "Awful", &typed)
+            .await
+            .unwrap();
+        assert_ne!(impostor.id, first.id);
+        let kept = get_feedback_by_id(&pool, DEFAULT_WORKSPACE_ID, &first.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(kept.content, "Rested");
+    }
+
+    #[tokio::test]
+    async fn test_search_feedback() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Pricing", "Thoughts?").await.unwrap();
+        create_feedback(&pool, &prompt.id, "The pricing page is confusing")
+            .await
+            .unwrap();
+        create_feedback(&pool, &prompt.id, "Love the new dashboard")
+            .await
+            .unwrap();
+
+        let hits = search_feedback(&pool, DEFAULT_WORKSPACE_ID, "pricing")
+            .await
+            .unwrap();
+
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].prompt_title, "Pricing");
+        assert!(hits[0].snippet.contains("\u{2}pricing\u{3}"));
+
+        // Every word must match
+        assert!(
+            search_feedback(&pool, DEFAULT_WORKSPACE_ID, "pricing dashboard")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_feedback_tracks_updates_and_deletes() {
+        let pool = setup_test_db().await;
+
+        let prompt = create_prompt(&pool, "Pulse", "How are you?").await.unwrap();
+        replace_respondent_feedback(
+            &pool,
+            &prompt.id,
+            "Feeling tired",
+            &respondent("ann@example.com"),
+        )
+        .await
+        .unwrap();
+        replace_respondent_feedback(
+            &pool,
+            &prompt.id,
+            "Feeling rested",
+            &respondent("ann@example.com"),
+        )
+        .await
+        .unwrap();
+
+        assert!(search_feedback(&pool, DEFAULT_WORKSPACE_ID, "tired")
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(
+            search_feedback(&pool, DEFAULT_WORKSPACE_ID, "rested")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        assert!(delete_prompt(&pool, DEFAULT_WORKSPACE_ID, &prompt.id)
+            .await
+            .unwrap());
+
+        assert!(search_feedback(&pool, DEFAULT_WORKSPACE_ID, "rested")
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_feedback_ignores_fts_syntax() {
+        let pool = setup_test_db().await;
 
-pub async fn get_prompt_by_id(pool: &SqlitePool, id: &str) -> Result<Option<Prompt>, sqlx::Error> {
-    sqlx::query_as::<_, Prompt>(
-        "SELECT id, title, description, created_at FROM prompts WHERE id = ?",
-    )
-    .bind(id)
-    .fetch_optional(pool)
-    .await
-}
+        let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+        create_feedback(&pool, &prompt.id, "Say \"hello\" AND goodbye")
+            .await
+            .unwrap();
 
-pub async fn create_feedback(
-    pool: &SqlitePool,
-    prompt_id: &str,
-    content: &str,
-) -> Result<Feedback, sqlx::Error> {
-    let id = uuid::Uuid::new_v4().to_string();
-    let created_at = Utc::now().to_rfc3339();
+        assert_eq!(
+            search_feedback(&pool, DEFAULT_WORKSPACE_ID, "\"hello")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert_eq!(
+            search_feedback(&pool, DEFAULT_WORKSPACE_ID, "AND")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(search_feedback(&pool, DEFAULT_WORKSPACE_ID, "NEAR(")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(search_feedback(&pool, DEFAULT_WORKSPACE_ID, "   ")
+            .await
+            .unwrap()
+            .is_empty());
+    }
 
-    sqlx::query("INSERT INTO feedback (id, prompt_id, content, created_at) VALUES (?, ?, ?, ?)")
-        .bind(&id)
-        .bind(prompt_id)
-        .bind(content)
-        .bind(&created_at)
-        .execute(pool)
-        .await?;
+    #[test]
+    fn test_recurrence_advance() {
+        let start = DateTime::parse_from_rfc3339("2024-01-31T09:00:00+00:00")
+            .unwrap()
+            .with_timezone(&Utc);
 
-    Ok(Feedback {
-        id,
-        prompt_id: prompt_id.to_string(),
-        content: content.to_string(),
-        created_at,
-    })
-}
+        assert_eq!(
+            Recurrence::Weekly.nth(start, 1).to_rfc3339(),
+            "2024-02-07T09:00:00+00:00"
+        );
+        assert_eq!(
+            Recurrence::Monthly.nth(start, 1).to_rfc3339(),
+            "2024-02-29T09:00:00+00:00"
+        );
+        // Later months go back to the 31st instead of keeping February's 29th
+        assert_eq!(
+            Recurrence::Monthly.nth(start, 2).to_rfc3339(),
+            "2024-03-31T09:00:00+00:00"
+        );
+        assert_eq!(
+            Recurrence::Monthly.nth(start, 3).to_rfc3339(),
+            "2024-04-30T09:00:00+00:00"
+        );
+        assert_eq!(Recurrence::parse("weekly"), Some(Recurrence::Weekly));
+        assert_eq!(Recurrence::parse("daily"), None);
+    }
 
-pub async fn get_feedback_for_prompt(
-    pool: &SqlitePool,
-    prompt_id: &str,
-) -> Result<Vec<Feedback>, sqlx::Error> {
-    sqlx::query_as::<_, Feedback>(
-        "SELECT id, prompt_id, content, created_at FROM feedback WHERE prompt_id = ? ORDER BY created_at DESC",
-    )
-    .bind(prompt_id)
-    .fetch_all(pool)
-    .await
-}
+    #[tokio::test]
+    async fn test_current_cycle_rolls_over() {
+        let pool = setup_test_db().await;
 
-pub async fn delete_prompt(pool: &SqlitePool, id: &str) -> Result<(), sqlx::Error> {
-    // Delete all feedback for this prompt first (foreign key constraint)
-    sqlx::query("DELETE FROM feedback WHERE prompt_id = ?")
-        .bind(id)
-        .execute(pool)
-        .await?;
+        let options = PromptOptions {
+            recurrence: Some(Recurrence::Weekly),
+            ..Default::default()
+        };
+        let prompt = create_prompt_with_options(
+            &pool,
+            DEFAULT_WORKSPACE_ID,
+            "Pulse",
+            "Weekly check-in",
+            &options,
+        )
+        .await
+        .unwrap();
+        let now = Utc::now();
 
-    // Delete the prompt
-    sqlx::query("DELETE FROM prompts WHERE id = ?")
-        .bind(id)
-        .execute(pool)
-        .await?;
+        let first = current_cycle(&pool, &prompt, now).await.unwrap().unwrap();
+        assert_eq!(first.number, 1);
+        let same = current_cycle(&pool, &prompt, now + Duration::days(6))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(same.id, first.id);
 
-    Ok(())
-}
+        // Skipping two periods opens the missed cycle too, so trends stay contiguous
+        let third = current_cycle(&pool, &prompt, now + Duration::days(15))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(third.number, 3);
+        assert_eq!(
+            get_cycle_summaries(&pool, &prompt.id).await.unwrap().len(),
+            3
+        );
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+        let plain = create_prompt(&pool, "Once", "Desc").await.unwrap();
+        assert!(current_cycle(&pool, &plain, now).await.unwrap().is_none());
 
-    async fn setup_test_db() -> SqlitePool {
-        init_db("sqlite::memory:").await.unwrap()
+        // Monthly cycles stay on the first cycle's day rather than drifting
+        // to wherever February clamped it
+        let options = PromptOptions {
+            recurrence: Some(Recurrence::Monthly),
+            ..Default::default()
+        };
+        let monthly =
+            create_prompt_with_options(&pool, DEFAULT_WORKSPACE_ID, "Pulse", "Monthly", &options)
+                .await
+                .unwrap();
+        let at = |t: &str| DateTime::parse_from_rfc3339(t).unwrap().with_timezone(&Utc);
+        current_cycle(&pool, &monthly, at("2024-01-31T09:00:00+00:00"))
+            .await
+            .unwrap();
+        let april = current_cycle(&pool, &monthly, at("2024-04-15T09:00:00+00:00"))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(april.number, 3);
+        assert_eq!(april.starts_at, "2024-03-31T09:00:00+00:00");
+        assert_eq!(april.ends_at, "2024-04-30T09:00:00+00:00");
     }
 
     #[tokio::test]
-    async fn test_create_prompt() {
+    async fn test_cycle_summaries_count_feedback() {
         let pool = setup_test_db().await;
 
-        let prompt = create_prompt(&pool, "Test Title", "Test Description")
+        let options = PromptOptions {
+            recurrence: Some(Recurrence::Monthly),
+            ..Default::default()
+        };
+        let prompt =
+            create_prompt_with_options(&pool, DEFAULT_WORKSPACE_ID, "Pulse", "Monthly", &options)
+                .await
+                .unwrap();
+        let now = Utc::now();
+
+        let first = current_cycle(&pool, &prompt, now).await.unwrap().unwrap();
+        let cycle_options = FeedbackOptions {
+            cycle_id: Some(first.id.clone()),
+            ..Default::default()
+        };
+        create_feedback_with_options(&pool, &prompt.id, "One", &cycle_options)
+            .await
+            .unwrap();
+        create_feedback_with_options(&pool, &prompt.id, "Two", &cycle_options)
+            .await
+            .unwrap();
+        current_cycle(&pool, &prompt, now + Duration::days(40))
             .await
             .unwrap();
 
-        assert_eq!(prompt.title, "Test Title");
-        assert_eq!(prompt.description, "Test Description");
-        assert!(!prompt.id.is_empty());
-        assert!(!prompt.created_at.is_empty());
+        let summaries = get_cycle_summaries(&pool, &prompt.id).await.unwrap();
+
+        assert_eq!(summaries.len(), 2);
+        assert_eq!(summaries[0].number, 2);
+        assert_eq!(summaries[0].response_count, 0);
+        assert_eq!(summaries[1].response_count, 2);
+        let filter = FeedbackFilter {
+            cycle_id: Some(first.id.clone()),
+            ..Default::default()
+        };
+        assert_eq!(
+            list_feedback(&pool, &prompt.id, &filter)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
     }
 
     #[tokio::test]
-    async fn test_get_all_prompts_empty() {
+    async fn test_list_feedback_since() {
         let pool = setup_test_db().await;
+        let prompt = create_prompt(&pool, "Since", "Desc").await.unwrap();
+        for (content, created_at, updated_at) in [
+            ("old", "2024-01-01T00:00:00+00:00", None),
+            (
+                "old but edited",
+                "2024-01-01T00:00:00+00:00",
+                Some("2024-03-01T00:00:00+00:00"),
+            ),
+            ("new", "2024-02-15T12:00:00.5+00:00", None),
+        ] {
+            let fb = create_feedback(&pool, &prompt.id, content).await.unwrap();
+            sqlx::query("UPDATE feedback SET created_at = ?, updated_at = ? WHERE id = ?")
+                .bind(created_at)
+                .bind(updated_at)
+                .bind(&fb.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
 
-        let prompts = get_all_prompts(&pool).await.unwrap();
+        assert_eq!(
+            parse_since("2024-02-01").as_deref(),
+            Some("2024-02-01T00:00:00+00:00")
+        );
+        assert_eq!(
+            parse_since("2024-02-01T02:00:00+02:00").as_deref(),
+            Some("2024-02-01T00:00:00+00:00")
+        );
+        assert_eq!(parse_since("last tuesday"), None);
 
-        assert!(prompts.is_empty());
+        let filter = FeedbackFilter {
+            since: parse_since("2024-02-01"),
+            ..Default::default()
+        };
+        let mut contents: Vec<String> = list_feedback(&pool, &prompt.id, &filter)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fb| fb.content)
+            .collect();
+        contents.sort();
+        assert_eq!(contents, vec!["new", "old but edited"]);
     }
 
     #[tokio::test]
-    async fn test_get_all_prompts() {
+    async fn test_list_feedback_ranges_text_ratings_and_order() {
         let pool = setup_test_db().await;
+        let prompt = create_prompt(&pool, "Support", "Desc").await.unwrap();
+        let spec = FieldSpec {
+            label: "Rating".to_string(),
+            help_text: None,
+            kind: FieldKind::Short,
+            required: true,
+            rules: Default::default(),
+        };
+        let rating = create_field(&pool, DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        add_prompt_field(&pool, &prompt.id, &rating.id)
+            .await
+            .unwrap();
+        for (content, score, created_at) in [
+            ("Slow checkout", "2", "2024-01-10T09:00:00+00:00"),
+            ("Great support", "5", "2024-02-01T23:30:00+00:00"),
+            ("Checkout is confusing", "1", "2024-02-20T12:00:00+00:00"),
+        ] {
+            let options = FeedbackOptions {
+                answers: vec![(rating.id.clone(), score.to_string())],
+                ..Default::default()
+            };
+            let fb = create_feedback_with_options(&pool, &prompt.id, content, &options)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE feedback SET created_at = ? WHERE id = ?")
+                .bind(created_at)
+                .bind(&fb.id)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+        let contents = |filter: FeedbackFilter| {
+            let pool = pool.clone();
+            let prompt_id = prompt.id.clone();
+            async move {
+                list_feedback(&pool, &prompt_id, &filter)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|fb| fb.content)
+                    .collect::<Vec<_>>()
+            }
+        };
 
-        create_prompt(&pool, "First", "First desc").await.unwrap();
-        create_prompt(&pool, "Second", "Second desc").await.unwrap();
+        assert_eq!(
+            parse_until("2024-02-01").as_deref(),
+            Some("2024-02-02T00:00:00+00:00")
+        );
+        let february = FeedbackFilter {
+            created_from: parse_since("2024-02-01"),
+            created_before: parse_until("2024-02-01"),
+            ..Default::default()
+        };
+        assert_eq!(contents(february).await, vec!["Great support"]);
 
-        let prompts = get_all_prompts(&pool).await.unwrap();
+        let checkout = FeedbackFilter {
+            contains: Some("CHECKOUT".to_string()),
+            sort: SortOrder::Oldest,
+            ..Default::default()
+        };
+        assert_eq!(
+            contents(checkout).await,
+            vec!["Slow checkout", "Checkout is confusing"]
+        );
 
-        assert_eq!(prompts.len(), 2);
-        // Should be ordered by created_at DESC (most recent first)
-        assert_eq!(prompts[0].title, "Second");
-        assert_eq!(prompts[1].title, "First");
+        let low = FeedbackFilter {
+            rating: Some(RatingRange {
+                field_id: rating.id.clone(),
+                min: None,
+                max: Some(2.0),
+            }),
+            ..Default::default()
+        };
+        assert_eq!(
+            contents(low).await,
+            vec!["Checkout is confusing", "Slow checkout"]
+        );
+        let high = FeedbackFilter {
+            rating: Some(RatingRange {
+                field_id: rating.id.clone(),
+                min: Some(4.0),
+                max: None,
+            }),
+            ..Default::default()
+        };
+        assert_eq!(contents(high).await, vec!["Great support"]);
     }
 
     #[tokio::test]
-    async fn test_get_prompt_by_id() {
+    async fn test_spam_is_quarantined_until_released() {
         let pool = setup_test_db().await;
+        let prompt = create_prompt(&pool, "Spam", "Desc").await.unwrap();
+        create_feedback(&pool, &prompt.id, "Real").await.unwrap();
+        let spam_options = FeedbackOptions {
+            respondent_email: Some("bot@example.com".to_string()),
+            spam_reason: Some("honeypot field filled in".to_string()),
+            ..Default::default()
+        };
+        let spam = create_feedback_with_options(&pool, &prompt.id, "Buy now", &spam_options)
+            .await
+            .unwrap();
+        let junk = create_feedback_with_options(&pool, &prompt.id, "Junk", &spam_options)
+            .await
+            .unwrap();
 
-        let created = create_prompt(&pool, "Test", "Description").await.unwrap();
+        assert_eq!(
+            get_feedback_for_prompt(&pool, &prompt.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        assert!(get_respondents(&pool, DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .is_empty());
+        let quarantined = get_spam(&pool, DEFAULT_WORKSPACE_ID).await.unwrap();
+        assert_eq!(quarantined.len(), 2);
+        assert_eq!(quarantined[0].spam_reason, "honeypot field filled in");
+        assert_eq!(quarantined[0].prompt_title, "Spam");
 
-        let found = get_prompt_by_id(&pool, &created.id).await.unwrap();
+        // Scoped to the workspace and to quarantined entries
+        assert!(!release_spam(&pool, "elsewhere", &spam.id).await.unwrap());
+        assert!(release_spam(&pool, DEFAULT_WORKSPACE_ID, &spam.id)
+            .await
+            .unwrap());
+        assert!(!release_spam(&pool, DEFAULT_WORKSPACE_ID, &spam.id)
+            .await
+            .unwrap());
+        assert!(!delete_spam(&pool, DEFAULT_WORKSPACE_ID, &spam.id)
+            .await
+            .unwrap());
+        assert!(delete_spam(&pool, DEFAULT_WORKSPACE_ID, &junk.id)
+            .await
+            .unwrap());
 
-        assert!(found.is_some());
-        let found = found.unwrap();
-        assert_eq!(found.id, created.id);
-        assert_eq!(found.title, "Test");
-        assert_eq!(found.description, "Description");
+        assert_eq!(
+            get_feedback_for_prompt(&pool, &prompt.id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+        assert!(get_spam(&pool, DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .is_empty());
     }
 
     #[tokio::test]
-    async fn test_get_prompt_by_id_not_found() {
+    async fn test_fields_are_shared_across_prompts() {
         let pool = setup_test_db().await;
+        let first = create_prompt(&pool, "Q1", "Desc").await.unwrap();
+        let second = create_prompt(&pool, "Q2", "Desc").await.unwrap();
+        let spec = FieldSpec {
+            label: "Team".to_string(),
+            help_text: None,
+            kind: FieldKind::Short,
+            required: true,
+            rules: FieldRules::default(),
+        };
+        let team = create_field(&pool, DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        let role = create_field(
+            &pool,
+            DEFAULT_WORKSPACE_ID,
+            &FieldSpec {
+                label: "Role".to_string(),
+                ..spec.clone()
+            },
+        )
+        .await
+        .unwrap();
 
-        let found = get_prompt_by_id(&pool, "nonexistent-id").await.unwrap();
+        add_prompt_field(&pool, &first.id, &role.id).await.unwrap();
+        add_prompt_field(&pool, &first.id, &team.id).await.unwrap();
+        add_prompt_field(&pool, &first.id, &role.id).await.unwrap();
+        add_prompt_field(&pool, &second.id, &team.id).await.unwrap();
+        let labels: Vec<String> = get_prompt_fields(&pool, &first.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|f| f.label)
+            .collect();
+        assert_eq!(labels, ["Role", "Team"]);
 
-        assert!(found.is_none());
+        let options = FeedbackOptions {
+            answers: vec![
+                (team.id.clone(), "Platform".to_string()),
+                (role.id.clone(), "SRE".to_string()),
+            ],
+            ..Default::default()
+        };
+        let fb = create_feedback_with_options(&pool, &first.id, "Good", &options)
+            .await
+            .unwrap();
+        assert_eq!(fb.answers[0].label, "Role");
+        assert_eq!(fb.answers[1].value, "Platform");
+
+        // Editing the field changes it everywhere, including old answers
+        let renamed = FieldSpec {
+            label: "Squad".to_string(),
+            ..spec
+        };
+        assert!(
+            update_field(&pool, DEFAULT_WORKSPACE_ID, &team.id, &renamed)
+                .await
+                .unwrap()
+        );
+        assert!(!update_field(&pool, "elsewhere", &team.id, &renamed)
+            .await
+            .unwrap());
+        assert_eq!(
+            get_prompt_fields(&pool, &second.id).await.unwrap()[0].label,
+            "Squad"
+        );
+        let listed = get_feedback_for_prompt(&pool, &first.id).await.unwrap();
+        assert_eq!(listed[0].answers[1].label, "Squad");
+
+        let usage = get_field_usage(&pool, DEFAULT_WORKSPACE_ID).await.unwrap();
+        assert_eq!(usage[0].field.label, "Role");
+        assert_eq!((usage[0].prompt_count, usage[0].answer_count), (1, 1));
+        assert_eq!((usage[1].prompt_count, usage[1].answer_count), (2, 1));
+
+        // Fields in use can't be deleted
+        assert!(!delete_field(&pool, &team.id).await.unwrap());
+        remove_prompt_field(&pool, &first.id, &team.id)
+            .await
+            .unwrap();
+        remove_prompt_field(&pool, &second.id, &team.id)
+            .await
+            .unwrap();
+        assert!(delete_field(&pool, &team.id).await.unwrap());
+        let listed = get_feedback_for_prompt(&pool, &first.id).await.unwrap();
+        assert_eq!(listed[0].answers.len(), 1);
+    }
+
+    #[test]
+    fn test_normalize_tag() {
+        assert_eq!(
+            normalize_tag("  Feature Request "),
+            Some("feature-request".to_string())
+        );
+        assert_eq!(normalize_tag("bug"), Some("bug".to_string()));
+        assert_eq!(normalize_tag("   "), None);
+        assert_eq!(normalize_tag("<script>"), None);
+        assert_eq!(normalize_tag(&"x".repeat(33)), None);
     }
 
     #[tokio::test]
-    async fn test_create_feedback() {
+    async fn test_tags_attach_filter_and_remove() {
         let pool = setup_test_db().await;
 
         let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+        let crash = create_feedback(&pool, &prompt.id, "It crashed")
+            .await
+            .unwrap();
+        let thanks = create_feedback(&pool, &prompt.id, "Thanks!").await.unwrap();
 
-        let feedback = create_feedback(&pool, &prompt.id, "Great feedback!")
+        add_tag(&pool, &crash.id, "bug").await.unwrap();
+        add_tag(&pool, &crash.id, "bug").await.unwrap();
+        add_tag(&pool, &crash.id, "urgent").await.unwrap();
+        add_tag(&pool, &thanks.id, "praise").await.unwrap();
+
+        let all = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        let crash_entry = all.iter().find(|fb| fb.id == crash.id).unwrap();
+        assert_eq!(crash_entry.tags, vec!["bug", "urgent"]);
+
+        let filter = FeedbackFilter {
+            tag: Some("praise".to_string()),
+            ..Default::default()
+        };
+        let praised = list_feedback(&pool, &prompt.id, &filter).await.unwrap();
+        assert_eq!(praised.len(), 1);
+        assert_eq!(praised[0].id, thanks.id);
+
+        let counts = get_tag_counts_for_prompt(&pool, &prompt.id).await.unwrap();
+        let names: Vec<_> = counts.iter().map(|t| (t.name.as_str(), t.count)).collect();
+        assert_eq!(names, vec![("bug", 1), ("praise", 1), ("urgent", 1)]);
+
+        remove_tag(&pool, &crash.id, "urgent").await.unwrap();
+        let crash_entry = get_feedback_by_id(&pool, DEFAULT_WORKSPACE_ID, &crash.id)
             .await
+            .unwrap()
             .unwrap();
+        assert_eq!(crash_entry.tags, vec!["bug"]);
+    }
 
-        assert_eq!(feedback.prompt_id, prompt.id);
-        assert_eq!(feedback.content, "Great feedback!");
-        assert!(!feedback.id.is_empty());
-        assert!(!feedback.created_at.is_empty());
+    #[tokio::test]
+    async fn test_respondents_across_prompts() {
+        let pool = setup_test_db().await;
+
+        let first = create_prompt(&pool, "First", "Desc").await.unwrap();
+        let second = create_prompt(&pool, "Second", "Desc").await.unwrap();
+        let ann = respondent("ann@example.com");
+
+        create_feedback_with_options(&pool, &first.id, "Ann on first", &ann)
+            .await
+            .unwrap();
+        create_feedback_with_options(&pool, &second.id, "Ann on second", &ann)
+            .await
+            .unwrap();
+        create_feedback_with_options(&pool, &first.id, "Bob", &respondent("bob@example.com"))
+            .await
+            .unwrap();
+        create_feedback(&pool, &first.id, "Anonymous")
+            .await
+            .unwrap();
+
+        let respondents = get_respondents(&pool, DEFAULT_WORKSPACE_ID).await.unwrap();
+        assert_eq!(respondents.len(), 2);
+        let ann_summary = respondents
+            .iter()
+            .find(|r| r.email == "ann@example.com")
+            .unwrap();
+        assert_eq!(ann_summary.response_count, 2);
+        assert_eq!(ann_summary.prompt_count, 2);
+
+        let history = get_feedback_by_respondent(&pool, DEFAULT_WORKSPACE_ID, "ann@example.com")
+            .await
+            .unwrap();
+        assert_eq!(history.len(), 2);
+        assert_eq!(history[0].prompt_title, "Second");
+        assert_eq!(history[0].feedback.content, "Ann on second");
+        assert_eq!(history[1].prompt_title, "First");
     }
 
     #[tokio::test]
-    async fn test_get_feedback_for_prompt_empty() {
+    async fn test_feedback_is_scored_for_sentiment() {
         let pool = setup_test_db().await;
 
         let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+        create_feedback(&pool, &prompt.id, "I love it, great work")
+            .await
+            .unwrap();
+        create_feedback(&pool, &prompt.id, "Terrible, it crashed twice")
+            .await
+            .unwrap();
+        create_feedback(&pool, &prompt.id, "Used it on Monday")
+            .await
+            .unwrap();
+        replace_respondent_feedback(
+            &pool,
+            &prompt.id,
+            "Awful experience",
+            &respondent("ann@example.com"),
+        )
+        .await
+        .unwrap();
+        // Replacing a response rescores it
+        let merged = replace_respondent_feedback(
+            &pool,
+            &prompt.id,
+            "Actually it is wonderful",
+            &respondent("ann@example.com"),
+        )
+        .await
+        .unwrap();
+        assert_eq!(merged.sentiment_label.as_deref(), Some("positive"));
 
-        let feedback_list = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        let breakdown = get_sentiment_breakdown(&pool, &prompt.id).await.unwrap();
+        assert_eq!(breakdown.positive, 2);
+        assert_eq!(breakdown.neutral, 1);
+        assert_eq!(breakdown.negative, 1);
 
-        assert!(feedback_list.is_empty());
+        let filter = FeedbackFilter {
+            sentiment: Some(sentiment::Label::Negative),
+            ..Default::default()
+        };
+        let negative = list_feedback(&pool, &prompt.id, &filter).await.unwrap();
+        assert_eq!(negative.len(), 1);
+        assert!(negative[0].sentiment_score.unwrap() < 0.0);
     }
 
     #[tokio::test]
-    async fn test_get_feedback_for_prompt() {
+    async fn test_bulk_delete_and_tag() {
         let pool = setup_test_db().await;
+        let default = get_workspace_by_slug(&pool, "default")
+            .await
+            .unwrap()
+            .unwrap();
+        let prompt = create_prompt(&pool, "Launch", "Desc").await.unwrap();
+        let mut ids = Vec::new();
+        for content in ["Buy now", "Cheap pills", "Nice launch"] {
+            let fb = create_feedback(&pool, &prompt.id, content).await.unwrap();
+            ids.push(fb.id);
+        }
+        add_tag(&pool, &ids[0], "old").await.unwrap();
+        let other = create_workspace(&pool, "design", "Design")
+            .await
+            .unwrap()
+            .unwrap();
+        let theirs = create_prompt_with_options(
+            &pool,
+            &other.id,
+            "Design review",
+            "Desc",
+            &PromptOptions::default(),
+        )
+        .await
+        .unwrap();
+        let foreign = create_feedback(&pool, &theirs.id, "Not yours")
+            .await
+            .unwrap();
 
-        let prompt = create_prompt(&pool, "Test", "Description").await.unwrap();
+        let selected = vec![ids[0].clone(), ids[1].clone(), foreign.id.clone()];
+        assert_eq!(
+            add_tag_bulk(&pool, &default.id, &selected, "spam-wave")
+                .await
+                .unwrap(),
+            2
+        );
+        assert!(get_feedback_for_prompt(&pool, &theirs.id).await.unwrap()[0]
+            .tags
+            .is_empty());
 
-        create_feedback(&pool, &prompt.id, "First feedback")
+        assert_eq!(
+            delete_feedback_bulk(&pool, &default.id, &selected)
+                .await
+                .unwrap(),
+            2
+        );
+        let left = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].content, "Nice launch");
+        assert_eq!(
+            get_feedback_for_prompt(&pool, &theirs.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+        let (orphans,): (i64,) = sqlx::query_as("SELECT COUNT(*) FROM feedback_tags")
+            .fetch_one(&pool)
             .await
             .unwrap();
-        create_feedback(&pool, &prompt.id, "Second feedback")
+        assert_eq!(orphans, 0);
+        assert_eq!(
+            delete_feedback_bulk(&pool, &default.id, &[]).await.unwrap(),
+            0
+        );
+    }
+
+    #[tokio::test]
+    async fn test_queries_are_scoped_to_workspace() {
+        let pool = setup_test_db().await;
+        let other = create_workspace(&pool, "design", "Design")
             .await
+            .unwrap()
             .unwrap();
+        let prompt = create_prompt_with_options(
+            &pool,
+            &other.id,
+            "Design review",
+            "Desc",
+            &PromptOptions::default(),
+        )
+        .await
+        .unwrap();
+        let feedback = create_feedback_with_options(
+            &pool,
+            &prompt.id,
+            "Secret roadmap notes",
+            &respondent("ann@example.com"),
+        )
+        .await
+        .unwrap();
 
-        let feedback_list = get_feedback_for_prompt(&pool, &prompt.id).await.unwrap();
+        assert!(get_all_prompts(&pool, DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(
+            get_workspace_prompt(&pool, DEFAULT_WORKSPACE_ID, &prompt.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(
+            get_feedback_by_id(&pool, DEFAULT_WORKSPACE_ID, &feedback.id)
+                .await
+                .unwrap()
+                .is_none()
+        );
+        assert!(get_respondents(&pool, DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(
+            get_feedback_by_respondent(&pool, DEFAULT_WORKSPACE_ID, "ann@example.com")
+                .await
+                .unwrap()
+                .is_empty()
+        );
+        assert!(search_feedback(&pool, DEFAULT_WORKSPACE_ID, "roadmap")
+            .await
+            .unwrap()
+            .is_empty());
+        assert!(!delete_prompt(&pool, DEFAULT_WORKSPACE_ID, &prompt.id)
+            .await
+            .unwrap());
 
-        assert_eq!(feedback_list.len(), 2);
-        // Should be ordered by created_at DESC
-        assert_eq!(feedback_list[0].content, "Second feedback");
-        assert_eq!(feedback_list[1].content, "First feedback");
+        assert_eq!(get_all_prompts(&pool, &other.id).await.unwrap().len(), 1);
+        assert_eq!(
+            search_feedback(&pool, &other.id, "roadmap")
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
     }
 
     #[tokio::test]
-    async fn test_feedback_isolation_between_prompts() {
+    async fn test_workspace_slugs() {
         let pool = setup_test_db().await;
 
-        let prompt1 = create_prompt(&pool, "Prompt 1", "Desc 1").await.unwrap();
-        let prompt2 = create_prompt(&pool, "Prompt 2", "Desc 2").await.unwrap();
+        assert_eq!(
+            normalize_workspace_slug("Design Team"),
+            Some("design-team".to_string())
+        );
+        assert_eq!(normalize_workspace_slug("backup"), None);
+        assert_eq!(normalize_workspace_slug("a/b"), None);
 
-        create_feedback(&pool, &prompt1.id, "Feedback for prompt 1")
+        assert!(create_workspace(&pool, "design", "Design")
+            .await
+            .unwrap()
+            .is_some());
+        assert!(create_workspace(&pool, "design", "Other")
+            .await
+            .unwrap()
+            .is_none());
+        let slugs: Vec<String> = get_workspaces(&pool)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|w| w.slug)
+            .collect();
+        assert_eq!(slugs, vec!["default", "design"]);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_slugs() {
+        let pool = setup_test_db().await;
+
+        assert_eq!(
+            normalize_prompt_slug("Spring Fair"),
+            Some("spring-fair".to_string())
+        );
+        assert_eq!(normalize_prompt_slug("backup"), Some("backup".to_string()));
+        assert_eq!(normalize_prompt_slug("fair?"), None);
+
+        let options = PromptOptions {
+            slug: Some("fair".to_string()),
+            ..Default::default()
+        };
+        let fair = create_prompt_with_options(&pool, DEFAULT_WORKSPACE_ID, "Fair", "", &options)
             .await
             .unwrap();
-        create_feedback(&pool, &prompt2.id, "Feedback for prompt 2")
+        let other = create_prompt(&pool, "Other", "").await.unwrap();
+        assert_eq!(
+            get_prompt_by_slug(&pool, "fair").await.unwrap().unwrap().id,
+            fair.id
+        );
+        assert!(!set_prompt_slug(&pool, &other.id, Some("fair"))
+            .await
+            .unwrap());
+        assert!(set_prompt_slug(&pool, &fair.id, None).await.unwrap());
+        assert!(set_prompt_slug(&pool, &other.id, Some("fair"))
+            .await
+            .unwrap());
+        assert_eq!(
+            get_prompt_by_slug(&pool, "fair").await.unwrap().unwrap().id,
+            other.id
+        );
+    }
+
+    #[tokio::test]
+    async fn test_revoked_api_tokens_stop_working() {
+        let pool = setup_test_db().await;
+        let token = create_api_token(&pool, "CI", "digest", Role::Viewer, None)
             .await
             .unwrap();
 
-        let feedback1 = get_feedback_for_prompt(&pool, &prompt1.id).await.unwrap();
-        let feedback2 = get_feedback_for_prompt(&pool, &prompt2.id).await.unwrap();
+        let used = use_api_token(&pool, "digest", Utc::now()).await.unwrap();
+        assert!(used.unwrap().last_used_at.is_some());
+        assert!(use_api_token(&pool, "other", Utc::now())
+            .await
+            .unwrap()
+            .is_none());
 
-        assert_eq!(feedback1.len(), 1);
-        assert_eq!(feedback1[0].content, "Feedback for prompt 1");
+        assert!(revoke_api_token(&pool, &token.id).await.unwrap());
+        assert!(!revoke_api_token(&pool, &token.id).await.unwrap());
+        assert!(use_api_token(&pool, "digest", Utc::now())
+            .await
+            .unwrap()
+            .is_none());
+        assert!(get_api_tokens(&pool).await.unwrap()[0].revoked_at.is_some());
+    }
 
-        assert_eq!(feedback2.len(), 1);
-        assert_eq!(feedback2[0].content, "Feedback for prompt 2");
+    #[tokio::test]
+    async fn test_sessions_expire_and_end_with_user() {
+        let pool = setup_test_db().await;
+        let user = create_user(&pool, "ann@example.com", "hash", Role::Editor)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(create_user(&pool, "ann@example.com", "hash", Role::Viewer)
+            .await
+            .unwrap()
+            .is_none());
+
+        let now = Utc::now();
+        create_session(&pool, "digest", &user.id, now + Duration::hours(1))
+            .await
+            .unwrap();
+        let found = get_session_user(&pool, "digest", now).await.unwrap();
+        assert_eq!(found.unwrap().role(), Role::Editor);
+        assert!(get_session_user(&pool, "digest", now + Duration::hours(2))
+            .await
+            .unwrap()
+            .is_none());
+
+        assert!(delete_user(&pool, &user.id).await.unwrap());
+        assert!(get_session_user(&pool, "digest", now)
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(count_users(&pool).await.unwrap(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_prompt_opens_at_publish_time() {
+        assert_eq!(
+            parse_publish_at("2024-05-01T09:30").as_deref(),
+            Some("2024-05-01T09:30:00+00:00")
+        );
+        assert_eq!(
+            parse_publish_at("2024-05-01T11:30:00+02:00").as_deref(),
+            Some("2024-05-01T09:30:00+00:00")
+        );
+        assert_eq!(parse_publish_at("next week"), None);
+
+        let pool = setup_test_db().await;
+        let later = (Utc::now() + Duration::days(2)).to_rfc3339();
+        let options = PromptOptions {
+            publish_at: Some(later.clone()),
+            ..Default::default()
+        };
+        let prompt = create_prompt_with_options(&pool, DEFAULT_WORKSPACE_ID, "Fair", "", &options)
+            .await
+            .unwrap();
+        assert!(prompt.is_scheduled());
+        assert!(!prompt.is_open());
+        assert_eq!(prompt.status(), PromptStatus::Open);
+        assert_eq!(prompt.publish_at_input().len(), "2024-05-01T09:30".len());
+
+        let earlier = (Utc::now() - Duration::minutes(1)).to_rfc3339();
+        set_publish_at(&pool, &prompt.id, Some(&earlier))
+            .await
+            .unwrap();
+        let prompt = get_prompt_by_id(&pool, &prompt.id).await.unwrap().unwrap();
+        assert!(!prompt.is_scheduled());
+        assert!(prompt.is_open());
     }
 }
@@ -0,0 +1,416 @@
+//! Scheduled email digest of new feedback for subscribed admins.
+//!
+//! On each run of `DIGEST_SCHEDULE` (a cron expression, UTC), everything
+//! received since the previous digest is summarized per prompt and mailed to
+//! users who turned the digest on. Runs are logged in `email_digests`, so a
+//! restart neither repeats nor skips a period; the very first check only
+//! records where the first digest will start.
+
+use chrono::{DateTime, Datelike, Duration, Timelike, Utc};
+use sqlx::sqlite::SqlitePool;
+
+use crate::{bounces, db, email, notify};
+
+/// Daily at 08:00 UTC
+pub const DEFAULT_SCHEDULE: &str = "0 8 * * *";
+
+/// Entries quoted per prompt; the rest are only counted
+const MAX_EXCERPTS: usize = 5;
+
+/// A five-field cron expression: minute, hour, day of month, month and day
+/// of week (0 or 7 is Sunday). Fields take `*`, numbers, `a-b` ranges,
+/// `,` lists and `/n` steps; `@hourly`, `@daily`, `@weekly` and `@monthly`
+/// are accepted too. As in cron, when both day fields are restricted a day
+/// matching either one counts.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    spec: String,
+    minutes: u64,
+    hours: u64,
+    days: u64,
+    months: u64,
+    weekdays: u64,
+    days_restricted: bool,
+    weekdays_restricted: bool,
+}
+
+impl Schedule {
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let spec = spec.trim();
+        let expanded = match spec {
+            "@hourly" => "0 * * * *",
+            "@daily" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            _ => spec,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            return Err(format!("expected 5 fields, got {}", fields.len()));
+        };
+        let mut weekdays = parse_field(weekday, 0, 7)?;
+        // 7 is another name for Sunday
+        if weekdays & (1 << 7) != 0 {
+            weekdays = (weekdays | 1) & !(1 << 7);
+        }
+        Ok(Schedule {
+            spec: spec.to_string(),
+            minutes: parse_field(minute, 0, 59)?,
+            hours: parse_field(hour, 0, 23)?,
+            days: parse_field(day, 1, 31)?,
+            months: parse_field(month, 1, 12)?,
+            weekdays,
+            days_restricted: day != "*",
+            weekdays_restricted: weekday != "*",
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.spec
+    }
+
+    fn day_matches(&self, t: DateTime<Utc>) -> bool {
+        let day = self.days & (1 << t.day()) != 0;
+        let weekday = self.weekdays & (1 << t.weekday().num_days_from_sunday()) != 0;
+        let day_ok = if self.days_restricted && self.weekdays_restricted {
+            day || weekday
+        } else {
+            day && weekday
+        };
+        day_ok && self.months & (1 << t.month()) != 0
+    }
+
+    /// The first minute strictly after `t` the schedule fires, if any within
+    /// a few years (`0 0 30 2 *` never does)
+    pub fn next_after(&self, t: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        let mut t = t.with_second(0)?.with_nanosecond(0)? + Duration::minutes(1);
+        let limit = t + Duration::days(5 * 366);
+        while t < limit {
+            if !self.day_matches(t) {
+                t = (t.date_naive() + Duration::days(1))
+                    .and_hms_opt(0, 0, 0)?
+                    .and_utc();
+            } else if self.hours & (1 << t.hour()) == 0 {
+                t = t.with_minute(0)? + Duration::hours(1);
+            } else if self.minutes & (1 << t.minute()) == 0 {
+                t += Duration::minutes(1);
+            } else {
+                return Some(t);
+            }
+        }
+        None
+    }
+}
+
+/// One cron field as a bitmask of the values it allows
+fn parse_field(field: &str, min: u32, max: u32) -> Result<u64, String> {
+    let mut mask = 0;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => (range, Some(step)),
+            None => (part, None),
+        };
+        let number = |s: &str| {
+            s.parse::<u32>()
+                .ok()
+                .filter(|n| (min..=max).contains(n))
+                .ok_or_else(|| format!("\"{}\" is not a number from {} to {}", s, min, max))
+        };
+        let (start, end) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((start, end)) => (number(start)?, number(end)?),
+                // `5/15` means every 15 starting at 5
+                None if step.is_some() => (number(range)?, max),
+                None => {
+                    let n = number(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if start > end {
+            return Err(format!("range \"{}\" runs backwards", range));
+        }
+        let step = match step {
+            Some(step) => step
+                .parse::<u32>()
+                .ok()
+                .filter(|s| *s > 0)
+                .ok_or_else(|| format!("\"{}\" is not a valid step", step))?,
+            None => 1,
+        };
+        for n in (start..=end).step_by(step as usize) {
+            mask |= 1 << n;
+        }
+    }
+    Ok(mask)
+}
+
+/// New feedback on one prompt since the last digest
+#[derive(Debug, Clone, PartialEq)]
+pub struct PromptDigest {
+    pub workspace: String,
+    pub title: String,
+    pub responses: usize,
+    /// The earliest few entries, shortened
+    pub excerpts: Vec<String>,
+}
+
+/// Feedback created after `since` up to and including `until`, grouped by
+/// prompt; spam is left out
+async fn collect(
+    pool: &SqlitePool,
+    since: &str,
+    until: &str,
+) -> Result<Vec<PromptDigest>, sqlx::Error> {
+    let rows: Vec<(String, String, String, String)> = sqlx::query_as(
+        r#"
+        SELECT w.name, p.id, p.title, f.content
+        FROM feedback f
+        JOIN prompts p ON p.id = f.prompt_id
+        JOIN workspaces w ON w.id = p.workspace_id
+        WHERE f.created_at > ? AND f.created_at <= ? AND f.spam_reason IS NULL
+        ORDER BY w.name, p.title, p.id, f.created_at
+        "#,
+    )
+    .bind(since)
+    .bind(until)
+    .fetch_all(pool)
+    .await?;
+
+    let mut digests: Vec<(String, PromptDigest)> = Vec::new();
+    for (workspace, prompt_id, title, content) in rows {
+        if digests.last().is_none_or(|(id, _)| *id != prompt_id) {
+            let digest = PromptDigest {
+                workspace,
+                title,
+                responses: 0,
+                excerpts: Vec::new(),
+            };
+            digests.push((prompt_id, digest));
+        }
+        let (_, digest) = digests.last_mut().expect("pushed above");
+        digest.responses += 1;
+        if digest.excerpts.len() < MAX_EXCERPTS {
+            digest.excerpts.push(notify::excerpt(&content));
+        }
+    }
+    Ok(digests.into_iter().map(|(_, digest)| digest).collect())
+}
+
+fn plural(n: usize) -> &'static str {
+    if n == 1 {
+        ""
+    } else {
+        "s"
+    }
+}
+
+/// Subject line for a digest of `responses` entries
+pub fn subject(responses: usize) -> String {
+    format!(
+        "Feedback digest: {} new response{}",
+        responses,
+        plural(responses)
+    )
+}
+
+/// Plain text email body for a digest covering the period from `since`
+pub fn email_body(digests: &[PromptDigest], since: DateTime<Utc>) -> String {
+    let mut body = format!(
+        "New feedback since {}:\n",
+        since.format("%Y-%m-%d %H:%M UTC")
+    );
+    for digest in digests {
+        body.push_str(&format!(
+            "\n{} ({}): {} new response{}\n",
+            digest.title,
+            digest.workspace,
+            digest.responses,
+            plural(digest.responses)
+        ));
+        for excerpt in &digest.excerpts {
+            body.push_str(&format!("  - {}\n", excerpt));
+        }
+        let more = digest.responses - digest.excerpts.len();
+        if more > 0 {
+            body.push_str(&format!("  ...and {} more\n", more));
+        }
+    }
+    body.push_str(
+        "\nYou're getting this because the feedback digest is turned on for your account.\n",
+    );
+    body
+}
+
+async fn last_sent_at(pool: &SqlitePool) -> Result<Option<DateTime<Utc>>, sqlx::Error> {
+    let last: Option<String> = sqlx::query_scalar("SELECT MAX(sent_at) FROM email_digests")
+        .fetch_one(pool)
+        .await?;
+    Ok(last
+        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+        .map(|t| t.with_timezone(&Utc)))
+}
+
+async fn record(
+    pool: &SqlitePool,
+    sent_at: DateTime<Utc>,
+    responses: usize,
+    recipients: usize,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO email_digests (id, sent_at, responses, recipients) VALUES (?, ?, ?, ?)",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(sent_at.to_rfc3339())
+    .bind(responses as i64)
+    .bind(recipients as i64)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Mail the digest to subscribers if the schedule has fired since the last
+/// one. Quiet periods send nothing but still count as a digest, and a failed
+/// delivery to one subscriber doesn't hold up the rest. Returns how many
+/// emails were sent.
+pub async fn send_due(
+    pool: &SqlitePool,
+    mailer: &email::Mailer,
+    schedule: &Schedule,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let Some(since) = last_sent_at(pool).await? else {
+        record(pool, now, 0, 0).await?;
+        return Ok(0);
+    };
+    if schedule.next_after(since).is_none_or(|next| next > now) {
+        return Ok(0);
+    }
+
+    let digests = collect(pool, &since.to_rfc3339(), &now.to_rfc3339()).await?;
+    let responses = digests.iter().map(|d| d.responses).sum();
+    let mut sent = 0;
+    if responses > 0 {
+        let subject = subject(responses);
+        let body = email_body(&digests, since);
+        for user in db::get_digest_subscribers(pool).await? {
+            match bounces::send(pool, mailer, &user.email, &subject, &body).await {
+                Ok(()) => sent += 1,
+                Err(e) => eprintln!("Digest email to {} failed: {}", user.email, e),
+            }
+        }
+    }
+    record(pool, now, responses, sent).await?;
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(t: &str) -> DateTime<Utc> {
+        t.parse().unwrap()
+    }
+
+    #[test]
+    fn test_schedule_next_after() {
+        let daily = Schedule::parse(DEFAULT_SCHEDULE).unwrap();
+        assert_eq!(
+            daily.next_after(at("2026-10-16T07:59:30Z")),
+            Some(at("2026-10-16T08:00:00Z"))
+        );
+        assert_eq!(
+            daily.next_after(at("2026-10-16T08:00:00Z")),
+            Some(at("2026-10-17T08:00:00Z"))
+        );
+
+        // Weekdays at 09:30, so Friday evening waits for Monday
+        let weekdays = Schedule::parse("30 9 * * 1-5").unwrap();
+        assert_eq!(
+            weekdays.next_after(at("2026-10-16T18:00:00Z")),
+            Some(at("2026-10-19T09:30:00Z"))
+        );
+
+        let quarter_hours = Schedule::parse("*/15 * * * *").unwrap();
+        assert_eq!(
+            quarter_hours.next_after(at("2026-10-16T23:50:00Z")),
+            Some(at("2026-10-17T00:00:00Z"))
+        );
+
+        let sundays = Schedule::parse("@weekly").unwrap();
+        assert_eq!(
+            sundays,
+            Schedule {
+                spec: "@weekly".to_string(),
+                ..Schedule::parse("0 0 * * 7").unwrap()
+            }
+        );
+        assert_eq!(
+            Schedule::parse("0 0 30 2 *")
+                .unwrap()
+                .next_after(at("2026-01-01T00:00:00Z")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_schedule_rejects_bad_fields() {
+        assert!(Schedule::parse("0 8 * *").is_err());
+        assert!(Schedule::parse("60 8 * * *").is_err());
+        assert!(Schedule::parse("0 8-6 * * *").is_err());
+        assert!(Schedule::parse("*/0 * * * *").is_err());
+        assert!(Schedule::parse("0 8 * * mon").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_send_due_mails_subscribers() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mailer = email::Mailer::stub("feedback@example.com").unwrap();
+        let schedule = Schedule::parse(DEFAULT_SCHEDULE).unwrap();
+        for (email, subscribed) in [("ana@example.com", true), ("bo@example.com", false)] {
+            let user = db::create_user(&pool, email, "hash", db::Role::Viewer)
+                .await
+                .unwrap()
+                .unwrap();
+            db::set_digest_subscribed(&pool, &user.id, subscribed)
+                .await
+                .unwrap();
+        }
+
+        // The first check only marks where the first digest starts
+        let start = Utc::now() - Duration::days(1);
+        assert_eq!(send_due(&pool, &mailer, &schedule, start).await.unwrap(), 0);
+        let prompt = db::create_prompt(&pool, "Onboarding", "How was setup?")
+            .await
+            .unwrap();
+        for content in ["Smooth", "Docs were confusing"] {
+            db::create_feedback(&pool, &prompt.id, content)
+                .await
+                .unwrap();
+        }
+
+        // Not due until 08:00 after the start
+        let due = schedule.next_after(start).unwrap();
+        let early = due - Duration::minutes(1);
+        assert_eq!(send_due(&pool, &mailer, &schedule, early).await.unwrap(), 0);
+        assert!(mailer.sent().await.is_empty());
+
+        let now = Utc::now();
+        assert_eq!(send_due(&pool, &mailer, &schedule, now).await.unwrap(), 1);
+        let sent = mailer.sent().await;
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("To: ana@example.com"));
+        assert!(sent[0].contains("Subject: Feedback digest: 2 new responses"));
+        assert!(sent[0].contains("Onboarding (Default): 2 new responses"));
+        assert!(sent[0].contains("  - Docs were confusing"));
+
+        // Covered by that digest, so nothing goes out next time
+        let tomorrow = schedule.next_after(now).unwrap();
+        assert_eq!(
+            send_due(&pool, &mailer, &schedule, tomorrow).await.unwrap(),
+            0
+        );
+        assert_eq!(mailer.sent().await.len(), 1);
+    }
+}
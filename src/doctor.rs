@@ -0,0 +1,388 @@
+//! `feedback-app doctor`: consistency checks for databases that may have been
+//! edited by hand.
+
+use chrono::{DateTime, NaiveDateTime};
+use sqlx::sqlite::SqlitePool;
+
+use crate::db;
+
+/// Feedback content longer than this is reported as oversized
+pub const MAX_CONTENT_BYTES: i64 = 64 * 1024;
+
+/// Timestamp columns that must hold RFC 3339 values
+const TIMESTAMP_COLUMNS: &[(&str, &str)] = &[
+    ("prompts", "created_at"),
+    ("feedback", "created_at"),
+    ("feedback", "updated_at"),
+    ("cycles", "starts_at"),
+    ("cycles", "ends_at"),
+];
+
+#[derive(Debug, Clone)]
+pub struct Issue {
+    pub check: &'static str,
+    pub detail: String,
+    pub repaired: bool,
+}
+
+#[derive(Debug, Default)]
+pub struct Report {
+    pub issues: Vec<Issue>,
+}
+
+impl Report {
+    fn push(&mut self, check: &'static str, detail: String, repaired: bool) {
+        self.issues.push(Issue {
+            check,
+            detail,
+            repaired,
+        });
+    }
+
+    /// Issues that are still present after the run
+    pub fn outstanding(&self) -> usize {
+        self.issues.iter().filter(|i| !i.repaired).count()
+    }
+}
+
+/// Run every check, fixing what can be fixed safely when `repair` is set
+pub async fn run(pool: &SqlitePool, repair: bool) -> Result<Report, sqlx::Error> {
+    let mut report = Report::default();
+
+    check_orphans(pool, repair, &mut report).await?;
+    check_timestamps(pool, repair, &mut report).await?;
+    check_indexes(pool, repair, &mut report).await?;
+    check_content_size(pool, &mut report).await?;
+
+    Ok(report)
+}
+
+async fn check_orphans(
+    pool: &SqlitePool,
+    repair: bool,
+    report: &mut Report,
+) -> Result<(), sqlx::Error> {
+    let orphaned: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, prompt_id FROM feedback WHERE prompt_id NOT IN (SELECT id FROM prompts)",
+    )
+    .fetch_all(pool)
+    .await?;
+    for (id, prompt_id) in orphaned {
+        if repair {
+            sqlx::query("DELETE FROM feedback_tags WHERE feedback_id = ?")
+                .bind(&id)
+                .execute(pool)
+                .await?;
+            sqlx::query("DELETE FROM answers WHERE feedback_id = ?")
+                .bind(&id)
+                .execute(pool)
+                .await?;
+            sqlx::query("DELETE FROM votes WHERE feedback_id = ?")
+                .bind(&id)
+                .execute(pool)
+                .await?;
+            sqlx::query("DELETE FROM replies WHERE feedback_id = ?")
+                .bind(&id)
+                .execute(pool)
+                .await?;
+            sqlx::query("DELETE FROM feedback WHERE id = ?")
+                .bind(&id)
+                .execute(pool)
+                .await?;
+        }
+        report.push(
+            "orphaned feedback",
+            format!("feedback {} references missing prompt {}", id, prompt_id),
+            repair,
+        );
+    }
+
+    let orphaned: Vec<(String, String)> = sqlx::query_as(
+        r#"
+        SELECT feedback_id, tag_id FROM feedback_tags
+        WHERE feedback_id NOT IN (SELECT id FROM feedback)
+           OR tag_id NOT IN (SELECT id FROM tags)
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+    for (feedback_id, tag_id) in orphaned {
+        if repair {
+            sqlx::query("DELETE FROM feedback_tags WHERE feedback_id = ? AND tag_id = ?")
+                .bind(&feedback_id)
+                .bind(&tag_id)
+                .execute(pool)
+                .await?;
+        }
+        report.push(
+            "orphaned tag",
+            format!(
+                "tag {} on feedback {} has a missing side",
+                tag_id, feedback_id
+            ),
+            repair,
+        );
+    }
+
+    let orphaned: Vec<(String, String)> = sqlx::query_as(
+        "SELECT id, prompt_id FROM cycles WHERE prompt_id NOT IN (SELECT id FROM prompts)",
+    )
+    .fetch_all(pool)
+    .await?;
+    for (id, prompt_id) in orphaned {
+        if repair {
+            sqlx::query("DELETE FROM cycles WHERE id = ?")
+                .bind(&id)
+                .execute(pool)
+                .await?;
+        }
+        report.push(
+            "orphaned cycle",
+            format!("cycle {} references missing prompt {}", id, prompt_id),
+            repair,
+        );
+    }
+
+    if db::column_exists(pool, "prompts", "workspace_id").await? {
+        let orphaned: Vec<(String, String)> = sqlx::query_as(
+            "SELECT id, workspace_id FROM prompts WHERE workspace_id NOT IN (SELECT id FROM workspaces)",
+        )
+        .fetch_all(pool)
+        .await?;
+        for (id, workspace_id) in orphaned {
+            if repair {
+                sqlx::query("UPDATE prompts SET workspace_id = ? WHERE id = ?")
+                    .bind(db::DEFAULT_WORKSPACE_ID)
+                    .bind(&id)
+                    .execute(pool)
+                    .await?;
+            }
+            report.push(
+                "orphaned prompt",
+                format!(
+                    "prompt {} references missing workspace {}; repair moves it to the default workspace",
+                    id, workspace_id
+                ),
+                repair,
+            );
+        }
+    }
+
+    if db::column_exists(pool, "feedback", "cycle_id").await? {
+        let dangling: Vec<(String, String)> = sqlx::query_as(
+            r#"
+            SELECT id, cycle_id FROM feedback
+            WHERE cycle_id IS NOT NULL AND cycle_id NOT IN (SELECT id FROM cycles)
+            "#,
+        )
+        .fetch_all(pool)
+        .await?;
+        for (id, cycle_id) in dangling {
+            if repair {
+                sqlx::query("UPDATE feedback SET cycle_id = NULL WHERE id = ?")
+                    .bind(&id)
+                    .execute(pool)
+                    .await?;
+            }
+            report.push(
+                "orphaned feedback",
+                format!("feedback {} references missing cycle {}", id, cycle_id),
+                repair,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+/// Timestamps written by SQLite's `datetime()` are common after manual edits
+/// and can be rewritten losslessly; anything else is left for a human.
+fn repaired_timestamp(value: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .map(|naive| naive.and_utc().to_rfc3339())
+}
+
+async fn check_timestamps(
+    pool: &SqlitePool,
+    repair: bool,
+    report: &mut Report,
+) -> Result<(), sqlx::Error> {
+    for (table, column) in TIMESTAMP_COLUMNS {
+        if !db::column_exists(pool, table, column).await? {
+            continue;
+        }
+
+        let rows: Vec<(String, String)> = sqlx::query_as(&format!(
+            "SELECT id, {column} FROM {table} WHERE {column} IS NOT NULL"
+        ))
+        .fetch_all(pool)
+        .await?;
+
+        for (id, value) in rows {
+            if DateTime::parse_from_rfc3339(&value).is_ok() {
+                continue;
+            }
+
+            let fixed = repaired_timestamp(&value);
+            let repaired = repair && fixed.is_some();
+            if let (true, Some(fixed)) = (repair, &fixed) {
+                sqlx::query(&format!("UPDATE {table} SET {column} = ? WHERE id = ?"))
+                    .bind(fixed)
+                    .bind(&id)
+                    .execute(pool)
+                    .await?;
+            }
+
+            let hint = if fixed.is_none() {
+                " (cannot be repaired automatically)"
+            } else {
+                ""
+            };
+            report.push(
+                "invalid timestamp",
+                format!("{}.{} of {} is {:?}{}", table, column, id, value, hint),
+                repaired,
+            );
+        }
+    }
+
+    Ok(())
+}
+
+async fn check_indexes(
+    pool: &SqlitePool,
+    repair: bool,
+    report: &mut Report,
+) -> Result<(), sqlx::Error> {
+    for (name, sql) in db::INDEXES {
+        let exists: Option<(String,)> =
+            sqlx::query_as("SELECT name FROM sqlite_master WHERE type = 'index' AND name = ?")
+                .bind(name)
+                .fetch_optional(pool)
+                .await?;
+        if exists.is_some() {
+            continue;
+        }
+
+        if repair {
+            sqlx::query(sql).execute(pool).await?;
+        }
+        report.push("missing index", format!("{} does not exist", name), repair);
+    }
+
+    Ok(())
+}
+
+async fn check_content_size(pool: &SqlitePool, report: &mut Report) -> Result<(), sqlx::Error> {
+    let oversized: Vec<(String, i64)> = sqlx::query_as(
+        "SELECT id, length(CAST(content AS BLOB)) AS size FROM feedback WHERE size > ?",
+    )
+    .bind(MAX_CONTENT_BYTES)
+    .fetch_all(pool)
+    .await?;
+
+    // Truncating would silently destroy a response, so these are report-only
+    for (id, size) in oversized {
+        report.push(
+            "oversized content",
+            format!(
+                "feedback {} is {} bytes (limit {}); edit or delete it by hand",
+                id, size, MAX_CONTENT_BYTES
+            ),
+            false,
+        );
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    async fn setup_test_db() -> SqlitePool {
+        db::init_db("sqlite::memory:").await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_clean_database_has_no_issues() {
+        let pool = setup_test_db().await;
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+        db::create_feedback(&pool, &prompt.id, "Fine")
+            .await
+            .unwrap();
+
+        let report = run(&pool, false).await.unwrap();
+
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+    }
+
+    #[tokio::test]
+    async fn test_reports_then_repairs_issues() {
+        let pool = setup_test_db().await;
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+        let feedback = db::create_feedback(&pool, &prompt.id, "Fine")
+            .await
+            .unwrap();
+
+        // The sqlite3 shell leaves foreign keys off, which is how orphans sneak in
+        let mut conn = pool.acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF")
+            .execute(&mut *conn)
+            .await
+            .unwrap();
+        sqlx::query(
+            "INSERT INTO feedback (id, prompt_id, content, created_at) VALUES ('orphan', 'gone', 'x', ?)",
+        )
+        .bind(chrono::Utc::now().to_rfc3339())
+        .execute(&mut *conn)
+        .await
+        .unwrap();
+        drop(conn);
+        sqlx::query("UPDATE feedback SET created_at = '2024-03-01 12:30:00' WHERE id = ?")
+            .bind(&feedback.id)
+            .execute(&pool)
+            .await
+            .unwrap();
+        sqlx::query("DROP INDEX idx_feedback_cycle")
+            .execute(&pool)
+            .await
+            .unwrap();
+
+        let report = run(&pool, false).await.unwrap();
+        let checks: Vec<&str> = report.issues.iter().map(|i| i.check).collect();
+        assert!(checks.contains(&"orphaned feedback"));
+        assert!(checks.contains(&"invalid timestamp"));
+        assert!(checks.contains(&"missing index"));
+        assert_eq!(report.outstanding(), 3);
+
+        let report = run(&pool, true).await.unwrap();
+        assert_eq!(report.outstanding(), 0);
+
+        let report = run(&pool, false).await.unwrap();
+        assert!(report.issues.is_empty(), "{:?}", report.issues);
+
+        let repaired = db::get_feedback_by_id(&pool, db::DEFAULT_WORKSPACE_ID, &feedback.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(repaired.created_at, "2024-03-01T12:30:00+00:00");
+    }
+
+    #[tokio::test]
+    async fn test_oversized_content_is_report_only() {
+        let pool = setup_test_db().await;
+        let prompt = db::create_prompt(&pool, "Title", "Desc").await.unwrap();
+        let content = "a".repeat(MAX_CONTENT_BYTES as usize + 1);
+        db::create_feedback(&pool, &prompt.id, &content)
+            .await
+            .unwrap();
+
+        let report = run(&pool, true).await.unwrap();
+
+        assert_eq!(report.issues.len(), 1);
+        assert_eq!(report.issues[0].check, "oversized content");
+        assert_eq!(report.outstanding(), 1);
+    }
+}
@@ -0,0 +1,35 @@
+//! Signed links to a prompt's read-only results widget. Each prompt that has
+//! embedding turned on gets its own key, so rotating or disabling one link
+//! leaves the others working.
+
+use crate::signing;
+
+const LABEL: &str = "embed-results";
+
+/// How long browsers and CDNs may reuse a rendered widget
+pub const CACHE_MAX_AGE_SECS: u64 = 60;
+
+/// The `sig` query parameter for a prompt's widget URL
+pub fn sign(secret: &str, prompt_id: &str) -> String {
+    signing::sign(secret, LABEL, &[prompt_id])
+}
+
+/// Whether `sig` is the prompt's widget signature under `secret`
+pub fn verify(secret: &str, prompt_id: &str, sig: &str) -> bool {
+    signing::verify(secret, LABEL, &[prompt_id], sig)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let sig = sign("key", "prompt-1");
+        assert_eq!(sig.len(), 64);
+        assert!(verify("key", "prompt-1", &sig));
+        assert!(!verify("key", "prompt-2", &sig));
+        assert!(!verify("other", "prompt-1", &sig));
+        assert!(!verify("key", "prompt-1", "not hex"));
+    }
+}
@@ -0,0 +1,511 @@
+//! Spreadsheet exports of a workspace's feedback, as CSV or xlsx. Which
+//! columns appear, in what order, under which headers and with what date
+//! format comes from an export template (`db::ExportTemplate`), so recurring
+//! reports keep the shape the spreadsheets downstream expect.
+
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, NaiveDateTime};
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use crate::archive::Archive;
+use crate::db::{ExportColumn, Feedback, Field};
+
+pub const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// Columns in a template, at most
+pub const MAX_COLUMNS: usize = 50;
+
+/// Where a column's values come from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Source {
+    Id,
+    Prompt,
+    PromptId,
+    Cycle,
+    Content,
+    Email,
+    CreatedAt,
+    UpdatedAt,
+    Status,
+    SentimentLabel,
+    SentimentScore,
+    Tags,
+    NpsScore,
+    Reaction,
+    /// The answer to a question bank field, by field ID
+    Answer(String),
+}
+
+impl Source {
+    /// Every source but answers, in the default column order
+    pub const BUILTIN: [Source; 14] = [
+        Source::Id,
+        Source::Prompt,
+        Source::PromptId,
+        Source::Cycle,
+        Source::Content,
+        Source::Email,
+        Source::CreatedAt,
+        Source::UpdatedAt,
+        Source::Status,
+        Source::SentimentLabel,
+        Source::SentimentScore,
+        Source::NpsScore,
+        Source::Reaction,
+        Source::Tags,
+    ];
+
+    pub fn parse(key: &str) -> Option<Self> {
+        if let Some(field_id) = key.strip_prefix("answer:") {
+            return (!field_id.is_empty()).then(|| Source::Answer(field_id.to_string()));
+        }
+        Source::BUILTIN.into_iter().find(|s| s.key() == key)
+    }
+
+    pub fn key(&self) -> String {
+        match self {
+            Source::Id => "id",
+            Source::Prompt => "prompt",
+            Source::PromptId => "prompt_id",
+            Source::Cycle => "cycle",
+            Source::Content => "content",
+            Source::Email => "email",
+            Source::CreatedAt => "created_at",
+            Source::UpdatedAt => "updated_at",
+            Source::Status => "status",
+            Source::SentimentLabel => "sentiment_label",
+            Source::SentimentScore => "sentiment_score",
+            Source::Tags => "tags",
+            Source::NpsScore => "nps_score",
+            Source::Reaction => "reaction",
+            Source::Answer(field_id) => return format!("answer:{}", field_id),
+        }
+        .to_string()
+    }
+
+    /// Header used when a column doesn't set its own
+    pub fn label(&self, fields: &[Field]) -> String {
+        match self {
+            Source::Id => "ID",
+            Source::Prompt => "Prompt",
+            Source::PromptId => "Prompt ID",
+            Source::Cycle => "Cycle",
+            Source::Content => "Feedback",
+            Source::Email => "Email",
+            Source::CreatedAt => "Submitted",
+            Source::UpdatedAt => "Updated",
+            Source::Status => "Status",
+            Source::SentimentLabel => "Sentiment",
+            Source::SentimentScore => "Sentiment score",
+            Source::Tags => "Tags",
+            Source::NpsScore => "NPS score",
+            Source::Reaction => "Reaction",
+            Source::Answer(field_id) => {
+                return fields
+                    .iter()
+                    .find(|f| &f.id == field_id)
+                    .map_or_else(|| "Answer".to_string(), |f| f.label.clone())
+            }
+        }
+        .to_string()
+    }
+
+    /// Written as numbers in xlsx
+    fn is_numeric(&self) -> bool {
+        matches!(
+            self,
+            Source::Cycle | Source::SentimentScore | Source::NpsScore
+        )
+    }
+}
+
+/// The columns an export without a template gets: every built-in source,
+/// then every question in the workspace
+pub fn default_columns(fields: &[Field]) -> Vec<ExportColumn> {
+    Source::BUILTIN
+        .iter()
+        .cloned()
+        .chain(fields.iter().map(|f| Source::Answer(f.id.clone())))
+        .map(|source| ExportColumn {
+            source: source.key(),
+            header: String::new(),
+        })
+        .collect()
+}
+
+/// Whether `format` is a strftime pattern chrono can render
+pub fn is_valid_date_format(format: &str) -> bool {
+    !format.trim().is_empty() && !StrftimeItems::new(format).any(|item| item == Item::Error)
+}
+
+/// Check a template's columns against the workspace's questions
+pub fn check_columns(columns: &[ExportColumn], fields: &[Field]) -> Result<(), String> {
+    if columns.is_empty() {
+        return Err("Choose at least one column".to_string());
+    }
+    if columns.len() > MAX_COLUMNS {
+        return Err(format!(
+            "Templates can have at most {} columns",
+            MAX_COLUMNS
+        ));
+    }
+    for column in columns {
+        match Source::parse(&column.source) {
+            None => return Err(format!("Unknown column {}", column.source)),
+            Some(Source::Answer(id)) if !fields.iter().any(|f| f.id == id) => {
+                return Err("A column refers to a question that no longer exists".to_string())
+            }
+            Some(_) => {}
+        }
+    }
+    Ok(())
+}
+
+/// An export laid out as rows of text, ready to write in either format
+#[derive(Debug, Clone, PartialEq)]
+pub struct Sheet {
+    pub headers: Vec<String>,
+    pub rows: Vec<Vec<String>>,
+    numeric: Vec<bool>,
+}
+
+/// Lay out the archive's feedback, one row per entry in the archive's order.
+/// Columns with an unknown source come out blank.
+pub fn sheet(archive: &Archive, columns: &[ExportColumn], date_format: &str) -> Sheet {
+    let sources: Vec<Option<Source>> = columns.iter().map(|c| Source::parse(&c.source)).collect();
+    let headers = columns
+        .iter()
+        .zip(&sources)
+        .map(|(column, source)| match (column.header.trim(), source) {
+            ("", Some(source)) => source.label(&archive.fields),
+            (header, _) => header.to_string(),
+        })
+        .collect();
+    let numeric = sources
+        .iter()
+        .map(|s| s.as_ref().is_some_and(Source::is_numeric))
+        .collect();
+
+    let prompts: HashMap<&str, &str> = archive
+        .prompts
+        .iter()
+        .map(|p| (p.prompt.id.as_str(), p.prompt.title.as_str()))
+        .collect();
+    let cycles: HashMap<&str, i64> = archive
+        .cycles
+        .iter()
+        .map(|c| (c.id.as_str(), c.number))
+        .collect();
+    let date = |value: &str| format_date(value, date_format);
+    let value = |fb: &Feedback, source: &Source| -> String {
+        match source {
+            Source::Id => fb.id.clone(),
+            Source::Prompt => prompts
+                .get(fb.prompt_id.as_str())
+                .map_or_else(String::new, |t| t.to_string()),
+            Source::PromptId => fb.prompt_id.clone(),
+            Source::Cycle => fb
+                .cycle_id
+                .as_deref()
+                .and_then(|id| cycles.get(id))
+                .map_or_else(String::new, |n| n.to_string()),
+            Source::Content => fb.content.clone(),
+            Source::Email => fb.respondent_email.clone().unwrap_or_default(),
+            Source::CreatedAt => date(&fb.created_at),
+            Source::UpdatedAt => fb.updated_at.as_deref().map(date).unwrap_or_default(),
+            Source::Status => fb.status.clone(),
+            Source::SentimentLabel => fb.sentiment_label.clone().unwrap_or_default(),
+            Source::SentimentScore => fb
+                .sentiment_score
+                .map_or_else(String::new, |s| s.to_string()),
+            Source::Tags => fb.tags.join(", "),
+            Source::NpsScore => fb.nps_score.map_or_else(String::new, |s| s.to_string()),
+            Source::Reaction => fb.reaction.clone().unwrap_or_default(),
+            Source::Answer(field_id) => fb
+                .answers
+                .iter()
+                .find(|a| &a.field_id == field_id)
+                .map_or_else(String::new, |a| a.value.clone()),
+        }
+    };
+    let rows = archive
+        .feedback
+        .iter()
+        .map(|fb| {
+            sources
+                .iter()
+                .map(|source| source.as_ref().map_or_else(String::new, |s| value(fb, s)))
+                .collect()
+        })
+        .collect();
+
+    Sheet {
+        headers,
+        rows,
+        numeric,
+    }
+}
+
+/// Timestamps are stored as RFC 3339; anything unparseable is left as is
+fn format_date(value: &str, format: &str) -> String {
+    let parsed = DateTime::parse_from_rfc3339(value)
+        .map(|t| t.naive_utc())
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S"));
+    match parsed {
+        Ok(t) => t.format(format).to_string(),
+        Err(_) => value.to_string(),
+    }
+}
+
+impl Sheet {
+    /// RFC 4180 CSV with CRLF line endings
+    pub fn to_csv(&self) -> String {
+        let mut out = String::new();
+        for row in std::iter::once(&self.headers).chain(&self.rows) {
+            let cells: Vec<String> = row.iter().map(|cell| csv_cell(cell)).collect();
+            out.push_str(&cells.join(","));
+            out.push_str("\r\n");
+        }
+        out
+    }
+
+    /// A single-sheet workbook
+    pub fn to_xlsx(&self) -> Vec<u8> {
+        let mut sheet = String::from(
+            r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><worksheet xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main"><sheetData>"#,
+        );
+        let header = (&self.headers, false);
+        for (index, (row, body)) in std::iter::once(header)
+            .chain(self.rows.iter().map(|row| (row, true)))
+            .enumerate()
+        {
+            let r = index + 1;
+            let _ = write!(sheet, r#"<row r="{}">"#, r);
+            for (col, cell) in row.iter().enumerate() {
+                let reference = format!("{}{}", column_name(col), r);
+                let number = body && self.numeric.get(col) == Some(&true);
+                if cell.is_empty() {
+                    continue;
+                } else if number && cell.parse::<f64>().is_ok() {
+                    let _ = write!(sheet, r#"<c r="{}"><v>{}</v></c>"#, reference, cell);
+                } else {
+                    let _ = write!(
+                        sheet,
+                        r#"<c r="{}" t="inlineStr"><is><t xml:space="preserve">{}</t></is></c>"#,
+                        reference,
+                        xml_escape(cell)
+                    );
+                }
+            }
+            sheet.push_str("</row>");
+        }
+        sheet.push_str("</sheetData></worksheet>");
+
+        zip_stored(&[
+            ("[Content_Types].xml", CONTENT_TYPES.as_bytes()),
+            ("_rels/.rels", ROOT_RELS.as_bytes()),
+            ("xl/workbook.xml", WORKBOOK.as_bytes()),
+            ("xl/_rels/workbook.xml.rels", WORKBOOK_RELS.as_bytes()),
+            ("xl/worksheets/sheet1.xml", sheet.as_bytes()),
+        ])
+    }
+}
+
+/// Quote cells that need it, and defuse ones a spreadsheet would run as a
+/// formula; feedback is written by the public
+fn csv_cell(cell: &str) -> String {
+    let cell = if cell.starts_with(['=', '+', '-', '@']) && cell.parse::<f64>().is_err() {
+        format!("'{}", cell)
+    } else {
+        cell.to_string()
+    };
+    if cell.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", cell.replace('"', "\"\""))
+    } else {
+        cell
+    }
+}
+
+/// Spreadsheet column letters: A..Z, AA..
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).expect("column letters are ASCII")
+}
+
+/// Escape for XML text, dropping control characters XML can't carry
+fn xml_escape(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => out.push_str("&amp;"),
+            '<' => out.push_str("&lt;"),
+            '>' => out.push_str("&gt;"),
+            '"' => out.push_str("&quot;"),
+            '\t' | '\n' | '\r' => out.push(c),
+            c if c < ' ' => {}
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+const CONTENT_TYPES: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Types xmlns="http://schemas.openxmlformats.org/package/2006/content-types"><Default Extension="rels" ContentType="application/vnd.openxmlformats-package.relationships+xml"/><Default Extension="xml" ContentType="application/xml"/><Override PartName="/xl/workbook.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.sheet.main+xml"/><Override PartName="/xl/worksheets/sheet1.xml" ContentType="application/vnd.openxmlformats-officedocument.spreadsheetml.worksheet+xml"/></Types>"#;
+const ROOT_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/officeDocument" Target="xl/workbook.xml"/></Relationships>"#;
+const WORKBOOK: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><workbook xmlns="http://schemas.openxmlformats.org/spreadsheetml/2006/main" xmlns:r="http://schemas.openxmlformats.org/officeDocument/2006/relationships"><sheets><sheet name="Feedback" sheetId="1" r:id="rId1"/></sheets></workbook>"#;
+const WORKBOOK_RELS: &str = r#"<?xml version="1.0" encoding="UTF-8" standalone="yes"?><Relationships xmlns="http://schemas.openxmlformats.org/package/2006/relationships"><Relationship Id="rId1" Type="http://schemas.openxmlformats.org/officeDocument/2006/relationships/worksheet" Target="worksheets/sheet1.xml"/></Relationships>"#;
+
+const CRC32: crc::Crc<u32> = crc::Crc::<u32>::new(&crc::CRC_32_ISO_HDLC);
+
+/// A zip archive of uncompressed entries, which is all xlsx needs. Entries
+/// are dated 1980-01-01, the earliest date zip can represent.
+fn zip_stored(entries: &[(&str, &[u8])]) -> Vec<u8> {
+    const DOS_DATE: u16 = (1 << 5) | 1;
+    let mut out = Vec::new();
+    let mut central = Vec::new();
+    for (name, data) in entries {
+        let offset = out.len() as u32;
+        let crc = CRC32.checksum(data);
+        let size = data.len() as u32;
+
+        out.extend_from_slice(&0x04034b50u32.to_le_bytes());
+        for value in [20u16, 0, 0, 0, DOS_DATE] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [crc, size, size] {
+            out.extend_from_slice(&value.to_le_bytes());
+        }
+        out.extend_from_slice(&(name.len() as u16).to_le_bytes());
+        out.extend_from_slice(&0u16.to_le_bytes());
+        out.extend_from_slice(name.as_bytes());
+        out.extend_from_slice(data);
+
+        central.extend_from_slice(&0x02014b50u32.to_le_bytes());
+        for value in [20u16, 20, 0, 0, 0, DOS_DATE] {
+            central.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [crc, size, size] {
+            central.extend_from_slice(&value.to_le_bytes());
+        }
+        for value in [name.len() as u16, 0, 0, 0, 0] {
+            central.extend_from_slice(&value.to_le_bytes());
+        }
+        central.extend_from_slice(&0u32.to_le_bytes());
+        central.extend_from_slice(&offset.to_le_bytes());
+        central.extend_from_slice(name.as_bytes());
+    }
+
+    let central_offset = out.len() as u32;
+    out.extend_from_slice(&central);
+    out.extend_from_slice(&0x06054b50u32.to_le_bytes());
+    for value in [0u16, 0, entries.len() as u16, entries.len() as u16] {
+        out.extend_from_slice(&value.to_le_bytes());
+    }
+    out.extend_from_slice(&(central.len() as u32).to_le_bytes());
+    out.extend_from_slice(&central_offset.to_le_bytes());
+    out.extend_from_slice(&0u16.to_le_bytes());
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    fn column(source: &str, header: &str) -> ExportColumn {
+        ExportColumn {
+            source: source.to_string(),
+            header: header.to_string(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sheet_follows_template() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt(&pool, "Retro", "How was it?")
+            .await
+            .unwrap();
+        let spec = db::FieldSpec {
+            label: "Team".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: false,
+            rules: Default::default(),
+        };
+        let team = db::create_field(&pool, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&pool, &prompt.id, &team.id)
+            .await
+            .unwrap();
+        let options = db::FeedbackOptions {
+            answers: vec![(team.id.clone(), "Platform".to_string())],
+            ..Default::default()
+        };
+        db::create_feedback_with_options(&pool, &prompt.id, "Too long, \"really\"", &options)
+            .await
+            .unwrap();
+        let archive = crate::archive::export(&pool, db::DEFAULT_WORKSPACE_ID, None)
+            .await
+            .unwrap();
+
+        let columns = vec![
+            column(&format!("answer:{}", team.id), ""),
+            column("content", "Comment"),
+            column("created_at", "Date"),
+        ];
+        assert!(check_columns(&columns, &archive.fields).is_ok());
+        let sheet = sheet(&archive, &columns, "%d/%m/%Y");
+        assert_eq!(sheet.headers, vec!["Team", "Comment", "Date"]);
+        assert_eq!(sheet.rows[0][0], "Platform");
+        assert_eq!(
+            sheet.rows[0][2],
+            chrono::Utc::now().format("%d/%m/%Y").to_string()
+        );
+
+        let csv = sheet.to_csv();
+        let mut lines = csv.split("\r\n");
+        assert_eq!(lines.next(), Some("Team,Comment,Date"));
+        assert!(lines
+            .next()
+            .unwrap()
+            .starts_with("Platform,\"Too long, \"\"really\"\"\","));
+
+        let xlsx = sheet.to_xlsx();
+        assert!(xlsx.starts_with(b"PK\x03\x04"));
+        let text = String::from_utf8_lossy(&xlsx);
+        assert!(text.contains(r#"<c r="B2" t="inlineStr"><is><t xml:space="preserve">Too long, &quot;really&quot;</t></is></c>"#));
+    }
+
+    #[test]
+    fn test_check_columns_and_date_format() {
+        assert!(check_columns(&[], &[]).is_err());
+        assert!(check_columns(&[column("status", "")], &[]).is_ok());
+        assert!(check_columns(&[column("nope", "")], &[]).is_err());
+        assert!(check_columns(&[column("answer:gone", "")], &[]).is_err());
+        assert!(is_valid_date_format("%Y-%m-%d"));
+        assert!(!is_valid_date_format("%Q"));
+        assert!(!is_valid_date_format(" "));
+    }
+
+    #[test]
+    fn test_csv_cell_and_column_names() {
+        assert_eq!(csv_cell("plain"), "plain");
+        assert_eq!(csv_cell("a\nb"), "\"a\nb\"");
+        assert_eq!(csv_cell("=HYPERLINK(\"x\")"), "\"'=HYPERLINK(\"\"x\"\")\"");
+        assert_eq!(csv_cell("-0.5"), "-0.5");
+        assert_eq!(column_name(0), "A");
+        assert_eq!(column_name(25), "Z");
+        assert_eq!(column_name(26), "AA");
+        assert_eq!(column_name(701), "ZZ");
+        assert_eq!(column_name(702), "AAA");
+    }
+}
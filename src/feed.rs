@@ -0,0 +1,178 @@
+//! A prompt's newest feedback as an Atom feed, for feed readers and
+//! automation that already speaks feeds. Readers can't sign in, so the feed
+//! URL carries a `token` signed with the prompt's own key; turning the feed
+//! off or making a new link revokes every copy of the old one.
+
+use std::fmt::Write;
+
+use crate::{db, signing};
+
+const LABEL: &str = "feedback-feed";
+
+/// Entries in a feed, newest first
+pub const MAX_ENTRIES: i64 = 50;
+/// Longest entry title before it's cut off with an ellipsis
+const TITLE_CHARS: usize = 80;
+
+/// The `token` query parameter for a prompt's feed URL
+pub fn sign(secret: &str, prompt_id: &str) -> String {
+    signing::sign(secret, LABEL, &[prompt_id])
+}
+
+/// Whether `token` is the prompt's feed token under `secret`
+pub fn verify(secret: &str, prompt_id: &str, token: &str) -> bool {
+    signing::verify(secret, LABEL, &[prompt_id], token)
+}
+
+/// Render `entries` (newest first) as an Atom document. `self_url` is the
+/// feed's own address, token included, and `page_url` the prompt's admin page.
+pub fn render(
+    prompt: &db::Prompt,
+    entries: &[db::Feedback],
+    self_url: &str,
+    page_url: &str,
+) -> String {
+    let updated = entries
+        .iter()
+        .map(last_changed)
+        .max()
+        .unwrap_or(&prompt.created_at);
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"utf-8\"?>\n");
+    xml.push_str("<feed xmlns=\"http://www.w3.org/2005/Atom\">\n");
+    let _ = writeln!(xml, "  <id>urn:uuid:{}</id>", escape(&prompt.id));
+    let _ = writeln!(xml, "  <title>{}</title>", escape(&prompt.title));
+    if !prompt.description.is_empty() {
+        let _ = writeln!(
+            xml,
+            "  <subtitle>{}</subtitle>",
+            escape(&prompt.description)
+        );
+    }
+    let _ = writeln!(xml, "  <updated>{}</updated>", escape(updated));
+    let _ = writeln!(
+        xml,
+        "  <link rel=\"self\" type=\"application/atom+xml\" href=\"{}\"/>",
+        escape(self_url)
+    );
+    let _ = writeln!(
+        xml,
+        "  <link rel=\"alternate\" href=\"{}\"/>",
+        escape(page_url)
+    );
+    xml.push_str("  <generator>feedback-app</generator>\n");
+
+    for entry in entries {
+        xml.push_str("  <entry>\n");
+        let _ = writeln!(xml, "    <id>urn:uuid:{}</id>", escape(&entry.id));
+        let _ = writeln!(xml, "    <title>{}</title>", escape(&title(&entry.content)));
+        let _ = writeln!(
+            xml,
+            "    <published>{}</published>",
+            escape(&entry.created_at)
+        );
+        let _ = writeln!(
+            xml,
+            "    <updated>{}</updated>",
+            escape(last_changed(entry))
+        );
+        let _ = writeln!(
+            xml,
+            "    <link rel=\"alternate\" href=\"{}#feedback-{}\"/>",
+            escape(page_url),
+            escape(&entry.id)
+        );
+        let author = entry.respondent_email.as_deref().unwrap_or("Anonymous");
+        let _ = writeln!(xml, "    <author><name>{}</name></author>", escape(author));
+        for tag in &entry.tags {
+            let _ = writeln!(xml, "    <category term=\"{}\"/>", escape(tag));
+        }
+        let _ = writeln!(
+            xml,
+            "    <content type=\"text\">{}</content>",
+            escape(&content(entry))
+        );
+        xml.push_str("  </entry>\n");
+    }
+    xml.push_str("</feed>\n");
+    xml
+}
+
+fn last_changed(entry: &db::Feedback) -> &String {
+    entry.updated_at.as_ref().unwrap_or(&entry.created_at)
+}
+
+/// The first line of the feedback, shortened for a reader's list view
+fn title(content: &str) -> String {
+    let line = content.lines().next().unwrap_or_default().trim();
+    if line.chars().count() > TITLE_CHARS {
+        let cut: String = line.chars().take(TITLE_CHARS - 1).collect();
+        format!("{}…", cut.trim_end())
+    } else {
+        line.to_string()
+    }
+}
+
+/// The feedback followed by its question bank answers, one per line
+fn content(entry: &db::Feedback) -> String {
+    let mut text = entry.content.clone();
+    if !entry.answers.is_empty() {
+        text.push('\n');
+    }
+    for answer in &entry.answers {
+        let _ = write!(text, "\n{}: {}", answer.label, answer.value);
+    }
+    text
+}
+
+fn escape(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            // Control characters other than tab and newlines aren't allowed in XML 1.0
+            c if c.is_control() && !matches!(c, '\t' | '\n' | '\r') => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_and_verify() {
+        let token = sign("key", "prompt-1");
+        assert!(verify("key", "prompt-1", &token));
+        assert!(!verify("key", "prompt-2", &token));
+        assert!(!verify("other", "prompt-1", &token));
+        // A results widget signature doesn't open the feed
+        assert!(!verify(
+            "key",
+            "prompt-1",
+            &crate::embed::sign("key", "prompt-1")
+        ));
+    }
+
+    #[test]
+    fn test_title() {
+        assert_eq!(title("  Great app\nbut slow"), "Great app");
+        let long = title(&"word ".repeat(40));
+        assert!(long.ends_with('…'));
+        assert!(long.chars().count() <= TITLE_CHARS);
+    }
+
+    #[test]
+    fn test_escape() {
+        assert_eq!(
+            escape("<b>\"Tom\" & 'Jerry'</b>\u{1}"),
+            "&lt;b&gt;&quot;Tom&quot; &amp; &apos;Jerry&apos;&lt;/b&gt;"
+        );
+    }
+}
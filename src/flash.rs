@@ -0,0 +1,166 @@
+//! One-shot notices carried across a redirect ("Prompt created", "Failed to
+//! save"). A handler answers with `flash::redirect`, which stores the notice
+//! in a short-lived cookie; on the next request `middleware` picks it up, the
+//! next page that renders `base.html` shows it (`AppState::layout` takes it),
+//! and the cookie is cleared. Partials and JSON responses leave it for the
+//! next full page.
+
+use std::sync::Mutex;
+
+use axum::{
+    extract::Request,
+    http::{header, HeaderValue},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
+};
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+
+use crate::auth;
+
+const COOKIE: &str = "flash";
+/// A notice nobody came back for shouldn't turn up much later
+const MAX_AGE_SECS: i64 = 60;
+/// Longest message kept, in characters
+const MAX_LEN: usize = 300;
+
+tokio::task_local! {
+    static PENDING: Mutex<Option<Flash>>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Success,
+    Error,
+}
+
+impl Level {
+    fn as_str(&self) -> &'static str {
+        match self {
+            Level::Success => "success",
+            Level::Error => "error",
+        }
+    }
+
+    /// The `base.html` alert style
+    pub fn class(&self) -> &'static str {
+        match self {
+            Level::Success => "alert-success",
+            Level::Error => "alert-danger",
+        }
+    }
+
+    pub fn is_error(&self) -> bool {
+        *self == Level::Error
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flash {
+    pub level: Level,
+    pub message: String,
+}
+
+impl Flash {
+    pub fn success(message: impl Into<String>) -> Self {
+        Flash {
+            level: Level::Success,
+            message: message.into(),
+        }
+    }
+
+    pub fn error(message: impl Into<String>) -> Self {
+        Flash {
+            level: Level::Error,
+            message: message.into(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        let message: String = self.message.chars().take(MAX_LEN).collect();
+        URL_SAFE_NO_PAD.encode(format!("{}:{}", self.level.as_str(), message))
+    }
+
+    fn decode(value: &str) -> Option<Self> {
+        let decoded = String::from_utf8(URL_SAFE_NO_PAD.decode(value).ok()?).ok()?;
+        let (level, message) = decoded.split_once(':')?;
+        let level = match level {
+            "success" => Level::Success,
+            "error" => Level::Error,
+            _ => return None,
+        };
+        Some(Flash {
+            level,
+            message: message.chars().take(MAX_LEN).collect(),
+        })
+    }
+}
+
+/// Redirect to `to`, showing `flash` on the page it lands on
+pub fn redirect(to: &str, flash: Flash) -> Response {
+    let cookie = format!(
+        "{}={}; Path=/; HttpOnly; SameSite=Lax; Max-Age={}",
+        COOKIE,
+        flash.encode(),
+        MAX_AGE_SECS
+    );
+    ([(header::SET_COOKIE, cookie)], Redirect::to(to)).into_response()
+}
+
+/// The notice waiting for this request's page, if any; only the first
+/// caller gets it
+pub fn take() -> Option<Flash> {
+    PENDING
+        .try_with(|pending| pending.lock().unwrap().take())
+        .ok()
+        .flatten()
+}
+
+/// Makes an incoming notice available to `take`, and clears the cookie once
+/// a page has shown it (or it couldn't be read)
+pub async fn middleware(request: Request, next: Next) -> Response {
+    let incoming = auth::cookie(request.headers(), COOKIE).map(Flash::decode);
+    let Some(incoming) = incoming else {
+        return next.run(request).await;
+    };
+    let unreadable = incoming.is_none();
+    let (mut response, shown) = PENDING
+        .scope(Mutex::new(incoming), async {
+            let response = next.run(request).await;
+            let shown = PENDING.with(|pending| pending.lock().unwrap().is_none());
+            (response, shown)
+        })
+        .await;
+
+    // A response that sets a new notice (a redirect) replaces the old one
+    let replaced = response
+        .headers()
+        .get_all(header::SET_COOKIE)
+        .iter()
+        .any(|v| v.as_bytes().starts_with(format!("{}=", COOKIE).as_bytes()));
+    if (shown || unreadable) && !replaced {
+        let clear = format!("{}=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0", COOKIE);
+        if let Ok(value) = HeaderValue::from_str(&clear) {
+            response.headers_mut().append(header::SET_COOKIE, value);
+        }
+    }
+    response
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_encode_round_trip() {
+        let flash = Flash::error("Failed to save: title; required, \"quoted\" é");
+        assert_eq!(Flash::decode(&flash.encode()), Some(flash));
+        assert_eq!(Flash::decode("not base64!"), None);
+        assert_eq!(Flash::decode(&URL_SAFE_NO_PAD.encode("warning:x")), None);
+
+        let long = Flash::success("x".repeat(MAX_LEN * 2));
+        assert_eq!(
+            Flash::decode(&long.encode()).unwrap().message.len(),
+            MAX_LEN
+        );
+    }
+}
@@ -0,0 +1,445 @@
+//! Read-only GraphQL over a workspace's prompts and feedback, for reporting
+//! tools that would rather write one query than walk the REST routes. It is
+//! served at `/api/v1/workspaces/:workspace/graphql` behind the same viewer
+//! check as the rest of the API, and every query is confined to that
+//! workspace.
+
+use async_graphql::{
+    Context, EmptyMutation, EmptySubscription, Enum, InputObject, Object, SimpleObject, ID,
+};
+use sqlx::sqlite::SqlitePool;
+
+use crate::{db, sentiment};
+
+pub type Schema = async_graphql::Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Entries returned for one prompt when `first` isn't given
+const DEFAULT_PAGE: i32 = 100;
+/// Most entries returned for one prompt
+pub const MAX_PAGE: i32 = 1000;
+/// Deepest nesting a query may use; prompt -> feedback -> prompt -> ... ends here
+const MAX_DEPTH: usize = 8;
+/// Rough cap on fields resolved per query
+const MAX_COMPLEXITY: usize = 1000;
+
+pub fn schema(pool: SqlitePool) -> Schema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .data(pool)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+/// Run `request` against `workspace`'s data
+pub async fn execute(
+    schema: &Schema,
+    workspace: db::Workspace,
+    request: async_graphql::Request,
+) -> async_graphql::Response {
+    schema.execute(request.data(workspace)).await
+}
+
+fn pool<'a>(ctx: &Context<'a>) -> &'a SqlitePool {
+    ctx.data_unchecked::<SqlitePool>()
+}
+
+fn workspace<'a>(ctx: &Context<'a>) -> &'a db::Workspace {
+    ctx.data_unchecked::<db::Workspace>()
+}
+
+/// Database details stay in the server log, as with the REST API
+fn database_error(e: sqlx::Error) -> async_graphql::Error {
+    eprintln!("Database error: {}", e);
+    async_graphql::Error::new("Internal server error")
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    /// The workspace's prompts, newest first
+    async fn prompts(
+        &self,
+        ctx: &Context<'_>,
+        status: Option<PromptStatus>,
+    ) -> async_graphql::Result<Vec<Prompt>> {
+        let prompts = db::get_all_prompts(pool(ctx), &workspace(ctx).id)
+            .await
+            .map_err(database_error)?;
+        Ok(prompts
+            .into_iter()
+            .filter(|p| status.is_none_or(|s| PromptStatus::from(p.status()) == s))
+            .map(Prompt)
+            .collect())
+    }
+
+    async fn prompt(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Prompt>> {
+        let prompt = db::get_workspace_prompt(pool(ctx), &workspace(ctx).id, &id)
+            .await
+            .map_err(database_error)?;
+        Ok(prompt.map(Prompt))
+    }
+
+    async fn feedback(&self, ctx: &Context<'_>, id: ID) -> async_graphql::Result<Option<Feedback>> {
+        let feedback = db::get_feedback_by_id(pool(ctx), &workspace(ctx).id, &id)
+            .await
+            .map_err(database_error)?;
+        Ok(feedback.map(Feedback))
+    }
+}
+
+pub struct Prompt(db::Prompt);
+
+#[Object]
+impl Prompt {
+    async fn id(&self) -> ID {
+        ID(self.0.id.clone())
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> &str {
+        &self.0.description
+    }
+
+    /// Short URL at `/f/:slug`, if set
+    async fn slug(&self) -> Option<&str> {
+        self.0.slug.as_deref()
+    }
+
+    async fn status(&self) -> PromptStatus {
+        self.0.status().into()
+    }
+
+    async fn suspended(&self) -> bool {
+        self.0.is_suspended()
+    }
+
+    async fn created_at(&self) -> &str {
+        &self.0.created_at
+    }
+
+    /// Tags on the prompt's feedback, with how many entries carry each
+    async fn tags(&self, ctx: &Context<'_>) -> async_graphql::Result<Vec<TagCount>> {
+        let counts = db::get_tag_counts_for_prompt(pool(ctx), &self.0.id)
+            .await
+            .map_err(database_error)?;
+        Ok(counts
+            .into_iter()
+            .map(|c| TagCount {
+                name: c.name,
+                count: c.count,
+            })
+            .collect())
+    }
+
+    /// The prompt's feedback, spam excluded
+    async fn feedback(
+        &self,
+        ctx: &Context<'_>,
+        filter: Option<FeedbackFilter>,
+        #[graphql(default_with = "DEFAULT_PAGE")] first: i32,
+        #[graphql(default)] sort: SortOrder,
+    ) -> async_graphql::Result<Vec<Feedback>> {
+        if !(1..=MAX_PAGE).contains(&first) {
+            return Err(format!("first must be between 1 and {}", MAX_PAGE).into());
+        }
+        let mut query = filter.unwrap_or_default().into_db()?;
+        query.limit = Some(first.into());
+        query.sort = match sort {
+            SortOrder::Newest => db::SortOrder::Newest,
+            SortOrder::Oldest => db::SortOrder::Oldest,
+        };
+        let feedback = db::list_feedback(pool(ctx), &self.0.id, &query)
+            .await
+            .map_err(database_error)?;
+        Ok(feedback.into_iter().map(Feedback).collect())
+    }
+}
+
+pub struct Feedback(db::Feedback);
+
+#[Object]
+impl Feedback {
+    async fn id(&self) -> ID {
+        ID(self.0.id.clone())
+    }
+
+    async fn content(&self) -> &str {
+        &self.0.content
+    }
+
+    async fn created_at(&self) -> &str {
+        &self.0.created_at
+    }
+
+    /// Set when the respondent edited their answer
+    async fn updated_at(&self) -> Option<&str> {
+        self.0.updated_at.as_deref()
+    }
+
+    async fn respondent_email(&self) -> Option<&str> {
+        self.0.respondent_email.as_deref()
+    }
+
+    async fn status(&self) -> FeedbackStatus {
+        db::FeedbackStatus::parse(&self.0.status)
+            .unwrap_or_default()
+            .into()
+    }
+
+    async fn sentiment(&self) -> Option<Sentiment> {
+        self.0
+            .sentiment_label
+            .as_deref()
+            .and_then(sentiment::Label::parse)
+            .map(Sentiment::from)
+    }
+
+    /// From -1 (negative) to 1 (positive)
+    async fn sentiment_score(&self) -> Option<f64> {
+        self.0.sentiment_score
+    }
+
+    async fn cycle_id(&self) -> Option<ID> {
+        self.0.cycle_id.clone().map(ID)
+    }
+
+    async fn tags(&self) -> &[String] {
+        &self.0.tags
+    }
+
+    /// Answers to the prompt's question bank fields, in the prompt's order
+    async fn answers(&self) -> Vec<Answer> {
+        self.0
+            .answers
+            .iter()
+            .map(|a| Answer {
+                field_id: ID(a.field_id.clone()),
+                label: a.label.clone(),
+                value: a.value.clone(),
+                verified: a.verified,
+            })
+            .collect()
+    }
+
+    async fn prompt(&self, ctx: &Context<'_>) -> async_graphql::Result<Option<Prompt>> {
+        let prompt = db::get_workspace_prompt(pool(ctx), &workspace(ctx).id, &self.0.prompt_id)
+            .await
+            .map_err(database_error)?;
+        Ok(prompt.map(Prompt))
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct Answer {
+    field_id: ID,
+    label: String,
+    value: String,
+    /// Came from a signed prefill link rather than the respondent
+    verified: bool,
+}
+
+#[derive(SimpleObject)]
+pub struct TagCount {
+    name: String,
+    count: i64,
+}
+
+/// Narrows `Prompt.feedback`; every condition given must hold
+#[derive(InputObject, Default)]
+pub struct FeedbackFilter {
+    /// Created or edited after this RFC 3339 timestamp or `YYYY-MM-DD` date
+    since: Option<String>,
+    /// Created at or after this timestamp or date
+    from: Option<String>,
+    /// Created before this timestamp, or on or before this date
+    to: Option<String>,
+    tag: Option<String>,
+    status: Option<FeedbackStatus>,
+    sentiment: Option<Sentiment>,
+    /// Text the feedback contains, ignoring case
+    contains: Option<String>,
+    cycle_id: Option<ID>,
+}
+
+impl FeedbackFilter {
+    fn into_db(self) -> async_graphql::Result<db::FeedbackFilter> {
+        let date = |name: &str, value: Option<String>, parse: fn(&str) -> Option<String>| {
+            value
+                .map(|v| {
+                    parse(&v).ok_or_else(|| {
+                        async_graphql::Error::new(format!(
+                            "{} must be an RFC 3339 timestamp or YYYY-MM-DD date",
+                            name
+                        ))
+                    })
+                })
+                .transpose()
+        };
+        Ok(db::FeedbackFilter {
+            since: date("since", self.since, db::parse_since)?,
+            created_from: date("from", self.from, db::parse_since)?,
+            created_before: date("to", self.to, db::parse_until)?,
+            tag: self.tag,
+            status: self.status.map(Into::into),
+            sentiment: self.sentiment.map(Into::into),
+            contains: self.contains,
+            cycle_id: self.cycle_id.map(|id| id.0),
+            ..Default::default()
+        })
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq, Default)]
+pub enum SortOrder {
+    #[default]
+    Newest,
+    Oldest,
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum PromptStatus {
+    Open,
+    Closed,
+    Archived,
+}
+
+impl From<db::PromptStatus> for PromptStatus {
+    fn from(status: db::PromptStatus) -> Self {
+        match status {
+            db::PromptStatus::Open => PromptStatus::Open,
+            db::PromptStatus::Closed => PromptStatus::Closed,
+            db::PromptStatus::Archived => PromptStatus::Archived,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum FeedbackStatus {
+    New,
+    Reviewed,
+    Actioned,
+    Dismissed,
+}
+
+impl From<db::FeedbackStatus> for FeedbackStatus {
+    fn from(status: db::FeedbackStatus) -> Self {
+        match status {
+            db::FeedbackStatus::New => FeedbackStatus::New,
+            db::FeedbackStatus::Reviewed => FeedbackStatus::Reviewed,
+            db::FeedbackStatus::Actioned => FeedbackStatus::Actioned,
+            db::FeedbackStatus::Dismissed => FeedbackStatus::Dismissed,
+        }
+    }
+}
+
+impl From<FeedbackStatus> for db::FeedbackStatus {
+    fn from(status: FeedbackStatus) -> Self {
+        match status {
+            FeedbackStatus::New => db::FeedbackStatus::New,
+            FeedbackStatus::Reviewed => db::FeedbackStatus::Reviewed,
+            FeedbackStatus::Actioned => db::FeedbackStatus::Actioned,
+            FeedbackStatus::Dismissed => db::FeedbackStatus::Dismissed,
+        }
+    }
+}
+
+#[derive(Enum, Copy, Clone, Eq, PartialEq)]
+pub enum Sentiment {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl From<sentiment::Label> for Sentiment {
+    fn from(label: sentiment::Label) -> Self {
+        match label {
+            sentiment::Label::Positive => Sentiment::Positive,
+            sentiment::Label::Neutral => Sentiment::Neutral,
+            sentiment::Label::Negative => Sentiment::Negative,
+        }
+    }
+}
+
+impl From<Sentiment> for sentiment::Label {
+    fn from(label: Sentiment) -> Self {
+        match label {
+            Sentiment::Positive => sentiment::Label::Positive,
+            Sentiment::Neutral => sentiment::Label::Neutral,
+            Sentiment::Negative => sentiment::Label::Negative,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_nested_query_is_confined_to_workspace() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let workspace = db::get_workspace_by_slug(&pool, "default")
+            .await
+            .unwrap()
+            .unwrap();
+        let prompt = db::create_prompt(&pool, "Onboarding", "").await.unwrap();
+        let first = db::create_feedback(&pool, &prompt.id, "Setup was easy")
+            .await
+            .unwrap();
+        db::add_tag(&pool, &first.id, "setup").await.unwrap();
+        db::create_feedback(&pool, &prompt.id, "Docs were thin")
+            .await
+            .unwrap();
+        let other = db::create_workspace(&pool, "other", "Other")
+            .await
+            .unwrap()
+            .unwrap();
+        let hidden = db::create_prompt_with_options(
+            &pool,
+            &other.id,
+            "Theirs",
+            "",
+            &db::PromptOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let schema = schema(pool.clone());
+        let response = execute(
+            &schema,
+            workspace.clone(),
+            async_graphql::Request::new(
+                r#"{
+                    prompts { title feedback(filter: {tag: "setup"}) { content tags prompt { title } } }
+                }"#,
+            ),
+        )
+        .await;
+        assert!(response.errors.is_empty(), "{:?}", response.errors);
+        let data = response.data.into_json().unwrap();
+        assert_eq!(data["prompts"].as_array().unwrap().len(), 1);
+        let feedback = &data["prompts"][0]["feedback"];
+        assert_eq!(feedback.as_array().unwrap().len(), 1);
+        assert_eq!(feedback[0]["content"], "Setup was easy");
+        assert_eq!(feedback[0]["tags"][0], "setup");
+        assert_eq!(feedback[0]["prompt"]["title"], "Onboarding");
+
+        let query = format!(r#"{{ prompt(id: "{}") {{ title }} }}"#, hidden.id);
+        let response = execute(&schema, workspace.clone(), query.as_str().into()).await;
+        assert_eq!(
+            response.data.into_json().unwrap()["prompt"],
+            serde_json::Value::Null
+        );
+
+        let response = execute(
+            &schema,
+            workspace,
+            r#"{ prompts { feedback(filter: {from: "yesterday"}) { id } } }"#.into(),
+        )
+        .await;
+        assert!(response.errors[0].message.contains("from must be"));
+    }
+}
@@ -0,0 +1,181 @@
+//! Translations for the pages respondents see: the feedback form and what
+//! follows a submission. Messages live in Fluent catalogs under `locales/`,
+//! compiled into the binary. The admin UI stays English.
+
+use fluent_bundle::concurrent::FluentBundle;
+use fluent_bundle::{FluentArgs, FluentResource, FluentValue};
+use fluent_langneg::{negotiate_languages, NegotiationStrategy};
+use std::sync::OnceLock;
+use unic_langid::LanguageIdentifier;
+
+/// Language used when nothing else matches, and for missing messages
+pub const DEFAULT: &str = "en";
+
+/// Supported languages as (code, name in that language), for pickers
+pub const LANGUAGES: &[(&str, &str)] = &[
+    ("en", "English"),
+    ("de", "Deutsch"),
+    ("es", "Español"),
+    ("fr", "Français"),
+];
+
+const SOURCES: &[(&str, &str)] = &[
+    ("en", include_str!("../locales/en.ftl")),
+    ("de", include_str!("../locales/de.ftl")),
+    ("es", include_str!("../locales/es.ftl")),
+    ("fr", include_str!("../locales/fr.ftl")),
+];
+
+/// One language's messages
+pub struct Catalog {
+    lang: &'static str,
+    bundle: FluentBundle<FluentResource>,
+}
+
+impl Catalog {
+    fn load(lang: &'static str, source: &str) -> Self {
+        let id: LanguageIdentifier = lang.parse().expect("catalog language is valid");
+        let resource = FluentResource::try_new(source.to_string())
+            .unwrap_or_else(|(_, errors)| panic!("locales/{}.ftl: {:?}", lang, errors));
+        let mut bundle = FluentBundle::new_concurrent(vec![id]);
+        // Isolation marks would end up inside attributes and plain text fields
+        bundle.set_use_isolating(false);
+        bundle
+            .add_resource(resource)
+            .unwrap_or_else(|errors| panic!("locales/{}.ftl: {:?}", lang, errors));
+        Catalog { lang, bundle }
+    }
+
+    /// Language code, for `<html lang>`
+    pub fn lang(&self) -> &'static str {
+        self.lang
+    }
+
+    /// The message `id`, from English if this catalog lacks it
+    pub fn msg(&self, id: &str) -> String {
+        self.format(id, None)
+    }
+
+    /// The message `id` with the number `value` as `$name`
+    pub fn msg_with(&self, id: &str, name: &str, value: &i64) -> String {
+        let mut args = FluentArgs::new();
+        args.set(name.to_string(), FluentValue::from(*value));
+        self.format(id, Some(&args))
+    }
+
+    fn format(&self, id: &str, args: Option<&FluentArgs>) -> String {
+        let Some(pattern) = self.bundle.get_message(id).and_then(|m| m.value()) else {
+            return match self.lang {
+                DEFAULT => id.to_string(),
+                _ => get(DEFAULT).format(id, args),
+            };
+        };
+        let mut errors = Vec::new();
+        self.bundle
+            .format_pattern(pattern, args, &mut errors)
+            .into_owned()
+    }
+}
+
+fn catalogs() -> &'static [Catalog] {
+    static CATALOGS: OnceLock<Vec<Catalog>> = OnceLock::new();
+    CATALOGS.get_or_init(|| {
+        SOURCES
+            .iter()
+            .map(|(lang, source)| Catalog::load(lang, source))
+            .collect()
+    })
+}
+
+pub fn is_supported(lang: &str) -> bool {
+    LANGUAGES.iter().any(|(code, _)| *code == lang)
+}
+
+/// The catalog for a supported language code, else the default
+pub fn get(lang: &str) -> &'static Catalog {
+    let catalogs = catalogs();
+    catalogs
+        .iter()
+        .find(|c| c.lang == lang)
+        .unwrap_or(&catalogs[0])
+}
+
+/// Name of a supported language, in that language
+pub fn name(lang: &str) -> &'static str {
+    LANGUAGES
+        .iter()
+        .find(|(code, _)| *code == lang)
+        .map_or("", |(_, name)| name)
+}
+
+/// The best of the `available` language codes for an `Accept-Language`
+/// header, or `fallback` if none of them match
+pub fn negotiate(
+    available: &[&str],
+    fallback: &str,
+    accept_language: Option<&str>,
+) -> &'static Catalog {
+    let requested = fluent_langneg::accepted_languages::parse(accept_language.unwrap_or(""));
+    let available: Vec<LanguageIdentifier> = available
+        .iter()
+        .filter_map(|code| code.parse().ok())
+        .collect();
+    let matched = negotiate_languages(&requested, &available, None, NegotiationStrategy::Lookup);
+    get(matched.first().map_or(fallback, |id| id.language.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_catalogs_have_every_message() {
+        let ids = SOURCES[0]
+            .1
+            .lines()
+            .filter_map(|line| line.split_once(" = "))
+            .map(|(id, _)| id)
+            .filter(|id| !id.starts_with('#'));
+        for id in ids {
+            for catalog in catalogs() {
+                assert!(
+                    catalog.bundle.has_message(id),
+                    "{} is missing {}",
+                    catalog.lang,
+                    id
+                );
+            }
+        }
+        assert_eq!(catalogs().len(), LANGUAGES.len());
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let all: Vec<&str> = LANGUAGES.iter().map(|(code, _)| *code).collect();
+        assert_eq!(negotiate(&all, DEFAULT, None).lang(), "en");
+        assert_eq!(
+            negotiate(&all, DEFAULT, Some("de-AT,de;q=0.9,en;q=0.5")).lang(),
+            "de"
+        );
+        assert_eq!(
+            negotiate(&all, DEFAULT, Some("pt-BR, fr;q=0.8")).lang(),
+            "fr"
+        );
+        assert_eq!(negotiate(&all, DEFAULT, Some("ja")).lang(), "en");
+        // Only the offered languages count
+        assert_eq!(
+            negotiate(&["es", "fr"], "es", Some("de, fr;q=0.5")).lang(),
+            "fr"
+        );
+        assert_eq!(negotiate(&["es"], "es", Some("de")).lang(), "es");
+    }
+
+    #[test]
+    fn test_msg_with() {
+        assert_eq!(
+            get("fr").msg_with("form-max-length", "max", &280),
+            "Jusqu’à 280 caractères."
+        );
+        assert_eq!(get("de").msg("no-such-message"), "no-such-message");
+    }
+}
@@ -0,0 +1,221 @@
+//! Single-use invite links. An editor generates a batch of tokens for a
+//! prompt, each becoming a `/feedback/:id?invite=` link that can submit
+//! exactly once. Once the prompt is made invite-only its form only opens
+//! through one, so every invited participant answers at most once and nobody
+//! else answers at all.
+
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+
+use crate::{auth, db};
+
+/// Most invites generated in one go
+pub const MAX_BATCH: i64 = 500;
+
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Invite {
+    pub id: String,
+    pub token: String,
+    pub created_at: String,
+    /// When its submission was stored; None while outstanding
+    pub redeemed_at: Option<String>,
+}
+
+impl Invite {
+    pub fn is_redeemed(&self) -> bool {
+        self.redeemed_at.is_some()
+    }
+}
+
+/// What an `invite` parameter amounts to for one prompt
+#[derive(Debug, PartialEq, Eq)]
+pub enum Check {
+    /// The prompt isn't invite-only, so anyone may answer
+    NotRequired,
+    /// An invite that hasn't been used yet, by token
+    Outstanding(String),
+    Missing,
+    Redeemed,
+    Unknown,
+}
+
+/// Generate `count` new invites for a prompt
+pub async fn create(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    count: i64,
+) -> Result<Vec<Invite>, sqlx::Error> {
+    let created_at = Utc::now().to_rfc3339();
+    let mut tx = pool.begin().await?;
+    let mut invites = Vec::new();
+    for _ in 0..count {
+        let invite = Invite {
+            id: uuid::Uuid::new_v4().to_string(),
+            token: auth::random_token(),
+            created_at: created_at.clone(),
+            redeemed_at: None,
+        };
+        sqlx::query("INSERT INTO invites (id, prompt_id, token, created_at) VALUES (?, ?, ?, ?)")
+            .bind(&invite.id)
+            .bind(prompt_id)
+            .bind(&invite.token)
+            .bind(&invite.created_at)
+            .execute(&mut *tx)
+            .await?;
+        invites.push(invite);
+    }
+    tx.commit().await?;
+    Ok(invites)
+}
+
+/// A prompt's invites, oldest batch first
+pub async fn list(pool: &SqlitePool, prompt_id: &str) -> Result<Vec<Invite>, sqlx::Error> {
+    sqlx::query_as::<_, Invite>(
+        "SELECT id, token, created_at, redeemed_at FROM invites WHERE prompt_id = ? ORDER BY created_at, rowid",
+    )
+    .bind(prompt_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Look up `given` against the prompt's invites without using it up
+pub async fn check(
+    pool: &SqlitePool,
+    prompt: &db::Prompt,
+    given: Option<&str>,
+) -> Result<Check, sqlx::Error> {
+    if !prompt.invite_only {
+        return Ok(Check::NotRequired);
+    }
+    let prompt_id = prompt.id.as_str();
+    let Some(token) = given.map(str::trim).filter(|t| !t.is_empty()) else {
+        return Ok(Check::Missing);
+    };
+    let found: Option<(Option<String>,)> =
+        sqlx::query_as("SELECT redeemed_at FROM invites WHERE prompt_id = ? AND token = ?")
+            .bind(prompt_id)
+            .bind(token)
+            .fetch_optional(pool)
+            .await?;
+    Ok(match found {
+        None => Check::Unknown,
+        Some((Some(_),)) => Check::Redeemed,
+        Some((None,)) => Check::Outstanding(token.to_string()),
+    })
+}
+
+/// Open the prompt's form only through an invite, or to anyone again
+pub async fn set_required(
+    pool: &SqlitePool,
+    prompt_id: &str,
+    required: bool,
+) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE prompts SET invite_only = ? WHERE id = ?")
+        .bind(required)
+        .bind(prompt_id)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Mark an invite used; false means it already was (or doesn't exist), so
+/// the submission must be refused
+pub async fn redeem(pool: &SqlitePool, prompt_id: &str, token: &str) -> Result<bool, sqlx::Error> {
+    let result = sqlx::query(
+        "UPDATE invites SET redeemed_at = ? WHERE prompt_id = ? AND token = ? AND redeemed_at IS NULL",
+    )
+    .bind(Utc::now().to_rfc3339())
+    .bind(prompt_id)
+    .bind(token)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Give an invite back when its submission couldn't be stored, so a retry works
+pub async fn release(pool: &SqlitePool, prompt_id: &str, token: &str) -> Result<(), sqlx::Error> {
+    sqlx::query("UPDATE invites SET redeemed_at = NULL WHERE prompt_id = ? AND token = ?")
+        .bind(prompt_id)
+        .bind(token)
+        .execute(pool)
+        .await?;
+    Ok(())
+}
+
+/// Withdraw an outstanding invite; redeemed ones stay as the record of who
+/// answered. Returns whether anything was deleted.
+pub async fn revoke(pool: &SqlitePool, prompt_id: &str, id: &str) -> Result<bool, sqlx::Error> {
+    let result =
+        sqlx::query("DELETE FROM invites WHERE prompt_id = ? AND id = ? AND redeemed_at IS NULL")
+            .bind(prompt_id)
+            .bind(id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_invites_redeem_once() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt(&pool, "Q", "").await.unwrap();
+        let invites = create(&pool, &prompt.id, 3).await.unwrap();
+        assert_eq!(invites.len(), 3);
+        let token = invites[0].token.clone();
+
+        // Having invites doesn't close the form; the prompt's setting does
+        assert_eq!(
+            check(&pool, &prompt, None).await.unwrap(),
+            Check::NotRequired
+        );
+        set_required(&pool, &prompt.id, true).await.unwrap();
+        let prompt = db::get_prompt_by_id(&pool, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(check(&pool, &prompt, None).await.unwrap(), Check::Missing);
+        assert_eq!(
+            check(&pool, &prompt, Some("nope")).await.unwrap(),
+            Check::Unknown
+        );
+        assert_eq!(
+            check(&pool, &prompt, Some(&token)).await.unwrap(),
+            Check::Outstanding(token.clone())
+        );
+
+        assert!(redeem(&pool, &prompt.id, &token).await.unwrap());
+        assert!(!redeem(&pool, &prompt.id, &token).await.unwrap());
+        assert_eq!(
+            check(&pool, &prompt, Some(&token)).await.unwrap(),
+            Check::Redeemed
+        );
+        release(&pool, &prompt.id, &token).await.unwrap();
+        assert!(redeem(&pool, &prompt.id, &token).await.unwrap());
+
+        // Redeemed invites can't be revoked, outstanding ones can
+        assert!(!revoke(&pool, &prompt.id, &invites[0].id).await.unwrap());
+        assert!(revoke(&pool, &prompt.id, &invites[1].id).await.unwrap());
+        let left = list(&pool, &prompt.id).await.unwrap();
+        assert_eq!(left.len(), 2);
+        assert_eq!(left.iter().filter(|i| i.is_redeemed()).count(), 1);
+
+        // Revoking every outstanding invite doesn't open the form to anyone
+        assert!(revoke(&pool, &prompt.id, &invites[2].id).await.unwrap());
+        let prompt = db::get_prompt_by_id(&pool, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(check(&pool, &prompt, None).await.unwrap(), Check::Missing);
+
+        // Another prompt's token opens nothing here
+        let other = db::create_prompt(&pool, "Other", "").await.unwrap();
+        let theirs = create(&pool, &other.id, 1).await.unwrap();
+        assert_eq!(
+            check(&pool, &prompt, Some(&theirs[0].token)).await.unwrap(),
+            Check::Unknown
+        );
+    }
+}
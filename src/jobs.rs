@@ -0,0 +1,144 @@
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{backup, db, digest, email, maintenance, notify, readonly, retention};
+
+const CYCLE_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+const MAINTENANCE_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+const READ_ONLY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+const RETENTION_CHECK_INTERVAL: Duration = Duration::from_secs(15 * 60);
+/// Cron schedules have minute resolution
+const EMAIL_DIGEST_CHECK_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Periodically open new cycles for recurring prompts whose period has ended,
+/// so a cycle appears on schedule even before anyone submits to it
+pub fn spawn_cycle_rollover(pool: SqlitePool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(CYCLE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = roll_over_cycles(&pool).await {
+                eprintln!("Cycle rollover failed: {}", e);
+            }
+        }
+    });
+}
+
+async fn roll_over_cycles(pool: &SqlitePool) -> Result<(), sqlx::Error> {
+    let now = Utc::now();
+    for prompt in db::get_recurring_prompts(pool).await? {
+        db::current_cycle(pool, &prompt, now).await?;
+    }
+    Ok(())
+}
+
+/// Write a database snapshot into `dir` every `every`, keeping the newest `keep`
+pub fn spawn_snapshots(pool: SqlitePool, dir: PathBuf, every: Duration, keep: usize) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(every);
+        loop {
+            interval.tick().await;
+            match backup::write_snapshot(&pool, &dir, keep).await {
+                Ok(path) => println!("Wrote snapshot {}", path.display()),
+                Err(e) => eprintln!("Snapshot failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Vacuum and analyze once per day, inside the quiet-hours `window`
+pub fn spawn_maintenance(pool: SqlitePool, window: maintenance::Window) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(MAINTENANCE_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match maintenance::is_due(&pool, window, Utc::now()).await {
+                Ok(true) => match maintenance::run(&pool).await {
+                    Ok(run) => println!(
+                        "Maintenance reclaimed {} bytes ({} vacuum)",
+                        run.reclaimed_bytes(),
+                        run.vacuum
+                    ),
+                    Err(e) => eprintln!("Maintenance failed: {}", e),
+                },
+                Ok(false) => {}
+                Err(e) => eprintln!("Maintenance check failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Delete feedback past its retention period once a day, logging each
+/// prompt that lost entries
+pub fn spawn_retention_purge(pool: SqlitePool) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(RETENTION_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match retention::is_due(&pool, Utc::now()).await {
+                Ok(true) => match retention::purge(&pool, Utc::now()).await {
+                    Ok(run) => {
+                        for purged in run.purged() {
+                            println!(
+                                "Retention purge deleted {} entries created before {} from prompt {} ({})",
+                                purged.deleted, purged.cutoff, purged.prompt_title, purged.prompt_id
+                            );
+                        }
+                    }
+                    Err(e) => eprintln!("Retention purge failed: {}", e),
+                },
+                Ok(false) => {}
+                Err(e) => eprintln!("Retention check failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Post notification digests as they come due
+pub fn spawn_notification_digests(pool: SqlitePool, http: reqwest::Client) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(DIGEST_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match notify::send_due_digests(&pool, &http, Utc::now()).await {
+                Ok(0) => {}
+                Ok(sent) => println!("Sent {} notification digest(s)", sent),
+                Err(e) => eprintln!("Notification digests failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Email the feedback digest to subscribed admins on `schedule`
+pub fn spawn_email_digests(pool: SqlitePool, mailer: email::Mailer, schedule: digest::Schedule) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(EMAIL_DIGEST_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match digest::send_due(&pool, &mailer, &schedule, Utc::now()).await {
+                Ok(0) => {}
+                Ok(sent) => println!("Emailed the feedback digest to {} admin(s)", sent),
+                Err(e) => eprintln!("Feedback digest failed: {}", e),
+            }
+        }
+    });
+}
+
+/// Check whether the database takes writes, so submissions pause when it
+/// stops and resume on their own once it recovers
+pub fn spawn_read_only_probe(pool: SqlitePool, monitor: Arc<readonly::Monitor>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(READ_ONLY_CHECK_INTERVAL);
+        loop {
+            interval.tick().await;
+            match readonly::probe(&pool).await {
+                Ok(writable) => monitor.set(!writable),
+                Err(e) => eprintln!("Read-only check failed: {}", e),
+            }
+        }
+    });
+}
@@ -0,0 +1,275 @@
+//! The most common words in a prompt's feedback, for a quick sense of its
+//! themes. Text is lowercased and split into words; stop words, numbers and
+//! anything shorter than three letters are dropped. Terms are counted once
+//! per entry, so one long rant doesn't outweigh many short mentions.
+
+use std::collections::{HashMap, HashSet};
+
+/// How many terms the prompt page shows
+pub const TOP_TERMS: usize = 20;
+/// Shorter words are almost never a theme
+const MIN_LEN: usize = 3;
+
+const STOP_WORDS: &[&str] = &[
+    "about",
+    "above",
+    "after",
+    "again",
+    "against",
+    "all",
+    "also",
+    "and",
+    "any",
+    "are",
+    "aren't",
+    "because",
+    "been",
+    "before",
+    "being",
+    "below",
+    "between",
+    "both",
+    "but",
+    "can",
+    "can't",
+    "cannot",
+    "could",
+    "couldn't",
+    "did",
+    "didn't",
+    "does",
+    "doesn't",
+    "doing",
+    "don't",
+    "down",
+    "during",
+    "each",
+    "even",
+    "ever",
+    "every",
+    "few",
+    "for",
+    "from",
+    "further",
+    "get",
+    "gets",
+    "got",
+    "had",
+    "hadn't",
+    "has",
+    "hasn't",
+    "have",
+    "haven't",
+    "having",
+    "her",
+    "here",
+    "hers",
+    "herself",
+    "him",
+    "himself",
+    "his",
+    "how",
+    "i'd",
+    "i'll",
+    "i'm",
+    "i've",
+    "into",
+    "isn't",
+    "it's",
+    "its",
+    "itself",
+    "just",
+    "let's",
+    "lot",
+    "lots",
+    "many",
+    "more",
+    "most",
+    "much",
+    "must",
+    "mustn't",
+    "myself",
+    "not",
+    "now",
+    "off",
+    "once",
+    "one",
+    "only",
+    "other",
+    "ought",
+    "our",
+    "ours",
+    "ourselves",
+    "out",
+    "over",
+    "own",
+    "really",
+    "same",
+    "shan't",
+    "she",
+    "she'd",
+    "she'll",
+    "she's",
+    "should",
+    "shouldn't",
+    "some",
+    "still",
+    "such",
+    "than",
+    "that",
+    "that's",
+    "the",
+    "their",
+    "theirs",
+    "them",
+    "themselves",
+    "then",
+    "there",
+    "there's",
+    "these",
+    "they",
+    "they'd",
+    "they'll",
+    "they're",
+    "they've",
+    "thing",
+    "things",
+    "this",
+    "those",
+    "through",
+    "too",
+    "under",
+    "until",
+    "very",
+    "was",
+    "wasn't",
+    "way",
+    "we'd",
+    "we'll",
+    "we're",
+    "we've",
+    "well",
+    "were",
+    "weren't",
+    "what",
+    "what's",
+    "when",
+    "when's",
+    "where",
+    "where's",
+    "which",
+    "while",
+    "who",
+    "who's",
+    "whom",
+    "why",
+    "why's",
+    "will",
+    "with",
+    "won't",
+    "would",
+    "wouldn't",
+    "yes",
+    "yet",
+    "you",
+    "you'd",
+    "you'll",
+    "you're",
+    "you've",
+    "your",
+    "yours",
+    "yourself",
+    "yourselves",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub word: String,
+    /// Entries mentioning the word
+    pub count: usize,
+}
+
+/// The `limit` words found in the most entries, most common first (ties
+/// alphabetically)
+pub fn top_terms<'a>(texts: impl IntoIterator<Item = &'a str>, limit: usize) -> Vec<Term> {
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for text in texts {
+        let words: HashSet<String> = words(text).collect();
+        for word in words {
+            *counts.entry(word).or_default() += 1;
+        }
+    }
+    let mut terms: Vec<Term> = counts
+        .into_iter()
+        .map(|(word, count)| Term { word, count })
+        .collect();
+    terms.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.word.cmp(&b.word)));
+    terms.truncate(limit);
+    terms
+}
+
+/// Text as terms are counted: lowercased, with curly apostrophes made
+/// straight. Matching a term against feedback should fold both sides.
+pub fn fold(text: &str) -> String {
+    text.to_lowercase().replace('’', "'")
+}
+
+/// The words of `text` worth counting
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !(c.is_alphanumeric() || c == '\'' || c == '’'))
+        .map(|word| fold(word.trim_matches(|c| c == '\'' || c == '’')))
+        .filter(|word| word.chars().count() >= MIN_LEN)
+        .filter(|word| !word.chars().all(|c| c.is_numeric()))
+        .filter(|word| !STOP_WORDS.contains(&word.as_str()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_top_terms() {
+        let texts = [
+            "The search is slow, really slow. SLOW!",
+            "Search results aren't relevant",
+            "Love the new dark mode; search could be faster",
+            "Dark mode please, and 2024 pricing",
+        ];
+        let terms = top_terms(texts, 3);
+        assert_eq!(
+            terms,
+            vec![
+                Term {
+                    word: "search".to_string(),
+                    count: 3
+                },
+                Term {
+                    word: "dark".to_string(),
+                    count: 2
+                },
+                Term {
+                    word: "mode".to_string(),
+                    count: 2
+                },
+            ]
+        );
+
+        // Counted once per entry, without stop words, numbers or short words
+        let all = top_terms(texts, 100);
+        assert_eq!(all.iter().find(|t| t.word == "slow").unwrap().count, 1);
+        assert!(all
+            .iter()
+            .all(|t| !["the", "aren't", "2024", "is"].contains(&t.word.as_str())));
+        assert!(top_terms(["Don’t ‘quote’ me"], 10)
+            .iter()
+            .any(|t| t.word == "quote"));
+    }
+
+    #[test]
+    fn test_fold_matches_counted_terms() {
+        let text = "ÉCOLE d’été";
+        let terms = top_terms([text], 10);
+        assert!(terms.iter().all(|t| fold(text).contains(&t.word)));
+        assert_eq!(fold(text), "école d'été");
+    }
+}
@@ -0,0 +1,4 @@
+//! Library half of the crate. The server itself is the `feedback-app` binary;
+//! this target exists so other Rust services can talk to it.
+
+pub mod client;
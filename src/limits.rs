@@ -0,0 +1,98 @@
+//! Request timeouts, body size limits and the global concurrency cap. A
+//! stuck SQLite write or a slow outbound call fails its own request with a
+//! 503 instead of piling up work until the runtime stops responding, and an
+//! oversized body is refused before it is read into memory.
+
+use axum::{
+    extract::{Request, State},
+    http::{header, StatusCode},
+    middleware::Next,
+    response::{AppendHeaders, IntoResponse, Response},
+    BoxError,
+};
+use std::time::Duration;
+use tower::{
+    limit::GlobalConcurrencyLimitLayer, load_shed::error::Overloaded, timeout::error::Elapsed,
+};
+
+use crate::error::ApiError;
+
+/// How long a client should wait before retrying a shed or timed-out request
+pub const RETRY_AFTER_SECS: u64 = 5;
+
+#[derive(Debug, Clone)]
+pub struct Limits {
+    /// Applied to ordinary pages and API calls
+    pub request_timeout: Duration,
+    /// Applied to routes that copy the whole database (backup, export, import, vacuum)
+    pub slow_request_timeout: Duration,
+    /// Requests handled at once; more than this are turned away, not queued
+    pub max_concurrent_requests: usize,
+    /// Largest form or JSON body read, in bytes (imports have their own limit)
+    pub max_body_bytes: usize,
+    /// How long a request refused for an oversized body waits for its
+    /// answer, so a script pasting novels can't do it quickly
+    pub oversized_delay: Duration,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Limits {
+            request_timeout: Duration::from_secs(15),
+            slow_request_timeout: Duration::from_secs(300),
+            max_concurrent_requests: 256,
+            max_body_bytes: 256 * 1024,
+            oversized_delay: Duration::from_secs(2),
+        }
+    }
+}
+
+impl Limits {
+    /// One limiter shared by every route it's layered onto
+    pub fn concurrency_layer(&self) -> GlobalConcurrencyLimitLayer {
+        GlobalConcurrencyLimitLayer::new(self.max_concurrent_requests.max(1))
+    }
+}
+
+/// Marks a response to an oversized body that doesn't carry a 413, such as
+/// the message htmx swaps into the form
+#[derive(Debug, Clone, Copy)]
+pub struct Oversized;
+
+/// Hold back answers to oversized bodies by the oversized delay. Layered
+/// outside the concurrency limit, so a client being slowed down doesn't
+/// keep a slot.
+pub async fn tarpit(State(delay): State<Duration>, request: Request, next: Next) -> Response {
+    let response = next.run(request).await;
+    if response.status() == StatusCode::PAYLOAD_TOO_LARGE
+        || response.extensions().get::<Oversized>().is_some()
+    {
+        tokio::time::sleep(delay).await;
+    }
+    response
+}
+
+/// Turn a timeout or load-shedding error into a 503 with `Retry-After`
+pub async fn handle_error(err: BoxError) -> Response {
+    let error = if err.is::<Elapsed>() {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "timeout",
+            "The request took too long to complete",
+        )
+    } else if err.is::<Overloaded>() {
+        ApiError::new(
+            StatusCode::SERVICE_UNAVAILABLE,
+            "overloaded",
+            "The server is busy; try again shortly",
+        )
+    } else {
+        eprintln!("Request failed: {}", err);
+        ApiError::internal()
+    };
+    (
+        AppendHeaders([(header::RETRY_AFTER, RETRY_AFTER_SECS.to_string())]),
+        error,
+    )
+        .into_response()
+}
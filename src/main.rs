@@ -1,545 +1,14861 @@
+mod access;
+mod archive;
+mod assets;
+mod auth;
+mod backup;
+mod bootstrap;
+mod bounces;
+mod cache;
+mod calendar;
+mod captcha;
+mod cli;
+mod cors;
 mod db;
+mod digest;
+mod doctor;
+mod email;
+mod embed;
+mod error;
+mod export;
+mod feed;
+mod flash;
+mod graphql;
+mod i18n;
+mod invites;
+mod jobs;
+mod keywords;
+mod limits;
+mod maintenance;
+mod markdown;
+mod notify;
+mod nps;
+mod oidc;
+mod origin;
+mod prefill;
+mod privacy;
+mod reactions;
+mod readonly;
+mod retention;
+mod rules;
+mod sentiment;
+mod settings;
+mod setup;
+mod signing;
+mod spam;
+mod storage;
+mod tls;
+mod validation;
+mod webhook;
 
 use askama::Template;
+use auth::{RequireEditor, RequireOwner, RequireViewer};
 use axum::{
-    extract::{Host, Path, State},
-    http::StatusCode,
-    response::{Html, IntoResponse, Redirect},
-    routing::{delete, get},
-    Form, Router,
+    async_trait,
+    body::Body,
+    error_handling::HandleErrorLayer,
+    extract::{
+        rejection::{FormRejection, JsonRejection, QueryRejection},
+        ConnectInfo, DefaultBodyLimit, FromRequestParts, Path, Query, State,
+    },
+    http::{header, request::Parts, HeaderMap, StatusCode},
+    response::{
+        sse::{Event, KeepAlive, Sse},
+        AppendHeaders, Html, IntoResponse, Redirect, Response,
+    },
+    routing::{delete, get, post, put},
+    Form, Json, Router,
 };
+use error::{ApiError, FieldError};
 use serde::Deserialize;
 use sqlx::sqlite::SqlitePool;
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, RwLock};
+use tokio::sync::broadcast;
+use tokio_stream::{wrappers::BroadcastStream, Stream, StreamExt};
+use tower::{util::option_layer, ServiceBuilder};
+
+/// Custom Askama filters, found by name from every template in this module
+mod filters {
+    /// Sanitized HTML for Markdown text; pair with `|safe`. (Askama's own
+    /// `markdown` filter doesn't sanitize, so it isn't enabled.)
+    pub fn md<T: std::fmt::Display>(text: T) -> askama::Result<String> {
+        Ok(crate::markdown::render(&text.to_string()))
+    }
+}
 
 // Application state
 pub struct AppState {
     pub pool: SqlitePool,
+    pub http_client: reqwest::Client,
+    /// Delivers prompt webhooks, refusing internal addresses unless allowed
+    pub webhooks: webhook::Client,
+    /// Newly submitted feedback, fanned out to live admin views
+    pub feedback_events: broadcast::Sender<db::Feedback>,
+    /// Cancelled when the server starts shutting down, ending live streams
+    /// so graceful shutdown isn't held open by them
+    pub shutdown: tokio_util::sync::CancellationToken,
+    /// Single sign-on provider, when configured
+    pub oidc: Option<oidc::Config>,
+    /// Quiet hours for the scheduled vacuum
+    pub maintenance_window: maintenance::Window,
+    /// Checks that quarantine bot submissions
+    pub spam: spam::Screen,
+    /// CAPTCHA required on the feedback form, when configured
+    pub captcha: Option<captcha::Config>,
+    /// Sites allowed to call the JSON submit endpoint from the browser
+    pub cors: Option<cors::Config>,
+    pub graphql: graphql::Schema,
+    /// Note identical feedback posted to other prompts when listing entries
+    pub detect_duplicates: bool,
+    /// Request timeouts and the concurrency cap applied by the router
+    pub limits: limits::Limits,
+    /// Sends admin replies to respondents, when configured
+    pub mailer: Option<email::Mailer>,
+    /// When the feedback digest goes out; `None` while email is off
+    pub digest_schedule: Option<digest::Schedule>,
+    /// Open abuse reports that suspend a prompt pending review; 0 never suspends
+    pub abuse_suspend_threshold: i64,
+    /// Caps on stored response bytes per prompt and per workspace
+    pub storage_quotas: storage::Quotas,
+    /// Set while the database refuses writes; public forms pause submissions
+    pub read_only: Arc<readonly::Monitor>,
+    /// Site name, base URL and branding
+    pub settings: RwLock<settings::Settings>,
+    /// Skip the setup wizard and let everyone act as an owner until the first
+    /// account exists; only for installs behind their own sign-in
+    pub open_admin: bool,
+    /// Prompt records for the public pages; evict on every prompt change
+    pub prompts: cache::PromptCache,
+    /// Wrong access codes by prompt and client
+    pub access_attempts: access::Throttle,
+    /// A reverse proxy in front sets the `Forwarded` / `X-Forwarded-*`
+    /// headers; otherwise they're ignored
+    pub trust_proxy_headers: bool,
+}
+
+impl AppState {
+    pub fn new(pool: SqlitePool) -> Self {
+        let (feedback_events, _) = broadcast::channel(100);
+        AppState {
+            graphql: graphql::schema(pool.clone()),
+            pool,
+            http_client: reqwest::Client::new(),
+            webhooks: webhook::Client::default(),
+            feedback_events,
+            shutdown: tokio_util::sync::CancellationToken::new(),
+            oidc: None,
+            maintenance_window: maintenance::Window::default(),
+            spam: spam::Screen::default(),
+            captcha: None,
+            cors: None,
+            detect_duplicates: false,
+            limits: limits::Limits::default(),
+            mailer: None,
+            digest_schedule: None,
+            abuse_suspend_threshold: 3,
+            storage_quotas: storage::Quotas::default(),
+            read_only: Arc::new(readonly::Monitor::default()),
+            settings: RwLock::new(settings::Settings::default()),
+            open_admin: false,
+            prompts: cache::PromptCache::default(),
+            access_attempts: access::Throttle::default(),
+            trust_proxy_headers: false,
+        }
+    }
+
+    pub fn settings(&self) -> settings::Settings {
+        self.settings.read().unwrap().clone()
+    }
+
+    /// Whether emailed copies can go out: they need a mailer and a base URL
+    /// for their edit links
+    pub fn receipts_enabled(&self) -> bool {
+        self.mailer.is_some() && self.settings.read().unwrap().base_url.is_some()
+    }
+
+    /// Branding for pages built on `base.html`
+    pub fn layout(&self) -> settings::Layout {
+        let mut layout = self.settings.read().unwrap().layout();
+        layout.flash = flash::take();
+        layout
+    }
+}
+
+/// The workspace named by a route's `:workspace` segment
+struct CurrentWorkspace(db::Workspace);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for CurrentWorkspace {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        let is_api = parts.uri.path().starts_with("/api/");
+        let not_found = || {
+            if is_api {
+                ApiError::not_found("Workspace not found").into_response()
+            } else {
+                (
+                    StatusCode::NOT_FOUND,
+                    Html("Workspace not found".to_string()),
+                )
+                    .into_response()
+            }
+        };
+
+        let Path(params) = Path::<HashMap<String, String>>::from_request_parts(parts, state)
+            .await
+            .map_err(|_| not_found())?;
+        let slug = params.get("workspace").ok_or_else(not_found)?;
+
+        match db::get_workspace_by_slug(&state.pool, slug).await {
+            Ok(Some(workspace)) => Ok(CurrentWorkspace(workspace)),
+            Ok(None) => Err(not_found()),
+            Err(e) => Err(ApiError::from(e).into_response()),
+        }
+    }
+}
+
+/// Origin for links back to this server: the configured base URL, else the
+/// one the client used to reach us (honouring reverse proxy headers when
+/// they're trusted). Only fit for links shown back to that client; emailed
+/// links use the base URL alone.
+struct RequestOrigin(String);
+
+#[async_trait]
+impl FromRequestParts<Arc<AppState>> for RequestOrigin {
+    type Rejection = Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &Arc<AppState>,
+    ) -> Result<Self, Self::Rejection> {
+        if let Some(base_url) = state.settings.read().unwrap().base_url.clone() {
+            return Ok(RequestOrigin(base_url));
+        }
+        match origin::detect(parts, state.trust_proxy_headers) {
+            Some(origin) => Ok(RequestOrigin(origin)),
+            None if parts.uri.path().starts_with("/api/") => {
+                Err(ApiError::bad_request("Missing or invalid Host header").into_response())
+            }
+            None => Err((
+                StatusCode::BAD_REQUEST,
+                Html("Missing or invalid Host header".to_string()),
+            )
+                .into_response()),
+        }
+    }
 }
 
 // Templates
+#[derive(Template)]
+#[template(path = "login.html")]
+struct LoginTemplate {
+    layout: settings::Layout,
+    next: String,
+    error: Option<String>,
+    sso: bool,
+}
+
+#[derive(Template)]
+#[template(path = "setup.html")]
+struct SetupTemplate {
+    layout: settings::Layout,
+    error: Option<String>,
+    email: String,
+    site_name: String,
+    base_url: String,
+    sample_prompt: bool,
+    min_password_len: usize,
+}
+
+#[derive(Template)]
+#[template(path = "admin_users.html")]
+struct AdminUsersTemplate {
+    layout: settings::Layout,
+    users: Vec<db::User>,
+    current_user_id: Option<String>,
+    roles: &'static [db::Role],
+    min_password_len: usize,
+    sso: bool,
+    /// Cron expression the digest is sent on, while email is configured
+    digest_schedule: Option<String>,
+    /// Addresses that have been bouncing, disabled ones first
+    bounces: Vec<bounces::Bounce>,
+    disable_after: i64,
+}
+
+#[derive(Template)]
+#[template(path = "admin_tokens.html")]
+struct AdminTokensTemplate {
+    layout: settings::Layout,
+    tokens: Vec<TokenRow>,
+    /// The secret of a token just created, shown only once
+    new_token: Option<String>,
+    roles: &'static [db::Role],
+    workspaces: Vec<db::Workspace>,
+}
+
+struct TokenRow {
+    token: db::ApiToken,
+    /// Name of the workspace the token is limited to
+    workspace: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin_settings.html")]
+struct AdminSettingsTemplate {
+    layout: settings::Layout,
+    settings: settings::Settings,
+    default_accent_color: &'static str,
+    error: Option<String>,
+    /// When the retention purge runs next (UTC)
+    next_purge: String,
+    /// Recent purges that deleted feedback
+    purges: Vec<retention::Run>,
+}
+
+/// Recent retention purges shown on the settings page
+const SETTINGS_PURGES: i64 = 10;
+
+#[derive(Template)]
+#[template(path = "admin_maintenance.html")]
+struct AdminMaintenanceTemplate {
+    layout: settings::Layout,
+    usage: maintenance::Usage,
+    window: maintenance::Window,
+    runs: Vec<maintenance::Run>,
+}
+
+#[derive(Template)]
+#[template(path = "webhook_test_partial.html")]
+struct WebhookTestPartialTemplate {
+    delivery: webhook::Delivery,
+}
+
+#[derive(Template)]
+#[template(path = "admin_workspaces.html")]
+struct AdminWorkspacesTemplate {
+    layout: settings::Layout,
+    workspaces: Vec<db::Workspace>,
+}
+
 #[derive(Template)]
 #[template(path = "admin_list.html")]
 struct AdminListTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
     prompts: Vec<db::Prompt>,
+    /// Whether this is the archived view rather than the default list
+    archived: bool,
+    archived_count: usize,
+    /// Response bytes stored across the workspace, against its quota
+    storage: String,
 }
 
 #[derive(Template)]
 #[template(path = "admin_new.html")]
-struct AdminNewTemplate;
+struct AdminNewTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    /// What was submitted, when showing the form again with its errors
+    form: NewPromptForm,
+    errors: validation::Errors,
+}
 
 #[derive(Template)]
 #[template(path = "admin_detail.html")]
 struct AdminDetailTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
     prompt: db::Prompt,
     feedback_list: Vec<db::Feedback>,
     feedback_url: String,
+    /// `/f/:slug` URL, if the prompt has a slug
+    short_url: Option<String>,
+    cycles: Vec<CycleRow>,
+    selected_cycle: Option<db::CycleSummary>,
+    tag_counts: Vec<db::TagCount>,
+    selected_tag: Option<String>,
+    /// Most common words across the prompt's feedback
+    top_terms: Vec<keywords::Term>,
+    sentiment: db::SentimentBreakdown,
+    selected_sentiment: Option<String>,
+    /// Only on NPS prompts
+    nps: Option<nps::Summary>,
+    /// Only on reactions prompts
+    reactions: Option<reactions::Counts>,
+    status_counts: db::StatusCounts,
+    selected_status: Option<String>,
+    controls: ListControls,
+    /// Fields offered in the rating filter
+    rating_fields: Vec<db::RatingAverage>,
+    fields: Vec<db::Field>,
+    available: Vec<db::Field>,
+    rules: Vec<RuleRow>,
+    error: Option<String>,
+    translations: Vec<db::PromptTranslation>,
+    /// Languages a translation can be added in, as (code, name)
+    translation_languages: Vec<(&'static str, &'static str)>,
+    /// Signed results widget URL, while embedding is on
+    embed_url: Option<String>,
+    /// Signed Atom feed URL, while the feed is on
+    feed_url: Option<String>,
+    /// Whether replies can be emailed to respondents
+    email_enabled: bool,
+    /// Response bytes stored for the prompt, against its quota
+    storage: String,
+    /// The site-wide retention period, which caps the prompt's own
+    site_retention_days: Option<i64>,
+    /// Responses so far, against the prompt's `max_responses`
+    responses: i64,
+}
+
+impl AdminDetailTemplate {
+    /// The retention period the purge applies to this prompt
+    fn retention_days(&self) -> Option<i64> {
+        retention::effective_days(self.prompt.retention_days, self.site_retention_days)
+    }
+
+    fn is_full(&self) -> bool {
+        self.prompt
+            .max_responses
+            .is_some_and(|max| self.responses >= max)
+    }
+}
+
+struct CycleRow {
+    summary: db::CycleSummary,
+    /// Change in response count relative to the previous cycle, e.g. "+3"
+    trend: String,
 }
 
 #[derive(Template)]
-#[template(path = "feedback_form.html")]
-struct FeedbackFormTemplate {
+#[template(path = "admin_invites.html")]
+struct AdminInvitesTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
     prompt: db::Prompt,
+    invites: Vec<invites::Invite>,
+    /// An invite link up to its token
+    link_base: String,
+}
+
+impl AdminInvitesTemplate {
+    fn outstanding(&self) -> usize {
+        self.invites.iter().filter(|i| !i.is_redeemed()).count()
+    }
+
+    fn redeemed(&self) -> usize {
+        self.invites.len() - self.outstanding()
+    }
+
+    /// The unused links, ready to paste into a mail merge
+    fn outstanding_links(&self) -> String {
+        self.invites
+            .iter()
+            .filter(|i| !i.is_redeemed())
+            .map(|i| format!("{}{}", self.link_base, i.token))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 #[derive(Template)]
-#[template(path = "feedback_success_partial.html")]
-struct FeedbackSuccessPartialTemplate;
+#[template(path = "admin_stats.html")]
+struct AdminStatsTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    summary: db::ResultsSummary,
+    days: Vec<ChartBar>,
+    hours: Vec<ChartBar>,
+    ratings: Vec<db::RatingAverage>,
+    /// Only on NPS prompts
+    nps: Option<NpsStats>,
+    /// Only on reactions prompts
+    reactions: Option<reactions::Counts>,
+}
+
+struct NpsStats {
+    summary: nps::Summary,
+    /// Responses per score, 0 to 10
+    scores: Vec<ChartBar>,
+    weeks: Vec<nps::Week>,
+}
+
+/// One bar of a stats chart, sized against the tallest bar
+struct ChartBar {
+    label: String,
+    count: i64,
+    /// Height as a percentage of the tallest bar
+    percent: i64,
+}
 
 #[derive(Template)]
-#[template(path = "feedback_list_partial.html")]
-struct FeedbackListPartialTemplate {
-    feedback_list: Vec<db::Feedback>,
+#[template(path = "feedback_suspended.html")]
+struct FeedbackSuspendedTemplate {
+    layout: settings::Layout,
 }
 
-// Form data
-#[derive(Deserialize)]
-struct NewPromptForm {
-    title: String,
-    description: String,
+#[derive(Template)]
+#[template(path = "feedback_report.html")]
+struct FeedbackReportTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    reasons: &'static [(&'static str, &'static str)],
+    /// Thank the reporter instead of showing the form
+    submitted: bool,
+    error: Option<String>,
 }
 
-#[derive(Deserialize)]
-struct FeedbackForm {
-    content: String,
+#[derive(Template)]
+#[template(path = "admin_abuse.html")]
+struct AdminAbuseTemplate {
+    layout: settings::Layout,
+    cases: Vec<db::AbuseCase>,
 }
 
-// Handlers
-async fn admin_list(State(state): State<Arc<AppState>>) -> impl IntoResponse {
-    match db::get_all_prompts(&state.pool).await {
-        Ok(prompts) => {
-            let template = AdminListTemplate { prompts };
-            Html(template.render().unwrap())
-        }
-        Err(_) => Html("Error loading prompts".to_string()),
-    }
+#[derive(Template)]
+#[template(path = "feedback_access.html")]
+struct FeedbackAccessTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    error: Option<String>,
+    /// Signed prefill token from the link, kept for the form
+    prefill: Option<String>,
+    /// Invite token from the link, kept for the form
+    invite: Option<String>,
 }
 
-async fn admin_new_form() -> impl IntoResponse {
-    let template = AdminNewTemplate;
-    Html(template.render().unwrap())
+#[derive(Template)]
+#[template(path = "feedback_invite.html")]
+struct FeedbackInviteTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    message: String,
 }
 
-async fn admin_new_submit(
-    State(state): State<Arc<AppState>>,
-    Form(form): Form<NewPromptForm>,
-) -> impl IntoResponse {
-    match db::create_prompt(&state.pool, &form.title, &form.description).await {
-        Ok(prompt) => Redirect::to(&format!("/admin/prompt/{}", prompt.id)),
-        Err(_) => Redirect::to("/admin"),
-    }
+#[derive(Template)]
+#[template(path = "feedback_closed.html")]
+struct FeedbackClosedTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
 }
 
-async fn admin_detail(
-    State(state): State<Arc<AppState>>,
-    Host(host): Host,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    let prompt = match db::get_prompt_by_id(&state.pool, &id).await {
-        Ok(Some(p)) => p,
-        _ => return Html("Prompt not found".to_string()),
-    };
+/// Shown in place of the form once a prompt has its `max_responses`
+#[derive(Template)]
+#[template(path = "feedback_full.html")]
+struct FeedbackFullTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+}
 
-    let feedback_list = db::get_feedback_for_prompt(&state.pool, &id)
-        .await
-        .unwrap_or_default();
+/// Shown in place of the form until a scheduled prompt's publish time
+#[derive(Template)]
+#[template(path = "feedback_scheduled.html")]
+struct FeedbackScheduledTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    opens_at: chrono::DateTime<chrono::Utc>,
+}
 
-    let protocol = if host.contains("localhost") || host.contains("127.0.0.1") {
-        "http"
-    } else {
-        "https"
-    };
-    let feedback_url = format!("{}://{}/feedback/{}", protocol, host, id);
+impl FeedbackScheduledTemplate {
+    fn opens_at_rfc3339(&self) -> String {
+        self.opens_at.to_rfc3339()
+    }
 
-    let template = AdminDetailTemplate {
-        prompt,
-        feedback_list,
-        feedback_url,
-    };
-    Html(template.render().unwrap())
+    fn opens_at_display(&self) -> String {
+        self.opens_at.format("%Y-%m-%d %H:%M UTC").to_string()
+    }
 }
 
-async fn feedback_form(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    match db::get_prompt_by_id(&state.pool, &id).await {
-        Ok(Some(prompt)) => {
-            let template = FeedbackFormTemplate { prompt };
-            Html(template.render().unwrap())
-        }
-        _ => Html("Prompt not found".to_string()),
+/// Shown when a submission is too big to read at all
+#[derive(Template)]
+#[template(path = "feedback_too_long.html")]
+struct FeedbackTooLongTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    message: String,
+}
+
+#[derive(Template)]
+#[template(path = "feedback_form.html")]
+struct FeedbackFormTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    /// Question bank fields shown below the main answer
+    fields: Vec<db::Field>,
+    honeypot_field: &'static str,
+    /// Signed render time (`spam::stamp`), echoed back so too-quick
+    /// submissions can be spotted
+    rendered_at: String,
+    captcha: Option<captcha::Config>,
+    /// One-time token that lets a repeated POST be recognised
+    submission_token: String,
+    /// Whether a receipt would actually be mailed
+    receipts_enabled: bool,
+    /// Signed prefill token from the link, posted back with the form
+    prefill: Option<String>,
+    /// The prompt's access code as the respondent entered it, posted back
+    /// with the form
+    access_code: Option<String>,
+    /// Single-use invite token from the link, posted back with the form
+    invite: Option<String>,
+    /// Answers the prefill token locks, keyed by field ID
+    locked: prefill::Values,
+    t: &'static i18n::Catalog,
+    /// The language switcher; empty unless the prompt is translated
+    languages: Vec<LanguageLink>,
+    /// What was submitted, when showing the form again with its errors
+    values: FeedbackValues,
+    errors: validation::Errors,
+}
+
+impl FeedbackFormTemplate {
+    fn content_max_length(&self) -> i64 {
+        feedback_max_length(&self.prompt)
     }
 }
 
-async fn feedback_submit(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-    Form(form): Form<FeedbackForm>,
-) -> impl IntoResponse {
-    // Verify prompt exists
-    match db::get_prompt_by_id(&state.pool, &id).await {
-        Ok(Some(_)) => {}
-        _ => return Html("Prompt not found".to_string()),
+/// The prompt's own limit on feedback, or the site-wide one
+fn feedback_max_length(prompt: &db::Prompt) -> i64 {
+    let max = validation::MAX_FEEDBACK_LEN as i64;
+    prompt.content_max_length.map_or(max, |n| n.min(max))
+}
+
+/// A rejected submission's answers, put back in the form
+#[derive(Default)]
+struct FeedbackValues {
+    content: String,
+    email: String,
+    /// The chosen 0-10 score, on NPS prompts
+    score: String,
+    /// Question bank answers keyed by field ID
+    answers: HashMap<String, String>,
+}
+
+impl FeedbackValues {
+    fn answer(&self, field_id: &str) -> &str {
+        self.answers.get(field_id).map_or("", String::as_str)
     }
 
-    match db::create_feedback(&state.pool, &id, &form.content).await {
-        Ok(_) => {
-            let template = FeedbackSuccessPartialTemplate;
-            Html(template.render().unwrap())
-        }
-        Err(_) => Html("Error submitting feedback".to_string()),
+    fn has_score(&self, score: &i64) -> bool {
+        self.score.trim().parse() == Ok(*score)
     }
 }
 
-async fn api_delete_prompt(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    match db::delete_prompt(&state.pool, &id).await {
-        Ok(_) => StatusCode::OK,
-        Err(_) => StatusCode::INTERNAL_SERVER_ERROR,
-    }
+struct LanguageLink {
+    code: &'static str,
+    /// In its own language
+    name: &'static str,
+    /// The language the page is shown in
+    current: bool,
 }
 
-async fn api_get_feedback(
-    State(state): State<Arc<AppState>>,
-    Path(id): Path<String>,
-) -> impl IntoResponse {
-    let feedback_list = db::get_feedback_for_prompt(&state.pool, &id)
-        .await
-        .unwrap_or_default();
+#[derive(Template)]
+#[template(path = "feedback_edit.html")]
+struct FeedbackEditTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    feedback: db::Feedback,
+    token: String,
+    /// Outcome of the last save, shown above the form
+    notice: Option<String>,
+}
 
-    let template = FeedbackListPartialTemplate { feedback_list };
-    Html(template.render().unwrap())
+#[derive(Template)]
+#[template(path = "feedback_board.html")]
+struct FeedbackBoardTemplate {
+    layout: settings::Layout,
+    prompt: db::Prompt,
+    entries: Vec<db::BoardEntry>,
 }
 
-async fn index() -> impl IntoResponse {
-    Redirect::to("/admin")
+#[derive(Template)]
+#[template(path = "vote_button_partial.html")]
+struct VoteButtonPartialTemplate {
+    prompt: db::Prompt,
+    entry: VoteCount,
 }
 
-/// Create the application router with the given state
-pub fn create_router(state: Arc<AppState>) -> Router {
-    Router::new()
-        .route("/", get(index))
-        .route("/admin", get(admin_list))
-        .route("/admin/new", get(admin_new_form).post(admin_new_submit))
-        .route("/admin/prompt/:id", get(admin_detail))
-        .route("/feedback/:id", get(feedback_form).post(feedback_submit))
-        .route("/api/prompts/:id", delete(api_delete_prompt))
-        .route("/api/feedback/:id", get(api_get_feedback))
-        .with_state(state)
+/// What the vote button shows; board entries have the same fields
+struct VoteCount {
+    id: String,
+    votes: i64,
+    voted: bool,
 }
 
-#[tokio::main]
-async fn main() {
-    // Initialize database
-    let database_url =
-        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:feedback.db?mode=rwc".to_string());
+#[derive(Template)]
+#[template(path = "feedback_success_partial.html")]
+struct FeedbackSuccessPartialTemplate {
+    submission_token: String,
+    /// The prompt's own thank-you message, in place of the standard one
+    message: Option<String>,
+    t: &'static i18n::Catalog,
+}
 
-    let pool = db::init_db(&database_url)
-        .await
-        .expect("Failed to initialize database");
+#[derive(Template)]
+#[template(path = "feedback_list_partial.html")]
+struct FeedbackListPartialTemplate {
+    workspace: db::Workspace,
+    feedback_list: Vec<db::Feedback>,
+    email_enabled: bool,
+}
 
-    let state = Arc::new(AppState { pool });
+#[derive(Template)]
+#[template(path = "feedback_replies_partial.html")]
+struct FeedbackRepliesPartialTemplate {
+    workspace: db::Workspace,
+    fb: db::Feedback,
+    email_enabled: bool,
+}
 
-    // Build router
-    let app = create_router(state);
+#[derive(Template)]
+#[template(path = "feedback_tags_partial.html")]
+struct FeedbackTagsPartialTemplate {
+    workspace: db::Workspace,
+    fb: db::Feedback,
+}
 
-    let addr = "0.0.0.0:3000";
-    println!("Server running at http://localhost:3000");
-    println!("Admin interface: http://localhost:3000/admin");
+/// Shown on public forms while the database refuses writes
+#[derive(Template)]
+#[template(path = "feedback_unavailable.html")]
+struct FeedbackUnavailableTemplate {
+    layout: settings::Layout,
+}
 
-    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
-    axum::serve(listener, app).await.unwrap();
+#[derive(Template)]
+#[template(path = "feedback_status_partial.html")]
+struct FeedbackStatusPartialTemplate {
+    workspace: db::Workspace,
+    fb: db::Feedback,
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use axum::{
-        body::Body,
-        http::{Request, StatusCode},
-    };
-    use http_body_util::BodyExt;
-    use tower::ServiceExt;
+#[derive(Template)]
+#[template(path = "admin_respondents.html")]
+struct AdminRespondentsTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    respondents: Vec<db::RespondentSummary>,
+}
 
-    async fn setup_test_app() -> (Router, Arc<AppState>) {
-        let pool = db::init_db("sqlite::memory:").await.unwrap();
-        let state = Arc::new(AppState { pool });
-        let app = create_router(state.clone());
-        (app, state)
+#[derive(Template)]
+#[template(path = "admin_privacy.html")]
+struct AdminPrivacyTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    /// The address looked up, normalized
+    email: String,
+    lookup: Option<PrivacyLookup>,
+    notice: Option<String>,
+    error: Option<String>,
+    history: Vec<privacy::Request>,
+}
+
+/// What the workspace holds for the address looked up
+struct PrivacyLookup {
+    entries: usize,
+    previously_deleted: bool,
+}
+
+#[derive(Template)]
+#[template(path = "admin_respondent_detail.html")]
+struct AdminRespondentDetailTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    email: String,
+    entries: Vec<db::RespondentEntry>,
+}
+
+#[derive(Template)]
+#[template(path = "admin_spam.html")]
+struct AdminSpamTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    entries: Vec<db::SpamEntry>,
+}
+
+#[derive(Template)]
+#[template(path = "admin_fields.html")]
+struct AdminFieldsTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    fields: Vec<db::FieldUsage>,
+    kinds: &'static [db::FieldKind],
+}
+
+#[derive(Template)]
+#[template(path = "admin_field_edit.html")]
+struct AdminFieldEditTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    field: db::Field,
+    kinds: &'static [db::FieldKind],
+}
+
+#[derive(Template)]
+#[template(path = "admin_exports.html")]
+struct AdminExportsTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    templates: Vec<ExportTemplateSummary>,
+    /// Column inputs for a new template, starting from the default columns
+    rows: Vec<db::ExportColumn>,
+    sources: Vec<ExportSourceOption>,
+    date_format: &'static str,
+}
+
+struct ExportTemplateSummary {
+    template: db::ExportTemplate,
+    /// The spreadsheet's header row, comma separated
+    headers: String,
+}
+
+/// A column source as offered in a template's column pickers
+struct ExportSourceOption {
+    key: String,
+    label: String,
+}
+
+#[derive(Template)]
+#[template(path = "admin_export_template_edit.html")]
+struct AdminExportTemplateEditTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    template: db::ExportTemplate,
+    rows: Vec<db::ExportColumn>,
+    sources: Vec<ExportSourceOption>,
+}
+
+#[derive(Template)]
+#[template(path = "prompt_fields_partial.html")]
+struct PromptFieldsPartialTemplate {
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    fields: Vec<db::Field>,
+    /// Bank fields not yet on the prompt
+    available: Vec<db::Field>,
+}
+
+#[derive(Template)]
+#[template(path = "embed_partial.html")]
+struct EmbedPartialTemplate {
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    embed_url: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "feed_partial.html")]
+struct FeedPartialTemplate {
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    feed_url: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "prefill_link_partial.html")]
+struct PrefillLinkPartialTemplate {
+    /// The new link, or what was wrong with the answers to lock
+    result: Result<String, String>,
+}
+
+#[derive(Template)]
+#[template(path = "embed_results.html")]
+struct EmbedResultsTemplate {
+    prompt: db::Prompt,
+    summary: db::ResultsSummary,
+    sentiment: db::SentimentBreakdown,
+    refresh_secs: u64,
+}
+
+#[derive(Template)]
+#[template(path = "notification_rules_partial.html")]
+struct NotificationRulesPartialTemplate {
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    rules: Vec<RuleRow>,
+    /// The prompt's fields, which answer conditions can check
+    fields: Vec<db::Field>,
+    error: Option<String>,
+}
+
+struct RuleRow {
+    rule: db::NotificationRule,
+    description: String,
+}
+
+#[derive(Template)]
+#[template(path = "prompt_translations_partial.html")]
+struct PromptTranslationsPartialTemplate {
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    translations: Vec<db::PromptTranslation>,
+    translation_languages: Vec<(&'static str, &'static str)>,
+    /// The prompt's fields, whose labels can be translated
+    fields: Vec<db::Field>,
+    error: Option<String>,
+}
+
+#[derive(Template)]
+#[template(path = "admin_search.html")]
+struct AdminSearchTemplate {
+    layout: settings::Layout,
+    workspace: db::Workspace,
+    query: String,
+    groups: Vec<SearchResultGroup>,
+}
+
+struct SearchResultGroup {
+    prompt_id: String,
+    prompt_title: String,
+    results: Vec<SearchResult>,
+}
+
+struct SearchResult {
+    feedback_id: String,
+    created_at: String,
+    snippet: Vec<SnippetPart>,
+}
+
+struct SnippetPart {
+    text: String,
+    highlighted: bool,
+}
+
+// Form data
+#[derive(Deserialize)]
+struct NewWorkspaceForm {
+    name: String,
+    #[serde(default)]
+    slug: String,
+}
+
+#[derive(Default, Deserialize)]
+struct NewPromptForm {
+    title: String,
+    description: String,
+    #[serde(default)]
+    webhook_url: String,
+    merge_responses: Option<String>,
+    public_board: Option<String>,
+    send_receipts: Option<String>,
+    #[serde(default)]
+    recurrence: String,
+    #[serde(default)]
+    content_min_length: String,
+    #[serde(default)]
+    content_max_length: String,
+    #[serde(default)]
+    content_pattern: String,
+    /// Empty to follow each respondent's browser
+    #[serde(default)]
+    language: String,
+    /// Empty for no short URL
+    #[serde(default)]
+    slug: String,
+    /// Empty to let anyone answer
+    #[serde(default)]
+    access_code: String,
+    /// `datetime-local` value in UTC; empty to open right away
+    #[serde(default)]
+    publish_at: String,
+    /// A `PromptKind`; empty for written feedback
+    #[serde(default)]
+    kind: String,
+    /// Days to keep feedback; empty to follow the site-wide period
+    #[serde(default)]
+    retention_days: String,
+    /// Empty for no response cap
+    #[serde(default)]
+    max_responses: String,
+    #[serde(flatten)]
+    thank_you: ThankYouForm,
+}
+
+impl NewPromptForm {
+    fn language_is(&self, code: &str) -> bool {
+        self.language == code
+    }
+
+    fn content_rules(&self) -> Result<rules::FieldRules, String> {
+        form_rules(
+            "Answer",
+            &self.content_min_length,
+            &self.content_max_length,
+            &self.content_pattern,
+        )
+    }
+}
+
+/// Answer rules from admin form inputs; blank inputs mean no limit.
+/// `subject` starts the error message, e.g. "Answer max length ...".
+fn form_rules(
+    subject: &str,
+    min_length: &str,
+    max_length: &str,
+    pattern: &str,
+) -> Result<rules::FieldRules, String> {
+    let length = |value: &str, label: &str| match value.trim() {
+        "" => Ok(None),
+        value => value
+            .parse()
+            .map(Some)
+            .map_err(|_| format!("{} must be a whole number", label)),
+    };
+    let rules = rules::FieldRules {
+        min_length: length(min_length, "Minimum length")?,
+        max_length: length(max_length, "Maximum length")?,
+        pattern: Some(pattern.trim().to_string()).filter(|p| !p.is_empty()),
+    };
+    rules.validate().map_err(|(field, message)| {
+        format!("{} {} {}", subject, field.replace('_', " "), message)
+    })?;
+    Ok(rules)
+}
+
+#[derive(Deserialize)]
+struct FieldForm {
+    label: String,
+    #[serde(default)]
+    help_text: String,
+    #[serde(default)]
+    kind: String,
+    required: Option<String>,
+    #[serde(default)]
+    min_length: String,
+    #[serde(default)]
+    max_length: String,
+    #[serde(default)]
+    pattern: String,
+}
+
+impl FieldForm {
+    fn spec(&self) -> Result<db::FieldSpec, String> {
+        let label = self.label.trim();
+        if label.is_empty() {
+            return Err("Label must not be empty".to_string());
+        }
+        let kind = db::FieldKind::parse(&self.kind).ok_or("Invalid field type")?;
+        Ok(db::FieldSpec {
+            label: label.to_string(),
+            help_text: Some(self.help_text.trim().to_string()).filter(|h| !h.is_empty()),
+            kind,
+            required: self.required.is_some(),
+            rules: form_rules("Field", &self.min_length, &self.max_length, &self.pattern)?,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct PromptFieldForm {
+    field_id: String,
+}
+
+#[derive(Deserialize)]
+struct NotificationRuleForm {
+    condition: String,
+    #[serde(default)]
+    field_id: String,
+    #[serde(default)]
+    operand: String,
+    delivery: String,
+    target_url: String,
+}
+
+/// Submission tokens are 64 hex characters; anything much longer isn't ours
+const MAX_SUBMISSION_TOKEN_LEN: usize = 128;
+
+#[derive(Deserialize)]
+struct FeedbackForm {
+    content: String,
+    #[serde(default)]
+    email: String,
+    /// The hidden `spam::HONEYPOT_FIELD` input
+    #[serde(default)]
+    website: String,
+    #[serde(default)]
+    rendered_at: String,
+    #[serde(default)]
+    submission_token: String,
+    #[serde(default, rename = "h-captcha-response")]
+    hcaptcha_response: String,
+    #[serde(default, rename = "cf-turnstile-response")]
+    turnstile_response: String,
+    /// Token from a signed prefill link
+    #[serde(default)]
+    prefill: String,
+    /// Entered before the form was shown, on prompts that require one
+    #[serde(default)]
+    access_code: String,
+    /// Single-use invite token, on prompts that only open through invites
+    #[serde(default)]
+    invite: String,
+    /// Language the form was shown in, for the reply
+    #[serde(default)]
+    lang: String,
+    /// The 0-10 answer on NPS prompts
+    #[serde(default)]
+    score: String,
+    /// The tapped `reactions::Reaction` key, on reactions prompts
+    #[serde(default)]
+    reaction: String,
+    /// Question bank answers, as `field_<id>` inputs
+    #[serde(flatten)]
+    extra: HashMap<String, String>,
+}
+
+impl FeedbackForm {
+    fn captcha_token(&self, provider: captcha::Provider) -> &str {
+        match provider {
+            captcha::Provider::HCaptcha => &self.hcaptcha_response,
+            captcha::Provider::Turnstile => &self.turnstile_response,
+        }
+    }
+
+    /// Question bank answers keyed by field ID
+    fn answers(&self) -> HashMap<String, String> {
+        self.extra
+            .iter()
+            .filter_map(|(key, value)| {
+                Some((key.strip_prefix("field_")?.to_string(), value.clone()))
+            })
+            .collect()
+    }
+}
+
+/// Check a submission's answers against the prompt's fields, returning them in
+/// form order with blank optional answers dropped. Errors name the field.
+fn collect_answers<'a>(
+    fields: &'a [db::Field],
+    values: &HashMap<String, String>,
+) -> Result<Vec<(String, String)>, (&'a db::Field, String)> {
+    let mut answers = Vec::new();
+    for field in fields {
+        let value = values.get(&field.id).map(|v| v.trim()).unwrap_or_default();
+        if value.is_empty() {
+            if field.required {
+                return Err((field, "is required".to_string()));
+            }
+            continue;
+        }
+        if let Err(message) = field.rules().check(value) {
+            return Err((field, message));
+        }
+        answers.push((field.id.clone(), value.to_string()));
+    }
+    Ok(answers)
+}
+
+// Handlers
+async fn admin_workspaces(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match db::get_workspaces(&state.pool).await {
+        Ok(workspaces) => {
+            let template = AdminWorkspacesTemplate {
+                layout: state.layout(),
+                workspaces,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading workspaces".to_string()),
+    }
+}
+
+async fn admin_create_workspace(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<NewWorkspaceForm>,
+) -> impl IntoResponse {
+    let name = form.name.trim();
+    // The slug defaults to one derived from the name
+    let slug_source = if form.slug.trim().is_empty() {
+        name
+    } else {
+        form.slug.trim()
+    };
+    let Some(slug) = db::normalize_workspace_slug(slug_source).filter(|_| !name.is_empty()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Workspaces need a name and a slug of letters, numbers and '-'".to_string()),
+        )
+            .into_response();
+    };
+
+    match db::create_workspace(&state.pool, &slug, name).await {
+        Ok(Some(workspace)) => flash::redirect(
+            &format!("/admin/{}", workspace.slug),
+            flash::Flash::success(format!("Workspace \"{}\" created", workspace.name)),
+        ),
+        Ok(None) => (
+            StatusCode::CONFLICT,
+            Html("A workspace with that slug already exists".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error creating workspace".to_string()).into_response(),
+    }
+}
+
+async fn admin_list(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    render_prompt_list(&state, workspace, false).await
+}
+
+/// Prompts that have been put away, kept with all their feedback
+async fn admin_archived(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    render_prompt_list(&state, workspace, true).await
+}
+
+async fn render_prompt_list(
+    state: &AppState,
+    workspace: db::Workspace,
+    archived: bool,
+) -> Html<String> {
+    let used = db::get_workspace_storage_bytes(&state.pool, &workspace.id)
+        .await
+        .unwrap_or_default();
+    let storage = storage::describe(used, state.storage_quotas.workspace_bytes);
+    match db::get_all_prompts(&state.pool, &workspace.id).await {
+        Ok(prompts) => {
+            let (archived_prompts, active): (Vec<_>, Vec<_>) =
+                prompts.into_iter().partition(|p| p.archived_at.is_some());
+            let archived_count = archived_prompts.len();
+            let template = AdminListTemplate {
+                layout: state.layout(),
+                workspace,
+                prompts: if archived { archived_prompts } else { active },
+                archived,
+                archived_count,
+                storage,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading prompts".to_string()),
+    }
+}
+
+async fn admin_new_form(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    let template = AdminNewTemplate {
+        layout: state.layout(),
+        workspace,
+        form: NewPromptForm::default(),
+        errors: validation::Errors::default(),
+    };
+    Html(template.render().unwrap())
+}
+
+async fn admin_new_submit(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Form(form): Form<NewPromptForm>,
+) -> Response {
+    let mut errors = validation::Errors::default();
+    let title = validation::title(&form.title).unwrap_or_else(|problem| {
+        errors.add("title", format!("Title {}", problem.message()));
+        ""
+    });
+    let description = validation::description(&form.description, true).unwrap_or_else(|problem| {
+        errors.add("description", format!("Description {}", problem.message()));
+        ""
+    });
+    let webhook_url = form.webhook_url.trim();
+    if !webhook_url.is_empty() && !state.webhooks.accepts(webhook_url) {
+        errors.add("webhook_url", "Invalid webhook URL");
+    }
+    let recurrence = match form.recurrence.as_str() {
+        "" => None,
+        value => {
+            let parsed = db::Recurrence::parse(value);
+            if parsed.is_none() {
+                errors.add("recurrence", "Invalid recurrence");
+            }
+            parsed
+        }
+    };
+    let content_rules = form.content_rules().unwrap_or_else(|message| {
+        errors.add("content_rules", message);
+        rules::FieldRules::default()
+    });
+    let language = match form.language.as_str() {
+        "" => None,
+        lang if i18n::is_supported(lang) => Some(lang.to_string()),
+        _ => {
+            errors.add("language", "Unsupported language");
+            None
+        }
+    };
+    let slug = match form.slug.trim() {
+        "" => None,
+        slug => {
+            let normalized = db::normalize_prompt_slug(slug);
+            if normalized.is_none() {
+                errors.add("slug", INVALID_PROMPT_SLUG);
+            }
+            normalized
+        }
+    };
+    if let Some(slug) = &slug {
+        if let Ok(Some(_)) = db::get_prompt_by_slug(&state.pool, slug).await {
+            errors.add("slug", PROMPT_SLUG_TAKEN);
+        }
+    }
+    let access_code = match form.access_code.trim() {
+        "" => None,
+        code => {
+            let normalized = access::normalize(code);
+            if normalized.is_none() {
+                errors.add("access_code", INVALID_ACCESS_CODE);
+            }
+            normalized
+        }
+    };
+    let publish_at = match form.publish_at.trim() {
+        "" => None,
+        value => {
+            let parsed = db::parse_publish_at(value);
+            if parsed.is_none() {
+                errors.add("publish_at", INVALID_PUBLISH_AT);
+            }
+            parsed
+        }
+    };
+    let kind = match form.kind.as_str() {
+        "" => db::PromptKind::default(),
+        value => db::PromptKind::parse(value).unwrap_or_else(|| {
+            errors.add("kind", "Invalid prompt type");
+            db::PromptKind::default()
+        }),
+    };
+    let retention_days = retention::parse_days(&form.retention_days).unwrap_or_else(|e| {
+        errors.add("retention_days", e);
+        None
+    });
+    let max_responses = match form.max_responses.trim() {
+        "" => None,
+        value => {
+            let parsed = value.parse().ok().filter(|max: &i64| *max >= 1);
+            if parsed.is_none() {
+                errors.add("max_responses", INVALID_MAX_RESPONSES);
+            }
+            parsed
+        }
+    };
+    let (thank_you_message, redirect_url) = form.thank_you.parse().unwrap_or_else(|| {
+        errors.add("redirect_url", INVALID_REDIRECT_URL);
+        (None, None)
+    });
+    if !errors.is_empty() {
+        let template = AdminNewTemplate {
+            layout: state.layout(),
+            workspace,
+            form,
+            errors,
+        };
+        return (
+            StatusCode::UNPROCESSABLE_ENTITY,
+            Html(template.render().unwrap()),
+        )
+            .into_response();
+    }
+
+    let options = db::PromptOptions {
+        webhook_url: Some(webhook_url.to_string()).filter(|u| !u.is_empty()),
+        merge_responses: form.merge_responses.is_some(),
+        recurrence,
+        content_rules,
+        public_board: form.public_board.is_some(),
+        send_receipts: form.send_receipts.is_some(),
+        language,
+        slug,
+        thank_you_message,
+        redirect_url,
+        access_code,
+        publish_at,
+        kind,
+        retention_days,
+        max_responses,
+    };
+    match db::create_prompt_with_options(&state.pool, &workspace.id, title, description, &options)
+        .await
+    {
+        Ok(prompt) => flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, prompt.id),
+            flash::Flash::success("Prompt created"),
+        ),
+        Err(_) => flash::redirect(
+            &format!("/admin/{}", workspace.slug),
+            flash::Flash::error("Failed to create the prompt; please try again"),
+        ),
+    }
+}
+
+async fn admin_respondents(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    match db::get_respondents(&state.pool, &workspace.id).await {
+        Ok(respondents) => {
+            let template = AdminRespondentsTemplate {
+                layout: state.layout(),
+                workspace,
+                respondents,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading respondents".to_string()),
+    }
+}
+
+async fn admin_spam(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    match db::get_spam(&state.pool, &workspace.id).await {
+        Ok(entries) => {
+            let template = AdminSpamTemplate {
+                layout: state.layout(),
+                workspace,
+                entries,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading spam".to_string()),
+    }
+}
+
+async fn admin_release_spam(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match db::release_spam(&state.pool, &workspace.id, &id).await {
+        // htmx swaps the entry out for nothing
+        Ok(true) => Html(String::new()).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("Spam entry not found".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error releasing entry".to_string()).into_response(),
+    }
+}
+
+async fn admin_delete_spam(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match db::delete_spam(&state.pool, &workspace.id, &id).await {
+        Ok(true) => Html(String::new()).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("Spam entry not found".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error deleting entry".to_string()).into_response(),
+    }
+}
+
+async fn admin_fields(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    match db::get_field_usage(&state.pool, &workspace.id).await {
+        Ok(fields) => {
+            let template = AdminFieldsTemplate {
+                layout: state.layout(),
+                workspace,
+                fields,
+                kinds: &db::FieldKind::ALL,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading questions".to_string()),
+    }
+}
+
+async fn admin_create_field(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Form(form): Form<FieldForm>,
+) -> impl IntoResponse {
+    let spec = match form.spec() {
+        Ok(spec) => spec,
+        Err(message) => return (StatusCode::BAD_REQUEST, Html(message)).into_response(),
+    };
+    match db::create_field(&state.pool, &workspace.id, &spec).await {
+        Ok(_) => flash::redirect(
+            &format!("/admin/{}/fields", workspace.slug),
+            flash::Flash::success("Question added"),
+        ),
+        Err(_) => flash::redirect(
+            &format!("/admin/{}/fields", workspace.slug),
+            flash::Flash::error("Failed to save the question"),
+        ),
+    }
+}
+
+async fn admin_edit_field_form(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match db::get_field(&state.pool, &workspace.id, &id).await {
+        Ok(Some(field)) => {
+            let template = AdminFieldEditTemplate {
+                layout: state.layout(),
+                workspace,
+                field,
+                kinds: &db::FieldKind::ALL,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        _ => (StatusCode::NOT_FOUND, Html("Field not found".to_string())).into_response(),
+    }
+}
+
+/// Edits apply to every prompt using the field
+async fn admin_update_field(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<FieldForm>,
+) -> impl IntoResponse {
+    let spec = match form.spec() {
+        Ok(spec) => spec,
+        Err(message) => return (StatusCode::BAD_REQUEST, Html(message)).into_response(),
+    };
+    match db::update_field(&state.pool, &workspace.id, &id, &spec).await {
+        Ok(true) => flash::redirect(
+            &format!("/admin/{}/fields", workspace.slug),
+            flash::Flash::success("Question saved"),
+        ),
+        Ok(false) => (StatusCode::NOT_FOUND, Html("Field not found".to_string())).into_response(),
+        Err(_) => flash::redirect(
+            &format!("/admin/{}/fields", workspace.slug),
+            flash::Flash::error("Failed to save the question"),
+        ),
+    }
+}
+
+async fn admin_delete_field(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match db::get_field(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Field not found".to_string())).into_response(),
+    }
+    match db::delete_field(&state.pool, &id).await {
+        Ok(true) => Html(String::new()).into_response(),
+        Ok(false) => (
+            StatusCode::CONFLICT,
+            Html("Remove this field from its prompts before deleting it".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error deleting field".to_string()).into_response(),
+    }
+}
+
+/// Empty column pickers offered after a template's columns
+const SPARE_EXPORT_COLUMNS: usize = 5;
+
+async fn workspace_fields(
+    state: &AppState,
+    workspace: &db::Workspace,
+) -> Result<Vec<db::Field>, sqlx::Error> {
+    let usage = db::get_field_usage(&state.pool, &workspace.id).await?;
+    Ok(usage.into_iter().map(|u| u.field).collect())
+}
+
+fn export_source_options(fields: &[db::Field]) -> Vec<ExportSourceOption> {
+    export::Source::BUILTIN
+        .iter()
+        .map(|source| ExportSourceOption {
+            key: source.key(),
+            label: source.label(fields),
+        })
+        .chain(fields.iter().map(|field| ExportSourceOption {
+            key: export::Source::Answer(field.id.clone()).key(),
+            label: format!("Answer: {}", field.label),
+        }))
+        .collect()
+}
+
+/// A template's columns followed by blank ones to add more
+fn export_column_rows(columns: &[db::ExportColumn]) -> Vec<db::ExportColumn> {
+    let blank = db::ExportColumn {
+        source: String::new(),
+        header: String::new(),
+    };
+    let mut rows = columns.to_vec();
+    rows.extend(std::iter::repeat_n(blank, SPARE_EXPORT_COLUMNS));
+    rows
+}
+
+/// Name, columns and date format from an export template form, whose columns
+/// arrive as numbered `source_N` and `header_N` inputs; blank sources are skipped
+fn export_template_form(
+    form: &HashMap<String, String>,
+    fields: &[db::Field],
+) -> Result<(String, Vec<db::ExportColumn>, String), String> {
+    let name = form.get("name").map_or("", |n| n.trim());
+    if name.is_empty() {
+        return Err("Name must not be empty".to_string());
+    }
+    let date_format = form.get("date_format").map_or("", |f| f.trim());
+    let date_format = if date_format.is_empty() {
+        export::DEFAULT_DATE_FORMAT
+    } else if export::is_valid_date_format(date_format) {
+        date_format
+    } else {
+        return Err("Invalid date format".to_string());
+    };
+
+    let mut numbered: Vec<(usize, &String)> = form
+        .iter()
+        .filter_map(|(key, source)| {
+            let n = key.strip_prefix("source_")?.parse().ok()?;
+            Some((n, source))
+        })
+        .filter(|(_, source)| !source.is_empty())
+        .collect();
+    numbered.sort();
+    let columns: Vec<db::ExportColumn> = numbered
+        .into_iter()
+        .map(|(n, source)| db::ExportColumn {
+            source: source.clone(),
+            header: form
+                .get(&format!("header_{}", n))
+                .map_or("", |h| h.trim())
+                .to_string(),
+        })
+        .collect();
+    export::check_columns(&columns, fields)?;
+    Ok((name.to_string(), columns, date_format.to_string()))
+}
+
+async fn admin_export_templates(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+) -> impl IntoResponse {
+    let (Ok(templates), Ok(fields)) = (
+        db::get_export_templates(&state.pool, &workspace.id).await,
+        workspace_fields(&state, &workspace).await,
+    ) else {
+        return Html("Error loading export templates".to_string());
+    };
+    let templates = templates
+        .into_iter()
+        .map(|template| {
+            let headers = template
+                .columns
+                .iter()
+                .map(|column| match column.header.as_str() {
+                    "" => export::Source::parse(&column.source)
+                        .map_or_else(String::new, |s| s.label(&fields)),
+                    header => header.to_string(),
+                })
+                .collect::<Vec<_>>()
+                .join(", ");
+            ExportTemplateSummary { template, headers }
+        })
+        .collect();
+    let template = AdminExportsTemplate {
+        layout: state.layout(),
+        workspace,
+        templates,
+        rows: export_column_rows(&export::default_columns(&fields)),
+        sources: export_source_options(&fields),
+        date_format: export::DEFAULT_DATE_FORMAT,
+    };
+    Html(template.render().unwrap())
+}
+
+async fn admin_create_export_template(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Form(form): Form<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let Ok(fields) = workspace_fields(&state, &workspace).await else {
+        return Html("Error saving export template".to_string()).into_response();
+    };
+    let (name, columns, date_format) = match export_template_form(&form, &fields) {
+        Ok(parts) => parts,
+        Err(message) => return (StatusCode::BAD_REQUEST, Html(message)).into_response(),
+    };
+    match db::create_export_template(&state.pool, &workspace.id, &name, &columns, &date_format)
+        .await
+    {
+        Ok(_) => flash::redirect(
+            &format!("/admin/{}/exports", workspace.slug),
+            flash::Flash::success("Export template saved"),
+        ),
+        Err(_) => flash::redirect(
+            &format!("/admin/{}/exports", workspace.slug),
+            flash::Flash::error("Failed to save the export template"),
+        ),
+    }
+}
+
+async fn admin_edit_export_template_form(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let template = match db::get_export_template(&state.pool, &workspace.id, &id).await {
+        Ok(Some(template)) => template,
+        _ => {
+            return (
+                StatusCode::NOT_FOUND,
+                Html("Export template not found".to_string()),
+            )
+                .into_response()
+        }
+    };
+    let fields = workspace_fields(&state, &workspace)
+        .await
+        .unwrap_or_default();
+    let page = AdminExportTemplateEditTemplate {
+        layout: state.layout(),
+        rows: export_column_rows(&template.columns),
+        sources: export_source_options(&fields),
+        workspace,
+        template,
+    };
+    Html(page.render().unwrap()).into_response()
+}
+
+async fn admin_update_export_template(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<HashMap<String, String>>,
+) -> impl IntoResponse {
+    let Ok(fields) = workspace_fields(&state, &workspace).await else {
+        return Html("Error saving export template".to_string()).into_response();
+    };
+    let (name, columns, date_format) = match export_template_form(&form, &fields) {
+        Ok(parts) => parts,
+        Err(message) => return (StatusCode::BAD_REQUEST, Html(message)).into_response(),
+    };
+    let template = db::ExportTemplate {
+        id,
+        workspace_id: workspace.id.clone(),
+        name,
+        columns,
+        date_format,
+        created_at: String::new(),
+        updated_at: String::new(),
+    };
+    match db::update_export_template(&state.pool, &template).await {
+        Ok(true) => flash::redirect(
+            &format!("/admin/{}/exports", workspace.slug),
+            flash::Flash::success("Export template saved"),
+        ),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("Export template not found".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error saving export template".to_string()).into_response(),
+    }
+}
+
+async fn admin_delete_export_template(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match db::delete_export_template(&state.pool, &workspace.id, &id).await {
+        Ok(true) => Html(String::new()).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("Export template not found".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error deleting export template".to_string()).into_response(),
+    }
+}
+
+async fn admin_respondent_detail(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, email)): Path<(String, String)>,
+) -> impl IntoResponse {
+    match db::get_feedback_by_respondent(&state.pool, &workspace.id, &email).await {
+        Ok(entries) if !entries.is_empty() => {
+            let template = AdminRespondentDetailTemplate {
+                layout: state.layout(),
+                workspace,
+                email,
+                entries,
+            };
+            Html(template.render().unwrap())
+        }
+        Ok(_) => Html("Respondent not found".to_string()),
+        Err(_) => Html("Error loading respondent".to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct PrivacyForm {
+    #[serde(default)]
+    email: String,
+    /// The address typed again, to confirm a deletion
+    #[serde(default)]
+    confirm: String,
+}
+
+impl PrivacyForm {
+    /// As stored with feedback
+    fn email(&self) -> String {
+        self.email.trim().to_lowercase()
+    }
+}
+
+/// The privacy page, looking up `email` if one is given
+async fn privacy_page(
+    state: &AppState,
+    workspace: db::Workspace,
+    email: String,
+    notice: Option<String>,
+    error: Option<String>,
+) -> Result<Html<String>, sqlx::Error> {
+    let lookup = if email.is_empty() {
+        None
+    } else {
+        let entries = db::get_respondent_data(&state.pool, &workspace.id, &email).await?;
+        let previously_deleted =
+            privacy::was_requested(&state.pool, &workspace.id, &email, privacy::Action::Delete)
+                .await?;
+        Some(PrivacyLookup {
+            entries: entries.len(),
+            previously_deleted,
+        })
+    };
+    let history = privacy::history(&state.pool, &workspace.id).await?;
+    let template = AdminPrivacyTemplate {
+        layout: state.layout(),
+        workspace,
+        email,
+        lookup,
+        notice,
+        error,
+        history,
+    };
+    Ok(Html(template.render().unwrap()))
+}
+
+async fn admin_privacy(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Query(query): Query<PrivacyForm>,
+) -> Response {
+    match privacy_page(&state, workspace, query.email(), None, None).await {
+        Ok(page) => page.into_response(),
+        Err(_) => Html("Error loading privacy requests".to_string()).into_response(),
+    }
+}
+
+/// Download everything the workspace holds from a respondent, as JSON
+async fn admin_privacy_export(
+    RequireOwner(admin): RequireOwner,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Form(form): Form<PrivacyForm>,
+) -> Response {
+    let email = form.email();
+    if !is_plausible_email(&email) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Invalid email address".to_string()),
+        )
+            .into_response();
+    }
+    let performed_by = admin.user.as_ref().map(|user| user.email.as_str());
+    match privacy::export(&state.pool, &workspace, &email, performed_by).await {
+        Ok(export) => (
+            [(
+                header::CONTENT_DISPOSITION,
+                "attachment; filename=\"respondent-data.json\"",
+            )],
+            Json(export),
+        )
+            .into_response(),
+        Err(_) => Html("Error exporting respondent data".to_string()).into_response(),
+    }
+}
+
+/// Permanently delete a respondent's feedback once the address is confirmed
+async fn admin_privacy_delete(
+    RequireOwner(admin): RequireOwner,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Form(form): Form<PrivacyForm>,
+) -> Response {
+    let email = form.email();
+    if email.is_empty() || form.confirm.trim().to_lowercase() != email {
+        let error =
+            Some("The address typed to confirm doesn't match; nothing was deleted".to_string());
+        return match privacy_page(&state, workspace, email, None, error).await {
+            Ok(page) => (StatusCode::BAD_REQUEST, page).into_response(),
+            Err(_) => Html("Error loading privacy requests".to_string()).into_response(),
+        };
+    }
+    let performed_by = admin.user.as_ref().map(|user| user.email.as_str());
+    let deleted = match privacy::erase(&state.pool, &workspace.id, &email, performed_by).await {
+        Ok(deleted) => deleted,
+        Err(e) => {
+            state.read_only.observe(&e);
+            return Html("Error deleting respondent data".to_string()).into_response();
+        }
+    };
+    let notice = Some(format!(
+        "Deleted {} entr{} from {}",
+        deleted,
+        if deleted == 1 { "y" } else { "ies" },
+        privacy::hint(&email)
+    ));
+    match privacy_page(&state, workspace, String::new(), notice, None).await {
+        Ok(page) => page.into_response(),
+        Err(_) => Html("Error loading privacy requests".to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct SearchQuery {
+    #[serde(default)]
+    q: String,
+}
+
+async fn admin_search(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Query(query): Query<SearchQuery>,
+) -> impl IntoResponse {
+    let hits = match db::search_feedback(&state.pool, &workspace.id, &query.q).await {
+        Ok(hits) => hits,
+        Err(_) => return Html("Error searching feedback".to_string()),
+    };
+
+    // Group by prompt, keeping prompts in order of their best-ranked match
+    let mut groups: Vec<SearchResultGroup> = Vec::new();
+    for hit in hits {
+        let result = SearchResult {
+            feedback_id: hit.feedback_id,
+            created_at: hit.created_at,
+            snippet: split_snippet(&hit.snippet),
+        };
+        match groups.iter_mut().find(|g| g.prompt_id == hit.prompt_id) {
+            Some(group) => group.results.push(result),
+            None => groups.push(SearchResultGroup {
+                prompt_id: hit.prompt_id,
+                prompt_title: hit.prompt_title,
+                results: vec![result],
+            }),
+        }
+    }
+
+    let template = AdminSearchTemplate {
+        layout: state.layout(),
+        workspace,
+        query: query.q,
+        groups,
+    };
+    Html(template.render().unwrap())
+}
+
+/// Split a search snippet on its match markers so matches can be highlighted
+/// without bypassing template escaping
+fn split_snippet(snippet: &str) -> Vec<SnippetPart> {
+    let mut parts = Vec::new();
+    let mut rest = snippet;
+    while let Some(start) = rest.find(db::SNIPPET_MATCH_START) {
+        let (before, after) = rest.split_at(start);
+        let after = &after[db::SNIPPET_MATCH_START.len_utf8()..];
+        let end = after.find(db::SNIPPET_MATCH_END).unwrap_or(after.len());
+        if !before.is_empty() {
+            parts.push(SnippetPart {
+                text: before.to_string(),
+                highlighted: false,
+            });
+        }
+        parts.push(SnippetPart {
+            text: after[..end].to_string(),
+            highlighted: true,
+        });
+        rest = after[end..]
+            .strip_prefix(db::SNIPPET_MATCH_END)
+            .unwrap_or("");
+    }
+    if !rest.is_empty() {
+        parts.push(SnippetPart {
+            text: rest.to_string(),
+            highlighted: false,
+        });
+    }
+    parts
+}
+
+#[derive(Deserialize)]
+struct DetailQuery {
+    cycle: Option<i64>,
+    tag: Option<String>,
+    sentiment: Option<String>,
+    status: Option<String>,
+}
+
+/// The prompt page's filter and sort form, kept as typed so it can be shown
+/// again; values that don't parse are ignored rather than rejected
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+struct ListControls {
+    /// First day included, `YYYY-MM-DD`
+    from: String,
+    /// Last day included, `YYYY-MM-DD`
+    to: String,
+    /// Text the feedback must contain
+    q: String,
+    rating_field: String,
+    rating_min: String,
+    rating_max: String,
+    /// `newest` (the default) or `oldest`
+    sort: String,
+}
+
+impl ListControls {
+    /// Narrow `filter` by whichever controls are set
+    fn apply(&self, filter: &mut db::FeedbackFilter) {
+        filter.created_from = db::parse_since(&self.from);
+        filter.created_before = db::parse_until(&self.to);
+        filter.contains = Some(self.q.trim())
+            .filter(|q| !q.is_empty())
+            .map(str::to_string);
+        filter.rating = Some(self.rating_field.trim())
+            .filter(|id| !id.is_empty())
+            .map(|field_id| db::RatingRange {
+                field_id: field_id.to_string(),
+                min: self.rating_min.trim().parse().ok(),
+                max: self.rating_max.trim().parse().ok(),
+            });
+        filter.sort = db::SortOrder::parse(&self.sort).unwrap_or_default();
+    }
+
+    /// Whether any control other than the sort order narrows the list
+    fn is_filtering(&self) -> bool {
+        let mut filter = db::FeedbackFilter::default();
+        self.apply(&mut filter);
+        filter.created_from.is_some()
+            || filter.created_before.is_some()
+            || filter.contains.is_some()
+            || filter.rating.is_some()
+    }
+
+    fn oldest_first(&self) -> bool {
+        db::SortOrder::parse(&self.sort) == Some(db::SortOrder::Oldest)
+    }
+}
+
+async fn admin_detail(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+    Query(query): Query<DetailQuery>,
+    Query(controls): Query<ListControls>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(p)) => p,
+        _ => return Html("Prompt not found".to_string()),
+    };
+
+    // Make sure a cycle that started since the last submission is listed
+    let _ = db::current_cycle(&state.pool, &prompt, chrono::Utc::now()).await;
+    let summaries = db::get_cycle_summaries(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let selected_cycle = query
+        .cycle
+        .and_then(|n| summaries.iter().find(|c| c.number == n).cloned());
+    let cycles = cycle_rows(summaries);
+    let selected_tag = query.tag.as_deref().and_then(db::normalize_tag);
+    let selected_sentiment = query.sentiment.as_deref().and_then(sentiment::Label::parse);
+    let selected_status = query.status.as_deref().and_then(db::FeedbackStatus::parse);
+
+    let mut filter = db::FeedbackFilter {
+        cycle_id: selected_cycle.as_ref().map(|c| c.id.clone()),
+        tag: selected_tag.clone(),
+        sentiment: selected_sentiment,
+        status: selected_status,
+        ..Default::default()
+    };
+    controls.apply(&mut filter);
+    let mut feedback_list = db::list_feedback(&state.pool, &id, &filter)
+        .await
+        .unwrap_or_default();
+    if state.detect_duplicates {
+        let _ = db::attach_duplicates(&state.pool, &mut feedback_list).await;
+    }
+    let tag_counts = db::get_tag_counts_for_prompt(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let contents = db::get_feedback_contents(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let top_terms = keywords::top_terms(contents.iter().map(String::as_str), keywords::TOP_TERMS);
+    let sentiment = db::get_sentiment_breakdown(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let nps = if prompt.is_nps() {
+        db::get_nps_scores(&state.pool, &id)
+            .await
+            .ok()
+            .map(|answers| nps::Summary::from_scores(answers.into_iter().map(|(_, s)| s)))
+    } else {
+        None
+    };
+    let reactions = reaction_counts(&state, &prompt).await;
+    let status_counts = db::get_status_counts(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let rating_fields = db::get_rating_averages(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+
+    let (fields, available) = prompt_field_lists(&state, &workspace, &id)
+        .await
+        .unwrap_or_default();
+    let rules = db::get_notification_rules(&state.pool, &id)
+        .await
+        .map(|rules| rule_rows(rules, &fields))
+        .unwrap_or_default();
+    let translations = db::get_prompt_translations(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let translation_languages = translation_languages(&prompt);
+
+    let feedback_url = format!("{}/feedback/{}", origin, id);
+    let short_url = prompt
+        .slug
+        .as_ref()
+        .map(|slug| format!("{}/f/{}", origin, slug));
+    let embed_url = db::get_embed_secret(&state.pool, &id)
+        .await
+        .ok()
+        .flatten()
+        .map(|secret| embed_url(&origin, &id, &secret));
+    let feed_url = db::get_feed_secret(&state.pool, &id)
+        .await
+        .ok()
+        .flatten()
+        .map(|secret| feed_url(&origin, &workspace, &id, &secret));
+    let usage = db::get_storage_usage(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let responses = db::accepted_responses(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+
+    let template = AdminDetailTemplate {
+        layout: state.layout(),
+        workspace,
+        prompt,
+        feedback_list,
+        feedback_url,
+        short_url,
+        cycles,
+        selected_cycle,
+        tag_counts,
+        selected_tag,
+        top_terms,
+        sentiment,
+        selected_sentiment: selected_sentiment.map(|l| l.as_str().to_string()),
+        nps,
+        reactions,
+        status_counts,
+        selected_status: selected_status.map(|s| s.as_str().to_string()),
+        controls,
+        rating_fields,
+        fields,
+        available,
+        rules,
+        error: None,
+        translations,
+        translation_languages,
+        embed_url,
+        feed_url,
+        email_enabled: state.mailer.is_some(),
+        storage: storage::describe(usage.prompt_bytes, state.storage_quotas.prompt_bytes),
+        site_retention_days: state.settings().retention_days,
+        responses,
+    };
+    Html(template.render().unwrap())
+}
+
+#[derive(Deserialize)]
+struct PromptStatusForm {
+    status: String,
+}
+
+/// Close a prompt to new feedback, or reopen it
+async fn admin_set_prompt_status(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<PromptStatusForm>,
+) -> Response {
+    let Some(status) = db::PromptStatus::parse(&form.status) else {
+        return (StatusCode::BAD_REQUEST, Html("Invalid status".to_string())).into_response();
+    };
+    match set_prompt_status(&state, &workspace, &id, status).await {
+        Ok(_) => flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, id),
+            flash::Flash::success(format!("Prompt is now {}", status.as_str())),
+        ),
+        Err(e) => (e.status, Html(e.message)).into_response(),
+    }
+}
+
+const INVALID_PROMPT_SLUG: &str =
+    "Short URLs may only use letters, numbers and hyphens, up to 40 characters";
+const PROMPT_SLUG_TAKEN: &str = "That short URL is already taken";
+const INVALID_ACCESS_CODE: &str =
+    "Access codes must be 4 to 64 letters, numbers, hyphens or underscores";
+
+const INVALID_PUBLISH_AT: &str = "Publish times must be a date and time like 2024-05-01T09:00";
+const INVALID_MAX_RESPONSES: &str = "The response limit must be a whole number of at least 1";
+
+#[derive(Deserialize)]
+struct SlugForm {
+    /// Empty to remove the short URL
+    #[serde(default)]
+    slug: String,
+}
+
+/// Set or clear the `/f/:slug` short URL for a prompt
+async fn admin_set_slug(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<SlugForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let slug = match form.slug.trim() {
+        "" => None,
+        slug => match db::normalize_prompt_slug(slug) {
+            Some(slug) => Some(slug),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Html(INVALID_PROMPT_SLUG.to_string()),
+                )
+                    .into_response()
+            }
+        },
+    };
+    match db::set_prompt_slug(&state.pool, &id, slug.as_deref()).await {
+        Ok(true) => {
+            state.prompts.invalidate(&id);
+            flash::redirect(
+                &format!("/admin/{}/prompt/{}", workspace.slug, id),
+                flash::Flash::success("Short link saved"),
+            )
+        }
+        Ok(false) => (StatusCode::CONFLICT, Html(PROMPT_SLUG_TAKEN.to_string())).into_response(),
+        Err(_) => flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, id),
+            flash::Flash::error("Failed to save the short link"),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct AccessCodeForm {
+    /// Empty to let anyone answer
+    #[serde(default)]
+    access_code: String,
+}
+
+async fn admin_set_access_code(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<AccessCodeForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let code = match form.access_code.trim() {
+        "" => None,
+        code => match access::normalize(code) {
+            Some(code) => Some(code),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Html(INVALID_ACCESS_CODE.to_string()),
+                )
+                    .into_response()
+            }
+        },
+    };
+    match db::set_access_code(&state.pool, &id, code.as_deref()).await {
+        Ok(()) => {
+            state.prompts.invalidate(&id);
+            let notice = if code.is_some() {
+                "Access code saved"
+            } else {
+                "Access code removed"
+            };
+            flash::redirect(
+                &format!("/admin/{}/prompt/{}", workspace.slug, id),
+                flash::Flash::success(notice),
+            )
+        }
+        Err(_) => flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, id),
+            flash::Flash::error("Failed to save the access code"),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct PublishAtForm {
+    /// `datetime-local` value in UTC; empty to open right away
+    #[serde(default)]
+    publish_at: String,
+}
+
+async fn admin_set_publish_at(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<PublishAtForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let publish_at = match form.publish_at.trim() {
+        "" => None,
+        value => match db::parse_publish_at(value) {
+            Some(publish_at) => Some(publish_at),
+            None => {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Html(INVALID_PUBLISH_AT.to_string()),
+                )
+                    .into_response()
+            }
+        },
+    };
+    let back = format!("/admin/{}/prompt/{}", workspace.slug, id);
+    match db::set_publish_at(&state.pool, &id, publish_at.as_deref()).await {
+        Ok(()) => {
+            state.prompts.invalidate(&id);
+            let notice = if publish_at.is_some() {
+                "Publish time saved"
+            } else {
+                "Publish time removed"
+            };
+            flash::redirect(&back, flash::Flash::success(notice))
+        }
+        Err(_) => flash::redirect(
+            &back,
+            flash::Flash::error("Failed to save the publish time"),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct RetentionForm {
+    /// Empty to follow the site-wide period
+    #[serde(default)]
+    retention_days: String,
+}
+
+async fn admin_set_retention(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<RetentionForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let days = match retention::parse_days(&form.retention_days) {
+        Ok(days) => days,
+        Err(e) => return (StatusCode::BAD_REQUEST, Html(e.to_string())).into_response(),
+    };
+    let back = format!("/admin/{}/prompt/{}", workspace.slug, id);
+    match db::set_retention_days(&state.pool, &id, days).await {
+        Ok(()) => {
+            state.prompts.invalidate(&id);
+            let notice = if days.is_some() {
+                "Retention period saved"
+            } else {
+                "Retention period removed"
+            };
+            flash::redirect(&back, flash::Flash::success(notice))
+        }
+        Err(_) => flash::redirect(
+            &back,
+            flash::Flash::error("Failed to save the retention period"),
+        ),
+    }
+}
+
+async fn admin_invites(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let invites = match invites::list(&state.pool, &prompt.id).await {
+        Ok(invites) => invites,
+        Err(_) => return Html("Error loading invites".to_string()).into_response(),
+    };
+    let link_base = format!("{}/feedback/{}?invite=", origin, prompt.id);
+    let template = AdminInvitesTemplate {
+        layout: state.layout(),
+        workspace,
+        prompt,
+        invites,
+        link_base,
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+#[derive(Deserialize)]
+struct InviteBatchForm {
+    count: String,
+}
+
+async fn admin_create_invites(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<InviteBatchForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let count = match form.count.trim().parse::<i64>() {
+        Ok(count) if (1..=invites::MAX_BATCH).contains(&count) => count,
+        _ => {
+            return (
+                StatusCode::BAD_REQUEST,
+                Html(format!(
+                    "Generate between 1 and {} invites at a time",
+                    invites::MAX_BATCH
+                )),
+            )
+                .into_response()
+        }
+    };
+    let back = format!("/admin/{}/prompt/{}/invites", workspace.slug, id);
+    match invites::create(&state.pool, &id, count).await {
+        Ok(created) if created.len() == 1 => {
+            flash::redirect(&back, flash::Flash::success("1 invite created"))
+        }
+        Ok(created) => flash::redirect(
+            &back,
+            flash::Flash::success(format!("{} invites created", created.len())),
+        ),
+        Err(_) => flash::redirect(&back, flash::Flash::error("Failed to create invites")),
+    }
+}
+
+#[derive(Deserialize)]
+struct InviteOnlyForm {
+    invite_only: Option<String>,
+}
+
+async fn admin_set_invite_only(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<InviteOnlyForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let required = form.invite_only.is_some();
+    let back = format!("/admin/{}/prompt/{}/invites", workspace.slug, id);
+    match invites::set_required(&state.pool, &id, required).await {
+        Ok(()) => {
+            state.prompts.invalidate(&id);
+            let notice = if required {
+                "The form now only opens through an invite"
+            } else {
+                "The form is open to anyone with its link"
+            };
+            flash::redirect(&back, flash::Flash::success(notice))
+        }
+        Err(_) => flash::redirect(
+            &back,
+            flash::Flash::error("Failed to save the invite setting"),
+        ),
+    }
+}
+
+async fn admin_revoke_invite(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id, invite_id)): Path<(String, String, String)>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    match invites::revoke(&state.pool, &id, &invite_id).await {
+        Ok(true) => Html(String::new()).into_response(),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("No outstanding invite with that ID".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error revoking invite".to_string()).into_response(),
+    }
+}
+
+const INVALID_REDIRECT_URL: &str = "Redirect URL must be a public http or https URL";
+
+#[derive(Default, Deserialize)]
+struct ThankYouForm {
+    /// Markdown; empty for the standard thanks
+    #[serde(default)]
+    thank_you_message: String,
+    /// Empty to stay on the form
+    #[serde(default)]
+    redirect_url: String,
+}
+
+impl ThankYouForm {
+    /// The message and redirect to store, or `None` if the redirect is not
+    /// an http(s) URL
+    fn parse(&self) -> Option<(Option<String>, Option<String>)> {
+        let message = Some(self.thank_you_message.trim().to_string()).filter(|m| !m.is_empty());
+        let redirect_url = match self.redirect_url.trim() {
+            "" => None,
+            url if webhook::is_valid_url(url) => Some(url.to_string()),
+            _ => return None,
+        };
+        Some((message, redirect_url))
+    }
+}
+
+/// Set what respondents see after submitting to a prompt
+async fn admin_set_thank_you(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<ThankYouForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    let Some((message, redirect_url)) = form.parse() else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html(INVALID_REDIRECT_URL.to_string()),
+        )
+            .into_response();
+    };
+    if db::set_prompt_thank_you(
+        &state.pool,
+        &id,
+        message.as_deref(),
+        redirect_url.as_deref(),
+    )
+    .await
+    .is_err()
+    {
+        return flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, id),
+            flash::Flash::error("Failed to save the prompt"),
+        );
+    }
+    state.prompts.invalidate(&id);
+    flash::redirect(
+        &format!("/admin/{}/prompt/{}", workspace.slug, id),
+        flash::Flash::success("Prompt saved"),
+    )
+}
+
+#[derive(Deserialize)]
+struct ReceiptsForm {
+    enabled: Option<String>,
+}
+
+/// Turn emailed submission receipts on or off for a prompt
+async fn admin_set_receipts(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<ReceiptsForm>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    if db::set_send_receipts(&state.pool, &id, form.enabled.is_some())
+        .await
+        .is_err()
+    {
+        return flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, id),
+            flash::Flash::error("Failed to save the prompt"),
+        );
+    }
+    state.prompts.invalidate(&id);
+    flash::redirect(
+        &format!("/admin/{}/prompt/{}", workspace.slug, id),
+        flash::Flash::success("Prompt saved"),
+    )
+}
+
+/// Copy a prompt and its questions into a new, empty prompt
+async fn admin_duplicate_prompt(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Response {
+    let source = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    if source.is_suspended() {
+        return (
+            StatusCode::CONFLICT,
+            Html("Suspended prompts can't be duplicated".to_string()),
+        )
+            .into_response();
+    }
+    let title = format!("{} (copy)", source.title);
+    match db::clone_prompt(&state.pool, &source, &title, &source.description).await {
+        Ok(prompt) => flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, prompt.id),
+            flash::Flash::success("Prompt duplicated"),
+        ),
+        Err(_) => flash::redirect(
+            &format!("/admin/{}/prompt/{}", workspace.slug, source.id),
+            flash::Flash::error("Failed to duplicate the prompt"),
+        ),
+    }
+}
+
+fn prefill_url(origin: &str, prompt_id: &str, token: &str) -> String {
+    format!(
+        "{}/feedback/{}?{}={}",
+        origin,
+        prompt_id,
+        prefill::PARAM,
+        token
+    )
+}
+
+/// Answers to lock into a prefill link, checked like submitted ones. Errors
+/// name the field ID and what's wrong with its value.
+fn prefill_values(
+    fields: &[db::Field],
+    values: HashMap<String, String>,
+) -> Result<prefill::Values, (String, String)> {
+    let mut locked = prefill::Values::new();
+    for (id, value) in values {
+        if id == prefill::EMAIL {
+            let email = value.trim().to_lowercase();
+            if !is_plausible_email(&email) {
+                return Err((id, "must be a valid email address".to_string()));
+            }
+            locked.insert(id, email);
+            continue;
+        }
+        let Some(field) = fields.iter().find(|f| f.id == id) else {
+            return Err((id, "is not a field on this prompt".to_string()));
+        };
+        let value = value.trim();
+        if value.is_empty() {
+            return Err((id, "must not be empty".to_string()));
+        }
+        if let Err(message) = field.rules().check(value) {
+            return Err((id, message));
+        }
+        locked.insert(id, value.to_string());
+    }
+    Ok(locked)
+}
+
+/// Sign a link locking the answers filled in on the detail page; blank
+/// inputs are left for the respondent
+async fn admin_create_prefill_link(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let fields = match db::get_prompt_fields(&state.pool, &prompt.id).await {
+        Ok(fields) => fields,
+        Err(_) => return Html("Error creating the link".to_string()).into_response(),
+    };
+    let values: HashMap<String, String> = form
+        .into_iter()
+        .filter(|(_, value)| !value.trim().is_empty())
+        .filter_map(|(key, value)| Some((key.strip_prefix("field_")?.to_string(), value)))
+        .collect();
+
+    let result = if values.is_empty() {
+        Err("Fill in at least one answer to lock".to_string())
+    } else {
+        match prefill_values(&fields, values) {
+            Ok(locked) => match db::get_or_create_prefill_secret(&state.pool, &prompt.id).await {
+                Ok(secret) => {
+                    let token = prefill::sign(&secret, &prompt.id, &locked);
+                    Ok(prefill_url(&origin, &prompt.id, &token))
+                }
+                Err(_) => Err("Error creating the link".to_string()),
+            },
+            Err((id, message)) => {
+                let label = match fields.iter().find(|f| f.id == id) {
+                    Some(field) => field.label.as_str(),
+                    None if id == prefill::EMAIL => "Email",
+                    None => "Answer",
+                };
+                Err(format!("{} {}", label, message))
+            }
+        }
+    };
+    Html(PrefillLinkPartialTemplate { result }.render().unwrap()).into_response()
+}
+
+fn embed_url(origin: &str, prompt_id: &str, secret: &str) -> String {
+    format!(
+        "{}/embed/{}/results?sig={}",
+        origin,
+        prompt_id,
+        embed::sign(secret, prompt_id)
+    )
+}
+
+/// Turn the results widget on, or replace its link with a new one
+async fn admin_enable_embed(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    set_embed(&state, workspace, &origin, &id, true).await
+}
+
+async fn admin_disable_embed(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    set_embed(&state, workspace, &origin, &id, false).await
+}
+
+async fn set_embed(
+    state: &AppState,
+    workspace: db::Workspace,
+    origin: &str,
+    id: &str,
+    enabled: bool,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let secret = match db::set_embed_secret(&state.pool, &prompt.id, enabled).await {
+        Ok(secret) => secret,
+        Err(_) => return Html("Error updating the results widget".to_string()).into_response(),
+    };
+    let template = EmbedPartialTemplate {
+        embed_url: secret.map(|secret| embed_url(origin, &prompt.id, &secret)),
+        workspace,
+        prompt,
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+#[derive(Deserialize)]
+struct EmbedQuery {
+    sig: Option<String>,
+}
+
+/// Public, read-only results widget for iframes on other sites
+async fn embed_results(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<EmbedQuery>,
+) -> Response {
+    // Unknown prompts, disabled widgets and bad signatures all look the same
+    let not_found = || (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response();
+    let Ok(Some(secret)) = db::get_embed_secret(&state.pool, &id).await else {
+        return not_found();
+    };
+    if !embed::verify(&secret, &id, query.sig.as_deref().unwrap_or_default()) {
+        return not_found();
+    }
+    let Ok(Some(prompt)) = state.prompts.get(&state.pool, &id).await else {
+        return not_found();
+    };
+    if prompt.is_suspended() {
+        return not_found();
+    }
+
+    let summary = db::get_results_summary(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let sentiment = db::get_sentiment_breakdown(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let template = EmbedResultsTemplate {
+        prompt,
+        summary,
+        sentiment,
+        refresh_secs: embed::CACHE_MAX_AGE_SECS,
+    };
+    (
+        AppendHeaders([(
+            header::CACHE_CONTROL,
+            format!("public, max-age={}", embed::CACHE_MAX_AGE_SECS),
+        )]),
+        Html(template.render().unwrap()),
+    )
+        .into_response()
+}
+
+fn feed_url(origin: &str, workspace: &db::Workspace, prompt_id: &str, secret: &str) -> String {
+    format!(
+        "{}/admin/{}/prompt/{}/feed.atom?token={}",
+        origin,
+        workspace.slug,
+        prompt_id,
+        feed::sign(secret, prompt_id)
+    )
+}
+
+/// Turn the Atom feed on, or replace its link with a new one
+async fn admin_enable_feed(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    set_feed(&state, workspace, &origin, &id, true).await
+}
+
+async fn admin_disable_feed(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    set_feed(&state, workspace, &origin, &id, false).await
+}
+
+async fn set_feed(
+    state: &AppState,
+    workspace: db::Workspace,
+    origin: &str,
+    id: &str,
+    enabled: bool,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let secret = match db::set_feed_secret(&state.pool, &prompt.id, enabled).await {
+        Ok(secret) => secret,
+        Err(_) => return Html("Error updating the feed".to_string()).into_response(),
+    };
+    let template = FeedPartialTemplate {
+        feed_url: secret.map(|secret| feed_url(origin, &workspace, &prompt.id, &secret)),
+        workspace,
+        prompt,
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+#[derive(Deserialize)]
+struct FeedQuery {
+    token: Option<String>,
+}
+
+/// A prompt's newest feedback as Atom, opened by the signed `token` rather
+/// than a session so feed readers can follow it
+async fn admin_prompt_feed(
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+    Query(query): Query<FeedQuery>,
+) -> Response {
+    // Unknown prompts, disabled feeds and bad tokens all look the same
+    let not_found = || (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response();
+    let Ok(Some(prompt)) = db::get_workspace_prompt(&state.pool, &workspace.id, &id).await else {
+        return not_found();
+    };
+    let Ok(Some(secret)) = db::get_feed_secret(&state.pool, &id).await else {
+        return not_found();
+    };
+    if !feed::verify(&secret, &id, query.token.as_deref().unwrap_or_default()) {
+        return not_found();
+    }
+
+    let filter = db::FeedbackFilter {
+        limit: Some(feed::MAX_ENTRIES),
+        ..Default::default()
+    };
+    let entries = match db::list_feedback(&state.pool, &id, &filter).await {
+        Ok(entries) => entries,
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html("Error loading feedback".to_string()),
+            )
+                .into_response()
+        }
+    };
+    let page_url = format!("{}/admin/{}/prompt/{}", origin, workspace.slug, id);
+    (
+        [
+            (header::CONTENT_TYPE, "application/atom+xml; charset=utf-8"),
+            (header::CACHE_CONTROL, "private, no-cache"),
+        ],
+        feed::render(
+            &prompt,
+            &entries,
+            &feed_url(&origin, &workspace, &id, &secret),
+            &page_url,
+        ),
+    )
+        .into_response()
+}
+
+fn rule_rows(rules: Vec<db::NotificationRule>, fields: &[db::Field]) -> Vec<RuleRow> {
+    rules
+        .into_iter()
+        .map(|rule| {
+            let description = match rule.condition() {
+                Some(condition) => condition.describe(fields),
+                None => "Invalid condition".to_string(),
+            };
+            RuleRow { rule, description }
+        })
+        .collect()
+}
+
+/// Re-render the Notifications section, with an error from the add form if any
+async fn notification_rules_partial(
+    state: &AppState,
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    error: Option<String>,
+) -> Response {
+    let loaded = async {
+        let fields = db::get_prompt_fields(&state.pool, &prompt.id).await?;
+        let rules = db::get_notification_rules(&state.pool, &prompt.id).await?;
+        Ok::<_, sqlx::Error>((rule_rows(rules, &fields), fields))
+    };
+    match loaded.await {
+        Ok((rules, fields)) => {
+            let template = NotificationRulesPartialTemplate {
+                workspace,
+                prompt,
+                rules,
+                fields,
+                error,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        Err(_) => Html("Error loading notification rules".to_string()).into_response(),
+    }
+}
+
+async fn admin_add_notification_rule(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<NotificationRuleForm>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+
+    let checked = async {
+        let target_url = form.target_url.trim();
+        if !state.webhooks.accepts(target_url) {
+            return Ok(Err(
+                "Notification URL must be a public http or https URL".to_string()
+            ));
+        }
+        let Some(delivery) = notify::Delivery::parse(&form.delivery) else {
+            return Ok(Err("Invalid delivery".to_string()));
+        };
+        let condition = match notify::Condition::from_parts(
+            &form.condition,
+            Some(&form.field_id),
+            Some(&form.operand),
+        ) {
+            Ok(condition) => condition,
+            Err(message) => return Ok(Err(message)),
+        };
+        if let Some(field_id) = condition.field_id() {
+            let fields = db::get_prompt_fields(&state.pool, &prompt.id).await?;
+            if !fields.iter().any(|f| f.id == field_id) {
+                return Ok(Err("Choose one of this prompt's questions".to_string()));
+            }
+        }
+        db::create_notification_rule(&state.pool, &prompt.id, &condition, delivery, target_url)
+            .await?;
+        Ok::<_, sqlx::Error>(Ok(()))
+    };
+    match checked.await {
+        Ok(Ok(())) => notification_rules_partial(&state, workspace, prompt, None).await,
+        Ok(Err(message)) => {
+            notification_rules_partial(&state, workspace, prompt, Some(message)).await
+        }
+        Err(_) => Html("Error saving notification rule".to_string()).into_response(),
+    }
+}
+
+async fn admin_delete_notification_rule(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id, rule_id)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    match db::delete_notification_rule(&state.pool, &prompt.id, &rule_id).await {
+        Ok(true) => notification_rules_partial(&state, workspace, prompt, None).await,
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("Notification rule not found".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error deleting notification rule".to_string()).into_response(),
+    }
+}
+
+/// Languages a prompt can be translated into: all but the one it's written in
+fn translation_languages(prompt: &db::Prompt) -> Vec<(&'static str, &'static str)> {
+    let base = prompt.language.as_deref().unwrap_or(i18n::DEFAULT);
+    i18n::LANGUAGES
+        .iter()
+        .copied()
+        .filter(|(code, _)| *code != base)
+        .collect()
+}
+
+/// Tidy a translation before saving: a supported language other than the
+/// prompt's own and a title are required, and labels must belong to the
+/// prompt's fields. Blank labels are dropped. Errors name the offending input.
+fn check_translation(
+    prompt: &db::Prompt,
+    fields: &[db::Field],
+    mut translation: db::PromptTranslation,
+) -> Result<db::PromptTranslation, (String, String)> {
+    let error = |field: &str, message: &str| Err((field.to_string(), message.to_string()));
+    if !translation_languages(prompt)
+        .iter()
+        .any(|(code, _)| *code == translation.language)
+    {
+        return error(
+            "language",
+            "must be a supported language other than the prompt's own",
+        );
+    }
+    translation.title = translation.title.trim().to_string();
+    if translation.title.is_empty() {
+        return error("title", "must not be empty");
+    }
+    translation.description = translation.description.trim().to_string();
+    let mut labels = std::collections::BTreeMap::new();
+    for (id, label) in translation.field_labels {
+        if !fields.iter().any(|f| f.id == id) {
+            return error(
+                &format!("field_labels.{}", id),
+                "is not a field on this prompt",
+            );
+        }
+        let label = label.trim();
+        if !label.is_empty() {
+            labels.insert(id, label.to_string());
+        }
+    }
+    translation.field_labels = labels;
+    Ok(translation)
+}
+
+/// Re-render the Translations section, with an error from the add form if any
+async fn translations_partial(
+    state: &AppState,
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+    error: Option<String>,
+) -> Response {
+    let loaded = async {
+        let fields = db::get_prompt_fields(&state.pool, &prompt.id).await?;
+        let translations = db::get_prompt_translations(&state.pool, &prompt.id).await?;
+        Ok::<_, sqlx::Error>((fields, translations))
+    };
+    match loaded.await {
+        Ok((fields, translations)) => {
+            let template = PromptTranslationsPartialTemplate {
+                translation_languages: translation_languages(&prompt),
+                workspace,
+                prompt,
+                translations,
+                fields,
+                error,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        Err(_) => Html("Error loading translations".to_string()).into_response(),
+    }
+}
+
+/// Add or replace a translation from the detail page. Inputs are `language`,
+/// `title`, `description` and a `label_<field id>` per field.
+async fn admin_save_translation(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(mut form): Form<HashMap<String, String>>,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let fields = match db::get_prompt_fields(&state.pool, &prompt.id).await {
+        Ok(fields) => fields,
+        Err(_) => return Html("Error saving translation".to_string()).into_response(),
+    };
+
+    let translation = db::PromptTranslation {
+        language: form.remove("language").unwrap_or_default(),
+        title: form.remove("title").unwrap_or_default(),
+        description: form.remove("description").unwrap_or_default(),
+        field_labels: form
+            .into_iter()
+            .filter_map(|(key, label)| Some((key.strip_prefix("label_")?.to_string(), label)))
+            .collect(),
+        updated_at: String::new(),
+    };
+    let translation = match check_translation(&prompt, &fields, translation) {
+        Ok(translation) => translation,
+        Err((field, message)) => {
+            let field = match field.as_str() {
+                "language" => "Language",
+                "title" => "Title",
+                _ => "Question",
+            };
+            let error = format!("{} {}", field, message);
+            return translations_partial(&state, workspace, prompt, Some(error)).await;
+        }
+    };
+    match db::set_prompt_translation(&state.pool, &prompt.id, &translation).await {
+        Ok(_) => translations_partial(&state, workspace, prompt, None).await,
+        Err(_) => Html("Error saving translation".to_string()).into_response(),
+    }
+}
+
+async fn admin_delete_translation(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id, language)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    match db::delete_prompt_translation(&state.pool, &prompt.id, &language).await {
+        Ok(true) => translations_partial(&state, workspace, prompt, None).await,
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("Translation not found".to_string()),
+        )
+            .into_response(),
+        Err(_) => Html("Error deleting translation".to_string()).into_response(),
+    }
+}
+
+/// A prompt's fields, and the rest of the workspace's question bank
+async fn prompt_field_lists(
+    state: &AppState,
+    workspace: &db::Workspace,
+    prompt_id: &str,
+) -> Result<(Vec<db::Field>, Vec<db::Field>), sqlx::Error> {
+    let fields = db::get_prompt_fields(&state.pool, prompt_id).await?;
+    let available = db::get_field_usage(&state.pool, &workspace.id)
+        .await?
+        .into_iter()
+        .map(|usage| usage.field)
+        .filter(|field| !fields.iter().any(|f| f.id == field.id))
+        .collect();
+    Ok((fields, available))
+}
+
+/// Re-render the Questions section after adding or removing a field
+async fn prompt_fields_partial(
+    state: &AppState,
+    workspace: db::Workspace,
+    prompt: db::Prompt,
+) -> Response {
+    match prompt_field_lists(state, &workspace, &prompt.id).await {
+        Ok((fields, available)) => {
+            let template = PromptFieldsPartialTemplate {
+                workspace,
+                prompt,
+                fields,
+                available,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        Err(_) => Html("Error loading questions".to_string()).into_response(),
+    }
+}
+
+async fn admin_add_prompt_field(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<PromptFieldForm>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    match db::get_field(&state.pool, &workspace.id, &form.field_id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Field not found".to_string())).into_response(),
+    }
+    if db::add_prompt_field(&state.pool, &prompt.id, &form.field_id)
+        .await
+        .is_err()
+    {
+        return Html("Error adding question".to_string()).into_response();
+    }
+    prompt_fields_partial(&state, workspace, prompt).await
+}
+
+async fn admin_remove_prompt_field(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id, field_id)): Path<(String, String, String)>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    if db::remove_prompt_field(&state.pool, &prompt.id, &field_id)
+        .await
+        .is_err()
+    {
+        return Html("Error removing question".to_string()).into_response();
+    }
+    prompt_fields_partial(&state, workspace, prompt).await
+}
+
+/// Pair each cycle (newest first) with its change from the cycle before it
+fn cycle_rows(summaries: Vec<db::CycleSummary>) -> Vec<CycleRow> {
+    let previous_counts: Vec<Option<i64>> = summaries
+        .iter()
+        .skip(1)
+        .map(|c| Some(c.response_count))
+        .chain(std::iter::once(None))
+        .collect();
+
+    summaries
+        .into_iter()
+        .zip(previous_counts)
+        .map(|(summary, previous)| {
+            let trend = match previous {
+                Some(previous) => format!("{:+}", summary.response_count - previous),
+                None => String::new(),
+            };
+            CycleRow { summary, trend }
+        })
+        .collect()
+}
+
+/// Days charted on the stats page, ending today
+const STATS_DAYS: i64 = 30;
+
+fn chart_bars(counts: Vec<(String, i64)>) -> Vec<ChartBar> {
+    let max = counts
+        .iter()
+        .map(|(_, count)| *count)
+        .max()
+        .unwrap_or(0)
+        .max(1);
+    counts
+        .into_iter()
+        .map(|(label, count)| ChartBar {
+            label,
+            count,
+            percent: count * 100 / max,
+        })
+        .collect()
+}
+
+/// Response volume over time, busiest hours and rating averages for a prompt
+async fn admin_prompt_stats(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+
+    let today = chrono::Utc::now().date_naive();
+    let first_day = today - chrono::Duration::days(STATS_DAYS - 1);
+    let since = first_day
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .to_rfc3339();
+    let daily = db::get_daily_counts(&state.pool, &id, &since)
+        .await
+        .unwrap_or_default();
+    let days = chart_bars(
+        first_day
+            .iter_days()
+            .take(STATS_DAYS as usize)
+            .map(|day| {
+                let day = day.format("%Y-%m-%d").to_string();
+                let count = daily.iter().find(|d| d.day == day).map_or(0, |d| d.count);
+                (day, count)
+            })
+            .collect(),
+    );
+
+    let hourly = db::get_hourly_counts(&state.pool, &id)
+        .await
+        .unwrap_or_default();
+    let hours = chart_bars(
+        (0..24)
+            .map(|hour| {
+                let count = hourly
+                    .iter()
+                    .find(|h| h.hour == hour)
+                    .map_or(0, |h| h.count);
+                (format!("{:02}:00", hour), count)
+            })
+            .collect(),
+    );
+
+    let nps = if prompt.is_nps() {
+        let answers = db::get_nps_scores(&state.pool, &id)
+            .await
+            .unwrap_or_default();
+        let summary = nps::Summary::from_scores(answers.iter().map(|(_, score)| *score));
+        Some(NpsStats {
+            scores: chart_bars(
+                (0..=nps::MAX_SCORE)
+                    .map(|score| (score.to_string(), summary.counts[score as usize]))
+                    .collect(),
+            ),
+            weeks: nps::weekly(&answers, today, nps::TREND_WEEKS),
+            summary,
+        })
+    } else {
+        None
+    };
+
+    let reactions = reaction_counts(&state, &prompt).await;
+
+    let template = AdminStatsTemplate {
+        layout: state.layout(),
+        workspace,
+        prompt,
+        nps,
+        reactions,
+        summary: db::get_results_summary(&state.pool, &id)
+            .await
+            .unwrap_or_default(),
+        days,
+        hours,
+        ratings: db::get_rating_averages(&state.pool, &id)
+            .await
+            .unwrap_or_default(),
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+/// How often each reaction was picked, on reactions prompts
+async fn reaction_counts(state: &AppState, prompt: &db::Prompt) -> Option<reactions::Counts> {
+    if !prompt.is_reactions() {
+        return None;
+    }
+    let rows = db::get_reaction_counts(&state.pool, &prompt.id)
+        .await
+        .ok()?;
+    Some(reactions::Counts::from_rows(&rows))
+}
+
+/// A year of daily response counts for the heat calendar, ending today (UTC)
+async fn calendar_days(
+    state: &AppState,
+    prompt_id: &str,
+) -> Result<Vec<calendar::Day>, sqlx::Error> {
+    let today = chrono::Utc::now().date_naive();
+    let since = calendar::start(today)
+        .and_hms_opt(0, 0, 0)
+        .unwrap()
+        .and_utc()
+        .to_rfc3339();
+    let counts = db::get_daily_counts(&state.pool, prompt_id, &since).await?;
+    Ok(calendar::days(today, &counts))
+}
+
+/// The stats page's heat calendar, as an SVG image
+async fn admin_prompt_calendar(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => {}
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+    match calendar_days(&state, &id).await {
+        Ok(days) => (
+            [
+                (header::CONTENT_TYPE, "image/svg+xml"),
+                (header::CACHE_CONTROL, "no-cache"),
+            ],
+            calendar::render_svg(&days),
+        )
+            .into_response(),
+        Err(_) => (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            Html("Error loading the calendar".to_string()),
+        )
+            .into_response(),
+    }
+}
+
+/// Send a synthetic receipt through the normal signing and retry path and
+/// report how the endpoint answered
+async fn admin_test_webhook(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> impl IntoResponse {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let (Some(url), Some(secret)) = (&prompt.webhook_url, &prompt.webhook_secret) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("This prompt has no webhook".to_string()),
+        )
+            .into_response();
+    };
+
+    let receipt = webhook::Receipt::test_event(&prompt);
+    let delivery = webhook::deliver(&state.webhooks, url, secret, &receipt).await;
+    let template = WebhookTestPartialTemplate { delivery };
+    Html(template.render().unwrap()).into_response()
+}
+
+async fn admin_feedback_events(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Response {
+    match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(_)) => feedback_event_stream(&state, id).into_response(),
+        _ => (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+}
+
+fn feedback_event_stream(
+    state: &AppState,
+    id: String,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    // Lagged receivers skip the missed entries rather than closing the stream
+    let stream = BroadcastStream::new(state.feedback_events.subscribe()).filter_map(move |msg| {
+        let feedback = msg.ok().filter(|fb| fb.prompt_id == id)?;
+        let event = Event::default()
+            .event("feedback")
+            .json_data(&feedback)
+            .ok()?;
+        Some(Ok(event))
+    });
+    let stream =
+        futures_util::StreamExt::take_until(stream, state.shutdown.clone().cancelled_owned());
+
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+#[derive(Deserialize)]
+struct FeedbackFormQuery {
+    prefill: Option<String>,
+    /// Access code, from the code page or a link that includes it
+    code: Option<String>,
+    /// Single-use invite token
+    invite: Option<String>,
+    /// Language picked from the switcher
+    lang: Option<String>,
+}
+
+const INVALID_PREFILL: &str =
+    "This link is invalid or has been changed. Ask whoever sent it for a new one.";
+
+/// Answers locked by a prefill token, if it was signed with the prompt's key
+async fn verify_prefill(state: &AppState, prompt_id: &str, token: &str) -> Option<prefill::Values> {
+    let secret = db::get_prefill_secret(&state.pool, prompt_id)
+        .await
+        .ok()??;
+    prefill::verify(&secret, prompt_id, token)
+}
+
+/// What a verified prefill token settles about a submission
+#[derive(Default)]
+struct PrefillLock {
+    /// IDs of the fields whose answers it locks
+    fields: Vec<String>,
+    /// The respondent's email, if the link was made out to one
+    email: Option<String>,
+}
+
+/// Overwrite submitted answers with the ones a prefill token locks; `None`
+/// if the token doesn't verify
+async fn apply_prefill(
+    state: &AppState,
+    prompt_id: &str,
+    token: Option<&str>,
+    answers: &mut HashMap<String, String>,
+) -> Option<PrefillLock> {
+    let Some(token) = token.map(str::trim).filter(|t| !t.is_empty()) else {
+        return Some(PrefillLock::default());
+    };
+    let mut locked = verify_prefill(state, prompt_id, token).await?;
+    let email = locked.remove(prefill::EMAIL);
+    let fields = locked.keys().cloned().collect();
+    answers.extend(locked);
+    Some(PrefillLock { fields, email })
+}
+
+/// Languages a respondent can have a prompt in: the one it's written in plus
+/// its translations, or any supported language if it sets neither
+fn offered_languages(
+    prompt: &db::Prompt,
+    translations: &[db::PromptTranslation],
+) -> Vec<&'static str> {
+    let base = prompt.language.as_deref().unwrap_or(i18n::DEFAULT);
+    i18n::LANGUAGES
+        .iter()
+        .map(|(code, _)| *code)
+        .filter(|code| {
+            (prompt.language.is_none() && translations.is_empty())
+                || *code == base
+                || translations.iter().any(|t| t.language == *code)
+        })
+        .collect()
+}
+
+/// Pick a respondent's language, from the switcher (`requested`) or else their
+/// browser, and swap in the prompt's translation for it. Returns the catalog
+/// and the switcher's languages, if the prompt is translated.
+async fn localize(
+    state: &AppState,
+    prompt: &mut db::Prompt,
+    fields: &mut [db::Field],
+    requested: Option<&str>,
+    headers: &HeaderMap,
+) -> (&'static i18n::Catalog, Vec<LanguageLink>) {
+    let translations = db::get_prompt_translations(&state.pool, &prompt.id)
+        .await
+        .unwrap_or_default();
+    let offered = offered_languages(prompt, &translations);
+    let t = match requested.filter(|lang| offered.contains(lang)) {
+        Some(lang) => i18n::get(lang),
+        None => {
+            let accept_language = headers
+                .get(header::ACCEPT_LANGUAGE)
+                .and_then(|value| value.to_str().ok());
+            let base = prompt.language.as_deref().unwrap_or(i18n::DEFAULT);
+            i18n::negotiate(&offered, base, accept_language)
+        }
+    };
+    if let Some(translation) = translations.iter().find(|tr| tr.language == t.lang()) {
+        translation.apply(prompt, fields);
+    }
+    if translations.is_empty() {
+        return (t, Vec::new());
+    }
+    let switcher = offered
+        .into_iter()
+        .map(|code| LanguageLink {
+            code,
+            name: i18n::name(code),
+            current: code == t.lang(),
+        })
+        .collect();
+    (t, switcher)
+}
+
+async fn closed_page(state: &AppState, mut prompt: db::Prompt, headers: &HeaderMap) -> Response {
+    let (t, _) = localize(state, &mut prompt, &mut [], None, headers).await;
+    Html(
+        FeedbackClosedTemplate {
+            layout: state.layout(),
+            prompt,
+            t,
+        }
+        .render()
+        .unwrap(),
+    )
+    .into_response()
+}
+
+async fn full_page(state: &AppState, mut prompt: db::Prompt, headers: &HeaderMap) -> Response {
+    let (t, _) = localize(state, &mut prompt, &mut [], None, headers).await;
+    Html(
+        FeedbackFullTemplate {
+            layout: state.layout(),
+            prompt,
+            t,
+        }
+        .render()
+        .unwrap(),
+    )
+    .into_response()
+}
+
+/// The answer to a form post on a full prompt; htmx only swaps in
+/// successful responses, so it gets the message alone
+async fn full_response(state: &AppState, mut prompt: db::Prompt, headers: &HeaderMap) -> Response {
+    if headers.contains_key("hx-request") {
+        let (t, _) = localize(state, &mut prompt, &mut [], None, headers).await;
+        return Html(t.msg("full-message")).into_response();
+    }
+    (
+        StatusCode::CONFLICT,
+        full_page(state, prompt, headers).await,
+    )
+        .into_response()
+}
+
+/// Whether the prompt already has all the responses its cap allows, for a
+/// submission made through `prefill`. Only for choosing what to show: the
+/// cap itself is enforced by the insert.
+async fn is_full(state: &AppState, prompt: &db::Prompt, prefill: Option<&str>) -> bool {
+    let Some(max) = prompt.max_responses else {
+        return false;
+    };
+    let full = db::accepted_responses(&state.pool, &prompt.id)
+        .await
+        .is_ok_and(|responses| responses >= max);
+    full && !replaces_entry(state, prompt, prefill).await
+}
+
+/// Whether a submission through `prefill` would replace the respondent's
+/// entry rather than add one, so doesn't need a place under the cap
+async fn replaces_entry(state: &AppState, prompt: &db::Prompt, prefill: Option<&str>) -> bool {
+    if !prompt.merge_responses {
+        return false;
+    }
+    let Some(token) = prefill.map(str::trim).filter(|t| !t.is_empty()) else {
+        return false;
+    };
+    let Some(email) = verify_prefill(state, &prompt.id, token)
+        .await
+        .and_then(|mut locked| locked.remove(prefill::EMAIL))
+    else {
+        return false;
+    };
+    let Ok(cycle) = db::current_cycle(&state.pool, prompt, chrono::Utc::now()).await else {
+        return false;
+    };
+    db::has_respondent_feedback(
+        &state.pool,
+        &prompt.id,
+        &email,
+        cycle.as_ref().map(|c| c.id.as_str()),
+    )
+    .await
+    .unwrap_or(false)
+}
+
+async fn scheduled_page(
+    state: &AppState,
+    mut prompt: db::Prompt,
+    opens_at: chrono::DateTime<chrono::Utc>,
+    headers: &HeaderMap,
+) -> Response {
+    let (t, _) = localize(state, &mut prompt, &mut [], None, headers).await;
+    Html(
+        FeedbackScheduledTemplate {
+            layout: state.layout(),
+            prompt,
+            t,
+            opens_at,
+        }
+        .render()
+        .unwrap(),
+    )
+    .into_response()
+}
+
+async fn feedback_form(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Query(query): Query<FeedbackFormQuery>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Response {
+    let client = client_address(&state, connect, &headers);
+    match state.prompts.get(&state.pool, &id).await {
+        Ok(Some(prompt)) => feedback_page(&state, prompt, query, &client, &headers).await,
+        _ => Html("Prompt not found".to_string()).into_response(),
+    }
+}
+
+/// The feedback form at a prompt's short URL; it still submits to `/feedback/:id`
+async fn feedback_form_by_slug(
+    State(state): State<Arc<AppState>>,
+    Path(slug): Path<String>,
+    Query(query): Query<FeedbackFormQuery>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+) -> Response {
+    let client = client_address(&state, connect, &headers);
+    let prompt = match db::normalize_prompt_slug(&slug) {
+        Some(slug) => db::get_prompt_by_slug(&state.pool, &slug).await,
+        None => Ok(None),
+    };
+    match prompt {
+        Ok(Some(prompt)) => feedback_page(&state, prompt, query, &client, &headers).await,
+        _ => (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    }
+}
+
+/// The feedback form for `prompt`, or whatever stands in for it while the
+/// prompt can't take responses
+async fn feedback_page(
+    state: &AppState,
+    mut prompt: db::Prompt,
+    query: FeedbackFormQuery,
+    client: &str,
+    headers: &HeaderMap,
+) -> Response {
+    if prompt.is_suspended() {
+        return suspended_page(state);
+    }
+    if let Some(opens_at) = prompt.scheduled_until() {
+        return scheduled_page(state, prompt, opens_at, headers).await;
+    }
+    if !prompt.is_open() {
+        return closed_page(state, prompt, headers).await;
+    }
+    if is_full(state, &prompt, query.prefill.as_deref()).await {
+        return full_page(state, prompt, headers).await;
+    }
+    if state.read_only.is_read_only() {
+        return unavailable_page(state);
+    }
+    let prefill = query.prefill.filter(|t| !t.is_empty());
+    let locked = match &prefill {
+        Some(token) => match verify_prefill(state, &prompt.id, token).await {
+            Some(locked) => locked,
+            None => {
+                return (StatusCode::BAD_REQUEST, Html(INVALID_PREFILL.to_string())).into_response()
+            }
+        },
+        None => prefill::Values::new(),
+    };
+    let mut fields = db::get_prompt_fields(&state.pool, &prompt.id)
+        .await
+        .unwrap_or_default();
+    let (t, languages) = localize(
+        state,
+        &mut prompt,
+        &mut fields,
+        query.lang.as_deref(),
+        headers,
+    )
+    .await;
+    let invite = match invites::check(&state.pool, &prompt, query.invite.as_deref()).await {
+        Ok(invites::Check::NotRequired) => None,
+        Ok(invites::Check::Outstanding(token)) => Some(token),
+        Ok(check) => return invite_refused(state, prompt, t, check),
+        Err(_) => return Html("Error loading feedback form".to_string()).into_response(),
+    };
+    let access_code = match check_access(state, &prompt, query.code.as_deref(), client) {
+        Access::Open => None,
+        Access::Granted(code) => Some(code),
+        // Arriving without a code is the normal first visit, not a failure
+        Access::Missing => {
+            return access_page(state, prompt, t, StatusCode::OK, None, prefill, invite)
+        }
+        refused => return access_refused(state, prompt, t, refused, prefill, invite),
+    };
+    let Ok(secret) = db::get_or_create_prefill_secret(&state.pool, &prompt.id).await else {
+        return Html("Error loading feedback form".to_string()).into_response();
+    };
+    let rendered_at = spam::stamp(&secret, &prompt.id, chrono::Utc::now().timestamp());
+    let template = FeedbackFormTemplate {
+        layout: state.layout(),
+        prompt,
+        fields,
+        honeypot_field: spam::HONEYPOT_FIELD,
+        rendered_at,
+        captcha: state.captcha.clone(),
+        submission_token: auth::random_token(),
+        receipts_enabled: state.receipts_enabled(),
+        prefill,
+        access_code,
+        invite,
+        locked,
+        t,
+        languages,
+        values: FeedbackValues::default(),
+        errors: validation::Errors::default(),
+    };
+    // The language can depend on the browser's Accept-Language
+    let page = Html(template.render().unwrap());
+    ([(header::VARY, "accept-language")], page).into_response()
+}
+
+/// The peer's IP address, for per-client throttles; requests handled in
+/// process, as in tests, have none
+/// The respondent's address, for per-client throttles: the client the proxy
+/// forwarded for when `TRUST_PROXY_HEADERS` is set (the TCP peer is then the
+/// proxy, shared by everyone), else the peer itself
+fn client_address(
+    state: &AppState,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: &HeaderMap,
+) -> String {
+    state
+        .trust_proxy_headers
+        .then(|| origin::forwarded_client(headers))
+        .flatten()
+        .or_else(|| connect.map(|ConnectInfo(addr)| addr.ip()))
+        .map_or_else(|| "unknown".to_string(), |ip| ip.to_string())
+}
+
+/// Whether a respondent may see and answer a prompt's form
+enum Access {
+    /// The prompt has no access code
+    Open,
+    /// The right code, as entered
+    Granted(String),
+    Missing,
+    Wrong,
+    /// Too many wrong codes from this client lately; even the right one is refused
+    Locked,
+}
+
+/// Check `given` against the prompt's access code, counting wrong guesses
+/// toward the client's lockout
+fn check_access(
+    state: &AppState,
+    prompt: &db::Prompt,
+    given: Option<&str>,
+    client: &str,
+) -> Access {
+    let Some(expected) = &prompt.access_code else {
+        return Access::Open;
+    };
+    if state.access_attempts.is_locked(&prompt.id, client) {
+        return Access::Locked;
+    }
+    match given.map(str::trim).filter(|code| !code.is_empty()) {
+        None => Access::Missing,
+        Some(code) if access::matches(expected, code) => {
+            state.access_attempts.reset(&prompt.id, client);
+            Access::Granted(code.to_string())
+        }
+        Some(_) => {
+            state.access_attempts.record_failure(&prompt.id, client);
+            Access::Wrong
+        }
+    }
+}
+
+/// The page asking for a prompt's access code
+fn access_page(
+    state: &AppState,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    status: StatusCode,
+    error: Option<String>,
+    prefill: Option<String>,
+    invite: Option<String>,
+) -> Response {
+    let template = FeedbackAccessTemplate {
+        layout: state.layout(),
+        prompt,
+        t,
+        error,
+        prefill,
+        invite,
+    };
+    (status, Html(template.render().unwrap())).into_response()
+}
+
+/// The code page again, saying why the code wasn't accepted
+fn access_refused(
+    state: &AppState,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    access: Access,
+    prefill: Option<String>,
+    invite: Option<String>,
+) -> Response {
+    let (status, error) = match access {
+        Access::Locked => (
+            StatusCode::TOO_MANY_REQUESTS,
+            Some(t.msg_with(
+                "access-locked",
+                "minutes",
+                &((access::LOCKOUT.as_secs() / 60) as i64),
+            )),
+        ),
+        Access::Wrong => (StatusCode::FORBIDDEN, Some(t.msg("access-wrong-code"))),
+        _ => (StatusCode::FORBIDDEN, None),
+    };
+    access_page(state, prompt, t, status, error, prefill, invite)
+}
+
+/// Shown instead of the form when an invite-only prompt is opened without a
+/// usable invite
+fn invite_refused(
+    state: &AppState,
+    prompt: db::Prompt,
+    t: &'static i18n::Catalog,
+    check: invites::Check,
+) -> Response {
+    let (status, key) = match check {
+        invites::Check::Redeemed => (StatusCode::GONE, "invite-used"),
+        invites::Check::Unknown => (StatusCode::NOT_FOUND, "invite-unknown"),
+        _ => (StatusCode::FORBIDDEN, "invite-required"),
+    };
+    let template = FeedbackInviteTemplate {
+        layout: state.layout(),
+        prompt,
+        t,
+        message: t.msg(key),
+    };
+    (status, Html(template.render().unwrap())).into_response()
+}
+
+/// Submissions are paused until the database takes writes again
+fn unavailable_page(state: &AppState) -> Response {
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        AppendHeaders([(header::RETRY_AFTER, limits::RETRY_AFTER_SECS.to_string())]),
+        Html(
+            FeedbackUnavailableTemplate {
+                layout: state.layout(),
+            }
+            .render()
+            .unwrap(),
+        ),
+    )
+        .into_response()
+}
+
+/// Shown in place of a suspended prompt, without any of its content
+fn suspended_page(state: &AppState) -> Response {
+    (
+        StatusCode::FORBIDDEN,
+        Html(
+            FeedbackSuspendedTemplate {
+                layout: state.layout(),
+            }
+            .render()
+            .unwrap(),
+        ),
+    )
+        .into_response()
+}
+
+#[derive(Deserialize)]
+struct AbuseReportForm {
+    reason: String,
+    #[serde(default)]
+    details: String,
+    #[serde(default)]
+    email: String,
+}
+
+/// Longest explanation accepted with an abuse report
+const MAX_REPORT_DETAILS_LEN: usize = 2000;
+
+async fn feedback_report_form(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match state.prompts.get(&state.pool, &id).await {
+        Ok(Some(prompt)) => {
+            let template = FeedbackReportTemplate {
+                layout: state.layout(),
+                prompt,
+                reasons: db::ABUSE_REASONS,
+                submitted: false,
+                error: None,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        _ => (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response(),
+    }
+}
+
+/// Public "report this form" submission. Each browser counts once per
+/// prompt; enough open reports suspend the prompt until an owner reviews it.
+async fn feedback_report_submit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+    Form(form): Form<AbuseReportForm>,
+) -> Response {
+    let prompt = match state.prompts.get(&state.pool, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response(),
+    };
+    if state.read_only.is_read_only() {
+        return unavailable_page(&state);
+    }
+
+    let email = form.email.trim().to_lowercase();
+    let details = form.details.trim();
+    let error = if !db::ABUSE_REASONS.iter().any(|(r, _)| *r == form.reason) {
+        Some("Please choose a reason".to_string())
+    } else if details.chars().count() > MAX_REPORT_DETAILS_LEN {
+        Some(format!(
+            "Details must be at most {} characters",
+            MAX_REPORT_DETAILS_LEN
+        ))
+    } else if !email.is_empty() && !is_plausible_email(&email) {
+        Some("Invalid email address".to_string())
+    } else {
+        None
+    };
+    if error.is_some() {
+        let template = FeedbackReportTemplate {
+            layout: state.layout(),
+            prompt,
+            reasons: db::ABUSE_REASONS,
+            submitted: false,
+            error,
+        };
+        return (StatusCode::BAD_REQUEST, Html(template.render().unwrap())).into_response();
+    }
+
+    let (reporter, set_cookie) = auth::voter_token(&headers);
+    let filed = db::create_abuse_report(
+        &state.pool,
+        &prompt.id,
+        &form.reason,
+        Some(details).filter(|d| !d.is_empty()),
+        Some(email.as_str()).filter(|e| !e.is_empty()),
+        &auth::token_digest(&reporter),
+    )
+    .await;
+    match filed {
+        Ok(Some(open)) if state.abuse_suspend_threshold > 0 => {
+            if open >= state.abuse_suspend_threshold && !prompt.is_suspended() {
+                if let Err(e) = db::suspend_prompt(&state.pool, &prompt.id).await {
+                    eprintln!("Suspending reported prompt {} failed: {}", prompt.id, e);
+                }
+                state.prompts.invalidate(&prompt.id);
+            }
+        }
+        // Already reported from this browser: thank them all the same
+        Ok(_) => {}
+        Err(_) => return Html("Error filing report".to_string()).into_response(),
+    }
+
+    let template = FeedbackReportTemplate {
+        layout: state.layout(),
+        prompt,
+        reasons: db::ABUSE_REASONS,
+        submitted: true,
+        error: None,
+    };
+    let page = Html(template.render().unwrap());
+    match set_cookie {
+        Some(cookie) => ([(header::SET_COOKIE, cookie)], page).into_response(),
+        None => page.into_response(),
+    }
+}
+
+async fn feedback_submit(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    form: Result<Form<FeedbackForm>, FormRejection>,
+) -> Response {
+    let Form(mut form) = match form {
+        Ok(form) => form,
+        Err(e) if e.status() == StatusCode::PAYLOAD_TOO_LARGE => {
+            return too_long_page(&state, &id, &headers).await;
+        }
+        Err(e) => return e.into_response(),
+    };
+    // Verify prompt exists
+    let mut prompt = match state.prompts.get(&state.pool, &id).await {
+        Ok(Some(p)) => p,
+        _ => return Html("Prompt not found".to_string()).into_response(),
+    };
+    let mut fields = match db::get_prompt_fields(&state.pool, &prompt.id).await {
+        Ok(fields) => fields,
+        Err(_) => return Html("Error submitting feedback".to_string()).into_response(),
+    };
+    let (t, languages) =
+        localize(&state, &mut prompt, &mut fields, Some(&form.lang), &headers).await;
+    if prompt.is_scheduled() {
+        return (StatusCode::FORBIDDEN, Html(t.msg("scheduled-message"))).into_response();
+    }
+    if !prompt.is_open() {
+        return Html(t.msg("closed-message")).into_response();
+    }
+    if is_full(&state, &prompt, Some(&form.prefill)).await {
+        return full_response(&state, prompt, &headers).await;
+    }
+    if state.read_only.is_read_only() {
+        return unavailable_page(&state);
+    }
+    let prefill = Some(form.prefill.clone()).filter(|t| !t.is_empty());
+    let client = client_address(&state, connect, &headers);
+    let invite = match invites::check(&state.pool, &prompt, Some(&form.invite)).await {
+        Ok(invites::Check::NotRequired) => None,
+        Ok(invites::Check::Outstanding(token)) => Some(token),
+        Ok(check) => return invite_refused(&state, prompt, t, check),
+        Err(_) => return Html("Error submitting feedback".to_string()).into_response(),
+    };
+    let access_code = match check_access(&state, &prompt, Some(&form.access_code), &client) {
+        Access::Open => None,
+        Access::Granted(code) => Some(code),
+        refused => return access_refused(&state, prompt, t, refused, prefill, invite),
+    };
+
+    let mut errors = validation::Errors::default();
+    let nps_score = if prompt.is_nps() {
+        let score = form.score.trim().parse().ok().filter(|s| nps::is_valid(*s));
+        if score.is_none() {
+            errors.add("score", t.msg("nps-score-required"));
+        }
+        score
+    } else {
+        None
+    };
+    let reaction = if prompt.is_reactions() {
+        let reaction = reactions::Reaction::parse(form.reaction.trim());
+        if reaction.is_none() {
+            errors.add("reaction", t.msg("reaction-required"));
+        }
+        reaction
+    } else {
+        None
+    };
+    // The comment is optional next to a score or reaction
+    if prompt.kind().comment_optional() && form.content.trim().is_empty() {
+        form.content = String::new();
+    } else {
+        match validation::feedback(&form.content, prompt.content_min_length) {
+            Ok(content) => {
+                if let Err(message) = prompt.content_rules().check(content) {
+                    errors.add("content", format!("Your feedback {}", message));
+                }
+                form.content = content.to_string();
+            }
+            Err(problem) => errors.add("content", feedback_problem(t, problem)),
+        }
+    }
+    let email = form.email.trim().to_lowercase();
+    if !email.is_empty() && !is_plausible_email(&email) {
+        errors.add("email", t.msg("form-invalid-email"));
+    }
+    let mut values = form.answers();
+    let Some(lock) = apply_prefill(&state, &prompt.id, Some(&form.prefill), &mut values).await
+    else {
+        return (StatusCode::BAD_REQUEST, Html(INVALID_PREFILL.to_string())).into_response();
+    };
+    let answers = collect_answers(&fields, &values).unwrap_or_else(|(field, message)| {
+        errors.add(
+            &format!("field_{}", field.id),
+            format!("{} {}", field.label, message),
+        );
+        Vec::new()
+    });
+    if !errors.is_empty() {
+        let mut locked: prefill::Values = lock
+            .fields
+            .iter()
+            .filter_map(|id| Some((id.clone(), values.get(id)?.clone())))
+            .collect();
+        if let Some(email) = &lock.email {
+            locked.insert(prefill::EMAIL.to_string(), email.clone());
+        }
+        let template = FeedbackFormTemplate {
+            layout: state.layout(),
+            prompt,
+            fields,
+            honeypot_field: spam::HONEYPOT_FIELD,
+            rendered_at: form.rendered_at.clone(),
+            captcha: state.captcha.clone(),
+            submission_token: form.submission_token.clone(),
+            receipts_enabled: state.receipts_enabled(),
+            prefill,
+            access_code,
+            invite,
+            locked,
+            t,
+            languages,
+            values: FeedbackValues {
+                content: form.content,
+                email: form.email,
+                score: form.score,
+                answers: values,
+            },
+            errors,
+        };
+        return invalid_submission(template, &headers);
+    }
+    let incoming = response_bytes(&form.content, &answers);
+    if let Err(e) = check_storage_quota(&state, &prompt.id, incoming).await {
+        return Html(e.message).into_response();
+    }
+
+    // A token that was already used means a double click or a refreshed
+    // POST, which gets the same answer as the first time
+    let submission_token = Some(form.submission_token.trim())
+        .filter(|t| !t.is_empty() && t.len() <= MAX_SUBMISSION_TOKEN_LEN);
+    if let Some(token) = submission_token {
+        match db::claim_submission_token(&state.pool, &prompt.id, token).await {
+            Ok(true) => {}
+            Ok(false) => return submission_success(&prompt, t, &headers),
+            Err(e) => {
+                state.read_only.observe(&e);
+                if state.read_only.is_read_only() {
+                    return unavailable_page(&state);
+                }
+                return Html("Error submitting feedback".to_string()).into_response();
+            }
+        }
+    }
+    // Only now, past the duplicate check, so a double click on an invited
+    // form is answered like the first click rather than as a used invite
+    if let Some(token) = &invite {
+        let redeemed = invites::redeem(&state.pool, &prompt.id, token).await;
+        if !matches!(redeemed, Ok(true)) {
+            if let Some(token) = submission_token {
+                let _ = db::release_submission_token(&state.pool, token).await;
+            }
+            return match redeemed {
+                Ok(_) => invite_refused(&state, prompt, t, invites::Check::Redeemed),
+                Err(_) => Html("Error submitting feedback".to_string()).into_response(),
+            };
+        }
+    }
+
+    let options = db::FeedbackOptions {
+        email_verified: lock.email.is_some(),
+        answers,
+        verified_fields: lock.fields,
+        nps_score,
+        reaction,
+        ..Default::default()
+    };
+    let email = lock.email.unwrap_or(email);
+    match accept_submission(&state, &prompt, &form, email, options).await {
+        Ok(()) => submission_success(&prompt, t, &headers),
+        Err(message) => {
+            // Let the respondent retry from the same form
+            if let Some(token) = submission_token {
+                let _ = db::release_submission_token(&state.pool, token).await;
+            }
+            if let Some(token) = &invite {
+                let _ = invites::release(&state.pool, &prompt.id, token).await;
+            }
+            if state.read_only.is_read_only() {
+                return unavailable_page(&state);
+            }
+            // Beaten to the last place by a concurrent submission
+            if is_full(&state, &prompt, Some(&form.prefill)).await {
+                return full_response(&state, prompt, &headers).await;
+            }
+            Html(message).into_response()
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct FeedbackEditForm {
+    content: String,
+}
+
+/// The prompt and entry an emailed edit link points at, or the page to show instead
+async fn editable_feedback(
+    state: &AppState,
+    prompt_id: &str,
+    token: &str,
+    headers: &HeaderMap,
+) -> Result<(db::Prompt, db::Feedback), Response> {
+    let not_found = || (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response();
+    let prompt = match state.prompts.get(&state.pool, prompt_id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return Err(not_found()),
+    };
+    let digest = auth::token_digest(token);
+    let feedback = match db::get_feedback_by_edit_token(&state.pool, &prompt.id, &digest).await {
+        Ok(Some(feedback)) => feedback,
+        _ => return Err(not_found()),
+    };
+    if prompt.is_suspended() {
+        return Err(suspended_page(state));
+    }
+    if !prompt.is_open() {
+        return Err(closed_page(state, prompt, headers).await);
+    }
+    Ok((prompt, feedback))
+}
+
+/// Page behind the edit link in a submission receipt
+async fn feedback_edit_form(
+    State(state): State<Arc<AppState>>,
+    Path((id, token)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    match editable_feedback(&state, &id, &token, &headers).await {
+        Ok((prompt, feedback)) => {
+            let template = FeedbackEditTemplate {
+                layout: state.layout(),
+                prompt,
+                feedback,
+                token,
+                notice: None,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        Err(response) => response,
+    }
+}
+
+async fn feedback_edit_submit(
+    State(state): State<Arc<AppState>>,
+    Path((id, token)): Path<(String, String)>,
+    headers: HeaderMap,
+    Form(form): Form<FeedbackEditForm>,
+) -> Response {
+    let (prompt, mut feedback) = match editable_feedback(&state, &id, &token, &headers).await {
+        Ok(found) => found,
+        Err(response) => return response,
+    };
+    if state.read_only.is_read_only() {
+        return unavailable_page(&state);
+    }
+
+    let content = validation::feedback(&form.content, prompt.content_min_length)
+        .map_err(|problem| problem.message())
+        .and_then(|content| prompt.content_rules().check(content).map(|()| content));
+    let notice = match content {
+        Err(message) => format!("Your feedback {}", message),
+        Ok(content) => {
+            let incoming = content.len() as i64 - feedback.content.len() as i64;
+            match check_storage_quota(&state, &prompt.id, incoming).await {
+                Err(e) => e.message,
+                Ok(()) => match db::edit_feedback(&state.pool, &mut feedback, content).await {
+                    Ok(()) => "Your changes have been saved.".to_string(),
+                    Err(_) => "Error saving your changes".to_string(),
+                },
+            }
+        }
+    };
+    let template = FeedbackEditTemplate {
+        layout: state.layout(),
+        prompt,
+        feedback,
+        token,
+        notice: Some(notice),
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+/// Public list of a prompt's feedback, most upvoted first; only for prompts
+/// with the public board turned on
+async fn feedback_board(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    headers: HeaderMap,
+) -> Response {
+    let prompt = match state.prompts.get(&state.pool, &id).await {
+        Ok(Some(prompt)) if prompt.public_board && !prompt.is_suspended() => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response(),
+    };
+
+    let (voter, set_cookie) = auth::voter_token(&headers);
+    let entries = db::get_board(&state.pool, &prompt.id, Some(&auth::token_digest(&voter)))
+        .await
+        .unwrap_or_default();
+    let page = Html(
+        FeedbackBoardTemplate {
+            layout: state.layout(),
+            prompt,
+            entries,
+        }
+        .render()
+        .unwrap(),
+    );
+    match set_cookie {
+        Some(cookie) => ([(header::SET_COOKIE, cookie)], page).into_response(),
+        None => page.into_response(),
+    }
+}
+
+/// Upvote an entry on a public board, returning its updated vote button.
+/// Votes are counted once per voter cookie.
+async fn feedback_vote(
+    State(state): State<Arc<AppState>>,
+    Path((id, feedback_id)): Path<(String, String)>,
+    headers: HeaderMap,
+) -> Response {
+    let not_found = || (StatusCode::NOT_FOUND, Html("Not found".to_string())).into_response();
+    let prompt = match state.prompts.get(&state.pool, &id).await {
+        Ok(Some(prompt)) if prompt.public_board && !prompt.is_suspended() => prompt,
+        _ => return not_found(),
+    };
+    if !prompt.is_open() {
+        return (
+            StatusCode::CONFLICT,
+            Html("This prompt is no longer accepting votes".to_string()),
+        )
+            .into_response();
+    }
+    if state.read_only.is_read_only() {
+        return unavailable_page(&state);
+    }
+
+    // A browser that skipped the board page gets its cookie here instead
+    let (voter, set_cookie) = auth::voter_token(&headers);
+    let votes = match db::add_vote(
+        &state.pool,
+        &prompt.id,
+        &feedback_id,
+        &auth::token_digest(&voter),
+    )
+    .await
+    {
+        Ok(Some(votes)) => votes,
+        Ok(None) => return not_found(),
+        Err(_) => {
+            return (
+                StatusCode::INTERNAL_SERVER_ERROR,
+                Html("Error recording vote".to_string()),
+            )
+                .into_response()
+        }
+    };
+    let entry = VoteCount {
+        id: feedback_id,
+        votes,
+        voted: true,
+    };
+    let button = Html(
+        VoteButtonPartialTemplate { prompt, entry }
+            .render()
+            .unwrap(),
+    );
+    match set_cookie {
+        Some(cookie) => ([(header::SET_COOKIE, cookie)], button).into_response(),
+        None => button.into_response(),
+    }
+}
+
+/// Where a respondent ends up after submitting: the prompt's redirect if it
+/// has one, else the success partial, carrying a fresh token so the reset
+/// form can be used again
+fn submission_success(
+    prompt: &db::Prompt,
+    t: &'static i18n::Catalog,
+    headers: &HeaderMap,
+) -> Response {
+    if let Some(url) = &prompt.redirect_url {
+        // htmx would swap a followed redirect into the form; ask it to
+        // navigate instead
+        if headers.contains_key("hx-request") {
+            return [("HX-Redirect", url.as_str())].into_response();
+        }
+        return Redirect::to(url).into_response();
+    }
+    let template = FeedbackSuccessPartialTemplate {
+        submission_token: auth::random_token(),
+        message: prompt.thank_you_message.clone(),
+        t,
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+/// A problem with the feedback text, in the respondent's language
+fn feedback_problem(t: &i18n::Catalog, problem: validation::Problem) -> String {
+    match problem {
+        validation::Problem::Required => t.msg("form-feedback-required"),
+        validation::Problem::TooShort(min) => {
+            t.msg_with("form-feedback-too-short", "min", &(min as i64))
+        }
+        validation::Problem::TooLong(max) => {
+            t.msg_with("form-feedback-too-long", "max", &(max as i64))
+        }
+    }
+}
+
+/// The answer to a form post over the body size limit: the form's own
+/// length limit, rather than a bare 413
+async fn too_long_page(state: &AppState, id: &str, headers: &HeaderMap) -> Response {
+    let mut prompt = match state.prompts.get(&state.pool, id).await {
+        Ok(Some(p)) => p,
+        _ => return Html("Prompt not found".to_string()).into_response(),
+    };
+    let (t, _) = localize(state, &mut prompt, &mut [], None, headers).await;
+    let message = t.msg_with("too-long-message", "max", &feedback_max_length(&prompt));
+    // htmx only swaps in successful responses
+    if headers.contains_key("hx-request") {
+        let mut response = Html(message).into_response();
+        response.extensions_mut().insert(limits::Oversized);
+        return response;
+    }
+    let template = FeedbackTooLongTemplate {
+        layout: state.layout(),
+        prompt,
+        t,
+        message,
+    };
+    (
+        StatusCode::PAYLOAD_TOO_LARGE,
+        Html(template.render().unwrap()),
+    )
+        .into_response()
+}
+
+/// The feedback form again, with the submission's answers and what's wrong
+/// with them. htmx would put the page in the result area below the form, so
+/// it's told to swap the new form in place of the old one instead.
+fn invalid_submission(template: FeedbackFormTemplate, headers: &HeaderMap) -> Response {
+    let page = Html(template.render().unwrap());
+    if headers.contains_key("hx-request") {
+        return (
+            [
+                ("HX-Retarget", "#feedback-form"),
+                ("HX-Reswap", "outerHTML"),
+                ("HX-Reselect", "#feedback-form"),
+            ],
+            page,
+        )
+            .into_response();
+    }
+    (StatusCode::UNPROCESSABLE_ENTITY, page).into_response()
+}
+
+/// Bytes a submission adds to its prompt's storage: the text and every answer
+fn response_bytes(content: &str, answers: &[(String, String)]) -> i64 {
+    (content.len() + answers.iter().map(|(_, value)| value.len()).sum::<usize>()) as i64
+}
+
+/// Refuse a write of `incoming` bytes that would take the prompt or its
+/// workspace past their storage quota
+async fn check_storage_quota(
+    state: &AppState,
+    prompt_id: &str,
+    incoming: i64,
+) -> Result<(), ApiError> {
+    if state.storage_quotas.is_unlimited() || incoming <= 0 {
+        return Ok(());
+    }
+    let usage = db::get_storage_usage(&state.pool, prompt_id).await?;
+    state
+        .storage_quotas
+        .check(&usage, incoming)
+        .map_err(|message| {
+            ApiError::new(
+                StatusCode::INSUFFICIENT_STORAGE,
+                "storage_quota_exceeded",
+                message,
+            )
+        })
+}
+
+/// Check the CAPTCHA, screen for spam and store a form submission; errors are
+/// messages for the respondent
+async fn accept_submission(
+    state: &AppState,
+    prompt: &db::Prompt,
+    form: &FeedbackForm,
+    email: String,
+    mut options: db::FeedbackOptions,
+) -> Result<(), String> {
+    if let Some(config) = &state.captcha {
+        let token = form.captcha_token(config.provider);
+        match captcha::verify(&state.http_client, config, token).await {
+            Ok(true) => {}
+            Ok(false) => return Err("Please complete the CAPTCHA".to_string()),
+            Err(e) => {
+                eprintln!("CAPTCHA verification failed: {}", e);
+                return Err("Could not verify the CAPTCHA, please try again".to_string());
+            }
+        }
+    }
+
+    options.respondent_email = Some(email).filter(|e| !e.is_empty());
+    let rendered_at = match db::get_prefill_secret(&state.pool, &prompt.id).await {
+        Ok(Some(secret)) => spam::stamped_at(&secret, &prompt.id, &form.rendered_at),
+        _ => None,
+    };
+    options.spam_reason = state.spam.check_form(
+        &form.website,
+        rendered_at,
+        chrono::Utc::now().timestamp(),
+        &form.content,
+    );
+    // Spam gets the same response so bots can't tell they were caught
+    record_feedback(state, prompt, &form.content, options)
+        .await
+        .map(|_| ())
+        .map_err(|e| {
+            state.read_only.observe(&e);
+            "Error submitting feedback".to_string()
+        })
+}
+
+/// Store a submission in the prompt's current cycle, then fire its receipt
+/// webhook, email the respondent their copy and notify live admin views.
+/// Shared by the form and JSON API. Spam is quarantined quietly: it never
+/// replaces a merged response, fires a receipt, or shows up live. The
+/// emailed copy's edit link is built from the configured base URL, never
+/// from request headers, so it isn't sent until one is set.
+/// The entry goes in the current cycle whatever `options` says.
+async fn record_feedback(
+    state: &AppState,
+    prompt: &db::Prompt,
+    content: &str,
+    mut options: db::FeedbackOptions,
+) -> Result<db::Feedback, sqlx::Error> {
+    let cycle = db::current_cycle(&state.pool, prompt, chrono::Utc::now()).await?;
+    options.cycle_id = cycle.map(|c| c.id);
+    if options.spam_reason.is_some() {
+        return db::create_feedback_with_options(&state.pool, &prompt.id, content, &options).await;
+    }
+    let feedback = if prompt.merge_responses && options.respondent_email.is_some() {
+        db::replace_respondent_feedback(&state.pool, &prompt.id, content, &options).await?
+    } else {
+        db::create_feedback_with_options(&state.pool, &prompt.id, content, &options).await?
+    };
+
+    webhook::spawn_receipt(state.webhooks.clone(), prompt, &feedback);
+    let base_url = state.settings().base_url;
+    if let (true, Some(mailer), Some(base_url)) = (prompt.send_receipts, &state.mailer, base_url) {
+        spawn_receipt_email(
+            state.pool.clone(),
+            mailer.clone(),
+            prompt,
+            &feedback,
+            &base_url,
+        );
+    }
+    notify::spawn_dispatch(
+        state.pool.clone(),
+        state.http_client.clone(),
+        prompt,
+        &feedback,
+    );
+    // No subscribers just means nobody is watching the detail page
+    let _ = state.feedback_events.send(feedback.clone());
+    Ok(feedback)
+}
+
+/// Mail a respondent who left an email a copy of their submission, with a
+/// fresh link that lets them edit it while the prompt stays open
+fn spawn_receipt_email(
+    pool: SqlitePool,
+    mailer: email::Mailer,
+    prompt: &db::Prompt,
+    feedback: &db::Feedback,
+    origin: &str,
+) {
+    let Some(to) = feedback.respondent_email.clone() else {
+        return;
+    };
+    let token = auth::random_token();
+    let edit_url = format!("{}/feedback/{}/edit/{}", origin, prompt.id, token);
+    let subject = format!("Your feedback on \u{201c}{}\u{201d}", prompt.title);
+    let body = receipt_email_body(prompt, feedback, &edit_url);
+    let feedback_id = feedback.id.clone();
+    tokio::spawn(async move {
+        if let Err(e) = db::set_edit_token(&pool, &feedback_id, &auth::token_digest(&token)).await {
+            eprintln!("Saving edit token for {} failed: {}", feedback_id, e);
+            return;
+        }
+        if let Err(e) = bounces::send(&pool, &mailer, &to, &subject, &body).await {
+            eprintln!("Emailing receipt for {} failed: {}", feedback_id, e);
+        }
+    });
+}
+
+fn receipt_email_body(prompt: &db::Prompt, feedback: &db::Feedback, edit_url: &str) -> String {
+    let mut body = format!(
+        "Thanks for your feedback on \u{201c}{}\u{201d}. Here's a copy of what you sent.\n\nReference: {}\n\n{}\n",
+        prompt.title, feedback.id, feedback.content
+    );
+    for answer in &feedback.answers {
+        body.push_str(&format!("\n{}: {}", answer.label, answer.value));
+    }
+    body.push_str(&format!(
+        "\n\nTo change your answer while the prompt is open, visit:\n{}\n",
+        edit_url
+    ));
+    body
+}
+
+/// Loose sanity check; respondents are identified by email, not authenticated by it
+fn is_plausible_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            !local.is_empty() && domain.contains('.') && !email.contains(char::is_whitespace)
+        }
+        None => false,
+    }
+}
+
+#[derive(Deserialize)]
+struct TagForm {
+    name: String,
+}
+
+/// Tag routes act on a feedback ID alone, so confirm it's in the workspace first
+async fn workspace_feedback(
+    state: &AppState,
+    workspace: &db::Workspace,
+    feedback_id: &str,
+) -> Result<db::Feedback, Response> {
+    match db::get_feedback_by_id(&state.pool, &workspace.id, feedback_id).await {
+        Ok(Some(fb)) => Ok(fb),
+        _ => Err((
+            StatusCode::NOT_FOUND,
+            Html("Feedback not found".to_string()),
+        )
+            .into_response()),
+    }
+}
+
+async fn admin_add_tag(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, feedback_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    Form(form): Form<TagForm>,
+) -> impl IntoResponse {
+    if let Err(response) = workspace_feedback(&state, &workspace, &feedback_id).await {
+        return response;
+    }
+    let Some(name) = db::normalize_tag(&form.name) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Tags may only contain letters, numbers, spaces, '-' and '_'".to_string()),
+        )
+            .into_response();
+    };
+    if db::add_tag(&state.pool, &feedback_id, &name).await.is_err() {
+        return Html("Error saving tag".to_string()).into_response();
+    }
+    render_feedback_tags(&state, workspace, &feedback_id, &headers).await
+}
+
+fn entries(count: u64) -> &'static str {
+    if count == 1 {
+        "entry"
+    } else {
+        "entries"
+    }
+}
+
+/// The prompt page's bulk action bar. Checked entries arrive as
+/// `select_<id>` inputs and `action` says what to do with them: `delete`,
+/// `tag` (with `tag`) or `export` as CSV.
+async fn admin_bulk_feedback(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Form(form): Form<HashMap<String, String>>,
+) -> Response {
+    let prompt = match db::get_workspace_prompt(&state.pool, &workspace.id, &id).await {
+        Ok(Some(prompt)) => prompt,
+        _ => return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response(),
+    };
+    let ids: Vec<String> = form
+        .keys()
+        .filter_map(|key| key.strip_prefix("select_"))
+        .map(str::to_string)
+        .collect();
+    if ids.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Select at least one entry".to_string()),
+        )
+            .into_response();
+    }
+    let back = format!("/admin/{}/prompt/{}", workspace.slug, prompt.id);
+
+    match form.get("action").map(String::as_str) {
+        Some("delete") => match db::delete_feedback_bulk(&state.pool, &workspace.id, &ids).await {
+            Ok(deleted) => flash::redirect(
+                &back,
+                flash::Flash::success(format!("Deleted {} {}", deleted, entries(deleted))),
+            ),
+            Err(_) => flash::redirect(&back, flash::Flash::error("Failed to delete feedback")),
+        },
+        Some("tag") => {
+            let Some(name) = form.get("tag").and_then(|name| db::normalize_tag(name)) else {
+                return (
+                    StatusCode::BAD_REQUEST,
+                    Html("Tags may only contain letters, numbers, spaces, '-' and '_'".to_string()),
+                )
+                    .into_response();
+            };
+            match db::add_tag_bulk(&state.pool, &workspace.id, &ids, &name).await {
+                Ok(tagged) => flash::redirect(
+                    &back,
+                    flash::Flash::success(format!(
+                        "Tagged {} {} \"{}\"",
+                        tagged,
+                        entries(tagged),
+                        name
+                    )),
+                ),
+                Err(_) => flash::redirect(&back, flash::Flash::error("Failed to save the tag")),
+            }
+        }
+        Some("export") => match archive::export(&state.pool, &workspace.id, None).await {
+            Ok(mut archive) => {
+                archive
+                    .feedback
+                    .retain(|f| f.prompt_id == prompt.id && ids.contains(&f.id));
+                let sheet = export::sheet(
+                    &archive,
+                    &export::default_columns(&archive.fields),
+                    export::DEFAULT_DATE_FORMAT,
+                );
+                let filename = format!(
+                    "feedback-selection-{}.csv",
+                    chrono::Utc::now().format("%Y%m%d-%H%M%S")
+                );
+                (
+                    [
+                        (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                        (
+                            header::CONTENT_DISPOSITION,
+                            format!("attachment; filename=\"{}\"", filename),
+                        ),
+                    ],
+                    sheet.to_csv(),
+                )
+                    .into_response()
+            }
+            Err(_) => Html("Error exporting feedback".to_string()).into_response(),
+        },
+        _ => (StatusCode::BAD_REQUEST, Html("Unknown action".to_string())).into_response(),
+    }
+}
+
+async fn admin_remove_tag(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, feedback_id, name)): Path<(String, String, String)>,
+    headers: HeaderMap,
+) -> impl IntoResponse {
+    if let Err(response) = workspace_feedback(&state, &workspace, &feedback_id).await {
+        return response;
+    }
+    if db::remove_tag(&state.pool, &feedback_id, &name)
+        .await
+        .is_err()
+    {
+        return Html("Error removing tag".to_string()).into_response();
+    }
+    render_feedback_tags(&state, workspace, &feedback_id, &headers).await
+}
+
+/// Tag changes come from htmx, which swaps in the entry's updated tag chips;
+/// plain form posts are sent back to the prompt page instead
+async fn render_feedback_tags(
+    state: &AppState,
+    workspace: db::Workspace,
+    feedback_id: &str,
+    headers: &HeaderMap,
+) -> Response {
+    let fb = match workspace_feedback(state, &workspace, feedback_id).await {
+        Ok(fb) => fb,
+        Err(response) => return response,
+    };
+
+    if headers.contains_key("hx-request") {
+        let template = FeedbackTagsPartialTemplate { workspace, fb };
+        Html(template.render().unwrap()).into_response()
+    } else {
+        Redirect::to(&format!(
+            "/admin/{}/prompt/{}",
+            workspace.slug, fb.prompt_id
+        ))
+        .into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct FeedbackStatusForm {
+    status: String,
+}
+
+/// Move an entry through triage (new, reviewed, actioned, dismissed)
+async fn admin_set_feedback_status(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, feedback_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    Form(form): Form<FeedbackStatusForm>,
+) -> Response {
+    let mut fb = match workspace_feedback(&state, &workspace, &feedback_id).await {
+        Ok(fb) => fb,
+        Err(response) => return response,
+    };
+    let Some(status) = db::FeedbackStatus::parse(&form.status) else {
+        return (StatusCode::BAD_REQUEST, Html("Invalid status".to_string())).into_response();
+    };
+    if db::set_feedback_status(&state.pool, &fb.id, status)
+        .await
+        .is_err()
+    {
+        return Html("Error saving status".to_string()).into_response();
+    }
+    fb.status = status.as_str().to_string();
+
+    if headers.contains_key("hx-request") {
+        let template = FeedbackStatusPartialTemplate { workspace, fb };
+        Html(template.render().unwrap()).into_response()
+    } else {
+        Redirect::to(&format!(
+            "/admin/{}/prompt/{}",
+            workspace.slug, fb.prompt_id
+        ))
+        .into_response()
+    }
+}
+
+#[derive(Deserialize)]
+struct ReplyForm {
+    content: String,
+    send_email: Option<String>,
+}
+
+async fn admin_reply(
+    RequireEditor(admin): RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, feedback_id)): Path<(String, String)>,
+    headers: HeaderMap,
+    Form(form): Form<ReplyForm>,
+) -> Response {
+    let fb = match workspace_feedback(&state, &workspace, &feedback_id).await {
+        Ok(fb) => fb,
+        Err(response) => return response,
+    };
+    let content = form.content.trim();
+    if content.is_empty() {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Reply must not be empty".to_string()),
+        )
+            .into_response();
+    }
+
+    let author = admin.user.as_ref().map(|user| user.email.as_str());
+    let reply = match db::create_reply(&state.pool, &fb.id, author, content).await {
+        Ok(reply) => reply,
+        Err(_) => return Html("Error saving reply".to_string()).into_response(),
+    };
+
+    // The reply is kept either way; its missing "emailed" mark shows a failed send
+    if let (true, Some(mailer), Some(to)) = (
+        form.send_email.is_some(),
+        &state.mailer,
+        &fb.respondent_email,
+    ) {
+        let title = db::get_prompt_by_id(&state.pool, &fb.prompt_id)
+            .await
+            .ok()
+            .flatten()
+            .map(|p| p.title)
+            .unwrap_or_default();
+        let subject = format!("Re: your feedback on \u{201c}{}\u{201d}", title);
+        match bounces::send(
+            &state.pool,
+            mailer,
+            to,
+            &subject,
+            &reply_email_body(&fb, content),
+        )
+        .await
+        {
+            Ok(()) => {
+                let _ = db::mark_reply_emailed(&state.pool, &reply.id).await;
+            }
+            Err(e) => eprintln!("Emailing reply {} failed: {}", reply.id, e),
+        }
+    }
+
+    if headers.contains_key("hx-request") {
+        let fb = match workspace_feedback(&state, &workspace, &feedback_id).await {
+            Ok(fb) => fb,
+            Err(response) => return response,
+        };
+        let template = FeedbackRepliesPartialTemplate {
+            workspace,
+            fb,
+            email_enabled: state.mailer.is_some(),
+        };
+        Html(template.render().unwrap()).into_response()
+    } else {
+        Redirect::to(&format!(
+            "/admin/{}/prompt/{}",
+            workspace.slug, fb.prompt_id
+        ))
+        .into_response()
+    }
+}
+
+/// The reply, followed by the respondent's feedback quoted
+fn reply_email_body(fb: &db::Feedback, reply: &str) -> String {
+    let quoted: Vec<String> = fb
+        .content
+        .lines()
+        .map(|line| format!("> {}", line))
+        .collect();
+    format!(
+        "{}\n\nYou wrote on {}:\n{}\n",
+        reply,
+        fb.created_at,
+        quoted.join("\n")
+    )
+}
+
+async fn api_delete_prompt(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<StatusCode, ApiError> {
+    if !db::delete_prompt(&state.pool, &workspace.id, &id).await? {
+        return Err(ApiError::not_found("Prompt not found"));
+    }
+    state.prompts.invalidate(&id);
+    Ok(StatusCode::OK)
+}
+
+async fn api_get_feedback(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    if db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::not_found("Prompt not found"));
+    }
+    let mut feedback_list = db::get_feedback_for_prompt(&state.pool, &id).await?;
+    if state.detect_duplicates {
+        db::attach_duplicates(&state.pool, &mut feedback_list).await?;
+    }
+
+    let template = FeedbackListPartialTemplate {
+        workspace,
+        feedback_list,
+        email_enabled: state.mailer.is_some(),
+    };
+    Ok(Html(template.render().unwrap()))
+}
+
+#[derive(Deserialize)]
+struct SinceQuery {
+    since: Option<String>,
+}
+
+impl SinceQuery {
+    /// The normalized `since` timestamp
+    fn parse(&self) -> Result<Option<String>, FieldError> {
+        match self.since.as_deref() {
+            None | Some("") => Ok(None),
+            Some(value) => db::parse_since(value).map(Some).ok_or_else(|| {
+                field_error("since", "must be an RFC 3339 timestamp or YYYY-MM-DD date")
+            }),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    since: Option<String>,
+    /// `json` (the default, an importable archive), `jsonl`, `csv` or `xlsx`
+    format: Option<String>,
+    /// Export template shaping a csv or xlsx export; without one, every
+    /// column is included
+    template: Option<String>,
+}
+
+async fn admin_export(
+    RequireViewer(admin): RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Query(query): Query<ExportQuery>,
+) -> Result<Response, ApiError> {
+    let format = match query.format.as_deref() {
+        None | Some("") => "json",
+        Some(format @ ("json" | "jsonl" | "csv" | "xlsx")) => format,
+        Some(_) => {
+            return Err(ApiError::validation(vec![field_error(
+                "format",
+                "must be json, jsonl, csv or xlsx",
+            )]))
+        }
+    };
+    // The full archive carries webhook secrets and access codes
+    if format == "json" && admin.role < db::Role::Owner {
+        return Err(ApiError::forbidden());
+    }
+    let spreadsheet = matches!(format, "csv" | "xlsx");
+    let template = match query.template.as_deref().filter(|t| !t.is_empty()) {
+        None => None,
+        Some(_) if !spreadsheet => {
+            return Err(ApiError::validation(vec![field_error(
+                "template",
+                "only applies to csv and xlsx exports",
+            )]))
+        }
+        Some(id) => Some(
+            db::get_export_template(&state.pool, &workspace.id, id)
+                .await?
+                .ok_or_else(|| ApiError::not_found("Export template not found"))?,
+        ),
+    };
+    let since = SinceQuery { since: query.since }
+        .parse()
+        .map_err(|e| ApiError::validation(vec![e]))?;
+    let archive = archive::export(&state.pool, &workspace.id, since.as_deref()).await?;
+
+    let filename = format!(
+        "feedback-export-{}-{}.{}",
+        workspace.slug,
+        chrono::Utc::now().format("%Y%m%d-%H%M%S"),
+        format
+    );
+    let disposition = (
+        header::CONTENT_DISPOSITION,
+        format!("attachment; filename=\"{}\"", filename),
+    );
+    let (content_type, body) = match format {
+        "jsonl" => (
+            "application/x-ndjson",
+            archive.to_jsonl(&workspace.slug).into_bytes(),
+        ),
+        "csv" | "xlsx" => {
+            let sheet = match &template {
+                Some(t) => export::sheet(&archive, &t.columns, &t.date_format),
+                None => export::sheet(
+                    &archive,
+                    &export::default_columns(&archive.fields),
+                    export::DEFAULT_DATE_FORMAT,
+                ),
+            };
+            if format == "csv" {
+                ("text/csv; charset=utf-8", sheet.to_csv().into_bytes())
+            } else {
+                (
+                    "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+                    sheet.to_xlsx(),
+                )
+            }
+        }
+        _ => return Ok(([disposition], Json(archive)).into_response()),
+    };
+    Ok((
+        [
+            (header::CONTENT_TYPE, content_type.to_string()),
+            disposition,
+        ],
+        body,
+    )
+        .into_response())
+}
+
+/// Stream a consistent snapshot of the live database
+async fn admin_backup(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+) -> Result<impl IntoResponse, ApiError> {
+    let path = std::env::temp_dir().join(format!("feedback-backup-{}.db", uuid::Uuid::new_v4()));
+    backup::snapshot_to(&state.pool, &path).await?;
+
+    let file = tokio::fs::File::open(&path).await.map_err(|e| {
+        eprintln!("Backup failed: {}", e);
+        ApiError::internal()
+    })?;
+    // The open handle keeps the data readable after the directory entry is gone
+    let _ = tokio::fs::remove_file(&path).await;
+
+    let filename = format!(
+        "feedback-{}.db",
+        chrono::Utc::now().format("%Y%m%dT%H%M%SZ")
+    );
+    Ok((
+        [
+            (header::CONTENT_TYPE, "application/vnd.sqlite3".to_string()),
+            (
+                header::CONTENT_DISPOSITION,
+                format!("attachment; filename=\"{}\"", filename),
+            ),
+        ],
+        Body::from_stream(tokio_util::io::ReaderStream::new(file)),
+    ))
+}
+
+#[derive(Deserialize)]
+struct ImportQuery {
+    on_conflict: Option<String>,
+}
+
+async fn admin_import(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    query: Result<Query<ImportQuery>, QueryRejection>,
+    archive: Result<Json<archive::Archive>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Query(query) = query.map_err(ApiError::from)?;
+    let Json(archive) = archive.map_err(ApiError::from)?;
+
+    let mode = match query.on_conflict.as_deref() {
+        None => archive::ConflictMode::default(),
+        Some(value) => archive::ConflictMode::parse(value).ok_or_else(|| {
+            ApiError::validation(vec![FieldError {
+                field: "on_conflict".to_string(),
+                message: "must be abort, skip or overwrite".to_string(),
+            }])
+        })?,
+    };
+
+    match archive::import(&state.pool, &workspace.id, &archive, mode).await {
+        Ok(summary) => {
+            state.prompts.clear();
+            Ok(Json(summary))
+        }
+        Err(archive::ImportError::Conflicts(ids)) => Err(ApiError::conflict(
+            "IDs in the archive already exist; retry with on_conflict=skip or on_conflict=overwrite",
+        )
+        .with_details(serde_json::json!({ "conflicting_ids": ids }))),
+        Err(archive::ImportError::ForeignIds(ids)) => Err(ApiError::conflict(
+            "IDs in the archive belong to another workspace",
+        )
+        .with_details(serde_json::json!({ "conflicting_ids": ids }))),
+        Err(archive::ImportError::UnknownPrompt(id)) => Err(ApiError::bad_request(format!(
+            "Archive references prompt {} which is not in the archive or this workspace",
+            id
+        ))),
+        Err(archive::ImportError::UnknownField(id)) => Err(ApiError::bad_request(format!(
+            "Archive references field {} which is not in the archive or this workspace",
+            id
+        ))),
+        Err(archive::ImportError::UnsupportedVersion(version)) => Err(ApiError::bad_request(
+            format!("Unsupported archive version {}", version),
+        )),
+        Err(archive::ImportError::Database(e)) => Err(e.into()),
+    }
+}
+
+#[derive(Deserialize)]
+struct ApiNewPrompt {
+    title: String,
+    #[serde(default)]
+    description: String,
+    webhook_url: Option<String>,
+    #[serde(default)]
+    merge_responses: bool,
+    recurrence: Option<String>,
+    content_min_length: Option<i64>,
+    content_max_length: Option<i64>,
+    content_pattern: Option<String>,
+    #[serde(default)]
+    public_board: bool,
+    #[serde(default)]
+    send_receipts: bool,
+    /// Fixed language for the public form; unset follows the browser
+    language: Option<String>,
+    /// Short URL path segment, served at `/f/:slug`
+    slug: Option<String>,
+    /// Markdown shown after a submission
+    thank_you_message: Option<String>,
+    /// Where respondents are sent after submitting
+    redirect_url: Option<String>,
+    /// Code respondents must enter before the form takes their feedback
+    access_code: Option<String>,
+    /// RFC 3339 time the form opens at
+    publish_at: Option<String>,
+    /// `text` (the default), `nps` or `reactions`
+    kind: Option<String>,
+    /// Days to keep feedback; the site-wide period still applies if shorter
+    retention_days: Option<i64>,
+    /// Stop taking responses once this many are in
+    max_responses: Option<i64>,
+}
+
+#[derive(Deserialize)]
+struct ApiNewFeedback {
+    /// Optional on NPS and reactions prompts, where it's a comment
+    #[serde(default)]
+    content: String,
+    email: Option<String>,
+    /// The 0-10 answer; required on NPS prompts and ignored elsewhere
+    score: Option<i64>,
+    /// A `reactions::Reaction` key; required on reactions prompts and
+    /// ignored elsewhere
+    reaction: Option<String>,
+    /// Question bank answers keyed by field ID
+    #[serde(default)]
+    answers: HashMap<String, String>,
+    /// Token from a signed prefill link; the answers it locks win
+    prefill: Option<String>,
+    /// Required when the prompt has an access code
+    access_code: Option<String>,
+    /// Single-use invite token; required once the prompt has invites
+    invite: Option<String>,
+    /// The solved challenge's response token, required when the instance
+    /// has a CAPTCHA configured
+    #[serde(default)]
+    captcha_token: String,
+}
+
+fn field_error(field: &str, message: &str) -> FieldError {
+    FieldError {
+        field: field.to_string(),
+        message: message.to_string(),
+    }
+}
+
+async fn api_v1_create_prompt(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    body: Result<Json<ApiNewPrompt>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(body) = body.map_err(ApiError::from)?;
+
+    let mut fields = Vec::new();
+    let title = validation::title(&body.title).unwrap_or_else(|problem| {
+        fields.push(field_error("title", &problem.message()));
+        ""
+    });
+    let description = validation::description(&body.description, false).unwrap_or_else(|problem| {
+        fields.push(field_error("description", &problem.message()));
+        ""
+    });
+    let webhook_url = body
+        .webhook_url
+        .as_deref()
+        .map(str::trim)
+        .filter(|u| !u.is_empty());
+    if webhook_url.is_some_and(|u| !state.webhooks.accepts(u)) {
+        fields.push(field_error(
+            "webhook_url",
+            "must be a public http or https URL",
+        ));
+    }
+    let recurrence = match body.recurrence.as_deref() {
+        None | Some("") => None,
+        Some(value) => {
+            let parsed = db::Recurrence::parse(value);
+            if parsed.is_none() {
+                fields.push(field_error("recurrence", "must be weekly or monthly"));
+            }
+            parsed
+        }
+    };
+    let content_rules = rules::FieldRules {
+        min_length: body.content_min_length,
+        max_length: body.content_max_length,
+        pattern: body.content_pattern.filter(|p| !p.trim().is_empty()),
+    };
+    if let Err((field, message)) = content_rules.validate() {
+        fields.push(field_error(&format!("content_{}", field), &message));
+    }
+    let language = body.language.filter(|lang| !lang.is_empty());
+    if language
+        .as_deref()
+        .is_some_and(|lang| !i18n::is_supported(lang))
+    {
+        fields.push(field_error("language", "is not a supported language"));
+    }
+    let slug = match body.slug.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(value) => {
+            let normalized = db::normalize_prompt_slug(value);
+            if normalized.is_none() {
+                fields.push(field_error(
+                    "slug",
+                    "must be letters, numbers and hyphens, up to 40 characters",
+                ));
+            }
+            normalized
+        }
+    };
+    let thank_you_message = body
+        .thank_you_message
+        .map(|m| m.trim().to_string())
+        .filter(|m| !m.is_empty());
+    let redirect_url = body
+        .redirect_url
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty());
+    if redirect_url
+        .as_deref()
+        .is_some_and(|u| !webhook::is_valid_url(u))
+    {
+        fields.push(field_error(
+            "redirect_url",
+            "must be a public http or https URL",
+        ));
+    }
+    let access_code = match body.access_code.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(value) => {
+            let normalized = access::normalize(value);
+            if normalized.is_none() {
+                fields.push(field_error(
+                    "access_code",
+                    "must be 4 to 64 letters, numbers, '-' or '_'",
+                ));
+            }
+            normalized
+        }
+    };
+    let publish_at = match body.publish_at.as_deref().map(str::trim) {
+        None | Some("") => None,
+        Some(value) => {
+            let parsed = db::parse_publish_at(value);
+            if parsed.is_none() {
+                fields.push(field_error("publish_at", "must be an RFC 3339 timestamp"));
+            }
+            parsed
+        }
+    };
+    let kind = match body.kind.as_deref().map(str::trim) {
+        None | Some("") => db::PromptKind::default(),
+        Some(value) => db::PromptKind::parse(value).unwrap_or_else(|| {
+            fields.push(field_error("kind", "must be text, nps or reactions"));
+            db::PromptKind::default()
+        }),
+    };
+    if body
+        .retention_days
+        .is_some_and(|days| !retention::is_valid_days(days))
+    {
+        fields.push(field_error(
+            "retention_days",
+            "must be a whole number of days from 1 to 3650",
+        ));
+    }
+    if body.max_responses.is_some_and(|max| max < 1) {
+        fields.push(field_error("max_responses", "must be at least 1"));
+    }
+    if !fields.is_empty() {
+        return Err(ApiError::validation(fields));
+    }
+    if let Some(slug) = &slug {
+        if db::get_prompt_by_slug(&state.pool, slug).await?.is_some() {
+            return Err(ApiError::conflict(format!(
+                "Slug {} is already taken",
+                slug
+            )));
+        }
+    }
+
+    let options = db::PromptOptions {
+        webhook_url: webhook_url.map(str::to_string),
+        merge_responses: body.merge_responses,
+        recurrence,
+        content_rules,
+        public_board: body.public_board,
+        send_receipts: body.send_receipts,
+        language,
+        slug,
+        thank_you_message,
+        redirect_url,
+        access_code,
+        publish_at,
+        kind,
+        retention_days: body.retention_days,
+        max_responses: body.max_responses,
+    };
+    let prompt =
+        db::create_prompt_with_options(&state.pool, &workspace.id, title, description, &options)
+            .await?;
+    Ok((StatusCode::CREATED, Json(prompt)))
+}
+
+async fn api_v1_open_prompt(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_prompt_status(&state, &workspace, &id, db::PromptStatus::Open).await
+}
+
+async fn api_v1_close_prompt(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_prompt_status(&state, &workspace, &id, db::PromptStatus::Closed).await
+}
+
+async fn api_v1_archive_prompt(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<impl IntoResponse, ApiError> {
+    set_prompt_status(&state, &workspace, &id, db::PromptStatus::Archived).await
+}
+
+/// Move a prompt to `status`, returning it as it now stands
+async fn set_prompt_status(
+    state: &AppState,
+    workspace: &db::Workspace,
+    id: &str,
+    status: db::PromptStatus,
+) -> Result<Json<db::Prompt>, ApiError> {
+    if db::get_workspace_prompt(&state.pool, &workspace.id, id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::not_found("Prompt not found"));
+    }
+    db::set_prompt_status(&state.pool, id, status).await?;
+    state.prompts.invalidate(id);
+    let prompt = db::get_workspace_prompt(&state.pool, &workspace.id, id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    Ok(Json(prompt))
+}
+
+#[derive(Deserialize)]
+struct ApiDuplicatePrompt {
+    /// Defaults to the original's title with " (copy)" appended
+    title: Option<String>,
+    description: Option<String>,
+}
+
+async fn api_v1_duplicate_prompt(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    body: Result<Json<ApiDuplicatePrompt>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(body) = body.map_err(ApiError::from)?;
+    let source = db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    // A copy would be a way around the takedown
+    if source.is_suspended() {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "prompt_suspended",
+            "Suspended prompts can't be duplicated",
+        ));
+    }
+
+    let title = match body.title.as_deref().map(str::trim) {
+        Some("") => {
+            return Err(ApiError::validation(vec![field_error(
+                "title",
+                "must not be empty",
+            )]))
+        }
+        Some(title) => title.to_string(),
+        None => format!("{} (copy)", source.title),
+    };
+    let description = body
+        .description
+        .unwrap_or_else(|| source.description.clone());
+
+    let prompt = db::clone_prompt(&state.pool, &source, &title, &description).await?;
+    Ok((StatusCode::CREATED, Json(prompt)))
+}
+
+#[derive(Deserialize)]
+struct ApiNewPrefillLink {
+    /// Answers to lock, keyed by field ID
+    values: HashMap<String, String>,
+}
+
+/// Sign a per-respondent link to the form with some answers locked
+async fn api_v1_create_prefill_link(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    RequestOrigin(origin): RequestOrigin,
+    Path((_, id)): Path<(String, String)>,
+    body: Result<Json<ApiNewPrefillLink>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(body) = body.map_err(ApiError::from)?;
+    let prompt = db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    if body.values.is_empty() {
+        return Err(ApiError::validation(vec![field_error(
+            "values",
+            "must lock at least one field",
+        )]));
+    }
+    let fields = db::get_prompt_fields(&state.pool, &prompt.id).await?;
+    let locked = prefill_values(&fields, body.values).map_err(|(id, message)| {
+        ApiError::validation(vec![field_error(&format!("values.{}", id), &message)])
+    })?;
+
+    let secret = db::get_or_create_prefill_secret(&state.pool, &prompt.id).await?;
+    let token = prefill::sign(&secret, &prompt.id, &locked);
+    let url = prefill_url(&origin, &prompt.id, &token);
+    Ok((
+        StatusCode::CREATED,
+        Json(serde_json::json!({ "token": token, "url": url })),
+    ))
+}
+
+async fn api_v1_list_translations(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<Json<Vec<db::PromptTranslation>>, ApiError> {
+    let prompt = db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    Ok(Json(
+        db::get_prompt_translations(&state.pool, &prompt.id).await?,
+    ))
+}
+
+#[derive(Deserialize)]
+struct ApiTranslation {
+    title: String,
+    #[serde(default)]
+    description: String,
+    /// Labels keyed by field ID
+    #[serde(default)]
+    field_labels: HashMap<String, String>,
+}
+
+/// Add or replace the prompt's translation into `language`
+async fn api_v1_put_translation(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id, language)): Path<(String, String, String)>,
+    body: Result<Json<ApiTranslation>, JsonRejection>,
+) -> Result<Json<db::PromptTranslation>, ApiError> {
+    let Json(body) = body.map_err(ApiError::from)?;
+    let prompt = db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    let fields = db::get_prompt_fields(&state.pool, &prompt.id).await?;
+    let translation = db::PromptTranslation {
+        language,
+        title: body.title,
+        description: body.description,
+        field_labels: body.field_labels.into_iter().collect(),
+        updated_at: String::new(),
+    };
+    let translation = check_translation(&prompt, &fields, translation)
+        .map_err(|(field, message)| ApiError::validation(vec![field_error(&field, &message)]))?;
+    Ok(Json(
+        db::set_prompt_translation(&state.pool, &prompt.id, &translation).await?,
+    ))
+}
+
+async fn api_v1_delete_translation(
+    _: RequireEditor,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id, language)): Path<(String, String, String)>,
+) -> Result<StatusCode, ApiError> {
+    let prompt = db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    if !db::delete_prompt_translation(&state.pool, &prompt.id, &language).await? {
+        return Err(ApiError::not_found("Translation not found"));
+    }
+    Ok(StatusCode::NO_CONTENT)
+}
+
+/// Responses per day for the past year, oldest first, with quiet days as zero
+async fn api_v1_prompt_calendar(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+) -> Result<Json<Vec<calendar::Day>>, ApiError> {
+    if db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::not_found("Prompt not found"));
+    }
+    Ok(Json(calendar_days(&state, &id).await?))
+}
+
+async fn api_v1_list_feedback(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    Path((_, id)): Path<(String, String)>,
+    Query(query): Query<SinceQuery>,
+) -> Result<Json<Vec<db::Feedback>>, ApiError> {
+    let filter = db::FeedbackFilter {
+        since: query.parse().map_err(|e| ApiError::validation(vec![e]))?,
+        ..Default::default()
+    };
+    if db::get_workspace_prompt(&state.pool, &workspace.id, &id)
+        .await?
+        .is_none()
+    {
+        return Err(ApiError::not_found("Prompt not found"));
+    }
+    Ok(Json(db::list_feedback(&state.pool, &id, &filter).await?))
+}
+
+async fn api_v1_graphql(
+    _: RequireViewer,
+    State(state): State<Arc<AppState>>,
+    CurrentWorkspace(workspace): CurrentWorkspace,
+    body: Result<Json<async_graphql::Request>, JsonRejection>,
+) -> Result<Json<async_graphql::Response>, ApiError> {
+    let Json(request) = body.map_err(ApiError::from)?;
+    Ok(Json(
+        graphql::execute(&state.graphql, workspace, request).await,
+    ))
+}
+
+/// JSON twin of the public feedback form
+async fn api_v1_submit_feedback(
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    connect: Option<ConnectInfo<SocketAddr>>,
+    headers: HeaderMap,
+    body: Result<Json<ApiNewFeedback>, JsonRejection>,
+) -> Result<impl IntoResponse, ApiError> {
+    let Json(body) = body.map_err(ApiError::from)?;
+    let prompt = state
+        .prompts
+        .get(&state.pool, &id)
+        .await?
+        .ok_or_else(|| ApiError::not_found("Prompt not found"))?;
+    if prompt.is_suspended() {
+        return Err(ApiError::new(
+            StatusCode::FORBIDDEN,
+            "prompt_suspended",
+            "This prompt has been suspended pending review",
+        ));
+    }
+    if let Some(opens_at) = prompt.scheduled_until() {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "prompt_scheduled",
+            format!("This prompt opens at {}", opens_at.to_rfc3339()),
+        ));
+    }
+    if !prompt.is_open() {
+        return Err(ApiError::new(
+            StatusCode::CONFLICT,
+            "prompt_closed",
+            "This prompt is no longer accepting feedback",
+        ));
+    }
+    if is_full(&state, &prompt, body.prefill.as_deref()).await {
+        return Err(ApiError::prompt_full());
+    }
+    if state.read_only.is_read_only() {
+        return Err(ApiError::read_only());
+    }
+    let client = client_address(&state, connect, &headers);
+    match check_access(&state, &prompt, body.access_code.as_deref(), &client) {
+        Access::Open | Access::Granted(_) => {}
+        Access::Locked => {
+            return Err(ApiError::new(
+                StatusCode::TOO_MANY_REQUESTS,
+                "access_locked",
+                "Too many wrong access codes; try again later",
+            ))
+        }
+        _ => {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "access_denied",
+                "This prompt needs a valid access_code",
+            ))
+        }
+    }
+    let invite = match invites::check(&state.pool, &prompt, body.invite.as_deref()).await? {
+        invites::Check::NotRequired => None,
+        invites::Check::Outstanding(token) => Some(token),
+        invites::Check::Redeemed => return Err(invite_used()),
+        _ => {
+            return Err(ApiError::new(
+                StatusCode::FORBIDDEN,
+                "invite_required",
+                "This prompt needs a valid invite",
+            ))
+        }
+    };
+
+    let mut fields = Vec::new();
+    let nps_score = if prompt.is_nps() {
+        let score = body.score.filter(|s| nps::is_valid(*s));
+        if score.is_none() {
+            fields.push(field_error("score", "must be a whole number from 0 to 10"));
+        }
+        score
+    } else {
+        None
+    };
+    let reaction = if prompt.is_reactions() {
+        let reaction = body
+            .reaction
+            .as_deref()
+            .and_then(reactions::Reaction::parse);
+        if reaction.is_none() {
+            fields.push(field_error(
+                "reaction",
+                "must be love, happy, neutral or unhappy",
+            ));
+        }
+        reaction
+    } else {
+        None
+    };
+    let content = if prompt.kind().comment_optional() && body.content.trim().is_empty() {
+        ""
+    } else {
+        match validation::feedback(&body.content, prompt.content_min_length) {
+            Ok(content) => {
+                if let Err(message) = prompt.content_rules().check(content) {
+                    fields.push(field_error("content", &message));
+                }
+                content
+            }
+            Err(problem) => {
+                fields.push(field_error("content", &problem.message()));
+                ""
+            }
+        }
+    };
+    let email = body
+        .email
+        .map(|e| e.trim().to_lowercase())
+        .filter(|e| !e.is_empty());
+    if email.as_deref().is_some_and(|e| !is_plausible_email(e)) {
+        fields.push(field_error("email", "must be a valid email address"));
+    }
+    let mut values = body.answers;
+    let lock = match apply_prefill(&state, &prompt.id, body.prefill.as_deref(), &mut values).await {
+        Some(lock) => lock,
+        None => {
+            fields.push(field_error(
+                "prefill",
+                "is not a valid prefill token for this prompt",
+            ));
+            PrefillLock::default()
+        }
+    };
+    let prompt_fields = db::get_prompt_fields(&state.pool, &prompt.id).await?;
+    for id in values.keys() {
+        if !prompt_fields.iter().any(|f| &f.id == id) {
+            fields.push(field_error(
+                &format!("answers.{}", id),
+                "is not a field on this prompt",
+            ));
+        }
+    }
+    let answers = match collect_answers(&prompt_fields, &values) {
+        Ok(answers) => answers,
+        Err((field, message)) => {
+            fields.push(field_error(&format!("answers.{}", field.id), &message));
+            Vec::new()
+        }
+    };
+    if !fields.is_empty() {
+        return Err(ApiError::validation(fields));
+    }
+    if let Some(config) = &state.captcha {
+        match captcha::verify(&state.http_client, config, &body.captcha_token).await {
+            Ok(true) => {}
+            Ok(false) => {
+                return Err(ApiError::new(
+                    StatusCode::FORBIDDEN,
+                    "captcha_failed",
+                    "This instance needs a solved CAPTCHA in captcha_token",
+                ))
+            }
+            Err(e) => {
+                eprintln!("CAPTCHA verification failed: {}", e);
+                return Err(ApiError::new(
+                    StatusCode::BAD_GATEWAY,
+                    "captcha_unavailable",
+                    "Could not verify the CAPTCHA, please try again",
+                ));
+            }
+        }
+    }
+    check_storage_quota(&state, &prompt.id, response_bytes(content, &answers)).await?;
+
+    let options = db::FeedbackOptions {
+        email_verified: lock.email.is_some(),
+        respondent_email: lock.email.or(email),
+        spam_reason: state.spam.check_content(content),
+        answers,
+        verified_fields: lock.fields,
+        nps_score,
+        reaction,
+        ..Default::default()
+    };
+    if let Some(token) = &invite {
+        if !invites::redeem(&state.pool, &prompt.id, token).await? {
+            return Err(invite_used());
+        }
+    }
+    let feedback = record_feedback(&state, &prompt, content, options)
+        .await
+        .inspect_err(|e| state.read_only.observe(e));
+    if let (Err(_), Some(token)) = (&feedback, &invite) {
+        let _ = invites::release(&state.pool, &prompt.id, token).await;
+    }
+    Ok((StatusCode::CREATED, Json(feedback?)))
+}
+
+fn invite_used() -> ApiError {
+    ApiError::new(
+        StatusCode::CONFLICT,
+        "invite_used",
+        "This invite has already been used",
+    )
+}
+
+#[derive(Deserialize)]
+struct LoginQuery {
+    #[serde(default)]
+    next: String,
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    email: String,
+    password: String,
+    #[serde(default)]
+    next: String,
+}
+
+async fn login_form(
+    State(state): State<Arc<AppState>>,
+    Query(query): Query<LoginQuery>,
+) -> impl IntoResponse {
+    // Nothing to sign in to until the first account exists
+    if matches!(db::count_users(&state.pool).await, Ok(0)) {
+        let to = if state.open_admin {
+            "/admin"
+        } else {
+            auth::SETUP_PATH
+        };
+        return Redirect::to(to).into_response();
+    }
+    let template = LoginTemplate {
+        layout: state.layout(),
+        next: auth::safe_next(&query.next).to_string(),
+        error: None,
+        sso: state.oidc.is_some(),
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+async fn login_submit(
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<LoginForm>,
+) -> impl IntoResponse {
+    let next = auth::safe_next(&form.next).to_string();
+    let email = form.email.trim().to_lowercase();
+
+    let user = match db::get_user_by_email(&state.pool, &email).await {
+        Ok(user) => user.filter(|u| auth::verify_password(&form.password, &u.password_hash)),
+        Err(_) => return Html("Error signing in".to_string()).into_response(),
+    };
+    let Some(user) = user else {
+        let template = LoginTemplate {
+            layout: state.layout(),
+            next,
+            error: Some("Invalid email or password".to_string()),
+            sso: state.oidc.is_some(),
+        };
+        return (StatusCode::UNAUTHORIZED, Html(template.render().unwrap())).into_response();
+    };
+
+    match auth::start_session(&state.pool, &user).await {
+        Ok(cookie) => ([(header::SET_COOKIE, cookie)], Redirect::to(&next)).into_response(),
+        Err(_) => Html("Error signing in".to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct SetupForm {
+    email: String,
+    password: String,
+    password_confirm: String,
+    site_name: String,
+    #[serde(default)]
+    base_url: String,
+    sample_prompt: Option<String>,
+}
+
+/// The first-run wizard; once an account exists there's nothing to set up
+async fn setup_form(State(state): State<Arc<AppState>>, origin: Option<RequestOrigin>) -> Response {
+    match db::count_users(&state.pool).await {
+        Ok(0) => {}
+        Ok(_) => return Redirect::to("/admin").into_response(),
+        Err(_) => return Html("Error loading setup".to_string()).into_response(),
+    }
+    let template = SetupTemplate {
+        layout: state.layout(),
+        error: None,
+        email: String::new(),
+        site_name: state.settings().site_name,
+        base_url: origin
+            .map(|RequestOrigin(origin)| origin)
+            .unwrap_or_default(),
+        sample_prompt: true,
+        min_password_len: auth::MIN_PASSWORD_LEN,
+    };
+    Html(template.render().unwrap()).into_response()
+}
+
+async fn setup_submit(State(state): State<Arc<AppState>>, Form(form): Form<SetupForm>) -> Response {
+    let email = form.email.trim().to_lowercase();
+    let site_name = form.site_name.trim();
+    let base_url = match form.base_url.trim() {
+        "" => Ok(None),
+        url => settings::normalize_base_url(url).map(Some).ok_or(()),
+    };
+    let error = if !is_plausible_email(&email) {
+        Some("Invalid email address".to_string())
+    } else if form.password.chars().count() < auth::MIN_PASSWORD_LEN {
+        Some(format!(
+            "Passwords must be at least {} characters",
+            auth::MIN_PASSWORD_LEN
+        ))
+    } else if form.password != form.password_confirm {
+        Some("The passwords don't match".to_string())
+    } else if site_name.is_empty() || site_name.chars().count() > settings::MAX_SITE_NAME_LEN {
+        Some(format!(
+            "The site name must be 1 to {} characters",
+            settings::MAX_SITE_NAME_LEN
+        ))
+    } else if base_url.is_err() {
+        Some("The base URL must be an http or https URL".to_string())
+    } else {
+        None
+    };
+    if error.is_some() {
+        let template = SetupTemplate {
+            layout: state.layout(),
+            error,
+            email,
+            site_name: site_name.to_string(),
+            base_url: form.base_url.trim().to_string(),
+            sample_prompt: form.sample_prompt.is_some(),
+            min_password_len: auth::MIN_PASSWORD_LEN,
+        };
+        return (StatusCode::BAD_REQUEST, Html(template.render().unwrap())).into_response();
+    }
+
+    let setup = setup::Setup {
+        email,
+        password_hash: auth::hash_password(&form.password),
+        settings: settings::Settings {
+            site_name: site_name.to_string(),
+            base_url: base_url.unwrap_or_default(),
+            ..state.settings()
+        },
+        sample_prompt: form.sample_prompt.is_some(),
+    };
+    let user = match setup::complete(&state.pool, &setup).await {
+        Ok(Some(user)) => user,
+        // Someone else finished setting up first
+        Ok(None) => return Redirect::to("/login").into_response(),
+        Err(_) => return Html("Error saving setup".to_string()).into_response(),
+    };
+    *state.settings.write().unwrap() = setup.settings;
+    match auth::start_session(&state.pool, &user).await {
+        Ok(cookie) => ([(header::SET_COOKIE, cookie)], Redirect::to("/admin")).into_response(),
+        Err(_) => Redirect::to("/login").into_response(),
+    }
+}
+
+async fn logout(State(state): State<Arc<AppState>>, headers: HeaderMap) -> impl IntoResponse {
+    if let Some(token) = auth::session_token(&headers) {
+        let _ = db::delete_session(&state.pool, &auth::token_digest(token)).await;
+    }
+    (
+        [(header::SET_COOKIE, auth::clear_session_cookie())],
+        Redirect::to("/login"),
+    )
+}
+
+const OIDC_COOKIE: &str = "feedback_oidc";
+
+fn oidc_redirect_uri(config: &oidc::Config, origin: &str) -> String {
+    config
+        .redirect_url
+        .clone()
+        .unwrap_or_else(|| format!("{}{}", origin, oidc::CALLBACK_PATH))
+}
+
+fn sso_error(status: StatusCode, message: &str) -> Response {
+    (status, Html(message.to_string())).into_response()
+}
+
+/// Send the browser to the identity provider, remembering the login's state,
+/// nonce and destination in a short-lived cookie
+async fn oidc_login(
+    State(state): State<Arc<AppState>>,
+    RequestOrigin(origin): RequestOrigin,
+    Query(query): Query<LoginQuery>,
+) -> impl IntoResponse {
+    let Some(config) = &state.oidc else {
+        return sso_error(StatusCode::NOT_FOUND, "Single sign-on is not configured");
+    };
+    let url = match oidc::discover(&state.http_client, config)
+        .await
+        .and_then(|provider| {
+            let login_state = auth::random_token();
+            let nonce = auth::random_token();
+            let redirect_uri = oidc_redirect_uri(config, &origin);
+            oidc::authorization_url(&provider, config, &redirect_uri, &login_state, &nonce)
+                .map(|url| (url, login_state, nonce))
+        }) {
+        Ok(url) => url,
+        Err(e) => {
+            eprintln!("OIDC login failed: {}", e);
+            return sso_error(
+                StatusCode::BAD_GATEWAY,
+                "Could not reach the identity provider",
+            );
+        }
+    };
+    let (url, login_state, nonce) = url;
+
+    let cookie = format!(
+        "{}={}.{}.{}; Path=/auth/oidc; HttpOnly; SameSite=Lax; Max-Age=600",
+        OIDC_COOKIE,
+        login_state,
+        nonce,
+        hex::encode(auth::safe_next(&query.next))
+    );
+    ([(header::SET_COOKIE, cookie)], Redirect::to(&url)).into_response()
+}
+
+#[derive(Deserialize)]
+struct OidcCallbackQuery {
+    code: Option<String>,
+    state: Option<String>,
+}
+
+/// Finish an SSO login: check it's the one this browser started, redeem the
+/// code, and sign in the local account the identity maps to
+async fn oidc_callback(
+    State(state): State<Arc<AppState>>,
+    RequestOrigin(origin): RequestOrigin,
+    headers: HeaderMap,
+    Query(query): Query<OidcCallbackQuery>,
+) -> impl IntoResponse {
+    let Some(config) = &state.oidc else {
+        return sso_error(StatusCode::NOT_FOUND, "Single sign-on is not configured");
+    };
+
+    let pending = auth::cookie(&headers, OIDC_COOKIE).and_then(|value| {
+        let mut parts = value.splitn(3, '.');
+        let login_state = parts.next()?;
+        let nonce = parts.next()?;
+        let next = String::from_utf8(hex::decode(parts.next()?).ok()?).ok()?;
+        Some((login_state, nonce, next))
+    });
+    let (Some((login_state, nonce, next)), Some(code)) = (pending, query.code) else {
+        return sso_error(
+            StatusCode::BAD_REQUEST,
+            "Sign-in was cancelled or has expired; please try again",
+        );
+    };
+    if query.state.as_deref() != Some(login_state) {
+        return sso_error(
+            StatusCode::BAD_REQUEST,
+            "Sign-in was cancelled or has expired; please try again",
+        );
+    }
+
+    let redirect_uri = oidc_redirect_uri(config, &origin);
+    let claims = async {
+        let provider = oidc::discover(&state.http_client, config).await?;
+        let id_token =
+            oidc::exchange_code(&state.http_client, &provider, config, &code, &redirect_uri)
+                .await?;
+        oidc::validate_id_token(
+            &id_token,
+            &provider,
+            config,
+            nonce,
+            chrono::Utc::now().timestamp(),
+        )
+    }
+    .await;
+    let claims = match claims {
+        Ok(claims) => claims,
+        Err(e) => {
+            eprintln!("OIDC callback failed: {}", e);
+            return sso_error(
+                StatusCode::BAD_GATEWAY,
+                "Could not complete sign-in with the identity provider",
+            );
+        }
+    };
+
+    let user = match find_sso_user(&state, &claims).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return sso_error(
+                StatusCode::FORBIDDEN,
+                "No admin account matches this identity; ask an owner to add your email",
+            )
+        }
+        Err(_) => return Html("Error signing in".to_string()).into_response(),
+    };
+
+    match auth::start_session(&state.pool, &user).await {
+        Ok(cookie) => (
+            AppendHeaders([
+                (header::SET_COOKIE, cookie),
+                (
+                    header::SET_COOKIE,
+                    format!("{}=; Path=/auth/oidc; HttpOnly; Max-Age=0", OIDC_COOKIE),
+                ),
+            ]),
+            Redirect::to(auth::safe_next(&next)),
+        )
+            .into_response(),
+        Err(_) => Html("Error signing in".to_string()).into_response(),
+    }
+}
+
+/// The account linked to this OIDC subject; on first use, the unlinked
+/// account whose email matches the identity's verified email gets linked
+async fn find_sso_user(
+    state: &AppState,
+    claims: &oidc::Claims,
+) -> Result<Option<db::User>, sqlx::Error> {
+    if let Some(user) = db::get_user_by_oidc_subject(&state.pool, &claims.sub).await? {
+        return Ok(Some(user));
+    }
+    let Some(email) = claims.verified_email() else {
+        return Ok(None);
+    };
+    let Some(user) = db::get_user_by_email(&state.pool, &email).await? else {
+        return Ok(None);
+    };
+    if !db::link_oidc_subject(&state.pool, &user.id, &claims.sub).await? {
+        // Already linked to a different identity
+        return Ok(None);
+    }
+    Ok(Some(user))
+}
+
+#[derive(Deserialize)]
+struct NewUserForm {
+    email: String,
+    password: String,
+    #[serde(default)]
+    role: String,
+}
+
+#[derive(Deserialize)]
+struct RoleForm {
+    role: String,
+}
+
+/// Reported and suspended prompts across every workspace
+async fn admin_abuse(_: RequireOwner, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match db::get_abuse_queue(&state.pool).await {
+        Ok(cases) => {
+            let template = AdminAbuseTemplate {
+                layout: state.layout(),
+                cases,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading abuse reports".to_string()),
+    }
+}
+
+/// Uphold a prompt's reports and keep it down
+async fn admin_suspend_prompt(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match db::suspend_prompt(&state.pool, &id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response()
+        }
+        Err(_) => return Html("Error suspending prompt".to_string()).into_response(),
+    }
+    state.prompts.invalidate(&id);
+    if db::resolve_abuse_reports(&state.pool, &id, "suspended")
+        .await
+        .is_err()
+    {
+        return flash::redirect(
+            "/admin/abuse",
+            flash::Flash::error("Failed to resolve the reports"),
+        );
+    }
+    flash::redirect("/admin/abuse", flash::Flash::success("Prompt suspended"))
+}
+
+/// Dismiss a prompt's reports and lift any suspension
+async fn admin_reinstate_prompt(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> Response {
+    match db::reinstate_prompt(&state.pool, &id).await {
+        Ok(true) => {}
+        Ok(false) => {
+            return (StatusCode::NOT_FOUND, Html("Prompt not found".to_string())).into_response()
+        }
+        Err(_) => return Html("Error reinstating prompt".to_string()).into_response(),
+    }
+    state.prompts.invalidate(&id);
+    if db::resolve_abuse_reports(&state.pool, &id, "dismissed")
+        .await
+        .is_err()
+    {
+        return flash::redirect(
+            "/admin/abuse",
+            flash::Flash::error("Failed to resolve the reports"),
+        );
+    }
+    flash::redirect(
+        "/admin/abuse",
+        flash::Flash::success("Reports dismissed and the prompt reinstated"),
+    )
+}
+
+async fn admin_users(
+    RequireOwner(admin): RequireOwner,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match db::get_users(&state.pool).await {
+        Ok(users) => {
+            let bounces = bounces::list(&state.pool).await.unwrap_or_default();
+            let template = AdminUsersTemplate {
+                layout: state.layout(),
+                users,
+                current_user_id: admin.user.map(|u| u.id),
+                roles: &db::Role::ALL,
+                min_password_len: auth::MIN_PASSWORD_LEN,
+                sso: state.oidc.is_some(),
+                digest_schedule: state
+                    .digest_schedule
+                    .as_ref()
+                    .map(|schedule| schedule.as_str().to_string()),
+                bounces,
+                disable_after: bounces::DISABLE_AFTER,
+            };
+            Html(template.render().unwrap())
+        }
+        Err(_) => Html("Error loading users".to_string()),
+    }
+}
+
+async fn admin_create_user(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<NewUserForm>,
+) -> impl IntoResponse {
+    let first_user = match db::count_users(&state.pool).await {
+        Ok(count) => count == 0,
+        Err(_) => {
+            return flash::redirect(
+                "/admin/users",
+                flash::Flash::error("Failed to create the account"),
+            )
+        }
+    };
+
+    let email = form.email.trim().to_lowercase();
+    if !is_plausible_email(&email) {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Invalid email address".to_string()),
+        )
+            .into_response();
+    }
+    // With SSO configured, accounts may skip a local password entirely
+    let sso_only = form.password.is_empty() && state.oidc.is_some();
+    if !sso_only && form.password.chars().count() < auth::MIN_PASSWORD_LEN {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html(format!(
+                "Passwords must be at least {} characters",
+                auth::MIN_PASSWORD_LEN
+            )),
+        )
+            .into_response();
+    }
+    // The first account must be able to manage the rest
+    let role = if first_user {
+        db::Role::Owner
+    } else {
+        match db::Role::parse(&form.role) {
+            Some(role) => role,
+            None => {
+                return (StatusCode::BAD_REQUEST, Html("Invalid role".to_string())).into_response()
+            }
+        }
+    };
+
+    // An empty hash never verifies, so SSO-only accounts can't use the login form
+    let password_hash = if sso_only {
+        String::new()
+    } else {
+        auth::hash_password(&form.password)
+    };
+    let user = match db::create_user(&state.pool, &email, &password_hash, role).await {
+        Ok(Some(user)) => user,
+        Ok(None) => {
+            return (
+                StatusCode::CONFLICT,
+                Html("An account with that email already exists".to_string()),
+            )
+                .into_response()
+        }
+        Err(_) => {
+            return flash::redirect(
+                "/admin/users",
+                flash::Flash::error("Failed to create the account"),
+            )
+        }
+    };
+
+    // Creating the first account turns sign-in on, so sign its creator in
+    if first_user {
+        if let Ok(cookie) = auth::start_session(&state.pool, &user).await {
+            return ([(header::SET_COOKIE, cookie)], Redirect::to("/admin/users")).into_response();
+        }
+    }
+    flash::redirect(
+        "/admin/users",
+        flash::Flash::success(format!("Account created for {}", user.email)),
+    )
+}
+
+/// Refuse changes that would leave nobody able to manage users
+async fn ensure_other_owner(state: &AppState, user: &db::User) -> Result<(), Response> {
+    if user.role() != db::Role::Owner {
+        return Ok(());
+    }
+    match db::count_owners(&state.pool).await {
+        Ok(count) if count > 1 => Ok(()),
+        Ok(_) => Err((
+            StatusCode::CONFLICT,
+            Html("There must always be at least one owner".to_string()),
+        )
+            .into_response()),
+        Err(_) => Err(Html("Error updating user".to_string()).into_response()),
+    }
+}
+
+async fn target_user(state: &AppState, id: &str) -> Result<db::User, Response> {
+    match db::get_user_by_id(&state.pool, id).await {
+        Ok(Some(user)) => Ok(user),
+        _ => Err((StatusCode::NOT_FOUND, Html("User not found".to_string())).into_response()),
+    }
+}
+
+#[derive(Deserialize)]
+struct DigestForm {
+    /// Checkbox: present when subscribing
+    enabled: Option<String>,
+}
+
+async fn admin_set_user_digest(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Form(form): Form<DigestForm>,
+) -> impl IntoResponse {
+    match db::set_digest_subscribed(&state.pool, &id, form.enabled.is_some()).await {
+        Ok(true) => flash::redirect(
+            "/admin/users",
+            flash::Flash::success("Digest subscription updated"),
+        ),
+        Ok(false) => (StatusCode::NOT_FOUND, Html("User not found".to_string())).into_response(),
+        Err(_) => flash::redirect(
+            "/admin/users",
+            flash::Flash::error("Failed to update the account"),
+        ),
+    }
+}
+
+#[derive(Deserialize)]
+struct BounceResetForm {
+    address: String,
+}
+
+/// Let email go to an address again after it bounced
+async fn admin_reset_bounces(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<BounceResetForm>,
+) -> impl IntoResponse {
+    match bounces::clear(&state.pool, &form.address).await {
+        Ok(true) => flash::redirect(
+            "/admin/users",
+            flash::Flash::success(format!("Email to {} resumed", form.address.trim())),
+        ),
+        Ok(false) => (
+            StatusCode::NOT_FOUND,
+            Html("No bounces recorded for that address".to_string()),
+        )
+            .into_response(),
+        Err(_) => flash::redirect(
+            "/admin/users",
+            flash::Flash::error("Failed to update the address"),
+        ),
+    }
+}
+
+async fn admin_set_user_role(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+    Form(form): Form<RoleForm>,
+) -> impl IntoResponse {
+    let Some(role) = db::Role::parse(&form.role) else {
+        return (StatusCode::BAD_REQUEST, Html("Invalid role".to_string())).into_response();
+    };
+    let user = match target_user(&state, &id).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    if role != db::Role::Owner {
+        if let Err(response) = ensure_other_owner(&state, &user).await {
+            return response;
+        }
+    }
+
+    match db::set_user_role(&state.pool, &id, role).await {
+        Ok(_) => flash::redirect(
+            "/admin/users",
+            flash::Flash::success(format!("{} is now {}", user.email, role.as_str())),
+        ),
+        Err(_) => flash::redirect(
+            "/admin/users",
+            flash::Flash::error("Failed to update the account"),
+        ),
+    }
+}
+
+async fn admin_delete_user(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let user = match target_user(&state, &id).await {
+        Ok(user) => user,
+        Err(response) => return response,
+    };
+    if let Err(response) = ensure_other_owner(&state, &user).await {
+        return response;
+    }
+
+    match db::delete_user(&state.pool, &id).await {
+        // htmx swaps the row out for nothing
+        Ok(_) => Html(String::new()).into_response(),
+        Err(_) => Html("Error deleting user".to_string()).into_response(),
+    }
+}
+
+#[derive(Deserialize)]
+struct NewTokenForm {
+    name: String,
+    role: String,
+    /// Workspace ID to limit the token to; empty for every workspace
+    #[serde(default)]
+    workspace: String,
+}
+
+async fn render_tokens(state: &AppState, new_token: Option<String>) -> Response {
+    let loaded = async {
+        Ok::<_, sqlx::Error>((
+            db::get_api_tokens(&state.pool).await?,
+            db::get_workspaces(&state.pool).await?,
+        ))
+    };
+    match loaded.await {
+        Ok((tokens, workspaces)) => {
+            let tokens = tokens
+                .into_iter()
+                .map(|token| TokenRow {
+                    workspace: workspaces
+                        .iter()
+                        .find(|w| token.workspace_id.as_ref() == Some(&w.id))
+                        .map(|w| w.name.clone()),
+                    token,
+                })
+                .collect();
+            let template = AdminTokensTemplate {
+                layout: state.layout(),
+                tokens,
+                new_token,
+                roles: &db::Role::ALL,
+                workspaces,
+            };
+            Html(template.render().unwrap()).into_response()
+        }
+        Err(_) => Html("Error loading tokens".to_string()).into_response(),
+    }
+}
+
+async fn admin_tokens(_: RequireOwner, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    render_tokens(&state, None).await
+}
+
+async fn admin_create_token(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<NewTokenForm>,
+) -> impl IntoResponse {
+    let name = form.name.trim();
+    let Some(role) = db::Role::parse(&form.role).filter(|_| !name.is_empty()) else {
+        return (
+            StatusCode::BAD_REQUEST,
+            Html("Tokens need a name and a valid role".to_string()),
+        )
+            .into_response();
+    };
+
+    let workspace_id = Some(form.workspace.trim()).filter(|w| !w.is_empty());
+    if let Some(workspace_id) = workspace_id {
+        if !matches!(db::get_workspaces(&state.pool).await, Ok(ws) if ws.iter().any(|w| w.id == workspace_id))
+        {
+            return (
+                StatusCode::BAD_REQUEST,
+                Html("Unknown workspace".to_string()),
+            )
+                .into_response();
+        }
+    }
+
+    let secret = auth::new_api_token();
+    let digest = auth::token_digest(&secret);
+    match db::create_api_token(&state.pool, name, &digest, role, workspace_id).await {
+        // Rendered rather than redirected so the secret never lands in a URL
+        Ok(_) => render_tokens(&state, Some(secret)).await,
+        Err(_) => Html("Error creating token".to_string()).into_response(),
+    }
+}
+
+async fn admin_revoke_token(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    match db::revoke_api_token(&state.pool, &id).await {
+        Ok(_) => flash::redirect("/admin/tokens", flash::Flash::success("Token revoked")),
+        Err(_) => flash::redirect(
+            "/admin/tokens",
+            flash::Flash::error("Failed to revoke the token"),
+        ),
+    }
+}
+
+async fn admin_maintenance(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    let page = async {
+        Ok::<_, sqlx::Error>(AdminMaintenanceTemplate {
+            layout: state.layout(),
+            usage: maintenance::usage(&state.pool).await?,
+            window: state.maintenance_window,
+            runs: maintenance::recent_runs(&state.pool, 20).await?,
+        })
+    };
+    match page.await {
+        Ok(template) => Html(template.render().unwrap()),
+        Err(_) => Html("Error loading maintenance status".to_string()),
+    }
+}
+
+#[derive(Deserialize)]
+struct SettingsForm {
+    site_name: String,
+    #[serde(default)]
+    base_url: String,
+    #[serde(default)]
+    organization_name: String,
+    #[serde(default)]
+    logo_url: String,
+    #[serde(default)]
+    accent_color: String,
+    #[serde(default)]
+    footer_text: String,
+    #[serde(default)]
+    retention_days: String,
+}
+
+impl SettingsForm {
+    /// The settings as entered, with blanks unset
+    fn settings(&self) -> settings::Settings {
+        let optional = |value: &str| Some(value.trim().to_string()).filter(|v| !v.is_empty());
+        settings::Settings {
+            site_name: self.site_name.trim().to_string(),
+            base_url: optional(&self.base_url),
+            organization_name: optional(&self.organization_name),
+            logo_url: optional(&self.logo_url),
+            accent_color: optional(&self.accent_color),
+            footer_text: optional(&self.footer_text),
+            retention_days: self.retention_days.trim().parse().ok(),
+        }
+    }
+
+    /// The settings to save, normalized, or what's wrong with them
+    fn parse(&self) -> Result<settings::Settings, String> {
+        let mut parsed = self.settings();
+        let too_long = |value: &Option<String>, max: usize| {
+            value.as_ref().is_some_and(|v| v.chars().count() > max)
+        };
+        if parsed.site_name.is_empty()
+            || parsed.site_name.chars().count() > settings::MAX_SITE_NAME_LEN
+        {
+            return Err(format!(
+                "The site name must be 1 to {} characters",
+                settings::MAX_SITE_NAME_LEN
+            ));
+        }
+        if too_long(&parsed.organization_name, settings::MAX_SITE_NAME_LEN) {
+            return Err(format!(
+                "The organization name must be at most {} characters",
+                settings::MAX_SITE_NAME_LEN
+            ));
+        }
+        if too_long(&parsed.footer_text, settings::MAX_FOOTER_LEN) {
+            return Err(format!(
+                "The footer must be at most {} characters",
+                settings::MAX_FOOTER_LEN
+            ));
+        }
+        if let Some(url) = &parsed.base_url {
+            parsed.base_url = Some(
+                settings::normalize_base_url(url)
+                    .ok_or("The base URL must be an http or https URL")?,
+            );
+        }
+        if parsed
+            .logo_url
+            .as_deref()
+            .is_some_and(|url| !webhook::is_valid_url(url))
+        {
+            return Err("The logo URL must be a public http or https URL".to_string());
+        }
+        if let Some(color) = &parsed.accent_color {
+            parsed.accent_color = Some(
+                settings::normalize_color(color)
+                    .ok_or("The accent color must be a hex color like #3498db")?,
+            );
+        }
+        parsed.retention_days = retention::parse_days(&self.retention_days)?;
+        Ok(parsed)
+    }
+}
+
+async fn settings_page(
+    state: &AppState,
+    settings: settings::Settings,
+    error: Option<String>,
+) -> AdminSettingsTemplate {
+    let next_purge = retention::next_run(&state.pool, chrono::Utc::now())
+        .await
+        .map(|t| t.format("%Y-%m-%d %H:%M UTC").to_string())
+        .unwrap_or_default();
+    AdminSettingsTemplate {
+        layout: state.layout(),
+        settings,
+        default_accent_color: settings::DEFAULT_ACCENT_COLOR,
+        error,
+        next_purge,
+        purges: retention::recent_purges(&state.pool, SETTINGS_PURGES)
+            .await
+            .unwrap_or_default(),
+    }
+}
+
+async fn admin_settings(_: RequireOwner, State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    let template = settings_page(&state, state.settings(), None).await;
+    Html(template.render().unwrap())
+}
+
+async fn admin_save_settings(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+    Form(form): Form<SettingsForm>,
+) -> Response {
+    let parsed = match form.parse() {
+        Ok(parsed) => parsed,
+        Err(error) => {
+            let template = settings_page(&state, form.settings(), Some(error)).await;
+            return (StatusCode::BAD_REQUEST, Html(template.render().unwrap())).into_response();
+        }
+    };
+    if settings::save(&state.pool, &parsed).await.is_err() {
+        return flash::redirect(
+            "/admin/settings",
+            flash::Flash::error("Failed to save the settings"),
+        );
+    }
+    *state.settings.write().unwrap() = parsed;
+    flash::redirect("/admin/settings", flash::Flash::success("Settings saved"))
+}
+
+async fn admin_run_maintenance(
+    _: RequireOwner,
+    State(state): State<Arc<AppState>>,
+) -> impl IntoResponse {
+    match maintenance::run(&state.pool).await {
+        Ok(_) => flash::redirect(
+            "/admin/maintenance",
+            flash::Flash::success("Maintenance finished"),
+        ),
+        Err(e) => {
+            eprintln!("Maintenance failed: {}", e);
+            flash::redirect(
+                "/admin/maintenance",
+                flash::Flash::error("Maintenance failed; see the server log"),
+            )
+        }
+    }
+}
+
+async fn index() -> impl IntoResponse {
+    Redirect::to("/admin")
+}
+
+/// A compiled-in stylesheet or script; see `assets`
+async fn static_asset(Path(file): Path<String>, headers: HeaderMap) -> Response {
+    let Some((asset, fingerprinted)) = assets::lookup(&file) else {
+        return StatusCode::NOT_FOUND.into_response();
+    };
+    let cache_control = if fingerprinted {
+        assets::IMMUTABLE_CACHE_CONTROL
+    } else {
+        assets::REVALIDATE_CACHE_CONTROL
+    };
+    let not_modified = headers
+        .get(header::IF_NONE_MATCH)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|v| v.split(',').any(|tag| tag.trim() == asset.etag));
+    let caching = AppendHeaders([
+        (header::CACHE_CONTROL, cache_control.to_string()),
+        (header::ETAG, asset.etag.clone()),
+    ]);
+    if not_modified {
+        return (StatusCode::NOT_MODIFIED, caching).into_response();
+    }
+    (
+        caching,
+        [(header::CONTENT_TYPE, asset.content_type)],
+        asset.body,
+    )
+        .into_response()
+}
+
+/// Liveness: the process is up and serving requests
+async fn healthz() -> impl IntoResponse {
+    "ok"
+}
+
+/// Readiness: the database answers a trivial query
+async fn readyz(State(state): State<Arc<AppState>>) -> impl IntoResponse {
+    match sqlx::query("SELECT 1").execute(&state.pool).await {
+        // Reads still work, so stay in rotation while submissions are paused
+        Ok(_) if state.read_only.is_read_only() => (StatusCode::OK, "ready (read-only)"),
+        Ok(_) => (StatusCode::OK, "ready"),
+        Err(e) => {
+            eprintln!("Readiness check failed: {}", e);
+            (StatusCode::SERVICE_UNAVAILABLE, "database unavailable")
+        }
+    }
+}
+
+/// Just the health probes, for listeners that don't serve the app
+fn probe_router(state: Arc<AppState>) -> Router {
+    Router::new()
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .with_state(state)
+}
+
+/// Archives are far larger than form posts, so imports get their own body limit
+const MAX_IMPORT_BYTES: usize = 64 * 1024 * 1024;
+
+/// Create the application router with the given state
+pub fn create_router(state: Arc<AppState>) -> Router {
+    let limits = state.limits.clone();
+    let cors = state.cors.as_ref().map(cors::Config::layer);
+    let timeout = |duration| {
+        ServiceBuilder::new()
+            .layer(HandleErrorLayer::new(limits::handle_error))
+            .timeout(duration)
+    };
+
+    // Routes that copy or rewrite the whole database get the longer timeout
+    let slow = Router::new()
+        .route("/admin/backup", get(admin_backup))
+        .route("/admin/maintenance/run", post(admin_run_maintenance))
+        .route("/admin/:workspace/export", get(admin_export))
+        .route(
+            "/admin/:workspace/exports",
+            get(admin_export_templates).post(admin_create_export_template),
+        )
+        .route(
+            "/admin/:workspace/exports/:id",
+            get(admin_edit_export_template_form)
+                .post(admin_update_export_template)
+                .delete(admin_delete_export_template),
+        )
+        .route(
+            "/admin/:workspace/import",
+            post(admin_import).layer(DefaultBodyLimit::max(MAX_IMPORT_BYTES)),
+        )
+        .route_layer(timeout(limits.slow_request_timeout));
+
+    Router::new()
+        .route("/", get(index))
+        .route("/admin", get(admin_workspaces))
+        .route("/admin/workspaces", post(admin_create_workspace))
+        .route("/admin/abuse", get(admin_abuse))
+        .route("/admin/abuse/:id/suspend", post(admin_suspend_prompt))
+        .route("/admin/abuse/:id/reinstate", post(admin_reinstate_prompt))
+        .route("/admin/users", get(admin_users).post(admin_create_user))
+        .route("/admin/users/:id/role", post(admin_set_user_role))
+        .route("/admin/users/:id/digest", post(admin_set_user_digest))
+        .route("/admin/users/bounces/reset", post(admin_reset_bounces))
+        .route("/admin/users/:id", delete(admin_delete_user))
+        .route("/admin/tokens", get(admin_tokens).post(admin_create_token))
+        .route("/admin/maintenance", get(admin_maintenance))
+        .route(
+            "/admin/settings",
+            get(admin_settings).post(admin_save_settings),
+        )
+        .route("/admin/tokens/:id/revoke", post(admin_revoke_token))
+        .route("/login", get(login_form).post(login_submit))
+        .route(auth::SETUP_PATH, get(setup_form).post(setup_submit))
+        .route("/logout", post(logout))
+        .route("/auth/oidc/login", get(oidc_login))
+        .route(oidc::CALLBACK_PATH, get(oidc_callback))
+        .route("/admin/:workspace", get(admin_list))
+        .route("/admin/:workspace/archived", get(admin_archived))
+        .route(
+            "/admin/:workspace/new",
+            get(admin_new_form).post(admin_new_submit),
+        )
+        .route("/admin/:workspace/search", get(admin_search))
+        .route("/admin/:workspace/respondents", get(admin_respondents))
+        .route(
+            "/admin/:workspace/respondents/:email",
+            get(admin_respondent_detail),
+        )
+        .route("/admin/:workspace/privacy", get(admin_privacy))
+        .route(
+            "/admin/:workspace/privacy/export",
+            post(admin_privacy_export),
+        )
+        .route(
+            "/admin/:workspace/privacy/delete",
+            post(admin_privacy_delete),
+        )
+        .route("/admin/:workspace/spam", get(admin_spam))
+        .route(
+            "/admin/:workspace/spam/:id/release",
+            post(admin_release_spam),
+        )
+        .route("/admin/:workspace/spam/:id", delete(admin_delete_spam))
+        .route(
+            "/admin/:workspace/fields",
+            get(admin_fields).post(admin_create_field),
+        )
+        .route(
+            "/admin/:workspace/fields/:id",
+            get(admin_edit_field_form)
+                .post(admin_update_field)
+                .delete(admin_delete_field),
+        )
+        .route("/admin/:workspace/prompt/:id", get(admin_detail))
+        .route(
+            "/admin/:workspace/prompt/:id/webhook/test",
+            post(admin_test_webhook),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/events",
+            get(admin_feedback_events),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/fields",
+            post(admin_add_prompt_field),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/fields/:field_id",
+            delete(admin_remove_prompt_field),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/notifications",
+            post(admin_add_notification_rule),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/notifications/:rule_id",
+            delete(admin_delete_notification_rule),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/translations",
+            post(admin_save_translation),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/translations/:language",
+            delete(admin_delete_translation),
+        )
+        .route("/admin/:workspace/feedback/:id/tags", post(admin_add_tag))
+        .route("/admin/:workspace/feedback/:id/replies", post(admin_reply))
+        .route(
+            "/admin/:workspace/feedback/:id/status",
+            post(admin_set_feedback_status),
+        )
+        .route(
+            "/admin/:workspace/feedback/:id/tags/:name",
+            delete(admin_remove_tag),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/status",
+            post(admin_set_prompt_status),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/thank-you",
+            post(admin_set_thank_you),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/receipts",
+            post(admin_set_receipts),
+        )
+        .route("/admin/:workspace/prompt/:id/slug", post(admin_set_slug))
+        .route(
+            "/admin/:workspace/prompt/:id/access-code",
+            post(admin_set_access_code),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/publish-at",
+            post(admin_set_publish_at),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/retention",
+            post(admin_set_retention),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/duplicate",
+            post(admin_duplicate_prompt),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/invites",
+            get(admin_invites).post(admin_create_invites),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/invites/required",
+            post(admin_set_invite_only),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/invites/:invite_id",
+            delete(admin_revoke_invite),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/stats",
+            get(admin_prompt_stats),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/calendar.svg",
+            get(admin_prompt_calendar),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/embed",
+            post(admin_enable_embed).delete(admin_disable_embed),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/feedback/bulk",
+            post(admin_bulk_feedback),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/feed",
+            post(admin_enable_feed).delete(admin_disable_feed),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/feed.atom",
+            get(admin_prompt_feed),
+        )
+        .route(
+            "/admin/:workspace/prompt/:id/prefill",
+            post(admin_create_prefill_link),
+        )
+        .route("/feedback/:id", get(feedback_form).post(feedback_submit))
+        .route("/f/:slug", get(feedback_form_by_slug))
+        .route(
+            "/feedback/:id/edit/:token",
+            get(feedback_edit_form).post(feedback_edit_submit),
+        )
+        .route(
+            "/feedback/:id/report",
+            get(feedback_report_form).post(feedback_report_submit),
+        )
+        .route("/feedback/:id/board", get(feedback_board))
+        .route("/feedback/:id/board/:feedback_id/vote", post(feedback_vote))
+        .route("/embed/:id/results", get(embed_results))
+        .route("/static/:file", get(static_asset))
+        .route("/api/:workspace/prompts/:id", delete(api_delete_prompt))
+        .route("/api/:workspace/feedback/:id", get(api_get_feedback))
+        .route(
+            "/api/v1/workspaces/:workspace/prompts",
+            post(api_v1_create_prompt),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/graphql",
+            post(api_v1_graphql),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/feedback",
+            get(api_v1_list_feedback),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/calendar",
+            get(api_v1_prompt_calendar),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/open",
+            post(api_v1_open_prompt),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/close",
+            post(api_v1_close_prompt),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/archive",
+            post(api_v1_archive_prompt),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/duplicate",
+            post(api_v1_duplicate_prompt),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/prefill-links",
+            post(api_v1_create_prefill_link),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/translations",
+            get(api_v1_list_translations),
+        )
+        .route(
+            "/api/v1/workspaces/:workspace/prompts/:id/translations/:language",
+            put(api_v1_put_translation).delete(api_v1_delete_translation),
+        )
+        .route(
+            "/api/v1/prompts/:id/feedback",
+            post(api_v1_submit_feedback).layer(option_layer(cors)),
+        )
+        .route_layer(timeout(limits.request_timeout))
+        .merge(slow)
+        .layer(DefaultBodyLimit::max(limits.max_body_bytes))
+        .layer(
+            ServiceBuilder::new()
+                .layer(HandleErrorLayer::new(limits::handle_error))
+                .load_shed()
+                .layer(limits.concurrency_layer()),
+        )
+        .layer(axum::middleware::from_fn_with_state(
+            limits.oversized_delay,
+            limits::tarpit,
+        ))
+        // Added after the limits so probes still answer when the app is saturated
+        .route("/healthz", get(healthz))
+        .route("/readyz", get(readyz))
+        .layer(axum::middleware::from_fn(flash::middleware))
+        .layer(axum::middleware::from_fn(error::request_id))
+        .with_state(state)
+}
+
+#[tokio::main]
+async fn main() {
+    // Initialize database
+    let database_url =
+        std::env::var("DATABASE_URL").unwrap_or_else(|_| "sqlite:feedback.db?mode=rwc".to_string());
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match cli::parse(&args) {
+        Ok(cli::Command::Serve) => {}
+        Ok(command) => std::process::exit(cli::run(command, &database_url).await),
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, cli::USAGE);
+            std::process::exit(2);
+        }
+    }
+
+    let defaults = db::PoolConfig::default();
+    let pool_config = db::PoolConfig {
+        max_connections: env_or("DB_MAX_CONNECTIONS", defaults.max_connections.into()).max(1)
+            as u32,
+        busy_timeout: std::time::Duration::from_millis(env_or(
+            "DB_BUSY_TIMEOUT_MS",
+            defaults.busy_timeout.as_millis() as u64,
+        )),
+        wal: env_or("DB_WAL", 1) != 0,
+    };
+    let pool = db::init_db_with(&database_url, &pool_config)
+        .await
+        .expect("Failed to initialize database");
+
+    if let Some(path) = bootstrap::configured_path() {
+        let applied = match bootstrap::load(&path) {
+            Ok(file) => bootstrap::apply(&pool, &file).await,
+            Err(e) => Err(e),
+        };
+        match applied {
+            Ok(summary) => println!(
+                "Applied {}: {} user(s), {} role change(s), {} workspace(s), {} prompt(s), {} token(s) added",
+                path.display(),
+                summary.users,
+                summary.roles_updated,
+                summary.workspaces,
+                summary.prompts,
+                summary.tokens
+            ),
+            // Better to fail the deploy than come up half-configured
+            Err(e) => {
+                eprintln!("Bootstrap from {} failed: {}", path.display(), e);
+                std::process::exit(1);
+            }
+        }
+    }
+
+    jobs::spawn_cycle_rollover(pool.clone());
+
+    // Scheduled snapshots are opt-in via BACKUP_DIR
+    if let Ok(dir) = std::env::var("BACKUP_DIR") {
+        let hours = env_or("BACKUP_INTERVAL_HOURS", 24).max(1);
+        let keep = env_or("BACKUP_KEEP", 7).max(1) as usize;
+        println!("Writing snapshots to {} every {}h", dir, hours);
+        jobs::spawn_snapshots(
+            pool.clone(),
+            dir.into(),
+            std::time::Duration::from_secs(hours * 3600),
+            keep,
+        );
+    }
+
+    let defaults = maintenance::Window::default();
+    let window = maintenance::Window {
+        start: env_or("MAINTENANCE_START_HOUR", defaults.start.into()) as u32 % 24,
+        end: env_or("MAINTENANCE_END_HOUR", defaults.end.into()) as u32 % 24,
+    };
+    jobs::spawn_maintenance(pool.clone(), window);
+    jobs::spawn_retention_purge(pool.clone());
+
+    let mut screen = spam::Screen::new(std::time::Duration::from_secs(env_or(
+        "SPAM_MIN_SUBMIT_SECONDS",
+        3,
+    )))
+    .with_filter(spam::LinkLimit {
+        max_links: env_or("SPAM_MAX_LINKS", 3) as usize,
+    });
+    if let Ok(words) = std::env::var("SPAM_BANNED_WORDS") {
+        screen = screen.with_filter(spam::BannedWords::new(words.split(',')));
+    }
+
+    let mut state = AppState::new(pool.clone());
+    state.settings = RwLock::new(
+        settings::load(&pool)
+            .await
+            .expect("Failed to load settings"),
+    );
+    state.open_admin = env_or("OPEN_ADMIN", 0) != 0;
+    state.prompts = cache::PromptCache::new(
+        std::time::Duration::from_secs(env_or(
+            "PROMPT_CACHE_TTL_SECS",
+            cache::DEFAULT_TTL.as_secs(),
+        )),
+        env_or("PROMPT_CACHE_CAPACITY", cache::DEFAULT_CAPACITY),
+    );
+    jobs::spawn_notification_digests(pool.clone(), state.http_client.clone());
+    jobs::spawn_read_only_probe(pool.clone(), state.read_only.clone());
+    state.oidc = oidc::Config::from_env();
+    state.maintenance_window = window;
+    state.spam = screen;
+    state.captcha = captcha::Config::from_env();
+    state.cors = cors::Config::from_env();
+    state.detect_duplicates = env_or("DETECT_DUPLICATES", 0) != 0;
+    state.trust_proxy_headers = env_or("TRUST_PROXY_HEADERS", 0) != 0;
+    state.webhooks = webhook::Client::new(env_or("WEBHOOK_ALLOW_PRIVATE", 0) != 0);
+    state.mailer = email::Mailer::from_env();
+    if let Some(mailer) = &state.mailer {
+        let spec = std::env::var("DIGEST_SCHEDULE")
+            .unwrap_or_else(|_| digest::DEFAULT_SCHEDULE.to_string());
+        match digest::Schedule::parse(&spec) {
+            Ok(schedule) => {
+                jobs::spawn_email_digests(pool.clone(), mailer.clone(), schedule.clone());
+                state.digest_schedule = Some(schedule);
+            }
+            Err(e) => eprintln!("Feedback digest disabled: DIGEST_SCHEDULE {}", e),
+        }
+    }
+    state.abuse_suspend_threshold = env_or("ABUSE_SUSPEND_THRESHOLD", 3) as i64;
+    state.storage_quotas = storage::Quotas {
+        prompt_bytes: env_or("PROMPT_STORAGE_QUOTA_BYTES", 0) as i64,
+        workspace_bytes: env_or("WORKSPACE_STORAGE_QUOTA_BYTES", 0) as i64,
+    };
+    let defaults = limits::Limits::default();
+    state.limits = limits::Limits {
+        request_timeout: std::time::Duration::from_secs(
+            env_or("REQUEST_TIMEOUT_SECS", defaults.request_timeout.as_secs()).max(1),
+        ),
+        slow_request_timeout: std::time::Duration::from_secs(
+            env_or(
+                "SLOW_REQUEST_TIMEOUT_SECS",
+                defaults.slow_request_timeout.as_secs(),
+            )
+            .max(1),
+        ),
+        max_concurrent_requests: env_or(
+            "MAX_CONCURRENT_REQUESTS",
+            defaults.max_concurrent_requests as u64,
+        )
+        .max(1) as usize,
+        max_body_bytes: env_or("MAX_BODY_BYTES", defaults.max_body_bytes as u64).max(1024) as usize,
+        oversized_delay: std::time::Duration::from_millis(env_or(
+            "OVERSIZED_DELAY_MS",
+            defaults.oversized_delay.as_millis() as u64,
+        )),
+    };
+    if let Some(config) = &state.captcha {
+        println!("Feedback form requires {:?} CAPTCHA", config.provider);
+    }
+    if let Some(config) = &state.oidc {
+        println!("Single sign-on enabled via {}", config.issuer);
+    }
+    if let Some(config) = &state.cors {
+        println!("Cross-origin feedback submissions allowed: {:?}", config);
+    }
+    let open_admin = state.open_admin;
+    let shutdown = state.shutdown.clone();
+    let state = Arc::new(state);
+
+    // Build router
+    let app = create_router(state.clone());
+
+    let addr = "0.0.0.0:3000";
+    let mut origin = "http://localhost:3000".to_string();
+    let mut http_app = app.clone();
+    let mut tls_server = None;
+    if let Some(config) = tls::Config::from_env() {
+        let acceptor = match config.acceptor() {
+            Ok(acceptor) => acceptor,
+            Err(e) => {
+                eprintln!("Failed to load TLS certificate: {}", e);
+                std::process::exit(1);
+            }
+        };
+        let listener = tokio::net::TcpListener::bind(("0.0.0.0", config.port))
+            .await
+            .unwrap();
+        origin = format!("https://localhost:{}", config.port);
+        println!("Serving HTTPS on port {}", config.port);
+        if config.redirect_http {
+            println!("Redirecting plain HTTP on {} to HTTPS", addr);
+            http_app = tls::redirect_router(config.port, probe_router(state.clone()));
+        }
+        tls_server = Some(tokio::spawn(tls::serve(
+            listener,
+            acceptor,
+            app,
+            shutdown.clone(),
+        )));
+    }
+
+    println!("Server running at {}", origin);
+    println!("Admin interface: {}/admin", origin);
+    if !open_admin && matches!(db::count_users(&pool).await, Ok(0)) {
+        println!("Finish setting up at {}{}", origin, auth::SETUP_PATH);
+    }
+
+    let listener = tokio::net::TcpListener::bind(addr).await.unwrap();
+    let signal = shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        signal.cancel();
+    });
+    axum::serve(
+        listener,
+        http_app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown.cancelled_owned())
+    .await
+    .unwrap();
+    if let Some(server) = tls_server {
+        let _ = server.await;
+    }
+
+    // Let pending writes finish and checkpoint the WAL before exiting
+    pool.close().await;
+    println!("Shut down cleanly");
+}
+
+/// Resolves on Ctrl+C or SIGTERM (what container runtimes send on stop).
+/// The server then stops accepting connections and finishes in-flight
+/// requests before `serve` returns.
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("Failed to listen for Ctrl+C");
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("Failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    println!("Shutting down, finishing in-flight requests");
+}
+
+/// Read a numeric setting from the environment, falling back to `default`
+fn env_or(name: &str, default: u64) -> u64 {
+    std::env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{
+        body::Body,
+        http::{Request, StatusCode},
+    };
+    use http_body_util::BodyExt;
+    use tower::ServiceExt;
+
+    /// Most tests exercise handlers rather than sign-in, so the admin stays
+    /// open until a test creates an account
+    fn test_state(pool: SqlitePool) -> AppState {
+        let mut state = AppState::new(pool);
+        state.open_admin = true;
+        // Tests post forms directly, without a stamp from a rendered one
+        state.spam.min_submit_time = std::time::Duration::ZERO;
+        // Webhook receivers in tests listen on loopback
+        state.webhooks = webhook::Client::new(true);
+        state
+    }
+
+    async fn setup_test_app() -> (Router, Arc<AppState>) {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let state = Arc::new(test_state(pool));
+        let app = create_router(state.clone());
+        (app, state)
+    }
+
+    #[tokio::test]
+    async fn test_index_redirects_to_admin() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers().get("location").unwrap(), "/admin");
+    }
+
+    #[tokio::test]
+    async fn test_signed_results_widget() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Widget", "Description")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "I love it, great work")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Secret details here")
+            .await
+            .unwrap();
+        let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let embed_uri = |sig: &str| format!("/embed/{}/results?sig={}", prompt.id, sig);
+
+        // Off until an editor turns it on
+        let sig = embed::sign("guess", &prompt.id);
+        let response = app.clone().oneshot(get(embed_uri(&sig))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/embed", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let secret = db::get_embed_secret(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let sig = embed::sign(&secret, &prompt.id);
+        assert!(body_str.contains(&format!("http://localhost:3000{}", embed_uri(&sig))));
+
+        let response = app.clone().oneshot(get(embed_uri(&sig))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            "public, max-age=60"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("<span class=\"count\">2</span>"));
+        assert!(body_str.contains("1 positive"));
+        assert!(!body_str.contains("Secret details"));
+
+        // Tampered and missing signatures are refused
+        let flipped = if sig.ends_with('0') { "1" } else { "0" };
+        let tampered = format!("{}{}", &sig[..sig.len() - 1], flipped);
+        let response = app
+            .clone()
+            .oneshot(get(embed_uri(&tampered)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        let response = app
+            .clone()
+            .oneshot(get(format!("/embed/{}/results", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Turning it off revokes the link
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/admin/default/prompt/{}/embed", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let response = app.oneshot(get(embed_uri(&sig))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_atom_feed() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Roadmap", "What next?")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "First <idea>")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Dark mode & more")
+            .await
+            .unwrap();
+        let get = |uri: String| {
+            Request::builder()
+                .uri(uri)
+                .header("host", "localhost:3000")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let feed_uri = |token: &str| {
+            format!(
+                "/admin/default/prompt/{}/feed.atom?token={}",
+                prompt.id, token
+            )
+        };
+        let set_feed = |method: &str| {
+            Request::builder()
+                .method(method)
+                .uri(format!("/admin/default/prompt/{}/feed", prompt.id))
+                .header("host", "localhost:3000")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Off until an editor turns it on
+        let token = feed::sign("guess", &prompt.id);
+        let response = app.clone().oneshot(get(feed_uri(&token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app.clone().oneshot(set_feed("POST")).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let secret = db::get_feed_secret(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .unwrap();
+        let token = feed::sign(&secret, &prompt.id);
+        assert!(body_str.contains(&format!("http://localhost:3000{}", feed_uri(&token))));
+
+        let response = app.clone().oneshot(get(feed_uri(&token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/atom+xml; charset=utf-8"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("<title>Roadmap</title>"));
+        assert_eq!(body_str.matches("<entry>").count(), 2);
+        assert!(body_str.contains("<content type=\"text\">First &lt;idea&gt;</content>"));
+        assert!(body_str.contains("Dark mode &amp; more"));
+
+        // Missing tokens are refused
+        let response = app
+            .clone()
+            .oneshot(get(format!(
+                "/admin/default/prompt/{}/feed.atom",
+                prompt.id
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A new link revokes the old one
+        app.clone().oneshot(set_feed("POST")).await.unwrap();
+        let response = app.clone().oneshot(get(feed_uri(&token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        app.clone().oneshot(set_feed("DELETE")).await.unwrap();
+        assert!(db::get_feed_secret(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_access_code_required() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Invite only", "")
+            .await
+            .unwrap();
+        let set_code = |code: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/prompt/{}/access-code", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(format!("access_code={}", code)))
+                .unwrap()
+        };
+        let get = |query: &str| {
+            Request::builder()
+                .uri(format!("/feedback/{}{}", prompt.id, query))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let submit = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(set_code("a b")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app.clone().oneshot(set_code("Spring-24")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // Without a code the form asks for one instead
+        let response = app.clone().oneshot(get("")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Enter the access code"));
+        assert!(!body_str.contains("name=\"content\""));
+
+        let response = app.clone().oneshot(get("?code=WRONG")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.clone().oneshot(get("?code=spring-24")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("name=\"access_code\" value=\"spring-24\""));
+
+        let response = app.clone().oneshot(submit("content=Hi")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(submit("content=Hi&access_code=spring-24"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let saved = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 1);
+
+        // The JSON endpoint asks for the same code
+        let api_submit = |body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(api_submit(serde_json::json!({"content": "Hi"})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(api_submit(
+                serde_json::json!({"content": "Hi", "access_code": "SPRING-24"}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+
+        // The right code cleared the earlier miss; enough new ones lock the
+        // client out, right code or not
+        for _ in 0..access::MAX_FAILURES {
+            app.clone().oneshot(get("?code=WRONG")).await.unwrap();
+        }
+        let response = app.clone().oneshot(get("?code=Spring-24")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+
+        // Clearing the code opens the form to everyone
+        app.clone().oneshot(set_code("")).await.unwrap();
+        let response = app.clone().oneshot(get("")).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("name=\"content\""));
+    }
+
+    #[tokio::test]
+    async fn test_access_lockout_is_per_forwarded_client() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt_with_options(
+            &pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Members",
+            "",
+            &db::PromptOptions {
+                access_code: access::normalize("Spring-24"),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let mut state = test_state(pool);
+        state.trust_proxy_headers = true;
+        let app = create_router(Arc::new(state));
+        // Everyone arrives from the proxy's address
+        let get = |client: &str, code: &str| {
+            Request::builder()
+                .uri(format!("/feedback/{}?code={}", prompt.id, code))
+                .header("x-forwarded-for", client)
+                .extension(ConnectInfo(SocketAddr::from(([10, 0, 0, 1], 40000))))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Prepending a fresh address each time doesn't dodge the lockout
+        for i in 0..access::MAX_FAILURES {
+            app.clone()
+                .oneshot(get(&format!("192.0.2.{}, 203.0.113.7", i), "WRONG"))
+                .await
+                .unwrap();
+        }
+        let response = app
+            .clone()
+            .oneshot(get("203.0.113.7", "Spring-24"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let response = app.oneshot(get("198.51.100.2", "Spring-24")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_invite_links_submit_once() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Retro", "").await.unwrap();
+        let get = |uri: String| {
+            Request::builder()
+                .uri(uri)
+                .header("host", "localhost:3000")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let submit = |body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/invites", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("count=2"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let invites = invites::list(&state.pool, &prompt.id).await.unwrap();
+        assert_eq!(invites.len(), 2);
+        let token = &invites[0].token;
+
+        // Generating invites alone leaves the form open
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let set_invite_only = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!(
+                    "/admin/default/prompt/{}/invites/required",
+                    prompt.id
+                ))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(set_invite_only("invite_only=on"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // The plain link no longer opens the form
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(submit("content=Sneaky".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}?invite={}", prompt.id, token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains(&format!("name=\"invite\" value=\"{}\"", token)));
+
+        let response = app
+            .clone()
+            .oneshot(submit(format!("content=Went+well&invite={}", token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(submit(format!("content=Again&invite={}", token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GONE);
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}?invite={}", prompt.id, token)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::GONE);
+        let saved = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(saved.len(), 1);
+
+        // The JSON endpoint redeems the other invite the same way
+        let api_submit = |body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let other = &invites[1].token;
+        let response = app
+            .clone()
+            .oneshot(api_submit(serde_json::json!({"content": "Hi"})))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(api_submit(
+                serde_json::json!({"content": "Hi", "invite": other}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app
+            .clone()
+            .oneshot(api_submit(
+                serde_json::json!({"content": "Hi", "invite": other}),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app
+            .clone()
+            .oneshot(get(format!("/admin/default/prompt/{}/invites", prompt.id)))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains(&format!(
+            "http://localhost:3000/feedback/{}?invite={}",
+            prompt.id, token
+        )));
+        assert!(!body_str.contains("Revoke"));
+
+        // Turning it off opens the form to anyone again
+        app.clone().oneshot(set_invite_only("")).await.unwrap();
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_json_submit_cors() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt(&pool, "Widget", "").await.unwrap();
+        let uri = format!("/api/v1/prompts/{}/feedback", prompt.id);
+        let preflight = |origin: &str| {
+            Request::builder()
+                .method("OPTIONS")
+                .uri(&uri)
+                .header("origin", origin)
+                .header("access-control-request-method", "POST")
+                .header("access-control-request-headers", "content-type")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let submit = |origin: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(&uri)
+                .header("origin", origin)
+                .header("content-type", "application/json")
+                .body(Body::from(r#"{"content": "From the app"}"#))
+                .unwrap()
+        };
+
+        // Without configuration the browser gets no CORS headers
+        let app = create_router(Arc::new(test_state(pool.clone())));
+        let response = app
+            .oneshot(submit("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+
+        let mut state = test_state(pool.clone());
+        state.cors = cors::Config::parse("https://app.example.com").unwrap();
+        let app = create_router(Arc::new(state));
+
+        let response = app
+            .clone()
+            .oneshot(preflight("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+        assert!(response.headers()[header::ACCESS_CONTROL_ALLOW_METHODS]
+            .to_str()
+            .unwrap()
+            .contains("POST"));
+
+        let response = app
+            .clone()
+            .oneshot(submit("https://app.example.com"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(
+            response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN],
+            "https://app.example.com"
+        );
+
+        // Other sites aren't let in
+        let response = app
+            .clone()
+            .oneshot(preflight("https://evil.example.net"))
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+
+        // Only the public submit endpoint is opened up
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/workspaces/default/prompts")
+                    .header("origin", "https://app.example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response
+            .headers()
+            .get(header::ACCESS_CONTROL_ALLOW_ORIGIN)
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_graphql_requires_api_token() {
+        let (app, state) = setup_test_app().await;
+        db::create_user(
+            &state.pool,
+            "owner@example.com",
+            &auth::hash_password("password1"),
+            db::Role::Owner,
+        )
+        .await
+        .unwrap();
+        let prompt = db::create_prompt(&state.pool, "Pricing", "").await.unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Too expensive")
+            .await
+            .unwrap();
+        let token = auth::new_api_token();
+        db::create_api_token(
+            &state.pool,
+            "Analytics",
+            &auth::token_digest(&token),
+            db::Role::Viewer,
+            None,
+        )
+        .await
+        .unwrap();
+        let other = db::create_workspace(&state.pool, "other", "Other")
+            .await
+            .unwrap()
+            .unwrap();
+        let scoped = auth::new_api_token();
+        db::create_api_token(
+            &state.pool,
+            "Other team",
+            &auth::token_digest(&scoped),
+            db::Role::Viewer,
+            Some(&other.id),
+        )
+        .await
+        .unwrap();
+        let query = |token: Option<&str>| {
+            let mut request = Request::builder()
+                .method("POST")
+                .uri("/api/v1/workspaces/default/graphql")
+                .header("content-type", "application/json");
+            if let Some(token) = token {
+                request = request.header("authorization", format!("Bearer {}", token));
+            }
+            request
+                .body(Body::from(
+                    serde_json::json!({
+                        "query": "query($n: Int!) { prompts { title feedback(first: $n) { content status } } }",
+                        "variables": {"n": 10}
+                    })
+                    .to_string(),
+                ))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(query(None)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let response = app.clone().oneshot(query(Some(&scoped))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app.clone().oneshot(query(Some(&token))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["data"]["prompts"][0]["title"], "Pricing");
+        assert_eq!(
+            json["data"]["prompts"][0]["feedback"][0]["content"],
+            "Too expensive"
+        );
+        assert_eq!(json["data"]["prompts"][0]["feedback"][0]["status"], "NEW");
+    }
+
+    #[tokio::test]
+    async fn test_static_assets_are_fingerprinted() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Styled", "").await.unwrap();
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get(&format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        let href = assets::url("app.css");
+        assert!(html.contains(&format!("href=\"{}\"", href)));
+        assert!(!html.contains("box-sizing"));
+
+        let response = app.clone().oneshot(get(&href)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/css; charset=utf-8"
+        );
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            assets::IMMUTABLE_CACHE_CONTROL
+        );
+        let etag = response.headers()[header::ETAG].clone();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("box-sizing"));
+
+        // The plain name works too, but must be revalidated
+        let response = app.clone().oneshot(get("/static/app.css")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CACHE_CONTROL],
+            assets::REVALIDATE_CACHE_CONTROL
+        );
+        let request = Request::builder()
+            .uri("/static/app.css")
+            .header(header::IF_NONE_MATCH, etag)
+            .body(Body::empty())
+            .unwrap();
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = app
+            .clone()
+            .oneshot(get("/static/app.00000000.css"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_flash_shown_once_after_redirect() {
+        let (app, _) = setup_test_app().await;
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("title=Roadmap&description=Ideas"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers()[header::LOCATION]
+            .to_str()
+            .unwrap()
+            .to_string();
+        let set_cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        let cookie = set_cookie.split(';').next().unwrap().to_string();
+        assert!(cookie.starts_with("flash="));
+
+        let page = |cookie: Option<&str>| {
+            let mut request = Request::builder()
+                .uri(&location)
+                .header("host", "localhost:3000");
+            if let Some(cookie) = cookie {
+                request = request.header(header::COOKIE, cookie);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+        let response = app.clone().oneshot(page(Some(&cookie))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cleared = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        assert!(cleared.starts_with("flash=;") && cleared.contains("Max-Age=0"));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("alert alert-success"));
+        assert!(html.contains("Prompt created"));
+
+        // Without the cookie the notice is gone
+        let response = app.clone().oneshot(page(None)).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Prompt created"));
+
+        // A garbled cookie is dropped rather than shown
+        let response = app.clone().oneshot(page(Some("flash=%%%"))).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .starts_with("flash=;"));
+    }
+
+    #[tokio::test]
+    async fn test_scheduled_prompt_not_open_yet() {
+        let (app, state) = setup_test_app().await;
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "title=Spring+fair&description=Tell+us&publish_at=2999-05-01T09%3A00",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let prompt = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(
+            prompt.publish_at.as_deref(),
+            Some("2999-05-01T09:00:00+00:00")
+        );
+
+        let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("open yet"));
+        assert!(html.contains("2999-05-01 09:00 UTC"));
+        assert!(!html.contains("<textarea"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=Too+early"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"content": "Too early"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "prompt_scheduled");
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let response = app
+            .clone()
+            .oneshot(get("/admin/default".to_string()))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("<span class=\"status-badge\">scheduled</span>"));
+
+        // Clearing the time opens the form
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/publish-at", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("publish_at="))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("<textarea"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail_top_terms() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Themes", "").await.unwrap();
+        for content in [
+            "Search is slow",
+            "Search results are stale",
+            "Dark mode please",
+            "ÉCOLE d’été closed",
+            "Our école reopened",
+        ] {
+            db::create_feedback(&state.pool, &prompt.id, content)
+                .await
+                .unwrap();
+        }
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        let terms = html.split("Top terms:").nth(1).unwrap();
+        assert!(terms.trim_start().starts_with("<a href=\"?q=search\""));
+        assert!(terms.contains("search (2)"));
+        assert!(terms.contains("dark (1)"));
+        assert!(!terms.split("</div>").next().unwrap().contains(">are ("));
+
+        // Each link finds the entries its word was counted from, whatever
+        // their case or apostrophes
+        for (shown, query, count) in [
+            ("école", "%C3%A9cole", 2),
+            ("d&#x27;été", "d%27%C3%A9t%C3%A9", 1),
+        ] {
+            assert!(terms.contains(&format!("{} ({})", shown, count)));
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/admin/default/prompt/{}?q={}", prompt.id, query))
+                        .header("host", "localhost:3000")
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let html = String::from_utf8(body.to_vec()).unwrap();
+            assert_eq!(
+                ["ÉCOLE d’été closed", "Our école reopened"]
+                    .iter()
+                    .filter(|content| html.contains(*content))
+                    .count(),
+                count
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_links_follow_forwarded_headers() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let prompt = db::create_prompt(&pool, "Proxied", "").await.unwrap();
+        let mut state = test_state(pool);
+        state.trust_proxy_headers = true;
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let detail = |headers: &[(&str, &str)]| {
+            let mut request =
+                Request::builder().uri(format!("/admin/default/prompt/{}", prompt.id));
+            for (name, value) in headers {
+                request = request.header(*name, *value);
+            }
+            request.body(Body::empty()).unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(detail(&[
+                ("host", "127.0.0.1:3000"),
+                ("x-forwarded-proto", "https"),
+                ("x-forwarded-host", "feedback.example.com"),
+            ]))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(&format!(
+            "https://feedback.example.com/feedback/{}",
+            prompt.id
+        )));
+        assert!(!html.contains("127.0.0.1"));
+
+        // A public host name alone doesn't mean HTTPS
+        let response = app
+            .clone()
+            .oneshot(detail(&[("host", "feedback.example.com")]))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(&format!(
+            "http://feedback.example.com/feedback/{}",
+            prompt.id
+        )));
+
+        let response = app
+            .oneshot(detail(&[("x-forwarded-host", "evil.example/x")]))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Without a trusted proxy in front, they're ignored
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Direct", "").await.unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "feedback.example.com")
+                    .header("x-forwarded-proto", "https")
+                    .header("x-forwarded-host", "evil.example")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(&format!(
+            "http://feedback.example.com/feedback/{}",
+            prompt.id
+        )));
+        assert!(!html.contains("evil.example"));
+    }
+
+    #[tokio::test]
+    async fn test_oversized_submission_shows_too_long_page() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.limits.max_body_bytes = 4096;
+        state.limits.oversized_delay = std::time::Duration::ZERO;
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let prompt = db::create_prompt(&state.pool, "Short please", "")
+            .await
+            .unwrap();
+        let novel = "x".repeat(8192);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(format!("content={}", novel)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("Your message is too long (max 10000 characters)."));
+        assert!(html.contains(&format!("href=\"/feedback/{}\"", prompt.id)));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(format!(r#"{{"content": "{}"}}"#, novel)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "payload_too_large");
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_oversized_delay_does_not_hold_a_concurrency_slot() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.limits.max_body_bytes = 4096;
+        state.limits.max_concurrent_requests = 1;
+        state.limits.oversized_delay = std::time::Duration::from_millis(500);
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let prompt = db::create_prompt(&state.pool, "Short please", "")
+            .await
+            .unwrap();
+
+        let started = std::time::Instant::now();
+        let oversized = tokio::spawn(
+            app.clone().oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .header("hx-request", "true")
+                    .body(Body::from(format!("content={}", "x".repeat(8192))))
+                    .unwrap(),
+            ),
+        );
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        // The only slot is free again while the oversized post waits
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(!oversized.is_finished());
+
+        let response = oversized.await.unwrap().unwrap();
+        assert!(started.elapsed() >= state.limits.oversized_delay);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Your message is too long"));
+    }
+
+    #[tokio::test]
+    async fn test_nps_prompt() {
+        let (app, state) = setup_test_app().await;
+        let post = |uri: String, content_type: &str, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let form = "application/x-www-form-urlencoded";
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/new".to_string(),
+                form,
+                "title=Recommend&description=Tell+us&kind=nps".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let prompt = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .remove(0);
+        assert!(prompt.is_nps());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(r#"name="score" value="10""#));
+        assert!(!html.contains(r#"name="content" required"#));
+
+        // The score is required, the comment isn't
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/feedback/{}", prompt.id),
+                form,
+                "content=".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("Please choose a score from 0 to 10."));
+        for body in [
+            "content=&score=10",
+            "content=Too+pricey&score=3",
+            "content=&score=9",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(post(
+                    format!("/feedback/{}", prompt.id),
+                    form,
+                    body.to_string(),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", body);
+        }
+
+        let api = format!("/api/v1/prompts/{}/feedback", prompt.id);
+        let response = app
+            .clone()
+            .oneshot(post(
+                api.clone(),
+                "application/json",
+                r#"{"content": "No score", "score": 11}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["fields"][0]["field"], "score");
+        let response = app
+            .clone()
+            .oneshot(post(api, "application/json", r#"{"score": 7}"#.to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["nps_score"], 7);
+
+        // 2 promoters, 1 passive, 1 detractor: (2 - 1) / 4 = 25
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}/stats", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("Net Promoter Score"));
+        assert!(html.contains(r#"<div class="stat-value">25</div>"#));
+        assert!(html.contains(r#"title="10: 1""#));
+    }
+
+    #[tokio::test]
+    async fn test_reactions_prompt() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Kiosk",
+            "Front desk",
+            &db::PromptOptions {
+                kind: db::PromptKind::Reactions,
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(r#"name="reaction" value="love""#));
+        assert!(!html.contains("btn btn-success"));
+
+        let submit = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        for body in [
+            "content=&reaction=love",
+            "content=Long+queue&reaction=unhappy",
+        ] {
+            let response = app.clone().oneshot(submit(body)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "{}", body);
+        }
+        let response = app
+            .clone()
+            .oneshot(submit("content=&reaction=angry"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"reaction": "love"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["reaction"], "love");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}/stats", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("😍 2"));
+        assert!(html.contains("Not good (33%)"));
+    }
+
+    #[tokio::test]
+    async fn test_response_cap() {
+        let (app, state) = setup_test_app().await;
+        let post = |uri: String, content_type: &str, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", content_type)
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let form = "application/x-www-form-urlencoded";
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/new".to_string(),
+                form,
+                "title=Paid+study&description=Tell+us&max_responses=0".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/new".to_string(),
+                form,
+                "title=Paid+study&description=Tell+us&max_responses=3".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let prompt = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(prompt.max_responses, Some(3));
+
+        // However many race for the last places, only three get in; spam
+        // doesn't take a place
+        let spam = db::FeedbackOptions {
+            spam_reason: Some("honeypot".to_string()),
+            ..Default::default()
+        };
+        db::create_feedback_with_options(&state.pool, &prompt.id, "Buy now", &spam)
+            .await
+            .unwrap();
+        let submissions: Vec<_> = (0..10)
+            .map(|i| {
+                let pool = state.pool.clone();
+                let id = prompt.id.clone();
+                tokio::spawn(async move {
+                    db::create_feedback_with_options(
+                        &pool,
+                        &id,
+                        &format!("Answer {}", i),
+                        &db::FeedbackOptions::default(),
+                    )
+                    .await
+                })
+            })
+            .collect();
+        let mut refused = 0;
+        for submission in submissions {
+            if let Err(e) = submission.await.unwrap() {
+                assert!(db::is_prompt_full(&e));
+                refused += 1;
+            }
+        }
+        assert_eq!(refused, 7);
+        assert_eq!(
+            db::accepted_responses(&state.pool, &prompt.id)
+                .await
+                .unwrap(),
+            3
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("This survey is full"));
+        assert!(!html.contains(r#"name="content""#));
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/feedback/{}", prompt.id),
+                form,
+                "content=One+more".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/api/v1/prompts/{}/feedback", prompt.id),
+                "application/json",
+                r#"{"content": "One more"}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "prompt_full");
+        assert_eq!(
+            db::accepted_responses(&state.pool, &prompt.id)
+                .await
+                .unwrap(),
+            3
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("Response limit: 3 of 3 in."));
+
+        // Deleting entries doesn't give their places back
+        let ids: Vec<String> = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fb| fb.id)
+            .collect();
+        db::delete_feedback_bulk(&state.pool, db::DEFAULT_WORKSPACE_ID, &ids)
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/api/v1/prompts/{}/feedback", prompt.id),
+                "application/json",
+                r#"{"content": "One more"}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        // A full merge prompt still takes updates to an existing entry
+        let options = db::PromptOptions {
+            merge_responses: true,
+            max_responses: Some(1),
+            ..Default::default()
+        };
+        let pulse = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Pulse",
+            "Weekly",
+            &options,
+        )
+        .await
+        .unwrap();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/api/v1/workspaces/default/prompts/{}/prefill-links",
+                        pulse.id
+                    ))
+                    .header("host", "localhost:3000")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "values": { "email": "ann@example.com" } }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let link: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = link["token"].as_str().unwrap().to_string();
+        for content in ["Busy week", "Calmer now"] {
+            let response = app
+                .clone()
+                .oneshot(post(
+                    format!("/api/v1/prompts/{}/feedback", pulse.id),
+                    "application/json",
+                    serde_json::json!({ "content": content, "prefill": token }).to_string(),
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::CREATED);
+        }
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}?prefill={}", pulse.id, token))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains(r#"name="content""#));
+        let response = app
+            .oneshot(post(
+                format!("/api/v1/prompts/{}/feedback", pulse.id),
+                "application/json",
+                r#"{"content": "Someone new"}"#.to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &pulse.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback_list.len(), 1);
+        assert_eq!(feedback_list[0].content, "Calmer now");
+    }
+
+    #[tokio::test]
+    async fn test_bulk_feedback_actions() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Launch", "Desc")
+            .await
+            .unwrap();
+        let mut ids = Vec::new();
+        for content in ["Buy now", "Cheap pills", "Nice launch"] {
+            let fb = db::create_feedback(&state.pool, &prompt.id, content)
+                .await
+                .unwrap();
+            ids.push(fb.id);
+        }
+        let bulk = |body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/prompt/{}/feedback/bulk", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let selected = format!("select_{}=1&select_{}=1", ids[0], ids[1]);
+
+        let response = app
+            .clone()
+            .oneshot(bulk(format!("action=export&{}", selected)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "text/csv; charset=utf-8"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        assert!(csv.contains("Buy now") && csv.contains("Cheap pills"));
+        assert!(!csv.contains("Nice launch"));
+
+        let response = app
+            .clone()
+            .oneshot(bulk(format!("action=tag&tag=Spam&{}", selected)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let tagged = db::list_feedback(
+            &state.pool,
+            &prompt.id,
+            &db::FeedbackFilter {
+                tag: Some("spam".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(tagged.len(), 2);
+
+        let response = app
+            .clone()
+            .oneshot(bulk("action=delete".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(bulk(format!("action=delete&{}", selected)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let left = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].content, "Nice launch");
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains(&format!("name=\"select_{}\"", ids[2])));
+    }
+
+    #[tokio::test]
+    async fn test_detail_page_filters_and_sorts_feedback() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Sorting", "Desc")
+            .await
+            .unwrap();
+        for (content, created_at) in [
+            ("First alpha", "2024-01-01T00:00:00+00:00"),
+            ("Second beta", "2024-02-01T00:00:00+00:00"),
+            ("Third alpha", "2024-03-01T00:00:00+00:00"),
+        ] {
+            let fb = db::create_feedback(&state.pool, &prompt.id, content)
+                .await
+                .unwrap();
+            sqlx::query("UPDATE feedback SET created_at = ? WHERE id = ?")
+                .bind(created_at)
+                .bind(&fb.id)
+                .execute(&state.pool)
+                .await
+                .unwrap();
+        }
+        let page = |query: &str| {
+            Request::builder()
+                .uri(format!("/admin/default/prompt/{}?{}", prompt.id, query))
+                .header("host", "localhost:3000")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(page("q=alpha&sort=oldest"))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let first = body_str.find("First alpha").unwrap();
+        let third = body_str.find("Third alpha").unwrap();
+        assert!(first < third);
+        assert!(!body_str.contains("Second beta"));
+        assert!(body_str.contains("name=\"q\" value=\"alpha\""));
+        assert!(body_str.contains("<option value=\"oldest\" selected>"));
+        assert!(body_str.contains("Clear filters"));
+
+        let response = app
+            .oneshot(page("from=2024-01-15&to=2024-02-01&rating_min=bad"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Second beta"));
+        assert!(!body_str.contains("First alpha"));
+        assert!(!body_str.contains("Third alpha"));
+    }
+
+    #[tokio::test]
+    async fn test_public_board_voting() {
+        let (app, state) = setup_test_app().await;
+        let private = db::create_prompt(&state.pool, "Private", "Description")
+            .await
+            .unwrap();
+        let options = db::PromptOptions {
+            public_board: true,
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Ideas",
+            "What should we build?",
+            &options,
+        )
+        .await
+        .unwrap();
+        let first = db::create_feedback(&state.pool, &prompt.id, "Dark mode")
+            .await
+            .unwrap();
+        let second = db::create_feedback(&state.pool, &prompt.id, "Offline support")
+            .await
+            .unwrap();
+        let board_uri = format!("/feedback/{}/board", prompt.id);
+        let vote = |cookie: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}/board/{}/vote", prompt.id, first.id))
+                .header(header::COOKIE, cookie)
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Boards are opt-in per prompt
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}/board", private.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // A new browser is handed a voter cookie
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(&board_uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let cookie = response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+        assert!(cookie.starts_with(auth::VOTER_COOKIE));
+
+        // Voting twice from the same browser counts once
+        for _ in 0..2 {
+            let response = app.clone().oneshot(vote(&cookie)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let body_str = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body_str.contains("&#9650; 1"));
+            assert!(body_str.contains("disabled"));
+        }
+        let other = format!("{}=someone-else", auth::VOTER_COOKIE);
+        app.clone().oneshot(vote(&other)).await.unwrap();
+
+        // Most upvoted first, showing what this browser already voted for
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(&board_uri)
+                    .header(header::COOKIE, &cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert!(response.headers().get(header::SET_COOKIE).is_none());
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let dark = body_str.find("Dark mode").unwrap();
+        let offline = body_str.find("Offline support").unwrap();
+        assert!(dark < offline);
+        assert!(body_str.contains("&#9650; 2"));
+        assert!(body_str.contains("You upvoted this"));
+
+        // Entries of other prompts can't be voted on through this board
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}/board/{}/vote", private.id, second.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Votes go with the prompt
+        assert!(
+            db::delete_prompt(&state.pool, db::DEFAULT_WORKSPACE_ID, &prompt.id)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_abuse_reports_suspend_prompt_until_reinstated() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.abuse_suspend_threshold = 2;
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+
+        let prompt = db::create_prompt(&state.pool, "Verify your bank login", "Enter password")
+            .await
+            .unwrap();
+        let report = |cookie: &str, body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}/report", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .header(header::COOKIE, cookie)
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let form_status = || async {
+            app.clone()
+                .oneshot(
+                    Request::builder()
+                        .uri(format!("/feedback/{}", prompt.id))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap()
+                .status()
+        };
+        let voter = |name: &str| format!("{}={}", auth::VOTER_COOKIE, name);
+
+        let response = app
+            .clone()
+            .oneshot(report(&voter("a"), "reason=bogus"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // The same browser reporting twice counts once
+        for _ in 0..2 {
+            let response = app
+                .clone()
+                .oneshot(report(
+                    &voter("a"),
+                    "reason=phishing&details=Asks+for+passwords",
+                ))
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        assert_eq!(form_status().await, StatusCode::OK);
+
+        app.clone()
+            .oneshot(report(&voter("b"), "reason=spam"))
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("bank login"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"content":"hunter2"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        // The queue shows both reports
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/abuse")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("2 open reports"));
+        assert!(body_str.contains("Asks for passwords"));
+
+        // Reopening the prompt from its workspace doesn't lift the suspension
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/status", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("status=open"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(form_status().await, StatusCode::FORBIDDEN);
+
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/abuse/{}/reinstate", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(form_status().await, StatusCode::OK);
+        assert!(db::get_abuse_queue(&state.pool).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_health_and_readiness() {
+        let (app, state) = setup_test_app().await;
+        let get = |uri: &'static str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app.clone().oneshot(get("/healthz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(get("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // Still alive, but no longer ready once the database is gone
+        state.pool.close().await;
+        let response = app.clone().oneshot(get("/healthz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    }
+
+    #[tokio::test]
+    async fn test_probes_answer_on_the_redirect_listener() {
+        let (_, state) = setup_test_app().await;
+        let app = tls::redirect_router(3443, probe_router(state));
+        let get = |uri: &'static str| {
+            Request::builder()
+                .uri(uri)
+                .header("host", "feedback.example.com")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(get("/healthz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.clone().oneshot(get("/readyz")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let response = app.oneshot(get("/admin")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PERMANENT_REDIRECT);
+        assert_eq!(
+            response.headers()["location"],
+            "https://feedback.example.com:3443/admin"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_list_empty() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Feedback Prompts"));
+        assert!(body_str.contains("No prompts yet"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_list_with_prompts() {
+        let (app, state) = setup_test_app().await;
+
+        // Create a prompt directly in the database
+        db::create_prompt(&state.pool, "Test Prompt", "Test Description")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Test Prompt"));
+        assert!(body_str.contains("Test Description"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_archive_hides_prompt_from_list() {
+        let (app, state) = setup_test_app().await;
+
+        let old = db::create_prompt(&state.pool, "Q1 retro", "Old news")
+            .await
+            .unwrap();
+        db::create_prompt(&state.pool, "Q2 retro", "Current")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &old.id, "Keep me")
+            .await
+            .unwrap();
+
+        let set_status = |status: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/prompt/{}/status", old.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(format!("status={}", status)))
+                .unwrap()
+        };
+        let page = |uri: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap())
+                    .await
+                    .unwrap();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+
+        let response = app.clone().oneshot(set_status("archived")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let list = page("/admin/default").await;
+        assert!(list.contains("Q2 retro"));
+        assert!(!list.contains("Q1 retro"));
+        assert!(list.contains("Archived (1)"));
+        let archived = page("/admin/default/archived").await;
+        assert!(archived.contains("Q1 retro"));
+        assert!(!archived.contains("Q2 retro"));
+        assert_eq!(
+            db::get_feedback_for_prompt(&state.pool, &old.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Unarchiving brings it back, still closed to new feedback
+        app.clone().oneshot(set_status("closed")).await.unwrap();
+        let list = page("/admin/default").await;
+        assert!(list.contains("Q1 retro"));
+        assert!(!list.contains("Archived ("));
+        let prompt = db::get_prompt_by_id(&state.pool, &old.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(prompt.archived_at.is_none() && prompt.closed_at.is_some());
+        assert!(page("/admin/default/archived")
+            .await
+            .contains("Nothing archived"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_new_form() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/new")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Create New Prompt"));
+        assert!(body_str.contains("Title"));
+        assert!(body_str.contains("Description"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_new_submit() {
+        let (app, state) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("title=New+Prompt&description=New+Description"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        // Verify prompt was created
+        let prompts = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 1);
+        assert_eq!(prompts[0].title, "New Prompt");
+        assert_eq!(prompts[0].description, "New Description");
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Detail Test", "Detail Description")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Detail Test"));
+        assert!(body_str.contains("Detail Description"));
+        assert!(body_str.contains(&format!("/feedback/{}", prompt.id)));
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail_not_found() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/prompt/nonexistent-id")
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Prompt not found"));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_form() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Feedback Test", "Give us feedback")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Feedback Test"));
+        assert!(body_str.contains("Give us feedback"));
+        assert!(body_str.contains("Your Feedback"));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_form_renders_markdown_description() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(
+            &state.pool,
+            "Markdown",
+            "See the [roadmap](https://example.com/roadmap) **first**.<script>alert(1)</script>",
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("<strong>first</strong>"));
+        assert!(body_str.contains("href=\"https://example.com/roadmap\""));
+        assert!(!body_str.contains("alert(1)"));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_form_not_found() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/feedback/nonexistent-id")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Prompt not found"));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_submit() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Submit Test", "Description")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=This+is+my+feedback"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Thank you!"));
+        assert!(body_str.contains("Your feedback has been submitted successfully"));
+
+        // Verify feedback was created
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback_list.len(), 1);
+        assert_eq!(feedback_list[0].content, "This is my feedback");
+    }
+
+    #[tokio::test]
+    async fn test_public_pages_follow_respondent_language() {
+        let (app, state) = setup_test_app().await;
+        let negotiated = db::create_prompt(&state.pool, "Negotiated", "Description")
+            .await
+            .unwrap();
+        let options = db::PromptOptions {
+            language: Some("fr".to_string()),
+            ..Default::default()
+        };
+        let french =
+            db::create_prompt_with_options(&state.pool, "default", "French", "Desc", &options)
+                .await
+                .unwrap();
+
+        let page = |id: String, accept: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(format!("/feedback/{}", id))
+                            .header("accept-language", accept)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                assert_eq!(response.headers()["vary"], "accept-language");
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+        let body = page(negotiated.id.clone(), "de-CH, de;q=0.9, en;q=0.5").await;
+        assert!(body.contains(r#"<html lang="de">"#));
+        assert!(body.contains("Ihr Feedback"));
+        assert!(body.contains("Feedback absenden"));
+        let body = page(negotiated.id.clone(), "ja").await;
+        assert!(body.contains(r#"<html lang="en">"#));
+        assert!(body.contains("Your Feedback"));
+        // A prompt's own language wins over the browser's
+        let body = page(french.id.clone(), "de").await;
+        assert!(body.contains(r#"<html lang="fr">"#));
+        assert!(body.contains("Votre avis"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", negotiated.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .header("accept-language", "es")
+                    .body(Body::from("content=Hola"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("¡Gracias!"));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/workspaces/default/prompts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"title":"Klingon","language":"tlh"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["fields"][0]["field"], "language");
+    }
+
+    #[tokio::test]
+    async fn test_translated_prompt_content() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Your visit", "How was it?")
+            .await
+            .unwrap();
+        let spec = db::FieldSpec {
+            label: "Order number".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: false,
+            rules: Default::default(),
+        };
+        let order = db::create_field(&state.pool, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&state.pool, &prompt.id, &order.id)
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/translations", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(format!(
+                        "language=de&title=Ihr+Besuch&description=Wie+war+es%3F&label_{}=Bestellnummer",
+                        order.id
+                    )))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Ihr Besuch"));
+
+        let page = |uri: String, accept: &'static str| {
+            let app = app.clone();
+            async move {
+                let response = app
+                    .oneshot(
+                        Request::builder()
+                            .uri(uri)
+                            .header("accept-language", accept)
+                            .body(Body::empty())
+                            .unwrap(),
+                    )
+                    .await
+                    .unwrap();
+                let body = response.into_body().collect().await.unwrap().to_bytes();
+                String::from_utf8(body.to_vec()).unwrap()
+            }
+        };
+        let form_uri = format!("/feedback/{}", prompt.id);
+        let body = page(form_uri.clone(), "de-DE,de;q=0.9").await;
+        assert!(body.contains("<h1>Ihr Besuch</h1>"));
+        assert!(body.contains("Wie war es?"));
+        assert!(body.contains("Bestellnummer"));
+        assert!(body.contains("Ihr Feedback"));
+        assert!(body.contains(r#"href="?lang=en""#));
+        // French isn't offered, so the prompt shows as written
+        let body = page(form_uri.clone(), "fr").await;
+        assert!(body.contains("<h1>Your visit</h1>"));
+        assert!(body.contains("Order number"));
+        // The switcher beats the browser
+        let body = page(format!("{}?lang=en", form_uri), "de").await;
+        assert!(body.contains("<h1>Your visit</h1>"));
+        assert!(body.contains(r#"href="?lang=de""#));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(&form_uri)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=Gut&lang=de"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Vielen Dank!"));
+
+        let api_uri = format!(
+            "/api/v1/workspaces/default/prompts/{}/translations",
+            prompt.id
+        );
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("{}/es", api_uri))
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"title":"Tu visita","field_labels":{"nope":"No"}}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["fields"][0]["field"], "field_labels.nope");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("PUT")
+                    .uri(format!("{}/en", api_uri))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"title":"Your visit"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("{}/de", api_uri))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NO_CONTENT);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(&api_uri)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json, serde_json::json!([]));
+    }
+
+    #[tokio::test]
+    async fn test_spam_submissions_are_quarantined() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.spam = spam::Screen::default();
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let prompt = db::create_prompt(&state.pool, "Spam Test", "Description")
+            .await
+            .unwrap();
+
+        let secret = db::get_or_create_prefill_secret(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        let now = chrono::Utc::now().timestamp();
+        let stamp = |at: i64| spam::stamp(&secret, &prompt.id, at);
+        for body in [
+            format!(
+                "content=Hello&website=http%3A%2F%2Fspam.example&rendered_at={}",
+                stamp(now - 60)
+            ),
+            format!("content=Too+quick&rendered_at={}", stamp(now)),
+            // A bot that drops or backdates the stamp
+            "content=No+stamp".to_string(),
+            format!("content=Backdated&rendered_at={}", now - 60),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/feedback/{}", prompt.id))
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert!(String::from_utf8(body.to_vec())
+                .unwrap()
+                .contains("Thank you!"));
+        }
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/spam")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("honeypot field filled in"));
+        assert!(body_str.contains("Too quick"));
+        assert!(body_str.contains("missing or forged form timestamp"));
+
+        let spam = db::get_spam(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        let quick = spam
+            .iter()
+            .find(|e| e.feedback.content == "Too quick")
+            .unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/spam/{}/release", quick.feedback.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback_list.len(), 1);
+        assert_eq!(feedback_list[0].content, "Too quick");
+    }
+
+    #[tokio::test]
+    async fn test_captcha_is_verified_before_storing() {
+        // Stand-in siteverify endpoint that accepts a single token
+        let verifier = Router::new().route(
+            "/siteverify",
+            post(|Form(params): Form<HashMap<String, String>>| async move {
+                assert_eq!(params["secret"], "shh");
+                axum::Json(serde_json::json!({ "success": params["response"] == "solved" }))
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let verifier_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, verifier).await.unwrap() });
+
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.captcha = Some(captcha::Config {
+            provider: captcha::Provider::Turnstile,
+            site_key: "site-key".to_string(),
+            secret: "shh".to_string(),
+            verify_url: Some(format!("http://{}/siteverify", verifier_addr)),
+        });
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let prompt = db::create_prompt(&state.pool, "Captcha", "Description")
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("challenges.cloudflare.com/turnstile"));
+        assert!(body_str.contains(r#"class="cf-turnstile" data-sitekey="site-key""#));
+
+        for (token, expected) in [
+            ("", "Please complete the CAPTCHA"),
+            ("forged", "Please complete the CAPTCHA"),
+            ("solved", "Thank you!"),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/feedback/{}", prompt.id))
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(Body::from(format!(
+                            "content=Hello&cf-turnstile-response={}",
+                            token
+                        )))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            assert!(String::from_utf8(body.to_vec()).unwrap().contains(expected));
+        }
+
+        // The JSON endpoint can't be used to skip the challenge
+        for (body, expected) in [
+            (
+                serde_json::json!({ "content": "Hello" }),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                serde_json::json!({ "content": "Hello", "captcha_token": "forged" }),
+                StatusCode::FORBIDDEN,
+            ),
+            (
+                serde_json::json!({ "content": "Hello", "captcha_token": "solved" }),
+                StatusCode::CREATED,
+            ),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                        .header("content-type", "application/json")
+                        .body(Body::from(body.to_string()))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), expected);
+        }
+
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback_list.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_repeated_submission_is_stored_once() {
+        let (app, state) = setup_test_app().await;
+        let options = db::PromptOptions {
+            content_rules: rules::FieldRules {
+                max_length: Some(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Once",
+            "Description",
+            &options,
+        )
+        .await
+        .unwrap();
+
+        let mut responses = Vec::new();
+        for body in [
+            // Rejected, which must not use up the token
+            "content=Far+too+long+for+this&submission_token=abc",
+            "content=Hello&submission_token=abc",
+            "content=Hello&submission_token=abc",
+            "content=Hi+again&submission_token=def",
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/feedback/{}", prompt.id))
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            responses.push(String::from_utf8(body.to_vec()).unwrap());
+        }
+        assert!(responses[0].contains("must be at most 10 characters"));
+        assert!(responses[1..].iter().all(|r| r.contains("Thank you!")));
+        // Each success hands the form a new token
+        assert!(responses[1].contains(r#"id="submission-token""#));
+        assert_ne!(responses[1], responses[2]);
+
+        let mut contents: Vec<String> = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|fb| fb.content)
+            .collect();
+        contents.sort();
+        assert_eq!(contents, vec!["Hello", "Hi again"]);
+    }
+
+    #[tokio::test]
+    async fn test_feedback_submit_prompt_not_found() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/feedback/nonexistent-id")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=This+is+my+feedback"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Prompt not found"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail_shows_feedback() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "With Feedback", "Description")
+            .await
+            .unwrap();
+
+        db::create_feedback(&state.pool, &prompt.id, "First response")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Second response")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("First response"));
+        assert!(body_str.contains("Second response"));
+        assert!(body_str.contains("Feedback Responses"));
+        assert!(body_str.contains("id=\"feedback-count\">2</span>"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_new_submit_with_webhook() {
+        let (app, state) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "title=Hooked&description=Desc&webhook_url=https%3A%2F%2Fexample.com%2Fhook",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let prompts = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        assert_eq!(
+            prompts[0].webhook_url.as_deref(),
+            Some("https://example.com/hook")
+        );
+        assert!(prompts[0].webhook_secret.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_admin_new_submit_rejects_invalid_webhook() {
+        let (app, state) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(
+                        "title=T&description=D&webhook_url=ftp%3A%2F%2Fnope",
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Invalid webhook URL"));
+        assert!(db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_feedback_submit_sends_signed_receipt() {
+        use axum::body::Bytes;
+        use tokio::sync::mpsc;
+
+        // Local receiver standing in for the respondent's product
+        let (tx, mut rx) = mpsc::channel::<(HeaderMap, Bytes)>(1);
+        let receiver = Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: Bytes| {
+                let tx = tx.clone();
+                async move {
+                    tx.send((headers, body)).await.unwrap();
+                    StatusCode::OK
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let (app, state) = setup_test_app().await;
+        let options = db::PromptOptions {
+            webhook_url: Some(format!("http://{}/hook", receiver_addr)),
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Hooked",
+            "Desc",
+            &options,
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=hello"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let (headers, body) = tokio::time::timeout(std::time::Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap()
+            .unwrap();
+
+        let secret = prompt.webhook_secret.unwrap();
+        assert_eq!(
+            headers.get(webhook::SIGNATURE_HEADER).unwrap(),
+            webhook::sign(&secret, &body).as_str()
+        );
+
+        let receipt: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(receipt["feedback_id"], feedback_list[0].id.as_str());
+        assert_eq!(receipt["prompt_id"], prompt.id.as_str());
+        assert_eq!(receipt["created_at"], feedback_list[0].created_at.as_str());
+        assert_eq!(
+            receipt["content_sha256"],
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_reply_is_listed_and_emailed() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.mailer = Some(email::Mailer::stub("team@example.com").unwrap());
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+
+        let prompt = db::create_prompt(&state.pool, "Checkout", "Description")
+            .await
+            .unwrap();
+        let options = db::FeedbackOptions {
+            respondent_email: Some("ana@example.com".to_string()),
+            ..Default::default()
+        };
+        let fb = db::create_feedback_with_options(
+            &state.pool,
+            &prompt.id,
+            "The coupon field is hidden",
+            &options,
+        )
+        .await
+        .unwrap();
+        let reply = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/feedback/{}/replies", fb.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .header("hx-request", "true")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(reply(
+                "content=Thanks%2C+fixed+in+today%27s+release&send_email=on",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Thanks, fixed in today&#x27;s release"));
+        assert!(body_str.contains("Emailed to respondent"));
+
+        let sent = state.mailer.as_ref().unwrap().sent().await;
+        assert_eq!(sent.len(), 1);
+        assert!(sent[0].contains("To: ana@example.com"));
+        assert!(sent[0].contains("Thanks, fixed in today's release"));
+        assert!(sent[0].contains("> The coupon field is hidden"));
+
+        // Without the box ticked the reply is only stored
+        let response = app
+            .clone()
+            .oneshot(reply("content=Follow-up+note"))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Follow-up note"));
+        assert_eq!(body_str.matches("Emailed to respondent").count(), 1);
+        assert_eq!(state.mailer.as_ref().unwrap().sent().await.len(), 1);
+
+        let response = app.oneshot(reply("content=++")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Replies go with the prompt
+        assert!(
+            db::delete_prompt(&state.pool, db::DEFAULT_WORKSPACE_ID, &prompt.id)
+                .await
+                .unwrap()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail_notes_cross_posted_feedback() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.detect_duplicates = true;
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+
+        let bugs = db::create_prompt(&state.pool, "Bug reports", "Description")
+            .await
+            .unwrap();
+        let ideas = db::create_prompt(&state.pool, "Ideas", "Description")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &bugs.id, "Export  crashes on Safari")
+            .await
+            .unwrap();
+        let copy = db::create_feedback(&state.pool, &ideas.id, "export crashes\non safari")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &bugs.id, "Search is slow")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", bugs.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        // Case and whitespace differences don't hide the repost
+        assert_eq!(body_str.matches("Also submitted to:").count(), 1);
+        assert!(body_str.contains(&format!(
+            "/admin/default/prompt/{}#feedback-{}\">Ideas</a>",
+            ideas.id, copy.id
+        )));
+    }
+
+    #[tokio::test]
+    async fn test_notification_rules_route_feedback() {
+        let (sent, mut received) = tokio::sync::mpsc::unbounded_channel::<String>();
+        let chat = Router::new().route(
+            "/chat",
+            post(move |Json(message): Json<serde_json::Value>| async move {
+                sent.send(message["text"].as_str().unwrap().to_string())
+                    .unwrap();
+                "ok"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let chat_url = format!("http://{}/chat", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, chat).await.unwrap() });
+
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Support", "How did we do?")
+            .await
+            .unwrap();
+        let spec = db::FieldSpec {
+            label: "Rating".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: true,
+            rules: Default::default(),
+        };
+        let rating = db::create_field(&state.pool, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&state.pool, &prompt.id, &rating.id)
+            .await
+            .unwrap();
+
+        let post_form = |uri: String, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let rules_uri = format!("/admin/default/prompt/{}/notifications", prompt.id);
+        let response = app
+            .clone()
+            .oneshot(post_form(
+                rules_uri.clone(),
+                format!(
+                    "condition=answer_at_most&field_id={}&operand=&delivery=immediate&target_url={}",
+                    rating.id, chat_url
+                ),
+            ))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Enter a number to compare the answer with"));
+        app.clone()
+            .oneshot(post_form(
+                rules_uri.clone(),
+                format!(
+                    "condition=answer_at_most&field_id={}&operand=2&delivery=immediate&target_url={}",
+                    rating.id, chat_url
+                ),
+            ))
+            .await
+            .unwrap();
+        let response = app
+            .clone()
+            .oneshot(post_form(
+                rules_uri,
+                format!("condition=always&delivery=digest&target_url={}", chat_url),
+            ))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body.contains("Rating \u{2264} 2"));
+        assert!(body.contains("Weekly digest"));
+
+        // A low rating is posted right away
+        let submit = |content: &str, value: &str| {
+            post_form(
+                format!("/feedback/{}", prompt.id),
+                format!("content={}&field_{}={}", content, rating.id, value),
+            )
+        };
+        app.clone()
+            .oneshot(submit("Slow+replies", "1"))
+            .await
+            .unwrap();
+        let message = tokio::time::timeout(std::time::Duration::from_secs(5), received.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(message.contains("Slow replies"));
+        assert!(message.contains("Rating: 1"));
+
+        // Anything else waits for the digest, which isn't due until a week on
+        app.oneshot(submit("All+good", "5")).await.unwrap();
+        let now = chrono::Utc::now();
+        assert_eq!(
+            notify::send_due_digests(&state.pool, &state.http_client, now)
+                .await
+                .unwrap(),
+            0
+        );
+        let next_week = now + chrono::Duration::days(8);
+        let mut digests = 0;
+        for _ in 0..50 {
+            digests = notify::send_due_digests(&state.pool, &state.http_client, next_week)
+                .await
+                .unwrap();
+            if digests > 0 {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(digests, 1);
+        let message = received.recv().await.unwrap();
+        assert!(message.starts_with("Digest for \u{201c}Support\u{201d}: 1 new response"));
+        assert!(message.contains("All good"));
+        assert!(!message.contains("Slow replies"));
+    }
+
+    #[tokio::test]
+    async fn test_webhook_test_event_reports_each_attempt() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        // Fails once, then accepts, so the retry shows up in the report
+        let calls = Arc::new(AtomicUsize::new(0));
+        let receiver = Router::new().route(
+            "/hook",
+            post({
+                let calls = calls.clone();
+                move |body: axum::body::Bytes| async move {
+                    let receipt: serde_json::Value = serde_json::from_slice(&body).unwrap();
+                    assert_eq!(receipt["test"], true);
+                    if calls.fetch_add(1, Ordering::SeqCst) == 0 {
+                        (StatusCode::SERVICE_UNAVAILABLE, "warming up")
+                    } else {
+                        (StatusCode::OK, "thanks")
+                    }
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let (app, state) = setup_test_app().await;
+        let options = db::PromptOptions {
+            webhook_url: Some(format!("http://{}/hook", receiver_addr)),
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Hooked",
+            "Desc",
+            &options,
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/webhook/test", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Test event delivered"));
+        assert!(body_str.contains("HTTP 503 Service Unavailable"));
+        assert!(body_str.contains("HTTP 200 OK"));
+        assert!(body_str.contains("thanks"));
+
+        // Nothing was recorded as real feedback
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_webhook_test_event_refuses_internal_targets() {
+        let receiver = Router::new().route("/hook", post(|| async { "internal secrets" }));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.webhooks = webhook::Client::default();
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+
+        for target in [
+            format!("http://{}/hook", receiver_addr),
+            format!("http://localhost:{}/hook", receiver_addr.port()),
+            "http://169.254.169.254/latest/meta-data/".to_string(),
+        ] {
+            let options = db::PromptOptions {
+                webhook_url: Some(target),
+                ..Default::default()
+            };
+            let prompt = db::create_prompt_with_options(
+                &state.pool,
+                db::DEFAULT_WORKSPACE_ID,
+                "Hooked",
+                "Desc",
+                &options,
+            )
+            .await
+            .unwrap();
+
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/admin/default/prompt/{}/webhook/test", prompt.id))
+                        .body(Body::empty())
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let body_str = String::from_utf8(body.to_vec()).unwrap();
+            assert!(body_str.contains("Refused: internal address"));
+            assert!(!body_str.contains("internal secrets"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_slow_requests_time_out_and_excess_requests_are_shed() {
+        // A webhook receiver that never answers in time
+        let receiver = Router::new().route(
+            "/hook",
+            post(|| async {
+                tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+                "late"
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let receiver_addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, receiver).await.unwrap() });
+
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.limits = limits::Limits {
+            request_timeout: std::time::Duration::from_millis(300),
+            max_concurrent_requests: 1,
+            ..Default::default()
+        };
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let options = db::PromptOptions {
+            webhook_url: Some(format!("http://{}/hook", receiver_addr)),
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Hooked",
+            "Desc",
+            &options,
+        )
+        .await
+        .unwrap();
+        let test_event = || {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/prompt/{}/webhook/test", prompt.id))
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // The stuck request holds the only slot until it times out
+        let stuck = tokio::spawn(app.clone().oneshot(test_event()));
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(response.headers()[header::RETRY_AFTER], "5");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], "overloaded");
+
+        // Probes bypass the limit
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/healthz")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = stuck.await.unwrap().unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().contains_key(header::RETRY_AFTER));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], "timeout");
+
+        // The slot is free again
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_admin_feedback_events_streams_new_feedback() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Live", "Description")
+            .await
+            .unwrap();
+        let other = db::create_prompt(&state.pool, "Other", "Description")
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}/events", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers().get("content-type").unwrap(),
+            "text/event-stream"
+        );
+
+        // Feedback for another prompt must not appear on this stream
+        for (prompt_id, content) in [(&other.id, "elsewhere"), (&prompt.id, "live+entry")] {
+            app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/feedback/{}", prompt_id))
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(Body::from(format!("content={}", content)))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let mut body = response.into_body();
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.frame())
+            .await
+            .unwrap()
+            .unwrap()
+            .unwrap();
+        let chunk = String::from_utf8(frame.into_data().unwrap().to_vec()).unwrap();
+
+        assert!(chunk.starts_with("event: feedback\n"));
+        assert!(chunk.contains("\"content\":\"live entry\""));
+        assert!(!chunk.contains("elsewhere"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_ends_feedback_event_streams() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Live", "Description")
+            .await
+            .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        let shutdown = state.shutdown.clone();
+        let server = tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app.into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .with_graceful_shutdown(shutdown.cancelled_owned())
+            .await
+        });
+
+        let mut response = reqwest::get(format!(
+            "http://{}/admin/default/prompt/{}/events",
+            addr, prompt.id
+        ))
+        .await
+        .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        // The subscriber stays connected while the server shuts down
+        state.shutdown.cancel();
+        tokio::time::timeout(std::time::Duration::from_secs(5), server)
+            .await
+            .expect("shutdown waited on the open event stream")
+            .unwrap()
+            .unwrap();
+        assert!(response.chunk().await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_receipt_email_links_to_edit_page() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.mailer = Some(email::Mailer::stub("team@example.com").unwrap());
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+        let mailer = state.mailer.clone().unwrap();
+
+        let options = db::PromptOptions {
+            send_receipts: true,
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Onboarding",
+            "How was setup?",
+            &options,
+        )
+        .await
+        .unwrap();
+        let submit = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}", prompt.id))
+                .header("host", "evil.example")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        // Edit links are never built from the request's own host, so
+        // nothing goes out until a base URL is configured
+        app.clone()
+            .oneshot(submit("content=Too+early&email=bo%40example.com"))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert!(mailer.sent().await.is_empty());
+        state.settings.write().unwrap().base_url = Some("http://localhost:3000".to_string());
+
+        // No email, no receipt
+        app.clone()
+            .oneshot(submit("content=Anonymous"))
+            .await
+            .unwrap();
+        app.clone()
+            .oneshot(submit("content=Took+an+hour&email=bo%40example.com"))
+            .await
+            .unwrap();
+        let mut sent = Vec::new();
+        for _ in 0..50 {
+            sent = mailer.sent().await;
+            if !sent.is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        assert_eq!(sent.len(), 1);
+        let fb = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|fb| fb.content == "Took an hour")
+            .unwrap();
+        // Undo quoted-printable soft line breaks so the long edit link is whole
+        let message = sent[0].replace("=\r\n", "");
+        assert!(message.contains("To: bo@example.com"));
+        assert!(message.contains("Took an hour"));
+        assert!(!message.contains("evil.example"));
+        assert!(message.contains(&format!("Reference: {}", fb.id)));
+        let prefix = format!("http://localhost:3000/feedback/{}/edit/", prompt.id);
+        let start = message.find(&prefix).unwrap();
+        let edit_path: String = message[start + "http://localhost:3000".len()..]
+            .chars()
+            .take_while(|c| !c.is_whitespace())
+            .collect();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(&edit_path)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains(">Took an hour</textarea>"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(&edit_path)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=Took+ten+minutes"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Your changes have been saved."));
+        let edited = db::get_feedback_by_id(&state.pool, db::DEFAULT_WORKSPACE_ID, &fb.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(edited.content, "Took ten minutes");
+        assert!(edited.updated_at.is_some());
+
+        // Guessed tokens go nowhere
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}/edit/{}", prompt.id, "0".repeat(64)))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        // Turned off per prompt
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/receipts", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        app.clone()
+            .oneshot(submit("content=Again&email=cy%40example.com"))
+            .await
+            .unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(100)).await;
+        assert_eq!(mailer.sent().await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_admin_duplicate_prompt() {
+        let (app, state) = setup_test_app().await;
+
+        let retro = db::create_prompt(&state.pool, "Sprint retro", "What went well?")
+            .await
+            .unwrap();
+        let spec = db::FieldSpec {
+            label: "Mood".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: true,
+            rules: Default::default(),
+        };
+        let mood = db::create_field(&state.pool, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&state.pool, &retro.id, &mood.id)
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &retro.id, "Too many meetings")
+            .await
+            .unwrap();
+        let translation = db::PromptTranslation {
+            language: "es".to_string(),
+            title: "Retro del sprint".to_string(),
+            description: String::new(),
+            field_labels: Default::default(),
+            updated_at: String::new(),
+        };
+        db::set_prompt_translation(&state.pool, &retro.id, &translation)
+            .await
+            .unwrap();
+
+        let duplicate = |id: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/prompt/{}/duplicate", id))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(duplicate(&retro.id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location = response.headers()["location"].to_str().unwrap();
+        let copy_id = location
+            .strip_prefix("/admin/default/prompt/")
+            .unwrap()
+            .to_string();
+        assert_ne!(copy_id, retro.id);
+
+        let copy = db::get_prompt_by_id(&state.pool, &copy_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(copy.title, "Sprint retro (copy)");
+        assert_eq!(copy.description, "What went well?");
+        let fields = db::get_prompt_fields(&state.pool, &copy_id).await.unwrap();
+        assert_eq!(fields[0].id, mood.id);
+        let translations = db::get_prompt_translations(&state.pool, &copy_id)
+            .await
+            .unwrap();
+        assert_eq!(translations[0].title, "Retro del sprint");
+        assert!(db::get_feedback_for_prompt(&state.pool, &copy_id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(location)
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Sprint retro (copy)"));
+
+        // Suspended prompts stay taken down
+        db::suspend_prompt(&state.pool, &retro.id).await.unwrap();
+        let response = app.clone().oneshot(duplicate(&retro.id)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = app.oneshot(duplicate("missing")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_thank_you_message_and_redirect() {
+        let (app, state) = setup_test_app().await;
+
+        let form = |uri: String, body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(form(
+                "/admin/default/new".to_string(),
+                "title=Fair&description=Desc&thank_you_message=See+you+**next+year**",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let prompts = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        let prompt = prompts.iter().find(|p| p.title == "Fair").unwrap();
+
+        let submit = format!("/feedback/{}", prompt.id);
+        let response = app
+            .clone()
+            .oneshot(form(submit.clone(), "content=Great"))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("See you <strong>next year</strong>"));
+        assert!(!body_str.contains("Thank you!"));
+
+        // A redirect replaces the message, through htmx or a plain POST
+        let thank_you = format!("/admin/default/prompt/{}/thank-you", prompt.id);
+        let response = app
+            .clone()
+            .oneshot(form(
+                thank_you.clone(),
+                "thank_you_message=&redirect_url=https%3A%2F%2Fexample.com%2Fthanks",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let response = app
+            .clone()
+            .oneshot(form(submit.clone(), "content=Again"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()["location"], "https://example.com/thanks");
+        let mut request = form(submit, "content=Once+more");
+        request
+            .headers_mut()
+            .insert("hx-request", "true".parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()["hx-redirect"],
+            "https://example.com/thanks"
+        );
+        assert_eq!(
+            db::get_feedback_for_prompt(&state.pool, &prompt.id)
+                .await
+                .unwrap()
+                .len(),
+            3
+        );
+
+        let response = app
+            .clone()
+            .oneshot(form(thank_you, "redirect_url=javascript%3Aalert(1)"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/api/v1/workspaces/default/prompts")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"title":"Api","redirect_url":"ftp://example.com"}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_api_prompt_responses_hide_secrets() {
+        let (app, state) = setup_test_app().await;
+        let post = |uri: String, body: serde_json::Value| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let secret_free = |json: &serde_json::Value| {
+            json.get("webhook_secret").is_none() && json.get("access_code").is_none()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/api/v1/workspaces/default/prompts".to_string(),
+                serde_json::json!({
+                    "title": "Launch",
+                    "webhook_url": "https://hooks.example.com/feedback",
+                    "access_code": "spring-24",
+                }),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert!(secret_free(&json));
+        let id = json["id"].as_str().unwrap().to_string();
+        let prompt = db::get_workspace_prompt(&state.pool, db::DEFAULT_WORKSPACE_ID, &id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(prompt.webhook_secret.is_some());
+        assert_eq!(prompt.access_code.as_deref(), Some("spring-24"));
+
+        for action in ["close", "duplicate"] {
+            let response = app
+                .clone()
+                .oneshot(post(
+                    format!("/api/v1/workspaces/default/prompts/{}/{}", id, action),
+                    serde_json::json!({}),
+                ))
+                .await
+                .unwrap();
+            assert!(response.status().is_success());
+            let body = response.into_body().collect().await.unwrap().to_bytes();
+            let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+            assert!(json["title"].as_str().unwrap().starts_with("Launch"));
+            assert!(secret_free(&json));
+        }
+
+        // Only owners get the full archive, which carries them
+        for (email, role) in [
+            ("owner@example.com", db::Role::Owner),
+            ("viewer@example.com", db::Role::Viewer),
+        ] {
+            db::create_user(&state.pool, email, &auth::hash_password("password1"), role)
+                .await
+                .unwrap();
+        }
+        let export = |cookie: &str, format: &str| {
+            Request::builder()
+                .uri(format!("/admin/default/export?format={}", format))
+                .header("cookie", cookie)
+                .body(Body::empty())
+                .unwrap()
+        };
+        let viewer = sign_in(&app, "viewer@example.com", "password1").await;
+        let response = app.clone().oneshot(export(&viewer, "json")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let response = app.clone().oneshot(export(&viewer, "jsonl")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("spring-24"));
+
+        let owner = sign_in(&app, "owner@example.com", "password1").await;
+        let response = app.oneshot(export(&owner, "json")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let archive: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(archive["prompts"][0]["access_code"], "spring-24");
+    }
+
+    #[tokio::test]
+    async fn test_prompt_short_urls() {
+        let (app, state) = setup_test_app().await;
+
+        let form = |uri: String, body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(form(
+                "/admin/default/new".to_string(),
+                "title=Spring+Fair&description=How+was+it%3F&slug=Spring+Fair",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let fair = db::get_prompt_by_slug(&state.pool, "spring-fair")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(fair.title, "Spring Fair");
+
+        // The short URL serves the same form, which still posts by ID
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/f/spring-fair")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("How was it?"));
+        assert!(body_str.contains(&format!("/feedback/{}", fair.id)));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", fair.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("http://localhost:3000/f/spring-fair"));
+
+        // Taken and malformed slugs are refused
+        let response = app
+            .clone()
+            .oneshot(form(
+                "/admin/default/new".to_string(),
+                "title=Other&description=Desc&slug=spring-fair",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains(PROMPT_SLUG_TAKEN));
+        let other = db::create_prompt(&state.pool, "Other", "Desc")
+            .await
+            .unwrap();
+        let slug_uri = format!("/admin/default/prompt/{}/slug", other.id);
+        let response = app
+            .clone()
+            .oneshot(form(slug_uri.clone(), "slug=spring-fair"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        let response = app
+            .clone()
+            .oneshot(form(slug_uri.clone(), "slug=fair%3F"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Moving a slug frees the old one
+        let response = app
+            .clone()
+            .oneshot(form(
+                format!("/admin/default/prompt/{}/slug", fair.id),
+                "slug=fair",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let response = app
+            .clone()
+            .oneshot(form(slug_uri.clone(), "slug=spring-fair"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let response = app.clone().oneshot(form(slug_uri, "slug=")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(db::get_prompt_by_slug(&state.pool, "spring-fair")
+            .await
+            .unwrap()
+            .is_none());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/f/spring-fair")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_admin_prompt_stats() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Workshop", "How was it?")
+            .await
+            .unwrap();
+        let mut fields = Vec::new();
+        for label in ["Score", "Highlight"] {
+            let spec = db::FieldSpec {
+                label: label.to_string(),
+                help_text: None,
+                kind: db::FieldKind::Short,
+                required: false,
+                rules: Default::default(),
+            };
+            let field = db::create_field(&state.pool, db::DEFAULT_WORKSPACE_ID, &spec)
+                .await
+                .unwrap();
+            db::add_prompt_field(&state.pool, &prompt.id, &field.id)
+                .await
+                .unwrap();
+            fields.push(field);
+        }
+        for (score, highlight) in [("4", "Lunch"), ("5", "Demos")] {
+            let options = db::FeedbackOptions {
+                answers: vec![
+                    (fields[0].id.clone(), score.to_string()),
+                    (fields[1].id.clone(), highlight.to_string()),
+                ],
+                ..Default::default()
+            };
+            db::create_feedback_with_options(&state.pool, &prompt.id, "Great", &options)
+                .await
+                .unwrap();
+        }
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}/stats", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("<div class=\"stat-value\">2</div>"));
+        assert!(html.contains("4.5"));
+        assert!(html.contains("average Score (2 answers)"));
+        // Free-text answers aren't ratings
+        assert!(!html.contains("average Highlight"));
+        assert!(html.contains("height: 100%"));
+    }
+
+    #[tokio::test]
+    async fn test_prompt_calendar() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Launch", "Thoughts?")
+            .await
+            .unwrap();
+        for content in ["First", "Second"] {
+            db::create_feedback(&state.pool, &prompt.id, content)
+                .await
+                .unwrap();
+        }
+        let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(get(format!(
+                "/api/v1/workspaces/default/prompts/{}/calendar",
+                prompt.id
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let days: Vec<serde_json::Value> = serde_json::from_slice(&body).unwrap();
+        let today = chrono::Utc::now().format("%Y-%m-%d").to_string();
+        let last = days.last().unwrap();
+        assert_eq!(last["date"], today);
+        assert_eq!(last["count"], 2);
+        assert!(days[..days.len() - 1].iter().all(|d| d["count"] == 0));
+
+        let response = app
+            .clone()
+            .oneshot(get(format!(
+                "/admin/default/prompt/{}/calendar.svg",
+                prompt.id
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["content-type"], "image/svg+xml");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let svg = String::from_utf8(body.to_vec()).unwrap();
+        assert!(svg.contains(&format!("2 responses on {}", today)));
+
+        let response = app
+            .oneshot(get("/admin/default/prompt/missing/calendar.svg".to_string()))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_signed_prefill_links() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Support", "How did we do?")
+            .await
+            .unwrap();
+        let spec = db::FieldSpec {
+            label: "Order".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: true,
+            rules: Default::default(),
+        };
+        let order = db::create_field(&state.pool, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&state.pool, &prompt.id, &order.id)
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/api/v1/workspaces/default/prompts/{}/prefill-links",
+                        prompt.id
+                    ))
+                    .header("host", "localhost:3000")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "values": { &order.id: "A-1001" } }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let link: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = link["token"].as_str().unwrap().to_string();
+        let url = link["url"].as_str().unwrap();
+        let path = url
+            .strip_prefix("http://localhost:3000")
+            .unwrap()
+            .to_string();
+        assert_eq!(path, format!("/feedback/{}?prefill={}", prompt.id, token));
+
+        // The locked answer is shown but not submitted as an input
+        let response = app
+            .clone()
+            .oneshot(Request::builder().uri(path).body(Body::empty()).unwrap())
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("value=\"A-1001\" readonly"));
+        assert!(!html.contains(&format!("name=\"field_{}\"", order.id)));
+        assert!(html.contains(&format!("name=\"prefill\" value=\"{}\"", token)));
+
+        // A tampered answer is ignored in favour of the signed one
+        let post = |body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(post(format!(
+                "content=Quick+delivery&field_{}=Z-9999&prefill={}",
+                order.id, token
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let feedback = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback.len(), 1);
+        assert_eq!(feedback[0].answers[0].value, "A-1001");
+        assert!(feedback[0].answers[0].verified);
+
+        // A forged token is refused outright
+        let forged = format!("{}0", token);
+        let response = app
+            .clone()
+            .oneshot(post(format!(
+                "content=Slow&field_{}=Z-9999&prefill={}",
+                order.id, forged
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}?prefill={}", prompt.id, forged))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        // Plain answers through the API aren't marked verified
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({
+                            "content": "Fine",
+                            "answers": { &order.id: "B-2002" },
+                        })
+                        .to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let created: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(created["answers"][0]["verified"], false);
+
+        // The detail page form signs links with the same key
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/prompt/{}/prefill", prompt.id))
+                    .header("host", "localhost:3000")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(format!("field_{}=A-1001", order.id)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec()).unwrap().contains(url));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_database_pauses_submissions() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Replica", "Desc")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Written earlier")
+            .await
+            .unwrap();
+        state.read_only.set(true);
+
+        let get = |uri: String| {
+            Request::builder()
+                .uri(uri)
+                .header("host", "localhost:3000")
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(get(format!("/feedback/{}", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        assert!(response.headers().contains_key("retry-after"));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Submissions paused"));
+
+        let form_submit = || {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/feedback/{}", prompt.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from("content=Hello"))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(form_submit()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                    .header("content-type", "application/json")
+                    .body(Body::from(r#"{"content":"Hello"}"#))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], "read_only");
+
+        // Admins can still read
+        let response = app
+            .clone()
+            .oneshot(get(format!("/admin/default/prompt/{}", prompt.id)))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Written earlier"));
+        let response = app.clone().oneshot(get("/readyz".into())).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(&body[..], b"ready (read-only)");
+
+        state.read_only.set(false);
+        let response = app.oneshot(form_submit()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            db::get_feedback_for_prompt(&state.pool, &prompt.id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+    }
+
+    #[tokio::test]
+    async fn test_storage_quota_refuses_submissions() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let mut state = test_state(pool);
+        state.storage_quotas = storage::Quotas {
+            prompt_bytes: 10,
+            workspace_bytes: 0,
+        };
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
+
+        let prompt = db::create_prompt(&state.pool, "Tiny", "Desc")
+            .await
+            .unwrap();
+        let submit = |content: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/api/v1/prompts/{}/feedback", prompt.id))
+                .header("content-type", "application/json")
+                .body(Body::from(format!(r#"{{"content":"{}"}}"#, content)))
+                .unwrap()
+        };
+
+        let response = app.clone().oneshot(submit("123456")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let response = app.clone().oneshot(submit("12345")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::INSUFFICIENT_STORAGE);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let error: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(error["error"]["code"], "storage_quota_exceeded");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=12345"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("reached its storage limit"));
+        assert_eq!(
+            db::get_feedback_for_prompt(&state.pool, &prompt.id)
+                .await
+                .unwrap()
+                .len(),
+            1
+        );
+
+        // Usage is shown against the quota
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Storage: 6 B of 10 B"));
+    }
+
+    #[tokio::test]
+    async fn test_feedback_submit_latest_response_wins() {
+        let (app, state) = setup_test_app().await;
+
+        let options = db::PromptOptions {
+            merge_responses: true,
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Pulse",
+            "Weekly",
+            &options,
+        )
+        .await
+        .unwrap();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/api/v1/workspaces/default/prompts/{}/prefill-links",
+                        prompt.id
+                    ))
+                    .header("host", "localhost:3000")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        serde_json::json!({ "values": { "email": "Ann@Example.com" } }).to_string(),
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CREATED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let link: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        let token = link["token"].as_str().unwrap().to_string();
+
+        // The link's email wins over a typed one, and replaces its own
+        // earlier answer; a typed address alone never replaces anything
+        for body in [
+            format!("content=Busy+week&prefill={}", token),
+            format!(
+                "content=Calmer+now&email=bo%40example.com&prefill={}",
+                token
+            ),
+            "content=Awful&email=ann%40example.com".to_string(),
+            "content=No+email".to_string(),
+        ] {
+            let response = app
+                .clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/feedback/{}", prompt.id))
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(Body::from(body))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback_list.len(), 3);
+        let ann: Vec<_> = feedback_list
+            .iter()
+            .filter(|fb| fb.respondent_email.as_deref() == Some("ann@example.com"))
+            .map(|fb| fb.content.as_str())
+            .collect();
+        assert_eq!(ann.len(), 2);
+        assert!(ann.contains(&"Calmer now"));
+        assert!(ann.contains(&"Awful"));
+        let merged = feedback_list
+            .iter()
+            .find(|fb| fb.content == "Calmer now")
+            .unwrap();
+        assert!(merged.updated_at.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_feedback_submit_appends_without_merge_mode() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Open", "Desc")
+            .await
+            .unwrap();
+
+        for _ in 0..2 {
+            app.clone()
+                .oneshot(
+                    Request::builder()
+                        .method("POST")
+                        .uri(format!("/feedback/{}", prompt.id))
+                        .header("content-type", "application/x-www-form-urlencoded")
+                        .body(Body::from("content=Again&email=ann%40example.com"))
+                        .unwrap(),
+                )
+                .await
+                .unwrap();
+        }
+
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback_list.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_feedback_submit_invalid_email() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Open", "Desc")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=Hi&email=not-an-email"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Invalid email address"));
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_admin_search_groups_by_prompt() {
+        let (app, state) = setup_test_app().await;
+
+        let pricing = db::create_prompt(&state.pool, "Pricing Survey", "Desc")
+            .await
+            .unwrap();
+        let onboarding = db::create_prompt(&state.pool, "Onboarding", "Desc")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &pricing.id, "Pricing is <b>too</b> high")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &onboarding.id, "Signup asked about pricing")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &onboarding.id, "Smooth experience")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/search?q=pricing")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Pricing Survey"));
+        assert!(body_str.contains("Onboarding"));
+        assert!(body_str.contains("<mark>pricing</mark>"));
+        assert!(body_str.contains("&lt;b&gt;too&lt;/b&gt;"));
+        assert!(!body_str.contains("Smooth experience"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_search_no_results() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/search?q=nothing")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("No feedback matches"));
+    }
+
+    #[test]
+    fn test_split_snippet() {
+        let parts = split_snippet("a \u{2}b\u{3} c \u{2}d\u{3}");
+
+        let rendered: Vec<_> = parts
+            .iter()
+            .map(|p| (p.text.as_str(), p.highlighted))
+            .collect();
+        assert_eq!(
+            rendered,
+            vec![("a ", false), ("b", true), (" c ", false), ("d", true)]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_admin_new_submit_recurring() {
+        let (app, state) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("title=Pulse&description=Desc&recurrence=weekly"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let prompts = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        assert_eq!(prompts[0].recurrence(), Some(db::Recurrence::Weekly));
+    }
+
+    #[tokio::test]
+    async fn test_recurring_prompt_cycles_on_detail_page() {
+        let (app, state) = setup_test_app().await;
+
+        let options = db::PromptOptions {
+            recurrence: Some(db::Recurrence::Weekly),
+            ..Default::default()
+        };
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            db::DEFAULT_WORKSPACE_ID,
+            "Pulse",
+            "Desc",
+            &options,
+        )
+        .await
+        .unwrap();
+
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=First+cycle+answer"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        let first_cycle = db::current_cycle(&state.pool, &prompt, chrono::Utc::now())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(feedback_list[0].cycle_id.as_ref(), Some(&first_cycle.id));
+
+        // Open the next period directly, as the scheduler would a week later
+        db::current_cycle(
+            &state.pool,
+            &prompt,
+            chrono::Utc::now() + chrono::Duration::days(8),
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Cycles"));
+        assert!(body_str.contains("?cycle=2"));
+        assert!(body_str.contains("-1"));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}?cycle=2", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Showing cycle 2"));
+        assert!(!body_str.contains("First cycle answer"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_add_and_remove_tag() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Tagged", "Desc")
+            .await
+            .unwrap();
+        let feedback = db::create_feedback(&state.pool, &prompt.id, "Crashes on save")
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/feedback/{}/tags", feedback.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .header("hx-request", "true")
+                    .body(Body::from("name=Feature+Request"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains(&format!("id=\"tags-{}\"", feedback.id)));
+        assert!(body_str.contains("feature-request"));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!(
+                        "/admin/default/feedback/{}/tags/feature-request",
+                        feedback.id
+                    ))
+                    .header("hx-request", "true")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let stored = db::get_feedback_by_id(&state.pool, db::DEFAULT_WORKSPACE_ID, &feedback.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert!(stored.tags.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_feedback_triage_status() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Triage", "Desc")
+            .await
+            .unwrap();
+        let handled = db::create_feedback(&state.pool, &prompt.id, "Export is slow")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Love the new theme")
+            .await
+            .unwrap();
+        assert_eq!(handled.status, "new");
+
+        let set_status = |status: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(format!("/admin/default/feedback/{}/status", handled.id))
+                .header("content-type", "application/x-www-form-urlencoded")
+                .header("hx-request", "true")
+                .body(Body::from(format!("status={}", status)))
+                .unwrap()
+        };
+        let response = app.clone().oneshot(set_status("actioned")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains(&format!("id=\"status-{}\"", handled.id)));
+        assert!(body_str.contains("<option value=\"actioned\" selected>"));
+
+        let response = app.clone().oneshot(set_status("done")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let counts = db::get_status_counts(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!((counts.new, counts.actioned), (1, 1));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!(
+                        "/admin/default/prompt/{}?status=actioned",
+                        prompt.id
+                    ))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("1 actioned"));
+        assert!(body_str.contains("Export is slow"));
+        assert!(!body_str.contains("Love the new theme"));
+
+        // Edited text needs another look
+        let mut stored = db::get_feedback_by_id(&state.pool, db::DEFAULT_WORKSPACE_ID, &handled.id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(stored.status, "actioned");
+        db::edit_feedback(&state.pool, &mut stored, "Export is slow on big prompts")
+            .await
+            .unwrap();
+        assert_eq!(stored.status, "new");
+    }
+
+    #[tokio::test]
+    async fn test_admin_add_tag_rejects_invalid_name() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Tagged", "Desc")
+            .await
+            .unwrap();
+        let feedback = db::create_feedback(&state.pool, &prompt.id, "Hi")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/feedback/{}/tags", feedback.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("name=%3Cb%3E"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert!(db::get_tag_counts_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail_filters_by_tag() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Tagged", "Desc")
+            .await
+            .unwrap();
+        let bug = db::create_feedback(&state.pool, &prompt.id, "Broken button")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Lovely colours")
+            .await
+            .unwrap();
+        db::add_tag(&state.pool, &bug.id, "bug").await.unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}?tag=bug", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Broken button"));
+        assert!(!body_str.contains("Lovely colours"));
+        assert!(body_str.contains("Showing entries tagged"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_respondents() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Survey", "Desc")
+            .await
+            .unwrap();
+        let options = db::FeedbackOptions {
+            respondent_email: Some("ann+vip@example.com".to_string()),
+            ..Default::default()
+        };
+        db::create_feedback_with_options(&state.pool, &prompt.id, "Hello", &options)
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/respondents")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("ann+vip@example.com"));
+        assert!(body_str.contains("/admin/default/respondents/ann%2Bvip%40example.com"));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/respondents/ann%2Bvip%40example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Survey"));
+        assert!(body_str.contains("Hello"));
+    }
+
+    #[tokio::test]
+    async fn test_admin_respondent_not_found() {
+        let (app, _) = setup_test_app().await;
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/respondents/nobody%40example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Respondent not found"));
+    }
+
+    #[tokio::test]
+    async fn test_privacy_export_and_delete() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Q", "Desc").await.unwrap();
+        for (content, email) in [("Mine", "ann@example.com"), ("Theirs", "bob@example.com")] {
+            let options = db::FeedbackOptions {
+                respondent_email: Some(email.to_string()),
+                ..Default::default()
+            };
+            db::create_feedback_with_options(&state.pool, &prompt.id, content, &options)
+                .await
+                .unwrap();
+        }
+        let post = |uri: &str, body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/privacy?email=Ann%40example.com")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("1 entry held in this workspace"));
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/privacy/export",
+                "email=ann%40example.com",
+            ))
+            .await
+            .unwrap();
+        assert!(response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .starts_with("attachment"));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["respondent"], "ann@example.com");
+        assert_eq!(json["entries"][0]["content"], "Mine");
+
+        // Nothing goes without the address typed again
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/privacy/delete",
+                "email=ann%40example.com&confirm=bob%40example.com",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(
+            db::get_feedback_for_prompt(&state.pool, &prompt.id)
+                .await
+                .unwrap()
+                .len(),
+            2
+        );
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/privacy/delete",
+                "email=ann%40example.com&confirm=ANN%40example.com",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Deleted 1 entry from a***@example.com"));
+        // The audit trail has both requests, without the address
+        assert!(body_str.contains("<td>export</td>"));
+        assert!(body_str.contains("<td>delete</td>"));
+        assert!(!body_str.contains("ann@example.com"));
+        let left = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].content, "Theirs");
+    }
+
+    #[tokio::test]
+    async fn test_admin_detail_shows_sentiment() {
+        let (app, state) = setup_test_app().await;
+
+        let prompt = db::create_prompt(&state.pool, "Mood", "Desc")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "This is terrible and broken")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Absolutely love it")
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("sentiment-badge sentiment-negative"));
+        assert!(body_str.contains("sentiment-badge sentiment-positive"));
+        assert!(body_str.contains("1 positive"));
+        assert!(body_str.contains("1 negative"));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!(
+                        "/admin/default/prompt/{}?sentiment=negative",
+                        prompt.id
+                    ))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("terrible and broken"));
+        assert!(!body_str.contains("Absolutely love it"));
+    }
+
+    #[tokio::test]
+    async fn test_export_then_import() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Moving", "Desc")
+            .await
+            .unwrap();
+        db::create_feedback(&state.pool, &prompt.id, "Carry me over")
+            .await
+            .unwrap();
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/export")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(response
+            .headers()
+            .get("content-disposition")
+            .unwrap()
+            .to_str()
+            .unwrap()
+            .starts_with("attachment"));
+        let export = response.into_body().collect().await.unwrap().to_bytes();
+
+        let (target_app, target_state) = setup_test_app().await;
+        let import = |uri: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/json")
+                .body(Body::from(export.clone()))
+                .unwrap()
+        };
+
+        let response = target_app
+            .clone()
+            .oneshot(import("/admin/default/import"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let restored = db::get_feedback_for_prompt(&target_state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(restored.len(), 1);
+        assert_eq!(restored[0].content, "Carry me over");
+
+        // A second import collides with the rows it just created
+        let response = target_app
+            .clone()
+            .oneshot(import("/admin/default/import"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let response = target_app
+            .oneshot(import("/admin/default/import?on_conflict=skip"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_incremental_export() {
+        let (app, state) = setup_test_app().await;
+        let old = db::create_prompt(&state.pool, "Old", "Desc").await.unwrap();
+        let fresh = db::create_prompt(&state.pool, "Fresh", "Desc")
+            .await
+            .unwrap();
+        for prompt in [&old, &fresh] {
+            let fb = db::create_feedback(&state.pool, &prompt.id, "Hi")
+                .await
+                .unwrap();
+            if prompt.id == old.id {
+                sqlx::query("UPDATE feedback SET created_at = ? WHERE id = ?")
+                    .bind("2024-01-01T00:00:00+00:00")
+                    .bind(&fb.id)
+                    .execute(&state.pool)
+                    .await
+                    .unwrap();
+            }
+        }
+        sqlx::query("UPDATE prompts SET created_at = ? WHERE id = ?")
+            .bind("2024-01-01T00:00:00+00:00")
+            .bind(&old.id)
+            .execute(&state.pool)
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/export?since=2025-01-01T00:00:00Z")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let archive: archive::Archive = serde_json::from_slice(&body).unwrap();
+        assert_eq!(archive.since.as_deref(), Some("2025-01-01T00:00:00+00:00"));
+        assert_eq!(archive.prompts.len(), 1);
+        assert_eq!(archive.prompts[0].prompt.id, fresh.id);
+        assert_eq!(archive.feedback.len(), 1);
+        assert_eq!(archive.feedback[0].prompt_id, fresh.id);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/export?since=2025-01-01&format=jsonl")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(
+            response.headers()[header::CONTENT_TYPE],
+            "application/x-ndjson"
+        );
+        assert!(response.headers()[header::CONTENT_DISPOSITION]
+            .to_str()
+            .unwrap()
+            .ends_with(".jsonl\""));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let lines: Vec<&str> = body_str.lines().collect();
+        assert_eq!(lines.len(), 1);
+        let record: serde_json::Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(record["prompt_title"], "Fresh");
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/export?format=pdf")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/export?since=soon")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+    }
+
+    #[tokio::test]
+    async fn test_export_templates_shape_spreadsheets() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Retro", "How was it?")
+            .await
+            .unwrap();
+        let spec = db::FieldSpec {
+            label: "Team".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: false,
+            rules: Default::default(),
+        };
+        let team = db::create_field(&state.pool, db::DEFAULT_WORKSPACE_ID, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&state.pool, &prompt.id, &team.id)
+            .await
+            .unwrap();
+        let options = db::FeedbackOptions {
+            answers: vec![(team.id.clone(), "Platform".to_string())],
+            ..Default::default()
+        };
+        db::create_feedback_with_options(&state.pool, &prompt.id, "Shorter please", &options)
+            .await
+            .unwrap();
+
+        let get = |uri: String| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/exports")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(format!(
+                        "name=Board&date_format=%25Y&source_0=content&header_0=Comment&source_1=&source_2=answer%3A{}&header_2=",
+                        team.id
+                    )))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let template = db::get_export_templates(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(template.columns.len(), 2);
+
+        let response = app
+            .clone()
+            .oneshot(get(format!(
+                "/admin/default/export?format=csv&template={}",
+                template.id
+            )))
+            .await
+            .unwrap();
+        assert_eq!(
+            response.headers()["content-type"],
+            "text/csv; charset=utf-8"
+        );
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert_eq!(body, "Comment,Team\r\nShorter please,Platform\r\n");
+
+        let response = app
+            .clone()
+            .oneshot(get(format!(
+                "/admin/default/export?format=xlsx&template={}",
+                template.id
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.starts_with(b"PK"));
+
+        // Without a template every column comes out, under default headers
+        let response = app
+            .clone()
+            .oneshot(get("/admin/default/export?format=csv".to_string()))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let csv = String::from_utf8(body.to_vec()).unwrap();
+        assert!(csv.starts_with("ID,Prompt,Prompt ID,Cycle,Feedback,"));
+        assert!(csv.lines().next().unwrap().ends_with(",Tags,Team"));
+
+        let response = app
+            .clone()
+            .oneshot(get(format!(
+                "/admin/default/export?template={}",
+                template.id
+            )))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let response = app
+            .clone()
+            .oneshot(get(
+                "/admin/default/export?format=csv&template=missing".to_string()
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/admin/default/exports/{}", template.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("name=Board&date_format=%25Q&source_0=content"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+
+        let response = app
+            .clone()
+            .oneshot(get(format!("/admin/default/exports/{}", template.id)))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains(r#"value="Comment""#));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/admin/default/exports/{}", template.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert!(
+            db::get_export_templates(&state.pool, db::DEFAULT_WORKSPACE_ID)
+                .await
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[tokio::test]
+    async fn test_backup_streams_sqlite_database() {
+        // VACUUM INTO can't copy out of an in-memory database
+        let path = std::env::temp_dir().join(format!("feedback-{}.db", uuid::Uuid::new_v4()));
+        let pool = db::init_db(&format!("sqlite:{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        db::create_prompt(&pool, "Backed up", "Desc").await.unwrap();
+        let app = create_router(Arc::new(test_state(pool.clone())));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/backup")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::OK);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(body.starts_with(b"SQLite format 3\0"));
+
+        pool.close().await;
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_api_errors_use_json_envelope() {
+        let (app, _state) = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri("/api/default/prompts/missing")
+                    .header("x-request-id", "req-123")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers().get("x-request-id").unwrap(), "req-123");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "not_found");
+        assert_eq!(json["error"]["message"], "Prompt not found");
+        assert_eq!(json["error"]["request_id"], "req-123");
+        assert_eq!(json["error"]["fields"], serde_json::json!([]));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/import?on_conflict=sometimes")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"version":1,"exported_at":"","prompts":[],"feedback":[]}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        assert!(response.headers().contains_key("x-request-id"));
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "validation_failed");
+        assert_eq!(json["error"]["fields"][0]["field"], "on_conflict");
+        assert!(json["error"]["request_id"].is_string());
+
+        // A query string that doesn't parse gets the JSON error too
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/import?on_conflict=skip&on_conflict=abort")
+                    .header("content-type", "application/json")
+                    .body(Body::from(
+                        r#"{"version":1,"exported_at":"","prompts":[],"feedback":[]}"#,
+                    ))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "bad_request");
+    }
+
+    #[tokio::test]
+    async fn test_create_workspace_and_list() {
+        let (app, state) = setup_test_app().await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/workspaces")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("name=Design+Team&slug="))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers().get("location").unwrap(),
+            "/admin/design-team"
+        );
+        assert!(db::get_workspace_by_slug(&state.pool, "design-team")
+            .await
+            .unwrap()
+            .is_some());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+
+        assert!(body_str.contains("Design Team"));
+        assert!(body_str.contains("/admin/default"));
+    }
+
+    #[tokio::test]
+    async fn test_workspaces_cannot_see_each_others_prompts() {
+        let (app, state) = setup_test_app().await;
+        let other = db::create_workspace(&state.pool, "design", "Design")
+            .await
+            .unwrap()
+            .unwrap();
+        let prompt = db::create_prompt_with_options(
+            &state.pool,
+            &other.id,
+            "Design review",
+            "Desc",
+            &db::PromptOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(!String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Design review"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Prompt not found"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/default/prompts/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert!(db::get_prompt_by_id(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_some());
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/no-such-team")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+    }
+
+    #[tokio::test]
+    async fn test_prompt_lifecycle_api_with_scoped_token() {
+        use feedback_app::client::{self, Client, DuplicatePrompt, NewFeedback};
+
+        let (app, state) = setup_test_app().await;
+        let releases = db::create_workspace(&state.pool, "releases", "Releases")
+            .await
+            .unwrap()
+            .unwrap();
+        let v1 = db::create_prompt_with_options(
+            &state.pool,
+            &releases.id,
+            "Release 1.0",
+            "How did the upgrade go?",
+            &Default::default(),
+        )
+        .await
+        .unwrap();
+        let spec = db::FieldSpec {
+            label: "Version".to_string(),
+            help_text: None,
+            kind: db::FieldKind::Short,
+            required: false,
+            rules: Default::default(),
+        };
+        let field = db::create_field(&state.pool, &releases.id, &spec)
+            .await
+            .unwrap();
+        db::add_prompt_field(&state.pool, &v1.id, &field.id)
+            .await
+            .unwrap();
+        db::create_notification_rule(
+            &state.pool,
+            &v1.id,
+            &notify::Condition::Always,
+            notify::Delivery::Digest,
+            "https://chat.example.com/hook",
+        )
+        .await
+        .unwrap();
+        db::create_feedback(&state.pool, &v1.id, "Smooth")
+            .await
+            .unwrap();
+        let other = db::create_prompt(&state.pool, "Elsewhere", "Desc")
+            .await
+            .unwrap();
+
+        let secret = auth::new_api_token();
+        db::create_api_token(
+            &state.pool,
+            "Release pipeline",
+            &auth::token_digest(&secret),
+            db::Role::Editor,
+            Some(&releases.id),
+        )
+        .await
+        .unwrap();
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = Client::new(format!("http://{}", addr)).with_token(&secret);
+
+        let v2 = client
+            .duplicate_prompt(
+                "releases",
+                &v1.id,
+                &DuplicatePrompt {
+                    title: Some("Release 1.1".to_string()),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+        assert_eq!(v2.title, "Release 1.1");
+        assert_eq!(v2.description, "How did the upgrade go?");
+        assert_eq!(v2.workspace_id, releases.id);
+        let fields = db::get_prompt_fields(&state.pool, &v2.id).await.unwrap();
+        assert_eq!(fields[0].id, field.id);
+        let rules = db::get_notification_rules(&state.pool, &v2.id)
+            .await
+            .unwrap();
+        assert_eq!(rules[0].target_url, "https://chat.example.com/hook");
+        assert!(db::get_feedback_for_prompt(&state.pool, &v2.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let closed = client.close_prompt("releases", &v1.id).await.unwrap();
+        assert!(closed.closed_at.is_some());
+        assert!(closed.archived_at.is_none());
+        match client
+            .submit_feedback(&v1.id, &NewFeedback::new("Too late"))
+            .await
+        {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 409);
+                assert_eq!(error.code, "prompt_closed");
+            }
+            other => panic!("expected a closed prompt error, got {:?}", other),
+        }
+        client
+            .submit_feedback(&v2.id, &NewFeedback::new("On time"))
+            .await
+            .unwrap();
+
+        let archived = client.archive_prompt("releases", &v1.id).await.unwrap();
+        assert_eq!(archived.closed_at, closed.closed_at);
+        assert!(archived.archived_at.is_some());
+        let reopened = client.open_prompt("releases", &v1.id).await.unwrap();
+        assert!(reopened.closed_at.is_none() && reopened.archived_at.is_none());
+
+        // The token is limited to its workspace
+        match client.close_prompt("default", &other.id).await {
+            Err(client::Error::Api { status, .. }) => assert_eq!(status, 403),
+            other => panic!("expected forbidden, got {:?}", other),
+        }
+        match client.close_prompt("releases", &other.id).await {
+            Err(client::Error::Api { status, .. }) => assert_eq!(status, 404),
+            other => panic!("expected not found, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_closed_prompt_form_refuses_feedback() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Closed", "Desc")
+            .await
+            .unwrap();
+        db::set_prompt_status(&state.pool, &prompt.id, db::PromptStatus::Closed)
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("no longer accepting feedback"));
+        assert!(!body_str.contains("<textarea"));
+
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=Hello"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("no longer accepting feedback"));
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_closing_prompt_evicts_cached_copy() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Hot", "Desc").await.unwrap();
+        let form = || {
+            Request::builder()
+                .uri(format!("/feedback/{}", prompt.id))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let response = app.clone().oneshot(form()).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("<textarea"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!(
+                        "/api/v1/workspaces/default/prompts/{}/close",
+                        prompt.id
+                    ))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app.oneshot(form()).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("no longer accepting feedback"));
+    }
+
+    #[tokio::test]
+    async fn test_invalid_forms_are_shown_again_with_errors() {
+        let (app, state) = setup_test_app().await;
+        let post = |uri: String, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/new".to_string(),
+                format!(
+                    "title=+++&description={}&slug=spring-fair",
+                    "x".repeat(validation::MAX_DESCRIPTION_LEN + 1)
+                ),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Title must not be empty"));
+        assert!(body_str.contains("Description must be at most 10000 characters"));
+        assert!(body_str.contains("value=\"spring-fair\""));
+        assert!(db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .is_empty());
+
+        // Titles and descriptions are stored trimmed
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/new".to_string(),
+                "title=++Roadmap++&description=+What+next%3F+".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let prompt = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .remove(0);
+        assert_eq!(prompt.title, "Roadmap");
+        assert_eq!(prompt.description, "What next?");
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/feedback/{}", prompt.id),
+                "content=+k+&email=me%40example.com".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Your feedback must be at least 2 characters."));
+        assert!(body_str.contains("value=\"me@example.com\""));
+
+        // htmx is asked to swap the form itself rather than the result area
+        let mut request = post(
+            format!("/feedback/{}", prompt.id),
+            format!("content={}", "x".repeat(validation::MAX_FEEDBACK_LEN + 1)),
+        );
+        request
+            .headers_mut()
+            .insert("hx-request", "true".parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()["hx-retarget"], "#feedback-form");
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Your feedback must be at most 10000 characters."));
+        assert!(db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let response = app
+            .oneshot(post(
+                format!("/feedback/{}", prompt.id),
+                "content=++Ship+it++".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        let feedback = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback[0].content, "Ship it");
+    }
+
+    #[tokio::test]
+    async fn test_json_api_round_trip_with_client() {
+        use feedback_app::client::{self, Client, NewFeedback, NewPrompt};
+
+        let (app, _state) = setup_test_app().await;
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = Client::new(format!("http://{}", addr));
+
+        let prompt = client
+            .create_prompt("default", &NewPrompt::new("Release", "How did it go?"))
+            .await
+            .unwrap();
+        assert_eq!(prompt.workspace_id, db::DEFAULT_WORKSPACE_ID);
+
+        let feedback = client
+            .submit_feedback(
+                &prompt.id,
+                &NewFeedback::new("Went great").with_email("Ann@Example.com"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(
+            feedback.respondent_email.as_deref(),
+            Some("ann@example.com")
+        );
+        assert_eq!(feedback.sentiment_label.as_deref(), Some("positive"));
+
+        let listed = client.list_feedback("default", &prompt.id).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].id, feedback.id);
+
+        let later = client
+            .list_feedback_since("default", &prompt.id, &feedback.created_at)
+            .await
+            .unwrap();
+        assert!(later.is_empty());
+        let earlier = client
+            .list_feedback_since("default", &prompt.id, "2000-01-01")
+            .await
+            .unwrap();
+        assert_eq!(earlier.len(), 1);
+        match client
+            .list_feedback_since("default", &prompt.id, "yesterday")
+            .await
+        {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 422);
+                assert_eq!(error.fields[0].field, "since");
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+
+        match client
+            .submit_feedback(&prompt.id, &NewFeedback::new("  "))
+            .await
+        {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 422);
+                assert_eq!(error.fields[0].field, "content");
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+
+        match client.list_feedback("default", "missing").await {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 404);
+                assert_eq!(error.code, "not_found");
+            }
+            other => panic!("expected not found, got {:?}", other),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_content_rules_are_enforced() {
+        use feedback_app::client::{self, Client, NewFeedback, NewPrompt};
+
+        let (app, state) = setup_test_app().await;
+
+        // Admin form: an impossible range is refused, a sensible one stored
+        let create = |body: &'static str| {
+            Request::builder()
+                .method("POST")
+                .uri("/admin/default/new")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+        let response = app
+            .clone()
+            .oneshot(create(
+                "title=T&description=D&content_min_length=50&content_max_length=10",
+            ))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Answer max length must not be less than the minimum"));
+        let response = app
+            .clone()
+            .oneshot(create(
+                "title=Short&description=D&content_min_length=&content_max_length=10",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let prompt = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .pop()
+            .unwrap();
+        assert_eq!(prompt.content_max_length, Some(10));
+        assert_eq!(prompt.content_min_length, None);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("content=This+is+far+too+long"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains("Your feedback must be at most 10 characters"));
+
+        // JSON API: rules on create, enforced on submit
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = Client::new(format!("http://{}", addr));
+
+        let mut new = NewPrompt::new("Ticket", "Which ticket?");
+        new.content_pattern = Some("(".to_string());
+        match client.create_prompt("default", &new).await {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 422);
+                assert_eq!(error.fields[0].field, "content_pattern");
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+        new.content_pattern = Some(r"[A-Z]+-\d+".to_string());
+        let prompt = client.create_prompt("default", &new).await.unwrap();
+
+        match client
+            .submit_feedback(&prompt.id, &NewFeedback::new("see the ticket"))
+            .await
+        {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 422);
+                assert_eq!(error.fields[0].message, "is not in the expected format");
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+        client
+            .submit_feedback(&prompt.id, &NewFeedback::new("OPS-42"))
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_question_bank_fields_on_forms() {
+        use feedback_app::client::{self, Client, NewFeedback};
+
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Retro", "How did it go?")
+            .await
+            .unwrap();
+        let post = |uri: String, body: String| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body))
+                .unwrap()
+        };
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/default/fields".to_string(),
+                "label=Team&kind=short&required=on&max_length=10".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let field = db::get_field_usage(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap()
+            .remove(0)
+            .field;
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/admin/default/prompt/{}/fields", prompt.id),
+                format!("field_id={}", field.id),
+            ))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec()).unwrap().contains("Team"));
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains(&format!("name=\"field_{}\" value=\"\" required", field.id)));
+
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/feedback/{}", prompt.id),
+                "content=Went+well".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNPROCESSABLE_ENTITY);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(body_str.contains("Team is required"));
+        // The answers given so far are kept
+        assert!(body_str.contains(">Went well</textarea>"));
+        app.clone()
+            .oneshot(post(
+                format!("/feedback/{}", prompt.id),
+                format!("content=Went+well&field_{}=Platform", field.id),
+            ))
+            .await
+            .unwrap();
+
+        // Renaming the field shows up on existing answers
+        let response = app
+            .clone()
+            .oneshot(post(
+                format!("/admin/default/fields/{}", field.id),
+                "label=Squad&kind=short&required=on".to_string(),
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let feedback = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(feedback.len(), 1);
+        assert_eq!(feedback[0].answers[0].label, "Squad");
+        assert_eq!(feedback[0].answers[0].value, "Platform");
+
+        // In use, so it can't be deleted yet
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/admin/default/fields/{}", field.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        let client = Client::new(format!("http://{}", addr));
+
+        match client
+            .submit_feedback(
+                &prompt.id,
+                &NewFeedback::new("Fine").with_answer("nope", "x"),
+            )
+            .await
+        {
+            Err(client::Error::Api { status, error }) => {
+                assert_eq!(status, 422);
+                assert_eq!(error.fields[0].field, "answers.nope");
+                assert_eq!(error.fields[1].field, format!("answers.{}", field.id));
+                assert_eq!(error.fields[1].message, "is required");
+            }
+            other => panic!("expected validation error, got {:?}", other),
+        }
+        let feedback = client
+            .submit_feedback(
+                &prompt.id,
+                &NewFeedback::new("Fine").with_answer(&field.id, "Data"),
+            )
+            .await
+            .unwrap();
+        assert_eq!(feedback.answers[0].value, "Data");
+    }
+
+    /// Sign in through the login form and return the session cookie pair
+    async fn sign_in(app: &Router, email: &str, password: &str) -> String {
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/login")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(format!(
+                        "email={}&password={}&next=/admin/default",
+                        email, password
+                    )))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()["location"], "/admin/default");
+        let cookie = response.headers()[header::SET_COOKIE].to_str().unwrap();
+        cookie.split(';').next().unwrap().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_admin_requires_sign_in_once_users_exist() {
+        let (app, state) = setup_test_app().await;
+        db::create_user(
+            &state.pool,
+            "owner@example.com",
+            &auth::hash_password("owner-password"),
+            db::Role::Owner,
+        )
+        .await
+        .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default/search?q=a&b=c")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(
+            response.headers()["location"],
+            "/login?next=/admin/default/search%3Fq%3Da%26b%3Dc"
+        );
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri("/api/v1/workspaces/default/prompts/x/feedback")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "unauthorized");
+
+        // The public form stays open
+        let prompt = db::create_prompt(&state.pool, "Public", "Desc")
+            .await
+            .unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/feedback/{}", prompt.id))
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_viewer_can_read_but_not_change() {
+        let (app, state) = setup_test_app().await;
+        for (email, role) in [
+            ("owner@example.com", db::Role::Owner),
+            ("viewer@example.com", db::Role::Viewer),
+        ] {
+            db::create_user(&state.pool, email, &auth::hash_password("password1"), role)
+                .await
+                .unwrap();
+        }
+        let prompt = db::create_prompt(&state.pool, "Roles", "Desc")
+            .await
+            .unwrap();
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/login")
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("email=viewer@example.com&password=wrong-one"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let cookie = sign_in(&app, "Viewer@Example.com", "password1").await;
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .uri(format!("/admin/default/prompt/{}", prompt.id))
+                    .header("host", "localhost:3000")
+                    .header("cookie", &cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/default/new")
+                    .header("cookie", &cookie)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("title=New&description=Desc"))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("DELETE")
+                    .uri(format!("/api/default/prompts/{}", prompt.id))
+                    .header("cookie", &cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let json: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(json["error"]["code"], "forbidden");
+
+        // Signing out ends the session server-side
+        app.clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/logout")
+                    .header("cookie", &cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/default")
+                    .header("cookie", &cookie)
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
     }
 
     #[tokio::test]
-    async fn test_index_redirects_to_admin() {
-        let (app, _) = setup_test_app().await;
+    async fn test_branding_settings_apply_to_every_page() {
+        let (app, state) = setup_test_app().await;
+        let prompt = db::create_prompt(&state.pool, "Branded", "Description")
+            .await
+            .unwrap();
+        let save = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/admin/settings")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
 
         let response = app
-            .oneshot(Request::builder().uri("/").body(Body::empty()).unwrap())
+            .clone()
+            .oneshot(save("site_name=Acme&accent_color=%23ff8800%3B%7D"))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(state.settings(), settings::Settings::default());
 
+        let response = app
+            .clone()
+            .oneshot(save(
+                "site_name=Acme+Feedback&organization_name=Acme+Corp\
+                 &logo_url=https%3A%2F%2Facme.test%2Flogo.png&accent_color=%23FF8800\
+                 &footer_text=Questions%3F+Email+**support%40acme.test**",
+            ))
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
-        assert_eq!(response.headers().get("location").unwrap(), "/admin");
-    }
-
-    #[tokio::test]
-    async fn test_admin_list_empty() {
-        let (app, _) = setup_test_app().await;
+        let saved = settings::load(&state.pool).await.unwrap();
+        assert_eq!(saved.accent_color.as_deref(), Some("#ff8800"));
+        assert_eq!(state.settings(), saved);
 
+        // Respondents see the branding on the public form
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/admin")
+                    .uri(format!("/feedback/{}", prompt.id))
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-
-        assert_eq!(response.status(), StatusCode::OK);
-
         let body = response.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8(body.to_vec()).unwrap();
-
-        assert!(body_str.contains("Feedback Prompts"));
-        assert!(body_str.contains("No prompts yet"));
+        assert!(body_str.contains("--accent: #ff8800;"));
+        assert!(body_str.contains(r#"<img src="https://acme.test/logo.png""#));
+        assert!(body_str.contains("Acme Corp"));
+        assert!(body_str.contains("<strong>support@acme.test</strong>"));
     }
 
     #[tokio::test]
-    async fn test_admin_list_with_prompts() {
+    async fn test_retention_periods() {
         let (app, state) = setup_test_app().await;
-
-        // Create a prompt directly in the database
-        db::create_prompt(&state.pool, "Test Prompt", "Test Description")
+        let prompt = db::create_prompt(&state.pool, "Exit survey", "Description")
             .await
             .unwrap();
+        let post = |uri: String, body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri(uri)
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+        let get = |uri: String| {
+            Request::builder()
+                .uri(uri)
+                .header("host", "localhost")
+                .body(Body::empty())
+                .unwrap()
+        };
 
         let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/admin")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(post(
+                "/admin/settings".to_string(),
+                "site_name=Acme&retention_days=0",
+            ))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(post(
+                "/admin/settings".to_string(),
+                "site_name=Acme&retention_days=365",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(state.settings().retention_days, Some(365));
 
-        assert_eq!(response.status(), StatusCode::OK);
-
+        let retention = format!("/admin/default/prompt/{}/retention", prompt.id);
+        let response = app
+            .clone()
+            .oneshot(post(retention.clone(), "retention_days=forever"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        let response = app
+            .clone()
+            .oneshot(post(retention, "retention_days=90"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let response = app
+            .clone()
+            .oneshot(get(format!("/admin/default/prompt/{}", prompt.id)))
+            .await
+            .unwrap();
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("Feedback older than 90 days is permanently deleted"));
 
-        assert!(body_str.contains("Test Prompt"));
-        assert!(body_str.contains("Test Description"));
+        // The purge log and schedule are on the settings page
+        db::create_feedback(&state.pool, &prompt.id, "Old")
+            .await
+            .unwrap();
+        retention::purge(&state.pool, chrono::Utc::now() + chrono::Duration::days(91))
+            .await
+            .unwrap();
+        let response = app
+            .oneshot(get("/admin/settings".to_string()))
+            .await
+            .unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        let html = String::from_utf8(body.to_vec()).unwrap();
+        assert!(html.contains("Next purge: "));
+        assert!(html.contains("<td>Exit survey</td>"));
+        assert!(html.contains(r#"name="retention_days" min="1" max="3650" value="365""#));
     }
 
     #[tokio::test]
-    async fn test_admin_new_form() {
-        let (app, _) = setup_test_app().await;
+    async fn test_setup_wizard_creates_first_owner() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let state = Arc::new(AppState::new(pool));
+        let app = create_router(state.clone());
+        let get = |uri: &str| Request::builder().uri(uri).body(Body::empty()).unwrap();
+        let setup = |body: &str| {
+            Request::builder()
+                .method("POST")
+                .uri("/setup")
+                .header("content-type", "application/x-www-form-urlencoded")
+                .body(Body::from(body.to_string()))
+                .unwrap()
+        };
+
+        // Nothing is open before setup
+        for uri in ["/admin", "/admin/default", "/login"] {
+            let response = app.clone().oneshot(get(uri)).await.unwrap();
+            assert_eq!(response.status(), StatusCode::SEE_OTHER);
+            assert_eq!(response.headers()["location"], "/setup");
+        }
+        let response = app
+            .clone()
+            .oneshot(get("/api/v1/workspaces/default/prompts/p1/feedback"))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let mut request = get("/setup");
+        request
+            .headers_mut()
+            .insert("host", "localhost:3000".parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        let body = response.into_body().collect().await.unwrap().to_bytes();
+        assert!(String::from_utf8(body.to_vec())
+            .unwrap()
+            .contains(r#"value="http://localhost:3000""#));
 
         let response = app
-            .oneshot(
-                Request::builder()
-                    .uri("/admin/new")
-                    .body(Body::empty())
-                    .unwrap(),
-            )
+            .clone()
+            .oneshot(setup(
+                "email=owner%40example.com&password=password1&password_confirm=password2&site_name=Acme",
+            ))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(db::count_users(&state.pool).await.unwrap(), 0);
 
-        assert_eq!(response.status(), StatusCode::OK);
+        let response = app
+            .clone()
+            .oneshot(setup(
+                "email=Owner%40example.com&password=password1&password_confirm=password1\
+                 &site_name=Acme+Feedback&base_url=https%3A%2F%2Ffeedback.acme.test%2F&sample_prompt=on",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()["location"], "/admin");
+        let cookie = response.headers()["set-cookie"]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
+        let owner = db::get_user_by_email(&state.pool, "owner@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(owner.role(), db::Role::Owner);
+        assert_eq!(state.settings().site_name, "Acme Feedback");
 
+        // Links use the configured base URL rather than the request's host
+        let prompts = db::get_all_prompts(&state.pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 1);
+        let mut request = get(&format!("/admin/default/prompt/{}", prompts[0].id));
+        let headers = request.headers_mut();
+        headers.insert("host", "internal:3000".parse().unwrap());
+        headers.insert("cookie", cookie.parse().unwrap());
+        let response = app.clone().oneshot(request).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
         let body = response.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        assert!(String::from_utf8(body.to_vec()).unwrap().contains(&format!(
+            "https://feedback.acme.test/feedback/{}",
+            prompts[0].id
+        )));
 
-        assert!(body_str.contains("Create New Prompt"));
-        assert!(body_str.contains("Title"));
-        assert!(body_str.contains("Description"));
+        // Setup only runs once
+        let response = app.clone().oneshot(get("/setup")).await.unwrap();
+        assert_eq!(response.headers()["location"], "/admin");
+        let response = app
+            .oneshot(setup(
+                "email=other%40example.com&password=password1&password_confirm=password1&site_name=Mine",
+            ))
+            .await
+            .unwrap();
+        assert_eq!(response.headers()["location"], "/login");
+        assert_eq!(db::count_users(&state.pool).await.unwrap(), 1);
+        assert_eq!(state.settings().site_name, "Acme Feedback");
     }
 
     #[tokio::test]
-    async fn test_admin_new_submit() {
+    async fn test_first_user_is_owner_and_last_owner_is_kept() {
         let (app, state) = setup_test_app().await;
 
+        // While the admin is open anyone may create the first account
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/admin/new")
+                    .uri("/admin/users")
                     .header("content-type", "application/x-www-form-urlencoded")
-                    .body(Body::from("title=New+Prompt&description=New+Description"))
+                    .body(Body::from(
+                        "email=first@example.com&password=password1&role=viewer",
+                    ))
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let cookie = response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
 
-        // Verify prompt was created
-        let prompts = db::get_all_prompts(&state.pool).await.unwrap();
-        assert_eq!(prompts.len(), 1);
-        assert_eq!(prompts[0].title, "New Prompt");
-        assert_eq!(prompts[0].description, "New Description");
-    }
-
-    #[tokio::test]
-    async fn test_admin_detail() {
-        let (app, state) = setup_test_app().await;
-
-        let prompt = db::create_prompt(&state.pool, "Detail Test", "Detail Description")
+        let user = db::get_user_by_email(&state.pool, "first@example.com")
             .await
+            .unwrap()
             .unwrap();
+        assert_eq!(user.role(), db::Role::Owner);
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri(&format!("/admin/prompt/{}", prompt.id))
-                    .header("host", "localhost:3000")
-                    .body(Body::empty())
+                    .method("POST")
+                    .uri(format!("/admin/users/{}/role", user.id))
+                    .header("cookie", &cookie)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("role=editor"))
                     .unwrap(),
             )
             .await
             .unwrap();
-
-        assert_eq!(response.status(), StatusCode::OK);
-
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body.to_vec()).unwrap();
-
-        assert!(body_str.contains("Detail Test"));
-        assert!(body_str.contains("Detail Description"));
-        assert!(body_str.contains(&format!("/feedback/{}", prompt.id)));
-    }
-
-    #[tokio::test]
-    async fn test_admin_detail_not_found() {
-        let (app, _) = setup_test_app().await;
+        assert_eq!(response.status(), StatusCode::CONFLICT);
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri("/admin/prompt/nonexistent-id")
-                    .header("host", "localhost:3000")
-                    .body(Body::empty())
+                    .method("POST")
+                    .uri(format!("/admin/users/{}/digest", user.id))
+                    .header("cookie", &cookie)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from("enabled=on"))
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let subscribers = db::get_digest_subscribers(&state.pool).await.unwrap();
+        assert_eq!(subscribers[0].id, user.id);
 
-        assert_eq!(response.status(), StatusCode::OK);
-
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body.to_vec()).unwrap();
-
-        assert!(body_str.contains("Prompt not found"));
-    }
-
-    #[tokio::test]
-    async fn test_feedback_form() {
-        let (app, state) = setup_test_app().await;
-
-        let prompt = db::create_prompt(&state.pool, "Feedback Test", "Give us feedback")
-            .await
-            .unwrap();
-
+        // Bouncing addresses show on the page until they're re-enabled
+        bounces::record(
+            &state.pool,
+            &user.email,
+            "550 mailbox full",
+            chrono::Utc::now(),
+        )
+        .await
+        .unwrap();
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri(&format!("/feedback/{}", prompt.id))
+                    .uri("/admin/users")
+                    .header("cookie", &cookie)
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
-
-        assert_eq!(response.status(), StatusCode::OK);
-
         let body = response.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8(body.to_vec()).unwrap();
-
-        assert!(body_str.contains("Feedback Test"));
-        assert!(body_str.contains("Give us feedback"));
-        assert!(body_str.contains("Your Feedback"));
-    }
-
-    #[tokio::test]
-    async fn test_feedback_form_not_found() {
-        let (app, _) = setup_test_app().await;
+        assert!(body_str.contains("550 mailbox full"));
+        let response = app
+            .clone()
+            .oneshot(
+                Request::builder()
+                    .method("POST")
+                    .uri("/admin/users/bounces/reset")
+                    .header("cookie", &cookie)
+                    .header("content-type", "application/x-www-form-urlencoded")
+                    .body(Body::from(format!("address={}", user.email)))
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert!(bounces::get(&state.pool, &user.email)
+            .await
+            .unwrap()
+            .is_none());
 
         let response = app
             .oneshot(
                 Request::builder()
-                    .uri("/feedback/nonexistent-id")
+                    .method("DELETE")
+                    .uri(format!("/admin/users/{}", user.id))
+                    .header("cookie", &cookie)
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::CONFLICT);
+        assert_eq!(db::count_owners(&state.pool).await.unwrap(), 1);
+    }
 
-        assert_eq!(response.status(), StatusCode::OK);
+    #[tokio::test]
+    async fn test_sso_links_only_verified_emails() {
+        let (_, state) = setup_test_app().await;
+        db::create_user(&state.pool, "owner@example.com", "", db::Role::Owner)
+            .await
+            .unwrap();
+        let claims = |sub: &str, verified: Option<bool>| {
+            let mut claims = serde_json::json!({
+                "iss": "https://idp.example.com",
+                "sub": sub,
+                "aud": "feedback",
+                "exp": 2000,
+                "email": "Owner@Example.com",
+            });
+            if let Some(verified) = verified {
+                claims["email_verified"] = verified.into();
+            }
+            serde_json::from_value::<oidc::Claims>(claims).unwrap()
+        };
 
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        // An IdP that doesn't vouch for the address can't claim the account
+        for (sub, verified) in [("sub-1", None), ("sub-2", Some(false))] {
+            let user = find_sso_user(&state, &claims(sub, verified)).await.unwrap();
+            assert!(user.is_none());
+        }
+        let owner = db::get_user_by_email(&state.pool, "owner@example.com")
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(owner.oidc_subject, None);
 
-        assert!(body_str.contains("Prompt not found"));
+        let user = find_sso_user(&state, &claims("sub-3", Some(true)))
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(user.id, owner.id);
+        let linked = db::get_user_by_oidc_subject(&state.pool, "sub-3")
+            .await
+            .unwrap();
+        assert_eq!(linked.map(|u| u.id), Some(owner.id));
     }
 
     #[tokio::test]
-    async fn test_feedback_submit() {
-        let (app, state) = setup_test_app().await;
+    async fn test_oidc_login_links_account_by_email() {
+        // A stand-in identity provider that issues a token for whatever nonce
+        // the test hands it
+        let nonce = Arc::new(std::sync::Mutex::new(String::new()));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let issuer = format!("http://{}", listener.local_addr().unwrap());
+        let idp = Router::new()
+            .route(
+                "/.well-known/openid-configuration",
+                get({
+                    let issuer = issuer.clone();
+                    move || async move {
+                        Json(serde_json::json!({
+                            "issuer": issuer,
+                            "authorization_endpoint": format!("{}/authorize", issuer),
+                            "token_endpoint": format!("{}/token", issuer),
+                        }))
+                    }
+                }),
+            )
+            .route(
+                "/token",
+                post({
+                    let issuer = issuer.clone();
+                    let nonce = nonce.clone();
+                    move |Form(form): Form<HashMap<String, String>>| async move {
+                        assert_eq!(form["code"], "code-1");
+                        assert_eq!(form["client_secret"], "secret");
+                        use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+                        let claims = serde_json::json!({
+                            "iss": issuer,
+                            "sub": "sub-1",
+                            "aud": "feedback",
+                            "exp": chrono::Utc::now().timestamp() + 300,
+                            "nonce": nonce.lock().unwrap().clone(),
+                            "email": "ann@example.com",
+                            "email_verified": true,
+                        });
+                        Json(serde_json::json!({
+                            "id_token": format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(claims.to_string())),
+                        }))
+                    }
+                }),
+            );
+        tokio::spawn(async move { axum::serve(listener, idp).await.unwrap() });
 
-        let prompt = db::create_prompt(&state.pool, "Submit Test", "Description")
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        db::create_user(&pool, "ann@example.com", "", db::Role::Editor)
             .await
             .unwrap();
+        let mut state = test_state(pool);
+        state.oidc = Some(oidc::Config {
+            issuer: issuer.clone(),
+            client_id: "feedback".to_string(),
+            client_secret: "secret".to_string(),
+            redirect_url: None,
+        });
+        let state = Arc::new(state);
+        let app = create_router(state.clone());
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .method("POST")
-                    .uri(&format!("/feedback/{}", prompt.id))
-                    .header("content-type", "application/x-www-form-urlencoded")
-                    .body(Body::from("content=This+is+my+feedback"))
+                    .uri("/auth/oidc/login?next=/admin/default")
+                    .header("host", "localhost:3000")
+                    .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        let location =
+            reqwest::Url::parse(response.headers()["location"].to_str().unwrap()).unwrap();
+        assert!(location
+            .as_str()
+            .starts_with(&format!("{}/authorize", issuer)));
+        let params: HashMap<String, String> = location.query_pairs().into_owned().collect();
+        assert_eq!(
+            params["redirect_uri"],
+            "http://localhost:3000/auth/oidc/callback"
+        );
+        *nonce.lock().unwrap() = params["nonce"].clone();
+        let login_cookie = response.headers()[header::SET_COOKIE]
+            .to_str()
+            .unwrap()
+            .split(';')
+            .next()
+            .unwrap()
+            .to_string();
 
-        assert_eq!(response.status(), StatusCode::OK);
+        let callback = |login_state: &str| {
+            Request::builder()
+                .uri(format!(
+                    "/auth/oidc/callback?code=code-1&state={}",
+                    login_state
+                ))
+                .header("host", "localhost:3000")
+                .header("cookie", &login_cookie)
+                .body(Body::empty())
+                .unwrap()
+        };
 
-        let body = response.into_body().collect().await.unwrap().to_bytes();
-        let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let response = app.clone().oneshot(callback("forged")).await.unwrap();
+        assert_eq!(response.status(), StatusCode::BAD_REQUEST);
 
-        assert!(body_str.contains("Thank you!"));
-        assert!(body_str.contains("Your feedback has been submitted successfully"));
+        let response = app.oneshot(callback(&params["state"])).await.unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+        assert_eq!(response.headers()["location"], "/admin/default");
+        assert!(response
+            .headers()
+            .get_all(header::SET_COOKIE)
+            .iter()
+            .any(|c| c.to_str().unwrap().starts_with("feedback_session=")));
 
-        // Verify feedback was created
-        let feedback_list = db::get_feedback_for_prompt(&state.pool, &prompt.id)
+        let user = db::get_user_by_oidc_subject(&state.pool, "sub-1")
             .await
+            .unwrap()
             .unwrap();
-        assert_eq!(feedback_list.len(), 1);
-        assert_eq!(feedback_list[0].content, "This is my feedback");
+        assert_eq!(user.email, "ann@example.com");
     }
 
     #[tokio::test]
-    async fn test_feedback_submit_prompt_not_found() {
-        let (app, _) = setup_test_app().await;
+    async fn test_api_tokens_grant_api_access_by_role() {
+        let (app, state) = setup_test_app().await;
+        db::create_user(
+            &state.pool,
+            "owner@example.com",
+            &auth::hash_password("password1"),
+            db::Role::Owner,
+        )
+        .await
+        .unwrap();
+        let cookie = sign_in(&app, "owner@example.com", "password1").await;
+        let prompt = db::create_prompt(&state.pool, "Tokens", "Desc")
+            .await
+            .unwrap();
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
                     .method("POST")
-                    .uri("/feedback/nonexistent-id")
+                    .uri("/admin/tokens")
+                    .header("cookie", &cookie)
                     .header("content-type", "application/x-www-form-urlencoded")
-                    .body(Body::from("content=This+is+my+feedback"))
+                    .body(Body::from("name=Dashboard&role=viewer"))
                     .unwrap(),
             )
             .await
             .unwrap();
-
         assert_eq!(response.status(), StatusCode::OK);
-
         let body = response.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8(body.to_vec()).unwrap();
+        let secret = body_str
+            .split("<code>")
+            .filter_map(|part| part.split("</code>").next())
+            .find(|code| code.starts_with("fbk_"))
+            .unwrap()
+            .to_string();
 
-        assert!(body_str.contains("Prompt not found"));
-    }
+        let api = |method: &str, uri: String, token: &str| {
+            Request::builder()
+                .method(method)
+                .uri(uri)
+                .header("authorization", format!("Bearer {}", token))
+                .body(Body::empty())
+                .unwrap()
+        };
+        let list_uri = format!("/api/v1/workspaces/default/prompts/{}/feedback", prompt.id);
 
-    #[tokio::test]
-    async fn test_admin_detail_shows_feedback() {
-        let (app, state) = setup_test_app().await;
+        let response = app
+            .clone()
+            .oneshot(api("GET", list_uri.clone(), &secret))
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
 
-        let prompt = db::create_prompt(&state.pool, "With Feedback", "Description")
+        let response = app
+            .clone()
+            .oneshot(api(
+                "DELETE",
+                format!("/api/default/prompts/{}", prompt.id),
+                &secret,
+            ))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::FORBIDDEN);
 
-        db::create_feedback(&state.pool, &prompt.id, "First response")
+        // Tokens don't open the HTML admin
+        let response = app
+            .clone()
+            .oneshot(api("GET", "/admin/default".to_string(), &secret))
             .await
             .unwrap();
-        db::create_feedback(&state.pool, &prompt.id, "Second response")
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
+
+        let response = app
+            .clone()
+            .oneshot(api("GET", list_uri.clone(), "fbk_wrong"))
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+
+        let token = &db::get_api_tokens(&state.pool).await.unwrap()[0];
+        assert!(token.last_used_at.is_some());
+        db::revoke_api_token(&state.pool, &token.id).await.unwrap();
+        let response = app.oneshot(api("GET", list_uri, &secret)).await.unwrap();
+        assert_eq!(response.status(), StatusCode::UNAUTHORIZED);
+    }
+
+    #[tokio::test]
+    async fn test_admin_maintenance_run_now() {
+        let (app, _state) = setup_test_app().await;
 
         let response = app
+            .clone()
             .oneshot(
                 Request::builder()
-                    .uri(&format!("/admin/prompt/{}", prompt.id))
-                    .header("host", "localhost:3000")
+                    .method("POST")
+                    .uri("/admin/maintenance/run")
                     .body(Body::empty())
                     .unwrap(),
             )
             .await
             .unwrap();
+        assert_eq!(response.status(), StatusCode::SEE_OTHER);
 
+        let response = app
+            .oneshot(
+                Request::builder()
+                    .uri("/admin/maintenance")
+                    .body(Body::empty())
+                    .unwrap(),
+            )
+            .await
+            .unwrap();
         assert_eq!(response.status(), StatusCode::OK);
-
         let body = response.into_body().collect().await.unwrap().to_bytes();
         let body_str = String::from_utf8(body.to_vec()).unwrap();
-
-        assert!(body_str.contains("First response"));
-        assert!(body_str.contains("Second response"));
-        assert!(body_str.contains("Feedback Responses"));
-        assert!(body_str.contains("id=\"feedback-count\">2</span>"));
+        assert!(body_str.contains("between 03:00 and 05:00 UTC"));
+        assert!(body_str.contains("<td>full</td>"));
     }
 }
@@ -0,0 +1,215 @@
+//! Space reclamation and query planner upkeep, run by a background job during
+//! configured quiet hours or on demand from the admin maintenance page.
+
+use chrono::{DateTime, Duration, Timelike, Utc};
+use serde::Serialize;
+use sqlx::{sqlite::SqlitePool, FromRow};
+
+use crate::db;
+
+/// `PRAGMA auto_vacuum` value for incremental mode
+const AUTO_VACUUM_INCREMENTAL: i64 = 2;
+
+/// UTC hours during which the scheduled job may run: `start` inclusive to
+/// `end` exclusive, wrapping past midnight when `end < start`; equal hours
+/// mean the whole day
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Window {
+    pub start: u32,
+    pub end: u32,
+}
+
+impl Default for Window {
+    fn default() -> Self {
+        Window { start: 3, end: 5 }
+    }
+}
+
+impl Window {
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start == self.end {
+            true
+        } else if self.start < self.end {
+            (self.start..self.end).contains(&hour)
+        } else {
+            hour >= self.start || hour < self.end
+        }
+    }
+}
+
+/// Database file size, from SQLite's own page accounting
+#[derive(Debug, Clone, Copy, Default, Serialize)]
+pub struct Usage {
+    pub total_bytes: i64,
+    /// Space held by deleted rows, reclaimable by vacuuming
+    pub free_bytes: i64,
+}
+
+#[derive(Debug, Clone, Serialize, FromRow)]
+pub struct Run {
+    pub id: String,
+    pub started_at: String,
+    pub finished_at: String,
+    pub size_before: i64,
+    pub size_after: i64,
+    /// `incremental` or `full`; a full vacuum happens once to switch the file
+    /// into incremental mode
+    pub vacuum: String,
+}
+
+impl Run {
+    pub fn reclaimed_bytes(&self) -> i64 {
+        self.size_before - self.size_after
+    }
+}
+
+pub async fn usage(pool: &SqlitePool) -> Result<Usage, sqlx::Error> {
+    let page_size: i64 = sqlx::query_scalar("PRAGMA page_size")
+        .fetch_one(pool)
+        .await?;
+    let page_count: i64 = sqlx::query_scalar("PRAGMA page_count")
+        .fetch_one(pool)
+        .await?;
+    let freelist: i64 = sqlx::query_scalar("PRAGMA freelist_count")
+        .fetch_one(pool)
+        .await?;
+    Ok(Usage {
+        total_bytes: page_size * page_count,
+        free_bytes: page_size * freelist,
+    })
+}
+
+/// Reclaim free pages and refresh planner statistics, recording the run
+pub async fn run(pool: &SqlitePool) -> Result<Run, sqlx::Error> {
+    let started_at = Utc::now().to_rfc3339();
+    // Clear out housekeeping rows first so the vacuum reclaims their space
+    db::prune_submission_tokens(pool, Utc::now() - Duration::days(1)).await?;
+    let before = usage(pool).await?;
+
+    let mode: i64 = sqlx::query_scalar("PRAGMA auto_vacuum")
+        .fetch_one(pool)
+        .await?;
+    let vacuum = if mode == AUTO_VACUUM_INCREMENTAL {
+        sqlx::query("PRAGMA incremental_vacuum")
+            .execute(pool)
+            .await?;
+        "incremental"
+    } else {
+        // Changing auto_vacuum only takes effect through a full VACUUM;
+        // later runs can then reclaim space without rewriting the file
+        let mut conn = pool.acquire().await?;
+        sqlx::query("PRAGMA auto_vacuum = INCREMENTAL")
+            .execute(&mut *conn)
+            .await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        "full"
+    };
+    sqlx::query("ANALYZE").execute(pool).await?;
+
+    let after = usage(pool).await?;
+    let run = Run {
+        id: uuid::Uuid::new_v4().to_string(),
+        started_at,
+        finished_at: Utc::now().to_rfc3339(),
+        size_before: before.total_bytes,
+        size_after: after.total_bytes,
+        vacuum: vacuum.to_string(),
+    };
+    sqlx::query(
+        "INSERT INTO maintenance_runs (id, started_at, finished_at, size_before, size_after, vacuum) VALUES (?, ?, ?, ?, ?, ?)",
+    )
+    .bind(&run.id)
+    .bind(&run.started_at)
+    .bind(&run.finished_at)
+    .bind(run.size_before)
+    .bind(run.size_after)
+    .bind(&run.vacuum)
+    .execute(pool)
+    .await?;
+
+    Ok(run)
+}
+
+/// Most recent runs first
+pub async fn recent_runs(pool: &SqlitePool, limit: i64) -> Result<Vec<Run>, sqlx::Error> {
+    sqlx::query_as::<_, Run>(
+        "SELECT id, started_at, finished_at, size_before, size_after, vacuum FROM maintenance_runs ORDER BY started_at DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// Whether the scheduled job should run now: inside the window, and not
+/// already run in this window
+pub async fn is_due(
+    pool: &SqlitePool,
+    window: Window,
+    now: DateTime<Utc>,
+) -> Result<bool, sqlx::Error> {
+    if !window.contains(now.hour()) {
+        return Ok(false);
+    }
+    let last: Option<String> = sqlx::query_scalar("SELECT MAX(started_at) FROM maintenance_runs")
+        .fetch_one(pool)
+        .await?;
+    let last = last
+        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+        .map(|t| t.with_timezone(&Utc));
+    // A window is at most a day long, so anything older belongs to an earlier one
+    Ok(last.is_none_or(|t| now - t >= Duration::hours(24) - window_length(window)))
+}
+
+fn window_length(window: Window) -> Duration {
+    let hours = (window.end + 24 - window.start) % 24;
+    Duration::hours(i64::from(if hours == 0 { 24 } else { hours }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_window_wraps_midnight() {
+        let night = Window { start: 22, end: 4 };
+        assert!(night.contains(23));
+        assert!(night.contains(0));
+        assert!(!night.contains(4));
+        assert!(!night.contains(12));
+        assert!(Window::default().contains(3));
+        assert!(!Window::default().contains(5));
+
+        // Agrees with the 24-hour length is_due assumes for it
+        let all_day = Window { start: 6, end: 6 };
+        assert!((0..24).all(|hour| all_day.contains(hour)));
+        assert_eq!(window_length(all_day), Duration::hours(24));
+    }
+
+    #[tokio::test]
+    async fn test_run_switches_to_incremental_and_records() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let at_three = "2024-06-01T03:30:00Z".parse::<DateTime<Utc>>().unwrap();
+        assert!(is_due(&pool, Window::default(), at_three).await.unwrap());
+
+        let first = run(&pool).await.unwrap();
+        assert_eq!(first.vacuum, "full");
+        let second = run(&pool).await.unwrap();
+        assert_eq!(second.vacuum, "incremental");
+
+        let runs = recent_runs(&pool, 10).await.unwrap();
+        assert_eq!(runs.len(), 2);
+        assert!(runs[0].size_after > 0);
+
+        // Just ran, so not due again until tomorrow's window
+        let now = Utc::now();
+        let window = Window {
+            start: now.hour(),
+            end: (now.hour() + 1) % 24,
+        };
+        assert!(!is_due(&pool, window, now).await.unwrap());
+        assert!(is_due(&pool, window, now + Duration::hours(24))
+            .await
+            .unwrap());
+    }
+}
@@ -0,0 +1,47 @@
+//! Markdown for prompt descriptions. Admins write Markdown; it's rendered to
+//! HTML and sanitized before reaching a page, since the description is shown
+//! to anonymous respondents.
+
+use pulldown_cmark::{html, Options, Parser};
+
+/// Render Markdown to HTML with scripts, event handlers and other unsafe
+/// markup stripped. Links open in a new tab without passing a referrer.
+pub fn render(text: &str) -> String {
+    let parser = Parser::new_ext(text, Options::ENABLE_STRIKETHROUGH | Options::ENABLE_TABLES);
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+
+    ammonia::Builder::default()
+        .link_rel(Some("noopener noreferrer nofollow"))
+        .set_tag_attribute_value("a", "target", "_blank")
+        .clean(&unsafe_html)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render() {
+        assert_eq!(
+            render("Tell us about **checkout**.\n\n- speed\n- [docs](https://example.com)"),
+            "<p>Tell us about <strong>checkout</strong>.</p>\n<ul>\n<li>speed</li>\n<li><a href=\"https://example.com\" target=\"_blank\" rel=\"noopener noreferrer nofollow\">docs</a></li>\n</ul>\n"
+        );
+        // Plain text still reads as it did before
+        assert_eq!(
+            render("What should we build next?"),
+            "<p>What should we build next?</p>\n"
+        );
+    }
+
+    #[test]
+    fn test_render_strips_unsafe_markup() {
+        let html = render(
+            "<script>alert(1)</script><img src=x onerror=alert(1)>\n\n[x](javascript:alert(1))",
+        );
+        assert!(!html.contains("<script"));
+        assert!(!html.contains("onerror"));
+        assert!(!html.contains("href=\"javascript:"));
+    }
+}
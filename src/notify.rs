@@ -0,0 +1,407 @@
+//! Per-prompt notification routing.
+//!
+//! Each prompt can have an ordered list of rules such as "if Rating ≤ 2, post
+//! to #support-escalation right away; otherwise add it to the weekly digest".
+//! New entries are checked against the rules in order and the first match
+//! decides where the entry goes. Targets are chat incoming webhooks (Slack,
+//! Mattermost, ...) that accept a JSON `{"text": ...}` body.
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+use std::time::Duration;
+
+use crate::{db, sentiment};
+
+/// How often a digest rule posts what it has collected
+pub const DIGEST_INTERVAL: chrono::Duration = chrono::Duration::days(7);
+
+/// Entries listed in one digest message; the rest are only counted
+const MAX_DIGEST_ENTRIES: usize = 20;
+
+/// Longest excerpt of an entry's text included in a message
+const MAX_EXCERPT_CHARS: usize = 280;
+
+/// When a rule applies to an entry
+#[derive(Debug, Clone, PartialEq)]
+pub enum Condition {
+    /// Every entry; the usual last rule
+    Always,
+    /// The answer to a question bank field, read as a number, is at most `value`
+    AnswerAtMost {
+        field_id: String,
+        value: f64,
+    },
+    /// The answer to a question bank field, read as a number, is at least `value`
+    AnswerAtLeast {
+        field_id: String,
+        value: f64,
+    },
+    Sentiment(sentiment::Label),
+    /// The entry's text contains this, ignoring case
+    Contains(String),
+}
+
+impl Condition {
+    /// Every kind, with its label for the admin form
+    pub const KINDS: &'static [(&'static str, &'static str)] = &[
+        ("answer_at_most", "Answer is at most"),
+        ("answer_at_least", "Answer is at least"),
+        ("sentiment", "Sentiment is"),
+        ("contains", "Text contains"),
+        ("always", "Always"),
+    ];
+
+    /// Build a condition from its stored or submitted parts, explaining
+    /// what's missing if they don't make one
+    pub fn from_parts(
+        kind: &str,
+        field_id: Option<&str>,
+        operand: Option<&str>,
+    ) -> Result<Self, String> {
+        let field_id = field_id.map(str::trim).filter(|f| !f.is_empty());
+        let operand = operand.map(str::trim).filter(|o| !o.is_empty());
+        let number = || {
+            operand
+                .and_then(|o| o.parse::<f64>().ok())
+                .filter(|n| n.is_finite())
+                .ok_or_else(|| "Enter a number to compare the answer with".to_string())
+        };
+        let field = || {
+            field_id
+                .map(str::to_string)
+                .ok_or_else(|| "Choose which question's answer to check".to_string())
+        };
+        match kind {
+            "always" => Ok(Condition::Always),
+            "answer_at_most" => Ok(Condition::AnswerAtMost {
+                field_id: field()?,
+                value: number()?,
+            }),
+            "answer_at_least" => Ok(Condition::AnswerAtLeast {
+                field_id: field()?,
+                value: number()?,
+            }),
+            "sentiment" => operand
+                .and_then(sentiment::Label::parse)
+                .map(Condition::Sentiment)
+                .ok_or_else(|| "Sentiment must be positive, neutral or negative".to_string()),
+            "contains" => operand
+                .map(|o| Condition::Contains(o.to_string()))
+                .ok_or_else(|| "Enter the text to look for".to_string()),
+            _ => Err("Unknown condition".to_string()),
+        }
+    }
+
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Condition::Always => "always",
+            Condition::AnswerAtMost { .. } => "answer_at_most",
+            Condition::AnswerAtLeast { .. } => "answer_at_least",
+            Condition::Sentiment(_) => "sentiment",
+            Condition::Contains(_) => "contains",
+        }
+    }
+
+    pub fn field_id(&self) -> Option<&str> {
+        match self {
+            Condition::AnswerAtMost { field_id, .. }
+            | Condition::AnswerAtLeast { field_id, .. } => Some(field_id),
+            _ => None,
+        }
+    }
+
+    pub fn operand(&self) -> Option<String> {
+        match self {
+            Condition::Always => None,
+            Condition::AnswerAtMost { value, .. } | Condition::AnswerAtLeast { value, .. } => {
+                Some(value.to_string())
+            }
+            Condition::Sentiment(label) => Some(label.as_str().to_string()),
+            Condition::Contains(text) => Some(text.clone()),
+        }
+    }
+
+    pub fn matches(&self, feedback: &db::Feedback) -> bool {
+        let answer = |field_id: &str| {
+            feedback
+                .answers
+                .iter()
+                .find(|a| a.field_id == field_id)
+                .and_then(|a| a.value.trim().parse::<f64>().ok())
+        };
+        match self {
+            Condition::Always => true,
+            Condition::AnswerAtMost { field_id, value } => {
+                answer(field_id).is_some_and(|n| n <= *value)
+            }
+            Condition::AnswerAtLeast { field_id, value } => {
+                answer(field_id).is_some_and(|n| n >= *value)
+            }
+            Condition::Sentiment(label) => {
+                feedback.sentiment_label.as_deref() == Some(label.as_str())
+            }
+            Condition::Contains(text) => feedback
+                .content
+                .to_lowercase()
+                .contains(&text.to_lowercase()),
+        }
+    }
+
+    /// Human-readable form, naming fields by their label
+    pub fn describe(&self, fields: &[db::Field]) -> String {
+        let label = |field_id: &str| {
+            fields
+                .iter()
+                .find(|f| f.id == field_id)
+                .map(|f| f.label.clone())
+                .unwrap_or_else(|| "(removed question)".to_string())
+        };
+        match self {
+            Condition::Always => "Always".to_string(),
+            Condition::AnswerAtMost { field_id, value } => {
+                format!("{} \u{2264} {}", label(field_id), value)
+            }
+            Condition::AnswerAtLeast { field_id, value } => {
+                format!("{} \u{2265} {}", label(field_id), value)
+            }
+            Condition::Sentiment(l) => format!("Sentiment is {}", l.as_str()),
+            Condition::Contains(text) => format!("Text contains \"{}\"", text),
+        }
+    }
+}
+
+/// What happens to an entry that matches a rule
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Delivery {
+    /// Posted as soon as it's recorded
+    Immediate,
+    /// Collected and posted once per `DIGEST_INTERVAL`
+    Digest,
+}
+
+impl Delivery {
+    pub const ALL: [Delivery; 2] = [Delivery::Immediate, Delivery::Digest];
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "immediate" => Some(Delivery::Immediate),
+            "digest" => Some(Delivery::Digest),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Delivery::Immediate => "immediate",
+            Delivery::Digest => "digest",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Delivery::Immediate => "Post immediately",
+            Delivery::Digest => "Weekly digest",
+        }
+    }
+}
+
+/// The first of a prompt's rules (already in order) that the entry matches
+pub fn route<'a>(
+    rules: &'a [db::NotificationRule],
+    feedback: &db::Feedback,
+) -> Option<&'a db::NotificationRule> {
+    rules
+        .iter()
+        .find(|rule| rule.condition().is_some_and(|c| c.matches(feedback)))
+}
+
+#[derive(Serialize)]
+struct Message<'a> {
+    text: &'a str,
+}
+
+/// POST a chat message to an incoming webhook
+pub async fn post_message(
+    http: &reqwest::Client,
+    url: &str,
+    text: &str,
+) -> Result<(), reqwest::Error> {
+    http.post(url)
+        .json(&Message { text })
+        .timeout(Duration::from_secs(10))
+        .send()
+        .await?
+        .error_for_status()?;
+    Ok(())
+}
+
+/// An entry's text, cut short for a chat message or email
+pub fn excerpt(content: &str) -> String {
+    let content = content.trim();
+    if content.chars().count() <= MAX_EXCERPT_CHARS {
+        return content.to_string();
+    }
+    let cut: String = content.chars().take(MAX_EXCERPT_CHARS).collect();
+    format!("{}\u{2026}", cut.trim_end())
+}
+
+fn entry_message(prompt: &db::Prompt, feedback: &db::Feedback) -> String {
+    let mut text = format!(
+        "New feedback on \u{201c}{}\u{201d}:\n{}",
+        prompt.title,
+        excerpt(&feedback.content)
+    );
+    for answer in &feedback.answers {
+        text.push_str(&format!("\n{}: {}", answer.label, answer.value));
+    }
+    if let Some(email) = &feedback.respondent_email {
+        text.push_str(&format!("\nFrom: {}", email));
+    }
+    text
+}
+
+fn digest_message(prompt: &db::Prompt, entries: &[db::Feedback]) -> String {
+    let mut text = format!(
+        "Digest for \u{201c}{}\u{201d}: {} new response{}",
+        prompt.title,
+        entries.len(),
+        if entries.len() == 1 { "" } else { "s" }
+    );
+    for fb in entries.iter().take(MAX_DIGEST_ENTRIES) {
+        text.push_str(&format!("\n\u{2022} {}", excerpt(&fb.content)));
+    }
+    if entries.len() > MAX_DIGEST_ENTRIES {
+        text.push_str(&format!(
+            "\n\u{2026}and {} more",
+            entries.len() - MAX_DIGEST_ENTRIES
+        ));
+    }
+    text
+}
+
+/// Route a newly recorded entry in the background: post it now, or queue it
+/// for its rule's digest. Entries matching no rule aren't sent anywhere.
+pub fn spawn_dispatch(
+    pool: SqlitePool,
+    http: reqwest::Client,
+    prompt: &db::Prompt,
+    feedback: &db::Feedback,
+) {
+    let prompt = prompt.clone();
+    let feedback = feedback.clone();
+    tokio::spawn(async move {
+        if let Err(e) = dispatch(&pool, &http, &prompt, &feedback).await {
+            eprintln!(
+                "Notification routing for feedback {} failed: {}",
+                feedback.id, e
+            );
+        }
+    });
+}
+
+async fn dispatch(
+    pool: &SqlitePool,
+    http: &reqwest::Client,
+    prompt: &db::Prompt,
+    feedback: &db::Feedback,
+) -> Result<(), sqlx::Error> {
+    let rules = db::get_notification_rules(pool, &prompt.id).await?;
+    let Some(rule) = route(&rules, feedback) else {
+        return Ok(());
+    };
+    match rule.delivery() {
+        Delivery::Immediate => {
+            let text = entry_message(prompt, feedback);
+            if let Err(e) = post_message(http, &rule.target_url, &text).await {
+                eprintln!("Notification for feedback {} failed: {}", feedback.id, e);
+            }
+        }
+        Delivery::Digest => db::queue_digest_entry(pool, &rule.id, &feedback.id).await?,
+    }
+    Ok(())
+}
+
+/// Post every digest whose interval has passed since it was last sent (or
+/// since the rule was created). Digests that fail to post keep their entries
+/// for the next run. Returns how many were sent.
+pub async fn send_due_digests(
+    pool: &SqlitePool,
+    http: &reqwest::Client,
+    now: DateTime<Utc>,
+) -> Result<usize, sqlx::Error> {
+    let due_before = (now - DIGEST_INTERVAL).to_rfc3339();
+    let mut sent = 0;
+    for rule in db::get_due_digest_rules(pool, &due_before).await? {
+        let Some(prompt) = db::get_prompt_by_id(pool, &rule.prompt_id).await? else {
+            continue;
+        };
+        let entries = db::get_digest_entries(pool, &rule.id).await?;
+        if !entries.is_empty() {
+            let text = digest_message(&prompt, &entries);
+            if let Err(e) = post_message(http, &rule.target_url, &text).await {
+                eprintln!("Digest for rule {} failed: {}", rule.id, e);
+                continue;
+            }
+            sent += 1;
+        }
+        db::finish_digest(pool, &rule.id, &entries, &now.to_rfc3339()).await?;
+    }
+    Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feedback(content: &str, rating: &str) -> db::Feedback {
+        db::Feedback {
+            content: content.to_string(),
+            sentiment_label: Some("negative".to_string()),
+            answers: vec![db::Answer {
+                field_id: "rating".to_string(),
+                label: "Rating".to_string(),
+                value: rating.to_string(),
+                verified: false,
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_conditions() {
+        let at_most = Condition::from_parts("answer_at_most", Some("rating"), Some("2")).unwrap();
+        assert!(at_most.matches(&feedback("x", "2")));
+        assert!(at_most.matches(&feedback("x", " 1.5 ")));
+        assert!(!at_most.matches(&feedback("x", "3")));
+        // Non-numeric and missing answers never match a comparison
+        assert!(!at_most.matches(&feedback("x", "bad")));
+        assert!(
+            !Condition::from_parts("answer_at_least", Some("other"), Some("0"))
+                .unwrap()
+                .matches(&feedback("x", "5"))
+        );
+
+        assert!(Condition::from_parts("contains", None, Some("REFUND"))
+            .unwrap()
+            .matches(&feedback("I want a refund", "5")));
+        assert!(Condition::from_parts("sentiment", None, Some("negative"))
+            .unwrap()
+            .matches(&feedback("x", "5")));
+
+        assert!(Condition::from_parts("answer_at_most", Some("rating"), None).is_err());
+        assert!(Condition::from_parts("answer_at_most", None, Some("2")).is_err());
+        assert!(Condition::from_parts("sentiment", None, Some("angry")).is_err());
+        assert!(Condition::from_parts("contains", None, Some("  ")).is_err());
+
+        // Stored parts round-trip
+        assert_eq!(
+            Condition::from_parts(
+                at_most.kind(),
+                at_most.field_id(),
+                at_most.operand().as_deref()
+            ),
+            Ok(at_most)
+        );
+    }
+}
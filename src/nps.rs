@@ -0,0 +1,153 @@
+//! Net Promoter Score for NPS prompts. Respondents answer "how likely are
+//! you to recommend us" from 0 to 10: 9-10 are promoters, 7-8 passives and
+//! 0-6 detractors. The score is the percentage of promoters minus the
+//! percentage of detractors, from -100 to 100.
+
+use chrono::{DateTime, Datelike, Duration, NaiveDate};
+
+pub const MAX_SCORE: i64 = 10;
+/// Weeks shown in the stats page's trend, ending with the current one
+pub const TREND_WEEKS: usize = 12;
+
+pub fn is_valid(score: i64) -> bool {
+    (0..=MAX_SCORE).contains(&score)
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Summary {
+    /// Responses per score, 0 to 10
+    pub counts: [i64; 11],
+}
+
+impl Summary {
+    pub fn from_scores(scores: impl IntoIterator<Item = i64>) -> Self {
+        let mut summary = Summary::default();
+        for score in scores.into_iter().filter(|s| is_valid(*s)) {
+            summary.counts[score as usize] += 1;
+        }
+        summary
+    }
+
+    pub fn responses(&self) -> i64 {
+        self.counts.iter().sum()
+    }
+
+    pub fn promoters(&self) -> i64 {
+        self.counts[9..].iter().sum()
+    }
+
+    pub fn passives(&self) -> i64 {
+        self.counts[7..9].iter().sum()
+    }
+
+    pub fn detractors(&self) -> i64 {
+        self.counts[..7].iter().sum()
+    }
+
+    /// The NPS, rounded; `None` before anyone has answered
+    pub fn score(&self) -> Option<i64> {
+        let responses = self.responses();
+        (responses > 0).then(|| {
+            ((self.promoters() - self.detractors()) as f64 * 100.0 / responses as f64).round()
+                as i64
+        })
+    }
+
+    pub fn promoter_percent(&self) -> i64 {
+        self.percent(self.promoters())
+    }
+
+    pub fn passive_percent(&self) -> i64 {
+        self.percent(self.passives())
+    }
+
+    pub fn detractor_percent(&self) -> i64 {
+        self.percent(self.detractors())
+    }
+
+    fn percent(&self, count: i64) -> i64 {
+        match self.responses() {
+            0 => 0,
+            responses => (count as f64 * 100.0 / responses as f64).round() as i64,
+        }
+    }
+}
+
+/// One week of the trend
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Week {
+    /// The Monday it starts on (UTC)
+    pub starts: NaiveDate,
+    pub summary: Summary,
+}
+
+/// Scores grouped into the `weeks` weeks ending with the one containing
+/// `today`, oldest first; `scores` are `(created_at, score)` pairs
+pub fn weekly(scores: &[(String, i64)], today: NaiveDate, weeks: usize) -> Vec<Week> {
+    let this_week = week_start(today);
+    let mut trend: Vec<Week> = (0..weeks as i64)
+        .rev()
+        .map(|ago| Week {
+            starts: this_week - Duration::weeks(ago),
+            summary: Summary::default(),
+        })
+        .collect();
+    for (created_at, score) in scores {
+        let Ok(created_at) = DateTime::parse_from_rfc3339(created_at) else {
+            continue;
+        };
+        let starts = week_start(created_at.naive_utc().date());
+        if let Some(week) = trend.iter_mut().find(|w| w.starts == starts) {
+            if is_valid(*score) {
+                week.summary.counts[*score as usize] += 1;
+            }
+        }
+    }
+    trend
+}
+
+fn week_start(day: NaiveDate) -> NaiveDate {
+    day - Duration::days(day.weekday().num_days_from_monday().into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_and_trend() {
+        // 3 promoters, 1 passive, 2 detractors: (3 - 2) / 6 = 17%
+        let summary = Summary::from_scores([10, 9, 9, 7, 6, 0, 11]);
+        assert_eq!(summary.responses(), 6);
+        assert_eq!(
+            (
+                summary.promoters(),
+                summary.passives(),
+                summary.detractors()
+            ),
+            (3, 1, 2)
+        );
+        assert_eq!(summary.score(), Some(17));
+        assert_eq!(summary.promoter_percent(), 50);
+        assert_eq!(Summary::default().score(), None);
+        assert_eq!(Summary::from_scores([0, 3]).score(), Some(-100));
+
+        // Wednesday 2024-05-15; weeks start on Mondays
+        let today = NaiveDate::from_ymd_opt(2024, 5, 15).unwrap();
+        let scores = vec![
+            ("2024-05-13T08:00:00+00:00".to_string(), 10),
+            ("2024-05-12T23:00:00+00:00".to_string(), 2),
+            ("2024-05-14T01:00:00+02:00".to_string(), 9),
+            ("2023-01-01T00:00:00+00:00".to_string(), 10),
+        ];
+        let trend = weekly(&scores, today, 3);
+        assert_eq!(trend.len(), 3);
+        assert_eq!(
+            trend[2].starts,
+            NaiveDate::from_ymd_opt(2024, 5, 13).unwrap()
+        );
+        assert_eq!(trend[2].summary.score(), Some(100));
+        assert_eq!(trend[1].summary.score(), Some(-100));
+        assert_eq!(trend[0].summary.responses(), 0);
+    }
+}
@@ -0,0 +1,286 @@
+//! OpenID Connect sign-in for the admin, as an alternative to local passwords.
+//! Enabled by setting `OIDC_ISSUER`, `OIDC_CLIENT_ID` and `OIDC_CLIENT_SECRET`.
+//!
+//! Uses the authorization code flow. The ID token comes straight from the
+//! token endpoint over TLS, so (per OIDC Core 3.1.3.7) its claims are checked
+//! rather than its signature.
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use serde::Deserialize;
+use std::fmt;
+
+pub const CALLBACK_PATH: &str = "/auth/oidc/callback";
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub issuer: String,
+    pub client_id: String,
+    pub client_secret: String,
+    /// Defaults to `CALLBACK_PATH` on the host the login started from
+    pub redirect_url: Option<String>,
+}
+
+impl Config {
+    pub fn from_env() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        Some(Config {
+            issuer: var("OIDC_ISSUER")?.trim_end_matches('/').to_string(),
+            client_id: var("OIDC_CLIENT_ID")?,
+            client_secret: var("OIDC_CLIENT_SECRET")?,
+            redirect_url: var("OIDC_REDIRECT_URL"),
+        })
+    }
+}
+
+#[derive(Debug)]
+pub enum Error {
+    Http(reqwest::Error),
+    /// The provider's response or ID token failed validation
+    Invalid(&'static str),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Http(e) => write!(f, "request to identity provider failed: {}", e),
+            Error::Invalid(reason) => {
+                write!(f, "invalid response from identity provider: {}", reason)
+            }
+        }
+    }
+}
+
+impl From<reqwest::Error> for Error {
+    fn from(e: reqwest::Error) -> Self {
+        Error::Http(e)
+    }
+}
+
+/// The parts of the provider's discovery document we use
+#[derive(Debug, Clone, Deserialize)]
+pub struct Provider {
+    pub issuer: String,
+    pub authorization_endpoint: String,
+    pub token_endpoint: String,
+}
+
+pub async fn discover(http: &reqwest::Client, config: &Config) -> Result<Provider, Error> {
+    let url = format!("{}/.well-known/openid-configuration", config.issuer);
+    let provider: Provider = http
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    if provider.issuer.trim_end_matches('/') != config.issuer {
+        return Err(Error::Invalid("discovery issuer mismatch"));
+    }
+    Ok(provider)
+}
+
+pub fn authorization_url(
+    provider: &Provider,
+    config: &Config,
+    redirect_uri: &str,
+    state: &str,
+    nonce: &str,
+) -> Result<String, Error> {
+    reqwest::Url::parse_with_params(
+        &provider.authorization_endpoint,
+        &[
+            ("response_type", "code"),
+            ("scope", "openid email"),
+            ("client_id", config.client_id.as_str()),
+            ("redirect_uri", redirect_uri),
+            ("state", state),
+            ("nonce", nonce),
+        ],
+    )
+    .map(String::from)
+    .map_err(|_| Error::Invalid("authorization endpoint is not a URL"))
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    id_token: String,
+}
+
+/// Trade an authorization code for the raw ID token
+pub async fn exchange_code(
+    http: &reqwest::Client,
+    provider: &Provider,
+    config: &Config,
+    code: &str,
+    redirect_uri: &str,
+) -> Result<String, Error> {
+    let response: TokenResponse = http
+        .post(&provider.token_endpoint)
+        .form(&[
+            ("grant_type", "authorization_code"),
+            ("code", code),
+            ("redirect_uri", redirect_uri),
+            ("client_id", config.client_id.as_str()),
+            ("client_secret", config.client_secret.as_str()),
+        ])
+        .send()
+        .await?
+        .error_for_status()?
+        .json()
+        .await?;
+    Ok(response.id_token)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Audience {
+    One(String),
+    Many(Vec<String>),
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Claims {
+    pub iss: String,
+    pub sub: String,
+    aud: Audience,
+    pub exp: i64,
+    pub nonce: Option<String>,
+    pub email: Option<String>,
+    pub email_verified: Option<bool>,
+}
+
+impl Claims {
+    /// The email, only if the provider says it has been verified; some
+    /// providers leave the claim out for addresses anyone could register
+    pub fn verified_email(&self) -> Option<String> {
+        self.email
+            .as_ref()
+            .filter(|_| self.email_verified == Some(true))
+            .map(|e| e.trim().to_lowercase())
+    }
+}
+
+/// Decode an ID token's claims and check they were issued to us, for this
+/// login, and haven't expired. `now` is a Unix timestamp.
+pub fn validate_id_token(
+    id_token: &str,
+    provider: &Provider,
+    config: &Config,
+    nonce: &str,
+    now: i64,
+) -> Result<Claims, Error> {
+    let payload = id_token
+        .split('.')
+        .nth(1)
+        .ok_or(Error::Invalid("ID token is not a JWT"))?;
+    let bytes = URL_SAFE_NO_PAD
+        .decode(payload)
+        .map_err(|_| Error::Invalid("ID token payload is not base64url"))?;
+    let claims: Claims = serde_json::from_slice(&bytes)
+        .map_err(|_| Error::Invalid("ID token claims are malformed"))?;
+
+    if claims.iss != provider.issuer {
+        return Err(Error::Invalid("ID token issuer mismatch"));
+    }
+    let audience_ok = match &claims.aud {
+        Audience::One(aud) => *aud == config.client_id,
+        Audience::Many(auds) => auds.contains(&config.client_id),
+    };
+    if !audience_ok {
+        return Err(Error::Invalid("ID token was issued to another client"));
+    }
+    if claims.exp <= now {
+        return Err(Error::Invalid("ID token has expired"));
+    }
+    if claims.nonce.as_deref() != Some(nonce) {
+        return Err(Error::Invalid("ID token nonce mismatch"));
+    }
+    Ok(claims)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> (Provider, Config) {
+        (
+            Provider {
+                issuer: "https://idp.example.com".to_string(),
+                authorization_endpoint: "https://idp.example.com/authorize".to_string(),
+                token_endpoint: "https://idp.example.com/token".to_string(),
+            },
+            Config {
+                issuer: "https://idp.example.com".to_string(),
+                client_id: "feedback".to_string(),
+                client_secret: "secret".to_string(),
+                redirect_url: None,
+            },
+        )
+    }
+
+    fn token(claims: serde_json::Value) -> String {
+        format!("e30.{}.sig", URL_SAFE_NO_PAD.encode(claims.to_string()))
+    }
+
+    #[test]
+    fn test_validate_id_token() {
+        let (provider, config) = fixtures();
+        let claims = serde_json::json!({
+            "iss": "https://idp.example.com",
+            "sub": "user-1",
+            "aud": ["other", "feedback"],
+            "exp": 2000,
+            "nonce": "n-1",
+            "email": "Ann@Example.com",
+            "email_verified": true,
+        });
+
+        let valid = validate_id_token(&token(claims.clone()), &provider, &config, "n-1", 1000);
+        let valid = valid.unwrap();
+        assert_eq!(valid.sub, "user-1");
+        assert_eq!(valid.verified_email().as_deref(), Some("ann@example.com"));
+
+        assert!(
+            validate_id_token(&token(claims.clone()), &provider, &config, "n-2", 1000).is_err()
+        );
+        assert!(
+            validate_id_token(&token(claims.clone()), &provider, &config, "n-1", 3000).is_err()
+        );
+
+        for verified in [serde_json::json!(false), serde_json::Value::Null] {
+            let mut unverified = claims.clone();
+            unverified["email_verified"] = verified;
+            let unverified =
+                validate_id_token(&token(unverified), &provider, &config, "n-1", 1000).unwrap();
+            assert_eq!(unverified.verified_email(), None);
+        }
+        let mut unclaimed = claims.clone();
+        unclaimed.as_object_mut().unwrap().remove("email_verified");
+        let unclaimed =
+            validate_id_token(&token(unclaimed), &provider, &config, "n-1", 1000).unwrap();
+        assert_eq!(unclaimed.verified_email(), None);
+
+        let mut foreign = claims;
+        foreign["aud"] = serde_json::json!("other");
+        assert!(validate_id_token(&token(foreign), &provider, &config, "n-1", 1000).is_err());
+        assert!(validate_id_token("garbage", &provider, &config, "n-1", 1000).is_err());
+    }
+
+    #[test]
+    fn test_authorization_url_carries_state_and_nonce() {
+        let (provider, config) = fixtures();
+        let url = authorization_url(
+            &provider,
+            &config,
+            "https://feedback.example.com/auth/oidc/callback",
+            "s-1",
+            "n-1",
+        )
+        .unwrap();
+        assert!(url.starts_with("https://idp.example.com/authorize?response_type=code"));
+        assert!(url.contains("state=s-1"));
+        assert!(url.contains("nonce=n-1"));
+        assert!(url
+            .contains("redirect_uri=https%3A%2F%2Ffeedback.example.com%2Fauth%2Foidc%2Fcallback"));
+    }
+}
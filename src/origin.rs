@@ -0,0 +1,224 @@
+//! The public origin (`https://feedback.example.com`) for absolute links in
+//! emails, embed snippets, feeds and OIDC redirects. Behind a reverse proxy
+//! the request arrives over plain HTTP with an internal `Host`, so the
+//! proxy's `Forwarded` / `X-Forwarded-Proto` / `X-Forwarded-Host` headers win
+//! over the request's own. Any client can send those headers, so they're
+//! only read when `TRUST_PROXY_HEADERS` says a proxy in front sets them. A
+//! base URL saved in settings overrides all of this (see `RequestOrigin` in
+//! main).
+
+use axum::http::{header, request::Parts, HeaderMap};
+use std::net::{IpAddr, SocketAddr};
+
+/// Marks requests that reached our own TLS listener (see `tls::serve`)
+#[derive(Debug, Clone, Copy)]
+pub struct Https;
+
+/// The origin the client used to reach us, or `None` when no host is known.
+/// `trust_proxy` honours the forwarding headers.
+pub fn detect(parts: &Parts, trust_proxy: bool) -> Option<String> {
+    let forwarded = parts
+        .headers
+        .get(header::FORWARDED)
+        .and_then(|v| v.to_str().ok())
+        .map(first_element)
+        .filter(|_| trust_proxy);
+    let forwarded_value = |name| first_value(&parts.headers, name).filter(|_| trust_proxy);
+
+    let scheme = forwarded
+        .and_then(|f| forwarded_param(f, "proto"))
+        .or_else(|| forwarded_value("x-forwarded-proto"))
+        .map(|s| s.to_ascii_lowercase())
+        .filter(|s| s == "http" || s == "https")
+        .or_else(|| parts.extensions.get::<Https>().map(|_| "https".to_string()))
+        .or_else(|| parts.uri.scheme_str().map(str::to_string))
+        .unwrap_or_else(|| "http".to_string());
+
+    let host = forwarded
+        .and_then(|f| forwarded_param(f, "host"))
+        .or_else(|| forwarded_value("x-forwarded-host"))
+        .or_else(|| {
+            parts
+                .headers
+                .get(header::HOST)
+                .and_then(|v| v.to_str().ok())
+        })
+        .or_else(|| parts.uri.authority().map(|a| a.as_str()))
+        .filter(|host| is_valid_host(host))?;
+
+    Some(format!("{}://{}", scheme, host.to_ascii_lowercase()))
+}
+
+/// The client's address as the proxy saw it, from `Forwarded: for=` or
+/// `X-Forwarded-For`. Proxies append to these headers, so everything left of
+/// the last entry came from the client; only the last one, added by the
+/// trusted proxy in front of us, is believed. Only meaningful behind a
+/// trusted proxy; `None` when that entry carries no IP (e.g. `for=unknown`
+/// or an obfuscated name).
+pub fn forwarded_client(headers: &HeaderMap) -> Option<IpAddr> {
+    let forwarded = headers
+        .get(header::FORWARDED)
+        .and_then(|v| v.to_str().ok())
+        .map(last_element)
+        .and_then(|f| forwarded_param(f, "for"));
+    forwarded
+        .or_else(|| last_value(headers, "x-forwarded-for"))
+        .and_then(parse_ip)
+}
+
+/// `1.2.3.4`, `1.2.3.4:5678`, `2001:db8::1` or `[2001:db8::1]:5678`
+fn parse_ip(value: &str) -> Option<IpAddr> {
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.parse::<SocketAddr>().ok().map(|a| a.ip()))
+        .or_else(|| {
+            value
+                .trim_start_matches('[')
+                .trim_end_matches(']')
+                .parse()
+                .ok()
+        })
+}
+
+/// The first (client-side) entry of a header a chain of proxies appends to
+fn first_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    let value = headers.get(name)?.to_str().ok()?;
+    Some(value.split(',').next()?.trim()).filter(|v| !v.is_empty())
+}
+
+/// The last entry, the one the nearest proxy appended
+fn last_value<'a>(headers: &'a HeaderMap, name: &str) -> Option<&'a str> {
+    let value = headers.get(name)?.to_str().ok()?;
+    Some(value.rsplit(',').next()?.trim()).filter(|v| !v.is_empty())
+}
+
+/// `Forwarded: for=1.2.3.4;proto=https, for=10.0.0.1` lists one element per hop
+fn first_element(value: &str) -> &str {
+    value.split(',').next().unwrap_or_default()
+}
+
+fn last_element(value: &str) -> &str {
+    value.rsplit(',').next().unwrap_or_default()
+}
+
+/// `proto=https` or `host="example.com"` from one `Forwarded` element
+fn forwarded_param<'a>(element: &'a str, name: &str) -> Option<&'a str> {
+    element.split(';').find_map(|pair| {
+        let (key, value) = pair.trim().split_once('=')?;
+        key.trim()
+            .eq_ignore_ascii_case(name)
+            .then(|| value.trim().trim_matches('"'))
+            .filter(|v| !v.is_empty())
+    })
+}
+
+/// A host with optional port, and nothing that could smuggle in a path
+fn is_valid_host(host: &str) -> bool {
+    !host.is_empty()
+        && host
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-' | ':' | '[' | ']'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::Request;
+
+    fn parts(headers: &[(&str, &str)]) -> Parts {
+        let mut request = Request::builder().uri("/admin");
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        request.body(()).unwrap().into_parts().0
+    }
+
+    fn origin(headers: &[(&str, &str)]) -> Option<String> {
+        detect(&parts(headers), true)
+    }
+
+    #[test]
+    fn test_detect() {
+        assert_eq!(
+            origin(&[("host", "localhost:3000")]).as_deref(),
+            Some("http://localhost:3000")
+        );
+        // Without a proxy saying otherwise, we're serving plain HTTP
+        assert_eq!(
+            origin(&[("host", "feedback.example.com")]).as_deref(),
+            Some("http://feedback.example.com")
+        );
+        assert_eq!(
+            origin(&[
+                ("host", "127.0.0.1:3000"),
+                ("x-forwarded-proto", "https"),
+                ("x-forwarded-host", "Feedback.Example.com, proxy.internal"),
+            ])
+            .as_deref(),
+            Some("https://feedback.example.com")
+        );
+        assert_eq!(
+            origin(&[
+                ("host", "app:3000"),
+                (
+                    "forwarded",
+                    "for=1.2.3.4;proto=https;host=\"fb.example.com\", for=10.0.0.1"
+                ),
+                ("x-forwarded-proto", "http"),
+            ])
+            .as_deref(),
+            Some("https://fb.example.com")
+        );
+
+        // Junk is ignored rather than echoed into links
+        assert_eq!(
+            origin(&[
+                ("host", "a.example.com"),
+                ("x-forwarded-proto", "javascript")
+            ])
+            .as_deref(),
+            Some("http://a.example.com")
+        );
+        assert_eq!(
+            origin(&[
+                ("host", "a.example.com"),
+                ("x-forwarded-host", "evil.com/x?")
+            ]),
+            None
+        );
+        assert_eq!(origin(&[]), None);
+
+        // Without a trusted proxy, the headers are the client's say-so
+        let spoofed = parts(&[
+            ("host", "feedback.example.com"),
+            ("x-forwarded-proto", "https"),
+            ("x-forwarded-host", "evil.example"),
+            ("forwarded", "proto=https;host=evil.example"),
+        ]);
+        assert_eq!(
+            detect(&spoofed, false).as_deref(),
+            Some("http://feedback.example.com")
+        );
+
+        let client = |headers: &[(&str, &str)]| forwarded_client(&parts(headers).headers);
+        // The client can prepend whatever it likes; the proxy's entry is last
+        assert_eq!(
+            client(&[("x-forwarded-for", "spoofed, 203.0.113.9")]),
+            "203.0.113.9".parse().ok()
+        );
+        assert_eq!(
+            client(&[("forwarded", "for=198.51.100.1, for=203.0.113.9")]),
+            "203.0.113.9".parse().ok()
+        );
+        assert_eq!(
+            client(&[
+                ("forwarded", "for=\"[2001:db8::1]:4711\";proto=https"),
+                ("x-forwarded-for", "203.0.113.7"),
+            ]),
+            "2001:db8::1".parse().ok()
+        );
+        assert_eq!(client(&[("forwarded", "for=unknown")]), None);
+        assert_eq!(client(&[]), None);
+    }
+}
@@ -0,0 +1,274 @@
+//! Respondents' requests about their own data: a copy of everything a
+//! workspace holds from an email address, or its deletion. Owners run both
+//! from the privacy page, and each is logged in `privacy_requests` with a
+//! digest of the address rather than the address itself.
+
+use chrono::Utc;
+use serde::Serialize;
+use sqlx::sqlite::SqlitePool;
+
+use crate::{auth, db};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    Export,
+    Delete,
+}
+
+impl Action {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Action::Export => "export",
+            Action::Delete => "delete",
+        }
+    }
+}
+
+/// One line of the audit trail
+#[derive(Debug, Clone, sqlx::FromRow)]
+pub struct Request {
+    pub action: String,
+    /// The address with most of its local part hidden, e.g. `a***@example.com`
+    pub respondent_hint: String,
+    pub entries: i64,
+    /// Email of the admin who ran it; None when run without an account
+    pub performed_by: Option<String>,
+    pub created_at: String,
+}
+
+/// The download handed to a respondent who asked for their data
+#[derive(Debug, Serialize)]
+pub struct Export {
+    pub respondent: String,
+    pub workspace: String,
+    pub exported_at: String,
+    pub entries: Vec<ExportEntry>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ExportEntry {
+    pub prompt_title: String,
+    #[serde(flatten)]
+    pub feedback: db::Feedback,
+    pub replies: Vec<ExportReply>,
+}
+
+/// An admin's reply, without who wrote it
+#[derive(Debug, Serialize)]
+pub struct ExportReply {
+    pub content: String,
+    pub created_at: String,
+}
+
+/// Everything `workspace` holds from `email`, logged as an export
+pub async fn export(
+    pool: &SqlitePool,
+    workspace: &db::Workspace,
+    email: &str,
+    performed_by: Option<&str>,
+) -> Result<Export, sqlx::Error> {
+    let entries: Vec<ExportEntry> = db::get_respondent_data(pool, &workspace.id, email)
+        .await?
+        .into_iter()
+        .map(|entry| {
+            let mut feedback = entry.feedback;
+            let replies = std::mem::take(&mut feedback.replies)
+                .into_iter()
+                .map(|reply| ExportReply {
+                    content: reply.content,
+                    created_at: reply.created_at,
+                })
+                .collect();
+            ExportEntry {
+                prompt_title: entry.prompt_title,
+                feedback,
+                replies,
+            }
+        })
+        .collect();
+    log(
+        pool,
+        &workspace.id,
+        Action::Export,
+        email,
+        entries.len() as i64,
+        performed_by,
+    )
+    .await?;
+    Ok(Export {
+        respondent: email.to_string(),
+        workspace: workspace.name.clone(),
+        exported_at: Utc::now().to_rfc3339(),
+        entries,
+    })
+}
+
+/// Delete everything `workspace` holds from `email`, logged; returns how
+/// many entries went
+pub async fn erase(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    email: &str,
+    performed_by: Option<&str>,
+) -> Result<u64, sqlx::Error> {
+    let deleted = db::delete_feedback_by_respondent(pool, workspace_id, email).await?;
+    log(
+        pool,
+        workspace_id,
+        Action::Delete,
+        email,
+        deleted as i64,
+        performed_by,
+    )
+    .await?;
+    Ok(deleted)
+}
+
+/// The workspace's most recent privacy requests, newest first
+pub async fn history(pool: &SqlitePool, workspace_id: &str) -> Result<Vec<Request>, sqlx::Error> {
+    sqlx::query_as::<_, Request>(
+        "SELECT action, respondent_hint, entries, performed_by, created_at FROM privacy_requests WHERE workspace_id = ? ORDER BY created_at DESC LIMIT 100",
+    )
+    .bind(workspace_id)
+    .fetch_all(pool)
+    .await
+}
+
+/// Whether a request for `email` was ever logged in the workspace, for
+/// answering "did you delete my data?" without keeping the address
+pub async fn was_requested(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    email: &str,
+    action: Action,
+) -> Result<bool, sqlx::Error> {
+    let found: Option<(String,)> = sqlx::query_as(
+        "SELECT id FROM privacy_requests WHERE workspace_id = ? AND respondent = ? AND action = ? LIMIT 1",
+    )
+    .bind(workspace_id)
+    .bind(auth::token_digest(email))
+    .bind(action.as_str())
+    .fetch_optional(pool)
+    .await?;
+    Ok(found.is_some())
+}
+
+async fn log(
+    pool: &SqlitePool,
+    workspace_id: &str,
+    action: Action,
+    email: &str,
+    entries: i64,
+    performed_by: Option<&str>,
+) -> Result<(), sqlx::Error> {
+    sqlx::query(
+        "INSERT INTO privacy_requests (id, workspace_id, action, respondent, respondent_hint, entries, performed_by, created_at) VALUES (?, ?, ?, ?, ?, ?, ?, ?)",
+    )
+    .bind(uuid::Uuid::new_v4().to_string())
+    .bind(workspace_id)
+    .bind(action.as_str())
+    .bind(auth::token_digest(email))
+    .bind(hint(email))
+    .bind(entries)
+    .bind(performed_by)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Enough of an address to tell requests apart in the log
+pub fn hint(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => {
+            let first: String = local.chars().take(1).collect();
+            format!("{}***@{}", first, domain)
+        }
+        None => "***".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hint() {
+        assert_eq!(hint("ann@example.com"), "a***@example.com");
+        assert_eq!(hint("@example.com"), "***@example.com");
+        assert_eq!(hint("nobody"), "***");
+    }
+
+    #[tokio::test]
+    async fn test_export_then_erase() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let workspace = db::get_workspace_by_slug(&pool, "default")
+            .await
+            .unwrap()
+            .unwrap();
+        let prompt = db::create_prompt(&pool, "Q", "").await.unwrap();
+        let options = |email: &str| db::FeedbackOptions {
+            respondent_email: Some(email.to_string()),
+            ..Default::default()
+        };
+        let ann =
+            db::create_feedback_with_options(&pool, &prompt.id, "Mine", &options("ann@x.com"))
+                .await
+                .unwrap();
+        db::create_reply(&pool, &ann.id, Some("owner@x.com"), "Thanks")
+            .await
+            .unwrap();
+        let spam = db::FeedbackOptions {
+            spam_reason: Some("honeypot".to_string()),
+            ..options("ann@x.com")
+        };
+        db::create_feedback_with_options(&pool, &prompt.id, "Also mine", &spam)
+            .await
+            .unwrap();
+        db::create_feedback_with_options(&pool, &prompt.id, "Not hers", &options("bob@x.com"))
+            .await
+            .unwrap();
+
+        let export = export(&pool, &workspace, "ann@x.com", Some("owner@x.com"))
+            .await
+            .unwrap();
+        assert_eq!(export.entries.len(), 2);
+        assert_eq!(export.entries[0].replies[0].content, "Thanks");
+        let json = serde_json::to_value(&export).unwrap();
+        assert_eq!(json["entries"][0]["content"], "Mine");
+        assert!(json["entries"][0]["replies"][0].get("author").is_none());
+
+        assert_eq!(
+            erase(&pool, &workspace.id, "ann@x.com", None)
+                .await
+                .unwrap(),
+            2
+        );
+        let left = db::get_feedback_for_prompt(&pool, &prompt.id)
+            .await
+            .unwrap();
+        assert_eq!(left.len(), 1);
+        assert_eq!(left[0].content, "Not hers");
+        assert!(db::get_respondent_data(&pool, &workspace.id, "ann@x.com")
+            .await
+            .unwrap()
+            .is_empty());
+
+        let log = history(&pool, &workspace.id).await.unwrap();
+        assert_eq!(log.len(), 2);
+        assert_eq!(log[0].action, "delete");
+        assert_eq!(log[0].entries, 2);
+        assert_eq!(log[1].performed_by.as_deref(), Some("owner@x.com"));
+        assert!(log.iter().all(|r| r.respondent_hint == "a***@x.com"));
+        assert!(
+            was_requested(&pool, &workspace.id, "ann@x.com", Action::Delete)
+                .await
+                .unwrap()
+        );
+        assert!(
+            !was_requested(&pool, &workspace.id, "bob@x.com", Action::Delete)
+                .await
+                .unwrap()
+        );
+    }
+}
@@ -0,0 +1,143 @@
+//! One-tap emoji reactions for kiosk-style prompts. The form shows each face
+//! as a submit button, so a single tap sends the reaction along with any
+//! comment typed first; feedback stores the reaction's key.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reaction {
+    Love,
+    Happy,
+    Neutral,
+    Unhappy,
+}
+
+impl Reaction {
+    /// In the order the form shows them, happiest first
+    pub const ALL: [Reaction; 4] = [
+        Reaction::Love,
+        Reaction::Happy,
+        Reaction::Neutral,
+        Reaction::Unhappy,
+    ];
+
+    pub fn parse(key: &str) -> Option<Self> {
+        Reaction::ALL.into_iter().find(|r| r.key() == key)
+    }
+
+    pub fn key(&self) -> &'static str {
+        match self {
+            Reaction::Love => "love",
+            Reaction::Happy => "happy",
+            Reaction::Neutral => "neutral",
+            Reaction::Unhappy => "unhappy",
+        }
+    }
+
+    pub fn emoji(&self) -> &'static str {
+        match self {
+            Reaction::Love => "😍",
+            Reaction::Happy => "🙂",
+            Reaction::Neutral => "😐",
+            Reaction::Unhappy => "🙁",
+        }
+    }
+
+    /// For the admin pages; the public form uses `message_id`
+    pub fn label(&self) -> &'static str {
+        match self {
+            Reaction::Love => "Love it",
+            Reaction::Happy => "Good",
+            Reaction::Neutral => "Okay",
+            Reaction::Unhappy => "Not good",
+        }
+    }
+
+    /// Locale key of the button's accessible name
+    pub fn message_id(&self) -> &'static str {
+        match self {
+            Reaction::Love => "reaction-love",
+            Reaction::Happy => "reaction-happy",
+            Reaction::Neutral => "reaction-neutral",
+            Reaction::Unhappy => "reaction-unhappy",
+        }
+    }
+}
+
+/// The emoji for a stored key, or the key itself if it's unknown
+pub fn emoji_for(key: &str) -> &str {
+    Reaction::parse(key).map_or(key, |r| r.emoji())
+}
+
+/// How often each reaction was picked on a prompt
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counts {
+    /// In `Reaction::ALL` order
+    pub counts: [i64; 4],
+}
+
+impl Counts {
+    /// From `(key, count)` rows; unknown keys are skipped
+    pub fn from_rows(rows: &[(String, i64)]) -> Self {
+        let mut counts = Counts::default();
+        for (key, count) in rows {
+            if let Some(index) = Reaction::ALL.iter().position(|r| r.key() == key) {
+                counts.counts[index] += count;
+            }
+        }
+        counts
+    }
+
+    pub fn total(&self) -> i64 {
+        self.counts.iter().sum()
+    }
+
+    /// `(reaction, count, percent of all reactions)`, in form order
+    pub fn breakdown(&self) -> Vec<(Reaction, i64, i64)> {
+        let total = self.total();
+        Reaction::ALL
+            .into_iter()
+            .zip(self.counts)
+            .map(|(reaction, count)| {
+                let percent = match total {
+                    0 => 0,
+                    total => (count as f64 * 100.0 / total as f64).round() as i64,
+                };
+                (reaction, count, percent)
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_counts() {
+        for reaction in Reaction::ALL {
+            assert_eq!(Reaction::parse(reaction.key()), Some(reaction));
+        }
+        assert_eq!(Reaction::parse("😍"), None);
+        assert_eq!(emoji_for("unhappy"), "🙁");
+        assert_eq!(emoji_for("angry"), "angry");
+
+        let counts = Counts::from_rows(&[
+            ("happy".to_string(), 3),
+            ("unhappy".to_string(), 1),
+            ("angry".to_string(), 5),
+        ]);
+        assert_eq!(counts.total(), 4);
+        assert_eq!(
+            counts.breakdown(),
+            vec![
+                (Reaction::Love, 0, 0),
+                (Reaction::Happy, 3, 75),
+                (Reaction::Neutral, 0, 0),
+                (Reaction::Unhappy, 1, 25),
+            ]
+        );
+        assert!(Counts::default()
+            .breakdown()
+            .iter()
+            .all(|(_, _, p)| *p == 0));
+    }
+}
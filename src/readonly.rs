@@ -0,0 +1,117 @@
+//! Degraded mode for a database that refuses writes: a full disk, a
+//! read-only mount or a replica. Public forms tell respondents submissions
+//! are paused instead of failing with opaque errors, while admin pages,
+//! which only read, keep working.
+
+use sqlx::sqlite::SqlitePool;
+use sqlx::Acquire;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// SQLite primary result codes that mean writes can't succeed right now
+const SQLITE_READONLY: i64 = 8;
+const SQLITE_FULL: i64 = 13;
+
+/// Whether the database is currently taking writes, as last observed
+#[derive(Debug, Default)]
+pub struct Monitor {
+    read_only: AtomicBool,
+}
+
+impl Monitor {
+    pub fn is_read_only(&self) -> bool {
+        self.read_only.load(Ordering::Relaxed)
+    }
+
+    /// Record the database's state, logging when it changes
+    pub fn set(&self, read_only: bool) {
+        if self.read_only.swap(read_only, Ordering::Relaxed) != read_only {
+            if read_only {
+                eprintln!("Database is refusing writes; submissions are paused");
+            } else {
+                println!("Database is writable again; submissions resumed");
+            }
+        }
+    }
+
+    /// Switch to read-only mode if a failed write says the database refuses writes
+    pub fn observe(&self, e: &sqlx::Error) {
+        if is_read_only_error(e) {
+            self.set(true);
+        }
+    }
+}
+
+/// Whether an error means the database can't be written (read-only or disk full)
+pub fn is_read_only_error(e: &sqlx::Error) -> bool {
+    let Some(code) = e
+        .as_database_error()
+        .and_then(|db| db.code())
+        .and_then(|code| code.parse::<i64>().ok())
+    else {
+        return false;
+    };
+    // Extended result codes keep the primary code in the low byte
+    matches!(code & 0xff, SQLITE_READONLY | SQLITE_FULL)
+}
+
+/// Try a write that changes nothing: `Ok(false)` when the database refuses it
+pub async fn probe(pool: &SqlitePool) -> Result<bool, sqlx::Error> {
+    let mut conn = pool.acquire().await?;
+    let (version,): (i64,) = sqlx::query_as("PRAGMA user_version")
+        .fetch_one(&mut *conn)
+        .await?;
+    let mut tx = conn.begin().await?;
+    // Rewriting the header field needs a write transaction, then rolls back
+    let result = sqlx::query(&format!("PRAGMA user_version = {}", version))
+        .execute(&mut *tx)
+        .await;
+    let _ = tx.rollback().await;
+    match result {
+        Ok(_) => Ok(true),
+        Err(e) if is_read_only_error(&e) => Ok(false),
+        Err(e) => Err(e),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[tokio::test]
+    async fn test_probe_detects_read_only_database() {
+        let dir = std::env::temp_dir().join(format!("readonly-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("feedback.db");
+
+        let pool = db::init_db(&format!("sqlite:{}?mode=rwc", path.display()))
+            .await
+            .unwrap();
+        let prompt = db::create_prompt(&pool, "Replica", "Desc").await.unwrap();
+        assert!(probe(&pool).await.unwrap());
+        pool.close().await;
+
+        // A replica opened read-only still starts up and reads
+        let replica = db::init_db(&format!("sqlite:{}?mode=ro", path.display()))
+            .await
+            .unwrap();
+        assert!(db::get_prompt_by_id(&replica, &prompt.id)
+            .await
+            .unwrap()
+            .is_some());
+        assert!(!probe(&replica).await.unwrap());
+
+        let e = db::create_feedback(&replica, &prompt.id, "Hello")
+            .await
+            .unwrap_err();
+        assert!(is_read_only_error(&e));
+        let monitor = Monitor::default();
+        monitor.observe(&e);
+        assert!(monitor.is_read_only());
+        monitor.set(false);
+        assert!(!monitor.is_read_only());
+
+        replica.close().await;
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
@@ -0,0 +1,191 @@
+//! Data retention: feedback older than a retention period is permanently
+//! deleted by a daily background purge. The period can be set site-wide on
+//! the settings page and per prompt; where both are set the shorter one
+//! applies, so the site-wide period is a ceiling no prompt can exceed. Each
+//! purge is recorded in `retention_runs` with what it deleted.
+
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::{sqlite::SqlitePool, FromRow};
+
+use crate::{db, settings};
+
+/// Ten years; anything longer is as good as forever
+pub const MAX_DAYS: i64 = 3650;
+/// How often feedback is purged
+pub const INTERVAL: Duration = Duration::hours(24);
+/// Shown when a form's retention period doesn't parse
+pub const INVALID_DAYS: &str = "The retention period must be a whole number of days from 1 to 3650";
+
+pub fn is_valid_days(days: i64) -> bool {
+    (1..=MAX_DAYS).contains(&days)
+}
+
+/// A retention period from a form: blank for none
+pub fn parse_days(value: &str) -> Result<Option<i64>, &'static str> {
+    match value.trim() {
+        "" => Ok(None),
+        value => value
+            .parse()
+            .ok()
+            .filter(|days| is_valid_days(*days))
+            .map(Some)
+            .ok_or(INVALID_DAYS),
+    }
+}
+
+/// The period that applies to a prompt: the shorter of its own and the
+/// site-wide one
+pub fn effective_days(prompt_days: Option<i64>, site_days: Option<i64>) -> Option<i64> {
+    match (prompt_days, site_days) {
+        (Some(prompt), Some(site)) => Some(prompt.min(site)),
+        (prompt, site) => prompt.or(site),
+    }
+}
+
+/// What one purge deleted from one prompt
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Purged {
+    pub prompt_id: String,
+    pub prompt_title: String,
+    /// Entries created before this were deleted
+    pub cutoff: String,
+    pub deleted: u64,
+}
+
+#[derive(Debug, Clone, FromRow)]
+pub struct Run {
+    pub id: String,
+    pub ran_at: String,
+    pub deleted: i64,
+    /// JSON array of `Purged`, one per prompt that lost entries
+    pub purged: String,
+}
+
+impl Run {
+    pub fn purged(&self) -> Vec<Purged> {
+        serde_json::from_str(&self.purged).unwrap_or_default()
+    }
+}
+
+/// Delete every entry past its prompt's retention period, recording the run
+pub async fn purge(pool: &SqlitePool, now: DateTime<Utc>) -> Result<Run, sqlx::Error> {
+    let site_days = settings::load(pool).await?.retention_days;
+    let prompts: Vec<(String, String, Option<i64>)> =
+        sqlx::query_as("SELECT id, title, retention_days FROM prompts ORDER BY created_at")
+            .fetch_all(pool)
+            .await?;
+
+    let mut purged = Vec::new();
+    for (prompt_id, prompt_title, prompt_days) in prompts {
+        let Some(days) = effective_days(prompt_days, site_days) else {
+            continue;
+        };
+        let cutoff = now - Duration::days(days);
+        let deleted = db::delete_feedback_before(pool, &prompt_id, cutoff).await?;
+        if deleted > 0 {
+            purged.push(Purged {
+                prompt_id,
+                prompt_title,
+                cutoff: cutoff.to_rfc3339(),
+                deleted,
+            });
+        }
+    }
+
+    let run = Run {
+        id: uuid::Uuid::new_v4().to_string(),
+        ran_at: now.to_rfc3339(),
+        deleted: purged.iter().map(|p| p.deleted as i64).sum(),
+        purged: serde_json::to_string(&purged).unwrap_or_else(|_| "[]".to_string()),
+    };
+    sqlx::query("INSERT INTO retention_runs (id, ran_at, deleted, purged) VALUES (?, ?, ?, ?)")
+        .bind(&run.id)
+        .bind(&run.ran_at)
+        .bind(run.deleted)
+        .bind(&run.purged)
+        .execute(pool)
+        .await?;
+    Ok(run)
+}
+
+/// Most recent purges that deleted something, newest first
+pub async fn recent_purges(pool: &SqlitePool, limit: i64) -> Result<Vec<Run>, sqlx::Error> {
+    sqlx::query_as::<_, Run>(
+        "SELECT id, ran_at, deleted, purged FROM retention_runs WHERE deleted > 0 ORDER BY ran_at DESC LIMIT ?",
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await
+}
+
+/// When the next purge is due: a day after the last one, or right away if
+/// there hasn't been one (or it's overdue)
+pub async fn next_run(pool: &SqlitePool, now: DateTime<Utc>) -> Result<DateTime<Utc>, sqlx::Error> {
+    let last: Option<String> = sqlx::query_scalar("SELECT MAX(ran_at) FROM retention_runs")
+        .fetch_one(pool)
+        .await?;
+    let last = last
+        .and_then(|t| DateTime::parse_from_rfc3339(&t).ok())
+        .map(|t| t.with_timezone(&Utc));
+    Ok(last.map_or(now, |t| (t + INTERVAL).max(now)))
+}
+
+pub async fn is_due(pool: &SqlitePool, now: DateTime<Utc>) -> Result<bool, sqlx::Error> {
+    Ok(next_run(pool, now).await? <= now)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_purge_respects_shorter_period() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        assert_eq!(parse_days(" 365 "), Ok(Some(365)));
+        assert_eq!(parse_days(""), Ok(None));
+        assert_eq!(parse_days("0"), Err(INVALID_DAYS));
+        assert_eq!(effective_days(Some(30), Some(365)), Some(30));
+        assert_eq!(effective_days(Some(800), Some(365)), Some(365));
+        assert_eq!(effective_days(None, None), None);
+
+        let kept = db::create_prompt(&pool, "Kept", "").await.unwrap();
+        let short = db::create_prompt(&pool, "Short", "").await.unwrap();
+        db::set_retention_days(&pool, &short.id, Some(1))
+            .await
+            .unwrap();
+        db::create_feedback(&pool, &kept.id, "Old news")
+            .await
+            .unwrap();
+        db::create_feedback(&pool, &short.id, "Also old")
+            .await
+            .unwrap();
+
+        // Two days on, only the one-day prompt has anything past its period
+        let later = Utc::now() + Duration::days(2);
+        assert!(is_due(&pool, later).await.unwrap());
+        let run = purge(&pool, later).await.unwrap();
+        assert_eq!(run.deleted, 1);
+        assert_eq!(run.purged()[0].prompt_title, "Short");
+        assert!(!is_due(&pool, later).await.unwrap());
+        assert_eq!(next_run(&pool, later).await.unwrap(), later + INTERVAL);
+
+        // A site-wide period catches the rest
+        let settings = settings::Settings {
+            retention_days: Some(1),
+            ..settings::Settings::default()
+        };
+        settings::save(&pool, &settings).await.unwrap();
+        let run = purge(&pool, later + INTERVAL).await.unwrap();
+        assert_eq!(run.deleted, 1);
+        assert!(db::get_feedback_for_prompt(&pool, &kept.id)
+            .await
+            .unwrap()
+            .is_empty());
+        assert_eq!(recent_purges(&pool, 10).await.unwrap().len(), 2);
+
+        // Quiet runs are recorded for scheduling but not listed
+        purge(&pool, later + INTERVAL * 2).await.unwrap();
+        assert_eq!(recent_purges(&pool, 10).await.unwrap().len(), 2);
+    }
+}
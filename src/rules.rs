@@ -0,0 +1,125 @@
+//! Constraints on a respondent's answer to a field: length bounds and an
+//! optional pattern. Set when building a prompt and checked server-side on
+//! every submission, so a short-answer field can't take an essay.
+
+use regex::{Regex, RegexBuilder};
+use serde::{Deserialize, Serialize};
+
+/// Longest pattern an admin may set
+pub const MAX_PATTERN_LEN: usize = 200;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct FieldRules {
+    /// Characters, counted after trimming
+    pub min_length: Option<i64>,
+    pub max_length: Option<i64>,
+    /// Must match the whole answer, like the HTML `pattern` attribute
+    pub pattern: Option<String>,
+}
+
+impl FieldRules {
+    /// Check the rules themselves make sense, naming the offending setting
+    pub fn validate(&self) -> Result<(), (&'static str, String)> {
+        if self.min_length.is_some_and(|n| n < 1) {
+            return Err(("min_length", "must be at least 1".to_string()));
+        }
+        if self.max_length.is_some_and(|n| n < 1) {
+            return Err(("max_length", "must be at least 1".to_string()));
+        }
+        if let (Some(min), Some(max)) = (self.min_length, self.max_length) {
+            if min > max {
+                return Err((
+                    "max_length",
+                    "must not be less than the minimum".to_string(),
+                ));
+            }
+        }
+        if let Some(pattern) = &self.pattern {
+            if pattern.len() > MAX_PATTERN_LEN {
+                return Err((
+                    "pattern",
+                    format!("must be at most {} characters", MAX_PATTERN_LEN),
+                ));
+            }
+            if let Err(e) = compile(pattern) {
+                return Err(("pattern", format!("is not a valid pattern: {}", e)));
+            }
+        }
+        Ok(())
+    }
+
+    /// Check an answer, returning what's wrong with it
+    pub fn check(&self, value: &str) -> Result<(), String> {
+        let len = value.trim().chars().count() as i64;
+        if let Some(min) = self.min_length.filter(|&min| len < min) {
+            return Err(format!("must be at least {} characters", min));
+        }
+        if let Some(max) = self.max_length.filter(|&max| len > max) {
+            return Err(format!("must be at most {} characters", max));
+        }
+        if let Some(pattern) = &self.pattern {
+            // Unparseable patterns are refused when set, so treat one here as no constraint
+            if compile(pattern).is_ok_and(|re| !re.is_match(value.trim())) {
+                return Err("is not in the expected format".to_string());
+            }
+        }
+        Ok(())
+    }
+}
+
+fn compile(pattern: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(&format!("^(?:{})$", pattern))
+        .size_limit(1 << 20)
+        .build()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_check() {
+        let rules = FieldRules {
+            min_length: Some(3),
+            max_length: Some(5),
+            pattern: Some("[a-z]+".to_string()),
+        };
+        assert_eq!(rules.check("  abcd "), Ok(()));
+        assert_eq!(
+            rules.check("ab"),
+            Err("must be at least 3 characters".to_string())
+        );
+        assert_eq!(
+            rules.check("abcdef"),
+            Err("must be at most 5 characters".to_string())
+        );
+        // Anchored, so a partial match isn't enough
+        assert_eq!(
+            rules.check("abc1"),
+            Err("is not in the expected format".to_string())
+        );
+        assert_eq!(FieldRules::default().check(""), Ok(()));
+    }
+
+    #[test]
+    fn test_validate() {
+        let rules = |min, max, pattern: Option<&str>| FieldRules {
+            min_length: min,
+            max_length: max,
+            pattern: pattern.map(str::to_string),
+        };
+        assert!(rules(Some(1), Some(280), Some(r"\d{5}")).validate().is_ok());
+        assert_eq!(
+            rules(Some(0), None, None).validate().unwrap_err().0,
+            "min_length"
+        );
+        assert_eq!(
+            rules(Some(10), Some(5), None).validate().unwrap_err().0,
+            "max_length"
+        );
+        assert_eq!(
+            rules(None, None, Some("(")).validate().unwrap_err().0,
+            "pattern"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+//! Lexicon-based sentiment scoring for feedback text.
+//!
+//! Each known word carries a weight; a preceding negation flips it and a
+//! preceding intensifier amplifies it. The summed weights are squashed into a
+//! compound score in [-1, 1].
+
+const POSITIVE: &[(&str, f64)] = &[
+    ("amazing", 3.0),
+    ("awesome", 3.0),
+    ("excellent", 3.0),
+    ("fantastic", 3.0),
+    ("love", 3.0),
+    ("loved", 3.0),
+    ("perfect", 3.0),
+    ("wonderful", 3.0),
+    ("brilliant", 3.0),
+    ("great", 2.5),
+    ("delightful", 2.5),
+    ("enjoy", 2.0),
+    ("enjoyed", 2.0),
+    ("happy", 2.0),
+    ("helpful", 2.0),
+    ("impressive", 2.0),
+    ("like", 1.5),
+    ("liked", 1.5),
+    ("nice", 1.5),
+    ("pleased", 2.0),
+    ("recommend", 2.0),
+    ("smooth", 1.5),
+    ("thanks", 1.5),
+    ("thank", 1.5),
+    ("useful", 1.5),
+    ("good", 1.5),
+    ("easy", 1.5),
+    ("fast", 1.0),
+    ("clear", 1.0),
+    ("intuitive", 1.5),
+    ("reliable", 1.5),
+    ("better", 1.0),
+    ("fine", 0.5),
+];
+
+const NEGATIVE: &[(&str, f64)] = &[
+    ("awful", -3.0),
+    ("horrible", -3.0),
+    ("terrible", -3.0),
+    ("hate", -3.0),
+    ("hated", -3.0),
+    ("worst", -3.0),
+    ("useless", -2.5),
+    ("broken", -2.5),
+    ("angry", -2.5),
+    ("furious", -3.0),
+    ("disappointed", -2.0),
+    ("disappointing", -2.0),
+    ("frustrating", -2.0),
+    ("frustrated", -2.0),
+    ("annoying", -2.0),
+    ("bad", -2.0),
+    ("crash", -2.0),
+    ("crashes", -2.0),
+    ("crashed", -2.0),
+    ("bug", -1.5),
+    ("buggy", -2.0),
+    ("confusing", -1.5),
+    ("confused", -1.5),
+    ("difficult", -1.5),
+    ("hard", -1.0),
+    ("poor", -2.0),
+    ("slow", -1.5),
+    ("expensive", -1.0),
+    ("problem", -1.5),
+    ("problems", -1.5),
+    ("issue", -1.0),
+    ("issues", -1.0),
+    ("unhappy", -2.0),
+    ("fail", -2.0),
+    ("failed", -2.0),
+    ("fails", -2.0),
+    ("error", -1.5),
+    ("errors", -1.5),
+    ("worse", -1.5),
+    ("missing", -1.0),
+];
+
+const NEGATIONS: &[&str] = &[
+    "not", "no", "never", "isn't", "wasn't", "don't", "doesn't", "didn't", "can't", "cannot",
+    "won't", "aren't", "hardly",
+];
+
+const INTENSIFIERS: &[&str] = &[
+    "very",
+    "really",
+    "extremely",
+    "super",
+    "so",
+    "incredibly",
+    "absolutely",
+    "totally",
+];
+
+/// Normalization constant for squashing summed weights into [-1, 1]
+const ALPHA: f64 = 15.0;
+
+/// Compound scores at or beyond this magnitude get a positive/negative label
+const LABEL_THRESHOLD: f64 = 0.05;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Label {
+    Positive,
+    Neutral,
+    Negative,
+}
+
+impl Label {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Label::Positive => "positive",
+            Label::Neutral => "neutral",
+            Label::Negative => "negative",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "positive" => Some(Label::Positive),
+            "neutral" => Some(Label::Neutral),
+            "negative" => Some(Label::Negative),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sentiment {
+    /// Compound score from -1 (most negative) to 1 (most positive)
+    pub score: f64,
+    pub label: Label,
+}
+
+fn word_weight(word: &str) -> Option<f64> {
+    POSITIVE
+        .iter()
+        .chain(NEGATIVE.iter())
+        .find(|(w, _)| *w == word)
+        .map(|(_, weight)| *weight)
+}
+
+pub fn analyze(text: &str) -> Sentiment {
+    let lowered = text.to_lowercase();
+    let words: Vec<&str> = lowered
+        .split(|c: char| !(c.is_alphanumeric() || c == '\''))
+        .filter(|w| !w.is_empty())
+        .collect();
+
+    let mut total = 0.0;
+    for (i, word) in words.iter().enumerate() {
+        let Some(mut weight) = word_weight(word) else {
+            continue;
+        };
+
+        // Look back a few words for modifiers, e.g. "not very good"
+        for previous in words[i.saturating_sub(3)..i].iter() {
+            if NEGATIONS.contains(previous) {
+                weight *= -0.75;
+            } else if INTENSIFIERS.contains(previous) {
+                weight *= 1.3;
+            }
+        }
+        total += weight;
+    }
+
+    let score = total / (total * total + ALPHA).sqrt();
+    let label = if score >= LABEL_THRESHOLD {
+        Label::Positive
+    } else if score <= -LABEL_THRESHOLD {
+        Label::Negative
+    } else {
+        Label::Neutral
+    };
+
+    Sentiment { score, label }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_positive_and_negative() {
+        assert_eq!(analyze("I love the new dashboard!").label, Label::Positive);
+        assert_eq!(
+            analyze("The app crashes and support was terrible").label,
+            Label::Negative
+        );
+        assert_eq!(analyze("I used it on Tuesday.").label, Label::Neutral);
+    }
+
+    #[test]
+    fn test_negation_flips_sentiment() {
+        assert_eq!(analyze("This is not good").label, Label::Negative);
+        assert_eq!(analyze("Honestly not bad at all").label, Label::Positive);
+    }
+
+    #[test]
+    fn test_intensifier_strengthens_score() {
+        let plain = analyze("it is good").score;
+        let intense = analyze("it is really good").score;
+        assert!(intense > plain);
+    }
+
+    #[test]
+    fn test_score_is_bounded() {
+        let score = analyze(&"amazing ".repeat(200)).score;
+        assert!(score <= 1.0 && score > 0.9);
+        let score = analyze(&"terrible ".repeat(200)).score;
+        assert!((-1.0..-0.9).contains(&score));
+    }
+}
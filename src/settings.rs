@@ -0,0 +1,179 @@
+//! Site-wide settings, kept in the single-row `settings` table: the name and
+//! base URL chosen in the setup wizard, and the branding and site-wide
+//! retention period owners pick on the settings page. Every page built on
+//! `base.html` gets the branding through its `layout` field.
+
+use chrono::Utc;
+use sqlx::sqlite::SqlitePool;
+
+pub const DEFAULT_SITE_NAME: &str = "Feedback App";
+
+/// Used for buttons and links unless an accent color is set
+pub const DEFAULT_ACCENT_COLOR: &str = "#3498db";
+
+pub const MAX_SITE_NAME_LEN: usize = 80;
+pub const MAX_FOOTER_LEN: usize = 500;
+
+const COLUMNS: &str =
+    "site_name, base_url, organization_name, logo_url, accent_color, footer_text, retention_days";
+
+#[derive(Debug, Clone, PartialEq, Eq, sqlx::FromRow)]
+pub struct Settings {
+    pub site_name: String,
+    /// Origin for links in pages and email, e.g. `https://feedback.example.com`;
+    /// unset derives it from each request's `Host`
+    pub base_url: Option<String>,
+    /// Shown beside the logo at the top of every page
+    pub organization_name: Option<String>,
+    pub logo_url: Option<String>,
+    /// `#rgb` or `#rrggbb`
+    pub accent_color: Option<String>,
+    /// Markdown shown at the bottom of every page
+    pub footer_text: Option<String>,
+    /// Site-wide retention period in days; see `retention`
+    pub retention_days: Option<i64>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            site_name: DEFAULT_SITE_NAME.to_string(),
+            base_url: None,
+            organization_name: None,
+            logo_url: None,
+            accent_color: None,
+            footer_text: None,
+            retention_days: None,
+        }
+    }
+}
+
+impl Settings {
+    pub fn layout(&self) -> Layout {
+        Layout {
+            site_name: self.site_name.clone(),
+            organization_name: self.organization_name.clone(),
+            logo_url: self.logo_url.clone(),
+            accent_color: self
+                .accent_color
+                .clone()
+                .unwrap_or_else(|| DEFAULT_ACCENT_COLOR.to_string()),
+            footer_text: self.footer_text.clone(),
+            flash: None,
+        }
+    }
+}
+
+/// What `base.html` needs to brand a page
+#[derive(Debug, Clone)]
+pub struct Layout {
+    pub site_name: String,
+    pub organization_name: Option<String>,
+    pub logo_url: Option<String>,
+    pub accent_color: String,
+    pub footer_text: Option<String>,
+    /// A notice carried over from the previous request; see `flash`
+    pub flash: Option<crate::flash::Flash>,
+}
+
+impl Layout {
+    /// Whether the page gets a branded header
+    pub fn has_header(&self) -> bool {
+        self.organization_name.is_some() || self.logo_url.is_some()
+    }
+}
+
+/// The saved settings, or the defaults before setup has run
+pub async fn load(pool: &SqlitePool) -> Result<Settings, sqlx::Error> {
+    let settings =
+        sqlx::query_as::<_, Settings>(&format!("SELECT {} FROM settings WHERE id = 1", COLUMNS))
+            .fetch_optional(pool)
+            .await?;
+    Ok(settings.unwrap_or_default())
+}
+
+pub async fn save(pool: &SqlitePool, settings: &Settings) -> Result<(), sqlx::Error> {
+    sqlx::query(&format!(
+        "INSERT OR REPLACE INTO settings (id, {}, updated_at) VALUES (1, ?, ?, ?, ?, ?, ?, ?, ?)",
+        COLUMNS
+    ))
+    .bind(&settings.site_name)
+    .bind(&settings.base_url)
+    .bind(&settings.organization_name)
+    .bind(&settings.logo_url)
+    .bind(&settings.accent_color)
+    .bind(&settings.footer_text)
+    .bind(settings.retention_days)
+    .bind(Utc::now().to_rfc3339())
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// A base URL as stored: an http(s) origin without a trailing slash
+pub fn normalize_base_url(url: &str) -> Option<String> {
+    let url = url.trim().trim_end_matches('/');
+    let rest = url
+        .strip_prefix("https://")
+        .or_else(|| url.strip_prefix("http://"))?;
+    let valid = !rest.is_empty() && !rest.contains(['?', '#', ' ']);
+    valid.then(|| url.to_string())
+}
+
+/// A hex color as stored, lowercased. Nothing else is allowed, since it's
+/// written into the page's CSS.
+pub fn normalize_color(color: &str) -> Option<String> {
+    let hex = color.trim().strip_prefix('#')?;
+    let valid = matches!(hex.len(), 3 | 6) && hex.chars().all(|c| c.is_ascii_hexdigit());
+    valid.then(|| format!("#{}", hex.to_ascii_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db;
+
+    #[test]
+    fn test_normalize_base_url() {
+        assert_eq!(
+            normalize_base_url(" https://feedback.example.com/ "),
+            Some("https://feedback.example.com".to_string())
+        );
+        assert_eq!(
+            normalize_base_url("http://localhost:3000/feedback"),
+            Some("http://localhost:3000/feedback".to_string())
+        );
+        assert_eq!(normalize_base_url("feedback.example.com"), None);
+        assert_eq!(normalize_base_url("https://"), None);
+        assert_eq!(normalize_base_url("https://example.com/?x=1"), None);
+    }
+
+    #[test]
+    fn test_normalize_color() {
+        assert_eq!(normalize_color("#FF8800"), Some("#ff8800".to_string()));
+        assert_eq!(normalize_color(" #abc "), Some("#abc".to_string()));
+        assert_eq!(normalize_color("ff8800"), None);
+        assert_eq!(normalize_color("#ff88"), None);
+        assert_eq!(normalize_color("#fff;} body {"), None);
+    }
+
+    #[tokio::test]
+    async fn test_save_and_load() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        assert_eq!(load(&pool).await.unwrap(), Settings::default());
+
+        let settings = Settings {
+            organization_name: Some("Acme".to_string()),
+            accent_color: Some("#ff8800".to_string()),
+            ..Settings::default()
+        };
+        save(&pool, &settings).await.unwrap();
+        save(&pool, &settings).await.unwrap();
+        assert_eq!(load(&pool).await.unwrap(), settings);
+        assert_eq!(settings.layout().accent_color, "#ff8800");
+        assert_eq!(
+            Settings::default().layout().accent_color,
+            DEFAULT_ACCENT_COLOR
+        );
+    }
+}
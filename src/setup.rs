@@ -0,0 +1,80 @@
+//! The first-run wizard at `/setup`. A fresh install has no accounts, and
+//! until the wizard has created the first owner every admin route sends the
+//! browser here. It also records the site's name and public base URL, and
+//! can seed a sample prompt to try the form out with.
+
+use sqlx::sqlite::SqlitePool;
+
+use crate::db;
+use crate::settings::{self, Settings};
+
+const SAMPLE_TITLE: &str = "How are we doing?";
+const SAMPLE_DESCRIPTION: &str =
+    "A sample prompt to try the form with. Share its link, or **edit** and duplicate it.";
+
+/// A submitted setup form, already validated
+pub struct Setup {
+    pub email: String,
+    pub password_hash: String,
+    pub settings: Settings,
+    pub sample_prompt: bool,
+}
+
+/// Create the first owner and save the settings. Returns None if an account
+/// already exists, so a second wizard racing the first changes nothing.
+pub async fn complete(pool: &SqlitePool, setup: &Setup) -> Result<Option<db::User>, sqlx::Error> {
+    let Some(user) = db::create_first_owner(pool, &setup.email, &setup.password_hash).await? else {
+        return Ok(None);
+    };
+    settings::save(pool, &setup.settings).await?;
+    if setup.sample_prompt {
+        db::create_prompt_with_options(
+            pool,
+            db::DEFAULT_WORKSPACE_ID,
+            SAMPLE_TITLE,
+            SAMPLE_DESCRIPTION,
+            &db::PromptOptions::default(),
+        )
+        .await?;
+    }
+    Ok(Some(user))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_complete_only_once() {
+        let pool = db::init_db("sqlite::memory:").await.unwrap();
+        let setup = Setup {
+            email: "owner@example.com".to_string(),
+            password_hash: "hash".to_string(),
+            settings: Settings {
+                site_name: "Acme Feedback".to_string(),
+                base_url: Some("https://feedback.acme.test".to_string()),
+                ..Settings::default()
+            },
+            sample_prompt: true,
+        };
+        let user = complete(&pool, &setup).await.unwrap().unwrap();
+        assert_eq!(user.role(), db::Role::Owner);
+        assert_eq!(settings::load(&pool).await.unwrap(), setup.settings);
+        let prompts = db::get_all_prompts(&pool, db::DEFAULT_WORKSPACE_ID)
+            .await
+            .unwrap();
+        assert_eq!(prompts.len(), 1);
+
+        let again = Setup {
+            email: "intruder@example.com".to_string(),
+            settings: Settings::default(),
+            ..setup
+        };
+        assert!(complete(&pool, &again).await.unwrap().is_none());
+        assert_eq!(db::count_users(&pool).await.unwrap(), 1);
+        assert_eq!(
+            settings::load(&pool).await.unwrap().site_name,
+            "Acme Feedback"
+        );
+    }
+}
@@ -0,0 +1,221 @@
+//! Spam screening for public submissions.
+//!
+//! The feedback form carries a honeypot field that people never see and a
+//! signed timestamp of when it was rendered; bots tend to fill in every field
+//! and submit instantly. Content is then run through a list of filters. Anything
+//! caught is still stored, but quarantined with the reason so an admin can
+//! release false positives.
+
+use std::time::Duration;
+
+use crate::signing;
+
+/// Hidden form field that only bots fill in
+pub const HONEYPOT_FIELD: &str = "website";
+
+const STAMP_LABEL: &str = "form-rendered";
+
+/// The hidden `rendered_at` value for a form served at `now`: the time and
+/// its signature under the prompt's key, so it can't be backdated
+pub fn stamp(secret: &str, prompt_id: &str, now: i64) -> String {
+    let now = now.to_string();
+    let sig = signing::sign(secret, STAMP_LABEL, &[prompt_id, &now]);
+    format!("{}.{}", now, sig)
+}
+
+/// When the form carrying `stamp` was served, if we signed it
+pub fn stamped_at(secret: &str, prompt_id: &str, stamp: &str) -> Option<i64> {
+    let (time, sig) = stamp.trim().split_once('.')?;
+    signing::verify(secret, STAMP_LABEL, &[prompt_id, time], sig)
+        .then(|| time.parse().ok())
+        .flatten()
+}
+
+/// A check on submitted text; returns why it looks like spam
+pub trait ContentFilter: Send + Sync {
+    fn check(&self, content: &str) -> Option<String>;
+}
+
+/// Flags submissions with more links than a person would usually paste
+pub struct LinkLimit {
+    pub max_links: usize,
+}
+
+impl ContentFilter for LinkLimit {
+    fn check(&self, content: &str) -> Option<String> {
+        let links = content
+            .split_whitespace()
+            .filter(|word| {
+                let word = word.trim_start_matches(|c: char| !c.is_alphanumeric());
+                let lower = word.to_lowercase();
+                lower.starts_with("http://")
+                    || lower.starts_with("https://")
+                    || lower.starts_with("www.")
+            })
+            .count();
+        (links > self.max_links).then(|| format!("{} links", links))
+    }
+}
+
+/// Flags submissions containing any of the listed words or phrases,
+/// ignoring case
+pub struct BannedWords {
+    words: Vec<String>,
+}
+
+impl BannedWords {
+    pub fn new<I, S>(words: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        BannedWords {
+            words: words
+                .into_iter()
+                .map(|w| w.as_ref().trim().to_lowercase())
+                .filter(|w| !w.is_empty())
+                .collect(),
+        }
+    }
+}
+
+impl ContentFilter for BannedWords {
+    fn check(&self, content: &str) -> Option<String> {
+        let lower = content.to_lowercase();
+        self.words
+            .iter()
+            .find(|word| lower.contains(word.as_str()))
+            .map(|word| format!("banned word \"{}\"", word))
+    }
+}
+
+pub struct Screen {
+    /// Forms submitted sooner than this after rendering are spam; zero turns
+    /// the time check off
+    pub min_submit_time: Duration,
+    filters: Vec<Box<dyn ContentFilter>>,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen {
+            min_submit_time: Duration::from_secs(3),
+            filters: vec![Box::new(LinkLimit { max_links: 3 })],
+        }
+    }
+}
+
+impl Screen {
+    /// No content filters; add them with `with_filter`
+    pub fn new(min_submit_time: Duration) -> Self {
+        Screen {
+            min_submit_time,
+            filters: Vec::new(),
+        }
+    }
+
+    pub fn with_filter(mut self, filter: impl ContentFilter + 'static) -> Self {
+        self.filters.push(Box::new(filter));
+        self
+    }
+
+    /// Run the content filters, returning the first reason found
+    pub fn check_content(&self, content: &str) -> Option<String> {
+        self.filters.iter().find_map(|f| f.check(content))
+    }
+
+    /// Screen a form submission. `rendered_at` is the Unix time the form was
+    /// served, from a verified `stamp`; a form without a genuine one was never
+    /// served by us, so it's spam too unless the time check is off.
+    pub fn check_form(
+        &self,
+        honeypot: &str,
+        rendered_at: Option<i64>,
+        now: i64,
+        content: &str,
+    ) -> Option<String> {
+        if !honeypot.trim().is_empty() {
+            return Some("honeypot field filled in".to_string());
+        }
+        if !self.min_submit_time.is_zero() {
+            let Some(rendered_at) = rendered_at else {
+                return Some("missing or forged form timestamp".to_string());
+            };
+            let elapsed = now - rendered_at;
+            if elapsed < self.min_submit_time.as_secs() as i64 {
+                return Some(format!("submitted {}s after loading", elapsed.max(0)));
+            }
+        }
+        self.check_content(content)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_content_filters() {
+        let screen = Screen::new(Duration::ZERO)
+            .with_filter(LinkLimit { max_links: 1 })
+            .with_filter(BannedWords::new(["casino", " Cheap Pills "]));
+
+        assert_eq!(
+            screen.check_content("See https://example.com for details"),
+            None
+        );
+        assert_eq!(
+            screen.check_content("https://a.example (https://b.example) www.c.example"),
+            Some("3 links".to_string())
+        );
+        assert_eq!(
+            screen.check_content("Buy CHEAP PILLS now"),
+            Some("banned word \"cheap pills\"".to_string())
+        );
+        assert_eq!(
+            screen.check_content("Loved the new casino-free UI"),
+            Some("banned word \"casino\"".to_string())
+        );
+    }
+
+    #[test]
+    fn test_form_checks() {
+        let screen = Screen::default();
+        let now = 1_700_000_000;
+
+        assert_eq!(
+            screen.check_form("", Some(now - 30), now, "Nice work"),
+            None
+        );
+        assert_eq!(
+            screen.check_form("", None, now, "Nice work"),
+            Some("missing or forged form timestamp".to_string())
+        );
+        assert_eq!(
+            Screen::new(Duration::ZERO).check_form("", None, now, "Nice work"),
+            None
+        );
+        assert_eq!(
+            screen.check_form("http://spam.example", Some(now - 30), now, "Nice work"),
+            Some("honeypot field filled in".to_string())
+        );
+        assert_eq!(
+            screen.check_form("", Some(now - 1), now, "Nice work"),
+            Some("submitted 1s after loading".to_string())
+        );
+    }
+
+    #[test]
+    fn test_stamps() {
+        let stamp = stamp("key", "prompt-1", 1_700_000_000);
+        assert_eq!(stamped_at("key", "prompt-1", &stamp), Some(1_700_000_000));
+        assert_eq!(stamped_at("key", "prompt-2", &stamp), None);
+        assert_eq!(stamped_at("other", "prompt-1", &stamp), None);
+        assert_eq!(stamped_at("key", "prompt-1", "1600000000"), None);
+
+        // Backdating breaks the signature
+        let (_, sig) = stamp.split_once('.').unwrap();
+        let backdated = format!("1600000000.{}", sig);
+        assert_eq!(stamped_at("key", "prompt-1", &backdated), None);
+    }
+}
@@ -0,0 +1,97 @@
+//! Storage accounting and quotas. Each prompt's response bytes (feedback
+//! text plus field answers) are kept current by database triggers on every
+//! write, so the numbers here are cheap lookups rather than table scans.
+//! Feedback has no attachments, so responses are all there is to count.
+
+use crate::db::StorageUsage;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Quotas {
+    /// Response bytes a single prompt may hold; 0 means no limit
+    pub prompt_bytes: i64,
+    /// Response bytes a workspace's prompts may hold together; 0 means no limit
+    pub workspace_bytes: i64,
+}
+
+impl Quotas {
+    pub fn is_unlimited(&self) -> bool {
+        self.prompt_bytes <= 0 && self.workspace_bytes <= 0
+    }
+
+    /// Whether `incoming` more bytes still fit, and which quota they'd break if not
+    pub fn check(&self, usage: &StorageUsage, incoming: i64) -> Result<(), &'static str> {
+        let over = |quota: i64, used: i64| quota > 0 && used + incoming > quota;
+        if over(self.prompt_bytes, usage.prompt_bytes) {
+            return Err("This prompt has reached its storage limit");
+        }
+        if over(self.workspace_bytes, usage.workspace_bytes) {
+            return Err("This workspace has reached its storage limit");
+        }
+        Ok(())
+    }
+}
+
+/// Usage against a quota for display, e.g. `1.5 KB of 10.0 MB`
+pub fn describe(used: i64, quota: i64) -> String {
+    if quota > 0 {
+        format!("{} of {}", format_bytes(used), format_bytes(quota))
+    } else {
+        format_bytes(used)
+    }
+}
+
+/// A byte count for people: `512 B`, `1.5 KB`, `20.0 MB`
+pub fn format_bytes(bytes: i64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{} B", bytes);
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1} {}", size, UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1536), "1.5 KB");
+        assert_eq!(format_bytes(20 * 1024 * 1024), "20.0 MB");
+        assert_eq!(describe(1536, 0), "1.5 KB");
+        assert_eq!(describe(1536, 2048), "1.5 KB of 2.0 KB");
+    }
+
+    #[test]
+    fn test_quotas_check() {
+        let usage = StorageUsage {
+            prompt_bytes: 90,
+            workspace_bytes: 900,
+        };
+        assert!(Quotas::default().check(&usage, i64::MAX / 2).is_ok());
+
+        let quotas = Quotas {
+            prompt_bytes: 100,
+            workspace_bytes: 0,
+        };
+        assert!(quotas.check(&usage, 10).is_ok());
+        assert!(quotas.check(&usage, 11).is_err());
+
+        let quotas = Quotas {
+            prompt_bytes: 0,
+            workspace_bytes: 1000,
+        };
+        assert!(quotas.check(&usage, 100).is_ok());
+        assert_eq!(
+            quotas.check(&usage, 101),
+            Err("This workspace has reached its storage limit")
+        );
+    }
+}
@@ -0,0 +1,187 @@
+//! Built-in HTTPS for deployments without a reverse proxy. Enabled by
+//! setting `TLS_CERT_FILE` and `TLS_KEY_FILE` (PEM, e.g. from certbot); the
+//! app is then served over TLS on `TLS_PORT` (default 3443) and the plain
+//! HTTP port only redirects there (answering the health probes itself),
+//! unless `TLS_REDIRECT_HTTP=0` keeps it serving the app too.
+
+use axum::{
+    extract::{ConnectInfo, Request},
+    http::{header, Uri},
+    response::{IntoResponse, Redirect, Response},
+    Router,
+};
+use hyper::server::conn::http1;
+use hyper_util::{
+    rt::{TokioIo, TokioTimer},
+    server::graceful::GracefulShutdown,
+    service::TowerToHyperService,
+};
+use std::{path::PathBuf, sync::Arc, time::Duration};
+use tokio::net::TcpListener;
+use tokio_rustls::{
+    rustls::{
+        self,
+        pki_types::{pem::PemObject, CertificateDer, PrivateKeyDer},
+    },
+    TlsAcceptor,
+};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use crate::origin;
+
+pub const DEFAULT_PORT: u16 = 3443;
+/// A client that can't finish the handshake in this long is dropped
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(10);
+/// Same for one that never finishes sending its request headers
+const HEADER_READ_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub cert_file: PathBuf,
+    pub key_file: PathBuf,
+    pub port: u16,
+    /// Answer plain HTTP with a redirect to HTTPS instead of the app
+    pub redirect_http: bool,
+}
+
+impl Config {
+    pub fn from_env() -> Option<Self> {
+        let var = |name| std::env::var(name).ok().filter(|v: &String| !v.is_empty());
+        Some(Config {
+            cert_file: var("TLS_CERT_FILE")?.into(),
+            key_file: var("TLS_KEY_FILE")?.into(),
+            port: var("TLS_PORT")
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_PORT),
+            redirect_http: var("TLS_REDIRECT_HTTP").is_none_or(|v| v != "0"),
+        })
+    }
+
+    /// Load the certificate chain and key; errors name the file at fault
+    pub fn acceptor(&self) -> Result<TlsAcceptor, String> {
+        let certs = CertificateDer::pem_file_iter(&self.cert_file)
+            .and_then(|certs| certs.collect::<Result<Vec<_>, _>>())
+            .map_err(|e| format!("{}: {}", self.cert_file.display(), e))?;
+        if certs.is_empty() {
+            return Err(format!(
+                "{}: no certificates found",
+                self.cert_file.display()
+            ));
+        }
+        let key = PrivateKeyDer::from_pem_file(&self.key_file)
+            .map_err(|e| format!("{}: {}", self.key_file.display(), e))?;
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        let mut config = rustls::ServerConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .and_then(|builder| builder.with_no_client_auth().with_single_cert(certs, key))
+            .map_err(|e| e.to_string())?;
+        config.alpn_protocols = vec![b"http/1.1".to_vec()];
+        Ok(TlsAcceptor::from(Arc::new(config)))
+    }
+}
+
+/// Serve `app` over TLS until `shutdown` fires, then let open connections
+/// finish their current request
+pub async fn serve(
+    listener: TcpListener,
+    acceptor: TlsAcceptor,
+    app: Router,
+    shutdown: CancellationToken,
+) {
+    let graceful = GracefulShutdown::new();
+    loop {
+        let (stream, addr) = tokio::select! {
+            accepted = listener.accept() => match accepted {
+                Ok(connection) => connection,
+                Err(e) => {
+                    eprintln!("Failed to accept TLS connection: {}", e);
+                    continue;
+                }
+            },
+            _ = shutdown.cancelled() => break,
+        };
+
+        let acceptor = acceptor.clone();
+        let watcher = graceful.watcher();
+        let service = app.clone().map_request(move |mut request: Request<_>| {
+            request.extensions_mut().insert(ConnectInfo(addr));
+            request.extensions_mut().insert(origin::Https);
+            request
+        });
+        tokio::spawn(async move {
+            let stream =
+                match tokio::time::timeout(HANDSHAKE_TIMEOUT, acceptor.accept(stream)).await {
+                    Ok(Ok(stream)) => stream,
+                    // Scanners and plain-HTTP clients; nothing worth logging
+                    _ => return,
+                };
+            let connection = http1::Builder::new()
+                .timer(TokioTimer::new())
+                .header_read_timeout(HEADER_READ_TIMEOUT)
+                .serve_connection(TokioIo::new(stream), TowerToHyperService::new(service));
+            let _ = watcher.watch(connection).await;
+        });
+    }
+    graceful.shutdown().await;
+}
+
+/// The plain-HTTP app when `redirect_http` is on: everything moves to HTTPS
+/// except `probes`, so health checks that can't follow redirects keep working
+pub fn redirect_router(tls_port: u16, probes: Router) -> Router {
+    probes.fallback(move |request: Request| async move { redirect(&request, tls_port) })
+}
+
+fn redirect(request: &Request, tls_port: u16) -> Response {
+    let host = request
+        .headers()
+        .get(header::HOST)
+        .and_then(|v| v.to_str().ok());
+    match host.and_then(|host| https_location(host, request.uri(), tls_port)) {
+        // 308 so a form POST is repeated as a POST
+        Some(location) => Redirect::permanent(&location).into_response(),
+        None => (axum::http::StatusCode::BAD_REQUEST, "Use HTTPS".to_string()).into_response(),
+    }
+}
+
+/// The same URL on the HTTPS port; `None` for a host we won't redirect to
+fn https_location(host: &str, uri: &Uri, tls_port: u16) -> Option<String> {
+    let authority: axum::http::uri::Authority = host.parse().ok()?;
+    let port = match tls_port {
+        443 => String::new(),
+        port => format!(":{}", port),
+    };
+    let path = uri.path_and_query().map_or("/", |p| p.as_str());
+    Some(format!("https://{}{}{}", authority.host(), port, path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_redirects_and_bad_files() {
+        let uri: Uri = "/feedback/abc?invite=x".parse().unwrap();
+        assert_eq!(
+            https_location("feedback.example.com:3000", &uri, 3443).as_deref(),
+            Some("https://feedback.example.com:3443/feedback/abc?invite=x")
+        );
+        assert_eq!(
+            https_location("feedback.example.com", &"/".parse().unwrap(), 443).as_deref(),
+            Some("https://feedback.example.com/")
+        );
+        assert_eq!(https_location("evil.com/x", &uri, 443), None);
+
+        let config = Config {
+            cert_file: "/nonexistent/cert.pem".into(),
+            key_file: "/nonexistent/key.pem".into(),
+            port: DEFAULT_PORT,
+            redirect_http: true,
+        };
+        let Err(e) = config.acceptor() else {
+            panic!("loaded a missing certificate");
+        };
+        assert!(e.starts_with("/nonexistent/cert.pem"));
+    }
+}
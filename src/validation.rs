@@ -0,0 +1,133 @@
+//! Limits on what people type into the new-prompt form and the public
+//! feedback form. Text is trimmed before it's checked and stored; a form
+//! that fails is shown again with each problem beside its input, and the API
+//! reports the same problems as field errors.
+
+use crate::error::FieldError;
+
+pub const MAX_TITLE_LEN: usize = 200;
+pub const MAX_DESCRIPTION_LEN: usize = 10_000;
+/// Shortest feedback accepted unless the prompt sets its own minimum
+pub const MIN_FEEDBACK_LEN: usize = 2;
+/// Longest feedback accepted, whatever the prompt's own maximum
+pub const MAX_FEEDBACK_LEN: usize = 10_000;
+
+/// What's wrong with a piece of text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Problem {
+    Required,
+    TooShort(usize),
+    TooLong(usize),
+}
+
+impl Problem {
+    /// Phrased to follow the field's name, like the API's field errors
+    pub fn message(&self) -> String {
+        match self {
+            Problem::Required => "must not be empty".to_string(),
+            Problem::TooShort(min) => format!("must be at least {} characters", min),
+            Problem::TooLong(max) => format!("must be at most {} characters", max),
+        }
+    }
+}
+
+/// `value` trimmed, if it's between `min` and `max` characters; a `min` of
+/// 0 allows it to be blank
+pub fn text(value: &str, min: usize, max: usize) -> Result<&str, Problem> {
+    let value = value.trim();
+    let len = value.chars().count();
+    if len == 0 && min > 0 {
+        Err(Problem::Required)
+    } else if len < min {
+        Err(Problem::TooShort(min))
+    } else if len > max {
+        Err(Problem::TooLong(max))
+    } else {
+        Ok(value)
+    }
+}
+
+/// A prompt's title, trimmed
+pub fn title(value: &str) -> Result<&str, Problem> {
+    text(value, 1, MAX_TITLE_LEN)
+}
+
+/// A prompt's description, trimmed
+pub fn description(value: &str, required: bool) -> Result<&str, Problem> {
+    text(value, required.into(), MAX_DESCRIPTION_LEN)
+}
+
+/// Feedback, trimmed. `min_length` is the prompt's own minimum, which
+/// replaces the default; its other rules are checked separately.
+pub fn feedback(value: &str, min_length: Option<i64>) -> Result<&str, Problem> {
+    let min = min_length.map_or(MIN_FEEDBACK_LEN, |min| min.max(1) as usize);
+    text(value, min, MAX_FEEDBACK_LEN)
+}
+
+/// Problems with a submitted form, by input name, in the order found
+#[derive(Debug, Clone, Default)]
+pub struct Errors(Vec<FieldError>);
+
+impl Errors {
+    /// Record `message` against `field`, keeping the first one per field
+    pub fn add(&mut self, field: &str, message: impl Into<String>) {
+        if self.get(field).is_none() {
+            self.0.push(FieldError {
+                field: field.to_string(),
+                message: message.into(),
+            });
+        }
+    }
+
+    pub fn get(&self, field: &str) -> Option<&str> {
+        self.0
+            .iter()
+            .find(|e| e.field == field)
+            .map(|e| e.message.as_str())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_text() {
+        assert_eq!(text("  Hello  ", 1, 10), Ok("Hello"));
+        assert_eq!(text("   ", 1, 10), Err(Problem::Required));
+        assert_eq!(text("", 0, 10), Ok(""));
+        assert_eq!(text("a", 2, 10), Err(Problem::TooShort(2)));
+        assert_eq!(text("abcdef", 1, 5), Err(Problem::TooLong(5)));
+        // Characters, not bytes
+        assert_eq!(text("ééééé", 1, 5), Ok("ééééé"));
+    }
+
+    #[test]
+    fn test_feedback_minimum() {
+        assert_eq!(
+            feedback("a", None),
+            Err(Problem::TooShort(MIN_FEEDBACK_LEN))
+        );
+        assert_eq!(feedback("a", Some(1)), Ok("a"));
+        assert_eq!(feedback(" ", Some(1)), Err(Problem::Required));
+        let essay = "x".repeat(MAX_FEEDBACK_LEN + 1);
+        assert_eq!(
+            feedback(&essay, Some(1)),
+            Err(Problem::TooLong(MAX_FEEDBACK_LEN))
+        );
+    }
+
+    #[test]
+    fn test_errors_keep_first_per_field() {
+        let mut errors = Errors::default();
+        assert!(errors.is_empty());
+        errors.add("title", "Title must not be empty");
+        errors.add("title", "Title is taken");
+        assert_eq!(errors.get("title"), Some("Title must not be empty"));
+        assert_eq!(errors.get("description"), None);
+    }
+}
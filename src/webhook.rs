@@ -0,0 +1,333 @@
+use hmac::{Hmac, Mac};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::net::{IpAddr, SocketAddr};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::db;
+
+/// Header carrying the HMAC-SHA256 signature of the request body
+pub const SIGNATURE_HEADER: &str = "X-Feedback-Signature";
+
+const MAX_ATTEMPTS: u32 = 3;
+
+/// Receipt sent to a prompt's webhook after feedback is recorded
+#[derive(Debug, Clone, Serialize)]
+pub struct Receipt {
+    pub feedback_id: String,
+    pub prompt_id: String,
+    pub created_at: String,
+    pub content_sha256: String,
+    /// Set on synthetic receipts sent from the admin's test button
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub test: bool,
+}
+
+impl Receipt {
+    pub fn for_feedback(feedback: &db::Feedback) -> Self {
+        Receipt {
+            feedback_id: feedback.id.clone(),
+            prompt_id: feedback.prompt_id.clone(),
+            created_at: feedback.created_at.clone(),
+            content_sha256: hex::encode(Sha256::digest(feedback.content.as_bytes())),
+            test: false,
+        }
+    }
+
+    /// A receipt for feedback that doesn't exist, to check an endpoint
+    pub fn test_event(prompt: &db::Prompt) -> Self {
+        let feedback = db::Feedback {
+            id: format!("test-{}", uuid::Uuid::new_v4()),
+            prompt_id: prompt.id.clone(),
+            content: "This is a test event".to_string(),
+            created_at: chrono::Utc::now().to_rfc3339(),
+            ..Default::default()
+        };
+        Receipt {
+            test: true,
+            ..Self::for_feedback(&feedback)
+        }
+    }
+}
+
+/// What happened when delivering one receipt
+#[derive(Debug, Clone, Default)]
+pub struct Delivery {
+    /// One line per attempt, e.g. `HTTP 503` or a connection error
+    pub attempts: Vec<String>,
+    pub succeeded: bool,
+    /// Start of the last response's body, if one arrived
+    pub last_response: Option<String>,
+}
+
+/// Response bodies are only kept for display, so cap what's read
+const MAX_RESPONSE_CHARS: usize = 500;
+const MAX_RESPONSE_BYTES: usize = 4 * 1024;
+
+/// Sends receipts. Redirects aren't followed, and unless internal targets
+/// are allowed nothing goes to a loopback, private or link-local address,
+/// checked on the addresses the host resolves to.
+#[derive(Clone)]
+pub struct Client {
+    http: reqwest::Client,
+    allow_private: bool,
+}
+
+impl Client {
+    pub fn new(allow_private: bool) -> Self {
+        let mut builder = reqwest::Client::builder().redirect(reqwest::redirect::Policy::none());
+        if !allow_private {
+            builder = builder.dns_resolver(Arc::new(PublicResolver));
+        }
+        Client {
+            http: builder.build().expect("webhook client config is static"),
+            allow_private,
+        }
+    }
+}
+
+impl Client {
+    /// Whether a webhook or notification URL can be saved for this client
+    pub fn accepts(&self, url: &str) -> bool {
+        if !self.allow_private {
+            return is_valid_url(url);
+        }
+        reqwest::Url::parse(url)
+            .map(|u| matches!(u.scheme(), "http" | "https") && u.host().is_some())
+            .unwrap_or(false)
+    }
+}
+
+impl Default for Client {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+/// Resolves hostnames, failing when any address is internal so a name
+/// can't be pointed at the host's own network
+struct PublicResolver;
+
+impl reqwest::dns::Resolve for PublicResolver {
+    fn resolve(&self, name: reqwest::dns::Name) -> reqwest::dns::Resolving {
+        let host = name.as_str().to_string();
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> =
+                tokio::net::lookup_host((host.as_str(), 0)).await?.collect();
+            if addrs.is_empty() || addrs.iter().any(|a| !is_public(a.ip())) {
+                return Err(format!("{} resolves to an internal address", host).into());
+            }
+            Ok(Box::new(addrs.into_iter()) as reqwest::dns::Addrs)
+        })
+    }
+}
+
+/// Whether an address is reachable on the public internet rather than this
+/// host or its network
+pub fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            !(ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_unspecified()
+                || ip.is_broadcast()
+                || ip.is_multicast()
+                || ip.is_documentation()
+                // Shared address space (100.64.0.0/10) used for carrier NAT
+                || (ip.octets()[0] == 100 && ip.octets()[1] & 0xc0 == 64))
+        }
+        IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+            Some(v4) => is_public(IpAddr::V4(v4)),
+            None => {
+                let first = ip.segments()[0];
+                !(ip.is_loopback()
+                    || ip.is_unspecified()
+                    || ip.is_multicast()
+                    // Unique local (fc00::/7) and link-local (fe80::/10)
+                    || first & 0xfe00 == 0xfc00
+                    || first & 0xffc0 == 0xfe80)
+            }
+        },
+    }
+}
+
+/// A literal address in the URL that isn't public; hostnames are checked
+/// when they're resolved
+fn internal_literal(url: &reqwest::Url) -> bool {
+    let Some(host) = url.host_str() else {
+        return true;
+    };
+    let host = host.to_ascii_lowercase();
+    match host.trim_matches(['[', ']']).parse::<IpAddr>() {
+        Ok(ip) => !is_public(ip),
+        Err(_) => host == "localhost" || host.ends_with(".localhost"),
+    }
+}
+
+/// Sign a payload with the prompt's webhook secret, formatted as `sha256=<hex>`
+pub fn sign(secret: &str, payload: &[u8]) -> String {
+    let mut mac =
+        Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any size");
+    mac.update(payload);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// Only absolute http(s) URLs are accepted as webhook targets, and not ones
+/// naming this host or a private address outright
+pub fn is_valid_url(url: &str) -> bool {
+    reqwest::Url::parse(url)
+        .map(|u| matches!(u.scheme(), "http" | "https") && !internal_literal(&u))
+        .unwrap_or(false)
+}
+
+/// POST a signed receipt to the webhook, retrying with backoff on failure
+pub async fn deliver(client: &Client, url: &str, secret: &str, receipt: &Receipt) -> Delivery {
+    let mut delivery = Delivery::default();
+    match reqwest::Url::parse(url) {
+        Ok(parsed) if client.allow_private || !internal_literal(&parsed) => {}
+        Ok(_) => {
+            delivery
+                .attempts
+                .push("Refused: internal address".to_string());
+            return delivery;
+        }
+        Err(e) => {
+            delivery.attempts.push(e.to_string());
+            return delivery;
+        }
+    }
+    let body = match serde_json::to_vec(receipt) {
+        Ok(body) => body,
+        Err(e) => {
+            delivery.attempts.push(e.to_string());
+            return delivery;
+        }
+    };
+    let signature = sign(secret, &body);
+
+    for attempt in 0..MAX_ATTEMPTS {
+        if attempt > 0 {
+            tokio::time::sleep(Duration::from_millis(500 * 2u64.pow(attempt))).await;
+        }
+
+        let result = client
+            .http
+            .post(url)
+            .header("content-type", "application/json")
+            .header(SIGNATURE_HEADER, &signature)
+            .body(body.clone())
+            .timeout(Duration::from_secs(10))
+            .send()
+            .await;
+
+        match result {
+            Ok(mut response) => {
+                let status = response.status();
+                delivery.attempts.push(format!("HTTP {}", status));
+                let mut body = Vec::new();
+                while body.len() < MAX_RESPONSE_BYTES {
+                    match response.chunk().await {
+                        Ok(Some(chunk)) => body.extend_from_slice(&chunk),
+                        _ => break,
+                    }
+                }
+                body.truncate(MAX_RESPONSE_BYTES);
+                let text = String::from_utf8_lossy(&body);
+                delivery.last_response = Some(text.chars().take(MAX_RESPONSE_CHARS).collect());
+                if status.is_success() {
+                    delivery.succeeded = true;
+                    return delivery;
+                }
+            }
+            Err(e) => delivery.attempts.push(e.to_string()),
+        }
+    }
+
+    delivery
+}
+
+/// Fire-and-forget delivery of a receipt if the prompt has a webhook configured
+pub fn spawn_receipt(client: Client, prompt: &db::Prompt, feedback: &db::Feedback) {
+    let (Some(url), Some(secret)) = (prompt.webhook_url.clone(), prompt.webhook_secret.clone())
+    else {
+        return;
+    };
+    let receipt = Receipt::for_feedback(feedback);
+
+    tokio::spawn(async move {
+        let delivery = deliver(&client, &url, &secret, &receipt).await;
+        if !delivery.succeeded {
+            eprintln!(
+                "Webhook delivery for feedback {} failed: {}",
+                receipt.feedback_id,
+                delivery.attempts.last().map(String::as_str).unwrap_or("")
+            );
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_is_deterministic_and_keyed() {
+        let a = sign("secret", b"payload");
+        assert_eq!(a, sign("secret", b"payload"));
+        assert_ne!(a, sign("other", b"payload"));
+        assert!(a.starts_with("sha256="));
+        assert_eq!(a.len(), "sha256=".len() + 64);
+    }
+
+    #[test]
+    fn test_receipt_hashes_content() {
+        let feedback = db::Feedback {
+            id: "fb".to_string(),
+            prompt_id: "p".to_string(),
+            content: "hello".to_string(),
+            created_at: "2024-01-01T00:00:00+00:00".to_string(),
+            ..Default::default()
+        };
+
+        let receipt = Receipt::for_feedback(&feedback);
+
+        assert_eq!(
+            receipt.content_sha256,
+            "2cf24dba5fb0a30e26e83b2ac5b9e29e1b161e5c1fa7425e73043362938b9824"
+        );
+    }
+
+    #[test]
+    fn test_is_valid_url() {
+        assert!(is_valid_url("https://example.com/hook"));
+        assert!(!is_valid_url("ftp://example.com"));
+        assert!(!is_valid_url("not a url"));
+        assert!(!is_valid_url("http://localhost:8080/receipts"));
+        assert!(!is_valid_url("http://127.0.0.1:8080/receipts"));
+        assert!(!is_valid_url("http://169.254.169.254/latest/meta-data"));
+        assert!(!is_valid_url("http://10.0.0.5/hook"));
+        assert!(!is_valid_url("http://[::1]/hook"));
+        assert!(!is_valid_url("http://[::ffff:192.168.1.1]/hook"));
+        assert!(is_valid_url("http://93.184.216.34/hook"));
+    }
+
+    #[tokio::test]
+    async fn test_deliver_refuses_internal_targets() {
+        let receipt = Receipt::for_feedback(&db::Feedback::default());
+        for url in ["http://127.0.0.1:9/hook", "http://169.254.169.254/"] {
+            let delivery = deliver(&Client::default(), url, "secret", &receipt).await;
+            assert!(!delivery.succeeded);
+            assert_eq!(delivery.attempts, vec!["Refused: internal address"]);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_resolver_refuses_names_for_internal_addresses() {
+        use reqwest::dns::Resolve;
+
+        let name = "localhost".parse().unwrap();
+        assert!(PublicResolver.resolve(name).await.is_err());
+    }
+}